@@ -0,0 +1,46 @@
+package com.paranid5.prima.rust;
+
+import org.jetbrains.annotations.NotNull;
+import org.jetbrains.annotations.Nullable;
+
+/**
+ * Named position in a track with an optional note.
+ * Instances are created by the native library
+ */
+
+public final class Bookmark {
+    public final long id;
+
+    @NotNull
+    public final String path;
+
+    public final long positionMillis;
+
+    @NotNull
+    public final String name;
+
+    @Nullable
+    public final String note;
+
+    /**
+     * Unix time in seconds when the bookmark was added
+     */
+
+    public final long createdAt;
+
+    public Bookmark(
+            final long id,
+            @NotNull final String path,
+            final long positionMillis,
+            @NotNull final String name,
+            @Nullable final String note,
+            final long createdAt
+    ) {
+        this.id = id;
+        this.path = path;
+        this.positionMillis = positionMillis;
+        this.name = name;
+        this.note = note;
+        this.createdAt = createdAt;
+    }
+}
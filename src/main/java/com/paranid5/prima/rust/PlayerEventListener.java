@@ -0,0 +1,43 @@
+package com.paranid5.prima.rust;
+
+import com.paranid5.prima.data.Track;
+import org.jetbrains.annotations.NotNull;
+import org.jetbrains.annotations.Nullable;
+
+/**
+ * Receives player's events pushed from the native library.
+ * All methods are called on the single dedicated thread,
+ * so implementations should switch to UI thread by themselves
+ */
+
+public interface PlayerEventListener {
+    default void onStateChanged(final boolean isPlaying) {}
+
+    default void onTrackChanged(final int index, @Nullable final Track track) {}
+
+    default void onPositionChanged(final long millis) {}
+
+    default void onQueueChanged(final int length, final int index) {}
+
+    default void onLibraryUpdated(final int tracksCount) {}
+
+    default void onOutputDeviceChanged(@Nullable final String device) {}
+
+    default void onSleepTimerFinished() {}
+
+    /**
+     * Internet radio station has announced another song
+     * @param title null if the station doesn't name it
+     */
+
+    default void onStreamTitleChanged(@Nullable final String title) {}
+
+    /**
+     * Podcasts were refreshed or their episodes were downloaded
+     * @param newEpisodes number of episodes that the feeds have published
+     */
+
+    default void onPodcastsUpdated(final int newEpisodes) {}
+
+    default void onError(@NotNull final String message) {}
+}
@@ -0,0 +1,74 @@
+package com.paranid5.prima.rust;
+
+import org.jetbrains.annotations.NotNull;
+import org.jetbrains.annotations.Nullable;
+
+/**
+ * Episode of the subscribed podcast with its download and progress.
+ * Instances are created by the native library
+ */
+
+public final class PodcastEpisode {
+    public final long id;
+
+    public final long feedId;
+
+    @NotNull
+    public final String title;
+
+    @NotNull
+    public final String audioUrl;
+
+    /**
+     * Unix time in seconds when the episode was published, -1 if the feed doesn't tell
+     */
+
+    public final long publishedAt;
+
+    /**
+     * Duration that the feed tells, -1 if it's unknown
+     */
+
+    public final long durationMillis;
+
+    /**
+     * Downloaded file or null if the episode is not downloaded
+     */
+
+    @Nullable
+    public final String file;
+
+    public final boolean isDownloadQueued;
+
+    public final boolean isPlayed;
+
+    /**
+     * Position where the episode was left
+     */
+
+    public final long positionMillis;
+
+    public PodcastEpisode(
+            final long id,
+            final long feedId,
+            @NotNull final String title,
+            @NotNull final String audioUrl,
+            final long publishedAt,
+            final long durationMillis,
+            @Nullable final String file,
+            final boolean isDownloadQueued,
+            final boolean isPlayed,
+            final long positionMillis
+    ) {
+        this.id = id;
+        this.feedId = feedId;
+        this.title = title;
+        this.audioUrl = audioUrl;
+        this.publishedAt = publishedAt;
+        this.durationMillis = durationMillis;
+        this.file = file;
+        this.isDownloadQueued = isDownloadQueued;
+        this.isPlayed = isPlayed;
+        this.positionMillis = positionMillis;
+    }
+}
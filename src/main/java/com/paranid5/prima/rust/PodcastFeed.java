@@ -0,0 +1,50 @@
+package com.paranid5.prima.rust;
+
+import org.jetbrains.annotations.NotNull;
+import org.jetbrains.annotations.Nullable;
+
+/**
+ * Subscribed podcast's RSS or Atom feed.
+ * Instances are created by the native library
+ */
+
+public final class PodcastFeed {
+    public final long id;
+
+    @NotNull
+    public final String url;
+
+    @NotNull
+    public final String title;
+
+    @Nullable
+    public final String description;
+
+    /**
+     * Unix time in seconds when the feed was subscribed
+     */
+
+    public final long addedAt;
+
+    /**
+     * Unix time in seconds when the feed was fetched last time
+     */
+
+    public final long refreshedAt;
+
+    public PodcastFeed(
+            final long id,
+            @NotNull final String url,
+            @NotNull final String title,
+            @Nullable final String description,
+            final long addedAt,
+            final long refreshedAt
+    ) {
+        this.id = id;
+        this.url = url;
+        this.title = title;
+        this.description = description;
+        this.addedAt = addedAt;
+        this.refreshedAt = refreshedAt;
+    }
+}
@@ -0,0 +1,36 @@
+package com.paranid5.prima.rust;
+
+import org.jetbrains.annotations.NotNull;
+
+/**
+ * Internet radio station of the user's list.
+ * Instances are created by the native library
+ */
+
+public final class RadioStation {
+    public final long id;
+
+    @NotNull
+    public final String name;
+
+    @NotNull
+    public final String url;
+
+    /**
+     * Unix time in seconds when the station was added
+     */
+
+    public final long addedAt;
+
+    public RadioStation(
+            final long id,
+            @NotNull final String name,
+            @NotNull final String url,
+            final long addedAt
+    ) {
+        this.id = id;
+        this.name = name;
+        this.url = url;
+        this.addedAt = addedAt;
+    }
+}
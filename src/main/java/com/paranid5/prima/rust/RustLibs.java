@@ -15,10 +15,558 @@ public final class RustLibs {
 
     static {
         System.load(LIBRARY_PATH);
+        Runtime.getRuntime().addShutdownHook(new Thread(RustLibs::shutdownBlocking));
     }
 
     public static native void initRust();
 
+    /**
+     * Saves playback position and writes all unsaved state to the disk.
+     * Called automatically when JVM shuts down
+     */
+
+    public static native void shutdownBlocking();
+
+    /**
+     * Registers listener of player's events.
+     * Only one listener is kept, null disables delivery
+     */
+
+    public static native void setPlayerEventListener(@Nullable PlayerEventListener listener);
+
+    /**
+     * Renders tracks with current speed, reverb and fade-in
+     * into a single WAV or FLAC file, chosen by output's extension
+     *
+     * @return true if file was written, false if export was cancelled
+     */
+
+    public static native boolean exportTracksBlocking(@NotNull String[] tracks, @NotNull String output);
+
+    /**
+     * @return progress of the running export in range [0, 1] or -1 if there is no export
+     */
+
+    public static native float getExportProgress();
+
+    public static native void cancelExport();
+
+    /**
+     * Decodes the track once on a low priority thread,
+     * next requests read peaks from the cache
+     *
+     * @return [min 0, max 0, min 1, max 1, ...] for {@code resolution} equal parts of the track
+     */
+
+    @NotNull
+    public static native byte[] getWaveformBlocking(@NotNull String path, int resolution);
+
+    /**
+     * Analyses tempo, key and loudness of new and modified library tracks
+     * on a low priority thread, pausing between tracks.
+     * Cancelled analysis continues from the same place next time
+     *
+     * @return true if all tracks were analysed, false if analysis was cancelled
+     */
+
+    public static native boolean analyseLibraryBlocking();
+
+    /**
+     * @return progress of the running library analysis in range [0, 1] or -1 if there is no analysis
+     */
+
+    public static native float getLibraryAnalysisProgress();
+
+    public static native void cancelLibraryAnalysis();
+
+    /**
+     * @return [bpm, key, energy, loudness range] or null if the track was not analysed yet.
+     * Key is in range [0, 24): C major is 0, ..., B major is 11, C minor is 12, ..., B minor is 23.
+     * Unknown BPM and key are -1. Energy is in range [0, 1], loudness range is in dB
+     */
+
+    @Nullable
+    public static native float[] getTrackAnalysisBlocking(@NotNull String path);
+
+    /**
+     * Library tracks for smart playlists, e.g. running tracks by BPM.
+     * Negative bounds and key are not checked, tracks that were not analysed never match
+     *
+     * @param key key in range [0, 24) as in {@link #getTrackAnalysisBlocking(String)}
+     * @param isHarmonic whether keys that mix well with the key match too
+     */
+
+    @NotNull
+    public static native Track[] getTracksByAnalysisBlocking(float minBpm, float maxBpm, int key, boolean isHarmonic, float minEnergy, float maxEnergy);
+
+    public static native boolean isAutoDjEnabledBlocking();
+
+    /**
+     * Turns Auto-DJ on or off. When the playlist is about to end without looping,
+     * tracks similar by artist, genre and year are appended to it
+     */
+
+    public static native void setAutoDjEnabledBlocking(boolean isEnabled);
+
+    /**
+     * Track that Auto-DJ picks are similar to or null if it's not seeded with a track
+     */
+
+    @Nullable
+    public static native String getAutoDjSeedTrackBlocking();
+
+    /**
+     * Seeds Auto-DJ with the track, null makes picks similar to the last queued track
+     */
+
+    public static native void setAutoDjSeedTrackBlocking(@Nullable String path);
+
+    /**
+     * Artist whose music Auto-DJ picks or null if it's not seeded with an artist
+     */
+
+    @Nullable
+    public static native String getAutoDjSeedArtistBlocking();
+
+    /**
+     * Seeds Auto-DJ with the artist, null makes picks similar to the last queued track
+     */
+
+    public static native void setAutoDjSeedArtistBlocking(@Nullable String artist);
+
+    /**
+     * Starts guess-the-melody game over the library.
+     * Harder games play shorter clips and offer decoys that sound alike
+     *
+     * @param difficulty 0 - easy, 1 - normal, 2 - hard
+     */
+
+    public static native void startGtmGameBlocking(int difficulty, int rounds);
+
+    /**
+     * Picks the next round of the game
+     *
+     * @return answers to choose from, one of them is the played track
+     */
+
+    @NotNull
+    public static native Track[] nextGtmRoundBlocking();
+
+    /**
+     * Plays clip of the current round apart from the current track,
+     * returns when it ends or is stopped
+     */
+
+    public static native void playGtmClipBlocking(boolean isFading);
+
+    public static native void stopGtmClip();
+
+    /**
+     * Answers the current round, the result of the last round is stored among high scores
+     *
+     * @return [is correct (0 or 1), index of the correct answer, points, answer time in millis]
+     */
+
+    @NotNull
+    public static native int[] answerGtmRound(int choice);
+
+    /**
+     * @return [score, answered rounds, all rounds] or null if there is no game
+     */
+
+    @Nullable
+    public static native int[] getGtmScore();
+
+    /**
+     * Ends the game without storing its score
+     */
+
+    public static native void quitGtmGame();
+
+    /**
+     * Best results of the finished games, the highest first
+     *
+     * @param difficulty 0 - easy, 1 - normal, 2 - hard, negative for all difficulties
+     * @return [score, difficulty, rounds, correct answers, unix time in seconds, ...]
+     */
+
+    @NotNull
+    public static native long[] getGtmHighScoresBlocking(int difficulty, int limit);
+
+    /**
+     * Latest levels and spectrum of the played samples, doesn't block.
+     * Levels are linear in range [0, 1],
+     * full scale sine has magnitude of 1 in its band
+     *
+     * @return [channels, rms 0, ..., peak 0, ..., band 0, ...]
+     * or null if nothing is playing
+     */
+
+    @Nullable
+    public static native float[] getMeterFrame();
+
+    public static native int getSpectrumBands();
+
+    /**
+     * Number of logarithmically spaced bands from 20 Hz,
+     * applied from the next meter frame
+     *
+     * @param bands number of bands in range [1, 256]
+     */
+
+    public static native void setSpectrumBands(int bands);
+
+    @NotNull
+    public static native String[] getOutputDevicesBlocking();
+
+    /**
+     * Chosen output device or null if system's default device is used
+     */
+
+    @Nullable
+    public static native String getOutputDeviceBlocking();
+
+    public static native void setOutputDeviceBlocking(@Nullable String device);
+
+    public static native boolean isHttpApiEnabledBlocking();
+
+    /**
+     * Stores the setting and starts or stops remote-control API
+     */
+
+    public static native void setHttpApiEnabledBlocking(boolean isEnabled);
+
+    public static native boolean isHttpApiLanEnabledBlocking();
+
+    /**
+     * Stores whether other devices may connect
+     * and restarts remote-control API if it's running.
+     * Only local clients are accepted by default
+     */
+
+    public static native void setHttpApiLanEnabledBlocking(boolean isEnabled);
+
+    public static native int getHttpApiPortBlocking();
+
+    /**
+     * Stores the port and restarts remote-control API if it's running
+     */
+
+    public static native void setHttpApiPortBlocking(int port);
+
+    /**
+     * Token that clients of remote-control API must present
+     * as a bearer token or the token query parameter
+     */
+
+    @NotNull
+    public static native String getHttpApiTokenBlocking();
+
+    public static native boolean isMpdEnabledBlocking();
+
+    /**
+     * Stores the setting and starts or stops MPD server
+     */
+
+    public static native void setMpdEnabledBlocking(boolean isEnabled);
+
+    public static native boolean isMpdLanEnabledBlocking();
+
+    /**
+     * Stores whether other devices may connect
+     * and restarts MPD server if it's running.
+     * Only local clients are accepted by default
+     */
+
+    public static native void setMpdLanEnabledBlocking(boolean isEnabled);
+
+    public static native int getMpdPortBlocking();
+
+    /**
+     * Stores the port and restarts MPD server if it's running
+     */
+
+    public static native void setMpdPortBlocking(int port);
+
+    /**
+     * Password that remote MPD clients must send
+     */
+
+    @NotNull
+    public static native String getMpdPasswordBlocking();
+
+    /**
+     * @return lyrics of the track as LRC (plain text if they are not synchronized)
+     * or null if the track has no lyrics
+     */
+
+    @Nullable
+    public static native String getLyricsBlocking(@NotNull String track);
+
+    /**
+     * @return index of the current track's lyrics line that is sung now,
+     * -1 if there is no such line or lyrics are not synchronized
+     */
+
+    public static native int getActiveLyricsLineIndexBlocking();
+
+    /**
+     * Saves edited LRC lyrics to the .lrc file next to the track
+     */
+
+    public static native void saveLyricsBlocking(@NotNull String track, @NotNull String lrc);
+
+    /**
+     * Stores ListenBrainz account and restarts the scrobbler
+     * @param token user token, null disables ListenBrainz
+     * @param apiUrl URL of a compatible server, null means the official one
+     */
+
+    public static native void setListenBrainzBlocking(@Nullable String token, @Nullable String apiUrl);
+
+    /**
+     * Stores Last.fm session and restarts the scrobbler
+     * @param sessionKey key of the authenticated session, null disables Last.fm
+     * @param apiUrl URL of a compatible API (e.g. Libre.fm), null means the official one
+     */
+
+    public static native void setLastFmBlocking(
+            @NotNull String apiKey,
+            @NotNull String apiSecret,
+            @Nullable String sessionKey,
+            @Nullable String apiUrl
+    );
+
+    /**
+     * @return number of listens that wait to be submitted to scrobbling services
+     */
+
+    public static native int getPendingScrobblesCountBlocking();
+
+    /**
+     * Starts sleep timer, replacing the running one.
+     * Position is saved when playback is stopped, so it's resumed next time
+     * @param mode 0 - after {@code millis}, 1 - end of the current track,
+     * 2 - end of the current album
+     * @param isFading whether volume is faded out during the last minute
+     */
+
+    public static native void startSleepTimerBlocking(int mode, long millis, boolean isFading);
+
+    public static native void cancelSleepTimerBlocking();
+
+    /**
+     * @return mode of the running sleep timer or -1 if there is no timer
+     */
+
+    public static native int getSleepTimerModeBlocking();
+
+    /**
+     * @return playback time in millis until the sleep timer stops it
+     * or -1 if there is no timer
+     */
+
+    public static native long getSleepTimerTimeLeftBlocking();
+
+    /**
+     * Sets A-B loop of the current track, which is stored for next time.
+     * Playing track jumps to A if it's outside of the segment
+     * @param iterations how many times the segment is played,
+     * it's repeated until cleared if {@code iterations <= 0}
+     */
+
+    public static native void setAbLoopBlocking(long startMillis, long endMillis, int iterations);
+
+    public static native void clearAbLoopBlocking();
+
+    /**
+     * @return [start millis, end millis, iterations (0 for an endless loop), jumps made]
+     * or null if the current track has no loop
+     */
+
+    @Nullable
+    public static native long[] getAbLoopBlocking();
+
+    /**
+     * @return position in millis where the track was left last time
+     * or -1 if it starts from the beginning
+     */
+
+    public static native long getResumePositionBlocking(@NotNull String path);
+
+    /**
+     * Tracks of at least this length resume where they were left
+     */
+
+    public static native long getResumeThresholdBlocking();
+
+    public static native void setResumeThresholdBlocking(long millis);
+
+    /**
+     * Adds bookmark at the current position of the current track
+     * @param name blank name is replaced with the position
+     */
+
+    @NotNull
+    public static native Bookmark addBookmarkBlocking(@NotNull String name, @Nullable String note);
+
+    /**
+     * @param path track whose bookmarks are returned, all bookmarks if null
+     */
+
+    @NotNull
+    public static native Bookmark[] getBookmarksBlocking(@Nullable String path);
+
+    /**
+     * @return false if there is no such bookmark
+     */
+
+    public static native boolean updateBookmarkBlocking(long id, @NotNull String name, @Nullable String note);
+
+    /**
+     * @return false if there is no such bookmark
+     */
+
+    public static native boolean removeBookmarkBlocking(long id);
+
+    /**
+     * Plays track of the bookmark from its position.
+     * Returns when the track ends
+     */
+
+    public static native void jumpToBookmarkBlocking(long id);
+
+    /**
+     * Plays internet radio from HTTP(S) URL, MP3 and Ogg Vorbis streams are supported.
+     * Returns when the radio is paused, stopped or lost
+     */
+
+    public static native void playStreamBlocking(@NotNull String url);
+
+    /**
+     * Plays the station's URL, see {@link #playStreamBlocking(String)}
+     */
+
+    public static native void playRadioStationBlocking(long id);
+
+    /**
+     * @return URL of the internet radio that is played or paused, null for tracks
+     */
+
+    @Nullable
+    public static native String getStreamUrlBlocking();
+
+    /**
+     * @return title of the song that the internet radio has announced
+     */
+
+    @Nullable
+    public static native String getStreamTitleBlocking();
+
+    /**
+     * @param name blank name is replaced with the URL
+     */
+
+    @NotNull
+    public static native RadioStation addRadioStationBlocking(@NotNull String name, @NotNull String url);
+
+    @NotNull
+    public static native RadioStation[] getRadioStationsBlocking();
+
+    /**
+     * @return false if there is no such station
+     */
+
+    public static native boolean updateRadioStationBlocking(long id, @NotNull String name, @NotNull String url);
+
+    /**
+     * @return false if there is no such station
+     */
+
+    public static native boolean removeRadioStationBlocking(long id);
+
+    /**
+     * Subscribes to RSS or Atom feed from HTTP(S) URL.
+     * Its current episodes are listed, the ones published later are downloaded automatically
+     */
+
+    @NotNull
+    public static native PodcastFeed subscribePodcastBlocking(@NotNull String url);
+
+    /**
+     * Forgets the feed and removes downloads of its episodes
+     * @return false if there is no such feed
+     */
+
+    public static native boolean unsubscribePodcastBlocking(long id);
+
+    @NotNull
+    public static native PodcastFeed[] getPodcastFeedsBlocking();
+
+    /**
+     * @return episodes of the feed, the newest first
+     */
+
+    @NotNull
+    public static native PodcastEpisode[] getPodcastEpisodesBlocking(long feedId);
+
+    /**
+     * Fetches the feed and downloads its new episodes
+     * @return number of new episodes
+     */
+
+    public static native int refreshPodcastBlocking(long id);
+
+    /**
+     * Fetches all feeds and downloads their new episodes.
+     * Feeds are also refreshed periodically once the library is initialised
+     * @return number of new episodes
+     */
+
+    public static native int refreshPodcastsBlocking();
+
+    /**
+     * Downloads the episode, interrupted download continues where it has stopped
+     * @return downloaded file
+     */
+
+    @NotNull
+    public static native String downloadPodcastEpisodeBlocking(long id);
+
+    /**
+     * @return false if there is no such episode
+     */
+
+    public static native boolean removePodcastDownloadBlocking(long id);
+
+    /**
+     * Marks episode played or unplayed, its position is reset
+     * @return false if there is no such episode
+     */
+
+    public static native boolean setPodcastEpisodePlayedBlocking(long id, boolean isPlayed);
+
+    /**
+     * @param feedId negative value returns episodes of all feeds
+     * @return downloaded episodes, the newest first
+     */
+
+    @NotNull
+    public static native Track[] getPodcastPlaylistBlocking(long feedId);
+
+    @NotNull
+    public static native String getPodcastsDirBlocking();
+
+    public static native void setPodcastsDirBlocking(@NotNull String dir);
+
+    public static native long getPodcastRefreshIntervalBlocking();
+
+    /**
+     * @param millis how often feeds are refreshed, applied after the current wait
+     */
+
+    public static native void setPodcastRefreshIntervalBlocking(long millis);
+
     @NotNull
     public static native String hello(@NotNull String name);
 
@@ -69,7 +617,8 @@ public final class RustLibs {
 
     /**
      * Gets track order's comparator and order as [int; 2]
-     * @return 0 -> comparator (number in [0..4]); 1 -> order (number in [5..6])
+     * @return 0 -> comparator (number in [0..7], as in {@link #setTrackOrderBlocking});
+     * 1 -> order (0 - asc, 1 - desc)
      */
 
     @NotNull
@@ -77,8 +626,11 @@ public final class RustLibs {
 
     /**
      * Updates track ordering
-     * @param comparator compare by: 0 - title, 1 - artist, 2 - album, 3 - date, 4 - № in album
-     * @param order compare by: 0 - asc, 1 - desc
+     * @param comparator compare by: 0 - title, 1 - artist, 2 - album, 3 - date, 4 - № in album,
+     *                   5 - BPM, 6 - key on the Camelot wheel, 7 - energy.
+     *                   Tracks that were not analysed go last
+     * @param order compare by: 0 - asc, 1 - desc.
+     *              Unknown codes are rejected with exception
      */
 
     public static native void setTrackOrderBlocking(int comparator, int order);
@@ -0,0 +1,13 @@
+package com.paranid5.prima.rust.exceptions;
+
+import org.jetbrains.annotations.NotNull;
+
+/**
+ * Settings are missing or invalid
+ */
+
+public final class PrimaConfigException extends PrimaException {
+    public PrimaConfigException(@NotNull final String message) {
+        super(message);
+    }
+}
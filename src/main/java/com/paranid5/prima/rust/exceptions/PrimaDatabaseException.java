@@ -0,0 +1,13 @@
+package com.paranid5.prima.rust.exceptions;
+
+import org.jetbrains.annotations.NotNull;
+
+/**
+ * SQLite connection or query failure
+ */
+
+public final class PrimaDatabaseException extends PrimaException {
+    public PrimaDatabaseException(@NotNull final String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,13 @@
+package com.paranid5.prima.rust.exceptions;
+
+import org.jetbrains.annotations.NotNull;
+
+/**
+ * Track can't be decoded or its format is not supported
+ */
+
+public final class PrimaDecodeException extends PrimaException {
+    public PrimaDecodeException(@NotNull final String message) {
+        super(message);
+    }
+}
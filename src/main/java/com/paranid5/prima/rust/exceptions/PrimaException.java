@@ -0,0 +1,15 @@
+package com.paranid5.prima.rust.exceptions;
+
+import org.jetbrains.annotations.NotNull;
+
+/**
+ * Base class for all errors thrown by the native library.
+ * Rust errors and panics never cross JNI boundary as crashes,
+ * they are rethrown as subclasses of this exception
+ */
+
+public class PrimaException extends RuntimeException {
+    public PrimaException(@NotNull final String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,13 @@
+package com.paranid5.prima.rust.exceptions;
+
+import org.jetbrains.annotations.NotNull;
+
+/**
+ * File system error: track or settings file can't be read or written
+ */
+
+public final class PrimaIOException extends PrimaException {
+    public PrimaIOException(@NotNull final String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,13 @@
+package com.paranid5.prima.rust.exceptions;
+
+import org.jetbrains.annotations.NotNull;
+
+/**
+ * Failure while calling JVM or converting its objects
+ */
+
+public final class PrimaJniException extends PrimaException {
+    public PrimaJniException(@NotNull final String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,13 @@
+package com.paranid5.prima.rust.exceptions;
+
+import org.jetbrains.annotations.NotNull;
+
+/**
+ * Unexpected internal error of the native library
+ */
+
+public final class PrimaPanicException extends PrimaException {
+    public PrimaPanicException(@NotNull final String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,13 @@
+package com.paranid5.prima.rust.exceptions;
+
+import org.jetbrains.annotations.NotNull;
+
+/**
+ * Player can't perform requested action, e.g. there is nothing to play
+ */
+
+public final class PrimaPlaybackException extends PrimaException {
+    public PrimaPlaybackException(@NotNull final String message) {
+        super(message);
+    }
+}
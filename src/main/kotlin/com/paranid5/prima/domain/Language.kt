@@ -18,6 +18,9 @@ sealed interface Language {
         private const val BY_ALBUM = "by-album"
         private const val BY_DATE = "by-date"
         private const val BY_NUMBER_IN_ALBUM = "by-number-in-album"
+        private const val BY_BPM = "by-bpm"
+        private const val BY_KEY = "by-key"
+        private const val BY_ENERGY = "by-energy"
         private const val ARTISTS = "artists"
         private const val TRACK_COLLECTIONS = "track-collections"
         private const val FAVOURITES = "favourites"
@@ -76,6 +79,9 @@ sealed interface Language {
     val byAlbum get() = BY_ALBUM.fromData
     val byDate get() = BY_DATE.fromData
     val byNumberInAlbum get() = BY_NUMBER_IN_ALBUM.fromData
+    val byBpm get() = BY_BPM.fromData
+    val byKey get() = BY_KEY.fromData
+    val byEnergy get() = BY_ENERGY.fromData
     val artists get() = ARTISTS.fromData
     val trackCollections get() = TRACK_COLLECTIONS.fromData
     val favourites get() = FAVOURITES.fromData
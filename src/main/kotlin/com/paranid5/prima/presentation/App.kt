@@ -23,10 +23,7 @@ fun App() {
             title = "Prima",
             icon = painterResource("images/app_icon.png"),
             state = WindowState(width = width.dp, height = height.dp),
-            onCloseRequest = {
-                RustLibs.storeCurPlaybackPosBlocking()
-                exitApplication()
-            },
+            onCloseRequest = ::exitApplication,
         ) {
             MainScreen()
         }
@@ -26,6 +26,10 @@ import kotlinx.coroutines.launch
 import kotlinx.coroutines.withContext
 import org.koin.compose.koinInject
 
+/** Positions of comparator's and order's codes in the track order state */
+private const val COMPARATOR = 0
+private const val ORDER = 1
+
 @Composable
 fun DefaultTracksBar(
     tracksState: MutableStateFlow<List<Track>>,
@@ -215,16 +219,19 @@ private fun TrackOrderMenu(
     ) {
         Row {
             Column(modifier = Modifier.weight(1F)) {
-                TrackOrderMenuItem(0, lang.byTitle, trackOrdState, tracksState, filteredTracksState)
-                TrackOrderMenuItem(1, lang.byArtist, trackOrdState, tracksState, filteredTracksState)
-                TrackOrderMenuItem(2, lang.byAlbum, trackOrdState, tracksState, filteredTracksState)
-                TrackOrderMenuItem(3, lang.byDate, trackOrdState, tracksState, filteredTracksState)
-                TrackOrderMenuItem(4, lang.byNumberInAlbum, trackOrdState, tracksState, filteredTracksState)
+                TrackOrderMenuItem(COMPARATOR, 0, lang.byTitle, trackOrdState, tracksState, filteredTracksState)
+                TrackOrderMenuItem(COMPARATOR, 1, lang.byArtist, trackOrdState, tracksState, filteredTracksState)
+                TrackOrderMenuItem(COMPARATOR, 2, lang.byAlbum, trackOrdState, tracksState, filteredTracksState)
+                TrackOrderMenuItem(COMPARATOR, 3, lang.byDate, trackOrdState, tracksState, filteredTracksState)
+                TrackOrderMenuItem(COMPARATOR, 4, lang.byNumberInAlbum, trackOrdState, tracksState, filteredTracksState)
+                TrackOrderMenuItem(COMPARATOR, 5, lang.byBpm, trackOrdState, tracksState, filteredTracksState)
+                TrackOrderMenuItem(COMPARATOR, 6, lang.byKey, trackOrdState, tracksState, filteredTracksState)
+                TrackOrderMenuItem(COMPARATOR, 7, lang.byEnergy, trackOrdState, tracksState, filteredTracksState)
             }
 
             Column(modifier = Modifier.weight(1F)) {
-                TrackOrderMenuItem(5, lang.ascending, trackOrdState, tracksState, filteredTracksState)
-                TrackOrderMenuItem(6, lang.descending, trackOrdState, tracksState, filteredTracksState)
+                TrackOrderMenuItem(ORDER, 0, lang.ascending, trackOrdState, tracksState, filteredTracksState)
+                TrackOrderMenuItem(ORDER, 1, lang.descending, trackOrdState, tracksState, filteredTracksState)
             }
         }
     }
@@ -232,7 +239,8 @@ private fun TrackOrderMenu(
 
 @Composable
 private fun TrackOrderMenuItem(
-    order: Int,
+    part: Int,
+    code: Int,
     title: String,
     trackOrdState: SnapshotStateList<Int>,
     tracksState: MutableStateFlow<List<Track>>,
@@ -245,19 +253,16 @@ private fun TrackOrderMenuItem(
     val secondaryAlternativeColor by storageHandler.secondaryAlternativeColorState.collectAsState()
 
     val coroutineScope = rememberCoroutineScope()
-    val isChecked by remember { derivedStateOf { order in trackOrdState } }
+    val isChecked by remember { derivedStateOf { trackOrdState.getOrNull(part) == code } }
 
     DropdownMenuItem(
         modifier = modifier,
         onClick = {
             if (!isChecked) {
-                when (order) {
-                    in 0..4 -> trackOrdState[0] = order
-                    else -> trackOrdState[1] = order
-                }
+                trackOrdState[part] = code
 
                 coroutineScope.launch {
-                    RustLibs.setTrackOrderBlocking(trackOrdState[0], trackOrdState[1])
+                    RustLibs.setTrackOrderBlocking(trackOrdState[COMPARATOR], trackOrdState[ORDER])
                     scanTracks(tracksState, filteredTracksState)
                 }
             }
@@ -267,12 +272,9 @@ private fun TrackOrderMenuItem(
             checked = isChecked,
             onCheckedChange = {
                 if (!isChecked) {
-                    when (order) {
-                        in 0..4 -> trackOrdState[0] = order
-                        else -> trackOrdState[1] = order
-                    }
+                    trackOrdState[part] = code
 
-                    RustLibs.setTrackOrderBlocking(trackOrdState[0], trackOrdState[1])
+                    RustLibs.setTrackOrderBlocking(trackOrdState[COMPARATOR], trackOrdState[ORDER])
                     coroutineScope.launch { scanTracks(tracksState, filteredTracksState) }
                 }
             },
@@ -0,0 +1,445 @@
+extern crate prima_pc;
+extern crate tokio;
+
+use prima_pc::{
+    data::entities::{
+        artists::artist_trait::ArtistTrait,
+        playlists::{default_playlist::DefaultPlaylist, playlist_type::PlaylistType},
+        tracks::{default_track::DefaultTrack, track_trait::TrackTrait},
+    },
+    data::{databases::favourites::db::favourite_db_url, utils::paths::APP_PATHS},
+    domain::{
+        http_api::http_api_server::HttpApiServer, metadata_reader::MetadataReader,
+        mpd::mpd_server::MpdServer, podcasts::podcast_refresher::PodcastRefresher, prima::Prima,
+        scrobbling::scrobbler::Scrobbler,
+    },
+    error::{Error, Result},
+};
+
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
+
+const USAGE: &str = r#"Usage: prima <command> [arguments]
+
+Commands:
+  scan [folder]                     Scans the music folder, remembers it if given
+  tracks                            Lists tracks of the music folder
+  artists                           Lists artists of the music folder
+  favourites [tracks|artists|playlists]
+                                    Lists liked tracks, artists or playlists
+  play <file|playlist.m3u>...       Plays files or playlists in the terminal
+  like <file>...                    Adds tracks to favourites
+  unlike <file>...                  Removes tracks from favourites
+  lyrics <file>                     Prints lyrics of the track as LRC
+  serve [port]                      Runs remote-control HTTP API, MPD server,
+                                    scrobbler and podcast refresher until interrupted
+  settings                          Prints stored settings
+  help                              Prints this message"#;
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let prima = match Prima::new() {
+        Ok(prima) => Arc::new(prima),
+
+        Err(e) => {
+            eprintln!("prima: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match args.first().map(String::as_str) {
+        Some("scan") => scan(&prima, args.get(1)),
+        Some("tracks") => list_tracks(&prima),
+        Some("artists") => list_artists(&prima),
+        Some("favourites") => list_favourites(&prima, args.get(1).map(String::as_str)),
+        Some("play") => play(prima.clone(), &args[1..]),
+        Some("like") => set_liked(&prima, &args[1..], true),
+        Some("unlike") => set_liked(&prima, &args[1..], false),
+        Some("lyrics") => print_lyrics(&prima, args.get(1)),
+        Some("serve") => serve(prima.clone(), args.get(1)),
+        Some("settings") => print_settings(&prima),
+
+        Some("help") | Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match result.and_then(|_| prima.flush()) {
+        Ok(()) => ExitCode::SUCCESS,
+
+        Err(e) => {
+            eprintln!("prima: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[inline]
+fn invalid_input(message: String) -> Error {
+    Error::Io(std::io::Error::new(ErrorKind::InvalidInput, message))
+}
+
+#[inline]
+fn library_tracks(prima: &Prima) -> Result<Vec<DefaultTrack>> {
+    if prima.get_music_search_path().is_none() {
+        return Err(Error::Config(
+            "Music folder is not set, run `prima scan <folder>`".to_string(),
+        ));
+    }
+
+    prima.get_all_tracks()
+}
+
+#[inline]
+fn format_track(track: &impl TrackTrait) -> String {
+    let seconds = track.get_duration().num_seconds();
+    let text = |s: Option<&String>| s.filter(|s| !s.is_empty()).cloned();
+
+    format!(
+        "{} - {} [{}:{:02}] {}",
+        text(track.get_artist()).unwrap_or_else(|| "Unknown artist".to_string()),
+        text(track.get_title()).unwrap_or_else(|| "Unknown track".to_string()),
+        seconds / 60,
+        seconds % 60,
+        track.get_path().display(),
+    )
+}
+
+#[inline]
+fn scan(prima: &Prima, folder: Option<&String>) -> Result<()> {
+    if let Some(folder) = folder {
+        let folder = fs::canonicalize(folder)?;
+
+        if !folder.is_dir() {
+            return Err(invalid_input(format!(
+                "{} is not a folder",
+                folder.display()
+            )));
+        }
+
+        prima.set_music_search_path(folder);
+    }
+
+    let tracks = library_tracks(prima)?;
+    let artists = tracks
+        .iter()
+        .filter_map(|t| t.get_artist())
+        .filter(|a| !a.is_empty())
+        .collect::<BTreeSet<_>>();
+
+    println!(
+        "{}: {} tracks, {} artists",
+        prima.get_music_search_path().unwrap_or_default().display(),
+        tracks.len(),
+        artists.len()
+    );
+
+    Ok(())
+}
+
+#[inline]
+fn list_tracks(prima: &Prima) -> Result<()> {
+    library_tracks(prima)?
+        .iter()
+        .for_each(|track| println!("{}", format_track(track)));
+    Ok(())
+}
+
+#[inline]
+fn list_artists(prima: &Prima) -> Result<()> {
+    library_tracks(prima)?;
+
+    prima
+        .get_all_artists()?
+        .into_iter()
+        .filter(|a| !a.is_empty())
+        .for_each(|artist| println!("{}", artist));
+
+    Ok(())
+}
+
+#[inline]
+fn list_favourites(prima: &Prima, kind: Option<&str>) -> Result<()> {
+    match kind.unwrap_or("tracks") {
+        "tracks" => prima
+            .get_favourite_tracks()?
+            .iter()
+            .for_each(|track| println!("{}", format_track(track))),
+
+        "artists" => prima
+            .get_favourite_artists()?
+            .iter()
+            .for_each(|artist| println!("{}", artist.get_name())),
+
+        "playlists" => prima
+            .get_favourite_playlists()?
+            .iter()
+            .for_each(|playlist| {
+                println!(
+                    "{}: {}",
+                    playlist.get_id(),
+                    playlist.get_title().as_deref().unwrap_or("Untitled")
+                )
+            }),
+
+        kind => return Err(invalid_input(format!("Unknown favourites kind: {}", kind))),
+    }
+
+    Ok(())
+}
+
+/// Expands `.m3u` playlists into the list of their tracks.
+/// Relative entries are resolved against the playlist's folder
+#[inline]
+fn expand_playlists(files: &[String]) -> Result<Vec<PathBuf>> {
+    let mut tracks = Vec::new();
+
+    for file in files.iter().map(PathBuf::from) {
+        let is_playlist = file
+            .extension()
+            .map(|e| e.eq_ignore_ascii_case("m3u") || e.eq_ignore_ascii_case("m3u8"))
+            .unwrap_or(false);
+
+        if !is_playlist {
+            tracks.push(file);
+            continue;
+        }
+
+        let folder = file.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        fs::read_to_string(&file)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .for_each(|line| tracks.push(folder.join(line)));
+    }
+
+    Ok(tracks)
+}
+
+/// Starts scrobbler if some scrobbling service is configured
+#[inline]
+fn start_scrobbler(prima: &Arc<Prima>) -> Option<Scrobbler> {
+    let submitters = prima.get_scrobble_submitters();
+
+    match submitters.is_empty() {
+        true => None,
+        false => Some(Scrobbler::start(prima.clone(), submitters)),
+    }
+}
+
+/// Plays tracks one after another through the session's player,
+/// so volume, speed, effects and output device are the stored ones.
+/// Listens are scrobbled if scrobbling is configured
+#[inline]
+fn play(prima: Arc<Prima>, files: &[String]) -> Result<()> {
+    let tracks = expand_playlists(files)?
+        .into_iter()
+        .filter_map(|path| {
+            let track = MetadataReader::read_track(&path);
+
+            if track.is_none() {
+                eprintln!("Skipping {}: unsupported audio file", path.display())
+            }
+
+            track
+        })
+        .collect::<Vec<_>>();
+
+    if tracks.is_empty() {
+        return Err(invalid_input("Nothing to play".to_string()));
+    }
+
+    let scrobbler = start_scrobbler(&prima);
+
+    for (index, track) in tracks.iter().enumerate() {
+        println!("Playing {}", format_track(track));
+
+        let playlist = DefaultPlaylist::new(None, PlaylistType::default(), tracks.clone(), index);
+
+        if let Err(e) = prima.play(playlist) {
+            eprintln!("Unable to play {}: {}", track.get_path().display(), e)
+        }
+    }
+
+    drop(scrobbler);
+    Ok(())
+}
+
+#[inline]
+fn set_liked(prima: &Prima, files: &[String], is_liked: bool) -> Result<()> {
+    if files.is_empty() {
+        return Err(invalid_input("No tracks are given".to_string()));
+    }
+
+    for file in files {
+        let path = fs::canonicalize(file)?;
+
+        let track = MetadataReader::read_track(&path)
+            .ok_or_else(|| Error::Decode(format!("Unsupported audio file: {}", path.display())))?;
+
+        prima.set_track_liked(track, is_liked)?;
+    }
+
+    Ok(())
+}
+
+/// Prints lyrics from `.lrc` file or tags
+#[inline]
+fn print_lyrics(prima: &Prima, file: Option<&String>) -> Result<()> {
+    let file = file.ok_or_else(|| invalid_input("Audio file is required".to_string()))?;
+
+    match prima.get_lyrics(Path::new(file)) {
+        None => println!("{} has no lyrics", file),
+        Some(lyrics) => print!("{}", lyrics.to_lrc()),
+    }
+
+    Ok(())
+}
+
+/// Serves remote-control API with the stored token, MPD server if it's enabled
+/// and MPRIS on Linux, refreshes podcasts, until Ctrl+C is pressed. Port from arguments is not stored
+#[inline]
+fn serve(prima: Arc<Prima>, port: Option<&String>) -> Result<()> {
+    let port = match port {
+        None => prima.get_http_api_port(),
+
+        Some(port) => port
+            .parse()
+            .map_err(|_| invalid_input(format!("Invalid port: {}", port)))?,
+    };
+
+    let token = prima.get_http_api_token()?;
+
+    let server = HttpApiServer::start(
+        prima.clone(),
+        port,
+        token.clone(),
+        prima.is_http_api_lan_enabled(),
+    )?;
+
+    let mpd_server = match prima.is_mpd_enabled() {
+        false => None,
+
+        true => Some(MpdServer::start(
+            prima.clone(),
+            prima.get_mpd_port(),
+            Some(prima.get_mpd_password()?),
+            prima.is_mpd_lan_enabled(),
+        )?),
+    };
+
+    let scrobbler = start_scrobbler(&prima);
+    let podcast_refresher = PodcastRefresher::start(prima.clone())?;
+
+    #[cfg(target_os = "linux")]
+    let _mpris_server = prima_pc::domain::mpris::mpris_server::MprisServer::start(prima.clone())
+        .map_err(|e| eprintln!("MPRIS is unavailable: {}", e))
+        .ok();
+
+    println!("HTTP API is listening on port {}", server.get_port());
+    println!("token: {}", token);
+
+    if let Some(mpd_server) = &mpd_server {
+        println!("MPD server is listening on port {}", mpd_server.get_port())
+    }
+
+    prima
+        .get_tokio_runtime()
+        .block_on(tokio::signal::ctrl_c())?;
+    drop(podcast_refresher);
+    drop(scrobbler);
+    drop(mpd_server);
+    drop(server);
+    prima.shutdown()
+}
+
+#[inline]
+fn print_settings(prima: &Prima) -> Result<()> {
+    let track_order = prima.get_track_order();
+
+    println!("state file: {}", APP_PATHS.get_state_file().display());
+    println!("favourites: {}", favourite_db_url());
+
+    println!(
+        "music folder: {}",
+        prima
+            .get_music_search_path()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "not set".to_string())
+    );
+
+    println!(
+        "track order: {:?} {:?}",
+        track_order.comparator, track_order.order
+    );
+
+    println!("looping: {:?}", prima.get_looping_state());
+    println!("volume: {}", prima.get_volume());
+    println!("speed: {}", prima.get_speed());
+
+    println!(
+        "output device: {}",
+        prima
+            .get_output_device()
+            .unwrap_or_else(|| "default".to_string())
+    );
+
+    println!(
+        "http api: {} on port {}",
+        match prima.is_http_api_enabled() {
+            true => "enabled",
+            false => "disabled",
+        },
+        prima.get_http_api_port()
+    );
+
+    println!(
+        "mpd: {} on port {}",
+        match prima.is_mpd_enabled() {
+            true => "enabled",
+            false => "disabled",
+        },
+        prima.get_mpd_port()
+    );
+
+    let scrobbling = prima.get_scrobbling_settings();
+
+    println!(
+        "scrobbling: listenbrainz {}, lastfm {}",
+        match scrobbling.listenbrainz {
+            Some(_) => "enabled",
+            None => "disabled",
+        },
+        match scrobbling.lastfm {
+            Some(_) => "enabled",
+            None => "disabled",
+        },
+    );
+
+    println!(
+        "pending scrobbles: {}",
+        prima.get_pending_scrobbles_count()?
+    );
+
+    println!("podcasts: {}", prima.get_podcasts_dir().display());
+
+    println!(
+        "podcast refresh interval: {} min",
+        prima.get_podcast_refresh_interval().as_secs() / 60
+    );
+    Ok(())
+}
@@ -0,0 +1,24 @@
+extern crate diesel;
+
+use crate::error::Result;
+use diesel::{RunQueryDsl, SqliteConnection};
+
+/// Creates table with tempo, key and loudness of tracks
+/// if it doesn't exist. It's kept in the favourites database
+#[inline]
+pub fn create_tables(connection: &mut SqliteConnection) -> Result<()> {
+    diesel::sql_query(
+        r#"CREATE TABLE IF NOT EXISTS track_analysis (
+  path TEXT PRIMARY KEY NOT NULL,
+  bpm REAL,
+  musical_key INTEGER,
+  energy REAL NOT NULL,
+  loudness_range REAL NOT NULL,
+  modified_at BIGINT NOT NULL,
+  analysed_at BIGINT NOT NULL
+)"#,
+    )
+    .execute(connection)?;
+
+    Ok(())
+}
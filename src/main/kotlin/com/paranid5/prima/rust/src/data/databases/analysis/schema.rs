@@ -0,0 +1,11 @@
+diesel::table! {
+    track_analysis (path) {
+        path -> Text,
+        bpm -> Nullable<Float>,
+        musical_key -> Nullable<Integer>,
+        energy -> Float,
+        loudness_range -> Float,
+        modified_at -> BigInt,
+        analysed_at -> BigInt,
+    }
+}
@@ -0,0 +1,102 @@
+extern crate diesel;
+
+use crate::{
+    data::databases::analysis::schema::track_analysis::{self, dsl},
+    domain::analysis::{musical_key::MusicalKey, track_analysis::TrackAnalysis},
+    error::Result,
+};
+
+use diesel::{prelude::*, SqliteConnection};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Debug, Queryable, Insertable)]
+#[diesel(table_name = track_analysis)]
+struct TrackAnalysisDBEntity {
+    path: String,
+    bpm: Option<f32>,
+    musical_key: Option<i32>,
+    energy: f32,
+    loudness_range: f32,
+    modified_at: i64,
+    analysed_at: i64,
+}
+
+impl From<TrackAnalysisDBEntity> for TrackAnalysis {
+    #[inline]
+    fn from(entity: TrackAnalysisDBEntity) -> Self {
+        Self {
+            bpm: entity.bpm,
+            key: entity
+                .musical_key
+                .and_then(|index| MusicalKey::from_index(u8::try_from(index).ok()?)),
+            energy: entity.energy,
+            loudness_range: entity.loudness_range,
+        }
+    }
+}
+
+/// Tempo, key and loudness of analysed tracks, one entry per track.
+/// Entry remembers modification time of the file it was computed from
+pub struct TrackAnalysisDao;
+
+impl TrackAnalysisDao {
+    #[inline]
+    pub fn store(
+        path: &Path,
+        analysis: &TrackAnalysis,
+        modified_at: i64,
+        analysed_at: i64,
+        conn: &mut SqliteConnection,
+    ) -> Result<()> {
+        diesel::replace_into(dsl::track_analysis)
+            .values(TrackAnalysisDBEntity {
+                path: path.to_string_lossy().to_string(),
+                bpm: analysis.bpm,
+                musical_key: analysis.key.map(|key| key.get_index() as i32),
+                energy: analysis.energy,
+                loudness_range: analysis.loudness_range,
+                modified_at,
+                analysed_at,
+            })
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get(path: &Path, conn: &mut SqliteConnection) -> Result<Option<TrackAnalysis>> {
+        let entity: Option<TrackAnalysisDBEntity> = dsl::track_analysis
+            .filter(dsl::path.eq(path.to_string_lossy().to_string()))
+            .first(conn)
+            .optional()?;
+
+        Ok(entity.map(TrackAnalysis::from))
+    }
+
+    #[inline]
+    pub fn get_all(conn: &mut SqliteConnection) -> Result<HashMap<PathBuf, TrackAnalysis>> {
+        let entities: Vec<TrackAnalysisDBEntity> = dsl::track_analysis.load(conn)?;
+
+        Ok(entities
+            .into_iter()
+            .map(|entity| (PathBuf::from(&entity.path), TrackAnalysis::from(entity)))
+            .collect())
+    }
+
+    /// Modification times of the analysed files,
+    /// files with other times have to be analysed again
+    #[inline]
+    pub fn get_modified_times(conn: &mut SqliteConnection) -> Result<HashMap<PathBuf, i64>> {
+        let entries: Vec<(String, i64)> = dsl::track_analysis
+            .select((dsl::path, dsl::modified_at))
+            .load(conn)?;
+
+        Ok(entries
+            .into_iter()
+            .map(|(path, modified_at)| (PathBuf::from(path), modified_at))
+            .collect())
+    }
+}
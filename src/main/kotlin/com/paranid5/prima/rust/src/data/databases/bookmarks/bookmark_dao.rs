@@ -0,0 +1,130 @@
+extern crate diesel;
+
+use crate::{
+    data::databases::bookmarks::schema::bookmarks::{self, dsl},
+    domain::bookmarks::bookmark::Bookmark,
+    error::Result,
+};
+
+use diesel::{prelude::*, SqliteConnection};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+#[derive(Clone, Debug, Queryable)]
+#[diesel(table_name = bookmarks)]
+struct BookmarkDBEntity {
+    id: i32,
+    path: String,
+    position: i64,
+    name: String,
+    note: Option<String>,
+    created_at: i64,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = bookmarks)]
+struct NewBookmarkDBEntity<'a> {
+    path: String,
+    position: i64,
+    name: &'a str,
+    note: Option<&'a str>,
+    created_at: i64,
+}
+
+impl From<BookmarkDBEntity> for Bookmark {
+    #[inline]
+    fn from(entity: BookmarkDBEntity) -> Self {
+        Self {
+            id: entity.id,
+            path: PathBuf::from(entity.path),
+            position: Duration::from_millis(entity.position.max(0) as u64),
+            name: entity.name,
+            note: entity.note,
+            created_at: entity.created_at,
+        }
+    }
+}
+
+/// Named positions in tracks with optional notes
+pub struct BookmarkDao;
+
+impl BookmarkDao {
+    /// Stores new bookmark and returns it with the assigned id
+    #[inline]
+    pub fn insert(
+        path: &Path,
+        position: Duration,
+        name: &str,
+        note: Option<&str>,
+        created_at: i64,
+        conn: &mut SqliteConnection,
+    ) -> Result<Bookmark> {
+        conn.transaction(|conn| {
+            diesel::insert_into(dsl::bookmarks)
+                .values(NewBookmarkDBEntity {
+                    path: path.to_string_lossy().to_string(),
+                    position: position.as_millis() as i64,
+                    name,
+                    note,
+                    created_at,
+                })
+                .execute(conn)?;
+
+            let entity: BookmarkDBEntity = dsl::bookmarks.order(dsl::id.desc()).first(conn)?;
+            Ok(entity.into())
+        })
+    }
+
+    #[inline]
+    pub fn get(id: i32, conn: &mut SqliteConnection) -> Result<Option<Bookmark>> {
+        let entity: Option<BookmarkDBEntity> = dsl::bookmarks
+            .filter(dsl::id.eq(id))
+            .first(conn)
+            .optional()?;
+
+        Ok(entity.map(Bookmark::from))
+    }
+
+    /// Bookmarks of the track ordered by position
+    #[inline]
+    pub fn get_by_path(path: &Path, conn: &mut SqliteConnection) -> Result<Vec<Bookmark>> {
+        let entities: Vec<BookmarkDBEntity> = dsl::bookmarks
+            .filter(dsl::path.eq(path.to_string_lossy().to_string()))
+            .order((dsl::position.asc(), dsl::id.asc()))
+            .load(conn)?;
+
+        Ok(entities.into_iter().map(Bookmark::from).collect())
+    }
+
+    /// Bookmarks of all tracks, grouped by track
+    #[inline]
+    pub fn get_all(conn: &mut SqliteConnection) -> Result<Vec<Bookmark>> {
+        let entities: Vec<BookmarkDBEntity> = dsl::bookmarks
+            .order((dsl::path.asc(), dsl::position.asc(), dsl::id.asc()))
+            .load(conn)?;
+
+        Ok(entities.into_iter().map(Bookmark::from).collect())
+    }
+
+    #[inline]
+    pub fn update(
+        id: i32,
+        name: &str,
+        note: Option<&str>,
+        conn: &mut SqliteConnection,
+    ) -> Result<bool> {
+        let updated = diesel::update(dsl::bookmarks.filter(dsl::id.eq(id)))
+            .set((dsl::name.eq(name), dsl::note.eq(note)))
+            .execute(conn)?;
+
+        Ok(updated > 0)
+    }
+
+    #[inline]
+    pub fn remove(id: i32, conn: &mut SqliteConnection) -> Result<bool> {
+        let removed = diesel::delete(dsl::bookmarks.filter(dsl::id.eq(id))).execute(conn)?;
+        Ok(removed > 0)
+    }
+}
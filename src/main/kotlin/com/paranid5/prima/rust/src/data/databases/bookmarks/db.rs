@@ -0,0 +1,35 @@
+extern crate diesel;
+
+use crate::error::Result;
+use diesel::{RunQueryDsl, SqliteConnection};
+
+/// Creates tables of remembered positions and bookmarks
+/// if they don't exist. Both are kept in the favourites database
+#[inline]
+pub fn create_tables(connection: &mut SqliteConnection) -> Result<()> {
+    diesel::sql_query(
+        r#"CREATE TABLE IF NOT EXISTS track_positions (
+  path TEXT PRIMARY KEY NOT NULL,
+  position BIGINT NOT NULL,
+  updated_at BIGINT NOT NULL
+)"#,
+    )
+    .execute(connection)?;
+
+    diesel::sql_query(
+        r#"CREATE TABLE IF NOT EXISTS bookmarks (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  path TEXT NOT NULL,
+  position BIGINT NOT NULL,
+  name TEXT NOT NULL,
+  note TEXT,
+  created_at BIGINT NOT NULL
+)"#,
+    )
+    .execute(connection)?;
+
+    diesel::sql_query("CREATE INDEX IF NOT EXISTS bookmarks_path ON bookmarks (path)")
+        .execute(connection)?;
+
+    Ok(())
+}
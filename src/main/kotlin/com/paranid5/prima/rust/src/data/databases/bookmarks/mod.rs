@@ -0,0 +1,4 @@
+pub mod bookmark_dao;
+pub mod db;
+pub mod schema;
+pub mod track_position_dao;
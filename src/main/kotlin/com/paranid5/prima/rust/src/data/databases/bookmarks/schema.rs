@@ -0,0 +1,18 @@
+diesel::table! {
+    track_positions (path) {
+        path -> Text,
+        position -> BigInt,
+        updated_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    bookmarks (id) {
+        id -> Integer,
+        path -> Text,
+        position -> BigInt,
+        name -> Text,
+        note -> Nullable<Text>,
+        created_at -> BigInt,
+    }
+}
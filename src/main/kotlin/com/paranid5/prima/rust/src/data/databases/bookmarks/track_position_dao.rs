@@ -0,0 +1,61 @@
+extern crate diesel;
+
+use crate::{
+    data::databases::bookmarks::schema::track_positions::{self, dsl},
+    error::Result,
+};
+
+use diesel::{prelude::*, SqliteConnection};
+use std::{path::Path, time::Duration};
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = track_positions)]
+struct TrackPositionDBEntity {
+    path: String,
+    position: i64,
+    updated_at: i64,
+}
+
+/// Positions where long tracks were left, one per track
+pub struct TrackPositionDao;
+
+impl TrackPositionDao {
+    #[inline]
+    pub fn store(
+        path: &Path,
+        position: Duration,
+        updated_at: i64,
+        conn: &mut SqliteConnection,
+    ) -> Result<()> {
+        diesel::replace_into(dsl::track_positions)
+            .values(TrackPositionDBEntity {
+                path: path.to_string_lossy().to_string(),
+                position: position.as_millis() as i64,
+                updated_at,
+            })
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get(path: &Path, conn: &mut SqliteConnection) -> Result<Option<Duration>> {
+        let position: Option<i64> = dsl::track_positions
+            .select(dsl::position)
+            .filter(dsl::path.eq(path.to_string_lossy().to_string()))
+            .first(conn)
+            .optional()?;
+
+        Ok(position.map(|millis| Duration::from_millis(millis.max(0) as u64)))
+    }
+
+    #[inline]
+    pub fn remove(path: &Path, conn: &mut SqliteConnection) -> Result<()> {
+        diesel::delete(
+            dsl::track_positions.filter(dsl::path.eq(path.to_string_lossy().to_string())),
+        )
+        .execute(conn)?;
+
+        Ok(())
+    }
+}
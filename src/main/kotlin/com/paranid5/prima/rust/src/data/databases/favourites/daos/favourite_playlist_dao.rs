@@ -17,7 +17,7 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
 #[diesel(table_name = favourite_playlists)]
-pub(crate) struct FavouritePlaylistDBEntity {
+pub struct FavouritePlaylistDBEntity {
     id: i32,
     title: Option<String>,
     tp: i32,
@@ -54,6 +54,7 @@ impl<T: TrackExt> From<FavouritePlaylist<T>> for FavouritePlaylistDBEntity {
     }
 }
 
+#[allow(dead_code)]
 impl FavouritePlaylistDBEntity {
     #[inline]
     pub fn new(id: i32, title: Option<String>, tp: i32) -> Self {
@@ -86,6 +87,7 @@ impl_dao!(
     playlists_dsl
 );
 
+#[allow(dead_code)]
 impl FavouritePlaylistDao {
     #[inline]
     pub(crate) fn get_by_title_and_type(
@@ -11,10 +11,11 @@ use crate::{
         },
         utils::extensions::path_buf_ext::PathBufExt,
     },
+    error::Result,
     impl_dao, DBEntity, EntityDao, FavouriteTrack,
 };
 
-use chrono::{DateTime, Duration};
+use chrono::{DateTime, Duration, Local};
 use diesel::SqliteConnection;
 use jni::sys::jshort;
 use serde::{Deserialize, Serialize};
@@ -58,7 +59,10 @@ impl FavouriteTrack {
             entity.album,
             path.clone(),
             Duration::milliseconds(entity.duration),
-            DateTime::from(std::fs::metadata(path).unwrap().created().unwrap()),
+            std::fs::metadata(path)
+                .and_then(|m| m.created())
+                .map(DateTime::from)
+                .unwrap_or_else(|_| Local::now()),
             entity.number_in_album as jshort,
         ))
     }
@@ -106,24 +110,22 @@ impl_dao!(
 
 impl EntityDao<PathBuf, FavouriteTrack> for FavouriteTrackDao {
     #[inline]
-    fn get_all(conn: &mut SqliteConnection) -> Vec<FavouriteTrack> {
-        let entities: Vec<FavouriteTrackDBEntity> = FavouriteTrackDao::get_all(conn);
+    fn get_all(conn: &mut SqliteConnection) -> Result<Vec<FavouriteTrack>> {
+        let entities: Vec<FavouriteTrackDBEntity> = FavouriteTrackDao::get_all(conn)?;
 
-        entities
+        Ok(entities
             .into_iter()
             .filter_map(FavouriteTrack::from)
-            .collect()
+            .collect())
     }
 
     #[inline]
-    fn get_by_key(key: PathBuf, conn: &mut SqliteConnection) -> Option<FavouriteTrack> {
-        FavouriteTrackDao::get_by_key(key.to_string(), conn)
-            .map(FavouriteTrack::from)
-            .flatten()
+    fn get_by_key(key: PathBuf, conn: &mut SqliteConnection) -> Result<Option<FavouriteTrack>> {
+        Ok(FavouriteTrackDao::get_by_key(key.to_string(), conn)?.and_then(FavouriteTrack::from))
     }
 
     #[inline]
-    fn insert(entities: Vec<FavouriteTrack>, conn: &mut SqliteConnection) {
+    fn insert(entities: Vec<FavouriteTrack>, conn: &mut SqliteConnection) -> Result<()> {
         FavouriteTrackDao::insert(
             entities
                 .into_iter()
@@ -134,7 +136,7 @@ impl EntityDao<PathBuf, FavouriteTrack> for FavouriteTrackDao {
     }
 
     #[inline]
-    fn remove(entities: Vec<FavouriteTrack>, conn: &mut SqliteConnection) {
+    fn remove(entities: Vec<FavouriteTrack>, conn: &mut SqliteConnection) -> Result<()> {
         FavouriteTrackDao::remove(
             entities
                 .into_iter()
@@ -145,7 +147,7 @@ impl EntityDao<PathBuf, FavouriteTrack> for FavouriteTrackDao {
     }
 
     #[inline]
-    fn update(new_entities: Vec<FavouriteTrack>, conn: &mut SqliteConnection) {
+    fn update(new_entities: Vec<FavouriteTrack>, conn: &mut SqliteConnection) -> Result<()> {
         FavouriteTrackDao::update(
             new_entities
                 .into_iter()
@@ -1,13 +1,63 @@
 extern crate diesel;
-extern crate dotenv;
 
-use diesel::{Connection, SqliteConnection};
-use dotenv::dotenv;
-use std::env::{var, VarError};
+use crate::{
+    data::utils::{extensions::path_buf_ext::PathBufExt, paths::APP_PATHS},
+    error::Result,
+};
 
+use diesel::{Connection, ConnectionResult, RunQueryDsl, SqliteConnection};
+use std::env::var;
+
+/// Overrides location of the favourites database
+const FAVOURITE_DB_URL_VAR: &str = "FAVOURITE_DB_URL";
+
+/// Location of the favourites database:
+/// `FAVOURITE_DB_URL` if it is set, otherwise file in the data directory
+#[inline]
+pub fn favourite_db_url() -> String {
+    var(FAVOURITE_DB_URL_VAR).unwrap_or_else(|_| APP_PATHS.get_favourite_db().to_string())
+}
+
+#[inline]
+pub fn establish_connection() -> ConnectionResult<SqliteConnection> {
+    establish_connection_to(favourite_db_url().as_str())
+}
+
+#[inline]
+pub fn establish_connection_to(db_url: &str) -> ConnectionResult<SqliteConnection> {
+    SqliteConnection::establish(db_url)
+}
+
+/// Creates tables of favourite tracks, artists and playlists
+/// if they don't exist yet
 #[inline]
-pub fn establish_connection() -> Result<SqliteConnection, VarError> {
-    dotenv().ok();
-    let db_url = var("FAVOURITE_DB_URL")?;
-    Ok(SqliteConnection::establish(db_url.as_str()).unwrap())
+pub fn create_tables(connection: &mut SqliteConnection) -> Result<()> {
+    diesel::sql_query(
+        r#"CREATE TABLE IF NOT EXISTS favourite_tracks (
+  title TEXT,
+  artist TEXT,
+  album TEXT,
+  path TEXT PRIMARY KEY NOT NULL,
+  duration BIGINT NOT NULL,
+  add_date BIGINT NOT NULL,
+  number_in_album INTEGER NOT NULL
+);"#,
+    )
+    .execute(connection)?;
+
+    diesel::sql_query(
+        "CREATE TABLE IF NOT EXISTS favourite_artists (name TEXT PRIMARY KEY NOT NULL)",
+    )
+    .execute(connection)?;
+
+    diesel::sql_query(
+        r#"CREATE TABLE IF NOT EXISTS favourite_playlists (
+  id INTEGER PRIMARY KEY,
+  title TEXT,
+  tp INTEGER NOT NULL
+)"#,
+    )
+    .execute(connection)?;
+
+    Ok(())
 }
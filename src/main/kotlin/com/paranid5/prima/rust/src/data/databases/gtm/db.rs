@@ -0,0 +1,23 @@
+extern crate diesel;
+
+use crate::error::Result;
+use diesel::{RunQueryDsl, SqliteConnection};
+
+/// Creates table with results of the guess-the-melody games
+/// if it doesn't exist. It's kept in the favourites database
+#[inline]
+pub fn create_tables(connection: &mut SqliteConnection) -> Result<()> {
+    diesel::sql_query(
+        r#"CREATE TABLE IF NOT EXISTS gtm_high_scores (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  score INTEGER NOT NULL,
+  difficulty INTEGER NOT NULL,
+  rounds INTEGER NOT NULL,
+  correct_answers INTEGER NOT NULL,
+  played_at BIGINT NOT NULL
+)"#,
+    )
+    .execute(connection)?;
+
+    Ok(())
+}
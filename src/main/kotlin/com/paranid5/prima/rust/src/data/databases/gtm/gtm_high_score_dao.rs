@@ -0,0 +1,86 @@
+extern crate diesel;
+
+use crate::{
+    data::databases::gtm::schema::gtm_high_scores::{self, dsl},
+    domain::gtm::{gtm_difficulty::GtmDifficulty, gtm_high_score::GtmHighScore},
+    error::Result,
+};
+
+use diesel::{prelude::*, SqliteConnection};
+
+#[derive(Clone, Debug, Queryable)]
+#[diesel(table_name = gtm_high_scores)]
+struct GtmHighScoreDBEntity {
+    #[allow(dead_code)]
+    id: i32,
+    score: i32,
+    difficulty: i32,
+    rounds: i32,
+    correct_answers: i32,
+    played_at: i64,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = gtm_high_scores)]
+struct NewGtmHighScoreDBEntity {
+    score: i32,
+    difficulty: i32,
+    rounds: i32,
+    correct_answers: i32,
+    played_at: i64,
+}
+
+impl From<GtmHighScoreDBEntity> for GtmHighScore {
+    #[inline]
+    fn from(entity: GtmHighScoreDBEntity) -> Self {
+        Self {
+            score: entity.score.max(0) as u32,
+            difficulty: GtmDifficulty::from_code(entity.difficulty).unwrap_or_default(),
+            rounds: entity.rounds.max(0) as u32,
+            correct_answers: entity.correct_answers.max(0) as u32,
+            played_at: entity.played_at,
+        }
+    }
+}
+
+/// Results of the finished guess-the-melody games
+pub struct GtmHighScoreDao;
+
+impl GtmHighScoreDao {
+    #[inline]
+    pub fn add(high_score: &GtmHighScore, conn: &mut SqliteConnection) -> Result<()> {
+        diesel::insert_into(dsl::gtm_high_scores)
+            .values(NewGtmHighScoreDBEntity {
+                score: high_score.score as i32,
+                difficulty: high_score.difficulty.get_code(),
+                rounds: high_score.rounds as i32,
+                correct_answers: high_score.correct_answers as i32,
+                played_at: high_score.played_at,
+            })
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Best results, the highest first; equal scores are ordered from the earliest.
+    /// None difficulty returns results of all difficulties
+    #[inline]
+    pub fn get_top(
+        difficulty: Option<GtmDifficulty>,
+        limit: usize,
+        conn: &mut SqliteConnection,
+    ) -> Result<Vec<GtmHighScore>> {
+        let mut query = dsl::gtm_high_scores.into_boxed();
+
+        if let Some(difficulty) = difficulty {
+            query = query.filter(dsl::difficulty.eq(difficulty.get_code()))
+        }
+
+        let entities: Vec<GtmHighScoreDBEntity> = query
+            .order((dsl::score.desc(), dsl::played_at.asc(), dsl::id.asc()))
+            .limit(limit as i64)
+            .load(conn)?;
+
+        Ok(entities.into_iter().map(GtmHighScore::from).collect())
+    }
+}
@@ -0,0 +1,3 @@
+pub mod db;
+pub mod gtm_high_score_dao;
+pub mod schema;
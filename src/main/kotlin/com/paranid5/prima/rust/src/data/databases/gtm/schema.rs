@@ -0,0 +1,10 @@
+diesel::table! {
+    gtm_high_scores (id) {
+        id -> Integer,
+        score -> Integer,
+        difficulty -> Integer,
+        rounds -> Integer,
+        correct_answers -> Integer,
+        played_at -> BigInt,
+    }
+}
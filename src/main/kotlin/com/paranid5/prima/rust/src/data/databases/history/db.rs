@@ -0,0 +1,20 @@
+extern crate diesel;
+
+use crate::error::Result;
+use diesel::{RunQueryDsl, SqliteConnection};
+
+/// Creates table with the started tracks if it doesn't exist.
+/// It's kept in the favourites database
+#[inline]
+pub fn create_tables(connection: &mut SqliteConnection) -> Result<()> {
+    diesel::sql_query(
+        r#"CREATE TABLE IF NOT EXISTS play_history (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  path TEXT NOT NULL,
+  played_at BIGINT NOT NULL
+)"#,
+    )
+    .execute(connection)?;
+
+    Ok(())
+}
@@ -0,0 +1,3 @@
+pub mod db;
+pub mod play_history_dao;
+pub mod schema;
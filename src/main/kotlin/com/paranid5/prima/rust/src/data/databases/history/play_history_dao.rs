@@ -0,0 +1,63 @@
+extern crate diesel;
+
+use crate::{
+    data::databases::history::schema::play_history::{self, dsl},
+    error::Result,
+};
+
+use diesel::{dsl::count_star, prelude::*, SqliteConnection};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = play_history)]
+struct NewPlayDBEntity {
+    path: String,
+    played_at: i64,
+}
+
+/// Tracks that were started, one entry per play
+pub struct PlayHistoryDao;
+
+impl PlayHistoryDao {
+    #[inline]
+    pub fn add(path: &Path, played_at: i64, conn: &mut SqliteConnection) -> Result<()> {
+        diesel::insert_into(dsl::play_history)
+            .values(NewPlayDBEntity {
+                path: path.to_string_lossy().to_string(),
+                played_at,
+            })
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Number of plays of every track that was played at least once
+    #[inline]
+    pub fn get_play_counts(conn: &mut SqliteConnection) -> Result<HashMap<PathBuf, usize>> {
+        let counts: Vec<(String, i64)> = dsl::play_history
+            .group_by(dsl::path)
+            .select((dsl::path, count_star()))
+            .load(conn)?;
+
+        Ok(counts
+            .into_iter()
+            .map(|(path, count)| (PathBuf::from(path), count as usize))
+            .collect())
+    }
+
+    /// Paths of the last plays, the newest first.
+    /// Track that was played several times is repeated
+    #[inline]
+    pub fn get_recent(limit: usize, conn: &mut SqliteConnection) -> Result<Vec<PathBuf>> {
+        let paths: Vec<String> = dsl::play_history
+            .select(dsl::path)
+            .order((dsl::played_at.desc(), dsl::id.desc()))
+            .limit(limit as i64)
+            .load(conn)?;
+
+        Ok(paths.into_iter().map(PathBuf::from).collect())
+    }
+}
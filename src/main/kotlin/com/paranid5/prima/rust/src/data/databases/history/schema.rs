@@ -0,0 +1,7 @@
+diesel::table! {
+    play_history (id) {
+        id -> Integer,
+        path -> Text,
+        played_at -> BigInt,
+    }
+}
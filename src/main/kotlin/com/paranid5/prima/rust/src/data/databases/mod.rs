@@ -1,3 +1,10 @@
+pub mod analysis;
+pub mod bookmarks;
 pub mod db_entity;
 pub mod entity_dao;
 pub mod favourites;
+pub mod gtm;
+pub mod history;
+pub mod podcasts;
+pub mod radio;
+pub mod scrobbles;
@@ -0,0 +1,46 @@
+extern crate diesel;
+
+use crate::error::Result;
+use diesel::{RunQueryDsl, SqliteConnection};
+
+/// Creates tables with subscribed podcasts and their episodes if they don't exist.
+/// They are kept in the favourites database
+#[inline]
+pub fn create_tables(connection: &mut SqliteConnection) -> Result<()> {
+    diesel::sql_query(
+        r#"CREATE TABLE IF NOT EXISTS podcast_feeds (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  url TEXT NOT NULL UNIQUE,
+  title TEXT NOT NULL,
+  description TEXT,
+  added_at BIGINT NOT NULL,
+  refreshed_at BIGINT NOT NULL
+)"#,
+    )
+    .execute(connection)?;
+
+    diesel::sql_query(
+        r#"CREATE TABLE IF NOT EXISTS podcast_episodes (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  feed_id INTEGER NOT NULL,
+  guid TEXT NOT NULL,
+  title TEXT NOT NULL,
+  audio_url TEXT NOT NULL,
+  published_at BIGINT,
+  duration BIGINT,
+  file TEXT,
+  is_download_queued BOOLEAN NOT NULL,
+  is_played BOOLEAN NOT NULL,
+  position BIGINT NOT NULL,
+  UNIQUE (feed_id, guid)
+)"#,
+    )
+    .execute(connection)?;
+
+    diesel::sql_query(
+        "CREATE INDEX IF NOT EXISTS podcast_episodes_file ON podcast_episodes (file)",
+    )
+    .execute(connection)?;
+
+    Ok(())
+}
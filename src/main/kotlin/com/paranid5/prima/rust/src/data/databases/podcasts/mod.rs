@@ -0,0 +1,4 @@
+pub mod db;
+pub mod podcast_episode_dao;
+pub mod podcast_feed_dao;
+pub mod schema;
@@ -0,0 +1,211 @@
+extern crate diesel;
+
+use crate::{
+    data::{
+        databases::podcasts::schema::podcast_episodes::{self, dsl},
+        utils::extensions::path_buf_ext::PathBufExt,
+    },
+    domain::podcasts::{feed_parser::ParsedEpisode, podcast_episode::PodcastEpisode},
+    error::Result,
+};
+
+use diesel::{prelude::*, SqliteConnection};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+#[derive(Clone, Debug, Queryable)]
+#[diesel(table_name = podcast_episodes)]
+struct PodcastEpisodeDBEntity {
+    id: i32,
+    feed_id: i32,
+    guid: String,
+    title: String,
+    audio_url: String,
+    published_at: Option<i64>,
+    duration: Option<i64>,
+    file: Option<String>,
+    is_download_queued: bool,
+    is_played: bool,
+    position: i64,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = podcast_episodes)]
+struct NewPodcastEpisodeDBEntity<'a> {
+    feed_id: i32,
+    guid: &'a str,
+    title: &'a str,
+    audio_url: &'a str,
+    published_at: Option<i64>,
+    duration: Option<i64>,
+    is_download_queued: bool,
+    is_played: bool,
+    position: i64,
+}
+
+impl From<PodcastEpisodeDBEntity> for PodcastEpisode {
+    #[inline]
+    fn from(entity: PodcastEpisodeDBEntity) -> Self {
+        Self {
+            id: entity.id,
+            feed_id: entity.feed_id,
+            guid: entity.guid,
+            title: entity.title,
+            audio_url: entity.audio_url,
+            published_at: entity.published_at,
+            duration: entity
+                .duration
+                .map(|duration| Duration::from_millis(duration.max(0) as u64)),
+            file: entity.file.map(PathBuf::from),
+            is_download_queued: entity.is_download_queued,
+            is_played: entity.is_played,
+            position: Duration::from_millis(entity.position.max(0) as u64),
+        }
+    }
+}
+
+/// Episodes of the subscribed podcasts with their downloads,
+/// played state and positions
+pub struct PodcastEpisodeDao;
+
+impl PodcastEpisodeDao {
+    /// Stores episodes that the feed didn't have before.
+    /// Returns how many episodes are new
+    #[inline]
+    pub fn insert_new(
+        feed_id: i32,
+        episodes: &[ParsedEpisode],
+        is_download_queued: bool,
+        conn: &mut SqliteConnection,
+    ) -> Result<usize> {
+        let entities = episodes
+            .iter()
+            .map(|episode| NewPodcastEpisodeDBEntity {
+                feed_id,
+                guid: &episode.guid,
+                title: &episode.title,
+                audio_url: &episode.audio_url,
+                published_at: episode.published_at,
+                duration: episode.duration.map(|duration| duration.as_millis() as i64),
+                is_download_queued,
+                is_played: false,
+                position: 0,
+            })
+            .collect::<Vec<_>>();
+
+        conn.transaction(|conn| {
+            entities.iter().try_fold(0, |inserted, entity| {
+                let is_inserted = diesel::insert_or_ignore_into(dsl::podcast_episodes)
+                    .values(entity)
+                    .execute(conn)?;
+
+                Ok(inserted + is_inserted)
+            })
+        })
+    }
+
+    #[inline]
+    pub fn get(id: i32, conn: &mut SqliteConnection) -> Result<Option<PodcastEpisode>> {
+        let entity: Option<PodcastEpisodeDBEntity> = dsl::podcast_episodes
+            .filter(dsl::id.eq(id))
+            .first(conn)
+            .optional()?;
+
+        Ok(entity.map(PodcastEpisode::from))
+    }
+
+    /// Episode whose download is the file
+    #[inline]
+    pub fn get_by_file(file: &Path, conn: &mut SqliteConnection) -> Result<Option<PodcastEpisode>> {
+        let entity: Option<PodcastEpisodeDBEntity> = dsl::podcast_episodes
+            .filter(dsl::file.eq(file.to_path_buf().to_string()))
+            .first(conn)
+            .optional()?;
+
+        Ok(entity.map(PodcastEpisode::from))
+    }
+
+    /// Episodes of the feed, the newest first
+    #[inline]
+    pub fn get_of_feed(feed_id: i32, conn: &mut SqliteConnection) -> Result<Vec<PodcastEpisode>> {
+        let entities: Vec<PodcastEpisodeDBEntity> = dsl::podcast_episodes
+            .filter(dsl::feed_id.eq(feed_id))
+            .order((dsl::published_at.desc(), dsl::id.asc()))
+            .load(conn)?;
+
+        Ok(entities.into_iter().map(PodcastEpisode::from).collect())
+    }
+
+    /// Downloaded episodes of the feed or of all feeds, the newest first
+    #[inline]
+    pub fn get_downloaded(
+        feed_id: Option<i32>,
+        conn: &mut SqliteConnection,
+    ) -> Result<Vec<PodcastEpisode>> {
+        let mut query = dsl::podcast_episodes
+            .filter(dsl::file.is_not_null())
+            .order((dsl::published_at.desc(), dsl::id.asc()))
+            .into_boxed();
+
+        if let Some(feed_id) = feed_id {
+            query = query.filter(dsl::feed_id.eq(feed_id))
+        }
+
+        let entities: Vec<PodcastEpisodeDBEntity> = query.load(conn)?;
+        Ok(entities.into_iter().map(PodcastEpisode::from).collect())
+    }
+
+    /// Episodes that wait for the download, the oldest first
+    #[inline]
+    pub fn get_queued(conn: &mut SqliteConnection) -> Result<Vec<PodcastEpisode>> {
+        let entities: Vec<PodcastEpisodeDBEntity> = dsl::podcast_episodes
+            .filter(dsl::is_download_queued.eq(true))
+            .filter(dsl::file.is_null())
+            .order(dsl::id.asc())
+            .load(conn)?;
+
+        Ok(entities.into_iter().map(PodcastEpisode::from).collect())
+    }
+
+    #[inline]
+    pub fn set_download_queued(
+        id: i32,
+        is_download_queued: bool,
+        conn: &mut SqliteConnection,
+    ) -> Result<bool> {
+        let updated = diesel::update(dsl::podcast_episodes.filter(dsl::id.eq(id)))
+            .set(dsl::is_download_queued.eq(is_download_queued))
+            .execute(conn)?;
+
+        Ok(updated > 0)
+    }
+
+    /// Stores the downloaded file or forgets the removed one
+    #[inline]
+    pub fn set_file(id: i32, file: Option<&Path>, conn: &mut SqliteConnection) -> Result<bool> {
+        let updated = diesel::update(dsl::podcast_episodes.filter(dsl::id.eq(id)))
+            .set(dsl::file.eq(file.map(|file| file.to_path_buf().to_string())))
+            .execute(conn)?;
+
+        Ok(updated > 0)
+    }
+
+    #[inline]
+    pub fn set_progress(
+        id: i32,
+        is_played: bool,
+        position: Duration,
+        conn: &mut SqliteConnection,
+    ) -> Result<bool> {
+        let updated = diesel::update(dsl::podcast_episodes.filter(dsl::id.eq(id)))
+            .set((
+                dsl::is_played.eq(is_played),
+                dsl::position.eq(position.as_millis() as i64),
+            ))
+            .execute(conn)?;
+
+        Ok(updated > 0)
+    }
+}
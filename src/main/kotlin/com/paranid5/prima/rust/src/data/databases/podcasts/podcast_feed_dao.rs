@@ -0,0 +1,146 @@
+extern crate diesel;
+
+use crate::{
+    data::databases::podcasts::schema::{
+        podcast_episodes,
+        podcast_feeds::{self, dsl},
+    },
+    domain::podcasts::podcast_feed::PodcastFeed,
+    error::Result,
+};
+
+use diesel::{prelude::*, SqliteConnection};
+
+#[derive(Clone, Debug, Queryable)]
+#[diesel(table_name = podcast_feeds)]
+struct PodcastFeedDBEntity {
+    id: i32,
+    url: String,
+    title: String,
+    description: Option<String>,
+    added_at: i64,
+    refreshed_at: i64,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = podcast_feeds)]
+struct NewPodcastFeedDBEntity<'a> {
+    url: &'a str,
+    title: &'a str,
+    description: Option<&'a str>,
+    added_at: i64,
+    refreshed_at: i64,
+}
+
+impl From<PodcastFeedDBEntity> for PodcastFeed {
+    #[inline]
+    fn from(entity: PodcastFeedDBEntity) -> Self {
+        Self {
+            id: entity.id,
+            url: entity.url,
+            title: entity.title,
+            description: entity.description,
+            added_at: entity.added_at,
+            refreshed_at: entity.refreshed_at,
+        }
+    }
+}
+
+/// Podcasts the user has subscribed to
+pub struct PodcastFeedDao;
+
+impl PodcastFeedDao {
+    /// Stores new feed, fetched at the moment of subscription,
+    /// and returns it with the assigned id
+    #[inline]
+    pub fn insert(
+        url: &str,
+        title: &str,
+        description: Option<&str>,
+        added_at: i64,
+        conn: &mut SqliteConnection,
+    ) -> Result<PodcastFeed> {
+        conn.transaction(|conn| {
+            diesel::insert_into(dsl::podcast_feeds)
+                .values(NewPodcastFeedDBEntity {
+                    url,
+                    title,
+                    description,
+                    added_at,
+                    refreshed_at: added_at,
+                })
+                .execute(conn)?;
+
+            let entity: PodcastFeedDBEntity =
+                dsl::podcast_feeds.order(dsl::id.desc()).first(conn)?;
+
+            Ok(entity.into())
+        })
+    }
+
+    #[inline]
+    pub fn get(id: i32, conn: &mut SqliteConnection) -> Result<Option<PodcastFeed>> {
+        let entity: Option<PodcastFeedDBEntity> = dsl::podcast_feeds
+            .filter(dsl::id.eq(id))
+            .first(conn)
+            .optional()?;
+
+        Ok(entity.map(PodcastFeed::from))
+    }
+
+    #[inline]
+    pub fn get_by_url(url: &str, conn: &mut SqliteConnection) -> Result<Option<PodcastFeed>> {
+        let entity: Option<PodcastFeedDBEntity> = dsl::podcast_feeds
+            .filter(dsl::url.eq(url))
+            .first(conn)
+            .optional()?;
+
+        Ok(entity.map(PodcastFeed::from))
+    }
+
+    /// Feeds in the order they were subscribed to
+    #[inline]
+    pub fn get_all(conn: &mut SqliteConnection) -> Result<Vec<PodcastFeed>> {
+        let entities: Vec<PodcastFeedDBEntity> =
+            dsl::podcast_feeds.order(dsl::id.asc()).load(conn)?;
+
+        Ok(entities.into_iter().map(PodcastFeed::from).collect())
+    }
+
+    /// Stores title and description of the refreshed feed
+    #[inline]
+    pub fn update(
+        id: i32,
+        title: &str,
+        description: Option<&str>,
+        refreshed_at: i64,
+        conn: &mut SqliteConnection,
+    ) -> Result<bool> {
+        let updated = diesel::update(dsl::podcast_feeds.filter(dsl::id.eq(id)))
+            .set((
+                dsl::title.eq(title),
+                dsl::description.eq(description),
+                dsl::refreshed_at.eq(refreshed_at),
+            ))
+            .execute(conn)?;
+
+        Ok(updated > 0)
+    }
+
+    /// Removes feed with its episodes
+    #[inline]
+    pub fn remove(id: i32, conn: &mut SqliteConnection) -> Result<bool> {
+        conn.transaction(|conn| {
+            diesel::delete(
+                podcast_episodes::dsl::podcast_episodes
+                    .filter(podcast_episodes::dsl::feed_id.eq(id)),
+            )
+            .execute(conn)?;
+
+            let removed =
+                diesel::delete(dsl::podcast_feeds.filter(dsl::id.eq(id))).execute(conn)?;
+
+            Ok(removed > 0)
+        })
+    }
+}
@@ -0,0 +1,26 @@
+diesel::table! {
+    podcast_feeds (id) {
+        id -> Integer,
+        url -> Text,
+        title -> Text,
+        description -> Nullable<Text>,
+        added_at -> BigInt,
+        refreshed_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    podcast_episodes (id) {
+        id -> Integer,
+        feed_id -> Integer,
+        guid -> Text,
+        title -> Text,
+        audio_url -> Text,
+        published_at -> Nullable<BigInt>,
+        duration -> Nullable<BigInt>,
+        file -> Nullable<Text>,
+        is_download_queued -> Bool,
+        is_played -> Bool,
+        position -> BigInt,
+    }
+}
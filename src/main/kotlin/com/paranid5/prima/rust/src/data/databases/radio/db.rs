@@ -0,0 +1,21 @@
+extern crate diesel;
+
+use crate::error::Result;
+use diesel::{RunQueryDsl, SqliteConnection};
+
+/// Creates table with the user's internet radio stations if it doesn't exist.
+/// It's kept in the favourites database
+#[inline]
+pub fn create_tables(connection: &mut SqliteConnection) -> Result<()> {
+    diesel::sql_query(
+        r#"CREATE TABLE IF NOT EXISTS radio_stations (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  name TEXT NOT NULL,
+  url TEXT NOT NULL,
+  added_at BIGINT NOT NULL
+)"#,
+    )
+    .execute(connection)?;
+
+    Ok(())
+}
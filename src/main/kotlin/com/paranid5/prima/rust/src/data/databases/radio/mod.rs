@@ -0,0 +1,3 @@
+pub mod db;
+pub mod radio_station_dao;
+pub mod schema;
@@ -0,0 +1,102 @@
+extern crate diesel;
+
+use crate::{
+    data::databases::radio::schema::radio_stations::{self, dsl},
+    domain::radio::radio_station::RadioStation,
+    error::Result,
+};
+
+use diesel::{prelude::*, SqliteConnection};
+
+#[derive(Clone, Debug, Queryable)]
+#[diesel(table_name = radio_stations)]
+struct RadioStationDBEntity {
+    id: i32,
+    name: String,
+    url: String,
+    added_at: i64,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = radio_stations)]
+struct NewRadioStationDBEntity<'a> {
+    name: &'a str,
+    url: &'a str,
+    added_at: i64,
+}
+
+impl From<RadioStationDBEntity> for RadioStation {
+    #[inline]
+    fn from(entity: RadioStationDBEntity) -> Self {
+        Self {
+            id: entity.id,
+            name: entity.name,
+            url: entity.url,
+            added_at: entity.added_at,
+        }
+    }
+}
+
+/// Internet radio stations added by the user
+pub struct RadioStationDao;
+
+impl RadioStationDao {
+    /// Stores new station and returns it with the assigned id
+    #[inline]
+    pub fn insert(
+        name: &str,
+        url: &str,
+        added_at: i64,
+        conn: &mut SqliteConnection,
+    ) -> Result<RadioStation> {
+        conn.transaction(|conn| {
+            diesel::insert_into(dsl::radio_stations)
+                .values(NewRadioStationDBEntity {
+                    name,
+                    url,
+                    added_at,
+                })
+                .execute(conn)?;
+
+            let entity: RadioStationDBEntity =
+                dsl::radio_stations.order(dsl::id.desc()).first(conn)?;
+
+            Ok(entity.into())
+        })
+    }
+
+    #[inline]
+    pub fn get(id: i32, conn: &mut SqliteConnection) -> Result<Option<RadioStation>> {
+        let entity: Option<RadioStationDBEntity> = dsl::radio_stations
+            .filter(dsl::id.eq(id))
+            .first(conn)
+            .optional()?;
+
+        Ok(entity.map(RadioStation::from))
+    }
+
+    /// Stations in the order they were added
+    #[inline]
+    pub fn get_all(conn: &mut SqliteConnection) -> Result<Vec<RadioStation>> {
+        let entities: Vec<RadioStationDBEntity> =
+            dsl::radio_stations.order(dsl::id.asc()).load(conn)?;
+
+        Ok(entities.into_iter().map(RadioStation::from).collect())
+    }
+
+    #[inline]
+    pub fn update(id: i32, name: &str, url: &str, conn: &mut SqliteConnection) -> Result<bool> {
+        let updated = diesel::update(dsl::radio_stations.filter(dsl::id.eq(id)))
+            .set((dsl::name.eq(name), dsl::url.eq(url)))
+            .execute(conn)?;
+
+        Ok(updated > 0)
+    }
+
+    #[inline]
+    pub fn remove(id: i32, conn: &mut SqliteConnection) -> Result<bool> {
+        let removed = diesel::delete(dsl::radio_stations.filter(dsl::id.eq(id))).execute(conn)?;
+
+        Ok(removed > 0)
+    }
+}
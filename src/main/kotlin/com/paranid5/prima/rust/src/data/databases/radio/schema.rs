@@ -0,0 +1,8 @@
+diesel::table! {
+    radio_stations (id) {
+        id -> Integer,
+        name -> Text,
+        url -> Text,
+        added_at -> BigInt,
+    }
+}
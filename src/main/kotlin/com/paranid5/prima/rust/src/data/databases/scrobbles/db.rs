@@ -0,0 +1,26 @@
+extern crate diesel;
+
+use crate::error::Result;
+use diesel::{RunQueryDsl, SqliteConnection};
+
+/// Creates table of scrobbles that are not submitted yet
+/// if it doesn't exist. Queue is kept in the favourites database
+#[inline]
+pub fn create_tables(connection: &mut SqliteConnection) -> Result<()> {
+    diesel::sql_query(
+        r#"CREATE TABLE IF NOT EXISTS scrobbles (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  backend TEXT NOT NULL,
+  artist TEXT NOT NULL,
+  title TEXT NOT NULL,
+  album TEXT,
+  duration BIGINT NOT NULL,
+  listened_at BIGINT NOT NULL,
+  attempts INTEGER NOT NULL DEFAULT 0,
+  next_attempt_at BIGINT NOT NULL DEFAULT 0
+)"#,
+    )
+    .execute(connection)?;
+
+    Ok(())
+}
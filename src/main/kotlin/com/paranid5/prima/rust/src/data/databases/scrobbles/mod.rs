@@ -0,0 +1,3 @@
+pub mod db;
+pub mod schema;
+pub mod scrobble_dao;
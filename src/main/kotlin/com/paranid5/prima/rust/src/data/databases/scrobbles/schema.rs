@@ -0,0 +1,13 @@
+diesel::table! {
+    scrobbles (id) {
+        id -> Integer,
+        backend -> Text,
+        artist -> Text,
+        title -> Text,
+        album -> Nullable<Text>,
+        duration -> BigInt,
+        listened_at -> BigInt,
+        attempts -> Integer,
+        next_attempt_at -> BigInt,
+    }
+}
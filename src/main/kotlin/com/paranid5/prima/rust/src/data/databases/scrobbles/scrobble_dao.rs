@@ -0,0 +1,132 @@
+extern crate diesel;
+
+use crate::{
+    data::databases::scrobbles::schema::scrobbles::{self, dsl},
+    domain::scrobbling::scrobble::Scrobble,
+    error::Result,
+};
+
+use diesel::{prelude::*, SqliteConnection};
+use std::time::Duration;
+
+#[derive(Clone, Debug, Queryable)]
+#[diesel(table_name = scrobbles)]
+struct ScrobbleDBEntity {
+    id: i32,
+    #[allow(dead_code)]
+    backend: String,
+    artist: String,
+    title: String,
+    album: Option<String>,
+    duration: i64,
+    listened_at: i64,
+    attempts: i32,
+    #[allow(dead_code)]
+    next_attempt_at: i64,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[diesel(table_name = scrobbles)]
+struct NewScrobbleDBEntity<'a> {
+    backend: &'a str,
+    artist: &'a str,
+    title: &'a str,
+    album: Option<&'a str>,
+    duration: i64,
+    listened_at: i64,
+}
+
+/// Scrobble that waits to be submitted with the number of failed attempts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueuedScrobble {
+    pub id: i32,
+    pub scrobble: Scrobble,
+    pub attempts: u32,
+}
+
+impl From<ScrobbleDBEntity> for QueuedScrobble {
+    #[inline]
+    fn from(entity: ScrobbleDBEntity) -> Self {
+        Self {
+            id: entity.id,
+            scrobble: Scrobble {
+                artist: entity.artist,
+                title: entity.title,
+                album: entity.album,
+                duration: Duration::from_millis(entity.duration.max(0) as u64),
+                listened_at: entity.listened_at,
+            },
+            attempts: entity.attempts.max(0) as u32,
+        }
+    }
+}
+
+/// Durable queue of scrobbles, every backend has its own entries,
+/// so they are retried independently
+pub struct ScrobbleDao;
+
+impl ScrobbleDao {
+    #[inline]
+    pub fn enqueue(backend: &str, scrobble: &Scrobble, conn: &mut SqliteConnection) -> Result<()> {
+        diesel::insert_into(dsl::scrobbles)
+            .values(NewScrobbleDBEntity {
+                backend,
+                artist: &scrobble.artist,
+                title: &scrobble.title,
+                album: scrobble.album.as_deref(),
+                duration: scrobble.duration.as_millis() as i64,
+                listened_at: scrobble.listened_at,
+            })
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Oldest scrobbles of the backend that may be submitted at the given time
+    #[inline]
+    pub fn get_due(
+        backend: &str,
+        now: i64,
+        limit: usize,
+        conn: &mut SqliteConnection,
+    ) -> Result<Vec<QueuedScrobble>> {
+        let entities: Vec<ScrobbleDBEntity> = dsl::scrobbles
+            .filter(dsl::backend.eq(backend))
+            .filter(dsl::next_attempt_at.le(now))
+            .order((dsl::listened_at.asc(), dsl::id.asc()))
+            .limit(limit as i64)
+            .load(conn)?;
+
+        Ok(entities.into_iter().map(QueuedScrobble::from).collect())
+    }
+
+    #[inline]
+    pub fn remove(ids: &[i32], conn: &mut SqliteConnection) -> Result<()> {
+        diesel::delete(dsl::scrobbles.filter(dsl::id.eq_any(ids))).execute(conn)?;
+        Ok(())
+    }
+
+    /// Stores the failed attempt and the time of the next one
+    #[inline]
+    pub fn postpone(
+        id: i32,
+        attempts: u32,
+        next_attempt_at: i64,
+        conn: &mut SqliteConnection,
+    ) -> Result<()> {
+        diesel::update(dsl::scrobbles.filter(dsl::id.eq(id)))
+            .set((
+                dsl::attempts.eq(attempts as i32),
+                dsl::next_attempt_at.eq(next_attempt_at),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn count(conn: &mut SqliteConnection) -> Result<usize> {
+        let count: i64 = dsl::scrobbles.count().get_result(conn)?;
+        Ok(count as usize)
+    }
+}
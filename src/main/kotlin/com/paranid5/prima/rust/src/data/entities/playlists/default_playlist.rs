@@ -1,8 +1,9 @@
 use crate::{
-    impl_playlist_methods, impl_playlist_traits, DefaultTrack, PlaylistType, TrackExt, AJVM,
+    domain::metadata_reader::MetadataReader, impl_playlist_methods, impl_playlist_traits,
+    DefaultTrack, PlaylistType, TrackExt,
 };
 
-use std::{cell::RefCell, rc::Rc};
+use std::path::Path;
 
 pub struct DefaultPlaylist<T: TrackExt> {
     title: Option<String>,
@@ -14,32 +15,26 @@ pub struct DefaultPlaylist<T: TrackExt> {
 impl_playlist_traits!(DefaultPlaylist);
 
 impl DefaultPlaylist<DefaultTrack> {
+    /// Restores playlist from the state file.
+    /// Tracks that are not found or can't be read are skipped
     #[inline]
-    pub async fn from_yaml(jvm: AJVM, playlist: &yaml_rust::yaml::Hash) -> Option<Self> {
+    pub fn from_yaml(playlist: &yaml_rust::yaml::Hash) -> Option<Self> {
         use yaml_rust::Yaml;
 
-        let jni_env = Rc::new(RefCell::new(
-            jvm.attach_current_thread_permanently().unwrap(),
-        ));
-
         let index = playlist
-            .get(&Yaml::String("current_index".to_string()))
-            .unwrap()
-            .as_i64()
-            .unwrap();
+            .get(&Yaml::String("current_index".to_string()))?
+            .as_i64()?;
 
         let tracks = playlist
-            .get(&Yaml::String("tracks".to_string()))
-            .unwrap()
-            .as_vec()
-            .unwrap();
+            .get(&Yaml::String("tracks".to_string()))?
+            .as_vec()?;
 
         Some(Self::new(
             None,
             PlaylistType::ALBUM,
             tracks
                 .iter()
-                .filter_map(|y| DefaultTrack::from_path(jni_env.clone(), y.as_str()?.to_string())),
+                .filter_map(|y| MetadataReader::read_track(Path::new(y.as_str()?))),
             index as usize,
         ))
     }
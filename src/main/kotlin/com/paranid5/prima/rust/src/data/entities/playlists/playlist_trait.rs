@@ -31,6 +31,11 @@ pub trait PlaylistTrait<T: TrackExt>: private::PrivatePlaylistTrait<T> {
         self.get_tracks().len()
     }
 
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.get_tracks().is_empty()
+    }
+
     #[inline]
     fn get_cur_track(&self) -> Option<&T> {
         self.get_tracks().get(self.get_cur_ind())
@@ -62,7 +67,6 @@ pub trait PlaylistTrait<T: TrackExt>: private::PrivatePlaylistTrait<T> {
 
     /// Adds track if it's not in the playlist
     /// or changes it's position
-
     #[inline]
     fn push(&mut self, track: T) {
         if let Some(index) = { self.get_tracks().iter().position(|t| track.eq(t)) } {
@@ -75,7 +79,6 @@ pub trait PlaylistTrait<T: TrackExt>: private::PrivatePlaylistTrait<T> {
     /// Adds track from given collection
     /// if it's not in the playlist
     /// or changes it's position
-
     #[inline]
     fn push_all<I: IntoIterator<Item = T>>(&mut self, tracks: I) {
         tracks.into_iter().for_each(|t| self.push(t))
@@ -88,10 +91,9 @@ pub trait PlaylistTrait<T: TrackExt>: private::PrivatePlaylistTrait<T> {
     /// # Returns
     /// true if the element has been successfully removed;
     /// false if it was not presented in the collection.
-
     #[inline]
     fn remove(&mut self, track: &T) -> bool {
-        match { self.get_tracks().iter().position(|t| t == track) } {
+        match self.get_tracks().iter().position(|t| t == track) {
             None => false,
             Some(ind) => {
                 let cur_ind = { self.get_cur_ind() };
@@ -129,7 +131,6 @@ pub trait PlaylistTrait<T: TrackExt>: private::PrivatePlaylistTrait<T> {
     /// # Returns
     /// true if any of elements have been successfully removed;
     /// false if all of tracks were not presented in the collection.
-
     #[inline]
     fn remove_all<I: IntoIterator<Item = T>>(&mut self, tracks: I) -> bool {
         tracks.into_iter().fold(false, |is_changed, t| {
@@ -151,10 +152,9 @@ pub trait PlaylistTrait<T: TrackExt>: private::PrivatePlaylistTrait<T> {
     /// # Returns
     /// true if track's changed
     /// false if it isn't founded
-
     #[inline]
     fn replace(&mut self, old_track: &T, new_track: T) -> bool {
-        match { self.get_tracks().iter().position(|t| t == old_track) } {
+        match self.get_tracks().iter().position(|t| t == old_track) {
             None => false,
             Some(ind) => unsafe {
                 *self.get_tracks_mut().get_unchecked_mut(ind) = new_track;
@@ -168,7 +168,7 @@ impl<T: TrackExt> Clone for DefaultPlaylist<T> {
     #[inline]
     fn clone(&self) -> Self {
         Self::new(
-            self.get_title().map(|title| title.clone()),
+            self.get_title().cloned(),
             self.get_type(),
             self.get_tracks().clone(),
             self.get_cur_ind(),
@@ -181,7 +181,7 @@ impl<T: TrackExt> Clone for FavouritePlaylist<T> {
     fn clone(&self) -> Self {
         Self::new(
             self.get_id(),
-            self.get_title().map(|title| title.clone()),
+            self.get_title().cloned(),
             self.get_type(),
             self.get_tracks().clone(),
             self.get_cur_ind(),
@@ -3,6 +3,7 @@ pub enum PlaylistType {
     ALBUM,
     CUSTOM,
     GTM,
+    PODCAST,
 }
 
 impl Default for PlaylistType {
@@ -19,6 +20,7 @@ impl From<i32> for PlaylistType {
             0 => PlaylistType::ALBUM,
             1 => PlaylistType::CUSTOM,
             2 => PlaylistType::GTM,
+            3 => PlaylistType::PODCAST,
             _ => panic!("Unknown index of PlaylistType"),
         }
     }
@@ -31,6 +33,7 @@ impl From<PlaylistType> for i32 {
             PlaylistType::ALBUM => 0,
             PlaylistType::CUSTOM => 1,
             PlaylistType::GTM => 2,
+            PlaylistType::PODCAST => 3,
         }
     }
 }
@@ -41,7 +41,7 @@ impl Favourable<FavouriteTrack> for DefaultTrack {
             self.album.clone(),
             self.path.clone(),
             self.duration,
-            self.add_date.clone(),
+            self.add_date,
             self.number_in_album,
         )
     }
@@ -20,11 +20,11 @@ impl<'a, 'b, 'c> JListExt for JList<'a, 'b, 'c> {
         env: Rc<RefCell<JNIEnv<'e>>>,
         transformer: F,
     ) -> Vec<T> {
-        let mut iter = self.iter(&mut *env.borrow_mut()).unwrap();
+        let mut iter = self.iter(&mut env.borrow_mut()).unwrap();
         let mut vec = Vec::new();
 
         while let Some(obj) = {
-            let obj = iter.next(&mut *env.borrow_mut()).unwrap();
+            let obj = iter.next(&mut env.borrow_mut()).unwrap();
             obj
         } {
             vec.push(transformer(obj, env.clone()))
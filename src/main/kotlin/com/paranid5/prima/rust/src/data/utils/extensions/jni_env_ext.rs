@@ -37,7 +37,7 @@ impl<'a> JNIEnvExt<'a> for JNIEnv<'a> {
     where
         'a: 'b,
     {
-        this.borrow_mut().get_field(object, &field, sig).unwrap()
+        this.borrow_mut().get_field(object, field, sig).unwrap()
     }
 
     #[inline]
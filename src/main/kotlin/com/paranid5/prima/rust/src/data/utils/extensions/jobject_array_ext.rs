@@ -61,7 +61,7 @@ impl JObjectArrayExt for JObjectArray<'_> {
             artist,
             album,
             path.clone(),
-            Duration::milliseconds(duration as i64),
+            Duration::milliseconds(duration),
             DateTime::from(std::fs::metadata(path).unwrap().created().unwrap()),
             number_in_album,
         ))
@@ -19,7 +19,7 @@ macro_rules! get_in_borders {
 #[macro_export]
 macro_rules! impl_track_traits {
     ($track_type:ty) => {
-        impl crate::data::entities::tracks::track_trait::TrackTrait for $track_type {
+        impl $crate::data::entities::tracks::track_trait::TrackTrait for $track_type {
             #[inline]
             fn get_title(&self) -> Option<&String> {
                 self.title.as_ref()
@@ -56,7 +56,7 @@ macro_rules! impl_track_traits {
             }
         }
 
-        impl crate::data::entities::tracks::track_trait::TrackTrait for &$track_type {
+        impl $crate::data::entities::tracks::track_trait::TrackTrait for &$track_type {
             #[inline]
             fn get_title(&self) -> Option<&String> {
                 self.title.as_ref()
@@ -97,7 +97,7 @@ macro_rules! impl_track_traits {
             #[inline]
             fn eq(&self, other: &Self) -> bool {
                 self.path
-                    .eq(crate::data::entities::tracks::track_trait::TrackTrait::get_path(other))
+                    .eq($crate::data::entities::tracks::track_trait::TrackTrait::get_path(other))
             }
         }
     };
@@ -106,14 +106,14 @@ macro_rules! impl_track_traits {
 #[macro_export]
 macro_rules! impl_artist_traits {
     ($artist_type:ty) => {
-        impl crate::data::entities::artists::artist_trait::ArtistTrait for $artist_type {
+        impl $crate::data::entities::artists::artist_trait::ArtistTrait for $artist_type {
             #[inline]
             fn get_name(&self) -> &String {
                 &self.name
             }
         }
 
-        impl crate::data::entities::artists::artist_trait::ArtistTrait for &$artist_type {
+        impl $crate::data::entities::artists::artist_trait::ArtistTrait for &$artist_type {
             #[inline]
             fn get_name(&self) -> &String {
                 &self.name
@@ -125,7 +125,7 @@ macro_rules! impl_artist_traits {
 #[macro_export]
 macro_rules! impl_playlist_traits {
     ($playlist_type:ident) => {
-        impl<T: crate::data::utils::extensions::track_ext::TrackExt> IntoIterator
+        impl<T: $crate::data::utils::extensions::track_ext::TrackExt> IntoIterator
             for $playlist_type<T>
         {
             type Item = T;
@@ -137,18 +137,18 @@ macro_rules! impl_playlist_traits {
             }
         }
 
-        impl<Tr: crate::data::utils::extensions::track_ext::TrackExt> Extend<Tr>
+        impl<Tr: $crate::data::utils::extensions::track_ext::TrackExt> Extend<Tr>
             for $playlist_type<Tr>
         {
             #[inline]
             fn extend<T: IntoIterator<Item = Tr>>(&mut self, iter: T) {
-                crate::data::entities::playlists::playlist_trait::PlaylistTrait::push_all(
+                $crate::data::entities::playlists::playlist_trait::PlaylistTrait::push_all(
                     self, iter,
                 )
             }
         }
 
-        impl<T: crate::data::utils::extensions::track_ext::TrackExt> From<$playlist_type<T>>
+        impl<T: $crate::data::utils::extensions::track_ext::TrackExt> From<$playlist_type<T>>
             for yaml_rust::Yaml
         {
             #[inline]
@@ -168,7 +168,7 @@ macro_rules! impl_playlist_traits {
                 hash.insert(
                     Yaml::String("tracks".to_string()),
                     Yaml::Array(Array::from_iter(playlist.into_iter().map(|t| {
-                        crate::data::utils::extensions::track_ext::TrackExt::to_yaml(&t)
+                        $crate::data::utils::extensions::track_ext::TrackExt::to_yaml(&t)
                     }))),
                 );
 
@@ -176,8 +176,8 @@ macro_rules! impl_playlist_traits {
             }
         }
 
-        impl<T: crate::data::utils::extensions::track_ext::TrackExt>
-            crate::data::entities::playlists::playlist_trait::PlaylistTrait<T>
+        impl<T: $crate::data::utils::extensions::track_ext::TrackExt>
+            $crate::data::entities::playlists::playlist_trait::PlaylistTrait<T>
             for $playlist_type<T>
         {
             #[inline]
@@ -186,7 +186,7 @@ macro_rules! impl_playlist_traits {
             }
 
             #[inline]
-            fn get_type(&self) -> crate::data::entities::playlists::playlist_type::PlaylistType {
+            fn get_type(&self) -> $crate::data::entities::playlists::playlist_type::PlaylistType {
                 self.tp
             }
 
@@ -207,12 +207,12 @@ macro_rules! impl_playlist_traits {
 macro_rules! impl_playlist_methods {
     () => {
         #[inline]
-        pub(in crate::data::entities::playlists) fn set_cur_ind(&mut self, new_ind: usize) {
+        pub(in $crate::data::entities::playlists) fn set_cur_ind(&mut self, new_ind: usize) {
             self.cur_ind = new_ind
         }
 
         #[inline]
-        pub(in crate::data::entities::playlists) fn get_tracks_mut(&mut self) -> &mut Vec<T> {
+        pub(in $crate::data::entities::playlists) fn get_tracks_mut(&mut self) -> &mut Vec<T> {
             &mut self.tracks
         }
     };
@@ -221,50 +221,63 @@ macro_rules! impl_playlist_methods {
 #[macro_export]
 macro_rules! impl_dao {
     ($pk_type:ty, $pk_ident:ident, $pk_getter_move:expr, $pk_getter_clone:expr, $entity_type:ty, $entity_dao_type:ty, $dsl:ident) => {
-        impl crate::EntityDao<$pk_type, $entity_type> for $entity_dao_type {
+        impl $crate::EntityDao<$pk_type, $entity_type> for $entity_dao_type {
             #[inline]
-            fn get_all(conn: &mut diesel::SqliteConnection) -> Vec<$entity_type> {
+            fn get_all(
+                conn: &mut diesel::SqliteConnection,
+            ) -> $crate::error::Result<Vec<$entity_type>> {
                 use diesel::prelude::*;
-                $dsl.load(conn).unwrap_or_default()
+                Ok($dsl.load(conn)?)
             }
 
             #[inline]
             fn get_by_key(
                 key: $pk_type,
                 conn: &mut diesel::SqliteConnection,
-            ) -> Option<$entity_type> {
+            ) -> $crate::error::Result<Option<$entity_type>> {
                 use diesel::prelude::*;
-                $dsl.find(key).first(conn).ok()
+                Ok($dsl.find(key).first(conn).optional()?)
             }
 
             #[inline]
-            fn insert(entities: Vec<$entity_type>, conn: &mut diesel::SqliteConnection) {
+            fn insert(
+                entities: Vec<$entity_type>,
+                conn: &mut diesel::SqliteConnection,
+            ) -> $crate::error::Result<()> {
                 use diesel::prelude::*;
-                diesel::insert_into($dsl)
-                    .values(entities)
-                    .execute(conn)
-                    .unwrap_or_default();
+                diesel::insert_into($dsl).values(entities).execute(conn)?;
+                Ok(())
             }
 
             #[inline]
-            fn remove(entities: Vec<$entity_type>, conn: &mut diesel::SqliteConnection) {
+            fn remove(
+                entities: Vec<$entity_type>,
+                conn: &mut diesel::SqliteConnection,
+            ) -> $crate::error::Result<()> {
                 use diesel::prelude::*;
-                entities.into_iter().for_each(|t| {
+
+                for t in entities {
                     diesel::delete($dsl.filter(dsl::$pk_ident.eq($pk_getter_move(t))))
-                        .execute(conn)
-                        .unwrap_or_default();
-                });
+                        .execute(conn)?;
+                }
+
+                Ok(())
             }
 
             #[inline]
-            fn update(new_entities: Vec<$entity_type>, conn: &mut diesel::SqliteConnection) {
+            fn update(
+                new_entities: Vec<$entity_type>,
+                conn: &mut diesel::SqliteConnection,
+            ) -> $crate::error::Result<()> {
                 use diesel::prelude::*;
-                new_entities.into_iter().for_each(|t| {
+
+                for t in new_entities {
                     diesel::update($dsl.filter(dsl::$pk_ident.eq($pk_getter_clone(&t))))
                         .set(t)
-                        .execute(conn)
-                        .unwrap_or_default();
-                })
+                        .execute(conn)?;
+                }
+
+                Ok(())
             }
         }
     };
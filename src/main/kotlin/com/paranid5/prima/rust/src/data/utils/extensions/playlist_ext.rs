@@ -20,7 +20,7 @@ where
             .new_object_array(
                 self.len() as jsize,
                 "com/paranid5/prima/data/Track",
-                &JObject::null(),
+                JObject::null(),
             )
             .unwrap();
 
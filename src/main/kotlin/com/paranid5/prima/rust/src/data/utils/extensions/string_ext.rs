@@ -4,6 +4,7 @@ use jni::{objects::JString, sys::jbyte, JNIEnv};
 
 pub(crate) trait StringExt {
     #[inline]
+    #[allow(dead_code)]
     fn from_jbyte_vec(vec: Vec<jbyte>) -> String {
         String::from_utf8_lossy(
             vec.into_iter()
@@ -24,7 +25,6 @@ pub(crate) trait StringExt {
     ///
     /// # Return
     /// Rust's string from given Java's string
-
     #[inline]
     unsafe fn from_jstring_unchecked(env: &mut JNIEnv, jstring: &JString) -> String {
         env.get_string(jstring).unwrap().into()
@@ -37,7 +37,6 @@ pub(crate) trait StringExt {
     ///
     /// # Return
     /// Rust's string from given Java's string or None if jstring was null
-
     #[inline]
     fn from_jstring(env: &mut JNIEnv, jstring: &JString) -> Option<String> {
         if jstring.is_null() {
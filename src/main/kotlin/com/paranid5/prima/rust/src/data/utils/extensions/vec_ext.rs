@@ -21,7 +21,7 @@ pub trait ExactSizeIteratorExt: ExactSizeIterator {
             .new_object_array(
                 self.len() as jsize,
                 "com/paranid5/prima/data/Track",
-                &JObject::null(),
+                JObject::null(),
             )
             .unwrap();
 
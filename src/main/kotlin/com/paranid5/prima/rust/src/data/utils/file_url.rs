@@ -0,0 +1,55 @@
+use std::{
+    ffi::OsString,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+};
+
+const FILE_URL_PREFIX: &str = "file://";
+
+/// Converts absolute path to `file://` URL.
+/// Bytes that are not allowed in URL's path are percent-encoded
+#[inline]
+pub fn path_to_file_url(path: &Path) -> String {
+    let mut url = String::from(FILE_URL_PREFIX);
+
+    for &byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+                url.push(byte as char)
+            }
+
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    url
+}
+
+/// Converts `file://` URL or plain absolute path to the path.
+/// Returns None for other schemes and malformed escapes
+#[inline]
+pub fn file_url_to_path(url: &str) -> Option<PathBuf> {
+    if url.starts_with('/') {
+        return Some(PathBuf::from(url));
+    }
+
+    let path = url.strip_prefix(FILE_URL_PREFIX)?;
+
+    // Host part, usually empty or `localhost`
+    let path = &path[path.find('/')?..];
+
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut chars = path.bytes();
+
+    while let Some(byte) = chars.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+
+        let hex = [chars.next()?, chars.next()?];
+        bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+    }
+
+    Some(PathBuf::from(OsString::from_vec(bytes)))
+}
@@ -0,0 +1,717 @@
+use crate::error::{Error, Result};
+
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    process::{Child, ChildStdout, Command, Stdio},
+    time::Duration,
+};
+
+const USER_AGENT: &str = concat!("Prima/", env!("CARGO_PKG_VERSION"));
+
+/// Connection and reply timeout of a single request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Redirects followed by GET requests
+const MAX_REDIRECTS: usize = 5;
+
+/// Stream is dropped when the server sends nothing for this long
+const STREAM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest head of the reply that is accepted from streaming servers
+const MAX_HEAD_LEN: usize = 64 * 1024;
+
+/// Reply of the HTTP server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpReply {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Reply whose body is read while it's being received,
+/// e.g. internet radio that never ends
+pub struct HttpStream {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    body: Box<dyn Read + Send + Sync>,
+}
+
+impl HttpReply {
+    #[inline]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    #[inline]
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+}
+
+impl HttpStream {
+    #[inline]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+impl Read for HttpStream {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.body.read(buf)
+    }
+}
+
+/// Minimal blocking HTTP/1.1 client.
+/// Plain HTTP is spoken directly, HTTPS requests are made with `curl`,
+/// which is present on all supported desktops
+#[derive(Debug)]
+pub struct HttpClient;
+
+impl HttpClient {
+    /// GET request that follows redirects
+    #[inline]
+    pub fn get(url: &str, headers: &[(&str, &str)]) -> Result<HttpReply> {
+        let mut url = url.to_string();
+
+        for _ in 0..=MAX_REDIRECTS {
+            let reply = Self::request("GET", &url, headers, &[])?;
+
+            match reply.header("Location") {
+                Some(location) if (300..400).contains(&reply.status) => {
+                    url = resolve_location(&url, location)
+                }
+
+                _ => return Ok(reply),
+            }
+        }
+
+        Err(invalid_data(format!("Too many redirects: {}", url)))
+    }
+
+    /// GET request that follows redirects and returns as soon as the head is received.
+    /// Body is read from the stream, reading fails if the server stalls
+    #[inline]
+    pub fn open_stream(url: &str, headers: &[(&str, &str)]) -> Result<HttpStream> {
+        let mut url = url.to_string();
+
+        for _ in 0..=MAX_REDIRECTS {
+            let stream = match url.split_once("://") {
+                Some((scheme, _)) if scheme.eq_ignore_ascii_case("http") => {
+                    open_plain_stream(&url, headers)?
+                }
+
+                Some((scheme, _)) if scheme.eq_ignore_ascii_case("https") => {
+                    open_curl_stream(&url, headers)?
+                }
+
+                _ => return Err(unsupported_url(&url)),
+            };
+
+            match stream.header("Location") {
+                Some(location) if (300..400).contains(&stream.status) => {
+                    url = resolve_location(&url, location)
+                }
+
+                _ => return Ok(stream),
+            }
+        }
+
+        Err(invalid_data(format!("Too many redirects: {}", url)))
+    }
+
+    #[inline]
+    pub fn post(url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<HttpReply> {
+        Self::request("POST", url, headers, body)
+    }
+
+    #[inline]
+    pub fn request(
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> Result<HttpReply> {
+        match url.split_once("://") {
+            Some((scheme, _)) if scheme.eq_ignore_ascii_case("http") => {
+                request_plain(method, url, headers, body)
+            }
+
+            Some((scheme, _)) if scheme.eq_ignore_ascii_case("https") => {
+                request_with_curl(method, url, headers, body)
+            }
+
+            _ => Err(unsupported_url(url)),
+        }
+    }
+}
+
+/// `application/x-www-form-urlencoded` and query encoding
+#[inline]
+pub fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Body of the form with the given fields
+#[inline]
+pub fn form_body(fields: &[(String, String)]) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| format!("{}={}", percent_encode(key), percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[inline]
+fn invalid_data(message: String) -> Error {
+    Error::Io(io::Error::new(ErrorKind::InvalidData, message))
+}
+
+#[inline]
+fn unsupported_url(url: &str) -> Error {
+    Error::Io(io::Error::new(
+        ErrorKind::InvalidInput,
+        format!("Unsupported URL: {}", url),
+    ))
+}
+
+/// Line breaks in the request line or in headers would let
+/// values taken from URLs and feeds inject headers or split the request
+#[inline]
+fn check_line_breaks(method: &str, url: &str, headers: &[(&str, &str)]) -> Result<()> {
+    let parts = [("Method", method), ("URL", url)].into_iter().chain(
+        headers
+            .iter()
+            .flat_map(|&(name, value)| [("Header name", name), ("Header value", value)]),
+    );
+
+    for (part, value) in parts {
+        if value.contains(['\r', '\n']) {
+            return Err(Error::Io(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} of the request contains line break", part),
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Absolute URL of the location that is relative to the URL,
+/// e.g. of a redirect or of a link in the page
+#[inline]
+pub fn resolve_location(url: &str, location: &str) -> String {
+    if location.contains("://") {
+        return location.to_string();
+    }
+
+    let (scheme, rest) = url.split_once("://").unwrap_or(("http", url));
+    let authority = rest.split('/').next().unwrap_or_default();
+
+    match location.starts_with('/') {
+        true => format!("{}://{}{}", scheme, authority, location),
+
+        false => {
+            let base = rest.rsplit_once('/').map(|(base, _)| base).unwrap_or(rest);
+            format!("{}://{}/{}", scheme, base, location)
+        }
+    }
+}
+
+/// Connects to the server of the plain HTTP URL.
+/// Returns the stream, authority for `Host` header and path of the request
+#[inline]
+fn connect_plain(url: &str, timeout: Duration) -> Result<(TcpStream, String, String)> {
+    let rest = &url[url.find("://").unwrap_or_default() + 3..];
+
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, "/"),
+    };
+
+    let address = match authority.contains(':') {
+        true => authority.to_string(),
+        false => format!("{}:80", authority),
+    };
+
+    let address = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| invalid_data(format!("Unknown host: {}", authority)))?;
+
+    let stream = TcpStream::connect_timeout(&address, REQUEST_TIMEOUT)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok((stream, authority.to_string(), path.to_string()))
+}
+
+#[inline]
+fn request_plain(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> Result<HttpReply> {
+    check_line_breaks(method, url, headers)?;
+    let (mut stream, authority, path) = connect_plain(url, REQUEST_TIMEOUT)?;
+
+    let mut request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nConnection: close\r\n",
+        method, path, authority, USER_AGENT
+    );
+
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value))
+    }
+
+    if !body.is_empty() || method != "GET" {
+        request.push_str(&format!("Content-Length: {}\r\n", body.len()))
+    }
+
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+    stream.write_all(body)?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply)?;
+    parse_reply(&reply)
+}
+
+/// HTTPS through `curl`: URL, headers and body are passed
+/// through stdin (see [curl_config]), reply with headers is read from stdout
+#[inline]
+fn request_with_curl(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> Result<HttpReply> {
+    let config = curl_config(url, headers, Some(body).filter(|body| !body.is_empty()))?;
+    let mut command = Command::new("curl");
+
+    command
+        .args(["--silent", "--show-error", "--include", "--http1.1"])
+        .args(["--max-time", &REQUEST_TIMEOUT.as_secs().to_string()])
+        .args(["--request", method])
+        .args(["--user-agent", USER_AGENT]);
+
+    let mut child = command
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            Error::Io(io::Error::new(
+                e.kind(),
+                format!("HTTPS requests require curl: {}", e),
+            ))
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&config)?;
+    }
+
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        return Err(Error::Io(io::Error::new(
+            ErrorKind::ConnectionAborted,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        )));
+    }
+
+    parse_reply(&output.stdout)
+}
+
+/// Plain HTTP stream, the body is de-chunked while it's read
+#[inline]
+fn open_plain_stream(url: &str, headers: &[(&str, &str)]) -> Result<HttpStream> {
+    check_line_breaks("GET", url, headers)?;
+    let (mut stream, authority, path) = connect_plain(url, STREAM_TIMEOUT)?;
+
+    let mut request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nConnection: close\r\n",
+        path, authority, USER_AGENT
+    );
+
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value))
+    }
+
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+
+    let mut reader = BufReader::new(stream);
+    let (status, headers) = read_head(&mut reader)?;
+
+    let body: Box<dyn Read + Send + Sync> = match find_header(&headers, "Transfer-Encoding") {
+        Some(encoding) if encoding.eq_ignore_ascii_case("chunked") => {
+            Box::new(ChunkedReader::new(reader))
+        }
+
+        _ => Box::new(reader),
+    };
+
+    Ok(HttpStream {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// HTTPS stream through `curl`, which follows redirects by itself
+/// and prints the heads of all replies before the body
+#[inline]
+fn open_curl_stream(url: &str, headers: &[(&str, &str)]) -> Result<HttpStream> {
+    let config = curl_config(url, headers, None)?;
+    let mut command = Command::new("curl");
+
+    command
+        .args(["--silent", "--show-error", "--include", "--http1.1"])
+        .args(["--no-buffer", "--location"])
+        .args(["--connect-timeout", &REQUEST_TIMEOUT.as_secs().to_string()])
+        .args(["--speed-limit", "1"])
+        .args(["--speed-time", &STREAM_TIMEOUT.as_secs().to_string()])
+        .args(["--user-agent", USER_AGENT]);
+
+    let mut child = command
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            Error::Io(io::Error::new(
+                e.kind(),
+                format!("HTTPS requests require curl: {}", e),
+            ))
+        })?;
+
+    // Closed stdin ends the config
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&config)?;
+    }
+
+    let mut reader = BufReader::new(child.stdout.take().unwrap());
+
+    loop {
+        let (status, headers) = match read_head(&mut reader) {
+            Ok(head) => head,
+
+            Err(e) => {
+                let _ = child.kill();
+                let mut message = String::new();
+
+                if let Some(mut stderr) = child.stderr.take() {
+                    stderr.read_to_string(&mut message).unwrap_or_default();
+                }
+
+                let _ = child.wait();
+
+                return match message.trim().is_empty() {
+                    true => Err(e),
+                    false => Err(Error::Io(io::Error::new(
+                        ErrorKind::ConnectionAborted,
+                        message.trim().to_string(),
+                    ))),
+                };
+            }
+        };
+
+        // Interim replies and redirects that curl has followed
+        if status < 200
+            || ((300..400).contains(&status) && find_header(&headers, "Location").is_some())
+        {
+            continue;
+        }
+
+        return Ok(HttpStream {
+            status,
+            headers,
+            body: Box::new(CurlBody { reader, child }),
+        });
+    }
+}
+
+/// Config of `curl` with URL, headers and body of the request.
+/// It's written to curl's stdin, so tokens and session keys
+/// are not visible in the process list like arguments are
+///
+/// # Arguments
+/// body - sent as is, `@` at the start is not treated as a file name.
+/// Unlike URL and headers, it may contain line breaks
+#[inline]
+pub fn curl_config(url: &str, headers: &[(&str, &str)], body: Option<&[u8]>) -> Result<Vec<u8>> {
+    check_line_breaks("", url, headers)?;
+    let mut config = Vec::new();
+
+    let mut push_option = |name: &str, value: &[u8]| {
+        if value.contains(&0) {
+            return Err(Error::Io(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} of the request contains NUL byte", name),
+            )));
+        }
+
+        config.extend(name.as_bytes());
+        config.extend(b" = \"");
+
+        for &byte in value {
+            match byte {
+                b'\\' => config.extend(b"\\\\"),
+                b'"' => config.extend(b"\\\""),
+                b'\n' => config.extend(b"\\n"),
+                b'\r' => config.extend(b"\\r"),
+                b'\t' => config.extend(b"\\t"),
+                0x0B => config.extend(b"\\v"),
+                byte => config.push(byte),
+            }
+        }
+
+        config.extend(b"\"\n");
+        Ok(())
+    };
+
+    push_option("url", url.as_bytes())?;
+
+    for (name, value) in headers {
+        push_option("header", format!("{}: {}", name, value).as_bytes())?;
+    }
+
+    if let Some(body) = body {
+        push_option("data-raw", body)?;
+    }
+
+    Ok(config)
+}
+
+/// Status line and headers of the reply, read line by line,
+/// so the body stays in the reader
+#[inline]
+fn read_head<R: BufRead>(reader: &mut R) -> Result<(u16, Vec<(String, String)>)> {
+    let mut head = Vec::new();
+
+    loop {
+        let line_start = head.len();
+        let read = reader.read_until(b'\n', &mut head)?;
+
+        if read == 0 {
+            return Err(invalid_data("Incomplete HTTP reply".to_string()));
+        }
+
+        if head.len() > MAX_HEAD_LEN {
+            return Err(invalid_data("HTTP reply's head is too long".to_string()));
+        }
+
+        let line = &head[line_start..];
+
+        if line == b"\r\n" || line == b"\n" {
+            break;
+        }
+    }
+
+    parse_head(&String::from_utf8_lossy(&head))
+}
+
+/// Status and headers of the head without its blank line.
+/// Shoutcast servers answer with `ICY 200 OK` status line
+#[inline]
+fn parse_head(head: &str) -> Result<(u16, Vec<(String, String)>)> {
+    let mut lines = head.lines();
+
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| invalid_data(format!("Invalid HTTP status line: {}", head)))?;
+
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect::<Vec<_>>();
+
+    Ok((status, headers))
+}
+
+/// Body of the chunked reply that is decoded while it's read
+struct ChunkedReader<R> {
+    reader: R,
+
+    /// Bytes left in the current chunk
+    left: usize,
+    is_finished: bool,
+}
+
+impl<R: BufRead> ChunkedReader<R> {
+    #[inline]
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            left: 0,
+            is_finished: false,
+        }
+    }
+
+    /// Reads size line of the next chunk, skipping the end of the previous one
+    #[inline]
+    fn next_chunk(&mut self) -> io::Result<usize> {
+        let mut line = String::new();
+
+        while line.trim().is_empty() {
+            line.clear();
+
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(ErrorKind::UnexpectedEof, "Incomplete chunk"));
+            }
+        }
+
+        let size = line.split(';').next().unwrap_or_default().trim();
+
+        usize::from_str_radix(size, 16).map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid chunk size: {}", size),
+            )
+        })
+    }
+}
+
+impl<R: BufRead> Read for ChunkedReader<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.is_finished || buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.left == 0 {
+            self.left = self.next_chunk()?;
+
+            if self.left == 0 {
+                self.is_finished = true;
+                return Ok(0);
+            }
+        }
+
+        let len = buf.len().min(self.left);
+        let read = self.reader.read(&mut buf[..len])?;
+
+        if read == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "Incomplete chunk"));
+        }
+
+        self.left -= read;
+        Ok(read)
+    }
+}
+
+/// Output of `curl` that is killed when the stream is dropped
+struct CurlBody {
+    reader: BufReader<ChildStdout>,
+    child: Child,
+}
+
+impl Read for CurlBody {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl Drop for CurlBody {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Status line, headers and body, which may be chunked.
+/// Interim `100 Continue` replies are skipped
+#[inline]
+fn parse_reply(mut data: &[u8]) -> Result<HttpReply> {
+    loop {
+        let head_end = data
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or_else(|| invalid_data("Incomplete HTTP reply".to_string()))?;
+
+        let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+        let body = &data[head_end + 4..];
+        let (status, headers) = parse_head(&head)?;
+
+        if status == 100 {
+            data = body;
+            continue;
+        }
+
+        let mut reply = HttpReply {
+            status,
+            headers,
+            body: Vec::new(),
+        };
+
+        reply.body = match reply.header("Transfer-Encoding") {
+            Some(encoding) if encoding.eq_ignore_ascii_case("chunked") => decode_chunked(body)?,
+            _ => body.to_vec(),
+        };
+
+        return Ok(reply);
+    }
+}
+
+#[inline]
+fn decode_chunked(mut data: &[u8]) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+
+    loop {
+        let line_end = data
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(|| invalid_data("Incomplete chunk".to_string()))?;
+
+        let size = String::from_utf8_lossy(&data[..line_end]);
+        let size = size.split(';').next().unwrap_or_default().trim();
+
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| invalid_data(format!("Invalid chunk size: {}", size)))?;
+
+        if size == 0 {
+            return Ok(body);
+        }
+
+        let chunk = data
+            .get(line_end + 2..line_end + 2 + size)
+            .ok_or_else(|| invalid_data("Incomplete chunk".to_string()))?;
+
+        body.extend_from_slice(chunk);
+        data = data.get(line_end + 4 + size..).unwrap_or_default();
+    }
+}
@@ -0,0 +1,127 @@
+extern crate jni;
+extern crate once_cell;
+
+use crate::{
+    domain::events::player_event::PlayerEvent,
+    error::{Error, Result, PRIMA_EXCEPTION_CLASS},
+    PRIMA,
+};
+
+use jni::{
+    sys::{jboolean, jfloat, jint, jlong, jobject, JNI_FALSE},
+    JNIEnv,
+};
+
+use std::panic::{self, AssertUnwindSafe};
+
+/// Value returned to JVM when exception is thrown.
+/// JVM ignores it, but native function still has to return something
+pub trait JniDefault {
+    fn jni_default() -> Self;
+}
+
+impl JniDefault for () {
+    #[inline]
+    fn jni_default() -> Self {}
+}
+
+impl JniDefault for jobject {
+    #[inline]
+    fn jni_default() -> Self {
+        std::ptr::null_mut()
+    }
+}
+
+impl JniDefault for jboolean {
+    #[inline]
+    fn jni_default() -> Self {
+        JNI_FALSE
+    }
+}
+
+impl JniDefault for jint {
+    #[inline]
+    fn jni_default() -> Self {
+        0
+    }
+}
+
+impl JniDefault for jlong {
+    #[inline]
+    fn jni_default() -> Self {
+        0
+    }
+}
+
+impl JniDefault for jfloat {
+    #[inline]
+    fn jni_default() -> Self {
+        0.0
+    }
+}
+
+/// Runs body of the extern JNI function.
+/// Errors and panics are not allowed to cross the boundary:
+/// they are converted to `PrimaException` subclasses
+/// and thrown in the calling Java thread.
+/// Error is also published to the event listener
+///
+/// # Arguments
+/// env - environment of the extern function
+///
+/// call - body of the function
+///
+/// # Return
+/// Result of the call or [JniDefault] value if exception was thrown
+#[inline]
+pub fn catch_jni_call<'local, T, F>(env: JNIEnv<'local>, call: F) -> T
+where
+    T: JniDefault,
+    F: FnOnce(JNIEnv<'local>) -> Result<T>,
+{
+    // Only one of the environments is used at a time
+    let mut throw_env = unsafe { env.unsafe_clone() };
+
+    let error = match panic::catch_unwind(AssertUnwindSafe(|| call(env))) {
+        Ok(Ok(result)) => return result,
+        Ok(Err(error)) => error,
+        Err(payload) => Error::from_panic(payload),
+    };
+
+    // Session may be the thing that has failed to start
+    if let Some(prima) = PRIMA.get() {
+        prima.get_event_bus().publish(PlayerEvent::Error {
+            message: error.to_string(),
+        })
+    }
+
+    throw_error(&mut throw_env, &error);
+    T::jni_default()
+}
+
+/// Throws Java exception that matches the error.
+/// If Java exception is already pending, it is left as is
+#[inline]
+pub fn throw_error(env: &mut JNIEnv, error: &Error) {
+    if env.exception_check().unwrap_or_default() {
+        return;
+    }
+
+    let message = error.to_string();
+
+    if env
+        .throw_new(error.java_exception_class(), &message)
+        .is_ok()
+    {
+        return;
+    }
+
+    // Exception classes may be missing from the classpath
+    env.exception_clear().unwrap_or_default();
+
+    if env.throw_new(PRIMA_EXCEPTION_CLASS, &message).is_err() {
+        env.exception_clear().unwrap_or_default();
+        env.throw_new("java/lang/RuntimeException", &message)
+            .unwrap_or_default();
+    }
+}
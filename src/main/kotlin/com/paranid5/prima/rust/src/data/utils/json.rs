@@ -0,0 +1,446 @@
+use crate::error::{Error, Result};
+
+use std::{
+    fmt::{Display, Formatter, Write},
+    io::{self, ErrorKind},
+};
+
+/// Nesting deeper than this is rejected,
+/// so malicious requests can't overflow the stack
+const MAX_DEPTH: usize = 64;
+
+/// JSON document. Objects keep the order of their keys
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+#[inline]
+fn json_error(message: impl Into<String>) -> Error {
+    Error::Io(io::Error::new(ErrorKind::InvalidData, message.into()))
+}
+
+impl JsonValue {
+    /// Builds object from key-value pairs
+    #[inline]
+    pub fn object<I: IntoIterator<Item = (&'static str, JsonValue)>>(entries: I) -> Self {
+        JsonValue::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Value of object's key or None for other values
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(items) => Some(items.as_slice()),
+            _ => None,
+        }
+    }
+
+    /// Parses complete document, trailing characters are an error
+    #[inline]
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = JsonParser {
+            bytes: text.as_bytes(),
+            position: 0,
+        };
+
+        let value = parser.parse_value(0)?;
+        parser.skip_whitespace();
+
+        match parser.position == parser.bytes.len() {
+            true => Ok(value),
+            false => Err(json_error("Unexpected characters after JSON value")),
+        }
+    }
+}
+
+impl From<bool> for JsonValue {
+    #[inline]
+    fn from(value: bool) -> Self {
+        JsonValue::Bool(value)
+    }
+}
+
+impl From<f64> for JsonValue {
+    #[inline]
+    fn from(value: f64) -> Self {
+        JsonValue::Number(value)
+    }
+}
+
+impl From<f32> for JsonValue {
+    #[inline]
+    fn from(value: f32) -> Self {
+        // Through the string, so 0.1f32 is written as 0.1, not 0.10000000149011612
+        JsonValue::Number(value.to_string().parse().unwrap_or_default())
+    }
+}
+
+impl From<i64> for JsonValue {
+    #[inline]
+    fn from(value: i64) -> Self {
+        JsonValue::Number(value as f64)
+    }
+}
+
+impl From<usize> for JsonValue {
+    #[inline]
+    fn from(value: usize) -> Self {
+        JsonValue::Number(value as f64)
+    }
+}
+
+impl From<&str> for JsonValue {
+    #[inline]
+    fn from(value: &str) -> Self {
+        JsonValue::String(value.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    #[inline]
+    fn from(value: String) -> Self {
+        JsonValue::String(value)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    #[inline]
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(JsonValue::Null)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Vec<T>> for JsonValue {
+    #[inline]
+    fn from(values: Vec<T>) -> Self {
+        JsonValue::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+#[inline]
+fn write_string(f: &mut Formatter<'_>, s: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+
+    f.write_char('"')
+}
+
+impl Display for JsonValue {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonValue::Null => f.write_str("null"),
+            JsonValue::Bool(b) => write!(f, "{}", b),
+
+            // JSON has no NaN and infinities
+            JsonValue::Number(x) if !x.is_finite() => f.write_str("null"),
+            JsonValue::Number(x) => write!(f, "{}", x),
+
+            JsonValue::String(s) => write_string(f, s),
+
+            JsonValue::Array(items) => {
+                f.write_char('[')?;
+
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?
+                    }
+
+                    write!(f, "{}", item)?
+                }
+
+                f.write_char(']')
+            }
+
+            JsonValue::Object(entries) => {
+                f.write_char('{')?;
+
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?
+                    }
+
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?
+                }
+
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl JsonParser<'_> {
+    #[inline]
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1
+        }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    #[inline]
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        match self.bytes[self.position..].starts_with(expected.as_bytes()) {
+            true => {
+                self.position += expected.len();
+                Ok(())
+            }
+
+            false => Err(json_error(format!(
+                "Expected `{}` at {}",
+                expected, self.position
+            ))),
+        }
+    }
+
+    #[inline]
+    fn parse_value(&mut self, depth: usize) -> Result<JsonValue> {
+        if depth > MAX_DEPTH {
+            return Err(json_error("JSON is nested too deep"));
+        }
+
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(b'n') => self.expect("null").map(|_| JsonValue::Null),
+            Some(b't') => self.expect("true").map(|_| JsonValue::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| JsonValue::Bool(false)),
+            Some(b'"') => self.parse_string().map(JsonValue::String),
+            Some(b'[') => self.parse_array(depth),
+            Some(b'{') => self.parse_object(depth),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            _ => Err(json_error(format!("Unexpected value at {}", self.position))),
+        }
+    }
+
+    #[inline]
+    fn parse_number(&mut self) -> Result<JsonValue> {
+        let start = self.position;
+
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.position += 1
+        }
+
+        std::str::from_utf8(&self.bytes[start..self.position])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .map(JsonValue::Number)
+            .ok_or_else(|| json_error(format!("Invalid number at {}", start)))
+    }
+
+    #[inline]
+    fn parse_hex4(&mut self) -> Result<u32> {
+        let hex = self
+            .bytes
+            .get(self.position..self.position + 4)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| json_error(format!("Invalid escape at {}", self.position)))?;
+
+        self.position += 4;
+        Ok(hex)
+    }
+
+    #[inline]
+    fn parse_string(&mut self) -> Result<String> {
+        self.expect("\"")?;
+        let mut bytes = Vec::new();
+
+        loop {
+            let byte = self
+                .peek()
+                .ok_or_else(|| json_error("Unterminated string"))?;
+
+            self.position += 1;
+
+            match byte {
+                b'"' => break,
+
+                b'\\' => {
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| json_error("Unterminated string"))?;
+                    self.position += 1;
+
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+
+                        b'u' => {
+                            let mut code = self.parse_hex4()?;
+
+                            // Characters outside of BMP are written as surrogate pairs
+                            if (0xD800..0xDC00).contains(&code) {
+                                self.expect("\\u")?;
+                                let low = self.parse_hex4()?;
+                                code = 0x10000
+                                    + ((code - 0xD800) << 10)
+                                    + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+
+                        _ => {
+                            return Err(json_error(format!("Invalid escape at {}", self.position)))
+                        }
+                    };
+
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes())
+                }
+
+                byte => bytes.push(byte),
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| json_error("String is not valid UTF-8"))
+    }
+
+    #[inline]
+    fn parse_array(&mut self, depth: usize) -> Result<JsonValue> {
+        self.expect("[")?;
+        let mut items = Vec::new();
+
+        self.skip_whitespace();
+
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(JsonValue::Array(items));
+        }
+
+        loop {
+            items.push(self.parse_value(depth + 1)?);
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(b',') => self.position += 1,
+
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+
+                _ => {
+                    return Err(json_error(format!(
+                        "Expected `,` or `]` at {}",
+                        self.position
+                    )))
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn parse_object(&mut self, depth: usize) -> Result<JsonValue> {
+        self.expect("{")?;
+        let mut entries = Vec::new();
+
+        self.skip_whitespace();
+
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(JsonValue::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+
+            self.skip_whitespace();
+            self.expect(":")?;
+
+            entries.push((key, self.parse_value(depth + 1)?));
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(b',') => self.position += 1,
+
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(JsonValue::Object(entries));
+                }
+
+                _ => {
+                    return Err(json_error(format!(
+                        "Expected `,` or `}}` at {}",
+                        self.position
+                    )))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+extern crate jni;
+extern crate once_cell;
+extern crate tokio;
+
+use crate::{
+    data::utils::{extensions::track_ext::TrackExt, types::AJVM},
+    domain::events::{event_bus::EventBus, player_event::PlayerEvent},
+    error::Result,
+};
+
+use jni::{
+    objects::{GlobalRef, JObject, JValue},
+    sys::{jint, jlong},
+    JNIEnv,
+};
+
+use once_cell::sync::OnceCell;
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use tokio::sync::broadcast::error::RecvError;
+
+const DISPATCH_THREAD_NAME: &str = "prima-events";
+const TRACK_CLASS_SIGNATURE: &str = "Lcom/paranid5/prima/data/Track;";
+
+/// Delivers [PlayerEvent]s to the `PlayerEventListener` registered from the JVM.
+/// Events are received from [EventBus] and dispatched
+/// on a single thread that is attached to JVM once
+pub struct JvmEventListener {
+    listener: Arc<Mutex<Option<GlobalRef>>>,
+    dispatch_thread: OnceCell<()>,
+}
+
+impl JvmEventListener {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            listener: Arc::new(Mutex::new(None)),
+            dispatch_thread: OnceCell::new(),
+        }
+    }
+
+    /// Replaces current listener. Null listener disables delivery.
+    /// Dispatch thread is started on the first call
+    #[inline]
+    pub fn set_listener(
+        &self,
+        env: &mut JNIEnv,
+        listener: &JObject,
+        event_bus: &EventBus,
+    ) -> Result<()> {
+        let listener = match listener.is_null() {
+            true => None,
+            false => Some(env.new_global_ref(listener)?),
+        };
+
+        *self.listener.lock().unwrap() = listener;
+
+        let jvm = Arc::new(env.get_java_vm()?);
+
+        self.dispatch_thread.get_or_try_init(|| {
+            let listener = self.listener.clone();
+            let receiver = event_bus.subscribe();
+
+            thread::Builder::new()
+                .name(DISPATCH_THREAD_NAME.to_string())
+                .spawn(move || Self::run_dispatch_loop(jvm, listener, receiver))
+                .map(|_| ())
+        })?;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn run_dispatch_loop(
+        jvm: AJVM,
+        listener: Arc<Mutex<Option<GlobalRef>>>,
+        mut receiver: tokio::sync::broadcast::Receiver<PlayerEvent>,
+    ) {
+        let mut env = match jvm.attach_current_thread_permanently() {
+            Ok(env) => env,
+
+            Err(e) => {
+                eprintln!("Unable to attach event thread to JVM: {}", e);
+                return;
+            }
+        };
+
+        loop {
+            let event = match receiver.blocking_recv() {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+
+            let listener = match listener.lock().unwrap().clone() {
+                None => continue,
+                Some(listener) => listener,
+            };
+
+            // Every event is delivered in its own local frame,
+            // so local references don't pile up on the long-living thread
+            let result = env.with_local_frame(8, |env| -> Result<()> {
+                Self::deliver(env, listener.as_obj(), &event)
+            });
+
+            if let Err(e) = result {
+                eprintln!("Unable to deliver {:?}: {}", event, e)
+            }
+
+            if env.exception_check().unwrap_or_default() {
+                env.exception_describe().unwrap_or_default();
+                env.exception_clear().unwrap_or_default();
+            }
+        }
+    }
+
+    #[inline]
+    fn deliver(env: &mut JNIEnv, listener: &JObject, event: &PlayerEvent) -> Result<()> {
+        match event {
+            PlayerEvent::StateChanged { is_playing } => {
+                env.call_method(
+                    listener,
+                    "onStateChanged",
+                    "(Z)V",
+                    &[JValue::Bool((*is_playing).into())],
+                )?;
+            }
+
+            PlayerEvent::TrackChanged { index, track } => {
+                let track = match track {
+                    None => JObject::null(),
+
+                    Some(track) => {
+                        let track_env = unsafe { env.unsafe_clone() };
+                        track.to_java_track(Rc::new(RefCell::new(track_env)))
+                    }
+                };
+
+                env.call_method(
+                    listener,
+                    "onTrackChanged",
+                    format!("(I{})V", TRACK_CLASS_SIGNATURE),
+                    &[JValue::Int(*index as jint), JValue::Object(&track)],
+                )?;
+            }
+
+            PlayerEvent::PositionTick { position } => {
+                env.call_method(
+                    listener,
+                    "onPositionChanged",
+                    "(J)V",
+                    &[JValue::Long(position.as_millis() as jlong)],
+                )?;
+            }
+
+            PlayerEvent::QueueChanged { length, index } => {
+                env.call_method(
+                    listener,
+                    "onQueueChanged",
+                    "(II)V",
+                    &[JValue::Int(*length as jint), JValue::Int(*index as jint)],
+                )?;
+            }
+
+            PlayerEvent::LibraryUpdated { tracks_count } => {
+                env.call_method(
+                    listener,
+                    "onLibraryUpdated",
+                    "(I)V",
+                    &[JValue::Int(*tracks_count as jint)],
+                )?;
+            }
+
+            PlayerEvent::OutputDeviceChanged { device } => {
+                let device = match device {
+                    None => JObject::null(),
+                    Some(device) => JObject::from(env.new_string(device)?),
+                };
+
+                env.call_method(
+                    listener,
+                    "onOutputDeviceChanged",
+                    "(Ljava/lang/String;)V",
+                    &[JValue::Object(&device)],
+                )?;
+            }
+
+            PlayerEvent::SleepTimerFinished => {
+                env.call_method(listener, "onSleepTimerFinished", "()V", &[])?;
+            }
+
+            PlayerEvent::StreamTitleChanged { title } => {
+                let title = match title {
+                    None => JObject::null(),
+                    Some(title) => JObject::from(env.new_string(title)?),
+                };
+
+                env.call_method(
+                    listener,
+                    "onStreamTitleChanged",
+                    "(Ljava/lang/String;)V",
+                    &[JValue::Object(&title)],
+                )?;
+            }
+
+            PlayerEvent::PodcastsUpdated { new_episodes } => {
+                env.call_method(
+                    listener,
+                    "onPodcastsUpdated",
+                    "(I)V",
+                    &[JValue::Int(*new_episodes as jint)],
+                )?;
+            }
+
+            PlayerEvent::Error { message } => {
+                let message = JObject::from(env.new_string(message)?);
+
+                env.call_method(
+                    listener,
+                    "onError",
+                    "(Ljava/lang/String;)V",
+                    &[JValue::Object(&message)],
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for JvmEventListener {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,6 +1,15 @@
 pub mod constants;
 pub mod extensions;
+#[cfg(unix)]
+pub mod file_url;
+pub mod http_client;
+pub mod jni_call;
+pub mod json;
+pub mod jvm_event_listener;
+pub mod paths;
+pub mod random;
 #[allow(dead_code)]
 pub mod track_order;
 pub mod types;
 pub mod wrappers;
+pub mod xml;
@@ -0,0 +1,194 @@
+extern crate dirs2;
+extern crate once_cell;
+
+use once_cell::sync::Lazy;
+
+use std::{
+    env, fs,
+    io::{ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+const APP_DIR: &str = "prima";
+const STATE_FILE: &str = "data.yaml";
+const FAVOURITE_DB_FILE: &str = "favourite.db";
+
+/// Marker file which enables portable mode
+/// when it is placed in the working directory
+const PORTABLE_MARKER: &str = "prima.portable";
+
+/// Overrides root directory and enables portable mode
+const PRIMA_HOME_VAR: &str = "PRIMA_HOME";
+
+/// Files that were stored in the working directory
+/// before the app started to follow XDG layout
+const LEGACY_CONFIG_FILES: [&str; 2] = [STATE_FILE, "data.yaml.bak"];
+const LEGACY_DATA_FILES: [&str; 1] = [FAVOURITE_DB_FILE];
+
+pub static APP_PATHS: Lazy<AppPaths> = Lazy::new(|| {
+    let paths = AppPaths::resolve();
+
+    if let Err(e) = paths.prepare() {
+        eprintln!("Unable to prepare application directories: {}", e)
+    }
+
+    paths
+});
+
+/// Directories where application keeps its settings, databases and caches.
+/// By default follows XDG Base Directory specification:
+/// `$XDG_CONFIG_HOME/prima`, `$XDG_DATA_HOME/prima` and `$XDG_CACHE_HOME/prima`.
+/// In portable mode everything is kept in a single directory
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppPaths {
+    config_dir: PathBuf,
+    data_dir: PathBuf,
+    cache_dir: PathBuf,
+    legacy_dir: Option<PathBuf>,
+    is_portable: bool,
+}
+
+impl AppPaths {
+    /// Resolves directories from the process environment
+    #[inline]
+    pub fn resolve() -> Self {
+        let cwd = env::current_dir().unwrap_or_default();
+        Self::resolve_with(|key| env::var_os(key).map(PathBuf::from), &cwd)
+    }
+
+    /// Resolves directories with given variables' source.
+    ///
+    /// # Arguments
+    /// var - returns value of environment variable
+    ///
+    /// cwd - working directory used for portable mode and legacy files
+    #[inline]
+    pub fn resolve_with<F: Fn(&str) -> Option<PathBuf>>(var: F, cwd: &Path) -> Self {
+        if let Some(home) = var(PRIMA_HOME_VAR).filter(|home| !home.as_os_str().is_empty()) {
+            return Self::portable(cwd.join(home));
+        }
+
+        if cwd.join(PORTABLE_MARKER).exists() {
+            return Self::portable(cwd.to_path_buf());
+        }
+
+        // Non-XDG platforms use their own conventional directories
+        let xdg_dir = |key: &str, home_suffix: &str, platform_dir: fn() -> Option<PathBuf>| {
+            var(key)
+                .filter(|dir| dir.is_absolute())
+                .or_else(|| match cfg!(all(unix, not(target_os = "macos"))) {
+                    true => var("HOME").map(|home| home.join(home_suffix)),
+                    false => platform_dir(),
+                })
+                .unwrap_or_else(|| cwd.to_path_buf())
+                .join(APP_DIR)
+        };
+
+        Self {
+            config_dir: xdg_dir("XDG_CONFIG_HOME", ".config", dirs2::config_dir),
+            data_dir: xdg_dir("XDG_DATA_HOME", ".local/share", dirs2::data_dir),
+            cache_dir: xdg_dir("XDG_CACHE_HOME", ".cache", dirs2::cache_dir),
+            legacy_dir: Some(cwd.to_path_buf()),
+            is_portable: false,
+        }
+    }
+
+    /// Keeps all files in the given directory,
+    /// so previous working directory layout continues to work
+    #[inline]
+    pub fn portable(root: PathBuf) -> Self {
+        Self {
+            config_dir: root.clone(),
+            data_dir: root.clone(),
+            cache_dir: root.join("cache"),
+            legacy_dir: None,
+            is_portable: true,
+        }
+    }
+
+    #[inline]
+    pub fn get_config_dir(&self) -> &Path {
+        self.config_dir.as_path()
+    }
+
+    #[inline]
+    pub fn get_data_dir(&self) -> &Path {
+        self.data_dir.as_path()
+    }
+
+    #[inline]
+    pub fn get_cache_dir(&self) -> &Path {
+        self.cache_dir.as_path()
+    }
+
+    #[inline]
+    pub fn is_portable(&self) -> bool {
+        self.is_portable
+    }
+
+    /// Path of YAML file with player's settings
+    #[inline]
+    pub fn get_state_file(&self) -> PathBuf {
+        self.config_dir.join(STATE_FILE)
+    }
+
+    /// Path of SQLite database with favourite tracks, artists and playlists
+    #[inline]
+    pub fn get_favourite_db(&self) -> PathBuf {
+        self.data_dir.join(FAVOURITE_DB_FILE)
+    }
+
+    /// Creates all directories and moves files
+    /// from the working directory if it's needed
+    #[inline]
+    pub fn prepare(&self) -> Result<()> {
+        fs::create_dir_all(&self.config_dir)?;
+        fs::create_dir_all(&self.data_dir)?;
+        fs::create_dir_all(&self.cache_dir)?;
+        self.migrate_legacy_files()
+    }
+
+    /// Moves settings and databases that were stored
+    /// in the working directory to XDG directories.
+    /// Files are moved only if there is no file with the same name
+    /// in the destination, so migration happens once
+    #[inline]
+    pub fn migrate_legacy_files(&self) -> Result<()> {
+        let legacy_dir = match &self.legacy_dir {
+            None => return Ok(()),
+            Some(dir) => dir,
+        };
+
+        let config_files = LEGACY_CONFIG_FILES.iter().map(|f| (f, &self.config_dir));
+        let data_files = LEGACY_DATA_FILES.iter().map(|f| (f, &self.data_dir));
+
+        for (file, dir) in config_files.chain(data_files) {
+            let from = legacy_dir.join(file);
+            let to = dir.join(file);
+
+            if from == to || !from.is_file() || to.exists() {
+                continue;
+            }
+
+            move_file(&from, &to)?
+        }
+
+        Ok(())
+    }
+}
+
+/// Renames file or copies and removes it
+/// when source and destination are on different file systems
+#[inline]
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+
+        Err(e) if e.kind() == ErrorKind::NotFound => Err(e),
+
+        Err(_) => {
+            fs::copy(from, to)?;
+            fs::remove_file(from)
+        }
+    }
+}
@@ -0,0 +1,53 @@
+extern crate getrandom;
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io,
+};
+
+use crate::error::{Error, Result};
+
+/// Fills the buffer from the OS's secure random generator
+#[inline]
+pub fn fill_random(bytes: &mut [u8]) -> Result<()> {
+    getrandom::getrandom(bytes).map_err(|e| {
+        Error::Io(io::Error::other(format!(
+            "Unable to get random bytes: {}",
+            e
+        )))
+    })
+}
+
+/// Random number from the OS's generator.
+/// If the OS can't give it, the number is taken from
+/// the hasher's keys, that are seeded by the OS as well
+#[inline]
+pub fn random_u64() -> u64 {
+    let mut bytes = [0_u8; 8];
+
+    match fill_random(&mut bytes) {
+        Ok(()) => u64::from_le_bytes(bytes),
+        Err(_) => RandomState::new().build_hasher().finish(),
+    }
+}
+
+/// Random index in range 0..len, zero for empty range
+#[inline]
+pub fn random_index(len: usize) -> usize {
+    (random_u64() % len.max(1) as u64) as usize
+}
+
+/// Random number in range [0, 1)
+#[inline]
+pub fn random_unit() -> f32 {
+    (random_u64() >> 40) as f32 / (1_u64 << 24) as f32
+}
+
+/// Fisher-Yates shuffle
+#[inline]
+pub fn shuffle<T>(items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        items.swap(i, random_index(i + 1))
+    }
+}
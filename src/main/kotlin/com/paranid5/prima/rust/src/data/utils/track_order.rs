@@ -4,6 +4,8 @@ extern crate yaml_rust;
 use jni::sys::jint;
 use yaml_rust::{yaml::Hash, Yaml};
 
+use crate::error::{Error, Result};
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Comparator {
     Title,
@@ -11,6 +13,9 @@ pub enum Comparator {
     Album,
     Date,
     NumberInAlbum,
+    Bpm,
+    Key,
+    Energy,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -32,31 +37,34 @@ impl Default for Comparator {
     }
 }
 
-impl From<jint> for Comparator {
+impl TryFrom<i64> for Comparator {
+    type Error = Error;
+
     #[inline]
-    fn from(comparator: jint) -> Self {
+    fn try_from(comparator: i64) -> Result<Self> {
         match comparator {
-            0 => Comparator::Title,
-            1 => Comparator::Artist,
-            2 => Comparator::Album,
-            3 => Comparator::Date,
-            4 => Comparator::NumberInAlbum,
-            _ => unreachable!(),
+            0 => Ok(Comparator::Title),
+            1 => Ok(Comparator::Artist),
+            2 => Ok(Comparator::Album),
+            3 => Ok(Comparator::Date),
+            4 => Ok(Comparator::NumberInAlbum),
+            5 => Ok(Comparator::Bpm),
+            6 => Ok(Comparator::Key),
+            7 => Ok(Comparator::Energy),
+            _ => Err(Error::Config(format!(
+                "Unknown track comparator: {}",
+                comparator
+            ))),
         }
     }
 }
 
-impl From<i64> for Comparator {
+impl TryFrom<jint> for Comparator {
+    type Error = Error;
+
     #[inline]
-    fn from(comparator: i64) -> Self {
-        match comparator {
-            0 => Comparator::Title,
-            1 => Comparator::Artist,
-            2 => Comparator::Album,
-            3 => Comparator::Date,
-            4 => Comparator::NumberInAlbum,
-            _ => unreachable!(),
-        }
+    fn try_from(comparator: jint) -> Result<Self> {
+        Self::try_from(comparator as i64)
     }
 }
 
@@ -69,6 +77,9 @@ impl From<Comparator> for jint {
             Comparator::Album => 2,
             Comparator::Date => 3,
             Comparator::NumberInAlbum => 4,
+            Comparator::Bpm => 5,
+            Comparator::Key => 6,
+            Comparator::Energy => 7,
         }
     }
 }
@@ -82,10 +93,22 @@ impl From<Comparator> for i64 {
             Comparator::Album => 2,
             Comparator::Date => 3,
             Comparator::NumberInAlbum => 4,
+            Comparator::Bpm => 5,
+            Comparator::Key => 6,
+            Comparator::Energy => 7,
         }
     }
 }
 
+impl Comparator {
+    /// Comparators that sort by values of the library analysis,
+    /// tracks are sorted by them only when the analysis is loaded
+    #[inline]
+    pub fn is_analysis(&self) -> bool {
+        matches!(self, Comparator::Bpm | Comparator::Key | Comparator::Energy)
+    }
+}
+
 impl Default for Ord {
     #[inline]
     fn default() -> Self {
@@ -93,25 +116,25 @@ impl Default for Ord {
     }
 }
 
-impl From<jint> for Ord {
+impl TryFrom<i64> for Ord {
+    type Error = Error;
+
     #[inline]
-    fn from(order: jint) -> Self {
+    fn try_from(order: i64) -> Result<Self> {
         match order {
-            0 => Ord::Asc,
-            1 => Ord::Desc,
-            _ => unreachable!(),
+            0 => Ok(Ord::Asc),
+            1 => Ok(Ord::Desc),
+            _ => Err(Error::Config(format!("Unknown track order: {}", order))),
         }
     }
 }
 
-impl From<i64> for Ord {
+impl TryFrom<jint> for Ord {
+    type Error = Error;
+
     #[inline]
-    fn from(order: i64) -> Self {
-        match order {
-            0 => Ord::Asc,
-            1 => Ord::Desc,
-            _ => unreachable!(),
-        }
+    fn try_from(order: jint) -> Result<Self> {
+        Self::try_from(order as i64)
     }
 }
 
@@ -162,19 +185,20 @@ impl From<TrackOrder> for Yaml {
 }
 
 impl From<&Hash> for TrackOrder {
+    /// Unknown codes in settings fall back to the default order
     #[inline]
     fn from(hash: &Hash) -> Self {
         let comparator = hash
             .get(&Yaml::String("comparator".to_string()))
-            .map(|yml| yml.as_i64().map(|cmp| Comparator::from(cmp)))
-            .flatten()
-            .unwrap_or(Comparator::default());
+            .and_then(|yml| yml.as_i64())
+            .and_then(|comparator| Comparator::try_from(comparator).ok())
+            .unwrap_or_default();
 
         let order = hash
             .get(&Yaml::String("order".to_string()))
-            .map(|yml| yml.as_i64().map(|ord| Ord::from(ord)))
-            .flatten()
-            .unwrap_or(Ord::default());
+            .and_then(|yml| yml.as_i64())
+            .and_then(|order| Ord::try_from(order).ok())
+            .unwrap_or_default();
 
         Self::new(comparator, order)
     }
@@ -64,7 +64,7 @@ impl JTrack {
                 get_byte_vec_field_of_jtrack(jni_env.clone(), &jobject, "path")
                     .unwrap()
                     .into_iter()
-                    .filter(|jb| *jb != *NULL_CHARACTER as i8)
+                    .filter(|jb| *jb != NULL_CHARACTER as i8)
                     .map(|jb| jb as u8)
                     .collect::<Vec<_>>()
                     .as_slice(),
@@ -201,7 +201,7 @@ impl Favourable<FavouriteTrack> for JTrack {
             self.album.clone(),
             self.path.clone(),
             self.duration,
-            self.add_date.clone(),
+            self.add_date,
             self.number_in_album,
         )
     }
@@ -0,0 +1,309 @@
+use crate::error::{Error, Result};
+
+use std::io::{self, ErrorKind};
+
+/// Nesting deeper than this is rejected,
+/// so malicious feeds can't overflow the stack
+const MAX_DEPTH: usize = 64;
+
+/// Element of XML document, e.g. RSS or Atom feed.
+/// Names keep their namespace prefixes, like `itunes:duration`.
+/// Text of the element joins its text and CDATA parts, entities are decoded
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct XmlElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlElement>,
+    pub text: String,
+}
+
+#[inline]
+fn xml_error(message: impl Into<String>) -> Error {
+    Error::Io(io::Error::new(ErrorKind::InvalidData, message.into()))
+}
+
+impl XmlElement {
+    /// Parses root element of the document.
+    /// Declaration, comments, processing instructions and doctype are skipped
+    #[inline]
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = XmlParser {
+            text: text.trim_start_matches('\u{feff}'),
+            position: 0,
+        };
+
+        parser.skip_misc()?;
+        let root = parser.parse_element(0)?;
+        parser.skip_misc()?;
+
+        match parser.position == parser.text.len() {
+            true => Ok(root),
+            false => Err(xml_error("Unexpected characters after root element")),
+        }
+    }
+
+    #[inline]
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// First child with the given name
+    #[inline]
+    pub fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    #[inline]
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlElement> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// Trimmed text of the first child with the given name, None if it's blank
+    #[inline]
+    pub fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name)
+            .map(|child| child.text.trim())
+            .filter(|text| !text.is_empty())
+    }
+}
+
+struct XmlParser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> XmlParser<'a> {
+    #[inline]
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    #[inline]
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len()
+    }
+
+    /// Moves past the given terminator
+    #[inline]
+    fn skip_until(&mut self, terminator: &str) -> Result<&'a str> {
+        let start = self.position;
+
+        let end = self
+            .rest()
+            .find(terminator)
+            .ok_or_else(|| xml_error(format!("Expected `{}` after {}", terminator, start)))?;
+
+        self.position += end + terminator.len();
+        Ok(&self.text[start..start + end])
+    }
+
+    /// Skips everything that may surround the root element
+    #[inline]
+    fn skip_misc(&mut self) -> Result<()> {
+        loop {
+            self.skip_whitespace();
+
+            if self.rest().starts_with("<?") {
+                self.skip_until("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_until("-->")?;
+            } else if self.rest().starts_with("<!DOCTYPE") {
+                self.skip_doctype()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Doctype may have internal subset in square brackets
+    #[inline]
+    fn skip_doctype(&mut self) -> Result<()> {
+        let mut depth = 0_usize;
+
+        for (offset, c) in self.rest().char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth = depth.saturating_sub(1),
+
+                '>' if depth == 0 => {
+                    self.position += offset + 1;
+                    return Ok(());
+                }
+
+                _ => {}
+            }
+        }
+
+        Err(xml_error("Unterminated doctype"))
+    }
+
+    #[inline]
+    fn parse_name(&mut self) -> Result<String> {
+        let rest = self.rest();
+
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(rest.len());
+
+        if len == 0 {
+            return Err(xml_error(format!("Expected name at {}", self.position)));
+        }
+
+        self.position += len;
+        Ok(rest[..len].to_string())
+    }
+
+    #[inline]
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        match self.rest().starts_with(expected) {
+            true => {
+                self.position += expected.len();
+                Ok(())
+            }
+
+            false => Err(xml_error(format!(
+                "Expected `{}` at {}",
+                expected, self.position
+            ))),
+        }
+    }
+
+    #[inline]
+    fn parse_element(&mut self, depth: usize) -> Result<XmlElement> {
+        if depth > MAX_DEPTH {
+            return Err(xml_error("XML is nested too deep"));
+        }
+
+        self.expect("<")?;
+
+        let mut element = XmlElement {
+            name: self.parse_name()?,
+            ..XmlElement::default()
+        };
+
+        loop {
+            self.skip_whitespace();
+
+            if self.rest().starts_with("/>") {
+                self.position += 2;
+                return Ok(element);
+            }
+
+            if self.rest().starts_with('>') {
+                self.position += 1;
+                break;
+            }
+
+            let name = self.parse_name()?;
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+
+            let quote = match self.rest().chars().next() {
+                Some(quote @ ('"' | '\'')) => quote,
+                _ => return Err(xml_error(format!("Expected quote at {}", self.position))),
+            };
+
+            self.position += 1;
+            let value = decode_entities(self.skip_until(&quote.to_string())?);
+            element.attributes.push((name, value));
+        }
+
+        loop {
+            let rest = self.rest();
+
+            if rest.is_empty() {
+                return Err(xml_error(format!("Unterminated element {}", element.name)));
+            }
+
+            if rest.starts_with("</") {
+                self.position += 2;
+                let name = self.parse_name()?;
+                self.skip_whitespace();
+                self.expect(">")?;
+
+                return match name == element.name {
+                    true => Ok(element),
+                    false => Err(xml_error(format!(
+                        "Element {} is closed by {}",
+                        element.name, name
+                    ))),
+                };
+            }
+
+            if rest.starts_with("<![CDATA[") {
+                self.position += "<![CDATA[".len();
+                let data = self.skip_until("]]>")?;
+                element.text.push_str(data);
+            } else if rest.starts_with("<!--") {
+                self.skip_until("-->")?;
+            } else if rest.starts_with("<?") {
+                self.skip_until("?>")?;
+            } else if rest.starts_with('<') {
+                let child = self.parse_element(depth + 1)?;
+                element.children.push(child);
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                element.text.push_str(&decode_entities(&rest[..len]));
+                self.position += len;
+            }
+        }
+    }
+}
+
+/// Predefined and numeric entities are decoded,
+/// unknown ones (e.g. HTML's `&nbsp;`) are kept as they are
+#[inline]
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest
+            .find(';')
+            .map(|end| (&rest[1..end], end))
+            .and_then(|(name, end)| decode_entity(name).map(|c| (c, end)));
+
+        match entity {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+#[inline]
+fn decode_entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+
+        _ => {
+            let code = match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => name.strip_prefix('#')?.parse().ok()?,
+            };
+
+            char::from_u32(code)
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::ops::RangeInclusive;
+
+use crate::domain::analysis::{musical_key::MusicalKey, track_analysis::TrackAnalysis};
+
+/// Conditions on the analysis of tracks for smart playlists,
+/// e.g. running tracks between 160 and 180 BPM.
+/// Conditions that are not set match every track
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnalysisFilter {
+    pub bpm: Option<RangeInclusive<f32>>,
+    pub key: Option<MusicalKey>,
+
+    /// Keys that mix well with [AnalysisFilter::key] also match
+    pub is_harmonic: bool,
+
+    pub energy: Option<RangeInclusive<f32>>,
+}
+
+impl AnalysisFilter {
+    #[inline]
+    pub fn matches(&self, analysis: &TrackAnalysis) -> bool {
+        let is_bpm_matched = match (&self.bpm, analysis.bpm) {
+            (None, _) => true,
+            (Some(range), Some(bpm)) => range.contains(&bpm),
+            (Some(_), None) => false,
+        };
+
+        let is_key_matched = match (&self.key, &analysis.key) {
+            (None, _) => true,
+            (Some(key), Some(track_key)) if self.is_harmonic => key.is_compatible_with(track_key),
+            (Some(key), Some(track_key)) => key == track_key,
+            (Some(_), None) => false,
+        };
+
+        let is_energy_matched = self
+            .energy
+            .as_ref()
+            .map(|range| range.contains(&analysis.energy))
+            .unwrap_or(true);
+
+        is_bpm_matched && is_key_matched && is_energy_matched
+    }
+}
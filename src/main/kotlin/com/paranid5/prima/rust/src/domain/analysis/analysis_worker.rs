@@ -0,0 +1,85 @@
+extern crate libc;
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc::{self, Sender},
+    thread,
+};
+
+use crate::error::{Error, Result};
+
+const ANALYSIS_THREAD_NAME: &str = "prima-analysis";
+
+/// Niceness of the analysis thread, the lowest priority on Linux
+#[cfg(target_os = "linux")]
+const ANALYSIS_NICENESS: libc::c_int = 19;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Thread with the lowest priority where whole tracks are decoded and analysed,
+/// so analysis doesn't compete with playback.
+/// Jobs are run one by one in the order they were submitted.
+/// Thread stops when the worker is dropped
+pub struct AnalysisWorker {
+    sender: Sender<Job>,
+}
+
+impl AnalysisWorker {
+    #[inline]
+    pub fn spawn() -> Result<Self> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+
+        thread::Builder::new()
+            .name(ANALYSIS_THREAD_NAME.to_string())
+            .spawn(move || {
+                lower_thread_priority();
+                receiver.into_iter().for_each(|job| job())
+            })?;
+
+        Ok(Self { sender })
+    }
+
+    /// Runs job on the analysis thread and waits for its result.
+    /// Panic of the job is returned as error, the thread keeps working
+    #[inline]
+    pub fn run<T, F>(&self, job: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T> + Send + 'static,
+    {
+        let (result_sender, result_receiver) = mpsc::channel();
+
+        let job = Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(job))
+                .unwrap_or_else(|payload| Err(Error::from_panic(payload)));
+
+            let _ = result_sender.send(result);
+        });
+
+        self.sender
+            .send(job)
+            .map_err(|_| Error::Playback("Analysis thread has stopped".to_string()))?;
+
+        result_receiver
+            .recv()
+            .map_err(|_| Error::Playback("Analysis thread has stopped".to_string()))?
+    }
+}
+
+/// Niceness is per thread on Linux, so the rest of the process keeps its priority
+#[cfg(target_os = "linux")]
+#[inline]
+fn lower_thread_priority() {
+    unsafe {
+        libc::setpriority(
+            libc::PRIO_PROCESS,
+            libc::gettid() as libc::id_t,
+            ANALYSIS_NICENESS,
+        );
+    }
+}
+
+/// Other platforms change priority of the whole process, so it's kept
+#[cfg(not(target_os = "linux"))]
+#[inline]
+fn lower_thread_priority() {}
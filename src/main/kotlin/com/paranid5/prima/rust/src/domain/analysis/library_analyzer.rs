@@ -0,0 +1,169 @@
+extern crate atomic_float;
+extern crate diesel;
+
+use atomic_float::AtomicF32;
+use diesel::SqliteConnection;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use crate::{
+    data::databases::analysis::track_analysis_dao::TrackAnalysisDao,
+    domain::{
+        analysis::{analysis_worker::AnalysisWorker, track_analysis::TrackAnalysis},
+        scrobbling::scrobble_queue::unix_time_now,
+    },
+    error::{Error, Result},
+};
+
+/// Share of the time the library pass works, it sleeps the rest,
+/// so the analysis doesn't keep the CPU busy for minutes
+pub const ANALYSIS_DUTY_CYCLE: f32 = 0.5;
+
+/// Pause of the library pass is split into steps, so it's cancelled quickly
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Progress of the running library analysis, shared with the threads
+/// that want to observe or cancel it
+#[derive(Clone, Debug, Default)]
+pub struct LibraryAnalysisTask {
+    progress: Arc<AtomicF32>,
+    is_cancelled: Arc<AtomicBool>,
+}
+
+impl LibraryAnalysisTask {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Analysed part of the pending tracks in range [0, 1]
+    #[inline]
+    pub fn get_progress(&self) -> f32 {
+        self.progress.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    pub fn cancel(&self) {
+        self.is_cancelled.store(true, Ordering::SeqCst)
+    }
+
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.is_cancelled.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    fn set_progress(&self, progress: f32) {
+        self.progress
+            .store(progress.clamp(0.0, 1.0), Ordering::SeqCst)
+    }
+}
+
+/// Offline pass that stores tempo, key and loudness of the library's tracks
+#[derive(Debug)]
+pub struct LibraryAnalyzer;
+
+impl LibraryAnalyzer {
+    /// Analyses tracks that were not analysed yet or were modified since.
+    /// Every result is stored as soon as it's computed,
+    /// so cancelled pass continues from the same track next time.
+    /// Tracks are decoded one by one on the analysis worker,
+    /// after every track the pass sleeps to keep the given duty cycle.
+    /// Tracks that fail to decode are reported and skipped
+    ///
+    /// # Return
+    /// true if all tracks were analysed, false if the pass was cancelled
+    #[inline]
+    pub fn analyse(
+        tracks: &[PathBuf],
+        worker: &AnalysisWorker,
+        task: &LibraryAnalysisTask,
+        duty_cycle: f32,
+        conn: &mut SqliteConnection,
+    ) -> Result<bool> {
+        if !(duty_cycle > 0.0 && duty_cycle <= 1.0) {
+            return Err(Error::Config(
+                "Analysis duty cycle must be in range (0, 1]".to_string(),
+            ));
+        }
+
+        let analysed = TrackAnalysisDao::get_modified_times(conn)?;
+
+        let pending = tracks
+            .iter()
+            .filter_map(|path| {
+                let modified_at = modified_at(path)?;
+                (analysed.get(path) != Some(&modified_at)).then_some((path, modified_at))
+            })
+            .collect::<Vec<_>>();
+
+        for (i, (path, modified_at)) in pending.iter().enumerate() {
+            if task.is_cancelled() {
+                return Ok(false);
+            }
+
+            let started_at = Instant::now();
+            let track = (*path).clone();
+
+            match worker.run(move || TrackAnalysis::compute(&track)) {
+                Ok(analysis) => {
+                    TrackAnalysisDao::store(path, &analysis, *modified_at, unix_time_now(), conn)?
+                }
+
+                // Worker has stopped, other tracks won't be analysed as well
+                Err(e @ Error::Playback(_)) => return Err(e),
+                Err(e) => eprintln!("Unable to analyse {}: {}", path.display(), e),
+            }
+
+            task.set_progress((i + 1) as f32 / pending.len() as f32);
+
+            let pause = started_at
+                .elapsed()
+                .mul_f32((1.0 - duty_cycle) / duty_cycle);
+
+            if i + 1 < pending.len() && !Self::sleep_unless_cancelled(pause, task) {
+                return Ok(false);
+            }
+        }
+
+        task.set_progress(1.0);
+        Ok(true)
+    }
+
+    /// false if the task was cancelled while sleeping
+    #[inline]
+    fn sleep_unless_cancelled(duration: Duration, task: &LibraryAnalysisTask) -> bool {
+        let wake_at = Instant::now() + duration;
+
+        loop {
+            if task.is_cancelled() {
+                return false;
+            }
+
+            let left = wake_at.saturating_duration_since(Instant::now());
+
+            if left.is_zero() {
+                return true;
+            }
+
+            thread::sleep(left.min(CANCEL_CHECK_INTERVAL));
+        }
+    }
+}
+
+/// Modification time of the file in milliseconds,
+/// None if it can't be read
+#[inline]
+fn modified_at(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64)
+}
@@ -0,0 +1,9 @@
+pub mod analysis_filter;
+pub mod analysis_worker;
+pub mod fft;
+pub mod library_analyzer;
+pub mod musical_key;
+pub mod spectrum_analyzer;
+pub mod track_analysis;
+pub mod waveform;
+pub mod waveform_cache;
@@ -0,0 +1,88 @@
+use std::fmt::{Display, Formatter};
+
+const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Key of the track: tonic as pitch class (C = 0, C# = 1, ..., B = 11) and mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MusicalKey {
+    pub pitch_class: u8,
+    pub is_minor: bool,
+}
+
+impl MusicalKey {
+    #[inline]
+    pub fn new(pitch_class: u8, is_minor: bool) -> Self {
+        Self {
+            pitch_class: pitch_class % 12,
+            is_minor,
+        }
+    }
+
+    /// Number of the key in range 0..24: majors first, then minors.
+    /// Used to store the key and pass it to Kotlin
+    #[inline]
+    pub fn get_index(&self) -> u8 {
+        self.pitch_class + self.is_minor as u8 * 12
+    }
+
+    #[inline]
+    pub fn from_index(index: u8) -> Option<Self> {
+        (index < 24).then(|| Self::new(index % 12, index >= 12))
+    }
+
+    /// Position on the Camelot wheel in range 1..=12.
+    /// Neighbouring numbers and the same number of the other mode
+    /// are harmonically compatible
+    #[inline]
+    pub fn get_camelot_number(&self) -> u8 {
+        // Relative major of the minor key shares the number
+        let major = match self.is_minor {
+            true => (self.pitch_class + 3) % 12,
+            false => self.pitch_class,
+        };
+
+        // C major is 8B, every fifth up adds one
+        ((major as u32 * 7 + 7) % 12 + 1) as u8
+    }
+
+    /// Camelot notation, e.g. `8B` for C major and `8A` for A minor
+    #[inline]
+    pub fn get_camelot(&self) -> String {
+        let letter = match self.is_minor {
+            true => 'A',
+            false => 'B',
+        };
+
+        format!("{}{}", self.get_camelot_number(), letter)
+    }
+
+    /// Same key, relative key or key that is a fifth apart
+    #[inline]
+    pub fn is_compatible_with(&self, other: &Self) -> bool {
+        let distance =
+            (self.get_camelot_number() as i32 - other.get_camelot_number() as i32).rem_euclid(12);
+
+        match self.is_minor == other.is_minor {
+            true => matches!(distance, 0 | 1 | 11),
+            false => distance == 0,
+        }
+    }
+}
+
+impl Display for MusicalKey {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mode = match self.is_minor {
+            true => "minor",
+            false => "major",
+        };
+
+        write!(
+            f,
+            "{} {}",
+            PITCH_CLASS_NAMES[self.pitch_class as usize], mode
+        )
+    }
+}
@@ -0,0 +1,98 @@
+use std::f32::consts::PI;
+
+use crate::domain::analysis::fft::fft;
+
+/// Lowest frequency of the first spectrum band, below it is inaudible
+const MIN_BAND_FREQUENCY: f32 = 20.0;
+
+/// Magnitude spectrum of Hann-windowed samples.
+/// Buffers are allocated once, so the analyzer can run on the audio thread
+pub struct SpectrumAnalyzer {
+    window: Vec<f32>,
+    window_sum: f32,
+    re: Vec<f32>,
+    im: Vec<f32>,
+}
+
+impl SpectrumAnalyzer {
+    /// Size must be a power of two
+    #[inline]
+    pub fn new(size: usize) -> Self {
+        debug_assert!(size.is_power_of_two());
+
+        let window = (0..size)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / size as f32).cos())
+            .collect::<Vec<_>>();
+
+        Self {
+            window_sum: window.iter().sum(),
+            window,
+            re: vec![0.0; size],
+            im: vec![0.0; size],
+        }
+    }
+
+    #[inline]
+    pub fn get_size(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Magnitudes of the first `size / 2` bins.
+    /// Sine with full scale amplitude has magnitude of 1.
+    /// Missing samples are treated as silence
+    #[inline]
+    pub fn magnitudes(&mut self, samples: impl IntoIterator<Item = f32>) -> &[f32] {
+        self.re.fill(0.0);
+        self.im.fill(0.0);
+
+        self.re
+            .iter_mut()
+            .zip(&self.window)
+            .zip(samples)
+            .for_each(|((re, w), sample)| *re = sample * w);
+
+        fft(&mut self.re, &mut self.im);
+
+        let half = self.re.len() / 2;
+        let scale = 2.0 / self.window_sum;
+
+        for i in 0..half {
+            self.re[i] = self.re[i].hypot(self.im[i]) * scale;
+        }
+
+        &self.re[..half]
+    }
+
+    /// Groups bins into bands with logarithmically spaced edges
+    /// from 20 Hz to the Nyquist frequency, each band takes its loudest bin.
+    /// Narrow low bands that contain no bin take the nearest one
+    #[inline]
+    pub fn bands(magnitudes: &[f32], sample_rate: u32, count: usize, bands: &mut Vec<f32>) {
+        bands.clear();
+
+        if magnitudes.is_empty() || count == 0 {
+            return;
+        }
+
+        let nyquist = sample_rate as f32 / 2.0;
+        let bin_width = nyquist / magnitudes.len() as f32;
+        let ratio = (nyquist / MIN_BAND_FREQUENCY).max(1.0);
+        let edge = |i: usize| MIN_BAND_FREQUENCY * ratio.powf(i as f32 / count as f32);
+        let last_bin = magnitudes.len() - 1;
+
+        bands.extend((0..count).map(|i| {
+            let (low, high) = (edge(i), edge(i + 1));
+            let first = ((low / bin_width).ceil() as usize).min(last_bin);
+            let last = ((high / bin_width).ceil() as usize).min(magnitudes.len());
+
+            match first < last {
+                true => magnitudes[first..last].iter().copied().fold(0.0, f32::max),
+
+                false => {
+                    let center = (low * high).sqrt();
+                    magnitudes[((center / bin_width).round() as usize).min(last_bin)]
+                }
+            }
+        }));
+    }
+}
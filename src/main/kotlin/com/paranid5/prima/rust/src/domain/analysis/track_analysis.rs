@@ -0,0 +1,293 @@
+extern crate rodio;
+
+use rodio::Source;
+
+use std::{path::Path, time::Duration};
+
+use crate::{
+    domain::{
+        analysis::{musical_key::MusicalKey, spectrum_analyzer::SpectrumAnalyzer},
+        audio_player::decoders::track_decoder::TrackDecoder,
+    },
+    error::Result,
+};
+
+/// Size of the spectrum frames that onsets and chroma are taken from
+const SPECTRUM_WINDOW_SIZE: usize = 2048;
+
+/// Spectrum frames per second, tempo is measured in these frames
+const ONSET_FRAME_RATE: u32 = 100;
+
+/// Makes quiet partials count in the onset strength
+const ONSET_COMPRESSION: f32 = 1000.0;
+
+const MIN_BPM: f32 = 60.0;
+const MAX_BPM: f32 = 200.0;
+
+/// Tempo that is preferred when its half or double fits as well
+const PREFERRED_BPM: f32 = 120.0;
+
+/// Frequencies that are folded into pitch classes.
+/// Lower bins are too wide to tell semitones apart
+const CHROMA_MIN_FREQUENCY: f32 = 100.0;
+const CHROMA_MAX_FREQUENCY: f32 = 5000.0;
+
+/// Krumhansl-Kessler key profiles, starting from the tonic
+const MAJOR_PROFILE: [f32; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+
+const MINOR_PROFILE: [f32; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Length of blocks that loudness is measured in
+const LOUDNESS_BLOCK: Duration = Duration::from_millis(400);
+
+/// Blocks below this level are silence and don't count in the loudness range
+const ABSOLUTE_GATE_DB: f32 = -70.0;
+
+/// Blocks this far below the track's level don't count in the loudness range
+const RELATIVE_GATE_DB: f32 = -20.0;
+
+/// Level that has zero energy, full scale has energy of 1
+const ENERGY_FLOOR_DB: f32 = -60.0;
+
+/// Tempo, key and loudness of the whole track
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackAnalysis {
+    /// None if the track has no steady beat
+    pub bpm: Option<f32>,
+
+    /// None if the track has no pitched sound
+    pub key: Option<MusicalKey>,
+
+    /// Average level mapped to range `0.0..=1.0`
+    pub energy: f32,
+
+    /// Spread between quiet and loud parts in dB
+    pub loudness_range: f32,
+}
+
+impl TrackAnalysis {
+    /// Decodes the whole track, channels are mixed to mono
+    #[inline]
+    pub fn compute(path: &Path) -> Result<Self> {
+        let decoder = TrackDecoder::open(path)?;
+        let channels = decoder.channels().max(1) as usize;
+        let sample_rate = decoder.sample_rate();
+        let mut samples = decoder.map(|sample| sample as f32 / i16::MAX as f32);
+
+        let mono = std::iter::from_fn(move || {
+            let mut sum = 0.0;
+
+            for _ in 0..channels {
+                sum += samples.next()?;
+            }
+
+            Some(sum / channels as f32)
+        });
+
+        Ok(Self::compute_from(mono, sample_rate))
+    }
+
+    /// Analyses mono samples in range `-1.0..=1.0`
+    #[inline]
+    pub fn compute_from(samples: impl IntoIterator<Item = f32>, sample_rate: u32) -> Self {
+        let sample_rate = sample_rate.max(1);
+        let hop = (sample_rate / ONSET_FRAME_RATE).max(1) as usize;
+        let block_len = ((sample_rate as f32 * LOUDNESS_BLOCK.as_secs_f32()) as usize).max(1);
+
+        let mut analyzer = SpectrumAnalyzer::new(SPECTRUM_WINDOW_SIZE);
+        let pitch_classes = pitch_classes_of_bins(sample_rate);
+
+        let mut window = vec![0.0; SPECTRUM_WINDOW_SIZE];
+        let mut window_pos = 0;
+        let mut since_hop = 0;
+        let mut previous = vec![0.0; SPECTRUM_WINDOW_SIZE / 2];
+        let mut onsets = Vec::new();
+        let mut chroma = [0.0; 12];
+
+        let mut block_sum = 0.0;
+        let mut block_samples = 0;
+        let mut blocks = Vec::new();
+
+        for sample in samples {
+            window[window_pos] = sample;
+            window_pos = (window_pos + 1) % SPECTRUM_WINDOW_SIZE;
+
+            block_sum += sample * sample;
+            block_samples += 1;
+
+            if block_samples == block_len {
+                blocks.push(block_sum / block_len as f32);
+                block_sum = 0.0;
+                block_samples = 0;
+            }
+
+            since_hop += 1;
+
+            if since_hop < hop {
+                continue;
+            }
+
+            since_hop = 0;
+
+            // Oldest samples go first
+            let (newest, oldest) = window.split_at(window_pos);
+            let magnitudes = analyzer.magnitudes(oldest.iter().chain(newest).copied());
+            let mut flux = 0.0;
+
+            for ((&magnitude, previous), pitch_class) in
+                magnitudes.iter().zip(&mut previous).zip(&pitch_classes)
+            {
+                let compressed = (1.0 + ONSET_COMPRESSION * magnitude).ln();
+                flux += (compressed - *previous).max(0.0);
+                *previous = compressed;
+
+                if let Some(pitch_class) = pitch_class {
+                    chroma[*pitch_class as usize] += magnitude;
+                }
+            }
+
+            onsets.push(flux);
+        }
+
+        if blocks.is_empty() && block_samples > 0 {
+            blocks.push(block_sum / block_samples as f32);
+        }
+
+        let frame_rate = sample_rate as f32 / hop as f32;
+        let (energy, loudness_range) = loudness_of(&blocks);
+
+        Self {
+            bpm: estimate_tempo(&onsets, frame_rate),
+            key: estimate_key(&chroma),
+            energy,
+            loudness_range,
+        }
+    }
+}
+
+/// Pitch class of every spectrum bin, None outside of the chroma range
+#[inline]
+fn pitch_classes_of_bins(sample_rate: u32) -> Vec<Option<u8>> {
+    let bin_width = sample_rate as f32 / SPECTRUM_WINDOW_SIZE as f32;
+
+    (0..SPECTRUM_WINDOW_SIZE / 2)
+        .map(|bin| {
+            let frequency = bin as f32 * bin_width;
+
+            (CHROMA_MIN_FREQUENCY..=CHROMA_MAX_FREQUENCY)
+                .contains(&frequency)
+                .then(|| {
+                    // MIDI note 69 is A4 at 440 Hz, note 60 is C4
+                    let note = 69.0 + 12.0 * (frequency / 440.0).log2();
+                    (note.round() as i32).rem_euclid(12) as u8
+                })
+        })
+        .collect()
+}
+
+/// Period of the onset strength found by autocorrelation.
+/// Lags are weighted towards [PREFERRED_BPM], so half and double tempo lose
+#[inline]
+fn estimate_tempo(onsets: &[f32], frame_rate: f32) -> Option<f32> {
+    let min_lag = ((frame_rate * 60.0 / MAX_BPM).floor() as usize).max(2);
+    let max_lag = (frame_rate * 60.0 / MIN_BPM).ceil() as usize;
+
+    if onsets.len() < max_lag * 2 {
+        return None;
+    }
+
+    let mean = onsets.iter().sum::<f32>() / onsets.len() as f32;
+    let envelope = onsets.iter().map(|x| x - mean).collect::<Vec<_>>();
+
+    let correlation = |lag: usize| {
+        let products = envelope.iter().zip(&envelope[lag..]).map(|(a, b)| a * b);
+        products.sum::<f32>() / (envelope.len() - lag) as f32
+    };
+
+    let prior = |lag: f32| {
+        let octaves = (frame_rate * 60.0 / lag / PREFERRED_BPM).log2();
+        (-0.5 * octaves * octaves).exp()
+    };
+
+    let (lag, score) = (min_lag..=max_lag)
+        .map(|lag| (lag, correlation(lag) * prior(lag as f32)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+    if score <= 0.0 {
+        return None;
+    }
+
+    // Peak lies between the lags
+    let (before, peak, after) = (correlation(lag - 1), correlation(lag), correlation(lag + 1));
+    let curvature = before - 2.0 * peak + after;
+
+    let offset = match curvature < 0.0 {
+        true => (0.5 * (before - after) / curvature).clamp(-0.5, 0.5),
+        false => 0.0,
+    };
+
+    Some(frame_rate * 60.0 / (lag as f32 + offset))
+}
+
+/// Key which profile correlates with the chroma the most
+#[inline]
+fn estimate_key(chroma: &[f32; 12]) -> Option<MusicalKey> {
+    if chroma.iter().sum::<f32>() <= f32::EPSILON {
+        return None;
+    }
+
+    (0..12_u8)
+        .flat_map(|tonic| {
+            [(MAJOR_PROFILE, false), (MINOR_PROFILE, true)]
+                .into_iter()
+                .map(move |(profile, is_minor)| {
+                    let rotated = (0..12).map(|i| chroma[(tonic as usize + i) % 12]);
+                    (MusicalKey::new(tonic, is_minor), pearson(rotated, &profile))
+                })
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(key, _)| key)
+}
+
+#[inline]
+fn pearson(xs: impl Iterator<Item = f32> + Clone, ys: &[f32; 12]) -> f32 {
+    let x_mean = xs.clone().sum::<f32>() / 12.0;
+    let y_mean = ys.iter().sum::<f32>() / 12.0;
+
+    let (covariance, x_variance, y_variance) =
+        xs.zip(ys).fold((0.0, 0.0, 0.0), |(c, vx, vy), (x, y)| {
+            let (dx, dy) = (x - x_mean, y - y_mean);
+            (c + dx * dy, vx + dx * dx, vy + dy * dy)
+        });
+
+    covariance / (x_variance * y_variance).sqrt().max(f32::EPSILON)
+}
+
+/// Energy from the average level of the track and
+/// loudness range as spread between 10th and 95th percentiles of gated blocks
+#[inline]
+fn loudness_of(blocks: &[f32]) -> (f32, f32) {
+    let to_db = |mean_square: f32| 10.0 * mean_square.max(1e-10).log10();
+
+    let mean_square = blocks.iter().sum::<f32>() / blocks.len().max(1) as f32;
+    let level = to_db(mean_square);
+    let energy = ((level - ENERGY_FLOOR_DB) / -ENERGY_FLOOR_DB).clamp(0.0, 1.0);
+
+    let mut gated = blocks
+        .iter()
+        .map(|&block| to_db(block))
+        .filter(|&db| db > ABSOLUTE_GATE_DB && db > level + RELATIVE_GATE_DB)
+        .collect::<Vec<_>>();
+
+    if gated.len() < 2 {
+        return (energy, 0.0);
+    }
+
+    gated.sort_by(f32::total_cmp);
+    let percentile = |p: f32| gated[((gated.len() - 1) as f32 * p).round() as usize];
+    (energy, percentile(0.95) - percentile(0.10))
+}
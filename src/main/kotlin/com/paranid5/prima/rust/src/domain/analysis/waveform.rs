@@ -0,0 +1,79 @@
+extern crate rodio;
+
+use rodio::Source;
+
+use std::{path::Path, time::Duration};
+
+use crate::{
+    domain::{
+        audio_player::decoders::track_decoder::TrackDecoder, metadata_reader::MetadataReader,
+    },
+    error::{Error, Result},
+};
+
+/// The most peaks that can be requested for the track
+pub const MAX_WAVEFORM_RESOLUTION: usize = 1 << 16;
+
+/// Minimal and maximal samples of the track's equal parts, scaled to i8.
+/// Peaks are interleaved: `[min 0, max 0, min 1, max 1, ...]`,
+/// so they can be passed to Kotlin as a single byte array
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Waveform {
+    pub peaks: Vec<i8>,
+}
+
+impl Waveform {
+    /// Decodes the whole track and splits it into the given number of parts.
+    /// All channels of the part are taken into account
+    #[inline]
+    pub fn compute(path: &Path, resolution: usize) -> Result<Self> {
+        if !(1..=MAX_WAVEFORM_RESOLUTION).contains(&resolution) {
+            return Err(Error::Config(format!(
+                "Waveform resolution must be in range 1..={}",
+                MAX_WAVEFORM_RESOLUTION
+            )));
+        }
+
+        let decoder = TrackDecoder::open(path)?;
+        let channels = decoder.channels().max(1) as u64;
+
+        // Decoders of compressed formats may not know the length, tags always do
+        let duration = decoder
+            .total_duration()
+            .or_else(|| {
+                MetadataReader::read_metadata(path)
+                    .map(|m| Duration::from_millis(m.duration_millis.max(0) as u64))
+            })
+            .filter(|duration| !duration.is_zero())
+            .ok_or_else(|| Error::Decode(format!("{}: unknown duration", path.display())))?;
+
+        let frames = ((duration.as_secs_f64() * decoder.sample_rate() as f64) as u64).max(1);
+        let mut mins = vec![i16::MAX; resolution];
+        let mut maxs = vec![i16::MIN; resolution];
+
+        for (i, sample) in decoder.enumerate() {
+            let frame = i as u64 / channels;
+            let part = ((frame * resolution as u64 / frames) as usize).min(resolution - 1);
+            mins[part] = mins[part].min(sample);
+            maxs[part] = maxs[part].max(sample);
+        }
+
+        let peaks = mins
+            .into_iter()
+            .zip(maxs)
+            .flat_map(|(min, max)| match min > max {
+                // Part without samples, e.g. when duration in tags is too long
+                true => [0, 0],
+                false => [(min >> 8) as i8, (max >> 8) as i8],
+            })
+            .collect();
+
+        Ok(Self { peaks })
+    }
+
+    /// Number of parts the track is split into
+    #[inline]
+    pub fn get_resolution(&self) -> usize {
+        self.peaks.len() / 2
+    }
+}
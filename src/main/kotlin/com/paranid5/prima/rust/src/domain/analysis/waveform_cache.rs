@@ -0,0 +1,94 @@
+use crate::{data::utils::paths::APP_PATHS, domain::analysis::waveform::Waveform, error::Result};
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// Subdirectory of the cache where computed waveforms are stored
+const WAVEFORMS_CACHE_DIR: &str = "waveforms";
+
+/// Marks cached waveform, so other files are not read as peaks
+const WAVEFORM_MAGIC: &[u8; 4] = b"PRWF";
+
+/// Waveforms that were computed before, kept on disk between sessions.
+/// Entry is a header with the resolution followed by peaks as bytes
+#[derive(Debug)]
+pub struct WaveformCache;
+
+impl WaveformCache {
+    /// Waveform of the track with the given resolution from the application's cache
+    #[inline]
+    pub fn load(track: &Path, resolution: usize) -> Option<Waveform> {
+        Self::load_in(track, resolution, &Self::get_dir())
+    }
+
+    /// Same as [WaveformCache::load], but looks in the given directory
+    #[inline]
+    pub fn load_in(track: &Path, resolution: usize, cache_dir: &Path) -> Option<Waveform> {
+        let data = fs::read(Self::entry_path_of(track, resolution, cache_dir)?).ok()?;
+        let (header, peaks) = data.split_at_checked(8)?;
+
+        let is_valid = &header[0..4] == WAVEFORM_MAGIC
+            && u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize == resolution
+            && peaks.len() == resolution * 2;
+
+        is_valid.then(|| Waveform {
+            peaks: peaks.iter().map(|&b| b as i8).collect(),
+        })
+    }
+
+    /// Stores waveform of the track in the application's cache
+    #[inline]
+    pub fn store(track: &Path, waveform: &Waveform) -> Result<()> {
+        Self::store_in(track, waveform, &Self::get_dir())
+    }
+
+    /// Same as [WaveformCache::store], but stores in the given directory
+    #[inline]
+    pub fn store_in(track: &Path, waveform: &Waveform, cache_dir: &Path) -> Result<()> {
+        let resolution = waveform.get_resolution();
+
+        let Some(path) = Self::entry_path_of(track, resolution, cache_dir) else {
+            return Ok(());
+        };
+
+        let mut data = Vec::with_capacity(8 + waveform.peaks.len());
+        data.extend(WAVEFORM_MAGIC);
+        data.extend((resolution as u32).to_le_bytes());
+        data.extend(waveform.peaks.iter().map(|&p| p as u8));
+
+        fs::create_dir_all(cache_dir)?;
+        fs::write(path, data)?;
+        Ok(())
+    }
+
+    #[inline]
+    fn get_dir() -> PathBuf {
+        APP_PATHS.get_cache_dir().join(WAVEFORMS_CACHE_DIR)
+    }
+
+    /// File of the cached waveform.
+    /// Name changes when the track's file is modified, so stale peaks are not used.
+    /// None if the track can't be read
+    #[inline]
+    fn entry_path_of(track: &Path, resolution: usize, cache_dir: &Path) -> Option<PathBuf> {
+        let metadata = fs::metadata(track).ok()?;
+
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+
+        let mut hasher = DefaultHasher::new();
+        track.hash(&mut hasher);
+        modified.hash(&mut hasher);
+        metadata.len().hash(&mut hasher);
+
+        Some(cache_dir.join(format!("{:016x}_{}.peaks", hasher.finish(), resolution)))
+    }
+}
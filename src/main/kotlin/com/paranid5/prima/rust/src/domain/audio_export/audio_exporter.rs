@@ -0,0 +1,286 @@
+extern crate atomic_float;
+extern crate hound;
+extern crate rodio;
+
+use crate::{
+    domain::{
+        audio_export::flac_writer::FlacWriter,
+        audio_player::{
+            decoders::track_decoder::TrackDecoder, effect_chain::apply_effects,
+            playback_params::PlaybackParams,
+        },
+    },
+    error::{Error, Result},
+};
+
+use atomic_float::AtomicF32;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rodio::{source::UniformSourceIterator, Source};
+
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufWriter, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Cancellation and progress are checked once per this number of samples
+const PROGRESS_UPDATE_SAMPLES: usize = 4096;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    Wav,
+    Flac,
+}
+
+impl ExportFormat {
+    /// Chooses format by the extension of output file
+    #[inline]
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+
+        match extension.as_deref() {
+            Some("wav") => Ok(ExportFormat::Wav),
+            Some("flac") => Ok(ExportFormat::Flac),
+
+            _ => Err(Error::Io(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unsupported export format: {}", path.display()),
+            ))),
+        }
+    }
+}
+
+/// Progress of the running export, shared with the threads
+/// that want to observe or cancel it
+#[derive(Clone, Debug, Default)]
+pub struct ExportTask {
+    progress: Arc<AtomicF32>,
+    is_cancelled: Arc<AtomicBool>,
+}
+
+impl ExportTask {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Exported part in range [0, 1]
+    #[inline]
+    pub fn get_progress(&self) -> f32 {
+        self.progress.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    pub fn cancel(&self) {
+        self.is_cancelled.store(true, Ordering::SeqCst)
+    }
+
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.is_cancelled.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    fn set_progress(&self, progress: f32) {
+        self.progress
+            .store(progress.clamp(0.0, 1.0), Ordering::SeqCst)
+    }
+}
+
+/// Encoder of exported samples
+trait SampleWriter {
+    fn write_sample(&mut self, sample: i16) -> Result<()>;
+    fn finalize(self: Box<Self>) -> Result<()>;
+}
+
+impl SampleWriter for WavWriter<BufWriter<File>> {
+    #[inline]
+    fn write_sample(&mut self, sample: i16) -> Result<()> {
+        Ok(WavWriter::write_sample(self, sample)?)
+    }
+
+    #[inline]
+    fn finalize(self: Box<Self>) -> Result<()> {
+        Ok(WavWriter::finalize(*self)?)
+    }
+}
+
+impl SampleWriter for FlacWriter<BufWriter<File>> {
+    #[inline]
+    fn write_sample(&mut self, sample: i16) -> Result<()> {
+        Ok(FlacWriter::write_sample(self, sample)?)
+    }
+
+    #[inline]
+    fn finalize(self: Box<Self>) -> Result<()> {
+        FlacWriter::finalize(*self)?
+            .into_inner()
+            .map_err(|e| e.into_error())?;
+        Ok(())
+    }
+}
+
+/// Renders tracks with the player's effects into an audio file.
+/// Samples are pulled from the same source chain that is played,
+/// but as fast as possible and into the encoder instead of a sink
+#[derive(Debug)]
+pub struct AudioExporter;
+
+impl AudioExporter {
+    /// Renders all tracks one after another into the single file.
+    /// Format is chosen by the output's extension (WAV or FLAC).
+    /// Channels and sample rate are taken from the first track
+    ///
+    /// # Arguments
+    /// tracks - paths of tracks to render
+    ///
+    /// output - file to create
+    ///
+    /// playback_params - speed, reverb and fade-in to apply
+    ///
+    /// task - receives progress and may cancel the export
+    ///
+    /// # Return
+    /// true if the file was written, false if export was cancelled
+    #[inline]
+    pub fn export(
+        tracks: &[PathBuf],
+        output: &Path,
+        playback_params: &PlaybackParams,
+        task: &ExportTask,
+    ) -> Result<bool> {
+        let format = ExportFormat::from_path(output)?;
+
+        if tracks.is_empty() {
+            return Err(Error::Playback("There are no tracks to export".to_string()));
+        }
+
+        let part_path = Self::part_path_of(output);
+        let result = Self::render(tracks, &part_path, format, playback_params, task);
+
+        match result {
+            Ok(true) => {
+                fs::rename(&part_path, output)?;
+                task.set_progress(1.0);
+                Ok(true)
+            }
+
+            _ => {
+                let _ = fs::remove_file(&part_path);
+                result
+            }
+        }
+    }
+
+    #[inline]
+    fn render(
+        tracks: &[PathBuf],
+        output: &Path,
+        format: ExportFormat,
+        playback_params: &PlaybackParams,
+        task: &ExportTask,
+    ) -> Result<bool> {
+        let speed = playback_params.get_speed();
+        let mut writer: Option<(Box<dyn SampleWriter>, u16, u32)> = None;
+
+        for (index, track) in tracks.iter().enumerate() {
+            let decoder = TrackDecoder::open(track)?;
+
+            let track_duration = decoder.total_duration();
+
+            // Speed changes reported sample rate of the source,
+            // output is resampled back to the track's own rate
+            let (sample_writer, channels, sample_rate) = match writer {
+                Some(ref mut writer) => writer,
+
+                None => {
+                    let channels = decoder.channels();
+                    let sample_rate = decoder.sample_rate();
+                    let sample_writer = Self::create_writer(output, format, channels, sample_rate)?;
+                    writer.insert((sample_writer, channels, sample_rate))
+                }
+            };
+
+            let source = Source::speed(
+                apply_effects(Source::buffered(decoder), playback_params),
+                speed,
+            );
+
+            // Progress inside of the track is unknown if decoder can't tell its length
+            let expected_samples = track_duration
+                .map(|d| d.as_secs_f64() / speed as f64 * *sample_rate as f64 * *channels as f64)
+                .unwrap_or_default();
+
+            let samples = UniformSourceIterator::<_, i16>::new(source, *channels, *sample_rate);
+
+            for (written, sample) in samples.enumerate() {
+                sample_writer.write_sample(sample)?;
+
+                if !written.is_multiple_of(PROGRESS_UPDATE_SAMPLES) {
+                    continue;
+                }
+
+                if task.is_cancelled() {
+                    return Ok(false);
+                }
+
+                let track_progress = match expected_samples > 0.0 {
+                    true => (written as f64 / expected_samples).min(1.0),
+                    false => 0.0,
+                };
+
+                task.set_progress(((index as f64 + track_progress) / tracks.len() as f64) as f32);
+            }
+
+            task.set_progress((index + 1) as f32 / tracks.len() as f32);
+        }
+
+        match writer {
+            None => Ok(false),
+
+            Some((writer, _, _)) => {
+                writer.finalize()?;
+                Ok(!task.is_cancelled())
+            }
+        }
+    }
+
+    #[inline]
+    fn create_writer(
+        output: &Path,
+        format: ExportFormat,
+        channels: u16,
+        sample_rate: u32,
+    ) -> Result<Box<dyn SampleWriter>> {
+        let file = BufWriter::new(File::create(output)?);
+
+        Ok(match format {
+            ExportFormat::Wav => Box::new(WavWriter::new(
+                file,
+                WavSpec {
+                    channels,
+                    sample_rate,
+                    bits_per_sample: 16,
+                    sample_format: SampleFormat::Int,
+                },
+            )?),
+
+            ExportFormat::Flac => Box::new(FlacWriter::new(file, channels, sample_rate)?),
+        })
+    }
+
+    /// Export is written to `<output>.part` and renamed when it's completed,
+    /// so unfinished files never look like finished ones
+    #[inline]
+    fn part_path_of(output: &Path) -> PathBuf {
+        let mut file_name = output.file_name().map(OsString::from).unwrap_or_default();
+
+        file_name.push(".part");
+        output.with_file_name(file_name)
+    }
+}
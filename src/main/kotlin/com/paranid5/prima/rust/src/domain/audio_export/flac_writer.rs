@@ -0,0 +1,424 @@
+use std::io::{Error, ErrorKind, Result, Seek, SeekFrom, Write};
+
+/// Number of inter-channel samples in every frame
+const BLOCK_SIZE: usize = 4096;
+const BITS_PER_SAMPLE: u64 = 16;
+
+/// Offset of STREAMINFO content: `fLaC` marker and metadata block header
+const STREAM_INFO_OFFSET: u64 = 8;
+const STREAM_INFO_LENGTH: usize = 34;
+
+/// Highest order of FLAC's fixed polynomial predictors
+const MAX_FIXED_ORDER: usize = 4;
+const MAX_PARTITION_ORDER: u32 = 8;
+
+/// Rice parameter is stored in 4 bits, 15 is reserved for escape code
+const MAX_RICE_PARAMETER: u32 = 14;
+
+/// Writes 16-bit PCM samples as FLAC stream.
+/// Every channel is stored in the smallest of constant, fixed predictor
+/// (with Rice coded residual) or verbatim subframes
+pub struct FlacWriter<W: Write + Seek> {
+    writer: W,
+    channels: u16,
+    sample_rate: u32,
+    block: Vec<i16>,
+    frame_number: u64,
+    total_samples: u64,
+}
+
+impl<W: Write + Seek> FlacWriter<W> {
+    #[inline]
+    pub fn new(mut writer: W, channels: u16, sample_rate: u32) -> Result<Self> {
+        if !(1..=8).contains(&channels) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("FLAC supports up to 8 channels, got {}", channels),
+            ));
+        }
+
+        if sample_rate == 0 || sample_rate >= 1 << 20 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unsupported FLAC sample rate: {}", sample_rate),
+            ));
+        }
+
+        writer.write_all(b"fLaC")?;
+
+        // Last metadata block, type STREAMINFO
+        writer.write_all(&[0x80])?;
+        writer.write_all(&(STREAM_INFO_LENGTH as u32).to_be_bytes()[1..])?;
+
+        let mut flac_writer = Self {
+            writer,
+            channels,
+            sample_rate,
+            block: Vec::with_capacity(BLOCK_SIZE * channels as usize),
+            frame_number: 0,
+            total_samples: 0,
+        };
+
+        flac_writer.write_stream_info()?;
+        Ok(flac_writer)
+    }
+
+    /// Writes interleaved sample
+    #[inline]
+    pub fn write_sample(&mut self, sample: i16) -> Result<()> {
+        self.block.push(sample);
+
+        if self.block.len() == BLOCK_SIZE * self.channels as usize {
+            self.write_frame()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes remaining samples and updates total length of the stream
+    #[inline]
+    pub fn finalize(mut self) -> Result<W> {
+        if !self.block.is_empty() {
+            self.write_frame()?;
+        }
+
+        self.writer.seek(SeekFrom::Start(STREAM_INFO_OFFSET))?;
+        self.write_stream_info()?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    #[inline]
+    fn write_stream_info(&mut self) -> Result<()> {
+        let mut info = Vec::with_capacity(STREAM_INFO_LENGTH);
+
+        info.extend((BLOCK_SIZE as u16).to_be_bytes());
+        info.extend((BLOCK_SIZE as u16).to_be_bytes());
+
+        // Frame sizes are unknown
+        info.extend([0; 6]);
+
+        let packed = (self.sample_rate as u64) << 44
+            | (self.channels as u64 - 1) << 41
+            | (BITS_PER_SAMPLE - 1) << 36
+            | self.total_samples & 0xF_FFFF_FFFF;
+
+        info.extend(packed.to_be_bytes());
+
+        // MD5 is not calculated
+        info.extend([0; 16]);
+
+        self.writer.write_all(&info)
+    }
+
+    #[inline]
+    fn write_frame(&mut self) -> Result<()> {
+        let channels = self.channels as usize;
+        let block_len = self.block.len() / channels;
+
+        let mut frame = Vec::with_capacity(16 + self.block.len() * 2 + channels);
+
+        // Sync code and fixed block size strategy
+        frame.extend([0xFF, 0xF8]);
+
+        // Block size is stored after the frame number,
+        // sample rate is taken from STREAMINFO
+        frame.push(0x70);
+
+        // Independent channels, 16 bits per sample
+        frame.push(((channels as u8 - 1) << 4) | 0b1000);
+
+        frame.extend(encode_utf8_number(self.frame_number));
+        frame.extend((block_len as u16 - 1).to_be_bytes());
+        frame.push(crc8(&frame));
+
+        let mut bits = BitWriter::new(frame);
+
+        for channel in 0..channels {
+            let samples = self
+                .block
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .map(|&sample| sample as i32)
+                .collect::<Vec<_>>();
+
+            write_subframe(&mut bits, &samples);
+        }
+
+        let mut frame = bits.into_bytes();
+        frame.extend(crc16(&frame).to_be_bytes());
+        self.writer.write_all(&frame)?;
+
+        self.frame_number += 1;
+        self.total_samples += block_len as u64;
+        self.block.clear();
+        Ok(())
+    }
+}
+
+/// Writes the smallest of constant, fixed and verbatim subframes
+#[inline]
+fn write_subframe(bits: &mut BitWriter, samples: &[i32]) {
+    if samples.iter().all(|&sample| sample == samples[0]) {
+        bits.write_bits(0x00, 8);
+        bits.write_bits(samples[0] as u64, BITS_PER_SAMPLE as u32);
+        return;
+    }
+
+    let order = best_fixed_order(samples);
+    let residual = fixed_residual(samples, order);
+    let (partition_order, parameters, residual_bits) =
+        best_rice_partitions(&residual, samples.len(), order);
+
+    let verbatim_bits = samples.len() as u64 * BITS_PER_SAMPLE;
+    let fixed_bits = order as u64 * BITS_PER_SAMPLE + 6 + residual_bits;
+
+    if fixed_bits >= verbatim_bits {
+        bits.write_bits(0x02, 8);
+
+        samples
+            .iter()
+            .for_each(|&sample| bits.write_bits(sample as u64, BITS_PER_SAMPLE as u32));
+
+        return;
+    }
+
+    // Fixed subframe: type 001xxx, where xxx is the order
+    bits.write_bits(((0b001000 | order) << 1) as u64, 8);
+
+    samples[..order]
+        .iter()
+        .for_each(|&sample| bits.write_bits(sample as u64, BITS_PER_SAMPLE as u32));
+
+    // Rice coding with 4-bit parameters
+    bits.write_bits(0b00, 2);
+    bits.write_bits(partition_order as u64, 4);
+
+    let partition_len = samples.len() >> partition_order;
+    let mut residual = residual.iter();
+
+    for (partition, &parameter) in parameters.iter().enumerate() {
+        let len = match partition {
+            0 => partition_len - order,
+            _ => partition_len,
+        };
+
+        bits.write_bits(parameter as u64, 4);
+
+        residual
+            .by_ref()
+            .take(len)
+            .for_each(|&value| bits.write_rice(value, parameter));
+    }
+}
+
+/// Order of the fixed predictor with the smallest sum of absolute residuals
+#[inline]
+fn best_fixed_order(samples: &[i32]) -> usize {
+    (0..=MAX_FIXED_ORDER.min(samples.len() - 1))
+        .min_by_key(|&order| {
+            fixed_residual(samples, order)
+                .iter()
+                .map(|&value| value.unsigned_abs() as u64)
+                .sum::<u64>()
+        })
+        .unwrap_or_default()
+}
+
+/// Difference between samples and the polynomial prediction of the given order
+#[inline]
+fn fixed_residual(samples: &[i32], order: usize) -> Vec<i32> {
+    let residual = (order..samples.len()).map(|i| {
+        let s = |back: usize| samples[i - back];
+
+        match order {
+            0 => s(0),
+            1 => s(0) - s(1),
+            2 => s(0) - 2 * s(1) + s(2),
+            3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+            _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+        }
+    });
+
+    residual.collect()
+}
+
+/// Chooses partition order and Rice parameters of every partition
+/// that give the shortest residual. Lengths are estimated from
+/// the sum of folded residuals, so every partition is checked in O(1)
+///
+/// # Return
+/// Partition order, parameters and size of the coded residual in bits
+#[inline]
+fn best_rice_partitions(residual: &[i32], block_len: usize, order: usize) -> (u32, Vec<u32>, u64) {
+    let mut prefix_sums = Vec::with_capacity(residual.len() + 1);
+    prefix_sums.push(0_u64);
+
+    residual.iter().fold(0, |sum, &value| {
+        let sum = sum + fold_signed(value) as u64;
+        prefix_sums.push(sum);
+        sum
+    });
+
+    let partition_orders = (0..=MAX_PARTITION_ORDER).take_while(|&partition_order| {
+        block_len.is_multiple_of(1 << partition_order) && block_len >> partition_order > order
+    });
+
+    partition_orders
+        .map(|partition_order| {
+            let partition_len = block_len >> partition_order;
+            let mut start = 0;
+            let mut bits = 0;
+
+            let parameters = (0..1 << partition_order)
+                .map(|partition| {
+                    let len = match partition {
+                        0 => partition_len - order,
+                        _ => partition_len,
+                    };
+
+                    let sum = prefix_sums[start + len] - prefix_sums[start];
+                    let (parameter, partition_bits) = best_rice_parameter(sum, len as u64);
+
+                    start += len;
+                    bits += 4 + partition_bits;
+                    parameter
+                })
+                .collect::<Vec<_>>();
+
+            (partition_order, parameters, bits)
+        })
+        .min_by_key(|(_, _, bits)| *bits)
+        .unwrap_or_default()
+}
+
+/// Estimates the best Rice parameter by the mean of folded residuals
+#[inline]
+fn best_rice_parameter(sum: u64, len: u64) -> (u32, u64) {
+    let estimate = |parameter: u32| len * (parameter as u64 + 1) + (sum >> parameter);
+
+    if len == 0 || sum < len {
+        return (0, estimate(0));
+    }
+
+    let parameter = (sum / len).ilog2().min(MAX_RICE_PARAMETER);
+
+    (parameter.saturating_sub(1)..=(parameter + 1).min(MAX_RICE_PARAMETER))
+        .map(|parameter| (parameter, estimate(parameter)))
+        .min_by_key(|(_, bits)| *bits)
+        .unwrap_or((parameter, estimate(parameter)))
+}
+
+/// Maps signed values to unsigned: 0, -1, 1, -2, 2...
+#[inline]
+fn fold_signed(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+/// Packs values of any bit length, most significant bits first
+struct BitWriter {
+    bytes: Vec<u8>,
+    accumulator: u64,
+    accumulated_bits: u32,
+}
+
+impl BitWriter {
+    #[inline]
+    fn new(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            accumulator: 0,
+            accumulated_bits: 0,
+        }
+    }
+
+    /// Writes lowest `bits` bits of value, up to 32 bits at once
+    #[inline]
+    fn write_bits(&mut self, value: u64, bits: u32) {
+        self.accumulator = (self.accumulator << bits) | (value & ((1 << bits) - 1));
+        self.accumulated_bits += bits;
+
+        while self.accumulated_bits >= 8 {
+            self.accumulated_bits -= 8;
+            self.bytes
+                .push((self.accumulator >> self.accumulated_bits) as u8);
+        }
+
+        self.accumulator &= (1 << self.accumulated_bits) - 1;
+    }
+
+    /// Writes quotient in unary code and remainder in `parameter` bits
+    #[inline]
+    fn write_rice(&mut self, value: i32, parameter: u32) {
+        let folded = fold_signed(value);
+        let mut quotient = folded >> parameter;
+
+        while quotient >= 32 {
+            self.write_bits(0, 32);
+            quotient -= 32;
+        }
+
+        self.write_bits(1, quotient + 1);
+        self.write_bits(folded as u64, parameter);
+    }
+
+    /// Pads the last byte with zeros
+    #[inline]
+    fn into_bytes(mut self) -> Vec<u8> {
+        if self.accumulated_bits > 0 {
+            let padding = 8 - self.accumulated_bits;
+            self.write_bits(0, padding);
+        }
+
+        self.bytes
+    }
+}
+
+/// Frame number is coded like UTF-8 characters, but up to 36 bits
+#[inline]
+fn encode_utf8_number(value: u64) -> Vec<u8> {
+    if value < 0x80 {
+        return vec![value as u8];
+    }
+
+    let len = match value {
+        v if v < 0x800 => 2,
+        v if v < 0x10000 => 3,
+        v if v < 0x200000 => 4,
+        v if v < 0x4000000 => 5,
+        v if v < 0x80000000 => 6,
+        _ => 7,
+    };
+
+    let prefix = !(0xFF_u8 >> len);
+    let mut bytes = vec![prefix | (value >> (6 * (len - 1))) as u8];
+
+    bytes.extend(
+        (0..len - 1)
+            .rev()
+            .map(|i| 0x80 | ((value >> (6 * i)) & 0x3F) as u8),
+    );
+    bytes
+}
+
+#[inline]
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ byte, |crc, _| match crc & 0x80 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x07,
+        })
+    })
+}
+
+#[inline]
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| match crc & 0x8000 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x8005,
+        })
+    })
+}
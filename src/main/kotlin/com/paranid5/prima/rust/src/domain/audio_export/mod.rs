@@ -0,0 +1,2 @@
+pub mod audio_exporter;
+pub mod flac_writer;
@@ -0,0 +1,249 @@
+extern crate rodio;
+extern crate yaml_rust;
+
+use rodio::{Sample, Source};
+use std::time::Duration;
+use yaml_rust::{yaml::Hash, Yaml};
+
+/// Segment of the track between points A and B that is played repeatedly
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AbLoop {
+    pub start: Duration,
+    pub end: Duration,
+
+    /// How many times the segment is played before playback
+    /// continues past B. None repeats it until the loop is cleared
+    pub iterations: Option<u32>,
+}
+
+impl AbLoop {
+    /// None if A is not before B or zero iterations are requested
+    #[inline]
+    pub fn new(start: Duration, end: Duration, iterations: Option<u32>) -> Option<Self> {
+        (start < end && iterations != Some(0)).then_some(Self {
+            start,
+            end,
+            iterations,
+        })
+    }
+
+    #[inline]
+    pub fn get_len(&self) -> Duration {
+        self.end - self.start
+    }
+
+    #[inline]
+    pub fn contains(&self, position: Duration) -> bool {
+        self.start <= position && position < self.end
+    }
+
+    /// Whether playback jumps back to A after the given number of jumps
+    #[inline]
+    pub fn has_jumps_left(&self, jumps: u32) -> bool {
+        self.iterations.map(|n| jumps + 1 < n).unwrap_or(true)
+    }
+
+    /// Position after it has passed B and jumped back to A
+    #[inline]
+    pub fn wrap_position(&self, position: Duration) -> Duration {
+        let overshoot = position.saturating_sub(self.end).as_nanos() % self.get_len().as_nanos();
+        self.start + Duration::from_nanos(overshoot as u64)
+    }
+}
+
+impl From<AbLoop> for Yaml {
+    #[inline]
+    fn from(ab_loop: AbLoop) -> Self {
+        let mut hash = Hash::new();
+
+        hash.insert(
+            Self::String("start".to_string()),
+            Self::Integer(ab_loop.start.as_millis() as i64),
+        );
+
+        hash.insert(
+            Self::String("end".to_string()),
+            Self::Integer(ab_loop.end.as_millis() as i64),
+        );
+
+        if let Some(iterations) = ab_loop.iterations {
+            hash.insert(
+                Self::String("iterations".to_string()),
+                Self::Integer(iterations as i64),
+            );
+        }
+
+        Self::Hash(hash)
+    }
+}
+
+impl AbLoop {
+    #[inline]
+    pub fn from_yaml(hash: &Hash) -> Option<Self> {
+        let millis = |key: &str| {
+            hash.get(&Yaml::String(key.to_string()))
+                .and_then(|y| y.as_i64())
+                .and_then(|millis| u64::try_from(millis).ok())
+                .map(Duration::from_millis)
+        };
+
+        let iterations = hash
+            .get(&Yaml::String("iterations".to_string()))
+            .and_then(|y| y.as_i64())
+            .and_then(|n| u32::try_from(n).ok());
+
+        Self::new(millis("start")?, millis("end")?, iterations)
+    }
+}
+
+/// Source that plays the loop's segment repeatedly.
+/// Beginning of the segment is kept as a clone of the buffered source,
+/// so jumps back to A reuse decoded samples and there is no gap
+#[derive(Clone)]
+pub struct AbLoopSource<S> {
+    current: S,
+    at_start: S,
+
+    /// Samples left until B, None when playback goes past B
+    samples_till_end: Option<u64>,
+    loop_samples: u64,
+    jumps_left: Option<u32>,
+}
+
+impl<S> AbLoopSource<S>
+where
+    S: Source + Clone,
+    S::Item: Sample,
+{
+    /// Source of the track that starts at the position.
+    /// Jumps that were already made are not repeated
+    #[inline]
+    pub fn new(source: S, position: Duration, ab_loop: AbLoop, jumps: u32) -> Self {
+        let mut current = source.clone();
+        skip_samples(&mut current, samples(&source, position));
+
+        let mut at_start = source.clone();
+        skip_samples(&mut at_start, samples(&source, ab_loop.start));
+
+        Self::from_positioned(current, at_start, position, ab_loop, jumps)
+    }
+
+    /// Source of the track from sources that are already
+    /// at the position and at A, e.g. seeked by their decoders
+    #[inline]
+    pub fn from_positioned(
+        current: S,
+        at_start: S,
+        position: Duration,
+        ab_loop: AbLoop,
+        jumps: u32,
+    ) -> Self {
+        let count = |duration: Duration| samples(&current, duration);
+        let loop_samples = count(ab_loop.end) - count(ab_loop.start);
+
+        let jumps_left = ab_loop
+            .iterations
+            .map(|n| n.saturating_sub(1).saturating_sub(jumps));
+
+        let samples_till_end = match position < ab_loop.end && jumps_left != Some(0) {
+            true => Some(count(ab_loop.end) - count(position)),
+            false => None,
+        };
+
+        Self {
+            current,
+            at_start,
+            samples_till_end,
+            loop_samples,
+            jumps_left,
+        }
+    }
+
+    #[inline]
+    fn jump_to_start(&mut self) {
+        self.current = self.at_start.clone();
+
+        if let Some(jumps_left) = self.jumps_left.as_mut() {
+            *jumps_left -= 1;
+        }
+
+        self.samples_till_end = match self.jumps_left {
+            Some(0) => None,
+            _ => Some(self.loop_samples),
+        };
+    }
+}
+
+/// Number of interleaved samples that are played during the duration
+#[inline]
+fn samples<S: Source>(source: &S, duration: Duration) -> u64
+where
+    S::Item: Sample,
+{
+    (duration.as_secs_f64() * source.sample_rate() as f64) as u64 * source.channels() as u64
+}
+
+#[inline]
+fn skip_samples<S: Iterator>(source: &mut S, count: u64) {
+    for _ in 0..count {
+        if source.next().is_none() {
+            return;
+        }
+    }
+}
+
+impl<S> Iterator for AbLoopSource<S>
+where
+    S: Source + Clone,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.samples_till_end == Some(0) {
+            self.jump_to_start();
+        }
+
+        if let Some(left) = self.samples_till_end.as_mut() {
+            *left -= 1;
+        }
+
+        self.current.next()
+    }
+}
+
+impl<S> Source for AbLoopSource<S>
+where
+    S: Source + Clone,
+    S::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        // Frame ends at B, so the sink notices the jump's format
+        match (self.current.current_frame_len(), self.samples_till_end) {
+            (_, Some(0)) => self.at_start.current_frame_len(),
+            (Some(len), Some(left)) => Some(len.min(left as usize)),
+            (None, Some(left)) => Some(left as usize),
+            (len, None) => len,
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.current.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.current.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        match self.samples_till_end {
+            Some(_) => None,
+            None => self.current.total_duration(),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+extern crate rodio;
+
+use crate::error::{Error, Result};
+
+use rodio::{
+    cpal::{self, traits::HostTrait},
+    queue::SourcesQueueOutput,
+    Device, DeviceTrait, OutputStream, Sink, Source,
+};
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Amount of audio consumed by null output at once
+const NULL_OUTPUT_CHUNK: Duration = Duration::from_millis(10);
+
+const DEVICE_THREAD_NAME: &str = "prima-audio-output";
+
+/// Where player sends decoded samples
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OutputMode {
+    /// Device with the given name or system's default device if name is absent
+    Device(Option<String>),
+
+    /// Samples are consumed in real time and discarded.
+    /// Used on headless machines and in tests
+    Null,
+}
+
+/// Opened output with the sink that plays into it.
+/// Output is closed when it's dropped, so the player keeps it
+/// while the track is paused or seeked
+pub struct AudioOutput {
+    _stream: OutputStreamKeeper,
+    sink: Arc<Sink>,
+    device_name: Option<String>,
+}
+
+/// Streams are only kept alive, samples are sent through the sink
+#[allow(dead_code)]
+enum OutputStreamKeeper {
+    Device(DeviceOutput),
+    Null(NullOutput),
+}
+
+/// Stream of the device is not thread-safe,
+/// so it's opened and kept on its own thread until the output is dropped
+struct DeviceOutput {
+    _is_alive: mpsc::Sender<()>,
+}
+
+/// Consumes samples of the idle sink on its own thread
+/// at the same pace as the real device would do
+struct NullOutput {
+    is_alive: Arc<AtomicBool>,
+}
+
+impl AudioOutput {
+    /// Opens output for the given mode.
+    /// If the chosen device is not available,
+    /// falls back to the default device or any other working one
+    #[inline]
+    pub fn open(mode: &OutputMode) -> Result<Self> {
+        match mode {
+            OutputMode::Null => Ok(Self::open_null()),
+            OutputMode::Device(name) => Self::open_device(name.as_ref()),
+        }
+    }
+
+    #[inline]
+    fn open_null() -> Self {
+        let (sink, output) = Sink::new_idle();
+
+        Self {
+            _stream: OutputStreamKeeper::Null(NullOutput::start(output)),
+            sink: Arc::new(sink),
+            device_name: None,
+        }
+    }
+
+    #[inline]
+    fn open_device(name: Option<&String>) -> Result<Self> {
+        let name = name.cloned();
+        let (opened_sender, opened) = mpsc::channel();
+        let (is_alive, is_closed) = mpsc::channel::<()>();
+
+        thread::Builder::new()
+            .name(DEVICE_THREAD_NAME.to_string())
+            .spawn(move || match open_device_stream(name.as_ref()) {
+                Err(e) => {
+                    let _ = opened_sender.send(Err(e));
+                }
+
+                Ok((stream, sink, device_name)) => {
+                    let _ = opened_sender.send(Ok((sink, device_name)));
+
+                    // Returns when the output is dropped
+                    let _ = is_closed.recv();
+                    drop(stream)
+                }
+            })?;
+
+        let (sink, device_name) = opened
+            .recv()
+            .map_err(|_| Error::Playback("Audio output has stopped".to_string()))??;
+
+        Ok(Self {
+            _stream: OutputStreamKeeper::Device(DeviceOutput {
+                _is_alive: is_alive,
+            }),
+            sink: Arc::new(sink),
+            device_name,
+        })
+    }
+
+    #[inline]
+    pub fn get_sink(&self) -> Arc<Sink> {
+        self.sink.clone()
+    }
+
+    /// Name of the opened device. Null output has no name
+    #[inline]
+    pub fn get_device_name(&self) -> Option<&String> {
+        self.device_name.as_ref()
+    }
+}
+
+impl NullOutput {
+    #[inline]
+    fn start(mut output: SourcesQueueOutput<f32>) -> Self {
+        let is_alive = Arc::new(AtomicBool::new(true));
+        let is_alive_clone = is_alive.clone();
+
+        thread::spawn(move || {
+            while is_alive_clone.load(Ordering::SeqCst) {
+                let samples_per_second = output.sample_rate() as u128 * output.channels() as u128;
+                let chunk = samples_per_second * NULL_OUTPUT_CHUNK.as_millis() / 1000;
+
+                for _ in 0..chunk.max(1) {
+                    output.next();
+                }
+
+                thread::sleep(NULL_OUTPUT_CHUNK);
+            }
+        });
+
+        Self { is_alive }
+    }
+}
+
+impl Drop for NullOutput {
+    #[inline]
+    fn drop(&mut self) {
+        self.is_alive.store(false, Ordering::SeqCst)
+    }
+}
+
+/// Names of all output devices that are currently connected
+#[inline]
+pub fn output_device_names() -> Vec<String> {
+    cpal::default_host()
+        .output_devices()
+        .map(|devices| devices.filter_map(|d| d.name().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Opens stream of the chosen device.
+/// If it's not available, falls back to the default device or any other working one
+#[inline]
+fn open_device_stream(name: Option<&String>) -> Result<(OutputStream, Sink, Option<String>)> {
+    let host = cpal::default_host();
+
+    let chosen = name.and_then(|name| {
+        let device = find_output_device(name);
+
+        if device.is_none() {
+            eprintln!("Output device {} is not found, default is used", name)
+        }
+
+        device
+    });
+
+    let candidates = chosen
+        .into_iter()
+        .chain(host.default_output_device())
+        .chain(host.output_devices().into_iter().flatten());
+
+    let mut last_error = None;
+
+    for device in candidates {
+        match OutputStream::try_from_device(&device) {
+            Ok((stream, handle)) => {
+                let sink = Sink::try_new(&handle)?;
+                return Ok((stream, sink, device.name().ok()));
+            }
+
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(match last_error {
+        None => Error::Playback("No audio output device is available".to_string()),
+        Some(e) => e.into(),
+    })
+}
+
+#[inline]
+fn find_output_device(name: &str) -> Option<Device> {
+    cpal::default_host()
+        .output_devices()
+        .ok()?
+        .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+}
@@ -8,59 +8,144 @@ extern crate tokio;
 use atomic_float::AtomicF32;
 use futures::future::{AbortHandle, Abortable};
 use futures_timer::Delay;
-use rodio::{source::Buffered, Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use rodio::{Sink, Source};
 use tokio::{sync::RwLock, task::JoinHandle};
 
 use std::{
-    fs::File,
-    io::BufReader,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
     },
+    thread,
     time::Duration,
 };
 
 use crate::{
     data::utils::types::*,
-    domain::audio_player::{
-        playback_params::*, playback_position_controller::PlaybackPositionController, result::*,
+    domain::{
+        audio_player::{
+            ab_loop::{AbLoop, AbLoopSource},
+            audio_output::{output_device_names, AudioOutput, OutputMode},
+            decoders::track_decoder::TrackDecoder,
+            effect_chain::apply_effects_at,
+            live_source::{BoxedSource, LiveSource, LiveSourceHandle},
+            meter_feed::MeterFeed,
+            meter_tap::MeterTap,
+            playback_params::*,
+            playback_position_controller::PlaybackPositionController,
+        },
+        events::{event_bus::EventBus, player_event::PlayerEvent},
+        radio::radio_source::RadioSource,
     },
+    error::{Error, Result},
     PlaylistTrait, TrackTrait,
 };
 
 pub struct AudioPlayer {
     source_path: Option<PathBuf>,
-    playback_data: Option<(Arc<OutputStreamHandle>, Arc<Sink>)>,
+
+    /// Output of the sink, it's kept open while the track is paused or seeked
+    output: Option<AudioOutput>,
+    playback_data: Option<Arc<Sink>>,
+    live_source: Option<LiveSourceHandle>,
+    output_mode: OutputMode,
+    output_device_name: Option<String>,
     total_duration: Duration,
     is_playing: Arc<AtomicBool>,
     playback_params: PlaybackParams,
     playback_position_controller: ARWLock<PlaybackPositionController>,
+    ab_loop_jumps: Arc<AtomicU32>,
+    event_bus: EventBus,
+
+    /// Changed when playback is started, paused or stopped,
+    /// so blocking calls of the previous playback return
+    playback_id: Arc<AtomicU64>,
+
+    /// Levels and spectrum of the played samples
+    meter_feed: Arc<MeterFeed>,
+
+    /// Internet radio that is played instead of the current track
+    stream_url: Option<String>,
+
+    /// Title of the song that the station has announced
+    stream_title: Arc<Mutex<Option<String>>>,
 }
 
+/// Position is published every 4th tick of the position task (~200 ms)
+const POSITION_EVENT_TICKS: u32 = 4;
+
+/// How often connected devices are checked while track is playing
+const OUTPUT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often blocking playback checks that the sink is still played
+const PLAYBACK_WAIT_INTERVAL: Duration = Duration::from_millis(20);
+
 impl AudioPlayer {
     #[inline]
-    pub async fn new(playback_params: PlaybackParams, storage_util: ARWLStorage) -> Self {
+    pub async fn new(
+        playback_params: PlaybackParams,
+        storage_util: ARWLStorage,
+        event_bus: EventBus,
+    ) -> Self {
+        let output_mode =
+            OutputMode::Device(storage_util.read().await.load_output_device().cloned());
+
         AudioPlayer {
             source_path: None,
+            output: None,
             playback_data: None,
+            live_source: None,
+            output_mode,
+            output_device_name: None,
             is_playing: Arc::new(AtomicBool::default()),
             playback_params,
             playback_position_controller: Arc::new(RwLock::new(
                 PlaybackPositionController::default(storage_util).await,
             )),
             total_duration: Duration::default(),
+            ab_loop_jumps: Arc::new(AtomicU32::default()),
+            event_bus,
+            playback_id: Arc::new(AtomicU64::default()),
+            meter_feed: Arc::new(MeterFeed::default()),
+            stream_url: None,
+            stream_title: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Updates playing flag and notifies subscribers if it was changed
     #[inline]
+    fn set_playing(is_playing: &AtomicBool, event_bus: &EventBus, new_state: bool) {
+        if is_playing.swap(new_state, Ordering::SeqCst) != new_state {
+            event_bus.publish(PlayerEvent::StateChanged {
+                is_playing: new_state,
+            })
+        }
+    }
+
+    #[inline]
+    pub fn get_event_bus(&self) -> &EventBus {
+        &self.event_bus
+    }
+
+    #[inline]
+    pub fn get_meter_feed(&self) -> Arc<MeterFeed> {
+        self.meter_feed.clone()
+    }
+
+    /// Advances position while the track is playing.
+    /// Position jumps back to A together with the source of the A-B loop
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
     async fn run_playback_control_task(
         is_playing: Arc<AtomicBool>,
         playback_position_controller: ARWLock<PlaybackPositionController>,
         speed: Arc<AtomicF32>,
         max_duration: Duration,
+        ab_loop: Option<AbLoop>,
+        ab_loop_jumps: Arc<AtomicU32>,
         storage_util: ARWLStorage,
+        event_bus: EventBus,
     ) {
         let (handle, reg) = AbortHandle::new_pair();
         let is_playing_clone = is_playing.clone();
@@ -68,27 +153,50 @@ impl AudioPlayer {
 
         let task = Abortable::new(
             async move {
+                let mut ticks = 0_u32;
+
                 while is_playing_clone.load(Ordering::SeqCst) {
                     Delay::new(Duration::from_millis((50.0) as u64)).await;
 
                     if is_playing_clone.load(Ordering::SeqCst) {
-                        let cur_dur = *position_clone.read().await
+                        let prev_dur = *position_clone.read().await;
+                        let mut cur_dur = prev_dur
                             + Duration::from_millis((50.0 * speed.load(Ordering::SeqCst)) as u64);
 
+                        if let Some(ab_loop) = ab_loop {
+                            let jumps = ab_loop_jumps.load(Ordering::SeqCst);
+
+                            if prev_dur < ab_loop.end
+                                && cur_dur >= ab_loop.end
+                                && ab_loop.has_jumps_left(jumps)
+                            {
+                                cur_dur = ab_loop.wrap_position(cur_dur);
+                                ab_loop_jumps.fetch_add(1, Ordering::SeqCst);
+                            }
+                        }
+
                         if cur_dur > max_duration {
                             *position_clone.write().await = max_duration;
-                            is_playing_clone.store(false, Ordering::SeqCst);
+
+                            event_bus.publish(PlayerEvent::PositionTick {
+                                position: max_duration,
+                            });
+
+                            Self::set_playing(&is_playing_clone, &event_bus, false);
                             break;
                         }
 
                         *position_clone.write().await = cur_dur;
+                        ticks += 1;
+
+                        if ticks.is_multiple_of(POSITION_EVENT_TICKS) {
+                            event_bus.publish(PlayerEvent::PositionTick { position: cur_dur });
+                        }
 
                         storage_util
                             .write()
                             .await
-                            .store_current_playback_position(cur_dur.as_millis() as u64)
-                            .await
-                            .unwrap_or_default();
+                            .store_current_playback_position(cur_dur.as_millis() as u64);
                     }
                 }
             },
@@ -99,11 +207,10 @@ impl AudioPlayer {
         task.await.unwrap_or_default()
     }
 
+    /// Path of the current track.
+    /// If nothing was played yet, it is the current track of the stored playlist
     #[inline]
-    async fn get_buffered_source(
-        this: ARWLPlayer,
-        storage_util: ARWLStorage,
-    ) -> Result<Buffered<Decoder<BufReader<File>>>> {
+    async fn get_source_path(this: ARWLPlayer, storage_util: ARWLStorage) -> Result<PathBuf> {
         if this.read().await.source_path.is_none() {
             this.write().await.source_path = Some(
                 storage_util
@@ -111,40 +218,115 @@ impl AudioPlayer {
                     .await
                     .load_current_playlist()
                     .get_cur_track()
-                    .unwrap()
+                    .ok_or_else(Error::no_current_track)?
                     .get_path()
                     .clone(),
             );
         }
 
-        Ok(Source::buffered(
-            match Decoder::new(BufReader::new(
-                match File::open(this.read().await.source_path.as_ref().unwrap().clone()) {
-                    Ok(x) => x,
-                    Err(_) => return Err(Error::FileOpeningError),
-                },
-            )) {
-                Ok(x) => x,
-                Err(_) => return Err(Error::FileNotSupportedError),
-            },
-        ))
+        Ok(this.read().await.source_path.clone().unwrap())
+    }
+
+    /// A-B loop that is stored for the current track
+    #[inline]
+    async fn load_ab_loop(this: &ARWLPlayer, storage_util: &ARWLStorage) -> Option<AbLoop> {
+        let path = this.read().await.source_path.clone()?;
+        storage_util.read().await.load_ab_loop(&path)
+    }
+
+    /// Track's source with effects, starting at the position.
+    /// Decoder is moved to the position by its own seeking.
+    /// Segment of the A-B loop is repeated by the source itself
+    #[inline]
+    fn build_source(
+        path: &Path,
+        playback_params: &PlaybackParams,
+        position: Duration,
+        ab_loop: Option<AbLoop>,
+        ab_loop_jumps: u32,
+    ) -> Result<BoxedSource> {
+        let source = Source::buffered(TrackDecoder::open_at(path, position)?);
+
+        match ab_loop {
+            None => Ok(Box::new(Source::buffered(apply_effects_at(
+                source,
+                playback_params,
+                position,
+            )))),
+
+            Some(ab_loop) => {
+                let at_start = Source::buffered(TrackDecoder::open_at(path, ab_loop.start)?);
+
+                Ok(Box::new(Source::buffered(apply_effects_at(
+                    AbLoopSource::from_positioned(
+                        source,
+                        at_start,
+                        position,
+                        ab_loop,
+                        ab_loop_jumps,
+                    ),
+                    playback_params,
+                    position,
+                ))))
+            }
+        }
+    }
+
+    /// Appends source to the sink, so it can be replaced while the sink plays it.
+    /// Meter stays in front of the live source, so it keeps measuring after seeks
+    #[inline]
+    fn append_live_source(&mut self, sink: &Sink, source: BoxedSource) {
+        let (source, handle) = LiveSource::new(source);
+        sink.append(MeterTap::new(source, self.meter_feed.clone()));
+        self.live_source = Some(handle);
     }
 
+    /// Replaces source that is played or paused by the sink.
+    /// If the sink has nothing to play, source is appended to it
     #[inline]
+    fn set_live_source(&mut self, sink: &Sink, source: BoxedSource) {
+        match self.live_source.as_ref() {
+            Some(handle) if !sink.empty() => handle.replace(source),
+            _ => self.append_live_source(sink, source),
+        }
+    }
+
+    /// Replaces source of the current sink and plays it
+    #[inline]
+    async fn replace_live_source(this: &ARWLPlayer, source: BoxedSource) -> Result<()> {
+        let mut this = this.write().await;
+        let sink = this
+            .playback_data
+            .clone()
+            .ok_or_else(Error::no_current_track)?;
+
+        this.set_live_source(&sink, source);
+        sink.play();
+        Ok(())
+    }
+
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn run_playback_preparation_tasks(
         is_playing: Arc<AtomicBool>,
         playback_position_controller: ARWLock<PlaybackPositionController>,
         speed: Arc<AtomicF32>,
         max_duration: Duration,
+        ab_loop: Option<AbLoop>,
+        ab_loop_jumps: Arc<AtomicU32>,
         tokio_runtime: TokioRuntime,
         storage_util: ARWLStorage,
+        event_bus: EventBus,
     ) {
         tokio_runtime.spawn(AudioPlayer::run_playback_control_task(
             is_playing,
             playback_position_controller,
             speed,
             max_duration,
+            ab_loop,
+            ab_loop_jumps,
             storage_util,
+            event_bus,
         ));
     }
 
@@ -161,8 +343,58 @@ impl AudioPlayer {
             .unwrap_or_default();
     }
 
+    /// Starts new playback, blocking calls of the previous one return
+    #[inline]
+    fn next_playback_id(&self) -> u64 {
+        self.playback_id.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Blocks until the sink has played everything
+    /// or its playback is paused, stopped or replaced
+    #[inline]
+    fn wait_until_end(sink: &Sink, playback_id: &AtomicU64, id: u64) {
+        while !sink.empty() && playback_id.load(Ordering::SeqCst) == id {
+            thread::sleep(PLAYBACK_WAIT_INTERVAL)
+        }
+    }
+
+    /// Plays the sink from the current position of the track
+    /// and blocks until the track ends or its playback is paused or stopped
+    #[inline]
+    async fn play_sink(
+        this: ARWLPlayer,
+        tokio_runtime: TokioRuntime,
+        storage_util: ARWLStorage,
+        sink: Arc<Sink>,
+        ab_loop: Option<AbLoop>,
+    ) {
+        let (playback_id, id) = {
+            let this = this.read().await;
+            sink.set_speed(this.playback_params.get_speed());
+            sink.set_volume(this.playback_params.get_volume());
+            sink.play();
+
+            Self::set_playing(&this.is_playing, &this.event_bus, true);
+            (this.playback_id.clone(), this.next_playback_id())
+        };
+
+        Self::run_playback_preparation_tasks(
+            this.read().await.is_playing.clone(),
+            this.read().await.playback_position_controller.clone(),
+            this.read().await.get_speed_ref(),
+            this.read().await.total_duration,
+            ab_loop,
+            this.read().await.ab_loop_jumps.clone(),
+            tokio_runtime,
+            storage_util,
+            this.read().await.event_bus.clone(),
+        );
+
+        Self::wait_until_end(&sink, &playback_id, id)
+    }
+
     #[inline]
-    async fn play_with_result(
+    pub async fn play(
         this: ARWLPlayer,
         tokio_runtime: TokioRuntime,
         storage_util: ARWLStorage,
@@ -178,63 +410,311 @@ impl AudioPlayer {
         )
         .await;
 
-        let source = Self::get_buffered_source(this.clone(), storage_util.clone()).await?;
+        let path = Self::get_source_path(this.clone(), storage_util.clone()).await?;
+        let ab_loop = Self::load_ab_loop(&this, &storage_util).await;
 
-        let src = Source::buffered(Source::fade_in(
-            Source::reverb(
-                source,
-                this.read()
-                    .await
-                    .playback_params
-                    .get_reverb()
-                    .get_duration(),
-                this.read()
-                    .await
-                    .playback_params
-                    .get_reverb()
-                    .get_amplitude(),
-            ),
-            this.read().await.playback_params.get_fade_in(),
-        ));
+        let src = Self::build_source(
+            &path,
+            &this.read().await.playback_params,
+            Duration::ZERO,
+            ab_loop,
+            0,
+        )?;
 
-        let (_stream, handle) = OutputStream::try_default().unwrap();
-        let sink = Sink::try_new(&handle).unwrap();
-        this.write().await.playback_data = Some((Arc::new(handle), Arc::new(sink)));
+        let sink =
+            Self::get_or_open_output(&this, tokio_runtime.clone(), storage_util.clone()).await?;
 
-        {
+        this.write().await.set_live_source(&sink, src);
+        Self::play_sink(this, tokio_runtime, storage_util, sink, ab_loop).await;
+        Ok(())
+    }
+
+    /// Plays internet radio instead of the current track.
+    /// Returns when the stream is paused, stopped or lost.
+    /// Position of the track is kept, speed is not applied to live audio
+    #[inline]
+    pub async fn play_stream(
+        this: ARWLPlayer,
+        tokio_runtime: TokioRuntime,
+        storage_util: ARWLStorage,
+        url: String,
+    ) -> Result<()> {
+        let event_bus = this.read().await.event_bus.clone();
+        let source = RadioSource::open(&url, event_bus)?;
+        let title = source.get_title_ref();
+
+        Self::stop(this.clone(), tokio_runtime.clone(), storage_util.clone()).await;
+        let sink = Self::get_or_open_output(&this, tokio_runtime, storage_util).await?;
+
+        let (playback_id, id) = {
+            let mut this = this.write().await;
+
+            let src = Box::new(Source::buffered(apply_effects_at(
+                Source::buffered(source),
+                &this.playback_params,
+                Duration::ZERO,
+            )));
+
+            this.set_live_source(&sink, src);
+            sink.set_speed(1.0);
+            sink.set_volume(this.playback_params.get_volume());
+            sink.play();
+
+            this.stream_url = Some(url);
+            this.stream_title = title;
+            Self::set_playing(&this.is_playing, &this.event_bus, true);
+            (this.playback_id.clone(), this.next_playback_id())
+        };
+
+        Self::wait_until_end(&sink, &playback_id, id);
+
+        // Lost stream leaves nothing to play
+        if playback_id.load(Ordering::SeqCst) == id {
             let this = this.read().await;
-            let speed = this.playback_params.get_speed();
-            let volume = this.playback_params.get_volume();
-            let refer = this.playback_data.as_ref().unwrap().1.clone();
+            Self::set_playing(&this.is_playing, &this.event_bus, false);
+        }
 
-            refer.append(src);
-            refer.set_speed(speed);
-            refer.set_volume(volume);
+        Ok(())
+    }
+
+    /// Sink of the opened output. If there is no output yet, it's opened for the current mode.
+    /// Subscribers are notified when playback moves to another device
+    #[inline]
+    async fn get_or_open_output(
+        this: &ARWLPlayer,
+        tokio_runtime: TokioRuntime,
+        storage_util: ARWLStorage,
+    ) -> Result<Arc<Sink>> {
+        if let Some(sink) = this.read().await.playback_data.clone() {
+            return Ok(sink);
         }
 
-        this.read().await.is_playing.store(true, Ordering::SeqCst);
+        let output_mode = this.read().await.output_mode.clone();
+        let output = AudioOutput::open(&output_mode)?;
 
-        Self::run_playback_preparation_tasks(
-            this.read().await.is_playing.clone(),
-            this.read().await.playback_position_controller.clone(),
-            this.read().await.get_speed_ref(),
-            this.read().await.total_duration,
-            tokio_runtime,
-            storage_util,
-        );
+        let mut this_ref = this.write().await;
 
-        let refer = {
-            let this = this.read().await;
-            this.playback_data.as_ref().unwrap().1.clone()
+        // Output may be opened by another call in the meantime
+        if let Some(sink) = this_ref.playback_data.clone() {
+            return Ok(sink);
+        }
+
+        if this_ref.output_device_name.as_ref() != output.get_device_name() {
+            this_ref.output_device_name = output.get_device_name().cloned();
+
+            this_ref
+                .event_bus
+                .publish(PlayerEvent::OutputDeviceChanged {
+                    device: this_ref.output_device_name.clone(),
+                })
+        }
+
+        let sink = output.get_sink();
+        Self::run_output_watch_task(this.clone(), tokio_runtime, storage_util, &output);
+
+        this_ref.playback_data = Some(sink.clone());
+        this_ref.output = Some(output);
+        Ok(sink)
+    }
+
+    /// Stops the sink and closes its output, the next playback opens it again
+    #[inline]
+    fn close_output(&mut self) {
+        self.next_playback_id();
+
+        if let Some(sink) = self.playback_data.take() {
+            sink.stop()
+        }
+
+        self.output = None;
+        self.live_source = None;
+    }
+
+    /// Pauses the sink, its output stays open.
+    /// Source of the track is kept, so it's resumed by seeking it,
+    /// while the stream is dropped and is connected again on resume
+    #[inline]
+    fn pause_sink(&mut self) {
+        self.next_playback_id();
+
+        let Some(sink) = &self.playback_data else {
+            return;
+        };
+
+        match self.stream_url {
+            None => sink.pause(),
+
+            Some(_) => {
+                sink.stop();
+                self.live_source = None;
+            }
+        }
+    }
+
+    /// Watches that the device of the given output is still connected.
+    /// When device disappears, playback continues on another device
+    /// or is paused if there are no devices left
+    #[inline]
+    fn run_output_watch_task(
+        this: ARWLPlayer,
+        tokio_runtime: TokioRuntime,
+        storage_util: ARWLStorage,
+        output: &AudioOutput,
+    ) {
+        let device_name = match output.get_device_name() {
+            None => return,
+            Some(name) => name.clone(),
+        };
+
+        let sink = output.get_sink();
+
+        tokio_runtime.clone().spawn(async move {
+            loop {
+                Delay::new(OUTPUT_CHECK_INTERVAL).await;
+
+                let (is_current_sink, is_playing) = {
+                    let this = this.read().await;
+
+                    let is_current_sink = this
+                        .playback_data
+                        .as_ref()
+                        .map(|cur| Arc::ptr_eq(cur, &sink))
+                        .unwrap_or(false);
+
+                    (is_current_sink, this.is_playing())
+                };
+
+                if !is_current_sink {
+                    return;
+                }
+
+                if !is_playing || output_device_names().contains(&device_name) {
+                    continue;
+                }
+
+                eprintln!("Output device {} is disconnected", device_name);
+
+                if output_device_names().is_empty() {
+                    Self::pause(this.clone(), tokio_runtime, storage_util).await;
+
+                    this.read().await.event_bus.publish(PlayerEvent::Error {
+                        message: "Audio output device is disconnected".to_string(),
+                    });
+                } else {
+                    Self::restart_playback(this, tokio_runtime, storage_util).await;
+                }
+
+                return;
+            }
+        });
+    }
+
+    /// Reopens output and continues current track from the same position.
+    /// Playback is blocking, so it is continued on a separate thread
+    #[inline]
+    async fn restart_playback(
+        this: ARWLPlayer,
+        tokio_runtime: TokioRuntime,
+        storage_util: ARWLStorage,
+    ) {
+        let (position, total_duration, stream_url) = {
+            let mut this = this.write().await;
+            this.close_output();
+
+            (
+                this.get_cur_playback_pos().await,
+                this.total_duration,
+                this.stream_url.clone(),
+            )
         };
 
-        Ok(refer.sleep_until_end())
+        let runtime = tokio_runtime.clone();
+
+        tokio_runtime.spawn_blocking(move || {
+            let handle = runtime.handle().clone();
+
+            let result = match stream_url {
+                Some(url) => {
+                    handle.block_on(Self::play_stream(this.clone(), runtime, storage_util, url))
+                }
+
+                None => handle.block_on(Self::seek_to(
+                    this.clone(),
+                    runtime,
+                    storage_util,
+                    position,
+                    total_duration,
+                )),
+            };
+
+            if let Err(e) = result {
+                eprintln!("Unable to continue playback: {}", e);
+                handle.block_on(async {
+                    this.read().await.event_bus.publish(PlayerEvent::Error {
+                        message: e.to_string(),
+                    })
+                })
+            }
+        });
+    }
+
+    /// Changes where samples are sent.
+    /// If track is playing, it continues on the new output,
+    /// otherwise the new output is opened by the next playback
+    #[inline]
+    pub async fn set_output_mode(
+        this: ARWLPlayer,
+        tokio_runtime: TokioRuntime,
+        storage_util: ARWLStorage,
+        output_mode: OutputMode,
+    ) {
+        let is_playing = {
+            let mut this = this.write().await;
+
+            if this.output_mode == output_mode {
+                return;
+            }
+
+            this.output_mode = output_mode;
+
+            match this.is_playing() {
+                true => true,
+
+                false => {
+                    this.close_output();
+                    false
+                }
+            }
+        };
+
+        if is_playing {
+            Self::restart_playback(this, tokio_runtime, storage_util).await
+        }
+    }
+
+    #[inline]
+    pub fn get_output_mode(&self) -> &OutputMode {
+        &self.output_mode
+    }
+
+    /// Device that is used by current or last played track
+    #[inline]
+    pub fn get_output_device_name(&self) -> Option<&String> {
+        self.output_device_name.as_ref()
+    }
+
+    /// Sink of the opened output, it's kept while the track is paused or seeked
+    #[inline]
+    pub fn get_sink(&self) -> Option<Arc<Sink>> {
+        self.playback_data.clone()
     }
 
     #[inline]
     async fn reset_on_play(this: ARWLPlayer, source: PathBuf, track_duration: Duration) {
+        this.write().await.stream_url = None;
         this.write().await.source_path = Some(source);
         this.write().await.total_duration = track_duration;
+        this.read().await.reset_ab_loop_jumps();
 
         *this
             .write()
@@ -247,17 +727,23 @@ impl AudioPlayer {
             .await = Duration::default()
     }
 
+    /// Moves position of the stopped track to its beginning
     #[inline]
-    pub async fn play(
-        this: ARWLPlayer,
-        tokio_runtime: TokioRuntime,
-        storage_util: ARWLStorage,
-        source: PathBuf,
-        track_duration: Duration,
-    ) {
-        AudioPlayer::play_with_result(this, tokio_runtime, storage_util, source, track_duration)
+    pub async fn rewind(this: ARWLPlayer, storage_util: ARWLStorage) {
+        *this
+            .read()
             .await
-            .unwrap_or_default()
+            .playback_position_controller
+            .read()
+            .await
+            .position
+            .write()
+            .await = Duration::default();
+
+        storage_util
+            .write()
+            .await
+            .store_current_playback_position(0)
     }
 
     #[inline]
@@ -273,36 +759,34 @@ impl AudioPlayer {
                 .write()
                 .await
                 .store_current_playback_position(pos)
-                .await
-                .unwrap_or_default()
         })
     }
 
     #[inline]
     pub async fn pause(this: ARWLPlayer, tokio_runtime: TokioRuntime, storage_util: ARWLStorage) {
         {
-            let this_ref = this.read().await;
-            this_ref.is_playing.store(false, Ordering::SeqCst);
+            let mut this_ref = this.write().await;
+            Self::set_playing(&this_ref.is_playing, &this_ref.event_bus, false);
 
             if let Some(task) = &this_ref.playback_position_controller.read().await.task {
                 task.abort()
             }
 
-            this_ref.playback_data.as_ref().unwrap().1.stop();
+            this_ref.pause_sink();
         }
 
         Self::save_cur_playback_pos_async(this, tokio_runtime, storage_util).await;
     }
 
     #[inline]
-    async fn resume_with_result(
+    pub async fn resume(
         this: ARWLPlayer,
         tokio_runtime: TokioRuntime,
         storage_util: ARWLStorage,
         track_duration: Duration,
     ) -> Result<()> {
         let cur_duration = this.read().await.get_cur_playback_pos().await;
-        Self::seek_to_with_result(
+        Self::seek_to(
             this,
             tokio_runtime,
             storage_util,
@@ -312,133 +796,164 @@ impl AudioPlayer {
         .await
     }
 
+    /// Same as pause: output is kept for the next playback
     #[inline]
-    pub async fn resume(
+    pub async fn stop(this: ARWLPlayer, tokio_runtime: TokioRuntime, storage_util: ARWLStorage) {
+        Self::pause(this, tokio_runtime, storage_util).await
+    }
+
+    /// Plays current track from the position and blocks until it ends.
+    /// Paused source is seeked by its own decoder, so the output is not reopened
+    #[inline]
+    pub async fn seek_to(
         this: ARWLPlayer,
         tokio_runtime: TokioRuntime,
         storage_util: ARWLStorage,
+        position: Duration,
         track_duration: Duration,
-    ) {
-        Self::resume_with_result(this, tokio_runtime, storage_util, track_duration)
-            .await
-            .unwrap_or_default()
-    }
+    ) -> Result<()> {
+        let path = Self::get_source_path(this.clone(), storage_util.clone()).await?;
+        let ab_loop = Self::load_ab_loop(&this, &storage_util).await;
 
-    #[inline]
-    pub async fn stop(this: ARWLPlayer, tokio_runtime: TokioRuntime, storage_util: ARWLStorage) {
-        {
-            let this_ref = this.read().await;
-            this_ref.is_playing.store(false, Ordering::SeqCst);
+        Self::stop(this.clone(), tokio_runtime.clone(), storage_util.clone()).await;
+        this.write().await.total_duration = track_duration;
+        this.write().await.stream_url = None;
 
-            if let Some(task) = &this_ref.playback_position_controller.read().await.task {
-                task.abort()
-            }
+        let is_seeked = Self::seek_live(
+            this.clone(),
+            tokio_runtime.clone(),
+            storage_util.clone(),
+            position,
+        )
+        .await?;
 
-            this_ref
-                .playback_data
-                .as_ref()
-                .map(|pd| pd.1.stop())
-                .unwrap_or_default();
+        let sink =
+            Self::get_or_open_output(&this, tokio_runtime.clone(), storage_util.clone()).await?;
+
+        if !is_seeked {
+            let src = Self::build_source(
+                &path,
+                &this.read().await.playback_params,
+                position,
+                ab_loop,
+                this.read().await.get_ab_loop_jumps(),
+            )?;
+
+            this.write().await.set_live_source(&sink, src);
+
+            *this
+                .write()
+                .await
+                .playback_position_controller
+                .write()
+                .await
+                .position
+                .write()
+                .await = position;
         }
 
-        Self::save_cur_playback_pos_async(this, tokio_runtime, storage_util).await;
+        Self::save_cur_playback_pos_async(
+            this.clone(),
+            tokio_runtime.clone(),
+            storage_util.clone(),
+        )
+        .await
+        .await
+        .unwrap_or_default();
+
+        Self::play_sink(this, tokio_runtime, storage_util, sink, ab_loop).await;
+        Ok(())
     }
 
+    /// Moves the playing or paused track to the position without stopping the sink:
+    /// decoder seeks by itself and its source replaces the one in the sink.
+    /// Returns false if the sink has nothing to seek, then playback has to be started again
     #[inline]
-    async fn seek_to_with_result(
+    pub async fn seek_live(
         this: ARWLPlayer,
         tokio_runtime: TokioRuntime,
         storage_util: ARWLStorage,
         position: Duration,
-        track_duration: Duration,
-    ) -> Result<()> {
-        let src = Self::get_buffered_source(this.clone(), storage_util.clone()).await?;
-
-        let src = Source::buffered(Source::skip_duration(
-            Source::fade_in(
-                Source::reverb(
-                    src,
-                    this.read()
-                        .await
-                        .playback_params
-                        .get_reverb()
-                        .get_duration(),
-                    this.read()
-                        .await
-                        .playback_params
-                        .get_reverb()
-                        .get_amplitude(),
-                ),
-                this.read().await.playback_params.get_fade_in(),
-            ),
-            position,
-        ));
+    ) -> Result<bool> {
+        let (handle, path, position, is_playing) = {
+            let this = this.read().await;
 
-        Self::stop(this.clone(), tokio_runtime.clone(), storage_util.clone()).await;
+            if this.stream_url.is_some() {
+                return match this.is_playing() {
+                    true => Err(Error::Playback(
+                        "Internet radio can't be seeked".to_string(),
+                    )),
 
-        let (_stream, handle) = OutputStream::try_default().unwrap();
-        let sink = Sink::try_new(&handle).unwrap();
+                    false => Ok(false),
+                };
+            }
 
-        this.write().await.playback_data = Some((Arc::new(handle), Arc::new(sink)));
-        this.write().await.total_duration = track_duration;
+            match (&this.live_source, &this.source_path) {
+                (Some(handle), Some(path)) if !this.is_done() => (
+                    handle.clone(),
+                    path.clone(),
+                    position.min(this.total_duration),
+                    this.is_playing(),
+                ),
 
-        {
-            let this = this.read().await;
-            let speed = this.playback_params.get_speed();
-            let volume = this.playback_params.get_volume();
-            let refer = this.playback_data.as_ref().unwrap().1.clone();
+                _ => return Ok(false),
+            }
+        };
 
-            refer.append(src);
-            refer.set_speed(speed);
-            refer.set_volume(volume);
-        }
+        let ab_loop = Self::load_ab_loop(&this, &storage_util).await;
+
+        let src = Self::build_source(
+            &path,
+            &this.read().await.playback_params,
+            position,
+            ab_loop,
+            this.read().await.get_ab_loop_jumps(),
+        )?;
 
+        // Position task is restarted, so it doesn't overwrite the new position
         Self::abort_playback_position_controller_tasks(this.clone()).await;
-        this.read().await.is_playing.store(true, Ordering::SeqCst);
+        handle.replace(src);
 
         *this
-            .write()
+            .read()
             .await
             .playback_position_controller
-            .write()
+            .read()
             .await
             .position
             .write()
             .await = position;
 
-        Self::run_playback_preparation_tasks(
-            this.read().await.is_playing.clone(),
-            this.read().await.playback_position_controller.clone(),
-            this.read().await.get_speed_ref(),
-            this.read().await.total_duration,
-            tokio_runtime.clone(),
-            storage_util.clone(),
-        );
+        if is_playing {
+            Self::run_playback_preparation_tasks(
+                this.read().await.is_playing.clone(),
+                this.read().await.playback_position_controller.clone(),
+                this.read().await.get_speed_ref(),
+                this.read().await.total_duration,
+                ab_loop,
+                this.read().await.ab_loop_jumps.clone(),
+                tokio_runtime,
+                storage_util.clone(),
+                this.read().await.event_bus.clone(),
+            );
+        }
 
-        Self::save_cur_playback_pos_async(this.clone(), tokio_runtime, storage_util)
-            .await
+        storage_util
+            .write()
             .await
-            .unwrap_or_default();
+            .store_current_playback_position(position.as_millis() as u64);
 
-        let refer = {
-            let this = this.read().await;
-            this.playback_data.as_ref().unwrap().1.clone()
-        };
+        this.read()
+            .await
+            .event_bus
+            .publish(PlayerEvent::PositionTick { position });
 
-        Ok(refer.sleep_until_end())
+        Ok(true)
     }
 
     #[inline]
-    pub async fn seek_to(
-        this: ARWLPlayer,
-        tokio_runtime: TokioRuntime,
-        storage_util: ARWLStorage,
-        position: Duration,
-        track_duration: Duration,
-    ) {
-        Self::seek_to_with_result(this, tokio_runtime, storage_util, position, track_duration)
-            .await
-            .unwrap_or_default()
+    pub fn get_playback_params(&self) -> &PlaybackParams {
+        &self.playback_params
     }
 
     #[inline]
@@ -476,8 +991,8 @@ impl AudioPlayer {
         this.write().await.playback_params.set_volume(volume);
         let volume = this.read().await.playback_params.get_volume();
 
-        if let Some(ref pd) = this.read().await.playback_data {
-            pd.1.set_volume(volume)
+        if let Some(ref sink) = this.read().await.playback_data {
+            sink.set_volume(volume)
         }
     }
 
@@ -486,11 +1001,46 @@ impl AudioPlayer {
         this.write().await.playback_params.set_speed(speed);
         let speed = this.read().await.playback_params.get_speed();
 
-        if let Some(ref pd) = this.read().await.playback_data {
-            pd.1.set_speed(speed)
+        if let Some(ref sink) = this.read().await.playback_data {
+            sink.set_speed(speed)
         }
     }
 
+    /// Scales volume of the playing track without changing the stored volume,
+    /// e.g. to fade it out. Next tracks are played with the stored volume
+    #[inline]
+    pub async fn set_volume_factor(this: ARWLPlayer, factor: f32) {
+        let this = this.read().await;
+
+        if let Some(ref sink) = this.playback_data {
+            sink.set_volume(this.playback_params.get_volume() * factor.clamp(0.0, 1.0))
+        }
+    }
+
+    /// Pauses playback and makes the track current without playing it,
+    /// so the next resume starts it from the beginning
+    #[inline]
+    pub async fn prepare(
+        this: ARWLPlayer,
+        storage_util: ARWLStorage,
+        source: PathBuf,
+        track_duration: Duration,
+    ) {
+        {
+            let mut this_ref = this.write().await;
+            Self::set_playing(&this_ref.is_playing, &this_ref.event_bus, false);
+            this_ref.pause_sink();
+        }
+
+        Self::abort_playback_position_controller_tasks(this.clone()).await;
+        Self::reset_on_play(this, source, track_duration).await;
+
+        storage_util
+            .write()
+            .await
+            .store_current_playback_position(0)
+    }
+
     #[inline]
     pub async fn set_reverb(
         this: ARWLPlayer,
@@ -511,31 +1061,37 @@ impl AudioPlayer {
             .read()
             .await;
 
-        let src = Self::get_buffered_source(this.clone(), storage_util.clone()).await?;
+        let path = Self::get_source_path(this.clone(), storage_util.clone()).await?;
+        let ab_loop = Self::load_ab_loop(&this, &storage_util).await;
 
-        let src = Source::buffered(Source::skip_duration(
-            Source::reverb(src, reverb.get_duration(), reverb.get_amplitude()),
+        let src = Self::build_source(
+            &path,
+            &this.read().await.playback_params,
             pos,
-        ));
+            ab_loop,
+            this.read().await.get_ab_loop_jumps(),
+        )?;
 
-        this.read()
-            .await
-            .playback_data
-            .as_ref()
-            .unwrap()
-            .1
-            .append(src);
+        Self::replace_live_source(&this, src).await?;
 
-        this.read().await.is_playing.store(true, Ordering::SeqCst);
+        {
+            let this = this.read().await;
+            Self::set_playing(&this.is_playing, &this.event_bus, true);
+        }
 
-        Ok(Self::run_playback_preparation_tasks(
+        Self::run_playback_preparation_tasks(
             this.read().await.is_playing.clone(),
             this.read().await.playback_position_controller.clone(),
             this.read().await.get_speed_ref(),
             this.read().await.total_duration,
+            ab_loop,
+            this.read().await.ab_loop_jumps.clone(),
             tokio_runtime,
             storage_util,
-        ))
+            this.read().await.event_bus.clone(),
+        );
+
+        Ok(())
     }
 
     #[inline]
@@ -558,32 +1114,45 @@ impl AudioPlayer {
             .read()
             .await;
 
-        let src = Source::buffered(Source::skip_duration(
-            Source::fade_in(
-                AudioPlayer::get_buffered_source(this.clone(), storage_util.clone()).await?,
-                fade_in,
-            ),
+        let path = Self::get_source_path(this.clone(), storage_util.clone()).await?;
+        let ab_loop = Self::load_ab_loop(&this, &storage_util).await;
+
+        let src = Self::build_source(
+            &path,
+            &this.read().await.playback_params,
             pos,
-        ));
+            ab_loop,
+            this.read().await.get_ab_loop_jumps(),
+        )?;
 
-        this.read()
-            .await
-            .playback_data
-            .as_ref()
-            .unwrap()
-            .1
-            .append(src);
+        Self::replace_live_source(&this, src).await?;
 
-        this.read().await.is_playing.store(true, Ordering::SeqCst);
+        {
+            let this = this.read().await;
+            Self::set_playing(&this.is_playing, &this.event_bus, true);
+        }
 
-        Ok(Self::run_playback_preparation_tasks(
+        Self::run_playback_preparation_tasks(
             this.read().await.is_playing.clone(),
             this.read().await.playback_position_controller.clone(),
             this.read().await.get_speed_ref(),
             this.read().await.total_duration,
+            ab_loop,
+            this.read().await.ab_loop_jumps.clone(),
             tokio_runtime,
             storage_util,
-        ))
+            this.read().await.event_bus.clone(),
+        );
+
+        Ok(())
+    }
+
+    #[inline]
+    pub async fn set_looping_state(this: ARWLPlayer, looping_state: LoopingState) {
+        this.write()
+            .await
+            .playback_params
+            .set_looping_state(looping_state)
     }
 
     #[inline]
@@ -591,6 +1160,18 @@ impl AudioPlayer {
         this.write().await.playback_params.set_next_looping_state()
     }
 
+    /// How many times playback has jumped from B back to A
+    #[inline]
+    pub fn get_ab_loop_jumps(&self) -> u32 {
+        self.ab_loop_jumps.load(Ordering::SeqCst)
+    }
+
+    /// Iterations of the A-B loop are counted again
+    #[inline]
+    pub fn reset_ab_loop_jumps(&self) {
+        self.ab_loop_jumps.store(0, Ordering::SeqCst)
+    }
+
     #[inline]
     pub fn is_playing(&self) -> bool {
         self.is_playing.load(Ordering::SeqCst)
@@ -601,6 +1182,20 @@ impl AudioPlayer {
         self.source_path.as_ref()
     }
 
+    /// Internet radio that is played or paused instead of the current track
+    #[inline]
+    pub fn get_stream_url(&self) -> Option<&String> {
+        self.stream_url.as_ref()
+    }
+
+    /// Title of the song that is played by the internet radio
+    #[inline]
+    pub fn get_stream_title(&self) -> Option<String> {
+        self.stream_url
+            .as_ref()
+            .and_then(|_| self.stream_title.lock().unwrap().clone())
+    }
+
     #[inline]
     pub async fn get_cur_playback_pos(&self) -> Duration {
         *self
@@ -614,6 +1209,9 @@ impl AudioPlayer {
 
     #[inline]
     pub fn is_done(&self) -> bool {
-        self.playback_data.as_ref().unwrap().1.empty()
+        self.playback_data
+            .as_ref()
+            .map(|sink| sink.empty())
+            .unwrap_or(true)
     }
 }
@@ -0,0 +1,250 @@
+extern crate rodio;
+
+use rodio::Source;
+
+use std::{
+    fs::File,
+    io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom},
+    path::Path,
+    time::Duration,
+};
+
+use crate::error::{Error, Result};
+
+/// Layout of the samples in the SSND chunk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AiffEncoding {
+    BigEndian,
+    LittleEndian,
+    Float,
+}
+
+/// COMM and SSND chunks of AIFF or AIFF-C file
+#[derive(Clone, Debug, PartialEq)]
+pub struct AiffHeader {
+    pub channels: u16,
+    pub frames: u32,
+    pub bits_per_sample: u16,
+    pub sample_rate: u32,
+
+    /// Compression type of AIFF-C, None for plain AIFF
+    pub compression: Option<[u8; 4]>,
+
+    /// Offset of the first sample in the file
+    pub data_start: u64,
+}
+
+impl AiffHeader {
+    #[inline]
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        let mut form = [0_u8; 12];
+        reader.read_exact(&mut form)?;
+
+        let is_aifc = match (&form[0..4], &form[8..12]) {
+            (b"FORM", b"AIFF") => false,
+            (b"FORM", b"AIFC") => true,
+            _ => return Err(Error::Decode("Not an AIFF file".to_string())),
+        };
+
+        let mut comm = None;
+        let mut data_start = None;
+
+        while comm.is_none() || data_start.is_none() {
+            let mut chunk = [0_u8; 8];
+
+            if let Err(e) = reader.read_exact(&mut chunk) {
+                return match e.kind() {
+                    ErrorKind::UnexpectedEof => Err(Error::Decode(
+                        "AIFF file has no COMM or SSND chunk".to_string(),
+                    )),
+                    _ => Err(e.into()),
+                };
+            }
+
+            let len = u32::from_be_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+            let chunk_start = reader.stream_position()?;
+
+            match &chunk[0..4] {
+                b"COMM" => {
+                    let mut data = vec![0_u8; len as usize];
+                    reader.read_exact(&mut data)?;
+                    comm = Some(data);
+                }
+
+                b"SSND" => {
+                    let mut offset = [0_u8; 4];
+                    reader.read_exact(&mut offset)?;
+                    data_start = Some(chunk_start + 8 + u32::from_be_bytes(offset) as u64);
+                }
+
+                _ => {}
+            }
+
+            // Chunks are padded to even length
+            reader.seek(SeekFrom::Start(chunk_start + len + len % 2))?;
+        }
+
+        let comm = comm.unwrap();
+
+        if comm.len() < 18 || (is_aifc && comm.len() < 22) {
+            return Err(Error::Decode("AIFF COMM chunk is too short".to_string()));
+        }
+
+        let sample_rate = extended_to_f64(comm[8..18].try_into().unwrap()) as u32;
+
+        if sample_rate == 0 {
+            return Err(Error::Decode("AIFF sample rate is zero".to_string()));
+        }
+
+        Ok(Self {
+            channels: u16::from_be_bytes([comm[0], comm[1]]),
+            frames: u32::from_be_bytes([comm[2], comm[3], comm[4], comm[5]]),
+            bits_per_sample: u16::from_be_bytes([comm[6], comm[7]]),
+            sample_rate,
+            compression: is_aifc.then(|| comm[18..22].try_into().unwrap()),
+            data_start: data_start.unwrap(),
+        })
+    }
+
+    /// Encoding of uncompressed samples, None if they are compressed
+    #[inline]
+    pub fn get_encoding(&self) -> Option<AiffEncoding> {
+        match self.compression.as_ref().unwrap_or(b"NONE") {
+            b"NONE" | b"twos" => Some(AiffEncoding::BigEndian),
+            b"sowt" => Some(AiffEncoding::LittleEndian),
+            b"fl32" | b"FL32" | b"fl64" | b"FL64" => Some(AiffEncoding::Float),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn get_duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames as f64 / self.sample_rate as f64)
+    }
+}
+
+/// 80-bit IEEE 754 extended precision number, which AIFF uses for sample rate
+#[inline]
+fn extended_to_f64(bytes: [u8; 10]) -> f64 {
+    let exponent = (u16::from_be_bytes([bytes[0], bytes[1]]) & 0x7FFF) as i32;
+    let mantissa = u64::from_be_bytes(bytes[2..10].try_into().unwrap());
+    mantissa as f64 * 2_f64.powi(exponent - 16383 - 63)
+}
+
+/// Decodes uncompressed AIFF and AIFF-C.
+/// Samples are stored uncompressed, so seeking only moves the file's cursor
+pub struct AiffDecoder {
+    reader: BufReader<File>,
+    header: AiffHeader,
+    encoding: AiffEncoding,
+    bytes_per_sample: usize,
+    samples_read: u64,
+}
+
+impl AiffDecoder {
+    #[inline]
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let header = AiffHeader::read(&mut reader)?;
+
+        let encoding = header.get_encoding().ok_or_else(|| {
+            Error::Decode(format!(
+                "{}: compressed AIFF-C is not supported",
+                path.display()
+            ))
+        })?;
+
+        let bytes_per_sample = (header.bits_per_sample as usize).div_ceil(8);
+
+        if bytes_per_sample == 0 || bytes_per_sample > 8 {
+            return Err(Error::Decode(format!(
+                "{}: {} bits per sample are not supported",
+                path.display(),
+                header.bits_per_sample
+            )));
+        }
+
+        reader.seek(SeekFrom::Start(header.data_start))?;
+
+        Ok(Self {
+            reader,
+            header,
+            encoding,
+            bytes_per_sample,
+            samples_read: 0,
+        })
+    }
+
+    #[inline]
+    pub fn seek(&mut self, position: Duration) -> Result<()> {
+        let frame = ((position.as_secs_f64() * self.header.sample_rate as f64) as u64)
+            .min(self.header.frames as u64);
+
+        let sample = frame * self.header.channels as u64;
+        let offset = sample * self.bytes_per_sample as u64;
+
+        self.reader
+            .seek(SeekFrom::Start(self.header.data_start + offset))?;
+
+        self.samples_read = sample;
+        Ok(())
+    }
+
+    #[inline]
+    fn read_sample(&mut self) -> io::Result<i16> {
+        let mut bytes = [0_u8; 8];
+        let bytes = &mut bytes[..self.bytes_per_sample];
+        self.reader.read_exact(bytes)?;
+
+        if self.encoding == AiffEncoding::LittleEndian {
+            bytes.reverse();
+        }
+
+        // Samples are left-justified, so the highest bytes are taken
+        let sample = match (self.encoding, bytes.len()) {
+            (AiffEncoding::Float, 4) => f32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+            (AiffEncoding::Float, 8) => f64::from_be_bytes(bytes.try_into().unwrap()),
+            (AiffEncoding::Float, _) => return Err(ErrorKind::InvalidData.into()),
+            (_, 1) => return Ok((bytes[0] as i8 as i16) << 8),
+            (_, _) => return Ok(i16::from_be_bytes([bytes[0], bytes[1]])),
+        };
+
+        Ok((sample.clamp(-1.0, 1.0) * i16::MAX as f64) as i16)
+    }
+}
+
+impl Iterator for AiffDecoder {
+    type Item = i16;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.samples_read >= self.header.frames as u64 * self.header.channels as u64 {
+            return None;
+        }
+
+        self.samples_read += 1;
+        self.read_sample().ok()
+    }
+}
+
+impl Source for AiffDecoder {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.header.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.header.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.header.get_duration())
+    }
+}
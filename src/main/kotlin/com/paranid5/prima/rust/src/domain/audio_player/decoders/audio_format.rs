@@ -0,0 +1,439 @@
+use std::{
+    fmt::{Display, Formatter},
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::{
+    domain::audio_player::decoders::aiff_decoder::{AiffEncoding, AiffHeader},
+    error::{Error, Result},
+};
+
+/// How many bytes from the beginning of the file are enough to recognize it
+const PROBE_LEN: u64 = 4096;
+
+/// WAV format tags
+const WAV_PCM: u16 = 0x0001;
+const WAV_FLOAT: u16 = 0x0003;
+const WAV_MP3: u16 = 0x0055;
+const WAV_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Container of the audio file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Container {
+    Wav,
+    Aiff,
+    Flac,
+    Ogg,
+    Mp4,
+    Mpeg,
+    Adts,
+    WavPack,
+}
+
+/// Codec of the audio stream inside of the container
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Codec {
+    Pcm,
+    FloatPcm,
+    Flac,
+    Vorbis,
+    Opus,
+    Speex,
+    Aac,
+    Alac,
+    Mp1,
+    Mp2,
+    Mp3,
+    WavPack,
+
+    /// Codec that is recognized only by its id, e.g. WAV format tag or MP4 sample entry
+    Other(String),
+}
+
+/// Container and codec of the audio file, recognized by its content.
+/// The same probe decides which decoder plays the file
+/// and whether the scanner treats it as a track
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AudioFormat {
+    pub container: Container,
+    pub codec: Codec,
+}
+
+impl AudioFormat {
+    #[inline]
+    pub fn new(container: Container, codec: Codec) -> Self {
+        Self { container, codec }
+    }
+
+    /// Recognizes the format by the file's content, extension is not used
+    #[inline]
+    pub fn probe(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut head = read_head(&mut reader, 0)?;
+
+        // MP3 and sometimes FLAC start with ID3 tag, the stream is after it and its padding
+        if let Some(tag_len) = id3_len(&head) {
+            head = read_head(&mut reader, tag_len)?;
+            let start = head.iter().position(|&b| b != 0).unwrap_or(head.len());
+            head.drain(..start);
+        }
+
+        let unknown = || Error::Decode(format!("{}: unknown audio container", path.display()));
+        let head = head.as_slice();
+
+        match head.get(0..4).ok_or_else(unknown)? {
+            b"fLaC" => return Ok(Self::new(Container::Flac, Codec::Flac)),
+            b"wvpk" => return Ok(Self::new(Container::WavPack, Codec::WavPack)),
+            b"OggS" => return Ok(Self::new(Container::Ogg, ogg_codec(head))),
+            b"RIFF" if head.get(8..12) == Some(b"WAVE") => {
+                return Ok(Self::new(
+                    Container::Wav,
+                    wav_codec(head).ok_or_else(unknown)?,
+                ))
+            }
+            b"FORM" => return Ok(Self::new(Container::Aiff, aiff_codec(&mut reader)?)),
+            _ => {}
+        }
+
+        if head.get(4..8) == Some(b"ftyp") {
+            let codec = mp4_codec(&mut reader).ok_or_else(|| {
+                Error::Decode(format!("{}: MP4 container has no audio", path.display()))
+            })?;
+
+            return Ok(Self::new(Container::Mp4, codec));
+        }
+
+        mpeg_format(head).ok_or_else(unknown)
+    }
+
+    /// Recognizes the stream by its first bytes, e.g. internet radio.
+    /// Streams are joined at any moment, so MPEG audio is found only
+    /// if the first frame starts right away
+    #[inline]
+    pub fn probe_stream(head: &[u8]) -> Option<Self> {
+        match head.get(0..4)? {
+            b"OggS" => Some(Self::new(Container::Ogg, ogg_codec(head))),
+            b"fLaC" => Some(Self::new(Container::Flac, Codec::Flac)),
+            _ => mpeg_format(head),
+        }
+    }
+
+    /// Format of the stream by its MIME type, parameters are ignored
+    #[inline]
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        match mime.as_str() {
+            "audio/mpeg" | "audio/mp3" | "audio/mpeg3" | "audio/x-mpeg" => {
+                Some(Self::new(Container::Mpeg, Codec::Mp3))
+            }
+
+            "audio/ogg" | "application/ogg" | "audio/vorbis" | "audio/x-ogg" => {
+                Some(Self::new(Container::Ogg, Codec::Vorbis))
+            }
+
+            "audio/opus" => Some(Self::new(Container::Ogg, Codec::Opus)),
+
+            "audio/aac" | "audio/aacp" | "audio/x-aac" | "audio/x-aacp" => {
+                Some(Self::new(Container::Adts, Codec::Aac))
+            }
+
+            "audio/flac" | "audio/x-flac" => Some(Self::new(Container::Flac, Codec::Flac)),
+            _ => None,
+        }
+    }
+
+    /// Whether the codec in this container can be decoded.
+    /// Opus is recognized, but there is no decoder for it yet
+    #[inline]
+    pub fn is_supported(&self) -> bool {
+        matches!(
+            (self.container, &self.codec),
+            (
+                Container::Wav | Container::Aiff,
+                Codec::Pcm | Codec::FloatPcm
+            ) | (Container::Flac, Codec::Flac)
+                | (Container::Ogg, Codec::Vorbis)
+                | (Container::Mpeg, Codec::Mp1 | Codec::Mp2 | Codec::Mp3)
+                | (Container::Adts, Codec::Aac)
+                | (Container::Mp4, Codec::Aac | Codec::Alac)
+                | (Container::WavPack, Codec::WavPack)
+        )
+    }
+
+    /// Error for the file of this format that can't be played
+    #[inline]
+    pub fn unsupported_error(&self, path: &Path) -> Error {
+        self.unsupported_stream_error(&path.display().to_string())
+    }
+
+    /// Error for the stream of this format that can't be played
+    #[inline]
+    pub fn unsupported_stream_error(&self, name: &str) -> Error {
+        Error::Decode(format!(
+            "{}: no decoder for {} audio in {} container",
+            name, self.codec, self.container
+        ))
+    }
+}
+
+impl Display for Container {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Container::Wav => "WAV",
+            Container::Aiff => "AIFF",
+            Container::Flac => "FLAC",
+            Container::Ogg => "Ogg",
+            Container::Mp4 => "MP4",
+            Container::Mpeg => "MPEG audio",
+            Container::Adts => "ADTS",
+            Container::WavPack => "WavPack",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl Display for Codec {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Codec::Pcm => "PCM",
+            Codec::FloatPcm => "floating point PCM",
+            Codec::Flac => "FLAC",
+            Codec::Vorbis => "Vorbis",
+            Codec::Opus => "Opus",
+            Codec::Speex => "Speex",
+            Codec::Aac => "AAC",
+            Codec::Alac => "ALAC",
+            Codec::Mp1 => "MP1",
+            Codec::Mp2 => "MP2",
+            Codec::Mp3 => "MP3",
+            Codec::WavPack => "WavPack",
+            Codec::Other(name) => name,
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// First bytes of the file after the position
+#[inline]
+fn read_head<R: Read + Seek>(reader: &mut R, pos: u64) -> Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(PROBE_LEN as usize);
+    reader.seek(SeekFrom::Start(pos))?;
+    reader.take(PROBE_LEN).read_to_end(&mut head)?;
+    Ok(head)
+}
+
+/// Length of ID3v2 tag at the file's start with its header and footer
+#[inline]
+fn id3_len(head: &[u8]) -> Option<u64> {
+    if head.get(0..3) != Some(b"ID3") {
+        return None;
+    }
+
+    // Size is synchsafe: 7 bits in every byte
+    let size = head
+        .get(6..10)?
+        .iter()
+        .fold(0_u64, |size, &b| (size << 7) | (b & 0x7F) as u64);
+
+    let footer = if head[5] & 0x10 != 0 { 10 } else { 0 };
+    Some(10 + size + footer)
+}
+
+/// Codec of the first packet of the first Ogg page
+#[inline]
+fn ogg_codec(head: &[u8]) -> Codec {
+    let packet = head
+        .get(26)
+        .and_then(|&segments| head.get(27 + segments as usize..))
+        .unwrap_or_default();
+
+    match packet {
+        p if p.starts_with(b"\x01vorbis") => Codec::Vorbis,
+        p if p.starts_with(b"OpusHead") => Codec::Opus,
+        p if p.starts_with(b"\x7fFLAC") => Codec::Flac,
+        p if p.starts_with(b"Speex   ") => Codec::Speex,
+        _ => Codec::Other("unknown".to_string()),
+    }
+}
+
+/// Codec by the format tag of WAV's fmt chunk
+#[inline]
+fn wav_codec(head: &[u8]) -> Option<Codec> {
+    let mut pos = 12;
+
+    loop {
+        let chunk = head.get(pos..pos + 8)?;
+        let len = u32::from_le_bytes(chunk[4..8].try_into().unwrap()) as usize;
+
+        if &chunk[0..4] == b"fmt " {
+            let fmt = head.get(pos + 8..)?;
+            let tag = u16::from_le_bytes(fmt.get(0..2)?.try_into().unwrap());
+
+            // Extensible format keeps the real tag in the first bytes of its subformat GUID
+            let tag = match tag {
+                WAV_EXTENSIBLE => u16::from_le_bytes(fmt.get(24..26)?.try_into().unwrap()),
+                tag => tag,
+            };
+
+            return Some(match tag {
+                WAV_PCM => Codec::Pcm,
+                WAV_FLOAT => Codec::FloatPcm,
+                WAV_MP3 => Codec::Mp3,
+                tag => Codec::Other(format!("WAV format 0x{:04X}", tag)),
+            });
+        }
+
+        pos += 8 + len + len % 2;
+    }
+}
+
+/// Codec by the compression type of AIFF-C
+#[inline]
+fn aiff_codec<R: Read + Seek>(reader: &mut R) -> Result<Codec> {
+    reader.seek(SeekFrom::Start(0))?;
+    let header = AiffHeader::read(reader)?;
+
+    Ok(match (header.get_encoding(), header.compression) {
+        (Some(AiffEncoding::Float), _) => Codec::FloatPcm,
+        (Some(_), _) => Codec::Pcm,
+        (None, Some(compression)) => {
+            Codec::Other(String::from_utf8_lossy(&compression).to_string())
+        }
+        (None, None) => Codec::Pcm,
+    })
+}
+
+/// Codec of the first sound track of MP4.
+/// Boxes are walked from the file's start, since `moov` may be after the media data
+#[inline]
+fn mp4_codec<R: Read + Seek>(reader: &mut R) -> Option<Codec> {
+    let len = reader.seek(SeekFrom::End(0)).ok()?;
+    let moov = mp4_boxes(reader, 0, len)
+        .into_iter()
+        .find(|b| &b.name == b"moov")?;
+
+    mp4_boxes(reader, moov.start, moov.end)
+        .into_iter()
+        .filter(|b| &b.name == b"trak")
+        .find_map(|trak| {
+            let mdia = mp4_child(reader, &trak, b"mdia")?;
+            let hdlr = mp4_child(reader, &mdia, b"hdlr")?;
+
+            // Version, flags and pre-defined field precede handler type
+            let mut handler = [0_u8; 4];
+            reader.seek(SeekFrom::Start(hdlr.start + 8)).ok()?;
+            reader.read_exact(&mut handler).ok()?;
+
+            if &handler != b"soun" {
+                return None;
+            }
+
+            let minf = mp4_child(reader, &mdia, b"minf")?;
+            let stbl = mp4_child(reader, &minf, b"stbl")?;
+            let stsd = mp4_child(reader, &stbl, b"stsd")?;
+
+            // Version, flags, entry count and size of the first entry precede its type
+            let mut entry = [0_u8; 4];
+            reader.seek(SeekFrom::Start(stsd.start + 12)).ok()?;
+            reader.read_exact(&mut entry).ok()?;
+
+            Some(match &entry {
+                b"mp4a" => Codec::Aac,
+                b"alac" => Codec::Alac,
+                b"Opus" => Codec::Opus,
+                b"fLaC" => Codec::Flac,
+                b".mp3" => Codec::Mp3,
+                entry => Codec::Other(String::from_utf8_lossy(entry).to_string()),
+            })
+        })
+}
+
+/// Box of MP4 file, start and end of its content
+struct Mp4Box {
+    name: [u8; 4],
+    start: u64,
+    end: u64,
+}
+
+/// Boxes between the positions, reading stops at the first broken box
+#[inline]
+fn mp4_boxes<R: Read + Seek>(reader: &mut R, mut pos: u64, end: u64) -> Vec<Mp4Box> {
+    let mut boxes = vec![];
+
+    while pos + 8 <= end {
+        let mut header = [0_u8; 8];
+
+        if reader.seek(SeekFrom::Start(pos)).is_err() || reader.read_exact(&mut header).is_err() {
+            break;
+        }
+
+        let name = header[4..8].try_into().unwrap();
+
+        let (size, header_len) = match u32::from_be_bytes(header[0..4].try_into().unwrap()) {
+            0 => (end - pos, 8),
+
+            1 => {
+                let mut size = [0_u8; 8];
+
+                if reader.read_exact(&mut size).is_err() {
+                    break;
+                }
+
+                (u64::from_be_bytes(size), 16)
+            }
+
+            size => (size as u64, 8),
+        };
+
+        if size < header_len || pos + size > end {
+            break;
+        }
+
+        boxes.push(Mp4Box {
+            name,
+            start: pos + header_len,
+            end: pos + size,
+        });
+
+        pos += size;
+    }
+
+    boxes
+}
+
+#[inline]
+fn mp4_child<R: Read + Seek>(reader: &mut R, parent: &Mp4Box, name: &[u8; 4]) -> Option<Mp4Box> {
+    mp4_boxes(reader, parent.start, parent.end)
+        .into_iter()
+        .find(|b| &b.name == name)
+}
+
+/// MPEG audio or ADTS stream by the header of its first frame
+#[inline]
+fn mpeg_format(head: &[u8]) -> Option<AudioFormat> {
+    let (b0, b1) = (*head.first()?, *head.get(1)?);
+
+    if b0 != 0xFF || b1 & 0xE0 != 0xE0 {
+        return None;
+    }
+
+    match (b1 >> 1) & 0b11 {
+        0b01 => Some(AudioFormat::new(Container::Mpeg, Codec::Mp3)),
+        0b10 => Some(AudioFormat::new(Container::Mpeg, Codec::Mp2)),
+        0b11 => Some(AudioFormat::new(Container::Mpeg, Codec::Mp1)),
+        _ if b1 & 0xF0 == 0xF0 => Some(AudioFormat::new(Container::Adts, Codec::Aac)),
+        _ => None,
+    }
+}
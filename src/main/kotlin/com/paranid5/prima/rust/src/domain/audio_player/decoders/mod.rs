@@ -0,0 +1,8 @@
+pub mod aiff_decoder;
+pub mod audio_format;
+pub mod rewind_reader;
+pub mod stream_decoder;
+pub mod symphonia_decoder;
+pub mod track_decoder;
+pub mod vorbis_decoder;
+pub mod wavpack_decoder;
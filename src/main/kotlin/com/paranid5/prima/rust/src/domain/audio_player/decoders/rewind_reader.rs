@@ -0,0 +1,121 @@
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind, Read, Seek, SeekFrom},
+};
+
+/// Bytes that are kept to be read again
+const REWIND_LIMIT: usize = 64 * 1024;
+
+/// Reader of the stream that can't be seeked, e.g. internet radio,
+/// for decoders that step back a little while they look for frames.
+/// The last read bytes are kept, so seeking back within them is possible,
+/// seeking forward skips bytes. Other seeks fail
+pub struct RewindReader<R> {
+    reader: R,
+    history: VecDeque<u8>,
+
+    /// Bytes of the history that are read again before the reader
+    replayed: usize,
+    position: u64,
+}
+
+impl<R: Read> RewindReader<R> {
+    #[inline]
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            history: VecDeque::with_capacity(REWIND_LIMIT),
+            replayed: 0,
+            position: 0,
+        }
+    }
+
+    #[inline]
+    fn skip(&mut self, mut len: u64) -> io::Result<()> {
+        let mut buf = [0_u8; 4096];
+
+        while len > 0 {
+            let read = self.read(&mut buf[..len.min(4096) as usize])?;
+
+            if read == 0 {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "Stream has ended before the position",
+                ));
+            }
+
+            len -= read as u64;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for RewindReader<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = match self.replayed {
+            0 => {
+                let read = self.reader.read(buf)?;
+                self.history.extend(&buf[..read]);
+
+                let excess = self.history.len().saturating_sub(REWIND_LIMIT);
+                self.history.drain(..excess);
+                read
+            }
+
+            replayed => {
+                let start = self.history.len() - replayed;
+                let len = buf.len().min(replayed);
+
+                self.history
+                    .range(start..start + len)
+                    .zip(buf.iter_mut())
+                    .for_each(|(byte, dst)| *dst = *byte);
+
+                self.replayed -= len;
+                len
+            }
+        };
+
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Read> Seek for RewindReader<R> {
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let offset = match pos {
+            SeekFrom::Current(offset) => offset,
+            SeekFrom::Start(start) => start as i64 - self.position as i64,
+
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    ErrorKind::Unsupported,
+                    "Stream has no end to seek from",
+                ))
+            }
+        };
+
+        match offset {
+            0.. => self.skip(offset as u64)?,
+
+            _ => {
+                let back = offset.unsigned_abs() as usize;
+
+                if self.replayed + back > self.history.len() {
+                    return Err(io::Error::new(
+                        ErrorKind::Unsupported,
+                        "Stream can't be seeked that far back",
+                    ));
+                }
+
+                self.replayed += back;
+                self.position -= back as u64;
+            }
+        }
+
+        Ok(self.position)
+    }
+}
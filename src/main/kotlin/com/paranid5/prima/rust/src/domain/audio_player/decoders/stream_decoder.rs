@@ -0,0 +1,112 @@
+extern crate rodio;
+
+use rodio::Source;
+
+use std::{
+    io::{Cursor, Read},
+    time::Duration,
+};
+
+use crate::{
+    domain::audio_player::decoders::{
+        audio_format::{AudioFormat, Container},
+        rewind_reader::RewindReader,
+        symphonia_decoder::SymphoniaDecoder,
+        vorbis_decoder::VorbisDecoder,
+    },
+    error::{Error, Result},
+};
+
+/// How many bytes of the stream are looked at when its type is unknown
+const PROBE_LEN: usize = 4096;
+
+pub type StreamReader = Box<dyn Read + Send + Sync>;
+
+/// Decoder of the stream that is played while it's received, e.g. internet radio.
+/// Streams can't be seeked
+pub enum StreamDecoder {
+    Symphonia(SymphoniaDecoder),
+    Vorbis(Box<VorbisDecoder<RewindReader<StreamReader>>>),
+}
+
+impl StreamDecoder {
+    /// Format is taken from the content type and is probed if it's unknown.
+    /// Error names the stream, its container and codec if there is no decoder for it
+    #[inline]
+    pub fn open(mut reader: StreamReader, content_type: Option<&str>, name: &str) -> Result<Self> {
+        let format = match content_type.and_then(AudioFormat::from_content_type) {
+            Some(format) => format,
+
+            None => {
+                let mut head = Vec::with_capacity(PROBE_LEN);
+                reader
+                    .by_ref()
+                    .take(PROBE_LEN as u64)
+                    .read_to_end(&mut head)?;
+
+                let format = AudioFormat::probe_stream(&head)
+                    .ok_or_else(|| Error::Decode(format!("{}: unknown audio stream", name)))?;
+
+                reader = Box::new(Cursor::new(head).chain(reader));
+                format
+            }
+        };
+
+        match (format.is_supported(), format.container) {
+            (true, Container::Mpeg) => Ok(Self::Symphonia(SymphoniaDecoder::from_stream(
+                reader, "mp3", name,
+            )?)),
+
+            (true, Container::Adts) => Ok(Self::Symphonia(SymphoniaDecoder::from_stream(
+                reader, "aac", name,
+            )?)),
+
+            (true, Container::Ogg) => Ok(Self::Vorbis(Box::new(
+                VorbisDecoder::from_reader(RewindReader::new(reader))
+                    .map_err(|e| Error::Decode(format!("{}: {}", name, e)))?,
+            ))),
+
+            _ => Err(format.unsupported_stream_error(name)),
+        }
+    }
+}
+
+impl Iterator for StreamDecoder {
+    type Item = i16;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Symphonia(decoder) => decoder.next(),
+            Self::Vorbis(decoder) => decoder.next(),
+        }
+    }
+}
+
+impl Source for StreamDecoder {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        match self {
+            Self::Symphonia(decoder) => decoder.channels(),
+            Self::Vorbis(decoder) => decoder.channels(),
+        }
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        match self {
+            Self::Symphonia(decoder) => decoder.sample_rate(),
+            Self::Vorbis(decoder) => decoder.sample_rate(),
+        }
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,260 @@
+extern crate rodio;
+extern crate symphonia;
+
+use rodio::Source;
+
+use std::{fmt::Display, fs::File, io::Read, path::Path, time::Duration};
+
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions},
+    errors::{Error as SymphoniaError, SeekErrorKind},
+    formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
+    io::{MediaSourceStream, ReadOnlySource},
+    meta::MetadataOptions,
+    probe::Hint,
+    units::Time,
+};
+
+use crate::error::{Error, Result};
+
+/// Decodes formats that are supported by symphonia.
+/// Seeking is done by the demuxer, then the decoder
+/// drops samples between the found packet and the position
+pub struct SymphoniaDecoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    sample_rate: u32,
+    channels: u16,
+    total_duration: Option<Duration>,
+
+    /// Samples of the last decoded packet
+    buffer: Option<SampleBuffer<i16>>,
+    buffer_pos: usize,
+    is_finished: bool,
+}
+
+impl SymphoniaDecoder {
+    #[inline]
+    pub fn open(path: &Path) -> Result<Self> {
+        let stream = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+        let mut hint = Hint::new();
+
+        if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(extension);
+        }
+
+        Self::from_media_source(stream, &hint, &path.display())
+    }
+
+    /// Decodes the stream that can't be seeked, e.g. internet radio.
+    /// Extension of the stream's format helps to find its frames
+    #[inline]
+    pub fn from_stream(
+        reader: impl Read + Send + Sync + 'static,
+        extension: &str,
+        name: &str,
+    ) -> Result<Self> {
+        let stream =
+            MediaSourceStream::new(Box::new(ReadOnlySource::new(reader)), Default::default());
+
+        let mut hint = Hint::new();
+        hint.with_extension(extension);
+        Self::from_media_source(stream, &hint, &name)
+    }
+
+    /// Error messages start with the name of the source
+    #[inline]
+    fn from_media_source(
+        stream: MediaSourceStream,
+        hint: &Hint,
+        path: &impl Display,
+    ) -> Result<Self> {
+        let format = symphonia::default::get_probe()
+            .format(
+                hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(|e| decode_error(path, e))?
+            .format;
+
+        let track = format
+            .default_track()
+            .ok_or_else(|| decode_error(path, "no audio track"))?;
+
+        let params = &track.codec_params;
+
+        let decoder = symphonia::default::get_codecs()
+            .make(params, &DecoderOptions::default())
+            .map_err(|e| decode_error(path, e))?;
+
+        let sample_rate = params
+            .sample_rate
+            .ok_or_else(|| decode_error(path, "unknown sample rate"))?;
+
+        let channels = params.channels.map(|c| c.count() as u16);
+
+        let total_duration = params
+            .n_frames
+            .map(|frames| Duration::from_secs_f64(frames as f64 / sample_rate as f64));
+
+        let mut source = Self {
+            track_id: track.id,
+            format,
+            decoder,
+            sample_rate,
+            channels: channels.unwrap_or_default(),
+            total_duration,
+            buffer: None,
+            buffer_pos: 0,
+            is_finished: false,
+        };
+
+        // MP4 doesn't tell channels of ALAC, they are known from the first decoded packet
+        if channels.is_none() && !source.decode_next_packet() {
+            return Err(decode_error(path, "unknown channels"));
+        }
+
+        Ok(source)
+    }
+
+    #[inline]
+    pub fn seek(&mut self, position: Duration) -> Result<()> {
+        let seek_to = SeekTo::Time {
+            time: Time::from(position.as_secs_f64()),
+            track_id: Some(self.track_id),
+        };
+
+        self.buffer_pos = self.buffer.as_ref().map(|b| b.len()).unwrap_or_default();
+
+        let seeked = match self.format.seek(SeekMode::Accurate, seek_to) {
+            Ok(seeked) => seeked,
+
+            Err(SymphoniaError::SeekError(SeekErrorKind::OutOfRange)) => {
+                self.is_finished = true;
+                return Ok(());
+            }
+
+            Err(e) => return Err(Error::Decode(e.to_string())),
+        };
+
+        self.decoder.reset();
+        self.is_finished = false;
+
+        // Packet starts before the position, samples till it are dropped
+        let lag = seeked.required_ts.saturating_sub(seeked.actual_ts);
+
+        let lag_frames = match self.decoder.codec_params().time_base {
+            Some(time_base) => {
+                let time = time_base.calc_time(lag);
+                ((time.seconds as f64 + time.frac) * self.sample_rate as f64) as u64
+            }
+
+            None => lag,
+        };
+
+        for _ in 0..lag_frames * self.channels as u64 {
+            if self.next().is_none() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decodes the next packet of the track into the buffer.
+    /// Corrupted packets are skipped
+    #[inline]
+    fn decode_next_packet(&mut self) -> bool {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(_) => return false,
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(_) => return false,
+            };
+
+            let channels = decoded.spec().channels.count();
+            let capacity = decoded.capacity() * channels;
+
+            if self.channels == 0 {
+                self.channels = channels as u16;
+            }
+
+            let buffer = match self.buffer.as_mut() {
+                Some(buffer) if buffer.capacity() >= capacity => buffer,
+                _ => self.buffer.insert(SampleBuffer::new(
+                    decoded.capacity() as u64,
+                    *decoded.spec(),
+                )),
+            };
+
+            buffer.copy_interleaved_ref(decoded);
+            self.buffer_pos = 0;
+            return true;
+        }
+    }
+}
+
+#[inline]
+fn decode_error(path: &impl Display, e: impl Display) -> Error {
+    Error::Decode(format!("{}: {}", path, e))
+}
+
+impl Iterator for SymphoniaDecoder {
+    type Item = i16;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_finished {
+            return None;
+        }
+
+        loop {
+            if let Some(buffer) = self.buffer.as_ref() {
+                if let Some(&sample) = buffer.samples().get(self.buffer_pos) {
+                    self.buffer_pos += 1;
+                    return Some(sample);
+                }
+            }
+
+            if !self.decode_next_packet() {
+                self.is_finished = true;
+                return None;
+            }
+        }
+    }
+}
+
+impl Source for SymphoniaDecoder {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+}
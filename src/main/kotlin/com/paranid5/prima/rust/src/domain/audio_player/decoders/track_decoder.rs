@@ -0,0 +1,119 @@
+extern crate rodio;
+
+use rodio::Source;
+
+use std::{path::Path, time::Duration};
+
+use crate::{
+    domain::audio_player::decoders::{
+        aiff_decoder::AiffDecoder,
+        audio_format::{AudioFormat, Container},
+        symphonia_decoder::SymphoniaDecoder,
+        wavpack_decoder::WavPackDecoder,
+    },
+    error::Result,
+};
+
+/// Decoder of the track's file that is chosen by its probed format.
+/// Every format is seeked by its demuxer, without decoding the skipped part
+pub enum TrackDecoder {
+    /// Symphonia counts the header of AIFF's sound chunk as samples,
+    /// so AIFF has its own decoder
+    Aiff(AiffDecoder),
+
+    Symphonia(SymphoniaDecoder),
+
+    /// Symphonia has no WavPack decoder
+    WavPack(WavPackDecoder),
+}
+
+impl TrackDecoder {
+    /// Error names container and codec of the file if there is no decoder for it
+    #[inline]
+    pub fn open(path: &Path) -> Result<Self> {
+        let format = AudioFormat::probe(path)?;
+
+        if !format.is_supported() {
+            return Err(format.unsupported_error(path));
+        }
+
+        match format.container {
+            Container::Aiff => Ok(Self::Aiff(AiffDecoder::open(path)?)),
+            Container::WavPack => Ok(Self::WavPack(WavPackDecoder::open(path)?)),
+            _ => Ok(Self::Symphonia(SymphoniaDecoder::open(path)?)),
+        }
+    }
+
+    /// Opens the decoder and moves it to the position
+    #[inline]
+    pub fn open_at(path: &Path, position: Duration) -> Result<Self> {
+        let mut decoder = Self::open(path)?;
+
+        if !position.is_zero() {
+            decoder.seek(position)?;
+        }
+
+        Ok(decoder)
+    }
+
+    /// Moves decoder to the position, the next sample is played at it
+    #[inline]
+    pub fn seek(&mut self, position: Duration) -> Result<()> {
+        match self {
+            Self::Aiff(decoder) => decoder.seek(position),
+            Self::Symphonia(decoder) => decoder.seek(position),
+            Self::WavPack(decoder) => decoder.seek(position),
+        }
+    }
+}
+
+impl Iterator for TrackDecoder {
+    type Item = i16;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Aiff(decoder) => decoder.next(),
+            Self::Symphonia(decoder) => decoder.next(),
+            Self::WavPack(decoder) => decoder.next(),
+        }
+    }
+}
+
+impl Source for TrackDecoder {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        match self {
+            Self::Aiff(decoder) => decoder.current_frame_len(),
+            Self::Symphonia(decoder) => decoder.current_frame_len(),
+            Self::WavPack(decoder) => decoder.current_frame_len(),
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        match self {
+            Self::Aiff(decoder) => decoder.channels(),
+            Self::Symphonia(decoder) => decoder.channels(),
+            Self::WavPack(decoder) => decoder.channels(),
+        }
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        match self {
+            Self::Aiff(decoder) => decoder.sample_rate(),
+            Self::Symphonia(decoder) => decoder.sample_rate(),
+            Self::WavPack(decoder) => decoder.sample_rate(),
+        }
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        match self {
+            Self::Aiff(decoder) => decoder.total_duration(),
+            Self::Symphonia(decoder) => decoder.total_duration(),
+            Self::WavPack(decoder) => decoder.total_duration(),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+extern crate lewton;
+extern crate rodio;
+
+use lewton::inside_ogg::OggStreamReader;
+use rodio::Source;
+
+use std::{
+    io::{Read, Seek},
+    time::Duration,
+};
+
+use crate::error::{Error, Result};
+
+/// Decodes Ogg Vorbis stream with lewton,
+/// files are decoded by [SymphoniaDecoder](super::symphonia_decoder::SymphoniaDecoder)
+pub struct VorbisDecoder<R: Read + Seek> {
+    reader: OggStreamReader<R>,
+
+    /// Interleaved samples of the last decoded packet
+    packet: Vec<i16>,
+    packet_pos: usize,
+}
+
+impl<R: Read + Seek> VorbisDecoder<R> {
+    /// Reads headers of the Vorbis stream from the reader
+    #[inline]
+    pub fn from_reader(reader: R) -> Result<Self> {
+        let reader = OggStreamReader::new(reader).map_err(|e| Error::Decode(e.to_string()))?;
+
+        Ok(Self {
+            reader,
+            packet: vec![],
+            packet_pos: 0,
+        })
+    }
+}
+
+impl<R: Read + Seek> Iterator for VorbisDecoder<R> {
+    type Item = i16;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(&sample) = self.packet.get(self.packet_pos) {
+                self.packet_pos += 1;
+                return Some(sample);
+            }
+
+            self.packet = self.reader.read_dec_packet_itl().ok()??;
+            self.packet_pos = 0;
+        }
+    }
+}
+
+impl<R: Read + Seek> Source for VorbisDecoder<R> {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.reader.ident_hdr.audio_channels as u16
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.reader.ident_hdr.audio_sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,1057 @@
+extern crate rodio;
+
+use rodio::Source;
+
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind, Read, Seek, SeekFrom},
+    path::Path,
+    time::Duration,
+};
+
+use crate::error::{Error, Result};
+
+/// Flags of the block header
+const BYTES_STORED: u32 = 0x3;
+const MONO_FLAG: u32 = 0x4;
+const HYBRID_FLAG: u32 = 0x8;
+const JOINT_STEREO: u32 = 0x10;
+const FLOAT_DATA: u32 = 0x80;
+const INT32_DATA: u32 = 0x100;
+const INITIAL_BLOCK: u32 = 0x800;
+const FINAL_BLOCK: u32 = 0x1000;
+const SHIFT_LSB: u32 = 13;
+const SHIFT_MASK: u32 = 0x1F << SHIFT_LSB;
+const SRATE_LSB: u32 = 23;
+const SRATE_MASK: u32 = 0xF << SRATE_LSB;
+const FALSE_STEREO: u32 = 0x4000_0000;
+const DSD_FLAG: u32 = 0x8000_0000;
+
+/// Block holds one channel, either mono or both channels are equal
+const MONO_DATA: u32 = MONO_FLAG | FALSE_STEREO;
+
+/// Ids of the metadata sub-blocks
+const ID_UNIQUE: u8 = 0x3F;
+const ID_ODD_SIZE: u8 = 0x40;
+const ID_LARGE: u8 = 0x80;
+const ID_DECORR_TERMS: u8 = 0x2;
+const ID_DECORR_WEIGHTS: u8 = 0x3;
+const ID_DECORR_SAMPLES: u8 = 0x4;
+const ID_ENTROPY_VARS: u8 = 0x5;
+const ID_WV_BITSTREAM: u8 = 0xA;
+const ID_SAMPLE_RATE: u8 = 0x27;
+
+/// Sample rates by their index in the flags, the last index means ID_SAMPLE_RATE
+const SAMPLE_RATES: [u32; 15] = [
+    6000, 8000, 9600, 11025, 12000, 16000, 22050, 24000, 32000, 44100, 48000, 64000, 88200, 96000,
+    192000,
+];
+
+/// Size of the block header, its `ckSize` doesn't count the first 8 bytes
+const HEADER_LEN: usize = 32;
+
+/// Versions of WavPack 4 and 5 streams
+const MIN_VERSION: u16 = 0x402;
+const MAX_VERSION: u16 = 0x410;
+
+/// Terms up to this one predict from the sample that many samples ago
+const MAX_TERM: usize = 8;
+const MAX_TERMS_COUNT: usize = 16;
+
+/// Weight of the cross-channel terms stays within ±1.0
+const MAX_CROSS_WEIGHT: i32 = 1024;
+
+/// Longer unary code of the ones count is continued by the count itself
+const LIMIT_ONES: u32 = 16;
+
+/// Rounding divisors of the adaptive medians
+const MEDIAN_DIVS: [u32; 3] = [128, 64, 32];
+
+/// Stream parameters, read from the blocks of the first frame
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WavPackInfo {
+    pub channels: u16,
+    pub sample_rate: u32,
+
+    /// None if the encoder didn't know the length, e.g. the stream was piped
+    pub total_samples: Option<u64>,
+
+    /// Flags of the first block, e.g. lossy and floating point streams are not decoded
+    flags: u32,
+}
+
+impl WavPackInfo {
+    #[inline]
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        let mut info: Option<Self> = None;
+
+        loop {
+            let block = Block::read(reader)?.ok_or_else(broken_stream)?;
+            let header = &block.header;
+
+            if header.block_samples == 0 {
+                continue;
+            }
+
+            let info = match info.as_mut() {
+                Some(info) => info,
+
+                None => info.insert(Self {
+                    channels: 0,
+                    sample_rate: block.sample_rate()?,
+                    total_samples: header.total_samples,
+                    flags: header.flags,
+                }),
+            };
+
+            info.channels += block.channels();
+
+            if header.flags & FINAL_BLOCK != 0 {
+                return Ok(info.clone());
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_duration(&self) -> Option<Duration> {
+        self.total_samples
+            .map(|samples| Duration::from_secs_f64(samples as f64 / self.sample_rate as f64))
+    }
+
+    /// Reason why the stream can't be decoded, None for lossless integer stream
+    #[inline]
+    fn unsupported_feature(&self) -> Option<&'static str> {
+        unsupported_feature(self.flags)
+    }
+}
+
+/// Decodes lossless WavPack 4 and 5 streams of integer samples.
+/// Every block is decoded on its own, so seeking finds the block
+/// by the headers and decodes only it
+pub struct WavPackDecoder {
+    reader: BufReader<File>,
+    info: WavPackInfo,
+
+    /// Interleaved samples of the last decoded frame
+    buffer: Vec<i16>,
+    buffer_pos: usize,
+}
+
+impl WavPackDecoder {
+    #[inline]
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let info = WavPackInfo::read(&mut reader)
+            .map_err(|e| Error::Decode(format!("{}: {}", path.display(), e)))?;
+
+        if let Some(feature) = info.unsupported_feature() {
+            return Err(Error::Decode(format!(
+                "{}: {} WavPack is not supported",
+                path.display(),
+                feature
+            )));
+        }
+
+        reader.seek(SeekFrom::Start(0))?;
+
+        Ok(Self {
+            reader,
+            info,
+            buffer: vec![],
+            buffer_pos: 0,
+        })
+    }
+
+    /// Finds the frame with the position by the block headers,
+    /// samples of the frame before the position are dropped
+    #[inline]
+    pub fn seek(&mut self, position: Duration) -> Result<()> {
+        let target = (position.as_secs_f64() * self.info.sample_rate as f64) as u64;
+
+        self.reader.seek(SeekFrom::Start(0))?;
+        self.buffer.clear();
+        self.buffer_pos = 0;
+
+        loop {
+            let start = self.reader.stream_position()?;
+
+            let header = match BlockHeader::read(&mut self.reader)? {
+                None => return Ok(()),
+                Some(header) => header,
+            };
+
+            let end = header.block_index + header.block_samples as u64;
+
+            if header.flags & INITIAL_BLOCK != 0 && target < end {
+                self.reader.seek(SeekFrom::Start(start))?;
+
+                if self.decode_next_frame() {
+                    let skipped = target.saturating_sub(header.block_index) as usize;
+                    self.buffer_pos =
+                        (skipped * self.info.channels as usize).min(self.buffer.len());
+                }
+
+                return Ok(());
+            }
+
+            self.reader.seek_relative(header.data_len as i64)?;
+        }
+    }
+
+    /// Decodes the next frame into the buffer.
+    /// Corrupted frames are skipped
+    #[inline]
+    fn decode_next_frame(&mut self) -> bool {
+        loop {
+            match self.read_frame() {
+                Ok(None) | Err(Error::Io(_)) => return false,
+                Err(_) => continue,
+
+                Ok(Some(frame)) => {
+                    self.buffer = frame;
+                    self.buffer_pos = 0;
+                    return true;
+                }
+            }
+        }
+    }
+
+    /// Decodes blocks from the initial to the final one,
+    /// their channels are interleaved in the order of the blocks
+    #[inline]
+    fn read_frame(&mut self) -> Result<Option<Vec<i16>>> {
+        let channels = self.info.channels as usize;
+        let mut frame = vec![];
+        let mut frame_channels = 0;
+
+        loop {
+            let block = match Block::read(&mut self.reader)? {
+                None => return Ok(None),
+                Some(block) => block,
+            };
+
+            let header = &block.header;
+
+            if header.block_samples == 0 {
+                continue;
+            }
+
+            if (header.flags & INITIAL_BLOCK != 0) != (frame_channels == 0) {
+                return Err(broken_stream());
+            }
+
+            let block_channels = block.channels() as usize;
+
+            if frame_channels + block_channels > channels {
+                return Err(broken_stream());
+            }
+
+            if frame.is_empty() {
+                frame = vec![0; header.block_samples as usize * channels];
+            }
+
+            if frame.len() != header.block_samples as usize * channels {
+                return Err(broken_stream());
+            }
+
+            let bits = ((header.flags & BYTES_STORED) + 1) * 8;
+            let samples = block.decode()?;
+
+            for (index, sample) in samples.into_iter().enumerate() {
+                let (pos, channel) = (index / block_channels, index % block_channels);
+                frame[pos * channels + frame_channels + channel] = to_i16(sample, bits);
+            }
+
+            frame_channels += block_channels;
+
+            if header.flags & FINAL_BLOCK != 0 {
+                return match frame_channels == channels {
+                    true => Ok(Some(frame)),
+                    false => Err(broken_stream()),
+                };
+            }
+        }
+    }
+}
+
+impl Iterator for WavPackDecoder {
+    type Item = i16;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(&sample) = self.buffer.get(self.buffer_pos) {
+                self.buffer_pos += 1;
+                return Some(sample);
+            }
+
+            if !self.decode_next_frame() {
+                return None;
+            }
+        }
+    }
+}
+
+impl Source for WavPackDecoder {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.info.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.info.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.info.get_duration()
+    }
+}
+
+/// Header of the block. Block holds one or two channels,
+/// blocks of all channels from the initial to the final one make a frame
+#[derive(Clone, Debug)]
+struct BlockHeader {
+    /// Length of the block after its header
+    data_len: usize,
+    total_samples: Option<u64>,
+
+    /// Index of the block's first sample in the stream
+    block_index: u64,
+    block_samples: u32,
+    flags: u32,
+    crc: u32,
+}
+
+impl BlockHeader {
+    /// Reads the next header, None at the end of the stream
+    #[inline]
+    fn read<R: Read>(reader: &mut R) -> Result<Option<Self>> {
+        let mut header = [0_u8; HEADER_LEN];
+
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+
+        let u32_at = |pos: usize| u32::from_le_bytes(header[pos..pos + 4].try_into().unwrap());
+        let block_len = u32_at(4) as usize + 8;
+        let version = u16::from_le_bytes([header[8], header[9]]);
+
+        if &header[0..4] != b"wvpk" || block_len < HEADER_LEN {
+            return Err(broken_stream());
+        }
+
+        if !(MIN_VERSION..=MAX_VERSION).contains(&version) {
+            return Err(Error::Decode(format!(
+                "WavPack version 0x{:X} is not supported",
+                version
+            )));
+        }
+
+        // Upper byte of 40-bit length is subtracted, so the lower part is never -1
+        let total_samples = match u32_at(12) {
+            u32::MAX => None,
+            low => Some(low as u64 + ((header[11] as u64) << 32) - header[11] as u64),
+        };
+
+        Ok(Some(Self {
+            data_len: block_len - HEADER_LEN,
+            total_samples,
+            block_index: u32_at(16) as u64 + ((header[10] as u64) << 32),
+            block_samples: u32_at(20),
+            flags: u32_at(24),
+            crc: u32_at(28),
+        }))
+    }
+}
+
+/// Block with its metadata sub-blocks
+struct Block {
+    header: BlockHeader,
+    data: Vec<u8>,
+}
+
+impl Block {
+    /// Reads the next block, None at the end of the stream
+    #[inline]
+    fn read<R: Read>(reader: &mut R) -> Result<Option<Self>> {
+        let header = match BlockHeader::read(reader)? {
+            None => return Ok(None),
+            Some(header) => header,
+        };
+
+        let mut data = vec![0; header.data_len];
+
+        match reader.read_exact(&mut data) {
+            Ok(()) => Ok(Some(Self { header, data })),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Channels of the block in the frame, false stereo is decoded as mono
+    #[inline]
+    fn channels(&self) -> u16 {
+        match self.header.flags & MONO_FLAG {
+            0 => 2,
+            _ => 1,
+        }
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> Result<u32> {
+        let index = ((self.header.flags & SRATE_MASK) >> SRATE_LSB) as usize;
+
+        if let Some(&sample_rate) = SAMPLE_RATES.get(index) {
+            return Ok(sample_rate);
+        }
+
+        let content = self
+            .sub_blocks()?
+            .into_iter()
+            .find(|(id, _)| *id == ID_SAMPLE_RATE)
+            .map(|(_, content)| content)
+            .filter(|content| content.len() >= 3)
+            .ok_or_else(|| Error::Decode("WavPack sample rate is unknown".to_string()))?;
+
+        Ok(content
+            .iter()
+            .take(4)
+            .rev()
+            .fold(0, |rate, &b| (rate << 8) | b as u32))
+    }
+
+    /// Ids and contents of the metadata sub-blocks
+    #[inline]
+    fn sub_blocks(&self) -> Result<Vec<(u8, &[u8])>> {
+        let data = self.data.as_slice();
+        let mut sub_blocks = vec![];
+        let mut pos = 0;
+
+        while pos < data.len() {
+            let id = data[pos];
+
+            // Size is counted in 16-bit words
+            let (words, header_len) = match id & ID_LARGE {
+                0 => (*data.get(pos + 1).ok_or_else(broken_stream)? as usize, 2),
+
+                _ => {
+                    let size = data.get(pos + 1..pos + 4).ok_or_else(broken_stream)?;
+                    (
+                        u32::from_le_bytes([size[0], size[1], size[2], 0]) as usize,
+                        4,
+                    )
+                }
+            };
+
+            let start = pos + header_len;
+            let len = (words * 2).saturating_sub((id & ID_ODD_SIZE != 0) as usize);
+            let content = data.get(start..start + len).ok_or_else(broken_stream)?;
+
+            sub_blocks.push((id & ID_UNIQUE, content));
+            pos = start + words * 2;
+        }
+
+        Ok(sub_blocks)
+    }
+
+    /// Decodes samples of the block, channels are interleaved.
+    /// Samples are checked by the block's CRC
+    #[inline]
+    fn decode(&self) -> Result<Vec<i32>> {
+        let flags = self.header.flags;
+
+        if let Some(feature) = unsupported_feature(flags) {
+            return Err(Error::Decode(format!(
+                "{} WavPack is not supported",
+                feature
+            )));
+        }
+
+        let is_mono = flags & MONO_DATA != 0;
+        let channels = if is_mono { 1 } else { 2 };
+
+        let mut passes: Vec<DecorrPass> = vec![];
+        let mut words = None;
+        let mut weights: &[u8] = &[];
+        let mut samples: &[u8] = &[];
+        let mut medians = [[0_u32; 3]; 2];
+
+        for (id, content) in self.sub_blocks()? {
+            match id {
+                ID_DECORR_TERMS => {
+                    if content.len() > MAX_TERMS_COUNT {
+                        return Err(broken_stream());
+                    }
+
+                    passes = content
+                        .iter()
+                        .map(|&b| DecorrPass::new((b & 0x1F) as i32 - 5, ((b >> 5) & 0x7) as i32))
+                        .collect::<Option<_>>()
+                        .ok_or_else(broken_stream)?;
+                }
+
+                ID_DECORR_WEIGHTS => weights = content,
+                ID_DECORR_SAMPLES => samples = content,
+
+                ID_ENTROPY_VARS => {
+                    if content.len() != 6 * channels {
+                        return Err(broken_stream());
+                    }
+
+                    for (index, log) in content.chunks(2).enumerate() {
+                        medians[index / 3][index % 3] =
+                            exp2s(u16::from_le_bytes([log[0], log[1]]) as i32) as u32;
+                    }
+                }
+
+                ID_WV_BITSTREAM => words = Some(WordReader::new(content, medians)),
+                _ => {}
+            }
+        }
+
+        // Medians may follow the bitstream
+        let mut words = words.ok_or_else(broken_stream)?;
+        words.medians = medians;
+
+        read_decorr_weights(&mut passes, weights, is_mono)?;
+        read_decorr_samples(&mut passes, samples, is_mono);
+
+        let mut buffer = (0..self.header.block_samples as usize * channels)
+            .map(|index| words.read_word(index % channels))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(broken_stream)?;
+
+        // Passes are undone in the reverse order of the encoder
+        for pass in passes.iter_mut().rev() {
+            match is_mono {
+                true => pass.decorr_mono(&mut buffer)?,
+                false => pass.decorr_stereo(&mut buffer),
+            }
+        }
+
+        if !is_mono && flags & JOINT_STEREO != 0 {
+            for frame in buffer.chunks_mut(2) {
+                frame[1] = frame[1].wrapping_sub(frame[0] >> 1);
+                frame[0] = frame[0].wrapping_add(frame[1]);
+            }
+        }
+
+        let crc = buffer.iter().fold(u32::MAX, |crc, &sample| {
+            crc.wrapping_mul(3).wrapping_add(sample as u32)
+        });
+
+        if crc != self.header.crc {
+            return Err(Error::Decode("WavPack block has wrong CRC".to_string()));
+        }
+
+        let shift = (flags & SHIFT_MASK) >> SHIFT_LSB;
+
+        if shift > 0 {
+            buffer.iter_mut().for_each(|sample| *sample <<= shift);
+        }
+
+        // False stereo has equal channels, only one is stored
+        if flags & FALSE_STEREO != 0 && flags & MONO_FLAG == 0 {
+            buffer = buffer.into_iter().flat_map(|sample| [sample; 2]).collect();
+        }
+
+        Ok(buffer)
+    }
+}
+
+/// Name of the feature that can't be decoded, None for lossless integer stream
+#[inline]
+fn unsupported_feature(flags: u32) -> Option<&'static str> {
+    match flags {
+        f if f & DSD_FLAG != 0 => Some("DSD"),
+        f if f & HYBRID_FLAG != 0 => Some("lossy (hybrid)"),
+        f if f & FLOAT_DATA != 0 => Some("floating point"),
+        f if f & INT32_DATA != 0 => Some("32-bit integer"),
+        _ => None,
+    }
+}
+
+/// Weights are stored for the first passes, the rest start from zero
+#[inline]
+fn read_decorr_weights(passes: &mut [DecorrPass], weights: &[u8], is_mono: bool) -> Result<()> {
+    let per_pass = if is_mono { 1 } else { 2 };
+
+    if !weights.len().is_multiple_of(per_pass) || weights.len() / per_pass > passes.len() {
+        return Err(broken_stream());
+    }
+
+    for (pass, weights) in passes.iter_mut().zip(weights.chunks(per_pass)) {
+        pass.weight_a = restore_weight(weights[0] as i8);
+        pass.weight_b = weights.get(1).map_or(0, |&w| restore_weight(w as i8));
+    }
+
+    Ok(())
+}
+
+/// History of the first passes, every sample is stored as its logarithm.
+/// Extrapolating terms store both samples of the first channel before the second one,
+/// other terms interleave the channels
+#[inline]
+fn read_decorr_samples(passes: &mut [DecorrPass], samples: &[u8], is_mono: bool) {
+    let channels = if is_mono { 1 } else { 2 };
+
+    let mut logs = samples
+        .chunks_exact(2)
+        .map(|log| exp2s(i16::from_le_bytes([log[0], log[1]]) as i32));
+
+    for pass in passes.iter_mut() {
+        let slots = match pass.term {
+            17 | 18 => (0..channels)
+                .flat_map(|channel| [(channel, 0), (channel, 1)])
+                .collect::<Vec<_>>(),
+
+            term if term < 0 => vec![(0, 0), (1, 0)],
+
+            term => (0..term as usize)
+                .flat_map(|index| (0..channels).map(move |channel| (channel, index)))
+                .collect(),
+        };
+
+        for (channel, index) in slots {
+            let Some(sample) = logs.next() else {
+                return;
+            };
+
+            match channel {
+                0 => pass.samples_a[index] = sample,
+                _ => pass.samples_b[index] = sample,
+            }
+        }
+    }
+}
+
+/// Weight is stored as signed byte with 1.0 = 64
+#[inline]
+fn restore_weight(weight: i8) -> i32 {
+    let weight = (weight as i32) << 3;
+
+    match weight > 0 {
+        true => weight + ((weight + 64) >> 7),
+        false => weight,
+    }
+}
+
+/// Inverse of WavPack's logarithm: 8 bits of fraction, the rest is the exponent
+#[inline]
+fn exp2s(log: i32) -> i32 {
+    if log < 0 {
+        return -exp2s(-log);
+    }
+
+    let fraction = (log & 0xFF) as f64 / 256.0;
+    let value = (256.0 * fraction.exp2()).round() as u32;
+    let exponent = log >> 8;
+
+    match exponent <= 9 {
+        true => (value >> (9 - exponent)) as i32,
+        false => value.checked_shl((exponent - 9) as u32).unwrap_or_default() as i32,
+    }
+}
+
+/// Sample of the given width as 16-bit one
+#[inline]
+fn to_i16(sample: i32, bits: u32) -> i16 {
+    match bits {
+        8 => (sample << 8) as i16,
+        16 => sample as i16,
+        bits => (sample >> (bits - 16)) as i16,
+    }
+}
+
+#[inline]
+fn broken_stream() -> Error {
+    Error::Decode("WavPack stream is broken".to_string())
+}
+
+/// Adaptive prediction from the previous samples of the same or the other channel
+#[derive(Clone, Debug, Default)]
+struct DecorrPass {
+    term: i32,
+    delta: i32,
+    weight_a: i32,
+    weight_b: i32,
+    samples_a: [i32; MAX_TERM],
+    samples_b: [i32; MAX_TERM],
+}
+
+impl DecorrPass {
+    /// Terms 1..8 predict from the sample that many samples ago,
+    /// 17 and 18 extrapolate two previous samples,
+    /// -1..-3 predict from the other channel of stereo
+    #[inline]
+    fn new(term: i32, delta: i32) -> Option<Self> {
+        match term {
+            -3..=-1 | 1..=8 | 17 | 18 => Some(Self {
+                term,
+                delta,
+                ..Self::default()
+            }),
+
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn decorr_mono(&mut self, buffer: &mut [i32]) -> Result<()> {
+        match self.term {
+            17 | 18 => {
+                for sample in buffer.iter_mut() {
+                    let predicted = extrapolate(self.term, &self.samples_a);
+                    self.samples_a[1] = self.samples_a[0];
+                    self.samples_a[0] =
+                        apply_weight(self.weight_a, predicted).wrapping_add(*sample);
+                    update_weight(&mut self.weight_a, self.delta, predicted, *sample);
+                    *sample = self.samples_a[0];
+                }
+            }
+
+            term if term > 0 => {
+                let term = term as usize;
+
+                for (index, sample) in buffer.iter_mut().enumerate() {
+                    let (m, k) = (index % MAX_TERM, (index + term) % MAX_TERM);
+                    let predicted = self.samples_a[m];
+                    self.samples_a[k] =
+                        apply_weight(self.weight_a, predicted).wrapping_add(*sample);
+                    update_weight(&mut self.weight_a, self.delta, predicted, *sample);
+                    *sample = self.samples_a[k];
+                }
+            }
+
+            // Mono has no other channel to predict from
+            _ => return Err(broken_stream()),
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn decorr_stereo(&mut self, buffer: &mut [i32]) {
+        let delta = self.delta;
+
+        for (index, frame) in buffer.chunks_mut(2).enumerate() {
+            match self.term {
+                17 | 18 => {
+                    let predicted_a = extrapolate(self.term, &self.samples_a);
+                    self.samples_a[1] = self.samples_a[0];
+                    self.samples_a[0] =
+                        apply_weight(self.weight_a, predicted_a).wrapping_add(frame[0]);
+                    update_weight(&mut self.weight_a, delta, predicted_a, frame[0]);
+                    frame[0] = self.samples_a[0];
+
+                    let predicted_b = extrapolate(self.term, &self.samples_b);
+                    self.samples_b[1] = self.samples_b[0];
+                    self.samples_b[0] =
+                        apply_weight(self.weight_b, predicted_b).wrapping_add(frame[1]);
+                    update_weight(&mut self.weight_b, delta, predicted_b, frame[1]);
+                    frame[1] = self.samples_b[0];
+                }
+
+                // Left is predicted from the previous right, right from the current left
+                -1 => {
+                    let left =
+                        frame[0].wrapping_add(apply_weight(self.weight_a, self.samples_a[0]));
+                    update_weight_clip(&mut self.weight_a, delta, self.samples_a[0], frame[0]);
+                    frame[0] = left;
+
+                    self.samples_a[0] = frame[1].wrapping_add(apply_weight(self.weight_b, left));
+                    update_weight_clip(&mut self.weight_b, delta, left, frame[1]);
+                    frame[1] = self.samples_a[0];
+                }
+
+                // Right is predicted from the previous left, left from the current right
+                -2 => {
+                    let right =
+                        frame[1].wrapping_add(apply_weight(self.weight_b, self.samples_b[0]));
+                    update_weight_clip(&mut self.weight_b, delta, self.samples_b[0], frame[1]);
+                    frame[1] = right;
+
+                    self.samples_b[0] = frame[0].wrapping_add(apply_weight(self.weight_a, right));
+                    update_weight_clip(&mut self.weight_a, delta, right, frame[0]);
+                    frame[0] = self.samples_b[0];
+                }
+
+                // Both channels are predicted from the previous sample of the other one
+                -3 => {
+                    let left =
+                        frame[0].wrapping_add(apply_weight(self.weight_a, self.samples_a[0]));
+                    update_weight_clip(&mut self.weight_a, delta, self.samples_a[0], frame[0]);
+
+                    let right =
+                        frame[1].wrapping_add(apply_weight(self.weight_b, self.samples_b[0]));
+                    update_weight_clip(&mut self.weight_b, delta, self.samples_b[0], frame[1]);
+
+                    self.samples_b[0] = left;
+                    self.samples_a[0] = right;
+                    frame[0] = left;
+                    frame[1] = right;
+                }
+
+                term => {
+                    let (m, k) = (index % MAX_TERM, (index + term as usize) % MAX_TERM);
+
+                    let predicted_a = self.samples_a[m];
+                    self.samples_a[k] =
+                        apply_weight(self.weight_a, predicted_a).wrapping_add(frame[0]);
+                    update_weight(&mut self.weight_a, delta, predicted_a, frame[0]);
+                    frame[0] = self.samples_a[k];
+
+                    let predicted_b = self.samples_b[m];
+                    self.samples_b[k] =
+                        apply_weight(self.weight_b, predicted_b).wrapping_add(frame[1]);
+                    update_weight(&mut self.weight_b, delta, predicted_b, frame[1]);
+                    frame[1] = self.samples_b[k];
+                }
+            }
+        }
+    }
+}
+
+/// Prediction of terms 17 and 18 from the two previous samples
+#[inline]
+fn extrapolate(term: i32, samples: &[i32; MAX_TERM]) -> i32 {
+    match term {
+        17 => samples[0].wrapping_mul(2).wrapping_sub(samples[1]),
+        _ => samples[0].wrapping_mul(3).wrapping_sub(samples[1]) >> 1,
+    }
+}
+
+/// Weight is a fixed point number with 1.0 = 1024
+#[inline]
+fn apply_weight(weight: i32, sample: i32) -> i32 {
+    ((weight as i64 * sample as i64 + 512) >> 10) as i32
+}
+
+/// Weight moves towards the sign of the sample's correlation with the prediction
+#[inline]
+fn update_weight(weight: &mut i32, delta: i32, source: i32, result: i32) {
+    if source != 0 && result != 0 {
+        match (source ^ result) < 0 {
+            false => *weight = weight.wrapping_add(delta),
+            true => *weight = weight.wrapping_sub(delta),
+        }
+    }
+}
+
+#[inline]
+fn update_weight_clip(weight: &mut i32, delta: i32, source: i32, result: i32) {
+    if source != 0 && result != 0 {
+        match (source ^ result) < 0 {
+            false => *weight = (*weight + delta).min(MAX_CROSS_WEIGHT),
+            true => *weight = (*weight - delta).max(-MAX_CROSS_WEIGHT),
+        }
+    }
+}
+
+/// Reads bits from the lowest bit of every byte
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    #[inline]
+    fn bit(&mut self) -> Option<bool> {
+        let byte = *self.data.get(self.pos / 8)?;
+        let bit = (byte >> (self.pos % 8)) & 1;
+        self.pos += 1;
+        Some(bit != 0)
+    }
+
+    /// Bits of the value from the lowest one
+    #[inline]
+    fn bits(&mut self, count: u32) -> Option<u32> {
+        (0..count).try_fold(0, |value, index| {
+            Some(value | (self.bit()? as u32) << index)
+        })
+    }
+
+    /// Count of bits in unary, then bits of the value below its highest bit
+    #[inline]
+    fn count(&mut self) -> Option<u32> {
+        let mut bit_count = 0;
+
+        while bit_count < 33 && self.bit()? {
+            bit_count += 1;
+        }
+
+        match bit_count {
+            33 => None,
+            0 | 1 => Some(bit_count),
+            _ => Some(self.bits(bit_count - 1)? | 1 << (bit_count - 1)),
+        }
+    }
+
+    /// Value up to the maximum, the shorter codes are given to the lower values
+    #[inline]
+    fn code(&mut self, max_code: u32) -> Option<u32> {
+        if max_code < 2 {
+            return match max_code {
+                0 => Some(0),
+                _ => self.bit().map(u32::from),
+            };
+        }
+
+        let bit_count = u32::BITS - max_code.leading_zeros();
+        let extras = (1 << bit_count) - max_code - 1;
+        let code = self.bits(bit_count - 1)?;
+
+        match code >= extras {
+            true => Some((code << 1) - extras + self.bit()? as u32),
+            false => Some(code),
+        }
+    }
+}
+
+/// Entropy decoder of the residuals.
+/// Magnitude is found between adaptive medians, then it's read as a code within the range.
+/// Runs of zeros are coded by their length when the signal is quiet
+struct WordReader<'a> {
+    bits: BitReader<'a>,
+    medians: [[u32; 3]; 2],
+    holding_one: bool,
+    holding_zero: bool,
+    zeros_left: u32,
+}
+
+impl<'a> WordReader<'a> {
+    #[inline]
+    fn new(data: &'a [u8], medians: [[u32; 3]; 2]) -> Self {
+        Self {
+            bits: BitReader { data, pos: 0 },
+            medians,
+            holding_one: false,
+            holding_zero: false,
+            zeros_left: 0,
+        }
+    }
+
+    #[inline]
+    fn read_word(&mut self, channel: usize) -> Option<i32> {
+        if self.medians[0][0] < 2
+            && self.medians[1][0] < 2
+            && !self.holding_zero
+            && !self.holding_one
+        {
+            if self.zeros_left > 0 {
+                self.zeros_left -= 1;
+
+                if self.zeros_left > 0 {
+                    return Some(0);
+                }
+            } else {
+                self.zeros_left = self.bits.count()?;
+
+                if self.zeros_left > 0 {
+                    self.medians = [[0; 3]; 2];
+                    return Some(0);
+                }
+            }
+        }
+
+        let ones_count = match self.holding_zero {
+            true => {
+                self.holding_zero = false;
+                0
+            }
+
+            false => {
+                let mut ones = 0;
+
+                while ones <= LIMIT_ONES && self.bits.bit()? {
+                    ones += 1;
+                }
+
+                if ones > LIMIT_ONES {
+                    return None;
+                }
+
+                if ones == LIMIT_ONES {
+                    ones = ones.checked_add(self.bits.count()?)?;
+                }
+
+                // Parity tells whether the next word has ones too
+                let ones_count = (ones >> 1) + self.holding_one as u32;
+                self.holding_one = ones & 1 != 0;
+                self.holding_zero = !self.holding_one;
+                ones_count
+            }
+        };
+
+        let medians = &mut self.medians[channel];
+        let median = |index: usize, medians: &[u32; 3]| (medians[index] >> 4) + 1;
+
+        let (low, high) = match ones_count {
+            0 => {
+                let high = median(0, medians) - 1;
+                decrease_median(medians, 0);
+                (0, high)
+            }
+
+            1 => {
+                let low = median(0, medians);
+                increase_median(medians, 0);
+
+                let high = low.wrapping_add(median(1, medians)) - 1;
+                decrease_median(medians, 1);
+                (low, high)
+            }
+
+            _ => {
+                let mut low = median(0, medians);
+                increase_median(medians, 0);
+                low = low.wrapping_add(median(1, medians));
+                increase_median(medians, 1);
+
+                if ones_count > 2 {
+                    low = low.wrapping_add((ones_count - 2).wrapping_mul(median(2, medians)));
+                }
+
+                let high = low.wrapping_add(median(2, medians)) - 1;
+
+                match ones_count {
+                    2 => decrease_median(medians, 2),
+                    _ => increase_median(medians, 2),
+                }
+
+                (low, high)
+            }
+        };
+
+        let low = low & 0x7FFF_FFFF;
+        let high = (high & 0x7FFF_FFFF).max(low);
+        let magnitude = self.bits.code(high - low)? + low;
+
+        match self.bits.bit()? {
+            true => Some(!magnitude as i32),
+            false => Some(magnitude as i32),
+        }
+    }
+}
+
+#[inline]
+fn increase_median(medians: &mut [u32; 3], index: usize) {
+    let div = MEDIAN_DIVS[index];
+    medians[index] = medians[index].wrapping_add((medians[index] + div) / div * 5);
+}
+
+#[inline]
+fn decrease_median(medians: &mut [u32; 3], index: usize) {
+    let div = MEDIAN_DIVS[index];
+    medians[index] -= (medians[index] + div - 2) / div * 2;
+}
@@ -0,0 +1,37 @@
+extern crate rodio;
+
+use crate::domain::audio_player::playback_params::PlaybackParams;
+use rodio::{Sample, Source};
+use std::time::Duration;
+
+/// Applies effects of the playback params to the track's source:
+/// reverb, then fade-in. Speed and volume are applied by the sink while playing,
+/// so exporting has to apply speed by itself
+#[inline]
+pub fn apply_effects<S>(source: S, playback_params: &PlaybackParams) -> impl Source<Item = S::Item>
+where
+    S: Source + Clone,
+    S::Item: Sample,
+{
+    apply_effects_at(source, playback_params, Duration::ZERO)
+}
+
+/// Applies effects to the source that starts at the position of the track.
+/// Fade-in that has already passed by the position is not applied
+#[inline]
+pub fn apply_effects_at<S>(
+    source: S,
+    playback_params: &PlaybackParams,
+    position: Duration,
+) -> impl Source<Item = S::Item>
+where
+    S: Source + Clone,
+    S::Item: Sample,
+{
+    let reverb = playback_params.get_reverb();
+
+    Source::fade_in(
+        Source::reverb(source, reverb.get_duration(), reverb.get_amplitude()),
+        playback_params.get_fade_in().saturating_sub(position),
+    )
+}
@@ -0,0 +1,172 @@
+extern crate rodio;
+
+use rodio::Source;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+pub type BoxedSource = Box<dyn Source<Item = i16> + Send>;
+
+/// Source that is appended to the sink, while samples it plays
+/// can be replaced at any moment, e.g. by the same track from another position.
+/// Sink and output keep playing, so there is no gap after a seek.
+/// Replacement starts at the boundary of inter-channel frame,
+/// so channels of the interleaved samples are never swapped
+pub struct LiveSource {
+    current: BoxedSource,
+
+    /// Replacement that waits for the end of the current frame
+    pending: Option<BoxedSource>,
+
+    /// Samples of the current source that are played, used to find frame boundaries
+    played_samples: usize,
+
+    replacement: Arc<Mutex<Option<BoxedSource>>>,
+    is_replaced: Arc<AtomicBool>,
+}
+
+/// Replaces samples of the live source from other threads
+#[derive(Clone)]
+pub struct LiveSourceHandle {
+    replacement: Arc<Mutex<Option<BoxedSource>>>,
+    is_replaced: Arc<AtomicBool>,
+}
+
+impl LiveSource {
+    #[inline]
+    pub fn new(source: BoxedSource) -> (Self, LiveSourceHandle) {
+        let replacement = Arc::new(Mutex::new(None));
+        let is_replaced = Arc::new(AtomicBool::default());
+
+        let handle = LiveSourceHandle {
+            replacement: replacement.clone(),
+            is_replaced: is_replaced.clone(),
+        };
+
+        let source = Self {
+            current: source,
+            pending: None,
+            played_samples: 0,
+            replacement,
+            is_replaced,
+        };
+
+        (source, handle)
+    }
+
+    /// Samples that are left till the end of the current inter-channel frame
+    #[inline]
+    fn samples_to_frame_end(&self) -> usize {
+        let channels = self.current.channels().max(1) as usize;
+        (channels - self.played_samples % channels) % channels
+    }
+
+    #[inline]
+    fn has_replacement(&self) -> bool {
+        self.pending.is_some() || self.is_replaced.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    fn switch_to(&mut self, source: BoxedSource) {
+        self.current = source;
+        self.played_samples = 0;
+    }
+
+    /// Parameter of the source that plays the next sample:
+    /// replacement's one at the frame boundary, current's one otherwise
+    #[inline]
+    fn next_source_param<T>(&self, param: impl Fn(&BoxedSource) -> T) -> T {
+        if self.samples_to_frame_end() == 0 {
+            // The latest replacement wins over the pending one
+            if self.is_replaced.load(Ordering::Acquire) {
+                if let Some(replacement) = self.replacement.lock().unwrap().as_ref() {
+                    return param(replacement);
+                }
+            }
+
+            if let Some(pending) = self.pending.as_ref() {
+                return param(pending);
+            }
+        }
+
+        param(&self.current)
+    }
+}
+
+impl LiveSourceHandle {
+    /// Source is switched after the current inter-channel frame is played
+    #[inline]
+    pub fn replace(&self, source: BoxedSource) {
+        *self.replacement.lock().unwrap() = Some(source);
+        self.is_replaced.store(true, Ordering::Release);
+    }
+}
+
+impl Iterator for LiveSource {
+    type Item = i16;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        // Flag is checked first, so the output thread locks only after a seek
+        if self.is_replaced.swap(false, Ordering::Acquire) {
+            if let Some(source) = self.replacement.lock().unwrap().take() {
+                self.pending = Some(source);
+            }
+        }
+
+        if self.samples_to_frame_end() == 0 {
+            if let Some(source) = self.pending.take() {
+                self.switch_to(source);
+            }
+        }
+
+        match self.current.next() {
+            Some(sample) => {
+                self.played_samples += 1;
+                Some(sample)
+            }
+
+            // Replacement of the finished source is played at once
+            None => {
+                let source = self.pending.take()?;
+                self.switch_to(source);
+                self.next()
+            }
+        }
+    }
+}
+
+impl Source for LiveSource {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let frame_len = self.current.current_frame_len();
+
+        if !self.has_replacement() {
+            return frame_len;
+        }
+
+        // Parameters may change as soon as the replacement starts
+        let samples_to_frame_end = self.samples_to_frame_end();
+        Some(frame_len.map_or(samples_to_frame_end, |len| len.min(samples_to_frame_end)))
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.next_source_param(|source| source.channels())
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.next_source_param(|source| source.sample_rate())
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.current.total_duration()
+    }
+}
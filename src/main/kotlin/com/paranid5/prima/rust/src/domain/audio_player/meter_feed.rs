@@ -0,0 +1,173 @@
+use std::{
+    hint,
+    sync::atomic::{fence, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::error::{Error, Result};
+
+/// Channels above this number are not metered
+pub const MAX_METER_CHANNELS: usize = 8;
+
+/// The most spectrum bands that can be requested
+pub const MAX_SPECTRUM_BANDS: usize = 256;
+
+/// Number of spectrum bands until another one is set
+pub const DEFAULT_SPECTRUM_BANDS: usize = 32;
+
+/// Frame is not returned when nothing was published for this long,
+/// e.g. playback is paused or stopped
+const STALE_FRAME_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Reader gives up after this many torn reads and returns nothing
+const MAX_READ_ATTEMPTS: usize = 16;
+
+/// Levels of the last played samples.
+/// RMS and peak levels are linear in range `0.0..=1.0`, one per channel.
+/// Bands are magnitudes of the spectrum, sine with full scale amplitude has 1
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeterFrame {
+    pub rms: Vec<f32>,
+    pub peaks: Vec<f32>,
+    pub bands: Vec<f32>,
+}
+
+/// Lock-free buffer with the latest meter frame.
+/// Frame is written by the audio thread under a sequence lock:
+/// writer never waits and skips the frame if another writer is active,
+/// readers retry while the frame is being written
+pub struct MeterFeed {
+    /// Odd while the frame is written
+    sequence: AtomicU64,
+
+    channels: AtomicUsize,
+    band_count: AtomicUsize,
+
+    /// RMS levels, then peaks of all channels, then bands, as f32 bits
+    values: Box<[AtomicU32]>,
+
+    /// Milliseconds since the feed was created, 0 if nothing was published
+    published_at: AtomicU64,
+    created_at: Instant,
+
+    /// Bands that writer should compute for the next frames
+    requested_bands: AtomicUsize,
+}
+
+impl Default for MeterFeed {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            sequence: AtomicU64::new(0),
+            channels: AtomicUsize::new(0),
+            band_count: AtomicUsize::new(0),
+            values: (0..MAX_METER_CHANNELS * 2 + MAX_SPECTRUM_BANDS)
+                .map(|_| AtomicU32::new(0))
+                .collect(),
+            published_at: AtomicU64::new(0),
+            created_at: Instant::now(),
+            requested_bands: AtomicUsize::new(DEFAULT_SPECTRUM_BANDS),
+        }
+    }
+}
+
+impl MeterFeed {
+    #[inline]
+    pub fn get_spectrum_bands(&self) -> usize {
+        self.requested_bands.load(Ordering::Relaxed)
+    }
+
+    /// Number of bands is applied from the next published frame
+    #[inline]
+    pub fn set_spectrum_bands(&self, bands: usize) -> Result<()> {
+        if !(1..=MAX_SPECTRUM_BANDS).contains(&bands) {
+            return Err(Error::Config(format!(
+                "Number of spectrum bands must be in range 1..={}",
+                MAX_SPECTRUM_BANDS
+            )));
+        }
+
+        self.requested_bands.store(bands, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Stores levels of all channels and bands as the latest frame.
+    /// Extra channels and bands are dropped
+    #[inline]
+    pub fn publish(&self, rms: &[f32], peaks: &[f32], bands: &[f32]) {
+        let sequence = self.sequence.load(Ordering::Relaxed);
+
+        if sequence % 2 == 1
+            || self
+                .sequence
+                .compare_exchange(sequence, sequence + 1, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+
+        fence(Ordering::Release);
+
+        let channels = rms.len().min(peaks.len()).min(MAX_METER_CHANNELS);
+        let band_count = bands.len().min(MAX_SPECTRUM_BANDS);
+        self.channels.store(channels, Ordering::Relaxed);
+        self.band_count.store(band_count, Ordering::Relaxed);
+
+        rms[..channels]
+            .iter()
+            .chain(&peaks[..channels])
+            .chain(&bands[..band_count])
+            .zip(self.values.iter())
+            .for_each(|(value, slot)| slot.store(value.to_bits(), Ordering::Relaxed));
+
+        let elapsed = self.created_at.elapsed().as_millis() as u64;
+        self.published_at.store(elapsed.max(1), Ordering::Relaxed);
+        self.sequence.store(sequence + 2, Ordering::Release);
+    }
+
+    /// Copy of the latest frame.
+    /// None if nothing was published recently or the writer kept overwriting it
+    #[inline]
+    pub fn latest(&self) -> Option<MeterFrame> {
+        for _ in 0..MAX_READ_ATTEMPTS {
+            let sequence = self.sequence.load(Ordering::Acquire);
+
+            if sequence % 2 == 1 {
+                hint::spin_loop();
+                continue;
+            }
+
+            let published_at = self.published_at.load(Ordering::Relaxed);
+            let channels = self.channels.load(Ordering::Relaxed);
+            let band_count = self.band_count.load(Ordering::Relaxed);
+
+            let values = self.values[..channels * 2 + band_count]
+                .iter()
+                .map(|slot| f32::from_bits(slot.load(Ordering::Relaxed)))
+                .collect::<Vec<_>>();
+
+            fence(Ordering::Acquire);
+
+            if self.sequence.load(Ordering::Relaxed) != sequence {
+                hint::spin_loop();
+                continue;
+            }
+
+            let now = self.created_at.elapsed().as_millis() as u64;
+
+            if published_at == 0
+                || now.saturating_sub(published_at) > STALE_FRAME_TIMEOUT.as_millis() as u64
+            {
+                return None;
+            }
+
+            return Some(MeterFrame {
+                rms: values[..channels].to_vec(),
+                peaks: values[channels..channels * 2].to_vec(),
+                bands: values[channels * 2..].to_vec(),
+            });
+        }
+
+        None
+    }
+}
@@ -0,0 +1,160 @@
+extern crate rodio;
+
+use rodio::Source;
+
+use std::{sync::Arc, time::Duration};
+
+use crate::domain::{
+    analysis::spectrum_analyzer::SpectrumAnalyzer,
+    audio_player::meter_feed::{MeterFeed, MAX_METER_CHANNELS, MAX_SPECTRUM_BANDS},
+};
+
+/// Frames of levels and spectrum published per second of played audio
+pub const METER_FRAME_RATE: u32 = 30;
+
+/// Number of the last samples the spectrum is computed from
+const SPECTRUM_WINDOW_SIZE: usize = 2048;
+
+/// Passes samples through unchanged, measuring them on the way.
+/// RMS and peak levels are taken from the samples played since the last frame,
+/// spectrum from the last samples of all channels mixed to mono
+pub struct MeterTap<S> {
+    source: S,
+    feed: Arc<MeterFeed>,
+    analyzer: SpectrumAnalyzer,
+
+    /// Channel of the next sample
+    channel: usize,
+    frames_since_publish: u32,
+    mono_sample: f32,
+
+    sum_squares: [f32; MAX_METER_CHANNELS],
+    peaks: [f32; MAX_METER_CHANNELS],
+    rms: [f32; MAX_METER_CHANNELS],
+
+    /// Ring buffer of the last mono samples
+    history: Vec<f32>,
+    history_pos: usize,
+    bands: Vec<f32>,
+}
+
+impl<S> MeterTap<S>
+where
+    S: Source<Item = i16>,
+{
+    #[inline]
+    pub fn new(source: S, feed: Arc<MeterFeed>) -> Self {
+        Self {
+            source,
+            feed,
+            analyzer: SpectrumAnalyzer::new(SPECTRUM_WINDOW_SIZE),
+            channel: 0,
+            frames_since_publish: 0,
+            mono_sample: 0.0,
+            sum_squares: [0.0; MAX_METER_CHANNELS],
+            peaks: [0.0; MAX_METER_CHANNELS],
+            rms: [0.0; MAX_METER_CHANNELS],
+            history: vec![0.0; SPECTRUM_WINDOW_SIZE],
+            history_pos: 0,
+            bands: Vec::with_capacity(MAX_SPECTRUM_BANDS),
+        }
+    }
+
+    #[inline]
+    fn measure(&mut self, sample: i16) {
+        let channels = self.source.channels().max(1) as usize;
+        let value = sample as f32 / i16::MAX as f32;
+
+        if let Some(sum) = self.sum_squares.get_mut(self.channel) {
+            *sum += value * value;
+            self.peaks[self.channel] = self.peaks[self.channel].max(value.abs());
+        }
+
+        self.mono_sample += value / channels as f32;
+        self.channel += 1;
+
+        if self.channel < channels {
+            return;
+        }
+
+        self.channel = 0;
+        self.history[self.history_pos] = self.mono_sample;
+        self.history_pos = (self.history_pos + 1) % SPECTRUM_WINDOW_SIZE;
+        self.mono_sample = 0.0;
+        self.frames_since_publish += 1;
+
+        let sample_rate = self.source.sample_rate();
+
+        if self.frames_since_publish >= (sample_rate / METER_FRAME_RATE).max(1) {
+            self.publish(channels.min(MAX_METER_CHANNELS), sample_rate);
+        }
+    }
+
+    #[inline]
+    fn publish(&mut self, channels: usize, sample_rate: u32) {
+        let frames = self.frames_since_publish as f32;
+
+        for (rms, sum) in self.rms.iter_mut().zip(&self.sum_squares) {
+            *rms = (sum / frames).sqrt();
+        }
+
+        // Oldest samples go first
+        let (newest, oldest) = self.history.split_at(self.history_pos);
+        let magnitudes = self
+            .analyzer
+            .magnitudes(oldest.iter().chain(newest).copied());
+
+        SpectrumAnalyzer::bands(
+            magnitudes,
+            sample_rate,
+            self.feed.get_spectrum_bands(),
+            &mut self.bands,
+        );
+
+        self.feed
+            .publish(&self.rms[..channels], &self.peaks[..channels], &self.bands);
+
+        self.frames_since_publish = 0;
+        self.sum_squares = [0.0; MAX_METER_CHANNELS];
+        self.peaks = [0.0; MAX_METER_CHANNELS];
+    }
+}
+
+impl<S> Iterator for MeterTap<S>
+where
+    S: Source<Item = i16>,
+{
+    type Item = i16;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.source.next()?;
+        self.measure(sample);
+        Some(sample)
+    }
+}
+
+impl<S> Source for MeterTap<S>
+where
+    S: Source<Item = i16>,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
@@ -1,4 +1,11 @@
+pub mod ab_loop;
+pub mod audio_output;
+#[allow(clippy::module_inception)]
 pub mod audio_player;
+pub mod decoders;
+pub mod effect_chain;
+pub mod live_source;
+pub mod meter_feed;
+pub mod meter_tap;
 pub mod playback_params;
 mod playback_position_controller;
-mod result;
@@ -116,6 +116,11 @@ impl PlaybackParams {
         self.fade_in = fade_in
     }
 
+    #[inline]
+    pub fn set_looping_state(&mut self, looping_state: LoopingState) {
+        self.looping_state = looping_state
+    }
+
     #[inline]
     pub fn set_next_looping_state(&mut self) {
         let looping = match self.looping_state {
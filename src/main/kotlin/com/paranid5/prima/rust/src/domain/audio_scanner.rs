@@ -1,63 +1,121 @@
-extern crate async_recursion;
-extern crate jni;
-extern crate once_cell;
 extern crate tokio;
 
 use crate::{
-    data::utils::types::AMutex, ARWLStorage, Comparator, DefaultTrack, Ord, TokioRuntime,
-    TrackOrder, TrackTrait,
+    domain::{analysis::track_analysis::TrackAnalysis, metadata_reader::MetadataReader},
+    error::{Error, Result},
+    ARWLStorage, Comparator, DefaultTrack, Ord, TrackOrder, TrackTrait,
 };
 
-use async_recursion::async_recursion;
-use jni::JavaVM;
-
 use std::{
-    cell::RefCell,
     cmp::Ordering,
+    collections::HashMap,
     fs,
     path::{Path, PathBuf},
-    rc::Rc,
-    sync::Arc,
 };
 
-use tokio::sync::Mutex;
-
 #[derive(Debug)]
 pub struct AudioScanner;
 
 impl AudioScanner {
+    /// Scans music folder from the settings.
+    /// Tracks are sorted with the stored track order.
+    /// Returns nothing if music folder is not chosen yet
     #[inline]
-    pub async fn get_all_tracks(
-        jvm: Arc<JavaVM>,
-        tokio_runtime: TokioRuntime,
-        storage_util: ARWLStorage,
-    ) -> AMutex<Vec<DefaultTrack>> {
-        let tracks = Arc::new(Mutex::new(Vec::new()));
-        let storage_util = storage_util.read().await;
-
-        let music_search_path = match storage_util.load_music_search_path() {
-            None => return tracks,
-            Some(msp) => msp,
+    pub async fn get_all_tracks(storage_util: ARWLStorage) -> Result<Vec<DefaultTrack>> {
+        let (music_search_path, track_order) = {
+            let storage_util = storage_util.read().await;
+
+            match storage_util.load_music_search_path() {
+                None => return Ok(Vec::new()),
+                Some(msp) => (msp.clone(), storage_util.load_track_order()),
+            }
         };
 
-        Self::search_all_tracks(&music_search_path, tracks.clone(), jvm, tokio_runtime)
+        tokio::task::spawn_blocking(move || Self::get_tracks_in(&music_search_path, track_order))
             .await
-            .unwrap();
-
-        {
-            let mut tracks = tracks.lock().await;
-            let track_order = storage_util.load_track_order();
-
-            tracks.sort_by(|f, s| match track_order.comparator {
-                Comparator::Title => Self::compare_by_title(track_order, f, s),
-                Comparator::Artist => Self::compare_by_artist(track_order, f, s),
-                Comparator::Album => Self::compare_by_album(track_order, f, s),
-                Comparator::Date => Self::compare_by_date(track_order, f, s),
-                Comparator::NumberInAlbum => Self::compare_by_number_in_album(track_order, f, s),
-            })
-        }
+            .map_err(|e| Error::Panic(e.to_string()))
+    }
 
-        tracks.clone()
+    /// Scans folder and all its subfolders,
+    /// tags are read by [MetadataReader].
+    /// Files are tracks if the player has a decoder for their probed format.
+    /// Unreadable subfolders are skipped
+    #[inline]
+    pub fn get_tracks_in(dir: &Path, track_order: TrackOrder) -> Vec<DefaultTrack> {
+        let mut tracks = Vec::new();
+        Self::search_all_tracks(dir, &mut tracks);
+        Self::sort_tracks(&mut tracks, track_order);
+        tracks
+    }
+
+    #[inline]
+    pub fn sort_tracks(tracks: &mut [DefaultTrack], track_order: TrackOrder) {
+        tracks.sort_by(|f, s| match track_order.comparator {
+            Comparator::Title => Self::compare_by_title(track_order, f, s),
+            Comparator::Artist => Self::compare_by_artist(track_order, f, s),
+            Comparator::Album => Self::compare_by_album(track_order, f, s),
+            Comparator::Date => Self::compare_by_date(track_order, f, s),
+            Comparator::NumberInAlbum => Self::compare_by_number_in_album(track_order, f, s),
+
+            // Analysis is not known here, see [AudioScanner::sort_tracks_by_analysis]
+            Comparator::Bpm | Comparator::Key | Comparator::Energy => Ordering::Equal,
+        })
+    }
+
+    /// Sorts tracks by values of their analysis.
+    /// Tracks without the value go last in both orders.
+    /// Keys are ordered along the Camelot wheel, so neighbours mix well
+    #[inline]
+    pub fn sort_tracks_by_analysis(
+        tracks: &mut [DefaultTrack],
+        track_order: TrackOrder,
+        analyses: &HashMap<PathBuf, TrackAnalysis>,
+    ) {
+        let value_of = |track: &DefaultTrack| {
+            let analysis = analyses.get(track.get_path())?;
+
+            match track_order.comparator {
+                Comparator::Bpm => analysis.bpm,
+                Comparator::Energy => Some(analysis.energy),
+
+                Comparator::Key => analysis.key.map(|key| {
+                    key.get_camelot_number() as f32 * 2.0 + (!key.is_minor) as u8 as f32
+                }),
+
+                _ => None,
+            }
+        };
+
+        tracks.sort_by(|f, s| match (value_of(f), value_of(s)) {
+            (Some(f), Some(s)) => match track_order.order {
+                Ord::Asc => f.total_cmp(&s),
+                Ord::Desc => s.total_cmp(&f),
+            },
+
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        })
+    }
+
+    #[inline]
+    fn search_all_tracks(dir: &Path, tracks: &mut Vec<DefaultTrack>) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+
+            Err(e) => {
+                eprintln!("Unable to scan {}: {}", dir.display(), e);
+                return;
+            }
+        };
+
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.is_dir() {
+                Self::search_all_tracks(&path, tracks)
+            } else if let Some(track) = MetadataReader::read_track(&path) {
+                tracks.push(track)
+            }
+        }
     }
 
     #[inline]
@@ -143,56 +201,4 @@ impl AudioScanner {
                 .unwrap(),
         }
     }
-
-    #[inline]
-    pub async fn scan_file(file: &Path, jvm: Arc<JavaVM>) -> Option<DefaultTrack> {
-        let jni_env = Rc::new(RefCell::new(
-            jvm.attach_current_thread_permanently().unwrap(),
-        ));
-
-        DefaultTrack::from_path(jni_env, file.to_string_lossy().to_string())
-    }
-
-    #[async_recursion]
-    async fn search_all_tracks(
-        dir: &Path,
-        tracks: AMutex<Vec<DefaultTrack>>,
-        jvm: Arc<JavaVM>,
-        tokio_runtime: TokioRuntime,
-    ) -> std::io::Result<()> {
-        let dir = fs::read_dir(dir)?;
-        let mut tasks = Vec::with_capacity(1000);
-
-        for entry in dir {
-            let path = entry?.path();
-            let tracks = tracks.clone();
-            let jvm = jvm.clone();
-            let trc = tokio_runtime.clone();
-
-            tasks.push(
-                tokio_runtime.spawn(async move { Self::search_step(path, tracks, jvm, trc).await }),
-            );
-        }
-
-        futures::future::join_all(tasks).await;
-        Ok(())
-    }
-
-    #[inline]
-    async fn search_step(
-        path: PathBuf,
-        tracks: AMutex<Vec<DefaultTrack>>,
-        jvm: Arc<JavaVM>,
-        tokio_runtime: TokioRuntime,
-    ) {
-        if path.is_dir() {
-            Self::search_all_tracks(path.as_path(), tracks, jvm.clone(), tokio_runtime)
-                .await
-                .unwrap();
-        } else {
-            if let Some(track) = Self::scan_file(path.as_path(), jvm.clone()).await {
-                tracks.lock().await.push(track)
-            }
-        }
-    }
 }
@@ -0,0 +1,311 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    data::{
+        entities::tracks::{default_track::DefaultTrack, track_trait::TrackTrait},
+        utils::random::random_unit,
+    },
+    domain::{auto_dj::auto_dj_settings::AutoDjSeed, metadata_reader::MetadataReader},
+};
+
+/// Number of tracks kept after the current one while Auto-DJ is on
+pub const AUTO_DJ_LOOKAHEAD: usize = 3;
+
+/// Tracks played this recently are not picked again
+pub const AUTO_DJ_RECENT_PLAYS: usize = 50;
+
+/// Next track is drawn from this many best matches,
+/// so the same seed doesn't produce the same mix every time
+const CANDIDATES_TO_DRAW_FROM: usize = 8;
+
+const ARTIST_WEIGHT: f32 = 3.0;
+const ALBUM_ARTIST_WEIGHT: f32 = 2.0;
+const GENRE_WEIGHT: f32 = 2.0;
+const YEAR_WEIGHT: f32 = 1.5;
+const FAVOURITE_TRACK_WEIGHT: f32 = 1.0;
+const FAVOURITE_ARTIST_WEIGHT: f32 = 0.5;
+const PLAY_COUNT_WEIGHT: f32 = 1.0;
+
+/// Years further apart than this are not similar at all
+const YEAR_SPAN: f32 = 10.0;
+
+/// Tracks with this many plays get the whole [PLAY_COUNT_WEIGHT]
+const SATURATED_PLAY_COUNT: usize = 10;
+
+/// Favourites and play history the picks are weighed with
+#[derive(Clone, Debug, Default)]
+pub struct AutoDjContext {
+    pub favourite_tracks: HashSet<PathBuf>,
+    pub favourite_artists: HashSet<String>,
+    pub play_counts: HashMap<PathBuf, usize>,
+
+    /// Recently played tracks, they are skipped while there are others
+    pub recent_plays: Vec<PathBuf>,
+}
+
+/// Tags that similarity is measured by,
+/// names are compared case-insensitively
+#[derive(Clone, Debug, Default)]
+struct TrackProfile {
+    artist: Option<String>,
+    album_artist: Option<String>,
+    genres: Vec<String>,
+    year: Option<i32>,
+}
+
+/// Picks library tracks that are similar to the seed
+/// or to the last track of the queue.
+/// Tags that are not stored in [DefaultTrack] are read once and cached
+/// for the whole session
+#[derive(Debug, Default)]
+pub struct AutoDj {
+    profiles: HashMap<PathBuf, TrackProfile>,
+}
+
+impl AutoDj {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Picks up to `count` tracks to follow the queue.
+    /// Tracks of the queue and recently played tracks are skipped,
+    /// until the library has nothing else.
+    /// Without seed every pick is similar to the previous one
+    #[inline]
+    pub fn pick(
+        &mut self,
+        library: &[DefaultTrack],
+        queue: &[DefaultTrack],
+        seed: Option<&AutoDjSeed>,
+        context: &AutoDjContext,
+        count: usize,
+    ) -> Vec<DefaultTrack> {
+        let mut reference = match seed {
+            Some(AutoDjSeed::Track(path)) => self.get_profile(path, None),
+
+            Some(AutoDjSeed::Artist(artist)) => TrackProfile {
+                artist: normalize(artist),
+                album_artist: normalize(artist),
+                ..TrackProfile::default()
+            },
+
+            None => queue
+                .last()
+                .map(|track| self.get_profile(track.get_path(), track.get_artist()))
+                .unwrap_or_default(),
+        };
+
+        let queued = queue.iter().map(|t| t.get_path()).collect::<HashSet<_>>();
+        let recent = context.recent_plays.iter().collect::<HashSet<_>>();
+        let seed_path = match seed {
+            Some(AutoDjSeed::Track(path)) => Some(path),
+            _ => None,
+        };
+
+        let last_queued = queue.last().map(|t| t.get_path());
+        let mut picked = HashSet::new();
+        let mut picks = Vec::with_capacity(count);
+
+        let is_fresh = |path: &PathBuf| {
+            !queued.contains(path) && !recent.contains(path) && Some(path) != seed_path
+        };
+
+        let is_not_recent = |path: &PathBuf| !recent.contains(path);
+        let is_any = |_: &PathBuf| true;
+
+        // Repeats are allowed only when the library has nothing else
+        let tiers: [&dyn Fn(&PathBuf) -> bool; 3] = [&is_fresh, &is_not_recent, &is_any];
+
+        for _ in 0..count {
+            let candidates = tiers
+                .iter()
+                .map(|is_candidate| {
+                    library
+                        .iter()
+                        .filter(|track| {
+                            let path = track.get_path();
+                            !picked.contains(path)
+                                && Some(path) != last_queued
+                                && is_candidate(path)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .find(|candidates| !candidates.is_empty());
+
+            let candidates = match candidates {
+                None => break,
+                Some(candidates) => candidates,
+            };
+
+            let mut scored = candidates
+                .into_iter()
+                .map(|track| {
+                    let profile = self.get_profile(track.get_path(), track.get_artist());
+                    let score = similarity(&reference, &profile, track.get_path(), context);
+                    (track, score)
+                })
+                .collect::<Vec<_>>();
+
+            scored.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+            let similar = scored
+                .iter()
+                .take(CANDIDATES_TO_DRAW_FROM)
+                .take_while(|(_, score)| *score > 0.0)
+                .count();
+
+            // Nothing is similar: any track is as good as another
+            let (pool, is_weighted) = match similar {
+                0 => (&scored[..], false),
+                _ => (&scored[..similar], true),
+            };
+
+            let track = draw(pool, is_weighted).clone();
+
+            if seed.is_none() {
+                reference = self.get_profile(track.get_path(), track.get_artist());
+            }
+
+            picked.insert(track.get_path().clone());
+            picks.push(track);
+        }
+
+        picks
+    }
+
+    /// Similarity of tags only, favourites and plays don't count
+    #[inline]
+    pub fn get_similarity(&mut self, track: &DefaultTrack, other: &DefaultTrack) -> f32 {
+        let reference = self.get_profile(track.get_path(), track.get_artist());
+        let profile = self.get_profile(other.get_path(), other.get_artist());
+        similarity(
+            &reference,
+            &profile,
+            other.get_path(),
+            &AutoDjContext::default(),
+        )
+    }
+
+    #[inline]
+    fn get_profile(&mut self, path: &Path, artist: Option<&String>) -> TrackProfile {
+        self.profiles
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                let metadata = MetadataReader::read_metadata(path).unwrap_or_default();
+
+                TrackProfile {
+                    artist: metadata
+                        .artist
+                        .as_deref()
+                        .or(artist.map(String::as_str))
+                        .and_then(normalize),
+                    album_artist: metadata.album_artist.as_deref().and_then(normalize),
+                    genres: metadata
+                        .genre
+                        .as_deref()
+                        .map(split_genres)
+                        .unwrap_or_default(),
+                    year: metadata.year,
+                }
+            })
+            .clone()
+    }
+}
+
+/// Sum of the weights of matching tags, favourites and plays
+#[inline]
+fn similarity(
+    reference: &TrackProfile,
+    candidate: &TrackProfile,
+    path: &Path,
+    context: &AutoDjContext,
+) -> f32 {
+    let is_same = |a: &Option<String>, b: &Option<String>| a.is_some() && a == b;
+    let mut score = 0.0;
+
+    if is_same(&reference.artist, &candidate.artist) {
+        score += ARTIST_WEIGHT
+    }
+
+    if is_same(&reference.album_artist, &candidate.album_artist) {
+        score += ALBUM_ARTIST_WEIGHT
+    }
+
+    let shared_genres = reference
+        .genres
+        .iter()
+        .filter(|genre| candidate.genres.contains(genre))
+        .count();
+
+    if shared_genres > 0 {
+        let genres = reference.genres.len().max(candidate.genres.len());
+        score += GENRE_WEIGHT * shared_genres as f32 / genres as f32
+    }
+
+    if let (Some(a), Some(b)) = (reference.year, candidate.year) {
+        score += YEAR_WEIGHT * (1.0 - (a - b).abs() as f32 / YEAR_SPAN).max(0.0)
+    }
+
+    if context.favourite_tracks.contains(path) {
+        score += FAVOURITE_TRACK_WEIGHT
+    }
+
+    let is_favourite_artist = candidate.artist.as_ref().is_some_and(|artist| {
+        context
+            .favourite_artists
+            .iter()
+            .any(|favourite| normalize(favourite).as_ref() == Some(artist))
+    });
+
+    if is_favourite_artist {
+        score += FAVOURITE_ARTIST_WEIGHT
+    }
+
+    if let Some(plays) = context.play_counts.get(path) {
+        let saturation = (*plays as f32).ln_1p() / (SATURATED_PLAY_COUNT as f32).ln_1p();
+        score += PLAY_COUNT_WEIGHT * saturation.min(1.0)
+    }
+
+    score
+}
+
+/// Random track of the pool, weighted by score if asked
+#[inline]
+fn draw<'a>(pool: &[(&'a DefaultTrack, f32)], is_weighted: bool) -> &'a DefaultTrack {
+    let weight = |score: f32| match is_weighted {
+        true => score,
+        false => 1.0,
+    };
+
+    let total = pool.iter().map(|(_, score)| weight(*score)).sum::<f32>();
+    let mut point = random_unit() * total;
+
+    for (track, score) in pool {
+        point -= weight(*score);
+
+        if point < 0.0 {
+            return track;
+        }
+    }
+
+    pool[pool.len() - 1].0
+}
+
+#[inline]
+fn normalize(name: &str) -> Option<String> {
+    let name = name.trim().to_lowercase();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Genres are often stored in one tag as `Rock; Indie` or `Rock/Indie`
+#[inline]
+fn split_genres(genre: &str) -> Vec<String> {
+    genre
+        .split([';', ',', '/', '\0'])
+        .filter_map(normalize)
+        .collect()
+}
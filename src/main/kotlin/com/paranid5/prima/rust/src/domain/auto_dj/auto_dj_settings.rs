@@ -0,0 +1,68 @@
+extern crate yaml_rust;
+
+use std::path::PathBuf;
+use yaml_rust::{yaml::Hash, Yaml};
+
+/// What Auto-DJ looks for: music similar to the track or music of the artist
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AutoDjSeed {
+    Track(PathBuf),
+    Artist(String),
+}
+
+/// Auto-DJ is disabled by default.
+/// Without seed picks are similar to the last track of the queue
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AutoDjSettings {
+    pub is_enabled: bool,
+    pub seed: Option<AutoDjSeed>,
+}
+
+#[inline]
+fn yaml_key(key: &str) -> Yaml {
+    Yaml::String(key.to_string())
+}
+
+impl From<AutoDjSettings> for Yaml {
+    #[inline]
+    fn from(settings: AutoDjSettings) -> Self {
+        let mut hash = Hash::new();
+        hash.insert(yaml_key("enabled"), Self::Boolean(settings.is_enabled));
+
+        match settings.seed {
+            None => {}
+
+            Some(AutoDjSeed::Track(path)) => {
+                hash.insert(
+                    yaml_key("seed_track"),
+                    Self::String(path.to_string_lossy().to_string()),
+                );
+            }
+
+            Some(AutoDjSeed::Artist(artist)) => {
+                hash.insert(yaml_key("seed_artist"), Self::String(artist));
+            }
+        }
+
+        Self::Hash(hash)
+    }
+}
+
+impl From<&Hash> for AutoDjSettings {
+    #[inline]
+    fn from(hash: &Hash) -> Self {
+        let string = |key| hash.get(&yaml_key(key)).and_then(|y| y.as_str());
+
+        let seed = string("seed_track")
+            .map(|path| AutoDjSeed::Track(PathBuf::from(path)))
+            .or_else(|| string("seed_artist").map(|artist| AutoDjSeed::Artist(artist.to_string())));
+
+        Self {
+            is_enabled: hash
+                .get(&yaml_key("enabled"))
+                .and_then(|y| y.as_bool())
+                .unwrap_or_default(),
+            seed,
+        }
+    }
+}
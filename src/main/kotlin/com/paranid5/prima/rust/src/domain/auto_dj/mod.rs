@@ -0,0 +1,3 @@
+#[allow(clippy::module_inception)]
+pub mod auto_dj;
+pub mod auto_dj_settings;
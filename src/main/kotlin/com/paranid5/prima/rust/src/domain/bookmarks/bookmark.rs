@@ -0,0 +1,15 @@
+use std::{path::PathBuf, time::Duration};
+
+/// Named position in the track, e.g. a chapter of an audiobook
+/// or a transition of a DJ mix
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bookmark {
+    pub id: i32,
+    pub path: PathBuf,
+    pub position: Duration,
+    pub name: String,
+    pub note: Option<String>,
+
+    /// Unix time when the bookmark was added
+    pub created_at: i64,
+}
@@ -0,0 +1,2 @@
+pub mod bookmark;
+pub mod resume_position;
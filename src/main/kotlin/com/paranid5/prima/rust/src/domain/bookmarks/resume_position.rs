@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+/// Tracks of at least this length resume where they were left,
+/// unless the user chooses another threshold
+pub const DEFAULT_RESUME_THRESHOLD: Duration = Duration::from_secs(20 * 60);
+
+/// Track that is left before this position is started over next time
+const MIN_RESUME_POSITION: Duration = Duration::from_secs(10);
+
+/// Track that is left this close to its end is considered finished
+const FINISHED_TOLERANCE: Duration = Duration::from_secs(10);
+
+/// Whether the track remembers its position
+#[inline]
+pub fn is_long_form(duration: Duration, threshold: Duration) -> bool {
+    duration >= threshold
+}
+
+/// Track that is left this close to its end is played to the end
+#[inline]
+pub fn is_finished(position: Duration, duration: Duration) -> bool {
+    position + FINISHED_TOLERANCE >= duration
+}
+
+/// Position from which the track is resumed next time.
+/// None if it's started over: track was barely started or is already finished
+#[inline]
+pub fn resume_position(position: Duration, duration: Duration) -> Option<Duration> {
+    (position >= MIN_RESUME_POSITION && !is_finished(position, duration)).then_some(position)
+}
@@ -0,0 +1,124 @@
+use crate::{
+    data::utils::paths::APP_PATHS,
+    domain::metadata_reader::{Cover, MetadataReader},
+};
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// Subdirectory of the cache where embedded covers are extracted
+const COVERS_CACHE_DIR: &str = "covers";
+
+/// Names of images in the track's folder that are treated as album's cover
+const FOLDER_COVER_NAMES: [&str; 4] = ["cover", "folder", "front", "album"];
+
+const FOLDER_COVER_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+
+/// Extensions that extracted pictures may have, see [Cover::get_extension]
+const CACHED_COVER_EXTENSIONS: [&str; 4] = ["jpg", "png", "webp", "gif"];
+
+/// Finds album art of the track: picture embedded into the file
+/// or image placed in the same folder (cover.jpg, folder.png, etc.)
+#[derive(Debug)]
+pub struct CoverArt;
+
+impl CoverArt {
+    /// Reads image of the track's cover.
+    /// Embedded picture is preferred to the folder's image
+    #[inline]
+    pub fn read(track: &Path) -> Option<Cover> {
+        if let Some(cover) = MetadataReader::read_cover(track) {
+            return Some(cover);
+        }
+
+        let image = Self::find_folder_image(track)?;
+        let extension = image.extension()?.to_string_lossy().to_lowercase();
+
+        Some(Cover {
+            mime_type: match extension.as_str() {
+                "png" => "image/png".to_string(),
+                _ => "image/jpeg".to_string(),
+            },
+            data: fs::read(image).ok()?,
+        })
+    }
+
+    /// File with the track's cover that can be handed to other applications.
+    /// Embedded pictures are extracted to the application's cache
+    #[inline]
+    pub fn find(track: &Path) -> Option<PathBuf> {
+        Self::find_in(track, &APP_PATHS.get_cache_dir().join(COVERS_CACHE_DIR))
+    }
+
+    /// Same as [CoverArt::find], but extracts embedded pictures to the given directory
+    #[inline]
+    pub fn find_in(track: &Path, cache_dir: &Path) -> Option<PathBuf> {
+        let cache_name = Self::cache_name_of(track)?;
+
+        let cached = CACHED_COVER_EXTENSIONS
+            .iter()
+            .map(|extension| cache_dir.join(format!("{}.{}", cache_name, extension)))
+            .find(|path| path.is_file());
+
+        if cached.is_some() {
+            return cached;
+        }
+
+        match MetadataReader::read_cover(track) {
+            None => Self::find_folder_image(track),
+
+            Some(cover) => {
+                let path = cache_dir.join(format!("{}.{}", cache_name, cover.get_extension()));
+                fs::create_dir_all(cache_dir).ok()?;
+                fs::write(&path, cover.data).ok()?;
+                Some(path)
+            }
+        }
+    }
+
+    /// Looks for the cover image in the track's folder, ignoring case of names
+    #[inline]
+    fn find_folder_image(track: &Path) -> Option<PathBuf> {
+        let mut images = fs::read_dir(track.parent()?)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter_map(|path| {
+                let stem = path.file_stem()?.to_string_lossy().to_lowercase();
+                let extension = path.extension()?.to_string_lossy().to_lowercase();
+
+                let name_rank = FOLDER_COVER_NAMES.iter().position(|n| *n == stem)?;
+                FOLDER_COVER_EXTENSIONS
+                    .contains(&extension.as_str())
+                    .then_some((name_rank, path))
+            })
+            .collect::<Vec<_>>();
+
+        images.sort();
+        images.into_iter().next().map(|(_, path)| path)
+    }
+
+    /// Name of the extracted picture.
+    /// Changes when the track's file is modified, so stale covers are not used
+    #[inline]
+    fn cache_name_of(track: &Path) -> Option<String> {
+        let metadata = fs::metadata(track).ok()?;
+
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+
+        let mut hasher = DefaultHasher::new();
+        track.hash(&mut hasher);
+        modified.hash(&mut hasher);
+        metadata.len().hash(&mut hasher);
+        Some(format!("{:016x}", hasher.finish()))
+    }
+}
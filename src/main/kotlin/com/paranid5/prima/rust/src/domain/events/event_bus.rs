@@ -0,0 +1,49 @@
+extern crate tokio;
+
+use crate::domain::events::player_event::PlayerEvent;
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+/// Number of events kept for slow subscribers.
+/// When subscriber falls behind, the oldest events are skipped
+const EVENT_BUS_CAPACITY: usize = 256;
+
+/// Broadcasts [PlayerEvent]s to every subscriber:
+/// JVM listener, scrobblers, history recording, etc.
+/// Cloned buses publish to the same subscribers
+#[derive(Clone, Debug)]
+pub struct EventBus {
+    sender: Sender<PlayerEvent>,
+}
+
+impl EventBus {
+    #[inline]
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+
+    /// Sends event to all current subscribers.
+    /// Events published when there are no subscribers are dropped
+    #[inline]
+    pub fn publish(&self, event: PlayerEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Receives all events published after this call
+    #[inline]
+    pub fn subscribe(&self) -> Receiver<PlayerEvent> {
+        self.sender.subscribe()
+    }
+
+    #[inline]
+    pub fn subscribers_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for EventBus {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
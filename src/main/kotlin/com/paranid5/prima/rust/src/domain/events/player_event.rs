@@ -0,0 +1,44 @@
+use crate::DefaultTrack;
+use std::time::Duration;
+
+/// Changes of the player and the library
+/// that are pushed to subscribers of [EventBus]
+///
+/// [EventBus]: crate::domain::events::event_bus::EventBus
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlayerEvent {
+    /// Playback was started, paused, stopped or track has ended
+    StateChanged { is_playing: bool },
+
+    /// Another track of the current playlist became current
+    TrackChanged {
+        index: usize,
+        track: Option<DefaultTrack>,
+    },
+
+    /// Periodical update of the playback position while track is playing
+    PositionTick { position: Duration },
+
+    /// Tracks of the current playlist were replaced or reordered
+    QueueChanged { length: usize, index: usize },
+
+    /// Scan of the music folder found different set of tracks
+    LibraryUpdated { tracks_count: usize },
+
+    /// Playback moved to another output device.
+    /// Absent name means that null output is used
+    OutputDeviceChanged { device: Option<String> },
+
+    /// Sleep timer has stopped playback or its track has ended
+    SleepTimerFinished,
+
+    /// Internet radio station has announced another song.
+    /// Absent title means that the station doesn't name it
+    StreamTitleChanged { title: Option<String> },
+
+    /// Podcasts were refreshed with new episodes or episodes were downloaded
+    PodcastsUpdated { new_episodes: usize },
+
+    /// Error that happened in the native library
+    Error { message: String },
+}
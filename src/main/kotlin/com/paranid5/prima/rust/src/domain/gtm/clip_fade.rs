@@ -0,0 +1,111 @@
+extern crate rodio;
+
+use rodio::Source;
+
+use std::time::Duration;
+
+/// Fade of the clip when the game asks for it
+pub const GTM_CLIP_FADE: Duration = Duration::from_millis(500);
+
+/// Cuts the clip of the given length from the source,
+/// fading it in at the start and out at the end
+pub struct ClipFade<S> {
+    source: S,
+    clip_frames: u64,
+    fade_frames: u64,
+
+    /// Channel of the next sample
+    channel: u16,
+    frame: u64,
+}
+
+impl<S> ClipFade<S>
+where
+    S: Source<Item = i16>,
+{
+    /// Zero fade plays the clip as it is
+    #[inline]
+    pub fn new(source: S, length: Duration, fade: Duration) -> Self {
+        let sample_rate = source.sample_rate() as f64;
+
+        Self {
+            clip_frames: (length.as_secs_f64() * sample_rate) as u64,
+            fade_frames: (fade.min(length / 2).as_secs_f64() * sample_rate) as u64,
+            source,
+            channel: 0,
+            frame: 0,
+        }
+    }
+
+    #[inline]
+    fn get_gain(&self) -> f32 {
+        if self.fade_frames == 0 {
+            return 1.0;
+        }
+
+        let from_edge = self.frame.min(self.clip_frames - self.frame - 1);
+        (from_edge as f32 / self.fade_frames as f32).min(1.0)
+    }
+}
+
+impl<S> Iterator for ClipFade<S>
+where
+    S: Source<Item = i16>,
+{
+    type Item = i16;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.frame >= self.clip_frames {
+            return None;
+        }
+
+        let sample = self.source.next()?;
+        let sample = (sample as f32 * self.get_gain()) as i16;
+
+        self.channel += 1;
+
+        if self.channel >= self.source.channels().max(1) {
+            self.channel = 0;
+            self.frame += 1;
+        }
+
+        Some(sample)
+    }
+}
+
+impl<S> Source for ClipFade<S>
+where
+    S: Source<Item = i16>,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let channels = self.source.channels().max(1) as u64;
+        let left = (self.clip_frames.saturating_sub(self.frame) * channels)
+            .saturating_sub(self.channel as u64) as usize;
+
+        Some(
+            self.source
+                .current_frame_len()
+                .map_or(left, |len| len.min(left)),
+        )
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let sample_rate = self.source.sample_rate().max(1) as f64;
+        Some(Duration::from_secs_f64(
+            self.clip_frames as f64 / sample_rate,
+        ))
+    }
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use crate::domain::analysis::waveform::Waveform;
+
+/// Resolution of the waveform that clips are searched in
+pub const GTM_WAVEFORM_RESOLUTION: usize = 1024;
+
+/// Share of the track at the start and at the end where clips don't begin:
+/// intros and outros are rarely recognizable
+const INTRO_SHARE: f32 = 0.15;
+const OUTRO_SHARE: f32 = 0.1;
+
+/// Parts quieter than this share of the full scale are silence
+const SILENCE_LEVEL: f32 = 0.02;
+
+/// Clip must be at least this loud compared to the whole track
+const MIN_RELATIVE_LEVEL: f32 = 0.75;
+
+/// Finds where clips of the game may start
+#[derive(Debug)]
+pub struct ClipFinder;
+
+impl ClipFinder {
+    /// Starts of clips that skip intro and outro, have no silence
+    /// and are about as loud as the track.
+    /// If the track has no such clips, the loudest one is returned.
+    /// Empty if the track is not longer than the clip
+    #[inline]
+    pub fn find_starts(
+        waveform: &Waveform,
+        track_duration: Duration,
+        clip_length: Duration,
+    ) -> Vec<Duration> {
+        let parts = waveform.get_resolution();
+
+        if parts == 0 || track_duration <= clip_length {
+            return vec![];
+        }
+
+        let part_duration = track_duration.as_secs_f32() / parts as f32;
+        let clip_parts =
+            ((clip_length.as_secs_f32() / part_duration).ceil() as usize).clamp(1, parts);
+
+        let levels = waveform
+            .peaks
+            .chunks_exact(2)
+            .map(|peak| (peak[1] as f32 - peak[0] as f32) / 255.0)
+            .collect::<Vec<_>>();
+
+        let track_level = levels.iter().sum::<f32>() / parts as f32;
+
+        let clip_level = |start: usize| {
+            levels[start..start + clip_parts].iter().sum::<f32>() / clip_parts as f32
+        };
+
+        let last_start = parts - clip_parts;
+        let first = ((parts as f32 * INTRO_SHARE) as usize).min(last_start);
+        let last = last_start.saturating_sub((parts as f32 * OUTRO_SHARE) as usize);
+
+        // Short tracks have no room for intro and outro
+        let range = match first <= last {
+            true => first..=last,
+            false => 0..=last_start,
+        };
+
+        let to_position = |start: usize| Duration::from_secs_f32(start as f32 * part_duration);
+
+        let starts = range
+            .clone()
+            .filter(|&start| {
+                let is_silent = levels[start..start + clip_parts]
+                    .iter()
+                    .any(|&level| level < SILENCE_LEVEL);
+
+                !is_silent && clip_level(start) >= track_level * MIN_RELATIVE_LEVEL
+            })
+            .map(to_position)
+            .collect::<Vec<_>>();
+
+        if !starts.is_empty() {
+            return starts;
+        }
+
+        range
+            .max_by(|&a, &b| clip_level(a).total_cmp(&clip_level(b)))
+            .map(to_position)
+            .into_iter()
+            .collect()
+    }
+}
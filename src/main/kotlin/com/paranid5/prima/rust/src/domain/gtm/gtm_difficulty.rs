@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+/// Difficulty of the guess-the-melody game:
+/// harder games play shorter clips and offer decoys that sound alike
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GtmDifficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl GtmDifficulty {
+    /// Difficulty as it's passed through JNI and stored: 0 - easy, 1 - normal, 2 - hard
+    #[inline]
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(Self::Easy),
+            1 => Some(Self::Normal),
+            2 => Some(Self::Hard),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn get_code(&self) -> i32 {
+        match self {
+            Self::Easy => 0,
+            Self::Normal => 1,
+            Self::Hard => 2,
+        }
+    }
+
+    #[inline]
+    pub fn get_clip_length(&self) -> Duration {
+        match self {
+            Self::Easy => Duration::from_secs(10),
+            Self::Normal => Duration::from_secs(5),
+            Self::Hard => Duration::from_secs(2),
+        }
+    }
+
+    /// Points of the correct answer are multiplied by it
+    #[inline]
+    pub fn get_score_multiplier(&self) -> u32 {
+        match self {
+            Self::Easy => 1,
+            Self::Normal => 2,
+            Self::Hard => 3,
+        }
+    }
+}
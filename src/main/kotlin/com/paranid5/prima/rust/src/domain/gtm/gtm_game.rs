@@ -0,0 +1,255 @@
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    data::{
+        entities::tracks::{default_track::DefaultTrack, track_trait::TrackTrait},
+        utils::random::random_index,
+    },
+    domain::gtm::{
+        gtm_difficulty::GtmDifficulty,
+        gtm_round::{GtmRound, GtmRoundResult},
+    },
+    error::{Error, Result},
+};
+
+/// Guessed track and decoys
+pub const GTM_ANSWERS_COUNT: usize = 4;
+
+/// Decoys are drawn from this many tracks:
+/// the most similar ones on hard, the least similar ones on easy
+const DECOY_POOL_SIZE: usize = 6;
+
+/// Points of every correct answer
+const BASE_POINTS: u32 = 100;
+
+/// Most points for a quick answer, they run out in [ANSWER_TIME_LIMIT]
+const SPEED_POINTS: u32 = 100;
+const ANSWER_TIME_LIMIT: Duration = Duration::from_secs(20);
+
+/// Guess-the-melody game over the tracks of the library.
+/// Every round plays a clip of a random track that was not guessed yet
+/// and offers it among decoys. Quick correct answers give more points
+#[derive(Debug)]
+pub struct GtmGame {
+    library: Vec<DefaultTrack>,
+    difficulty: GtmDifficulty,
+    rounds_count: usize,
+    used_tracks: HashSet<PathBuf>,
+
+    /// Round that is not answered yet
+    round: Option<(GtmRound, Instant)>,
+    results: Vec<GtmRoundResult>,
+}
+
+impl GtmGame {
+    /// Library must have enough tracks with different titles for the answers
+    #[inline]
+    pub fn new(
+        library: Vec<DefaultTrack>,
+        difficulty: GtmDifficulty,
+        rounds_count: usize,
+    ) -> Result<Self> {
+        if rounds_count == 0 {
+            return Err(Error::Config(
+                "Game must have at least one round".to_string(),
+            ));
+        }
+
+        let titles = library.iter().map(answer_title).collect::<HashSet<_>>();
+
+        if titles.len() < GTM_ANSWERS_COUNT {
+            return Err(Error::Config(format!(
+                "Game needs at least {} different tracks",
+                GTM_ANSWERS_COUNT
+            )));
+        }
+
+        Ok(Self {
+            library,
+            difficulty,
+            rounds_count,
+            used_tracks: HashSet::new(),
+            round: None,
+            results: vec![],
+        })
+    }
+
+    /// Picks the track and the answers of the next round, its clock starts now.
+    /// Similarity of tracks chooses decoys by difficulty,
+    /// clip starts are given for the track's duration and the clip's length.
+    /// Without clip starts the clip is played from the beginning
+    #[inline]
+    pub fn next_round(
+        &mut self,
+        mut similarity: impl FnMut(&DefaultTrack, &DefaultTrack) -> f32,
+        clip_starts_of: impl FnOnce(&DefaultTrack, Duration) -> Result<Vec<Duration>>,
+    ) -> Result<GtmRound> {
+        if self.round.is_some() {
+            return Err(Error::Playback("Round is not answered yet".to_string()));
+        }
+
+        if self.is_over() {
+            return Err(Error::Playback("Game is over".to_string()));
+        }
+
+        // Every track is played once, until the library runs out
+        if self
+            .library
+            .iter()
+            .all(|t| self.used_tracks.contains(t.get_path()))
+        {
+            self.used_tracks.clear()
+        }
+
+        let fresh = self
+            .library
+            .iter()
+            .filter(|track| !self.used_tracks.contains(track.get_path()))
+            .collect::<Vec<_>>();
+
+        let track = fresh[random_index(fresh.len())].clone();
+        let title = answer_title(&track);
+
+        let mut others = self
+            .library
+            .iter()
+            .filter(|other| answer_title(other) != title)
+            .map(|other| (other, similarity(&track, other)))
+            .collect::<Vec<_>>();
+
+        others.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        // Same title may be stored in several files
+        let mut titles = HashSet::from([title]);
+        others.retain(|(other, _)| titles.insert(answer_title(other)));
+
+        let pool_size = DECOY_POOL_SIZE.max(GTM_ANSWERS_COUNT - 1).min(others.len());
+
+        let mut pool = match self.difficulty {
+            GtmDifficulty::Hard => others[..pool_size].to_vec(),
+            GtmDifficulty::Normal => others,
+            GtmDifficulty::Easy => others[others.len() - pool_size..].to_vec(),
+        };
+
+        let mut answers = (0..GTM_ANSWERS_COUNT - 1)
+            .map(|_| pool.remove(random_index(pool.len())).0.clone())
+            .collect::<Vec<_>>();
+
+        let correct_answer = random_index(GTM_ANSWERS_COUNT);
+        answers.insert(correct_answer, track.clone());
+
+        let clip_length = self.difficulty.get_clip_length();
+        let duration = track.get_duration().to_std().unwrap_or_default();
+        let clip_starts = clip_starts_of(&track, clip_length)?;
+
+        let clip_start = match clip_starts.is_empty() {
+            true => Duration::ZERO,
+            false => clip_starts[random_index(clip_starts.len())],
+        };
+
+        let round = GtmRound {
+            track,
+            clip_start,
+            clip_length: clip_length.min(duration.saturating_sub(clip_start)),
+            answers,
+            correct_answer,
+        };
+
+        self.used_tracks.insert(round.track.get_path().clone());
+        self.round = Some((round.clone(), Instant::now()));
+        Ok(round)
+    }
+
+    /// Checks the answer of the current round and adds its points to the score
+    #[inline]
+    pub fn answer(&mut self, choice: usize) -> Result<GtmRoundResult> {
+        if choice >= GTM_ANSWERS_COUNT {
+            return Err(Error::Config(format!(
+                "Answer must be in range 0..{}",
+                GTM_ANSWERS_COUNT
+            )));
+        }
+
+        let (round, started_at) = self
+            .round
+            .take()
+            .ok_or_else(|| Error::Playback("There is no round to answer".to_string()))?;
+
+        let answer_time = started_at.elapsed();
+        let is_correct = choice == round.correct_answer;
+
+        let points = match is_correct {
+            false => 0,
+
+            true => {
+                let time_left = 1.0 - answer_time.as_secs_f32() / ANSWER_TIME_LIMIT.as_secs_f32();
+                let speed_points = (SPEED_POINTS as f32 * time_left.max(0.0)).round() as u32;
+                (BASE_POINTS + speed_points) * self.difficulty.get_score_multiplier()
+            }
+        };
+
+        let result = GtmRoundResult {
+            is_correct,
+            correct_answer: round.correct_answer,
+            points,
+            answer_time,
+        };
+
+        self.results.push(result);
+        Ok(result)
+    }
+
+    #[inline]
+    pub fn get_difficulty(&self) -> GtmDifficulty {
+        self.difficulty
+    }
+
+    #[inline]
+    pub fn get_rounds_count(&self) -> usize {
+        self.rounds_count
+    }
+
+    /// Round that waits for the answer
+    #[inline]
+    pub fn get_round(&self) -> Option<&GtmRound> {
+        self.round.as_ref().map(|(round, _)| round)
+    }
+
+    /// Results of the answered rounds
+    #[inline]
+    pub fn get_results(&self) -> &[GtmRoundResult] {
+        &self.results
+    }
+
+    #[inline]
+    pub fn get_score(&self) -> u32 {
+        self.results.iter().map(|result| result.points).sum()
+    }
+
+    #[inline]
+    pub fn get_correct_answers(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.is_correct)
+            .count()
+    }
+
+    #[inline]
+    pub fn is_over(&self) -> bool {
+        self.results.len() >= self.rounds_count
+    }
+}
+
+/// Answers are shown as titles, files without title show their names
+#[inline]
+fn answer_title(track: &DefaultTrack) -> String {
+    track
+        .get_title()
+        .filter(|title| !title.trim().is_empty())
+        .map(|title| title.trim().to_lowercase())
+        .unwrap_or_else(|| track.get_path().to_string_lossy().to_string())
+}
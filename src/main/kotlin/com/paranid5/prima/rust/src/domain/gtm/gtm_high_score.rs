@@ -0,0 +1,13 @@
+use crate::domain::gtm::gtm_difficulty::GtmDifficulty;
+
+/// Result of the finished game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GtmHighScore {
+    pub score: u32,
+    pub difficulty: GtmDifficulty,
+    pub rounds: u32,
+    pub correct_answers: u32,
+
+    /// Unix time in seconds
+    pub played_at: i64,
+}
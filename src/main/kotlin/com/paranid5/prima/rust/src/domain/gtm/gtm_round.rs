@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use crate::data::entities::tracks::default_track::DefaultTrack;
+
+/// Clip of the track to guess and the answers to choose from
+#[derive(Clone, Debug, PartialEq)]
+pub struct GtmRound {
+    pub track: DefaultTrack,
+    pub clip_start: Duration,
+    pub clip_length: Duration,
+
+    /// Guessed track and decoys in random order
+    pub answers: Vec<DefaultTrack>,
+
+    /// Index of the guessed track in [GtmRound::answers]
+    pub correct_answer: usize,
+}
+
+/// How the round was answered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GtmRoundResult {
+    pub is_correct: bool,
+    pub correct_answer: usize,
+    pub points: u32,
+
+    /// Time since the round has started
+    pub answer_time: Duration,
+}
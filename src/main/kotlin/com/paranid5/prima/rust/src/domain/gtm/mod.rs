@@ -0,0 +1,6 @@
+pub mod clip_fade;
+pub mod clip_finder;
+pub mod gtm_difficulty;
+pub mod gtm_game;
+pub mod gtm_high_score;
+pub mod gtm_round;
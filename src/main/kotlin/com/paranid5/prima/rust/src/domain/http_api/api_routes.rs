@@ -0,0 +1,562 @@
+use crate::{
+    data::{
+        entities::{
+            playlists::{
+                default_playlist::DefaultPlaylist, playlist_trait::PlaylistTrait,
+                playlist_type::PlaylistType,
+            },
+            tracks::{default_track::DefaultTrack, track_trait::TrackTrait},
+        },
+        utils::json::JsonValue,
+    },
+    domain::{
+        audio_player::playback_params::LoopingState,
+        bookmarks::bookmark::Bookmark,
+        events::player_event::PlayerEvent,
+        http_api::{
+            http_request::{bad_request, HttpRequest},
+            http_response::HttpResponse,
+        },
+        lyrics::lyrics::Lyrics,
+        metadata_reader::MetadataReader,
+        prima::Prima,
+    },
+    error::{Error, Result},
+};
+
+use std::{io::ErrorKind, path::Path, sync::Arc, time::Duration};
+
+#[inline]
+pub fn track_json(track: &DefaultTrack) -> JsonValue {
+    JsonValue::object([
+        ("path", track.get_path().to_string_lossy().as_ref().into()),
+        ("title", track.get_title().cloned().into()),
+        ("artist", track.get_artist().cloned().into()),
+        ("album", track.get_album().cloned().into()),
+        (
+            "duration_ms",
+            track.get_duration().num_milliseconds().into(),
+        ),
+        (
+            "number_in_album",
+            (track.get_number_in_album() as i64).into(),
+        ),
+    ])
+}
+
+#[inline]
+fn tracks_json(tracks: &[DefaultTrack]) -> JsonValue {
+    JsonValue::Array(tracks.iter().map(track_json).collect())
+}
+
+#[inline]
+fn looping_state_name(looping_state: LoopingState) -> &'static str {
+    match looping_state {
+        LoopingState::NoLooping => "none",
+        LoopingState::Track => "track",
+        LoopingState::Playlist => "playlist",
+    }
+}
+
+#[inline]
+fn bookmark_json(bookmark: &Bookmark) -> JsonValue {
+    JsonValue::object([
+        ("id", (bookmark.id as i64).into()),
+        ("path", bookmark.path.to_string_lossy().as_ref().into()),
+        ("position_ms", (bookmark.position.as_millis() as i64).into()),
+        ("name", bookmark.name.as_str().into()),
+        ("note", bookmark.note.clone().into()),
+        ("created_at", bookmark.created_at.into()),
+    ])
+}
+
+/// Lines of lyrics with the one that is sung at the position
+#[inline]
+fn lyrics_json(lyrics: &Lyrics, position: Duration) -> JsonValue {
+    let lines = lyrics
+        .get_lines()
+        .iter()
+        .map(|line| {
+            JsonValue::object([
+                ("time_ms", (line.time.as_millis() as i64).into()),
+                ("text", line.text.as_str().into()),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    JsonValue::object([
+        ("synced", lyrics.is_synced().into()),
+        ("offset_ms", lyrics.get_offset_millis().into()),
+        ("active_line", lyrics.active_line_index(position).into()),
+        ("lines", lines.into()),
+    ])
+}
+
+/// Event as it's sent to the WebSocket: `type` field and event's data
+#[inline]
+pub fn event_json(event: &PlayerEvent) -> JsonValue {
+    match event {
+        PlayerEvent::StateChanged { is_playing } => JsonValue::object([
+            ("type", "state_changed".into()),
+            ("is_playing", (*is_playing).into()),
+        ]),
+
+        PlayerEvent::TrackChanged { index, track } => JsonValue::object([
+            ("type", "track_changed".into()),
+            ("index", (*index).into()),
+            ("track", track.as_ref().map(track_json).into()),
+        ]),
+
+        PlayerEvent::PositionTick { position } => JsonValue::object([
+            ("type", "position".into()),
+            ("position_ms", (position.as_millis() as i64).into()),
+        ]),
+
+        PlayerEvent::QueueChanged { length, index } => JsonValue::object([
+            ("type", "queue_changed".into()),
+            ("length", (*length).into()),
+            ("index", (*index).into()),
+        ]),
+
+        PlayerEvent::LibraryUpdated { tracks_count } => JsonValue::object([
+            ("type", "library_updated".into()),
+            ("tracks_count", (*tracks_count).into()),
+        ]),
+
+        PlayerEvent::OutputDeviceChanged { device } => JsonValue::object([
+            ("type", "output_device_changed".into()),
+            ("device", device.clone().into()),
+        ]),
+
+        PlayerEvent::SleepTimerFinished => {
+            JsonValue::object([("type", "sleep_timer_finished".into())])
+        }
+
+        PlayerEvent::StreamTitleChanged { title } => JsonValue::object([
+            ("type", "stream_title_changed".into()),
+            ("title", title.clone().into()),
+        ]),
+
+        PlayerEvent::PodcastsUpdated { new_episodes } => JsonValue::object([
+            ("type", "podcasts_updated".into()),
+            ("new_episodes", (*new_episodes).into()),
+        ]),
+
+        PlayerEvent::Error { message } => JsonValue::object([
+            ("type", "error".into()),
+            ("message", message.as_str().into()),
+        ]),
+    }
+}
+
+/// Snapshot of the player, returned by `GET /api/state`
+/// and sent as the first message of the event stream
+#[inline]
+pub fn state_json(prima: &Prima) -> JsonValue {
+    let playlist = prima.get_cur_playlist();
+
+    JsonValue::object([
+        ("is_playing", prima.is_playing().into()),
+        (
+            "position_ms",
+            (prima.get_playback_position().as_millis() as i64).into(),
+        ),
+        ("volume", prima.get_volume().into()),
+        ("speed", prima.get_speed().into()),
+        (
+            "looping",
+            looping_state_name(prima.get_looping_state()).into(),
+        ),
+        ("shuffle", prima.is_shuffled().into()),
+        ("index", playlist.get_cur_ind().into()),
+        ("queue_length", playlist.len().into()),
+        ("track", playlist.get_cur_track().map(track_json).into()),
+    ])
+}
+
+/// Status code for the error returned by the session
+#[inline]
+fn error_status(error: &Error) -> u16 {
+    match error {
+        Error::Io(e) if e.kind() == ErrorKind::InvalidInput => 400,
+        Error::Playback(_) => 409,
+        _ => 500,
+    }
+}
+
+/// Playback is started in the background, so the request is answered at once
+#[inline]
+fn spawn_action<F>(prima: Arc<Prima>, action: F) -> Result<HttpResponse>
+where
+    F: FnOnce(&Prima) -> Result<()> + Send + 'static,
+{
+    Prima::spawn_action(prima, action)?;
+    Ok(HttpResponse::no_content())
+}
+
+#[inline]
+fn required<'a>(body: &'a JsonValue, key: &str) -> Result<&'a JsonValue> {
+    body.get(key)
+        .ok_or_else(|| bad_request(format!("Field `{}` is missing", key)))
+}
+
+#[inline]
+fn required_f64(body: &JsonValue, key: &str) -> Result<f64> {
+    required(body, key)?
+        .as_f64()
+        .filter(|x| x.is_finite())
+        .ok_or_else(|| bad_request(format!("Field `{}` must be a number", key)))
+}
+
+#[inline]
+fn required_bool(body: &JsonValue, key: &str) -> Result<bool> {
+    required(body, key)?
+        .as_bool()
+        .ok_or_else(|| bad_request(format!("Field `{}` must be a boolean", key)))
+}
+
+#[inline]
+fn required_str<'a>(body: &'a JsonValue, key: &str) -> Result<&'a str> {
+    required(body, key)?
+        .as_str()
+        .ok_or_else(|| bad_request(format!("Field `{}` must be a string", key)))
+}
+
+#[inline]
+fn optional_str<'a>(body: &'a JsonValue, key: &str) -> Result<Option<&'a str>> {
+    match body.get(key) {
+        None | Some(JsonValue::Null) => Ok(None),
+
+        Some(value) => value
+            .as_str()
+            .map(Some)
+            .ok_or_else(|| bad_request(format!("Field `{}` must be a string", key))),
+    }
+}
+
+#[inline]
+fn optional_index(body: &JsonValue, key: &str) -> Result<Option<usize>> {
+    match body.get(key) {
+        None | Some(JsonValue::Null) => Ok(None),
+
+        Some(value) => value
+            .as_f64()
+            .filter(|x| *x >= 0.0 && x.fract() == 0.0)
+            .map(|x| Some(x as usize))
+            .ok_or_else(|| bad_request(format!("Field `{}` must be an index", key))),
+    }
+}
+
+/// Reads tracks of `paths` field, unreadable files are rejected
+#[inline]
+fn read_tracks(body: &JsonValue) -> Result<Vec<DefaultTrack>> {
+    required(body, "paths")?
+        .as_array()
+        .ok_or_else(|| bad_request("Field `paths` must be an array"))?
+        .iter()
+        .map(|path| {
+            let path = path
+                .as_str()
+                .ok_or_else(|| bad_request("Paths must be strings"))?;
+
+            MetadataReader::read_track(Path::new(path))
+                .ok_or_else(|| bad_request(format!("Unable to read track {}", path)))
+        })
+        .collect()
+}
+
+#[inline]
+fn parse_index(segment: &str) -> Result<usize> {
+    segment
+        .parse()
+        .map_err(|_| bad_request(format!("Invalid index: {}", segment)))
+}
+
+#[inline]
+fn parse_id(segment: &str) -> Result<i32> {
+    segment
+        .parse()
+        .map_err(|_| bad_request(format!("Invalid id: {}", segment)))
+}
+
+#[inline]
+fn bookmark_not_found() -> HttpResponse {
+    HttpResponse::error(404, "There is no such bookmark")
+}
+
+/// Handles authorized request. Blocks on the session,
+/// so it must run outside of the session's runtime
+#[inline]
+pub fn handle(prima: Arc<Prima>, request: &HttpRequest) -> HttpResponse {
+    route(prima, request).unwrap_or_else(|e| HttpResponse::error(error_status(&e), e.to_string()))
+}
+
+#[inline]
+fn route(prima: Arc<Prima>, request: &HttpRequest) -> Result<HttpResponse> {
+    let segments = request.path_segments();
+    let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let path = match segments.split_first() {
+        Some((&"api", path)) => path,
+        _ => return Ok(HttpResponse::error(404, "Not found")),
+    };
+
+    let method = request.method.as_str();
+
+    match (method, path) {
+        ("GET", ["state"]) => Ok(HttpResponse::json(200, &state_json(&prima))),
+
+        ("POST", ["play-pause"]) => spawn_action(prima, |prima| prima.play_pause()),
+        ("POST", ["next"]) => spawn_action(prima, |prima| prima.next_track()),
+        ("POST", ["previous"]) => spawn_action(prima, |prima| prima.previous_track()),
+        ("POST", ["replay"]) => spawn_action(prima, |prima| prima.replay_cur_track()),
+
+        ("POST", ["play"]) => {
+            let body = request.json()?;
+
+            match body.get("paths") {
+                // New queue: `{"paths": [...], "index": 0}`
+                Some(_) => {
+                    let tracks = read_tracks(&body)?;
+                    let index = optional_index(&body, "index")?.unwrap_or_default();
+
+                    if index >= tracks.len() {
+                        return Err(bad_request("Index is out of the queue"));
+                    }
+
+                    spawn_action(prima, move |prima| {
+                        prima.play(DefaultPlaylist::new(
+                            None,
+                            PlaylistType::default(),
+                            tracks,
+                            index,
+                        ))
+                    })
+                }
+
+                // Resume the current track
+                None if prima.is_playing() => Ok(HttpResponse::no_content()),
+                None => spawn_action(prima, |prima| prima.play_pause()),
+            }
+        }
+
+        ("POST", ["pause"]) => {
+            prima.pause_playback();
+            Ok(HttpResponse::no_content())
+        }
+
+        ("POST", ["stop"]) => {
+            prima.stop_playback();
+            Ok(HttpResponse::no_content())
+        }
+
+        ("POST", ["seek"]) => {
+            let position = required_f64(&request.json()?, "position_ms")?.max(0.0);
+            let position = Duration::from_millis(position as u64);
+            spawn_action(prima, move |prima| prima.seek_to(position))
+        }
+
+        ("PUT", ["volume"]) => {
+            let volume = required_f64(&request.json()?, "volume")?;
+            prima.set_volume(volume.clamp(0.0, 1.0) as f32);
+            Ok(HttpResponse::no_content())
+        }
+
+        ("PUT", ["speed"]) => {
+            let speed = required_f64(&request.json()?, "speed")?;
+
+            if speed <= 0.0 {
+                return Err(bad_request("Speed must be positive"));
+            }
+
+            prima.set_speed(speed as f32);
+            Ok(HttpResponse::no_content())
+        }
+
+        ("PUT", ["looping"]) => {
+            let looping_state = match required_str(&request.json()?, "looping")? {
+                "none" => LoopingState::NoLooping,
+                "track" => LoopingState::Track,
+                "playlist" => LoopingState::Playlist,
+                other => return Err(bad_request(format!("Unknown looping state: {}", other))),
+            };
+
+            prima.set_looping_state(looping_state);
+            Ok(HttpResponse::no_content())
+        }
+
+        ("PUT", ["shuffle"]) => {
+            prima.set_shuffle(required_bool(&request.json()?, "shuffle")?);
+            Ok(HttpResponse::no_content())
+        }
+
+        ("GET", ["queue"]) => {
+            let playlist = prima.get_cur_playlist();
+
+            Ok(HttpResponse::json(
+                200,
+                &JsonValue::object([
+                    ("index", playlist.get_cur_ind().into()),
+                    ("tracks", tracks_json(playlist.get_tracks())),
+                ]),
+            ))
+        }
+
+        ("POST", ["queue"]) => {
+            let body = request.json()?;
+            prima.add_to_queue(read_tracks(&body)?, optional_index(&body, "position")?);
+            Ok(HttpResponse::no_content())
+        }
+
+        ("DELETE", ["queue", index]) => {
+            prima.remove_from_queue(parse_index(index)?)?;
+            Ok(HttpResponse::no_content())
+        }
+
+        ("POST", ["queue", index, "play"]) => {
+            let index = parse_index(index)?;
+
+            if index >= prima.get_cur_playlist().len() {
+                return Err(Error::Playback(format!("There is no track at {}", index)));
+            }
+
+            spawn_action(prima, move |prima| prima.play_at(index))
+        }
+
+        ("GET", ["lyrics"]) => match prima.get_cur_lyrics() {
+            None => Ok(HttpResponse::error(404, "Current track has no lyrics")),
+
+            Some(lyrics) => {
+                let position = prima.get_playback_position();
+                Ok(HttpResponse::json(200, &lyrics_json(&lyrics, position)))
+            }
+        },
+
+        ("GET", ["library", "tracks"]) => Ok(HttpResponse::json(
+            200,
+            &tracks_json(&prima.get_all_tracks()?),
+        )),
+
+        ("GET", ["library", "artists"]) => {
+            Ok(HttpResponse::json(200, &prima.get_all_artists()?.into()))
+        }
+
+        ("GET", ["library", "artists", artist, "tracks"]) => Ok(HttpResponse::json(
+            200,
+            &tracks_json(&prima.get_artist_tracks(artist)?),
+        )),
+
+        ("GET", ["favourites", "tracks"]) => {
+            let tracks = prima
+                .get_favourite_tracks()?
+                .into_iter()
+                .map(|track| track.into_default())
+                .collect::<Vec<_>>();
+
+            Ok(HttpResponse::json(200, &tracks_json(&tracks)))
+        }
+
+        ("GET", ["favourites", "artists"]) => {
+            let artists = prima
+                .get_favourite_artists()?
+                .into_iter()
+                .map(|artist| artist.into_string())
+                .collect::<Vec<_>>();
+
+            Ok(HttpResponse::json(200, &artists.into()))
+        }
+
+        ("PUT", ["favourites", "tracks"]) => {
+            let body = request.json()?;
+            let path = required_str(&body, "path")?;
+
+            let track = MetadataReader::read_track(Path::new(path))
+                .ok_or_else(|| bad_request(format!("Unable to read track {}", path)))?;
+
+            prima.set_track_liked(track, required_bool(&body, "liked")?)?;
+            Ok(HttpResponse::no_content())
+        }
+
+        ("PUT", ["favourites", "artists"]) => {
+            let body = request.json()?;
+            let artist = required_str(&body, "artist")?.to_string();
+
+            if prima.is_artist_liked(artist.clone())? != required_bool(&body, "liked")? {
+                prima.toggle_artist_liked(artist)?
+            }
+
+            Ok(HttpResponse::no_content())
+        }
+
+        ("GET", ["bookmarks"]) => {
+            let path = request.query_param("path").map(Path::new);
+
+            let bookmarks = prima
+                .get_bookmarks(path)?
+                .iter()
+                .map(bookmark_json)
+                .collect::<Vec<_>>();
+
+            Ok(HttpResponse::json(200, &bookmarks.into()))
+        }
+
+        // Bookmark at the current position: `{"name": "...", "note": "..."}`
+        ("POST", ["bookmarks"]) => {
+            let body = request.json()?;
+            let name = optional_str(&body, "name")?.unwrap_or_default();
+            let bookmark = prima.add_bookmark(name, optional_str(&body, "note")?)?;
+            Ok(HttpResponse::json(201, &bookmark_json(&bookmark)))
+        }
+
+        ("PUT", ["bookmarks", id]) => {
+            let id = parse_id(id)?;
+            let body = request.json()?;
+
+            match prima.update_bookmark(
+                id,
+                required_str(&body, "name")?,
+                optional_str(&body, "note")?,
+            )? {
+                true => Ok(HttpResponse::no_content()),
+                false => Ok(bookmark_not_found()),
+            }
+        }
+
+        ("DELETE", ["bookmarks", id]) => match prima.remove_bookmark(parse_id(id)?)? {
+            true => Ok(HttpResponse::no_content()),
+            false => Ok(bookmark_not_found()),
+        },
+
+        ("POST", ["bookmarks", id, "jump"]) => {
+            let id = parse_id(id)?;
+
+            if prima.get_bookmark(id)?.is_none() {
+                return Ok(bookmark_not_found());
+            }
+
+            spawn_action(prima, move |prima| prima.jump_to_bookmark(id))
+        }
+
+        _ if is_known_path(path) => Ok(HttpResponse::error(405, "Method not allowed")),
+        _ => Ok(HttpResponse::error(404, "Not found")),
+    }
+}
+
+/// Distinguishes wrong method from the unknown path
+#[inline]
+fn is_known_path(path: &[&str]) -> bool {
+    matches!(
+        path,
+        ["state" | "play-pause" | "play" | "pause" | "stop" | "next" | "previous" | "replay"]
+            | ["seek" | "volume" | "speed" | "looping" | "shuffle" | "queue" | "events"]
+            | ["lyrics"]
+            | ["queue", _]
+            | ["queue", _, "play"]
+            | ["library", "tracks" | "artists"]
+            | ["library", "artists", _, "tracks"]
+            | ["favourites", "tracks" | "artists"]
+            | ["bookmarks"]
+            | ["bookmarks", _]
+            | ["bookmarks", _, "jump"]
+    )
+}
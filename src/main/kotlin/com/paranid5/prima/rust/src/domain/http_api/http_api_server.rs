@@ -0,0 +1,318 @@
+extern crate tokio;
+
+use crate::{
+    data::utils::{json::JsonValue, types::TokioRuntime},
+    domain::{
+        http_api::{
+            api_routes::{self, event_json, state_json},
+            http_request::{bad_request, HttpRequest},
+            http_response::HttpResponse,
+            websocket::{
+                accept_key, read_frame, write_frame, WebSocketFrame, OPCODE_CLOSE, OPCODE_PING,
+                OPCODE_PONG, OPCODE_TEXT,
+            },
+        },
+        prima::Prima,
+    },
+    error::Result,
+};
+
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::Arc,
+};
+
+use tokio::{
+    io::{AsyncWriteExt, BufReader},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener, TcpStream,
+    },
+    runtime::Handle,
+    sync::{broadcast::error::RecvError, mpsc, watch},
+    task::{self, JoinHandle},
+};
+
+/// Client frames that are not handled yet
+const CLIENT_FRAMES_CAPACITY: usize = 8;
+
+/// Embedded HTTP/JSON server to control the player remotely,
+/// e.g. from a phone or a script. Requests must present the token
+/// as `Authorization: Bearer <token>` header or `token` query parameter.
+/// `GET /api/events` upgrades to a WebSocket with player's events.
+///
+/// Server runs on the session's runtime and stops when it's dropped
+pub struct HttpApiServer {
+    port: u16,
+    runtime: TokioRuntime,
+    accept_task: JoinHandle<()>,
+
+    /// Dropped with the server, which closes open connections
+    _shutdown: watch::Sender<()>,
+}
+
+impl HttpApiServer {
+    /// Listens at the given port, port 0 lets the system choose a free one.
+    /// Only local clients may connect unless `is_lan_enabled` is set,
+    /// then server listens on all interfaces
+    #[inline]
+    pub fn start(
+        prima: Arc<Prima>,
+        port: u16,
+        token: String,
+        is_lan_enabled: bool,
+    ) -> Result<Self> {
+        let address = match is_lan_enabled {
+            true => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            false => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        };
+
+        let listener = std::net::TcpListener::bind((address, port))?;
+        listener.set_nonblocking(true)?;
+
+        let port = listener.local_addr()?.port();
+        let runtime = prima.get_tokio_runtime();
+
+        let listener = {
+            let _guard = runtime.enter();
+            TcpListener::from_std(listener)?
+        };
+
+        let (shutdown, shutdown_rx) = watch::channel(());
+
+        let accept_task = runtime.spawn(accept_connections(
+            prima,
+            listener,
+            Arc::new(token),
+            shutdown_rx,
+        ));
+
+        Ok(Self {
+            port,
+            runtime,
+            accept_task,
+            _shutdown: shutdown,
+        })
+    }
+
+    #[inline]
+    pub fn get_port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for HttpApiServer {
+    #[inline]
+    fn drop(&mut self) {
+        self.accept_task.abort();
+
+        // Waiting for the listener to close lets the port be bound again at once,
+        // e.g. when the port is changed in settings
+        if Handle::try_current().is_err() {
+            let _ = self.runtime.block_on(&mut self.accept_task);
+        }
+    }
+}
+
+#[inline]
+async fn accept_connections(
+    prima: Arc<Prima>,
+    listener: TcpListener,
+    token: Arc<String>,
+    mut shutdown: watch::Receiver<()>,
+) {
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+
+                Err(e) => {
+                    eprintln!("HTTP API is unable to accept connection: {}", e);
+                    continue;
+                }
+            },
+
+            _ = shutdown.changed() => return,
+        };
+
+        tokio::spawn(serve_connection(
+            prima.clone(),
+            stream,
+            token.clone(),
+            shutdown.clone(),
+        ));
+    }
+}
+
+/// Compares in constant time, so the token can't be guessed by timing
+#[inline]
+fn is_token_valid(presented: &str, token: &str) -> bool {
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[inline]
+fn is_authorized(request: &HttpRequest, token: &str) -> bool {
+    request
+        .header("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| request.query_param("token"))
+        .map(|presented| is_token_valid(presented.trim(), token))
+        .unwrap_or(false)
+}
+
+/// Serves requests of the persistent connection until it's closed
+#[inline]
+async fn serve_connection(
+    prima: Arc<Prima>,
+    stream: TcpStream,
+    token: Arc<String>,
+    mut shutdown: watch::Receiver<()>,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    loop {
+        let request = tokio::select! {
+            request = HttpRequest::read_from(&mut reader) => request,
+            _ = shutdown.changed() => return,
+        };
+
+        let request = match request {
+            Ok(Some(request)) => request,
+            Ok(None) => return,
+
+            Err(e) => {
+                let response = HttpResponse::error(400, e.to_string());
+                let _ = writer.write_all(&response.to_bytes(false)).await;
+                return;
+            }
+        };
+
+        let is_keep_alive = request.is_keep_alive();
+
+        let response = match request.method.as_str() {
+            // CORS preflight of browsers carries no credentials
+            "OPTIONS" => HttpResponse::no_content()
+                .with_header("Access-Control-Allow-Methods", "GET, POST, PUT, DELETE")
+                .with_header(
+                    "Access-Control-Allow-Headers",
+                    "Authorization, Content-Type",
+                ),
+
+            _ if !is_authorized(&request, &token) => {
+                HttpResponse::error(401, "Missing or invalid token")
+                    .with_header("WWW-Authenticate", "Bearer")
+            }
+
+            "GET" if request.path == "/api/events" && request.is_websocket_upgrade() => {
+                if let Err(e) = serve_events(prima, reader, writer, &request, shutdown).await {
+                    eprintln!("HTTP API event stream is closed: {}", e)
+                }
+
+                return;
+            }
+
+            _ => {
+                let prima = prima.clone();
+
+                task::spawn_blocking(move || api_routes::handle(prima, &request))
+                    .await
+                    .unwrap_or_else(|e| HttpResponse::error(500, e.to_string()))
+            }
+        };
+
+        if writer
+            .write_all(&response.to_bytes(is_keep_alive))
+            .await
+            .is_err()
+            || !is_keep_alive
+        {
+            return;
+        }
+    }
+}
+
+/// Streams events as JSON text messages, the first one is the current state
+#[inline]
+async fn serve_events(
+    prima: Arc<Prima>,
+    reader: BufReader<OwnedReadHalf>,
+    mut writer: OwnedWriteHalf,
+    request: &HttpRequest,
+    mut shutdown: watch::Receiver<()>,
+) -> Result<()> {
+    let key = request
+        .header("Sec-WebSocket-Key")
+        .ok_or_else(|| bad_request("Sec-WebSocket-Key is missing"))?;
+
+    let response = HttpResponse::new(101)
+        .with_header("Upgrade", "websocket")
+        .with_header("Connection", "Upgrade")
+        .with_header("Sec-WebSocket-Accept", accept_key(key));
+
+    writer.write_all(&response.to_bytes(true)).await?;
+
+    // Subscribed before the snapshot, so no change is missed
+    let mut events = prima.get_event_bus().subscribe();
+
+    let mut state = task::spawn_blocking(move || state_json(&prima))
+        .await
+        .unwrap_or(JsonValue::Null);
+
+    if let JsonValue::Object(entries) = &mut state {
+        entries.insert(0, ("type".to_string(), "state".into()))
+    }
+
+    write_frame(&mut writer, OPCODE_TEXT, state.to_string().as_bytes()).await?;
+
+    // Reading is not cancel-safe, so frames are read by a separate task
+    let (frames_sender, mut frames) = mpsc::channel::<WebSocketFrame>(CLIENT_FRAMES_CAPACITY);
+
+    let reader_task = tokio::spawn(async move {
+        let mut reader = reader;
+
+        while let Ok(frame) = read_frame(&mut reader).await {
+            if frames_sender.send(frame).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let result = async {
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        let message = event_json(&event).to_string();
+                        write_frame(&mut writer, OPCODE_TEXT, message.as_bytes()).await?
+                    }
+
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+
+                frame = frames.recv() => match frame {
+                    Some(frame) if frame.opcode == OPCODE_PING => {
+                        write_frame(&mut writer, OPCODE_PONG, &frame.payload).await?
+                    }
+
+                    Some(frame) if frame.opcode != OPCODE_CLOSE => continue,
+                    _ => break,
+                },
+
+                _ = shutdown.changed() => break,
+            }
+        }
+
+        write_frame(&mut writer, OPCODE_CLOSE, &[]).await
+    }
+    .await;
+
+    reader_task.abort();
+    result
+}
@@ -0,0 +1,217 @@
+extern crate tokio;
+
+use crate::{
+    data::utils::json::JsonValue,
+    error::{Error, Result},
+};
+
+use std::io::{self, ErrorKind};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+
+/// Longer request and header lines are rejected
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+
+/// Bodies are small JSON documents, bigger ones are rejected
+const MAX_BODY_LENGTH: usize = 1024 * 1024;
+
+#[inline]
+pub fn bad_request(message: impl Into<String>) -> Error {
+    Error::Io(io::Error::new(ErrorKind::InvalidInput, message.into()))
+}
+
+/// Decodes `%XX` escapes, `+` is a space in query strings
+#[inline]
+pub fn percent_decode(text: &str, is_query: bool) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut input = text.bytes();
+
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' if is_query => bytes.push(b' '),
+
+            b'%' => {
+                let hex = [input.next().unwrap_or(b'0'), input.next().unwrap_or(b'0')];
+
+                let decoded = std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+                match decoded {
+                    Some(decoded) => bytes.push(decoded),
+                    None => bytes.extend_from_slice(&[b'%', hex[0], hex[1]]),
+                }
+            }
+
+            byte => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// HTTP/1.1 request of the remote-control API
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Reads the next request of the connection.
+    /// Returns None when client closed connection between requests
+    #[inline]
+    pub async fn read_from<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Self>> {
+        let request_line = match Self::read_line(reader).await? {
+            None => return Ok(None),
+            Some(line) => line,
+        };
+
+        let mut parts = request_line.split_whitespace();
+
+        let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+                (method.to_string(), target.to_string())
+            }
+
+            _ => {
+                return Err(bad_request(format!(
+                    "Invalid request line: {}",
+                    request_line
+                )))
+            }
+        };
+
+        let mut request = Self {
+            method,
+            ..Self::default()
+        };
+
+        let (path, query) = target.split_once('?').unwrap_or((target.as_str(), ""));
+        request.path = path.to_string();
+
+        request.query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode(key, true), percent_decode(value, true))
+            })
+            .collect();
+
+        loop {
+            let line = Self::read_line(reader)
+                .await?
+                .ok_or_else(|| bad_request("Connection closed inside of headers"))?;
+
+            if line.is_empty() {
+                break;
+            }
+
+            if request.headers.len() == MAX_HEADERS {
+                return Err(bad_request("Too many headers"));
+            }
+
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| bad_request(format!("Invalid header: {}", line)))?;
+
+            request
+                .headers
+                .push((name.trim().to_string(), value.trim().to_string()))
+        }
+
+        let body_length = request
+            .header("Content-Length")
+            .map(|length| length.parse::<usize>())
+            .transpose()
+            .map_err(|_| bad_request("Invalid Content-Length"))?
+            .unwrap_or_default();
+
+        if body_length > MAX_BODY_LENGTH {
+            return Err(bad_request("Request body is too big"));
+        }
+
+        request.body = vec![0; body_length];
+        reader.read_exact(&mut request.body).await?;
+        Ok(Some(request))
+    }
+
+    /// Reads line without CRLF, None on the end of stream
+    #[inline]
+    async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<String>> {
+        let mut line = Vec::new();
+        let read = (&mut *reader)
+            .take(MAX_LINE_LENGTH as u64 + 1)
+            .read_until(b'\n', &mut line)
+            .await?;
+
+        if read == 0 {
+            return Ok(None);
+        }
+
+        if line.len() > MAX_LINE_LENGTH || line.last() != Some(&b'\n') {
+            return Err(bad_request("Line is too long or truncated"));
+        }
+
+        let line = String::from_utf8(line).map_err(|_| bad_request("Line is not UTF-8"))?;
+        Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+    }
+
+    /// Value of the header, names are case-insensitive
+    #[inline]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[inline]
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Decoded non-empty segments of the path
+    #[inline]
+    pub fn path_segments(&self) -> Vec<String> {
+        self.path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| percent_decode(segment, false))
+            .collect()
+    }
+
+    /// Body parsed as JSON, empty body is an empty object
+    #[inline]
+    pub fn json(&self) -> Result<JsonValue> {
+        if self.body.iter().all(u8::is_ascii_whitespace) {
+            return Ok(JsonValue::Object(vec![]));
+        }
+
+        let text = std::str::from_utf8(&self.body).map_err(|_| bad_request("Body is not UTF-8"))?;
+        JsonValue::parse(text).map_err(|e| bad_request(format!("Invalid JSON: {}", e)))
+    }
+
+    /// HTTP/1.1 connections are persistent unless client asks to close them
+    #[inline]
+    pub fn is_keep_alive(&self) -> bool {
+        !self
+            .header("Connection")
+            .map(|value| value.eq_ignore_ascii_case("close"))
+            .unwrap_or(false)
+    }
+
+    #[inline]
+    pub fn is_websocket_upgrade(&self) -> bool {
+        self.header("Upgrade")
+            .map(|value| value.eq_ignore_ascii_case("websocket"))
+            .unwrap_or(false)
+    }
+}
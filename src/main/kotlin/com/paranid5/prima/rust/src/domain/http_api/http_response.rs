@@ -0,0 +1,104 @@
+use crate::data::utils::json::JsonValue;
+
+/// Response of the remote-control API
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpResponse {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+#[inline]
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        101 => "Switching Protocols",
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    }
+}
+
+impl HttpResponse {
+    #[inline]
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    #[inline]
+    pub fn json(status: u16, body: &JsonValue) -> Self {
+        Self {
+            body: body.to_string().into_bytes(),
+            ..Self::new(status)
+        }
+        .with_header("Content-Type", "application/json; charset=utf-8")
+    }
+
+    /// Successful action without content
+    #[inline]
+    pub fn no_content() -> Self {
+        Self::new(204)
+    }
+
+    /// Error with `{"error": message}` body
+    #[inline]
+    pub fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(
+            status,
+            &JsonValue::object([("error", JsonValue::String(message.into()))]),
+        )
+    }
+
+    #[inline]
+    pub fn with_header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    #[inline]
+    pub fn get_status(&self) -> u16 {
+        self.status
+    }
+
+    #[inline]
+    pub fn get_body(&self) -> &[u8] {
+        self.body.as_slice()
+    }
+
+    /// Serializes status line, headers and body.
+    /// Browsers on phones may call the API from other origins, so CORS is allowed
+    #[inline]
+    pub fn to_bytes(&self, is_keep_alive: bool) -> Vec<u8> {
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\n",
+            self.status,
+            reason_phrase(self.status)
+        );
+
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value))
+        }
+
+        if self.status != 101 {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+            head.push_str("Access-Control-Allow-Origin: *\r\n");
+
+            let connection = if is_keep_alive { "keep-alive" } else { "close" };
+            head.push_str(&format!("Connection: {}\r\n", connection))
+        }
+
+        head.push_str("\r\n");
+
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
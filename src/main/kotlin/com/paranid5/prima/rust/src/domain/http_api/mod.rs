@@ -0,0 +1,5 @@
+pub mod api_routes;
+pub mod http_api_server;
+pub mod http_request;
+pub mod http_response;
+pub mod websocket;
@@ -0,0 +1,185 @@
+extern crate tokio;
+
+use crate::{domain::http_api::http_request::bad_request, error::Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Appended to the client's key before hashing, see RFC 6455
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Clients only send control frames, so big payloads are rejected
+const MAX_PAYLOAD_LENGTH: u64 = 64 * 1024;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub const OPCODE_TEXT: u8 = 0x1;
+pub const OPCODE_CLOSE: u8 = 0x8;
+pub const OPCODE_PING: u8 = 0x9;
+pub const OPCODE_PONG: u8 = 0xA;
+
+/// Frame of WebSocket connection
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebSocketFrame {
+    pub opcode: u8,
+    pub payload: Vec<u8>,
+}
+
+/// Value of `Sec-WebSocket-Accept` for the client's `Sec-WebSocket-Key`
+#[inline]
+pub fn accept_key(key: &str) -> String {
+    base64_encode(&sha1(
+        format!("{}{}", key.trim(), HANDSHAKE_GUID).as_bytes(),
+    ))
+}
+
+/// Writes unmasked frame, as servers do
+#[inline]
+pub async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    opcode: u8,
+    payload: &[u8],
+) -> Result<()> {
+    let mut frame = vec![0x80 | opcode];
+
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes())
+        }
+
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes())
+        }
+    }
+
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame).await?;
+    Ok(())
+}
+
+/// Reads frame and unmasks its payload
+#[inline]
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<WebSocketFrame> {
+    let mut header = [0_u8; 2];
+    reader.read_exact(&mut header).await?;
+
+    let opcode = header[0] & 0x0F;
+    let is_masked = header[1] & 0x80 != 0;
+
+    let length = match header[1] & 0x7F {
+        126 => reader.read_u16().await? as u64,
+        127 => reader.read_u64().await?,
+        length => length as u64,
+    };
+
+    if length > MAX_PAYLOAD_LENGTH {
+        return Err(bad_request("WebSocket frame is too big"));
+    }
+
+    let mut mask = [0_u8; 4];
+
+    if is_masked {
+        reader.read_exact(&mut mask).await?;
+    }
+
+    let mut payload = vec![0_u8; length as usize];
+    reader.read_exact(&mut payload).await?;
+
+    payload
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, byte)| *byte ^= mask[i % 4]);
+
+    Ok(WebSocketFrame { opcode, payload })
+}
+
+#[inline]
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let triple = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => {
+                    encoded.push(BASE64_ALPHABET[(triple >> (18 - 6 * i) & 0x3F) as usize] as char)
+                }
+                false => encoded.push('='),
+            }
+        }
+    }
+
+    encoded
+}
+
+/// SHA-1 digest, used only by the handshake
+#[inline]
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+
+    while message.len() % 64 != 56 {
+        message.push(0)
+    }
+
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut words = [0_u32; 80];
+
+        for i in 0..16 {
+            words[i] = u32::from_be_bytes(block[i * 4..i * 4 + 4].try_into().unwrap())
+        }
+
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1)
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (value, added) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(added)
+        }
+    }
+
+    let mut digest = [0_u8; 20];
+
+    for (i, value) in state.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&value.to_be_bytes())
+    }
+
+    digest
+}
@@ -0,0 +1,317 @@
+use crate::domain::lyrics::lyrics_line::{LyricsLine, LyricsWord};
+
+use std::{fmt::Write, time::Duration};
+
+/// ID tag of LRC that shifts all timestamps
+const OFFSET_TAG: &str = "offset";
+
+/// Lyrics of the track: timed lines of LRC or SYLT,
+/// or plain text of USLT and `LYRICS` tags
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lyrics {
+    /// Sorted by time if lyrics are synchronized
+    lines: Vec<LyricsLine>,
+
+    /// ID tags of LRC, e.g. `ar`, `ti`, `al`, `by`
+    tags: Vec<(String, String)>,
+
+    /// Positive offset shows lines earlier
+    offset_millis: i64,
+    is_synced: bool,
+}
+
+impl Lyrics {
+    /// Synchronized lyrics, lines are sorted by time
+    #[inline]
+    pub fn synced(mut lines: Vec<LyricsLine>) -> Self {
+        lines.sort_by_key(|line| line.time);
+
+        Self {
+            lines,
+            is_synced: true,
+            ..Self::default()
+        }
+    }
+
+    /// Unsynchronized lyrics, every line of the text is a line of lyrics
+    #[inline]
+    pub fn plain(text: &str) -> Self {
+        Self {
+            lines: text
+                .lines()
+                .map(|line| LyricsLine::new(Duration::ZERO, line.trim_end().to_string()))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Parses LRC, including enhanced `<mm:ss.xx>` word timestamps.
+    /// Text without timestamps is parsed as plain lyrics
+    #[inline]
+    pub fn parse(text: &str) -> Self {
+        let mut lyrics = Self::default();
+        let mut plain_lines = Vec::new();
+
+        for line in text.lines() {
+            let line = line.trim();
+            let mut rest = line;
+            let mut times = Vec::new();
+
+            while let Some((group, tail)) =
+                rest.strip_prefix('[').and_then(|rest| rest.split_once(']'))
+            {
+                match parse_timestamp(group) {
+                    Some(time) => times.push(time),
+
+                    None if times.is_empty() => match group.split_once(':') {
+                        Some((key, value)) if tail.trim().is_empty() => {
+                            lyrics.set_tag(key.trim(), value.trim())
+                        }
+
+                        _ => break,
+                    },
+
+                    None => break,
+                }
+
+                rest = tail
+            }
+
+            match times.is_empty() {
+                true if rest.len() == line.len() => plain_lines.push(line),
+                true => {}
+
+                false => lyrics
+                    .lines
+                    .extend(times.into_iter().map(|time| parse_timed_text(time, rest))),
+            }
+        }
+
+        match lyrics.lines.is_empty() {
+            true => lyrics.lines = Self::plain(&plain_lines.join("\n")).lines,
+
+            false => {
+                lyrics.lines.sort_by_key(|line| line.time);
+                lyrics.is_synced = true
+            }
+        }
+
+        lyrics
+    }
+
+    #[inline]
+    pub fn get_lines(&self) -> &[LyricsLine] {
+        &self.lines
+    }
+
+    #[inline]
+    pub fn is_synced(&self) -> bool {
+        self.is_synced
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lines.iter().all(|line| line.text.is_empty())
+    }
+
+    #[inline]
+    pub fn get_tag(&self, key: &str) -> Option<&str> {
+        match key.eq_ignore_ascii_case(OFFSET_TAG) {
+            true => None,
+
+            false => self
+                .tags
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value.as_str()),
+        }
+    }
+
+    /// Replaces the tag if it's present, `offset` changes the offset
+    #[inline]
+    pub fn set_tag(&mut self, key: &str, value: &str) {
+        if key.eq_ignore_ascii_case(OFFSET_TAG) {
+            self.offset_millis = value.trim_start_matches('+').parse().unwrap_or_default();
+            return;
+        }
+
+        match self
+            .tags
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    #[inline]
+    pub fn get_offset_millis(&self) -> i64 {
+        self.offset_millis
+    }
+
+    #[inline]
+    pub fn set_offset_millis(&mut self, offset_millis: i64) {
+        self.offset_millis = offset_millis
+    }
+
+    /// Position of the track shifted by the offset of lyrics
+    #[inline]
+    fn lyrics_position(&self, position: Duration) -> Duration {
+        let millis = position.as_millis() as i64 + self.offset_millis;
+        Duration::from_millis(millis.max(0) as u64)
+    }
+
+    /// Index of the line that is sung at the track's position.
+    /// None before the first line and for unsynchronized lyrics
+    #[inline]
+    pub fn active_line_index(&self, position: Duration) -> Option<usize> {
+        if !self.is_synced {
+            return None;
+        }
+
+        let position = self.lyrics_position(position);
+
+        self.lines
+            .partition_point(|line| line.time <= position)
+            .checked_sub(1)
+    }
+
+    #[inline]
+    pub fn active_line(&self, position: Duration) -> Option<&LyricsLine> {
+        self.active_line_index(position)
+            .and_then(|index| self.lines.get(index))
+    }
+
+    /// Index of the word of the active line, if it has word timestamps
+    #[inline]
+    pub fn active_word_index(&self, position: Duration) -> Option<usize> {
+        self.active_line(position)?
+            .active_word_index(self.lyrics_position(position))
+    }
+
+    /// Formats lyrics as LRC. Lines of the same time are written separately,
+    /// unsynchronized lyrics are written as plain text
+    #[inline]
+    pub fn to_lrc(&self) -> String {
+        let mut lrc = String::new();
+
+        for (key, value) in &self.tags {
+            let _ = writeln!(lrc, "[{}:{}]", key, value);
+        }
+
+        if self.offset_millis != 0 {
+            let _ = writeln!(lrc, "[{}:{:+}]", OFFSET_TAG, self.offset_millis);
+        }
+
+        for line in &self.lines {
+            if !self.is_synced {
+                let _ = writeln!(lrc, "{}", line.text);
+                continue;
+            }
+
+            lrc.push_str(&format_timestamp('[', line.time, ']'));
+
+            match line.words.is_empty() {
+                true => lrc.push_str(&line.text),
+
+                false => line.words.iter().for_each(|word| {
+                    lrc.push_str(&format_timestamp('<', word.time, '>'));
+                    lrc.push_str(&word.text)
+                }),
+            }
+
+            lrc.push('\n')
+        }
+
+        lrc
+    }
+}
+
+/// Text of the timed line, `<mm:ss.xx>` markers split it into words.
+/// Text before the first marker starts with the line
+#[inline]
+fn parse_timed_text(time: Duration, text: &str) -> LyricsLine {
+    let mut words = Vec::new();
+    let mut word_time = time;
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        let stamp = rest[start + 1..]
+            .split_once('>')
+            .and_then(|(stamp, tail)| Some((parse_timestamp(stamp)?, tail)));
+
+        let Some((stamp, tail)) = stamp else {
+            break;
+        };
+
+        if start > 0 || !words.is_empty() {
+            words.push(LyricsWord {
+                time: word_time,
+                text: rest[..start].to_string(),
+            })
+        }
+
+        word_time = stamp;
+        rest = tail
+    }
+
+    match words.is_empty() && word_time == time && rest.len() == text.len() {
+        true => LyricsLine::new(time, text.trim().to_string()),
+
+        false => {
+            if !rest.is_empty() {
+                words.push(LyricsWord {
+                    time: word_time,
+                    text: rest.to_string(),
+                })
+            }
+
+            LyricsLine::with_words(time, words)
+        }
+    }
+}
+
+/// `mm:ss`, `mm:ss.xx` or `mm:ss.xxx`, some editors write `mm:ss:xx`
+#[inline]
+pub fn parse_timestamp(stamp: &str) -> Option<Duration> {
+    let (minutes, rest) = stamp.trim().split_once(':')?;
+    let minutes = minutes.parse::<u64>().ok()?;
+
+    let (seconds, fraction) = match rest.split_once(['.', ':']) {
+        Some((seconds, fraction)) => (seconds, fraction),
+        None => (rest, ""),
+    };
+
+    if seconds.len() != 2 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let seconds = seconds.parse::<u64>().ok()?;
+
+    let millis = match fraction.len() {
+        0 => 0,
+        1 => fraction.parse::<u64>().ok()? * 100,
+        2 => fraction.parse::<u64>().ok()? * 10,
+        _ => fraction[..3].parse::<u64>().ok()?,
+    };
+
+    Some(Duration::from_millis(
+        (minutes * 60 + seconds) * 1000 + millis,
+    ))
+}
+
+/// `[mm:ss.xx]` with the given brackets
+#[inline]
+fn format_timestamp(open: char, time: Duration, close: char) -> String {
+    let centis = time.as_millis() / 10;
+
+    format!(
+        "{}{:02}:{:02}.{:02}{}",
+        open,
+        centis / 6000,
+        centis / 100 % 60,
+        centis % 100,
+        close
+    )
+}
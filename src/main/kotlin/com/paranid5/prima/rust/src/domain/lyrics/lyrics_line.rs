@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+/// Word of the enhanced LRC line with the time it starts to be sung
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LyricsWord {
+    pub time: Duration,
+    pub text: String,
+}
+
+/// Line of lyrics with the time it starts to be sung.
+/// Unsynchronized lyrics have all times at zero.
+/// Words are present only in enhanced (word-level) LRC
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LyricsLine {
+    pub time: Duration,
+    pub text: String,
+    pub words: Vec<LyricsWord>,
+}
+
+impl LyricsLine {
+    #[inline]
+    pub fn new(time: Duration, text: String) -> Self {
+        Self {
+            time,
+            text,
+            words: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn with_words(time: Duration, words: Vec<LyricsWord>) -> Self {
+        Self {
+            time,
+            text: words
+                .iter()
+                .map(|w| w.text.as_str())
+                .collect::<String>()
+                .trim()
+                .to_string(),
+            words,
+        }
+    }
+
+    /// Index of the last word that started before the position
+    #[inline]
+    pub fn active_word_index(&self, position: Duration) -> Option<usize> {
+        self.words
+            .partition_point(|word| word.time <= position)
+            .checked_sub(1)
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod lyrics;
+pub mod lyrics_line;
+pub mod track_lyrics;
@@ -0,0 +1,74 @@
+use crate::{
+    domain::{lyrics::lyrics::Lyrics, metadata_reader::MetadataReader},
+    error::Result,
+};
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Extension of the sidecar lyrics file
+const LRC_EXTENSION: &str = "lrc";
+
+/// Finds lyrics of the track: `.lrc` file next to it
+/// or lyrics embedded into the file (ID3 SYLT/USLT, Vorbis `LYRICS`)
+#[derive(Debug)]
+pub struct TrackLyrics;
+
+impl TrackLyrics {
+    /// Reads lyrics of the track. Sidecar file is preferred,
+    /// because it's where edited lyrics are saved
+    #[inline]
+    pub fn read(track: &Path) -> Option<Lyrics> {
+        Self::read_sidecar(track)
+            .or_else(|| MetadataReader::read_lyrics(track))
+            .filter(|lyrics| !lyrics.is_empty())
+    }
+
+    /// `.lrc` file with the same name as the track's file
+    #[inline]
+    pub fn sidecar_path(track: &Path) -> PathBuf {
+        track.with_extension(LRC_EXTENSION)
+    }
+
+    #[inline]
+    pub fn read_sidecar(track: &Path) -> Option<Lyrics> {
+        let data = fs::read(Self::find_sidecar(track)?).ok()?;
+        let text = String::from_utf8_lossy(&data);
+        Some(Lyrics::parse(text.trim_start_matches('\u{feff}')))
+    }
+
+    /// Writes lyrics to the sidecar file,
+    /// so they are preferred to the embedded ones next time
+    #[inline]
+    pub fn save(track: &Path, lyrics: &Lyrics) -> Result<PathBuf> {
+        let path = Self::find_sidecar(track).unwrap_or_else(|| Self::sidecar_path(track));
+        fs::write(&path, lyrics.to_lrc())?;
+        Ok(path)
+    }
+
+    /// Sidecar file, its extension may be in any case, e.g. `.LRC`
+    #[inline]
+    fn find_sidecar(track: &Path) -> Option<PathBuf> {
+        let stem = track.file_stem()?;
+        let dir = track.parent()?;
+
+        let default = Self::sidecar_path(track);
+
+        if default.is_file() {
+            return Some(default);
+        }
+
+        fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .find(|path| {
+                path.file_stem() == Some(stem)
+                    && path
+                        .extension()
+                        .is_some_and(|e| e.eq_ignore_ascii_case(LRC_EXTENSION))
+                    && path.is_file()
+            })
+    }
+}
@@ -0,0 +1,656 @@
+extern crate chrono;
+extern crate claxon;
+extern crate hound;
+extern crate lewton;
+extern crate symphonia;
+
+use crate::{
+    domain::{
+        audio_player::decoders::{
+            aiff_decoder::AiffHeader,
+            audio_format::{AudioFormat, Container},
+            wavpack_decoder::WavPackInfo,
+        },
+        lyrics::{lyrics::Lyrics, lyrics_line::LyricsLine},
+    },
+    DefaultTrack,
+};
+use chrono::{DateTime, Duration, Local};
+use lewton::inside_ogg::OggStreamReader;
+
+use std::{
+    fs::{self, File},
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+    time,
+};
+
+use symphonia::core::{
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey},
+    probe::{Hint, ProbeResult},
+};
+
+/// Type of FLAC metadata block with picture
+const FLAC_PICTURE_BLOCK: u8 = 6;
+
+/// Picture type of the front cover in FLAC and ID3
+const FRONT_COVER_PICTURE: u32 = 3;
+
+/// Maximal size of Ogg page, the last page is searched in this range
+const MAX_OGG_PAGE_SIZE: u64 = 65307;
+
+/// Vorbis comments with lyrics, the first one found is used
+const VORBIS_LYRICS_TAGS: [&str; 2] = ["LYRICS", "UNSYNCEDLYRICS"];
+
+/// SYLT timestamps in milliseconds, MPEG frames are not supported
+const SYLT_MILLIS_FORMAT: u8 = 2;
+
+/// ID3v2 header flags
+const ID3_UNSYNCHRONISATION_FLAG: u8 = 0x80;
+const ID3_EXTENDED_HEADER_FLAG: u8 = 0x40;
+
+/// Tags and length of the track
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrackMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<i32>,
+    pub number_in_album: Option<i16>,
+    pub duration_millis: i64,
+}
+
+/// Picture embedded into the audio file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cover {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Cover {
+    /// Extension of the image file by its MIME type
+    #[inline]
+    pub fn get_extension(&self) -> &'static str {
+        match self.mime_type.as_str() {
+            "image/png" => "png",
+            "image/webp" => "webp",
+            "image/gif" => "gif",
+            _ => "jpg",
+        }
+    }
+}
+
+/// Reads tags without JVM: ID3 (MP3), MP4 atoms (AAC, ALAC), Vorbis comments (FLAC, Ogg Vorbis).
+/// Missing tags are empty strings, so tracks look the same
+/// as when they are read by the JVM's tag library
+#[derive(Debug)]
+pub struct MetadataReader;
+
+impl MetadataReader {
+    /// Reads track from the file.
+    /// Returns None if file is not a supported audio file
+    #[inline]
+    pub fn read_track(path: &Path) -> Option<DefaultTrack> {
+        let metadata = Self::read_metadata(path)?;
+
+        let add_date = fs::metadata(path)
+            .and_then(|m| m.created())
+            .map(DateTime::<Local>::from)
+            .unwrap_or_else(|_| Local::now());
+
+        Some(DefaultTrack::new(
+            Some(metadata.title.unwrap_or_default()),
+            Some(metadata.artist.unwrap_or_default()),
+            Some(metadata.album.unwrap_or_default()),
+            path.to_path_buf(),
+            Duration::milliseconds(metadata.duration_millis),
+            add_date,
+            metadata.number_in_album.unwrap_or_default(),
+        ))
+    }
+
+    /// Format is recognized by the same probe that chooses the player's decoder,
+    /// so files that can't be played are not read
+    #[inline]
+    pub fn read_metadata(path: &Path) -> Option<TrackMetadata> {
+        let format = AudioFormat::probe(path).ok()?;
+
+        if !format.is_supported() {
+            return None;
+        }
+
+        match format.container {
+            Container::Mpeg => Self::read_probed(path, "mp3"),
+            Container::Mp4 => Self::read_probed(path, "m4a"),
+            Container::Adts => Self::read_probed(path, "aac"),
+            Container::Flac => Self::read_flac(path),
+            Container::Ogg => Self::read_ogg(path),
+            Container::Wav => Self::read_wav(path),
+            Container::Aiff => Self::read_aiff(path),
+            Container::WavPack => Self::read_wavpack(path),
+        }
+    }
+
+    /// Reads picture embedded into MP3 (ID3 APIC), MP4 (covr) or FLAC (PICTURE block).
+    /// Front cover is preferred if there are several pictures
+    #[inline]
+    pub fn read_cover(path: &Path) -> Option<Cover> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+
+        match extension.as_str() {
+            "mp3" | "m4a" | "mp4" => Self::read_probed_cover(path, &extension),
+            "flac" => read_flac_picture(path),
+            _ => None,
+        }
+    }
+
+    /// Reads lyrics embedded into MP3 (ID3 SYLT is preferred to USLT),
+    /// FLAC or Ogg Vorbis (`LYRICS` and `UNSYNCEDLYRICS` comments).
+    /// Text of tags may be LRC, it's parsed with timestamps then
+    #[inline]
+    pub fn read_lyrics(path: &Path) -> Option<Lyrics> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+
+        match extension.as_str() {
+            "mp3" => read_id3_synced_lyrics(path).or_else(|| Self::read_mp3_lyrics(path)),
+
+            "flac" => {
+                let reader = claxon::FlacReader::open(path).ok()?;
+
+                VORBIS_LYRICS_TAGS
+                    .iter()
+                    .find_map(|name| reader.get_tag(name).next().map(Lyrics::parse))
+            }
+
+            "ogg" | "oga" => {
+                let reader = OggStreamReader::new(BufReader::new(File::open(path).ok()?)).ok()?;
+
+                VORBIS_LYRICS_TAGS.iter().find_map(|name| {
+                    reader
+                        .comment_hdr
+                        .comment_list
+                        .iter()
+                        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                        .map(|(_, value)| Lyrics::parse(value))
+                })
+            }
+
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn read_mp3_lyrics(path: &Path) -> Option<Lyrics> {
+        let mut probed = Self::probe(path, "mp3")?;
+
+        let find_lyrics = |revision: &MetadataRevision| {
+            revision
+                .tags()
+                .iter()
+                .find(|tag| tag.std_key == Some(StandardTagKey::Lyrics))
+                .map(|tag| Lyrics::parse(&tag.value.to_string()))
+        };
+
+        probed
+            .metadata
+            .get()
+            .as_ref()
+            .and_then(|m| m.current().and_then(find_lyrics))
+            .or_else(|| probed.format.metadata().current().and_then(find_lyrics))
+    }
+
+    /// Opens the file with Symphonia's format reader,
+    /// extension is the hint for the container
+    #[inline]
+    fn probe(path: &Path, extension: &str) -> Option<ProbeResult> {
+        let source = MediaSourceStream::new(Box::new(File::open(path).ok()?), Default::default());
+
+        let mut hint = Hint::new();
+        hint.with_extension(extension);
+
+        symphonia::default::get_probe()
+            .format(
+                &hint,
+                source,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .ok()
+    }
+
+    #[inline]
+    fn read_probed_cover(path: &Path, extension: &str) -> Option<Cover> {
+        let mut probed = Self::probe(path, extension)?;
+
+        let mut visuals = probed
+            .metadata
+            .get()
+            .as_ref()
+            .and_then(|m| m.current().map(|r| r.visuals().to_vec()))
+            .unwrap_or_default();
+
+        if let Some(revision) = probed.format.metadata().current() {
+            visuals.extend_from_slice(revision.visuals())
+        }
+
+        let visual = visuals
+            .iter()
+            .find(|v| v.usage == Some(StandardVisualKey::FrontCover))
+            .or_else(|| visuals.first())?;
+
+        Some(Cover {
+            mime_type: visual.media_type.clone(),
+            data: visual.data.to_vec(),
+        })
+    }
+
+    #[inline]
+    fn read_probed(path: &Path, extension: &str) -> Option<TrackMetadata> {
+        let mut probed = Self::probe(path, extension)?;
+
+        let mut metadata = TrackMetadata::default();
+
+        // ID3v2 is read by the probe, ID3v1, APE and MP4 tags by the format reader
+        if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+            Self::apply_revision(&mut metadata, revision)
+        }
+
+        if let Some(revision) = probed.format.metadata().current() {
+            Self::apply_revision(&mut metadata, revision)
+        }
+
+        let params = probed.format.default_track()?.codec_params.clone();
+        let sample_rate = params.sample_rate? as i64;
+
+        let frames = match params.n_frames {
+            Some(frames) => frames,
+
+            // Without Xing header MP3 and ADTS length is known only after reading all packets
+            None => {
+                let mut frames = 0;
+
+                while let Ok(packet) = probed.format.next_packet() {
+                    frames += packet.dur;
+                }
+
+                frames
+            }
+        };
+
+        metadata.duration_millis = frames as i64 * 1000 / sample_rate;
+        Some(metadata)
+    }
+
+    #[inline]
+    fn apply_revision(metadata: &mut TrackMetadata, revision: &MetadataRevision) {
+        for tag in revision.tags() {
+            let value = tag.value.to_string();
+
+            match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => metadata.title = Some(value),
+                Some(StandardTagKey::Artist) => metadata.artist = Some(value),
+                Some(StandardTagKey::Album) => metadata.album = Some(value),
+                Some(StandardTagKey::AlbumArtist) => metadata.album_artist = Some(value),
+                Some(StandardTagKey::Genre) => metadata.genre = Some(value),
+                Some(StandardTagKey::Date) => metadata.year = parse_year(&value),
+
+                Some(StandardTagKey::TrackNumber) => {
+                    metadata.number_in_album = parse_track_number(&value)
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    #[inline]
+    fn read_flac(path: &Path) -> Option<TrackMetadata> {
+        let reader = claxon::FlacReader::open(path).ok()?;
+        let info = reader.streaminfo();
+        let tag = |name| reader.get_tag(name).next().map(String::from);
+
+        Some(TrackMetadata {
+            title: tag("TITLE"),
+            artist: tag("ARTIST"),
+            album: tag("ALBUM"),
+            album_artist: tag("ALBUMARTIST"),
+            genre: tag("GENRE"),
+            year: tag("DATE").and_then(|date| parse_year(&date)),
+            number_in_album: tag("TRACKNUMBER").and_then(|n| parse_track_number(&n)),
+            duration_millis: (info.samples.unwrap_or_default() * 1000 / info.sample_rate as u64)
+                as i64,
+        })
+    }
+
+    #[inline]
+    fn read_ogg(path: &Path) -> Option<TrackMetadata> {
+        let reader = OggStreamReader::new(BufReader::new(File::open(path).ok()?)).ok()?;
+        let sample_rate = reader.ident_hdr.audio_sample_rate as u64;
+
+        let tag = |name: &str| {
+            reader
+                .comment_hdr
+                .comment_list
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+        };
+
+        let samples = last_ogg_granule_position(path).unwrap_or_default();
+
+        Some(TrackMetadata {
+            title: tag("TITLE"),
+            artist: tag("ARTIST"),
+            album: tag("ALBUM"),
+            album_artist: tag("ALBUMARTIST"),
+            genre: tag("GENRE"),
+            year: tag("DATE").and_then(|date| parse_year(&date)),
+            number_in_album: tag("TRACKNUMBER").and_then(|n| parse_track_number(&n)),
+            duration_millis: (samples * 1000 / sample_rate.max(1)) as i64,
+        })
+    }
+
+    #[inline]
+    fn read_wav(path: &Path) -> Option<TrackMetadata> {
+        let reader = hound::WavReader::open(path).ok()?;
+        let sample_rate = reader.spec().sample_rate as u64;
+
+        Some(TrackMetadata {
+            title: path.file_stem().map(|s| s.to_string_lossy().to_string()),
+            duration_millis: (reader.duration() as u64 * 1000 / sample_rate.max(1)) as i64,
+            ..TrackMetadata::default()
+        })
+    }
+
+    #[inline]
+    fn read_aiff(path: &Path) -> Option<TrackMetadata> {
+        let header = AiffHeader::read(&mut BufReader::new(File::open(path).ok()?)).ok()?;
+
+        Some(TrackMetadata {
+            title: path.file_stem().map(|s| s.to_string_lossy().to_string()),
+            duration_millis: header.get_duration().as_millis() as i64,
+            ..TrackMetadata::default()
+        })
+    }
+
+    /// APEv2 tags at the end of WavPack files are not read
+    #[inline]
+    fn read_wavpack(path: &Path) -> Option<TrackMetadata> {
+        let info = WavPackInfo::read(&mut BufReader::new(File::open(path).ok()?)).ok()?;
+
+        Some(TrackMetadata {
+            title: path.file_stem().map(|s| s.to_string_lossy().to_string()),
+            duration_millis: info.get_duration().unwrap_or_default().as_millis() as i64,
+            ..TrackMetadata::default()
+        })
+    }
+}
+
+/// Track numbers are often stored as `3/12`
+#[inline]
+fn parse_track_number(value: &str) -> Option<i16> {
+    value.split('/').next()?.trim().parse().ok()
+}
+
+/// Dates are stored as `2004`, `2004-05-17` or `2004-05-17T12:00:00`
+#[inline]
+fn parse_year(value: &str) -> Option<i32> {
+    value.trim().get(..4)?.parse().ok()
+}
+
+/// Granule position of the last Ogg page is the number of samples in the stream
+#[inline]
+fn last_ogg_granule_position(path: &Path) -> Option<u64> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let start = len.saturating_sub(MAX_OGG_PAGE_SIZE);
+
+    file.seek(SeekFrom::Start(start)).ok()?;
+
+    let mut tail = Vec::with_capacity((len - start) as usize);
+    file.read_to_end(&mut tail).ok()?;
+
+    let page = tail.windows(4).rposition(|w| w == b"OggS")?;
+    let granule = tail.get(page + 6..page + 14)?;
+    Some(u64::from_le_bytes(granule.try_into().ok()?))
+}
+
+/// Reads PICTURE metadata blocks of FLAC file, prefers the front cover
+#[inline]
+fn read_flac_picture(path: &Path) -> Option<Cover> {
+    let mut file = BufReader::new(File::open(path).ok()?);
+    let mut marker = [0_u8; 4];
+    file.read_exact(&mut marker).ok()?;
+
+    if &marker != b"fLaC" {
+        return None;
+    }
+
+    let mut found = None;
+
+    loop {
+        let mut header = [0_u8; 4];
+        file.read_exact(&mut header).ok()?;
+
+        let is_last = header[0] & 0x80 != 0;
+        let block_type = header[0] & 0x7F;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+
+        if block_type != FLAC_PICTURE_BLOCK {
+            file.seek_relative(len as i64).ok()?;
+        } else {
+            let mut block = vec![0_u8; len];
+            file.read_exact(&mut block).ok()?;
+
+            if let Some((picture_type, cover)) = parse_flac_picture(&block) {
+                if picture_type == FRONT_COVER_PICTURE {
+                    return Some(cover);
+                }
+
+                found.get_or_insert(cover);
+            }
+        }
+
+        if is_last {
+            return found;
+        }
+    }
+}
+
+#[inline]
+fn parse_flac_picture(block: &[u8]) -> Option<(u32, Cover)> {
+    let mut pos = 0;
+
+    let next_u32 = |pos: &mut usize| {
+        let value = u32::from_be_bytes(block.get(*pos..*pos + 4)?.try_into().ok()?);
+        *pos += 4;
+        Some(value)
+    };
+
+    let picture_type = next_u32(&mut pos)?;
+
+    let mime_len = next_u32(&mut pos)? as usize;
+    let mime_type = String::from_utf8_lossy(block.get(pos..pos + mime_len)?).to_string();
+    pos += mime_len;
+
+    let description_len = next_u32(&mut pos)? as usize;
+    pos += description_len;
+
+    // Width, height, color depth and number of colors
+    pos += 16;
+
+    let data_len = next_u32(&mut pos)? as usize;
+    let data = block.get(pos..pos + data_len)?.to_vec();
+
+    Some((picture_type, Cover { mime_type, data }))
+}
+
+/// Reads SYLT frame of ID3v2.3 or ID3v2.4 tag.
+/// Every synchronized text item becomes a line of lyrics
+#[inline]
+fn read_id3_synced_lyrics(path: &Path) -> Option<Lyrics> {
+    let mut file = BufReader::new(File::open(path).ok()?);
+    let mut header = [0_u8; 10];
+    file.read_exact(&mut header).ok()?;
+
+    let version = header[3];
+
+    if &header[..3] != b"ID3" || !(3..=4).contains(&version) {
+        return None;
+    }
+
+    let mut tag = vec![0_u8; synchsafe_u32(&header[6..10]) as usize];
+    file.read_exact(&mut tag).ok()?;
+
+    if header[5] & ID3_UNSYNCHRONISATION_FLAG != 0 {
+        tag = remove_unsynchronisation(&tag)
+    }
+
+    let mut pos = 0;
+
+    if header[5] & ID3_EXTENDED_HEADER_FLAG != 0 {
+        let size = tag.get(0..4)?;
+
+        pos = match version {
+            3 => u32::from_be_bytes(size.try_into().ok()?) as usize + 4,
+            _ => synchsafe_u32(size) as usize,
+        };
+    }
+
+    while let Some(frame_header) = tag.get(pos..pos + 10) {
+        let id = &frame_header[..4];
+
+        // Padding after the last frame
+        if id[0] == 0 {
+            return None;
+        }
+
+        let size = match version {
+            3 => u32::from_be_bytes(frame_header[4..8].try_into().ok()?),
+            _ => synchsafe_u32(&frame_header[4..8]),
+        } as usize;
+
+        let frame = tag.get(pos + 10..pos + 10 + size)?;
+
+        if id == b"SYLT" {
+            return parse_sylt_frame(frame);
+        }
+
+        pos += 10 + size
+    }
+
+    None
+}
+
+/// 7 bits of every byte are used, so the size doesn't look like a sync word
+#[inline]
+fn synchsafe_u32(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0, |acc, byte| (acc << 7) | (*byte & 0x7F) as u32)
+}
+
+/// Unsynchronised tag has zero byte inserted after every 0xFF
+#[inline]
+fn remove_unsynchronisation(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len());
+
+    for (i, byte) in data.iter().enumerate() {
+        if *byte == 0 && i > 0 && data[i - 1] == 0xFF {
+            continue;
+        }
+
+        result.push(*byte)
+    }
+
+    result
+}
+
+#[inline]
+fn parse_sylt_frame(frame: &[u8]) -> Option<Lyrics> {
+    let encoding = *frame.first()?;
+
+    // Language is skipped
+    if *frame.get(4)? != SYLT_MILLIS_FORMAT {
+        return None;
+    }
+
+    // Content type is skipped, then the content descriptor
+    let (_, mut rest) = split_id3_text(frame.get(6..)?, encoding)?;
+    let mut lines = Vec::new();
+
+    while !rest.is_empty() {
+        let (text, tail) = split_id3_text(rest, encoding)?;
+        let millis = u32::from_be_bytes(tail.get(..4)?.try_into().ok()?);
+
+        lines.push(LyricsLine::new(
+            time::Duration::from_millis(millis as u64),
+            text.trim().to_string(),
+        ));
+
+        rest = &tail[4..]
+    }
+
+    Some(Lyrics::synced(lines))
+}
+
+/// Splits the null-terminated string of the given ID3 encoding
+#[inline]
+fn split_id3_text(data: &[u8], encoding: u8) -> Option<(String, &[u8])> {
+    match encoding {
+        // Latin-1 and UTF-8
+        0 | 3 => {
+            let end = data.iter().position(|b| *b == 0)?;
+
+            let text = match encoding {
+                0 => data[..end].iter().map(|b| *b as char).collect(),
+                _ => String::from_utf8_lossy(&data[..end]).to_string(),
+            };
+
+            Some((text, &data[end + 1..]))
+        }
+
+        // UTF-16 with BOM and UTF-16BE
+        1 | 2 => {
+            let end = (0..data.len() / 2)
+                .map(|i| i * 2)
+                .find(|i| data[*i] == 0 && data[*i + 1] == 0)?;
+
+            let mut units = data[..end].chunks_exact(2);
+            let mut is_little_endian = false;
+
+            let bom = match encoding {
+                1 => units.clone().next(),
+                _ => None,
+            };
+
+            match bom {
+                Some([0xFF, 0xFE]) => {
+                    is_little_endian = true;
+                    units.next();
+                }
+
+                Some([0xFE, 0xFF]) => {
+                    units.next();
+                }
+
+                _ => {}
+            }
+
+            let units = units
+                .map(|unit| match is_little_endian {
+                    true => u16::from_le_bytes([unit[0], unit[1]]),
+                    false => u16::from_be_bytes([unit[0], unit[1]]),
+                })
+                .collect::<Vec<_>>();
+
+            Some((String::from_utf16_lossy(&units), &data[end + 2..]))
+        }
+
+        _ => None,
+    }
+}
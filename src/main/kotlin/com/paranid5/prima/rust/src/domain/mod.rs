@@ -1,3 +1,22 @@
+pub mod analysis;
+pub mod audio_export;
 pub mod audio_player;
 pub mod audio_scanner;
+pub mod auto_dj;
+pub mod bookmarks;
+pub mod cover_art;
+pub mod events;
+pub mod gtm;
+pub mod http_api;
+pub mod lyrics;
+pub mod metadata_reader;
+pub mod mpd;
+#[cfg(target_os = "linux")]
+pub mod mpris;
+pub mod podcasts;
+pub mod prima;
+pub mod radio;
+pub mod scrobbling;
+pub mod sleep_timer;
+pub mod state_store;
 pub mod storage_util;
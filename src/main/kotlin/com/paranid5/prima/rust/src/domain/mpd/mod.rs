@@ -0,0 +1,7 @@
+pub mod mpd_command;
+pub mod mpd_context;
+pub mod mpd_filter;
+pub mod mpd_library;
+pub mod mpd_response;
+pub mod mpd_server;
+pub mod mpd_session;
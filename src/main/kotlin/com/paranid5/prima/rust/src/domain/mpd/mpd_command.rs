@@ -0,0 +1,188 @@
+use crate::error::Error;
+
+use std::{
+    fmt::{Display, Formatter},
+    io::ErrorKind,
+    str::FromStr,
+};
+
+pub const ACK_ERROR_ARG: u32 = 2;
+pub const ACK_ERROR_PASSWORD: u32 = 3;
+pub const ACK_ERROR_PERMISSION: u32 = 4;
+pub const ACK_ERROR_UNKNOWN: u32 = 5;
+pub const ACK_ERROR_NO_EXIST: u32 = 50;
+pub const ACK_ERROR_SYSTEM: u32 = 52;
+
+/// Failed command, sent to the client as `ACK [code@index] {command} message`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MpdError {
+    pub code: u32,
+    pub message: String,
+}
+
+impl MpdError {
+    #[inline]
+    pub fn new(code: u32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    #[inline]
+    pub fn arg(message: impl Into<String>) -> Self {
+        Self::new(ACK_ERROR_ARG, message)
+    }
+
+    #[inline]
+    pub fn no_exist(message: impl Into<String>) -> Self {
+        Self::new(ACK_ERROR_NO_EXIST, message)
+    }
+
+    /// Line of the protocol, `index` is the position in the command list
+    #[inline]
+    pub fn to_ack(&self, index: usize, command: &str) -> String {
+        format!(
+            "ACK [{}@{}] {{{}}} {}\n",
+            self.code, index, command, self.message
+        )
+    }
+}
+
+impl Display for MpdError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<Error> for MpdError {
+    #[inline]
+    fn from(error: Error) -> Self {
+        match &error {
+            Error::Io(e) if e.kind() == ErrorKind::InvalidInput => Self::arg(error.to_string()),
+            Error::Io(e) if e.kind() == ErrorKind::NotFound => Self::no_exist(error.to_string()),
+            Error::Playback(_) => Self::no_exist(error.to_string()),
+            _ => Self::new(ACK_ERROR_SYSTEM, error.to_string()),
+        }
+    }
+}
+
+/// Command line of the client: name and its arguments.
+/// Arguments may be quoted with `"`, backslash escapes `"` and `\` inside quotes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MpdCommand {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl MpdCommand {
+    #[inline]
+    pub fn parse(line: &str) -> Result<Self, MpdError> {
+        let mut words = Vec::new();
+        let mut chars = line.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+            match chars.peek() {
+                None => break,
+
+                Some('"') => {
+                    chars.next();
+                    let mut word = String::new();
+
+                    loop {
+                        match chars.next() {
+                            None => return Err(MpdError::arg("Missing closing '\"'")),
+                            Some('"') => break,
+
+                            Some('\\') => match chars.next() {
+                                Some(c) => word.push(c),
+                                None => return Err(MpdError::arg("Missing closing '\"'")),
+                            },
+
+                            Some(c) => word.push(c),
+                        }
+                    }
+
+                    words.push(word)
+                }
+
+                Some(_) => {
+                    let mut word = String::new();
+
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                        word.push(c)
+                    }
+
+                    words.push(word)
+                }
+            }
+        }
+
+        if words.is_empty() {
+            return Err(MpdError::new(ACK_ERROR_UNKNOWN, "No command given"));
+        }
+
+        let name = words.remove(0);
+        Ok(Self { name, args: words })
+    }
+
+    #[inline]
+    pub fn arg(&self, index: usize) -> Result<&str, MpdError> {
+        self.args
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| MpdError::arg("Too few arguments"))
+    }
+
+    #[inline]
+    pub fn parse_arg<T: FromStr>(&self, index: usize) -> Result<T, MpdError> {
+        let arg = self.arg(index)?;
+
+        arg.parse()
+            .map_err(|_| MpdError::arg(format!("Invalid argument: {}", arg)))
+    }
+
+    /// `0` or `1` argument
+    #[inline]
+    pub fn bool_arg(&self, index: usize) -> Result<bool, MpdError> {
+        match self.arg(index)? {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            arg => Err(MpdError::arg(format!("Boolean (0/1) expected: {}", arg))),
+        }
+    }
+
+    /// `POS`, `START:END` or `START:` argument as the range of positions.
+    /// Absent argument is the whole range
+    #[inline]
+    pub fn range_arg(&self, index: usize, len: usize) -> Result<(usize, usize), MpdError> {
+        let arg = match self.args.get(index) {
+            None => return Ok((0, len)),
+            Some(arg) => arg.as_str(),
+        };
+
+        let invalid = || MpdError::arg(format!("Invalid range: {}", arg));
+
+        let (start, end) = match arg.split_once(':') {
+            None => {
+                let position = arg.parse::<usize>().map_err(|_| invalid())?;
+                (position, position + 1)
+            }
+
+            Some((start, "")) => (start.parse().map_err(|_| invalid())?, len),
+
+            Some((start, end)) => (
+                start.parse().map_err(|_| invalid())?,
+                end.parse().map_err(|_| invalid())?,
+            ),
+        };
+
+        match start <= end {
+            true => Ok((start, end)),
+            false => Err(invalid()),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+extern crate tokio;
+
+use crate::{
+    domain::{events::player_event::PlayerEvent, mpd::mpd_library::MpdLibrary, prima::Prima},
+    error::Result,
+};
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+/// Changes kept for clients that are busy with other commands
+const CHANGES_CAPACITY: usize = 64;
+
+/// State shared by all connections of the MPD server
+pub struct MpdContext {
+    prima: Arc<Prima>,
+    password: Option<String>,
+    start_time: Instant,
+
+    /// Scanned lazily, because scanning reads tags of every file
+    library: Mutex<Option<Arc<MpdLibrary>>>,
+
+    /// Names of changed subsystems, as `idle` reports them
+    changes: Sender<&'static str>,
+}
+
+impl MpdContext {
+    #[inline]
+    pub fn new(prima: Arc<Prima>, password: Option<String>) -> Self {
+        let (changes, _) = broadcast::channel(CHANGES_CAPACITY);
+
+        Self {
+            prima,
+            password,
+            start_time: Instant::now(),
+            library: Mutex::new(None),
+            changes,
+        }
+    }
+
+    #[inline]
+    pub fn get_prima(&self) -> &Arc<Prima> {
+        &self.prima
+    }
+
+    #[inline]
+    pub fn get_password(&self) -> Option<&String> {
+        self.password.as_ref()
+    }
+
+    #[inline]
+    pub fn get_start_time(&self) -> Instant {
+        self.start_time
+    }
+
+    #[inline]
+    pub fn subscribe(&self) -> Receiver<&'static str> {
+        self.changes.subscribe()
+    }
+
+    /// Wakes up idling clients that wait for the subsystem
+    #[inline]
+    pub fn notify(&self, subsystem: &'static str) {
+        let _ = self.changes.send(subsystem);
+    }
+
+    /// Library of the last scan, music folder is scanned on the first call
+    #[inline]
+    pub fn get_library(&self) -> Result<Arc<MpdLibrary>> {
+        if let Some(library) = self.library.lock().unwrap().as_ref() {
+            return Ok(library.clone());
+        }
+
+        self.rescan()
+    }
+
+    #[inline]
+    pub fn rescan(&self) -> Result<Arc<MpdLibrary>> {
+        let library = Arc::new(MpdLibrary::scan(&self.prima)?);
+        *self.library.lock().unwrap() = Some(library.clone());
+        Ok(library)
+    }
+
+    /// Translates player's event into changed subsystems.
+    /// Library scanned by other front-ends is scanned again on demand,
+    /// own scans report the same number of tracks and keep the cache
+    #[inline]
+    pub fn on_event(&self, event: &PlayerEvent) {
+        let subsystems: &[&'static str] = match event {
+            PlayerEvent::StateChanged { .. }
+            | PlayerEvent::TrackChanged { .. }
+            | PlayerEvent::StreamTitleChanged { .. } => &["player"],
+            PlayerEvent::QueueChanged { .. } => &["playlist"],
+            PlayerEvent::OutputDeviceChanged { .. } => &["output"],
+
+            PlayerEvent::LibraryUpdated { tracks_count } => {
+                let mut library = self.library.lock().unwrap();
+
+                if library.as_ref().map(|l| l.get_tracks().len()) != Some(*tracks_count) {
+                    *library = None
+                }
+
+                &["database", "update"]
+            }
+
+            PlayerEvent::PositionTick { .. }
+            | PlayerEvent::SleepTimerFinished
+            | PlayerEvent::PodcastsUpdated { .. }
+            | PlayerEvent::Error { .. } => &[],
+        };
+
+        subsystems
+            .iter()
+            .for_each(|subsystem| self.notify(subsystem))
+    }
+}
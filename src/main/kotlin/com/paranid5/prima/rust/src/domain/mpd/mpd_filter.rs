@@ -0,0 +1,290 @@
+use crate::{
+    data::entities::tracks::default_track::DefaultTrack,
+    domain::mpd::{mpd_command::MpdError, mpd_library::tag_value},
+};
+
+use std::{iter::Peekable, str::Chars};
+
+/// Arguments that end the filter and start options of the command
+const KEYWORDS: [&str; 4] = ["sort", "window", "group", "position"];
+
+/// Tags that are compared by `any`
+const ANY_TAGS: [&str; 4] = ["artist", "album", "title", "file"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Equals,
+    Contains,
+    StartsWith,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Condition {
+    tag: String,
+    operator: Operator,
+    value: String,
+    is_negated: bool,
+}
+
+impl Condition {
+    #[inline]
+    fn matches(&self, track: &DefaultTrack, uri: &str, is_case_insensitive: bool) -> bool {
+        let normalize = |s: &str| match is_case_insensitive {
+            true => s.to_lowercase(),
+            false => s.to_string(),
+        };
+
+        let expected = normalize(&self.value);
+
+        let is_matched = match self.tag.as_str() {
+            "base" => {
+                let base = self.value.trim_end_matches('/');
+                uri == base || base.is_empty() || uri.starts_with(&format!("{}/", base))
+            }
+
+            tag => {
+                let values = match tag {
+                    "any" => ANY_TAGS
+                        .iter()
+                        .filter_map(|tag| tag_value(track, tag, uri))
+                        .collect(),
+
+                    tag => vec![tag_value(track, tag, uri).unwrap_or_default()],
+                };
+
+                values
+                    .iter()
+                    .map(|value| normalize(value))
+                    .any(|value| match self.operator {
+                        Operator::Equals => value == expected,
+                        Operator::Contains => value.contains(&expected),
+                        Operator::StartsWith => value.starts_with(&expected),
+                    })
+            }
+        };
+
+        is_matched != self.is_negated
+    }
+}
+
+/// Songs filter of `find`, `search`, `list` and other commands.
+/// Both old `TAG VALUE` pairs and expressions like
+/// `((artist == 'X') AND (album contains 'Y'))` are supported.
+/// Conditions are joined with AND
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MpdFilter {
+    conditions: Vec<Condition>,
+    is_case_insensitive: bool,
+}
+
+impl MpdFilter {
+    /// Parses filter at the start of arguments.
+    /// Returns it with the rest of arguments, e.g. `sort` and `window`.
+    /// `search` commands are case-insensitive and match substrings in pairs
+    #[inline]
+    pub fn parse(
+        args: &[String],
+        is_case_insensitive: bool,
+    ) -> Result<(Self, &[String]), MpdError> {
+        let mut conditions = Vec::new();
+        let mut rest = args;
+
+        loop {
+            match rest {
+                [expression, tail @ ..] if expression.starts_with('(') => {
+                    ExpressionParser {
+                        chars: expression.chars().peekable(),
+                    }
+                    .parse_into(&mut conditions)?;
+
+                    rest = tail
+                }
+
+                [keyword, ..] if KEYWORDS.contains(&keyword.to_lowercase().as_str()) => break,
+
+                [tag, value, tail @ ..] => {
+                    conditions.push(Condition {
+                        tag: tag.to_lowercase(),
+                        operator: match is_case_insensitive {
+                            true => Operator::Contains,
+                            false => Operator::Equals,
+                        },
+                        value: value.clone(),
+                        is_negated: false,
+                    });
+
+                    rest = tail
+                }
+
+                [tag] => return Err(MpdError::arg(format!("Missing value for {}", tag))),
+                [] => break,
+            }
+        }
+
+        Ok((
+            Self {
+                conditions,
+                is_case_insensitive,
+            },
+            rest,
+        ))
+    }
+
+    /// Filter with the single `TAG == VALUE` condition
+    #[inline]
+    pub fn equals(tag: &str, value: &str) -> Self {
+        Self {
+            conditions: vec![Condition {
+                tag: tag.to_lowercase(),
+                operator: Operator::Equals,
+                value: value.to_string(),
+                is_negated: false,
+            }],
+            is_case_insensitive: false,
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    #[inline]
+    pub fn matches(&self, track: &DefaultTrack, uri: &str) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.matches(track, uri, self.is_case_insensitive))
+    }
+}
+
+struct ExpressionParser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl ExpressionParser<'_> {
+    #[inline]
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    #[inline]
+    fn expect(&mut self, expected: char) -> Result<(), MpdError> {
+        self.skip_whitespace();
+
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(MpdError::arg(format!("'{}' expected", expected))),
+        }
+    }
+
+    #[inline]
+    fn word(&mut self) -> String {
+        self.skip_whitespace();
+        let mut word = String::new();
+
+        while let Some(c) = self.chars.next_if(|c| !c.is_whitespace() && *c != ')') {
+            word.push(c)
+        }
+
+        word
+    }
+
+    #[inline]
+    fn quoted(&mut self) -> Result<String, MpdError> {
+        self.skip_whitespace();
+
+        let quote = match self.chars.next() {
+            Some(quote @ ('\'' | '"')) => quote,
+            _ => return Err(MpdError::arg("Quoted value expected")),
+        };
+
+        let mut value = String::new();
+
+        loop {
+            match self.chars.next() {
+                None => return Err(MpdError::arg("Closing quote expected")),
+                Some(c) if c == quote => return Ok(value),
+                Some('\\') => value.extend(self.chars.next()),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    /// Parses the whole argument as one expression
+    #[inline]
+    fn parse_into(mut self, conditions: &mut Vec<Condition>) -> Result<(), MpdError> {
+        self.parse_expression(conditions)?;
+        self.skip_whitespace();
+
+        match self.chars.next() {
+            None => Ok(()),
+            Some(_) => Err(MpdError::arg("Unparsed garbage after expression")),
+        }
+    }
+
+    #[inline]
+    fn parse_expression(&mut self, conditions: &mut Vec<Condition>) -> Result<(), MpdError> {
+        self.expect('(')?;
+        self.skip_whitespace();
+
+        match self.chars.peek() {
+            Some('(') => loop {
+                self.parse_expression(conditions)?;
+                self.skip_whitespace();
+
+                if self.chars.next_if_eq(&')').is_some() {
+                    return Ok(());
+                }
+
+                if self.word() != "AND" {
+                    return Err(MpdError::arg("'AND' expected"));
+                }
+            },
+
+            Some('!') => {
+                self.chars.next();
+                let mut negated = Vec::new();
+                self.parse_expression(&mut negated)?;
+
+                match negated.as_mut_slice() {
+                    [condition] => condition.is_negated = !condition.is_negated,
+                    _ => return Err(MpdError::arg("Only single condition can be negated")),
+                }
+
+                conditions.extend(negated);
+                self.expect(')')
+            }
+
+            _ => {
+                let tag = self.word().to_lowercase();
+
+                let (operator, is_negated) = match tag.as_str() {
+                    "base" => (Operator::Equals, false),
+
+                    _ => match self.word().as_str() {
+                        "==" => (Operator::Equals, false),
+                        "!=" => (Operator::Equals, true),
+                        "contains" => (Operator::Contains, false),
+                        "!contains" => (Operator::Contains, true),
+                        "starts_with" => (Operator::StartsWith, false),
+                        operator => {
+                            return Err(MpdError::arg(format!(
+                                "Unsupported operator: {}",
+                                operator
+                            )))
+                        }
+                    },
+                };
+
+                conditions.push(Condition {
+                    tag,
+                    operator,
+                    value: self.quoted()?,
+                    is_negated,
+                });
+
+                self.expect(')')
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    data::entities::tracks::{default_track::DefaultTrack, track_trait::TrackTrait},
+    domain::{metadata_reader::MetadataReader, prima::Prima},
+    error::Result,
+};
+
+use std::{
+    collections::BTreeSet,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Scanned music folder as MPD's database.
+/// Songs are addressed by URIs relative to the music folder,
+/// tracks outside of it are addressed by absolute paths.
+/// Clients can't reach other files by URIs
+#[derive(Clone, Debug)]
+pub struct MpdLibrary {
+    music_dir: Option<PathBuf>,
+    tracks: Vec<DefaultTrack>,
+    scan_time: SystemTime,
+}
+
+impl MpdLibrary {
+    #[inline]
+    pub fn scan(prima: &Prima) -> Result<Self> {
+        Ok(Self {
+            music_dir: prima.get_music_search_path(),
+            tracks: prima.get_all_tracks()?,
+            scan_time: SystemTime::now(),
+        })
+    }
+
+    #[inline]
+    pub fn get_tracks(&self) -> &[DefaultTrack] {
+        self.tracks.as_slice()
+    }
+
+    #[inline]
+    pub fn get_scan_time(&self) -> SystemTime {
+        self.scan_time
+    }
+
+    #[inline]
+    pub fn track_uri(&self, track: &DefaultTrack) -> String {
+        let path = track.get_path();
+
+        match self
+            .music_dir
+            .as_ref()
+            .and_then(|dir| path.strip_prefix(dir).ok())
+        {
+            None => path.to_string_lossy().to_string(),
+
+            Some(relative) => relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        }
+    }
+
+    /// Existing file inside of the music folder or library's track.
+    /// Neither `..` nor symbolic links can lead out of the folder
+    #[inline]
+    pub fn uri_to_path(&self, uri: &str) -> Option<PathBuf> {
+        let path = Path::new(uri);
+
+        if path.is_absolute() {
+            if self.tracks.iter().any(|track| track.get_path() == path) {
+                return Some(path.to_path_buf());
+            }
+        } else if path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return None;
+        }
+
+        let dir = self.music_dir.as_ref()?;
+        let root = dir.canonicalize().ok()?;
+        let path = dir.join(path);
+
+        path.canonicalize()
+            .ok()
+            .filter(|canonical| canonical.starts_with(&root))
+            .map(|_| path)
+    }
+
+    /// Song of the library or any readable file with the given URI
+    #[inline]
+    pub fn find_track(&self, uri: &str) -> Option<DefaultTrack> {
+        let path = self.uri_to_path(uri)?;
+
+        self.tracks
+            .iter()
+            .find(|track| *track.get_path() == path)
+            .cloned()
+            .or_else(|| MetadataReader::read_track(&path))
+    }
+
+    /// Songs of the directory and all its subdirectories,
+    /// empty URI is the root of the music folder
+    #[inline]
+    pub fn tracks_in(&self, dir_uri: &str) -> Vec<&DefaultTrack> {
+        let prefix = dir_prefix(dir_uri);
+
+        self.tracks
+            .iter()
+            .filter(|track| {
+                let uri = self.track_uri(track);
+                !uri.starts_with('/') && uri.starts_with(&prefix)
+            })
+            .collect()
+    }
+
+    /// Direct subdirectories and songs of the directory
+    #[inline]
+    pub fn list_dir(&self, dir_uri: &str) -> (BTreeSet<String>, Vec<&DefaultTrack>) {
+        let prefix = dir_prefix(dir_uri);
+        let mut dirs = BTreeSet::new();
+        let mut files = Vec::new();
+
+        for track in self.tracks_in(dir_uri) {
+            let uri = self.track_uri(track);
+
+            match uri[prefix.len()..].split_once('/') {
+                Some((dir, _)) => {
+                    dirs.insert(format!("{}{}", prefix, dir));
+                }
+
+                None => files.push(track),
+            }
+        }
+
+        (dirs, files)
+    }
+}
+
+#[inline]
+fn dir_prefix(dir_uri: &str) -> String {
+    match dir_uri.trim_matches('/') {
+        "" => String::new(),
+        dir => format!("{}/", dir),
+    }
+}
+
+/// Value of MPD's tag, names are case-insensitive.
+/// Tags that tracks don't have are absent
+#[inline]
+pub fn tag_value(track: &DefaultTrack, tag: &str, uri: &str) -> Option<String> {
+    match tag.to_lowercase().as_str() {
+        "file" => Some(uri.to_string()),
+        "artist" | "artistsort" | "albumartist" | "albumartistsort" => track.get_artist().cloned(),
+        "album" | "albumsort" => track.get_album().cloned(),
+        "title" | "titlesort" => track.get_title().cloned(),
+
+        "track" => match track.get_number_in_album() {
+            number if number > 0 => Some(number.to_string()),
+            _ => None,
+        },
+
+        _ => None,
+    }
+}
+
+/// Name of the tag as MPD writes it
+#[inline]
+pub fn tag_display_name(tag: &str) -> String {
+    const NAMES: [&str; 12] = [
+        "Artist",
+        "ArtistSort",
+        "Album",
+        "AlbumSort",
+        "AlbumArtist",
+        "AlbumArtistSort",
+        "Title",
+        "TitleSort",
+        "Track",
+        "Genre",
+        "Date",
+        "Composer",
+    ];
+
+    match NAMES.iter().find(|name| name.eq_ignore_ascii_case(tag)) {
+        Some(name) => name.to_string(),
+        None if tag.eq_ignore_ascii_case("file") => "file".to_string(),
+
+        None => {
+            let mut chars = tag.chars();
+
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt::Display;
+
+/// Body of the successful command: `key: value` lines
+/// and, for cover art, a chunk of binary data
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MpdResponse {
+    bytes: Vec<u8>,
+}
+
+impl MpdResponse {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Line breaks would break the protocol, so they are replaced with spaces
+    #[inline]
+    pub fn pair(&mut self, key: &str, value: impl Display) {
+        let value = value.to_string().replace(['\n', '\r'], " ");
+        self.bytes
+            .extend_from_slice(format!("{}: {}\n", key, value).as_bytes())
+    }
+
+    #[inline]
+    pub fn binary(&mut self, data: &[u8]) {
+        self.pair("binary", data.len());
+        self.bytes.extend_from_slice(data);
+        self.bytes.push(b'\n')
+    }
+
+    #[inline]
+    pub fn append(&mut self, other: MpdResponse) {
+        self.bytes.extend(other.bytes)
+    }
+
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
@@ -0,0 +1,405 @@
+extern crate tokio;
+
+use crate::{
+    data::utils::types::TokioRuntime,
+    domain::{
+        mpd::{
+            mpd_command::{MpdCommand, MpdError, ACK_ERROR_SYSTEM},
+            mpd_context::MpdContext,
+            mpd_session::MpdSession,
+        },
+        prima::Prima,
+    },
+    error::Result,
+};
+
+use std::{
+    collections::BTreeSet,
+    io::{self, ErrorKind},
+    net::{IpAddr, Ipv4Addr},
+    sync::Arc,
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener, TcpStream,
+    },
+    runtime::Handle,
+    sync::{
+        broadcast::{
+            error::{RecvError, TryRecvError},
+            Receiver,
+        },
+        watch,
+    },
+    task::{self, JoinHandle},
+};
+
+/// Version of the protocol that is implemented
+const GREETING: &[u8] = b"OK MPD 0.23.5\n";
+
+/// Longer lines are rejected, so clients can't exhaust memory
+const MAX_LINE_LEN: usize = 64 * 1024;
+
+/// Server of the Music Player Daemon protocol,
+/// so existing clients (ncmpcpp, mpc, MPDroid, ...) can control the player.
+/// Remote clients must send the password, local ones are trusted.
+///
+/// Server runs on the session's runtime and stops when it's dropped
+pub struct MpdServer {
+    port: u16,
+    runtime: TokioRuntime,
+    accept_task: JoinHandle<()>,
+    events_task: JoinHandle<()>,
+
+    /// Dropped with the server, which closes open connections
+    _shutdown: watch::Sender<()>,
+}
+
+impl MpdServer {
+    /// Listens at the given port, port 0 lets the system choose a free one.
+    /// Only local clients may connect unless `is_lan_enabled` is set,
+    /// then server listens on all interfaces
+    #[inline]
+    pub fn start(
+        prima: Arc<Prima>,
+        port: u16,
+        password: Option<String>,
+        is_lan_enabled: bool,
+    ) -> Result<Self> {
+        let address = match is_lan_enabled {
+            true => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            false => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        };
+
+        let listener = std::net::TcpListener::bind((address, port))?;
+        listener.set_nonblocking(true)?;
+
+        let port = listener.local_addr()?.port();
+        let runtime = prima.get_tokio_runtime();
+
+        let listener = {
+            let _guard = runtime.enter();
+            TcpListener::from_std(listener)?
+        };
+
+        let mut events = prima.get_event_bus().subscribe();
+        let context = Arc::new(MpdContext::new(prima, password));
+        let (shutdown, shutdown_rx) = watch::channel(());
+
+        let events_task = runtime.spawn({
+            let context = context.clone();
+
+            async move {
+                loop {
+                    match events.recv().await {
+                        Ok(event) => context.on_event(&event),
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return,
+                    }
+                }
+            }
+        });
+
+        let accept_task = runtime.spawn(accept_connections(context, listener, shutdown_rx));
+
+        Ok(Self {
+            port,
+            runtime,
+            accept_task,
+            events_task,
+            _shutdown: shutdown,
+        })
+    }
+
+    #[inline]
+    pub fn get_port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for MpdServer {
+    #[inline]
+    fn drop(&mut self) {
+        self.events_task.abort();
+        self.accept_task.abort();
+
+        // Waiting for the listener to close lets the port be bound again at once
+        if Handle::try_current().is_err() {
+            let _ = self.runtime.block_on(&mut self.accept_task);
+        }
+    }
+}
+
+#[inline]
+async fn accept_connections(
+    context: Arc<MpdContext>,
+    listener: TcpListener,
+    mut shutdown: watch::Receiver<()>,
+) {
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+
+                Err(e) => {
+                    eprintln!("MPD server is unable to accept connection: {}", e);
+                    continue;
+                }
+            },
+
+            _ = shutdown.changed() => return,
+        };
+
+        tokio::spawn(serve_connection(context.clone(), stream, shutdown.clone()));
+    }
+}
+
+/// Command list that is being received
+struct CommandList {
+    commands: Vec<MpdCommand>,
+    is_list_ok: bool,
+}
+
+/// Serves the client until it closes the connection or sends `close`
+#[inline]
+async fn serve_connection(
+    context: Arc<MpdContext>,
+    stream: TcpStream,
+    mut shutdown: watch::Receiver<()>,
+) {
+    let is_trusted = stream
+        .peer_addr()
+        .map(|addr| addr.ip().is_loopback())
+        .unwrap_or(false);
+
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let mut session = Some(MpdSession::new(context.clone(), is_trusted));
+    let mut command_list: Option<CommandList> = None;
+    let mut changes = context.subscribe();
+    let mut line = Vec::new();
+
+    if writer.write_all(GREETING).await.is_err() {
+        return;
+    }
+
+    loop {
+        // Reading to the same buffer is cancel-safe
+        let read = tokio::select! {
+            read = read_line(&mut reader, &mut line) => read,
+            _ = shutdown.changed() => return,
+        };
+
+        match read {
+            Ok(true) => {}
+            Ok(false) => return,
+
+            Err(e) => {
+                let _ = writer
+                    .write_all(MpdError::arg(e.to_string()).to_ack(0, "").as_bytes())
+                    .await;
+                return;
+            }
+        }
+
+        let text = String::from_utf8_lossy(&line).trim_end().to_string();
+        line.clear();
+
+        let command = match MpdCommand::parse(&text) {
+            Ok(command) => command,
+
+            // Broken command list fails as a whole
+            Err(e) => {
+                command_list = None;
+
+                if writer.write_all(e.to_ack(0, "").as_bytes()).await.is_err() {
+                    return;
+                }
+
+                continue;
+            }
+        };
+
+        if let Some(list) = command_list.as_mut() {
+            if command.name != "command_list_end" {
+                list.commands.push(command);
+                continue;
+            }
+        }
+
+        let reply = match command.name.as_str() {
+            "close" => return,
+
+            "command_list_begin" | "command_list_ok_begin" => {
+                command_list = Some(CommandList {
+                    commands: Vec::new(),
+                    is_list_ok: command.name == "command_list_ok_begin",
+                });
+
+                continue;
+            }
+
+            "command_list_end" => match command_list.take() {
+                Some(list) => execute(&mut session, list.commands, list.is_list_ok).await,
+
+                None => MpdError::arg("not in command list mode")
+                    .to_ack(0, "command_list_end")
+                    .into_bytes(),
+            },
+
+            "idle" if session.as_ref().is_some_and(MpdSession::is_authorized) => {
+                let subsystems = command
+                    .args
+                    .iter()
+                    .map(|subsystem| subsystem.to_lowercase())
+                    .collect::<BTreeSet<_>>();
+
+                match idle(
+                    &mut reader,
+                    &mut writer,
+                    &mut line,
+                    &mut changes,
+                    &subsystems,
+                )
+                .await
+                {
+                    Ok(true) => continue,
+                    _ => return,
+                }
+            }
+
+            "noidle" => continue,
+            _ => execute(&mut session, vec![command], false).await,
+        };
+
+        if writer.write_all(&reply).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Reads the line to the buffer, `false` at the end of stream.
+/// Bytes read before cancellation stay in the buffer
+#[inline]
+async fn read_line(reader: &mut BufReader<OwnedReadHalf>, line: &mut Vec<u8>) -> io::Result<bool> {
+    loop {
+        let available = reader.fill_buf().await?;
+
+        if available.is_empty() {
+            return Ok(false);
+        }
+
+        let (consumed, is_complete) = match available.iter().position(|b| *b == b'\n') {
+            Some(end) => (end + 1, true),
+            None => (available.len(), false),
+        };
+
+        line.extend_from_slice(&available[..consumed]);
+        reader.consume(consumed);
+
+        if line.len() > MAX_LINE_LEN {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Line is too long"));
+        }
+
+        if is_complete {
+            return Ok(true);
+        }
+    }
+}
+
+/// Commands block on the session, so they run on the blocking pool.
+/// Session is moved there and back
+#[inline]
+async fn execute(
+    session: &mut Option<MpdSession>,
+    commands: Vec<MpdCommand>,
+    is_list_ok: bool,
+) -> Vec<u8> {
+    let mut taken = match session.take() {
+        Some(taken) => taken,
+        None => return Vec::new(),
+    };
+
+    let result = task::spawn_blocking(move || {
+        let reply = taken.execute_list(&commands, is_list_ok);
+        (taken, reply)
+    })
+    .await;
+
+    match result {
+        Ok((taken, reply)) => {
+            *session = Some(taken);
+            reply
+        }
+
+        Err(e) => MpdError::new(ACK_ERROR_SYSTEM, e.to_string())
+            .to_ack(0, "")
+            .into_bytes(),
+    }
+}
+
+/// Waits for changes of the subsystems (any if empty) or `noidle`.
+/// Returns `false` when the connection must be closed
+#[inline]
+async fn idle(
+    reader: &mut BufReader<OwnedReadHalf>,
+    writer: &mut OwnedWriteHalf,
+    line: &mut Vec<u8>,
+    changes: &mut Receiver<&'static str>,
+    subsystems: &BTreeSet<String>,
+) -> io::Result<bool> {
+    let is_wanted = |subsystem: &str| subsystems.is_empty() || subsystems.contains(subsystem);
+    let mut changed = BTreeSet::new();
+
+    // Changes made while the client was busy are reported at once
+    loop {
+        match changes.try_recv() {
+            Ok(subsystem) if is_wanted(subsystem) => {
+                changed.insert(subsystem);
+            }
+
+            Ok(_) | Err(TryRecvError::Lagged(_)) => continue,
+            Err(_) => break,
+        }
+    }
+
+    while changed.is_empty() {
+        tokio::select! {
+            change = changes.recv() => match change {
+                Ok(subsystem) if is_wanted(subsystem) => {
+                    changed.insert(subsystem);
+                }
+
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return Ok(false),
+            },
+
+            read = read_line(reader, line) => {
+                if !read? {
+                    return Ok(false);
+                }
+
+                let text = String::from_utf8_lossy(line).trim().to_string();
+                line.clear();
+
+                return match text.as_str() {
+                    "noidle" => writer.write_all(b"OK\n").await.map(|_| true),
+                    _ => Ok(false),
+                };
+            }
+        }
+    }
+
+    let mut reply = changed
+        .iter()
+        .map(|subsystem| format!("changed: {}\n", subsystem))
+        .collect::<String>();
+
+    reply.push_str("OK\n");
+    writer.write_all(reply.as_bytes()).await?;
+    Ok(true)
+}
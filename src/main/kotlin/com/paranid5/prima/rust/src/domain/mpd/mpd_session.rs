@@ -0,0 +1,1059 @@
+extern crate chrono;
+
+use crate::{
+    data::entities::{
+        playlists::{default_playlist::DefaultPlaylist, playlist_trait::PlaylistTrait},
+        tracks::{default_track::DefaultTrack, track_trait::TrackTrait},
+    },
+    domain::{
+        audio_player::playback_params::LoopingState,
+        cover_art::CoverArt,
+        metadata_reader::Cover,
+        mpd::{
+            mpd_command::{
+                MpdCommand, MpdError, ACK_ERROR_PASSWORD, ACK_ERROR_PERMISSION, ACK_ERROR_UNKNOWN,
+            },
+            mpd_context::MpdContext,
+            mpd_filter::MpdFilter,
+            mpd_library::{tag_display_name, tag_value, MpdLibrary},
+            mpd_response::MpdResponse,
+        },
+        prima::Prima,
+    },
+};
+
+use chrono::Utc;
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet, HashSet},
+    hash::{Hash, Hasher},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Chunk of cover art sent by default, clients may change it with `binarylimit`
+const DEFAULT_BINARY_LIMIT: usize = 8192;
+const MIN_BINARY_LIMIT: usize = 64;
+
+/// Commands that are handled by the server itself
+/// and those that are allowed without password
+const CONNECTION_COMMANDS: [&str; 6] = [
+    "close",
+    "command_list_begin",
+    "command_list_end",
+    "command_list_ok_begin",
+    "idle",
+    "noidle",
+];
+
+const PUBLIC_COMMANDS: [&str; 5] = ["close", "commands", "notcommands", "password", "ping"];
+
+const COMMANDS: [&str; 63] = [
+    "add",
+    "addid",
+    "albumart",
+    "binarylimit",
+    "clear",
+    "clearerror",
+    "commands",
+    "config",
+    "count",
+    "currentsong",
+    "decoders",
+    "delete",
+    "deleteid",
+    "find",
+    "findadd",
+    "getvol",
+    "list",
+    "listall",
+    "listallinfo",
+    "listplaylist",
+    "listplaylistinfo",
+    "listplaylists",
+    "load",
+    "lsinfo",
+    "move",
+    "moveid",
+    "next",
+    "notcommands",
+    "outputs",
+    "password",
+    "pause",
+    "ping",
+    "play",
+    "playid",
+    "playlist",
+    "playlistfind",
+    "playlistid",
+    "playlistinfo",
+    "playlistsearch",
+    "plchanges",
+    "plchangesposid",
+    "previous",
+    "random",
+    "readpicture",
+    "repeat",
+    "replay_gain_status",
+    "rescan",
+    "search",
+    "searchadd",
+    "seek",
+    "seekcur",
+    "seekid",
+    "setvol",
+    "shuffle",
+    "single",
+    "stats",
+    "status",
+    "stop",
+    "swap",
+    "swapid",
+    "tagtypes",
+    "update",
+    "urlhandlers",
+];
+
+const TAG_TYPES: [&str; 7] = [
+    "Artist",
+    "ArtistSort",
+    "Album",
+    "AlbumSort",
+    "AlbumArtist",
+    "Title",
+    "Track",
+];
+
+const DECODER_SUFFIXES: [(&str, &str); 7] = [
+    ("mp3", "audio/mpeg"),
+    ("flac", "audio/flac"),
+    ("ogg", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("aiff", "audio/aiff"),
+    ("m4a", "audio/mp4"),
+    ("aac", "audio/aac"),
+];
+
+/// Options that follow the filter of library commands
+#[derive(Debug, Default)]
+struct FilterOptions {
+    sort: Option<String>,
+    window: Option<(usize, usize)>,
+    groups: Vec<String>,
+    position: Option<String>,
+}
+
+impl FilterOptions {
+    #[inline]
+    fn parse(args: &[String]) -> Result<Self, MpdError> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+
+        while let Some(keyword) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| MpdError::arg(format!("Missing value for {}", keyword)))?;
+
+            match keyword.to_lowercase().as_str() {
+                "sort" => options.sort = Some(value.clone()),
+                "group" => options.groups.push(value.to_lowercase()),
+                "position" => options.position = Some(value.clone()),
+
+                "window" => {
+                    let command = MpdCommand {
+                        name: String::new(),
+                        args: vec![value.clone()],
+                    };
+
+                    options.window = Some(command.range_arg(0, usize::MAX)?)
+                }
+
+                keyword => return Err(MpdError::arg(format!("Unknown option: {}", keyword))),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Commands of one client's connection.
+/// Methods block on the session, so they must run outside of its runtime
+pub struct MpdSession {
+    context: Arc<MpdContext>,
+    is_authorized: bool,
+    binary_limit: usize,
+}
+
+impl MpdSession {
+    /// Trusted clients, e.g. local ones, don't need the password
+    #[inline]
+    pub fn new(context: Arc<MpdContext>, is_trusted: bool) -> Self {
+        let is_authorized = is_trusted || context.get_password().is_none();
+
+        Self {
+            context,
+            is_authorized,
+            binary_limit: DEFAULT_BINARY_LIMIT,
+        }
+    }
+
+    #[inline]
+    pub fn is_authorized(&self) -> bool {
+        self.is_authorized
+    }
+
+    /// Executes commands one by one until the first failure.
+    /// Reply ends with `OK` or with `ACK` of the failed command.
+    /// In `command_list_ok_begin` lists every command is followed by `list_OK`
+    #[inline]
+    pub fn execute_list(&mut self, commands: &[MpdCommand], is_list_ok: bool) -> Vec<u8> {
+        let mut reply = Vec::new();
+
+        for (index, command) in commands.iter().enumerate() {
+            match self.execute(command) {
+                Ok(response) => reply.extend(response.into_bytes()),
+
+                Err(e) => {
+                    reply.extend_from_slice(e.to_ack(index, &command.name).as_bytes());
+                    return reply;
+                }
+            }
+
+            if is_list_ok {
+                reply.extend_from_slice(b"list_OK\n")
+            }
+        }
+
+        reply.extend_from_slice(b"OK\n");
+        reply
+    }
+
+    #[inline]
+    fn prima(&self) -> &Arc<Prima> {
+        self.context.get_prima()
+    }
+
+    #[inline]
+    fn library(&self) -> Result<Arc<MpdLibrary>, MpdError> {
+        Ok(self.context.get_library()?)
+    }
+
+    #[inline]
+    fn execute(&mut self, command: &MpdCommand) -> Result<MpdResponse, MpdError> {
+        let name = command.name.as_str();
+
+        if !self.is_authorized && !PUBLIC_COMMANDS.contains(&name) {
+            return Err(MpdError::new(
+                ACK_ERROR_PERMISSION,
+                format!("you don't have permission for \"{}\"", name),
+            ));
+        }
+
+        let mut response = MpdResponse::new();
+
+        match name {
+            "ping" | "clearerror" => {}
+
+            "password" => match self.context.get_password() {
+                Some(password) if password != command.arg(0)? => {
+                    return Err(MpdError::new(ACK_ERROR_PASSWORD, "incorrect password"))
+                }
+
+                _ => self.is_authorized = true,
+            },
+
+            "binarylimit" => {
+                self.binary_limit = command.parse_arg::<usize>(0)?.max(MIN_BINARY_LIMIT);
+            }
+
+            "commands" => {
+                let mut commands = match self.is_authorized {
+                    true => COMMANDS.to_vec(),
+                    false => PUBLIC_COMMANDS.to_vec(),
+                };
+
+                commands.extend(CONNECTION_COMMANDS);
+                commands.sort_unstable();
+                commands.dedup();
+                commands
+                    .iter()
+                    .for_each(|command| response.pair("command", command))
+            }
+
+            "notcommands" if !self.is_authorized => COMMANDS
+                .iter()
+                .filter(|command| !PUBLIC_COMMANDS.contains(command))
+                .for_each(|command| response.pair("command", command)),
+
+            "notcommands" => {}
+
+            // Tag types can't be disabled, so `tagtypes clear` and others are ignored
+            "tagtypes" if command.args.is_empty() => TAG_TYPES
+                .iter()
+                .for_each(|tag| response.pair("tagtype", tag)),
+
+            "tagtypes" => {}
+
+            "urlhandlers" => {}
+
+            "decoders" => {
+                response.pair("plugin", "prima");
+
+                for (suffix, mime_type) in DECODER_SUFFIXES {
+                    response.pair("suffix", suffix);
+                    response.pair("mime_type", mime_type)
+                }
+            }
+
+            "config" => {
+                return Err(MpdError::new(
+                    ACK_ERROR_PERMISSION,
+                    "Music folder is available only for local clients",
+                ))
+            }
+
+            "outputs" => {
+                let device = self.prima().get_output_device();
+                response.pair("outputid", 0);
+                response.pair("outputname", device.as_deref().unwrap_or("Default"));
+                response.pair("plugin", "prima");
+                response.pair("outputenabled", 1)
+            }
+
+            "replay_gain_status" => response.pair("replay_gain_mode", "off"),
+
+            "status" => self.status(&mut response),
+            "stats" => self.stats(&mut response)?,
+
+            "currentsong" => {
+                let playlist = self.prima().get_cur_playlist();
+
+                if let Some(track) = playlist.get_cur_track() {
+                    let library = self.library()?;
+                    song_info(&mut response, &library, track, Some(playlist.get_cur_ind()))
+                }
+            }
+
+            "play" | "playid" => match command.args.first() {
+                Some(_) => {
+                    let index = command.parse_arg::<usize>(0)?;
+                    self.check_position(index)?;
+                    Prima::spawn_action(self.prima().clone(), move |prima| prima.play_at(index))?
+                }
+
+                None if self.prima().is_playing() => {}
+                None => Prima::spawn_action(self.prima().clone(), |prima| prima.play_pause())?,
+            },
+
+            "pause" => match command.args.first() {
+                None => Prima::spawn_action(self.prima().clone(), |prima| prima.play_pause())?,
+                Some(_) if command.bool_arg(0)? => self.prima().pause_playback(),
+                Some(_) if self.prima().is_playing() => {}
+                Some(_) => Prima::spawn_action(self.prima().clone(), |prima| prima.play_pause())?,
+            },
+
+            "stop" => self.prima().stop_playback(),
+            "next" => Prima::spawn_action(self.prima().clone(), |prima| prima.next_track())?,
+
+            "previous" => {
+                Prima::spawn_action(self.prima().clone(), |prima| prima.previous_track())?
+            }
+
+            "seek" | "seekid" => {
+                let index = command.parse_arg::<usize>(0)?;
+
+                if index != self.prima().get_cur_track_index() {
+                    return Err(MpdError::arg("Only the current song can be seeked"));
+                }
+
+                self.seek(parse_seconds(command.arg(1)?)?)?
+            }
+
+            "seekcur" => {
+                let time = command.arg(0)?;
+                let seconds = parse_seconds(time.trim_start_matches(['+', '-']))?;
+                let position = self.prima().get_playback_position();
+
+                let position = match time.chars().next() {
+                    Some('+') => position + seconds,
+                    Some('-') => position.saturating_sub(seconds),
+                    _ => seconds,
+                };
+
+                self.seek(position)?
+            }
+
+            "getvol" => response.pair("volume", self.volume_percent()),
+
+            "setvol" | "volume" => {
+                let change = command.parse_arg::<i32>(0)?;
+
+                let volume = match name {
+                    "volume" => self.volume_percent() + change,
+                    _ => change,
+                };
+
+                if !(0..=100).contains(&volume) && name == "setvol" {
+                    return Err(MpdError::arg("Volume must be between 0 and 100"));
+                }
+
+                self.prima().set_volume(volume.clamp(0, 100) as f32 / 100.0);
+                self.context.notify("mixer")
+            }
+
+            "random" => {
+                self.prima().set_shuffle(command.bool_arg(0)?);
+                self.context.notify("options")
+            }
+
+            "repeat" => {
+                let looping_state = match (command.bool_arg(0)?, self.prima().get_looping_state()) {
+                    (false, _) => LoopingState::NoLooping,
+                    (true, LoopingState::NoLooping) => LoopingState::Playlist,
+                    (true, state) => state,
+                };
+
+                self.prima().set_looping_state(looping_state);
+                self.context.notify("options")
+            }
+
+            "single" => {
+                let looping_state = match (command.arg(0)?, self.prima().get_looping_state()) {
+                    ("1" | "oneshot", _) => LoopingState::Track,
+                    ("0", LoopingState::Track) => LoopingState::Playlist,
+                    ("0", state) => state,
+                    (arg, _) => return Err(MpdError::arg(format!("Invalid single mode: {}", arg))),
+                };
+
+                self.prima().set_looping_state(looping_state);
+                self.context.notify("options")
+            }
+
+            "add" | "addid" => {
+                let library = self.library()?;
+                let uri = command.arg(0)?;
+
+                let tracks = match library.find_track(uri) {
+                    Some(track) => vec![track],
+                    None if name == "addid" => return Err(MpdError::no_exist("No such song")),
+                    None => library.tracks_in(uri).into_iter().cloned().collect(),
+                };
+
+                if tracks.is_empty() {
+                    return Err(MpdError::no_exist("No such directory"));
+                }
+
+                let position = command
+                    .args
+                    .get(1)
+                    .map(|position| self.queue_position(position))
+                    .transpose()?;
+
+                let id = position.unwrap_or_else(|| self.prima().get_cur_playlist().len());
+                self.prima().add_to_queue(tracks, position);
+
+                if name == "addid" {
+                    response.pair("Id", id)
+                }
+            }
+
+            "delete" | "deleteid" => {
+                let len = self.prima().get_cur_playlist().len();
+                let (start, end) = command.range_arg(0, len)?;
+
+                if end > len || command.args.is_empty() {
+                    return Err(MpdError::arg("Bad song index"));
+                }
+
+                for index in (start..end).rev() {
+                    self.prima().remove_from_queue(index)?
+                }
+            }
+
+            "clear" => self.prima().clear_queue(),
+
+            "move" | "moveid" => {
+                let len = self.prima().get_cur_playlist().len();
+                let (start, end) = command.range_arg(0, len)?;
+                let to = command.parse_arg::<usize>(1)?;
+                let count = end - start;
+
+                if end > len || to + count > len {
+                    return Err(MpdError::arg("Bad song index"));
+                }
+
+                for i in 0..count {
+                    match to > start {
+                        true => self.prima().move_in_queue(start, to + count - 1)?,
+                        false => self.prima().move_in_queue(start + i, to + i)?,
+                    }
+                }
+            }
+
+            "swap" | "swapid" => {
+                let (first, second) = (
+                    command.parse_arg::<usize>(0)?,
+                    command.parse_arg::<usize>(1)?,
+                );
+                let (first, second) = (first.min(second), first.max(second));
+                self.check_position(second)?;
+
+                if first != second {
+                    self.prima().move_in_queue(first, second)?;
+                    self.prima().move_in_queue(second - 1, first)?
+                }
+            }
+
+            "shuffle" => {
+                self.prima().set_shuffle(false);
+                self.prima().set_shuffle(true);
+                self.context.notify("options")
+            }
+
+            "playlist" => {
+                let library = self.library()?;
+
+                for (index, track) in self
+                    .prima()
+                    .get_cur_playlist()
+                    .get_tracks()
+                    .iter()
+                    .enumerate()
+                {
+                    response.pair(
+                        &index.to_string(),
+                        format!("file: {}", library.track_uri(track)),
+                    )
+                }
+            }
+
+            "playlistinfo" | "playlistid" | "plchanges" | "plchangesposid" => {
+                let playlist = self.prima().get_cur_playlist();
+                let library = self.library()?;
+
+                let range_index = match name {
+                    "plchanges" | "plchangesposid" => {
+                        if command.parse_arg::<u32>(0)? == queue_version(&playlist) {
+                            return Ok(response);
+                        }
+
+                        1
+                    }
+
+                    _ => 0,
+                };
+
+                let (start, end) = command.range_arg(range_index, playlist.len())?;
+
+                if end > playlist.len() && name == "playlistid" {
+                    return Err(MpdError::no_exist("No such song"));
+                }
+
+                let tracks = playlist.get_tracks().iter().enumerate();
+
+                for (index, track) in tracks.skip(start).take(end.saturating_sub(start)) {
+                    match name {
+                        "plchangesposid" => {
+                            response.pair("cpos", index);
+                            response.pair("Id", index)
+                        }
+
+                        _ => song_info(&mut response, &library, track, Some(index)),
+                    }
+                }
+            }
+
+            "playlistfind" | "playlistsearch" => {
+                let library = self.library()?;
+                let (filter, _) = MpdFilter::parse(&command.args, name == "playlistsearch")?;
+                let playlist = self.prima().get_cur_playlist();
+
+                for (index, track) in playlist.get_tracks().iter().enumerate() {
+                    if filter.matches(track, &library.track_uri(track)) {
+                        song_info(&mut response, &library, track, Some(index))
+                    }
+                }
+            }
+
+            // Prima has no stored playlists with tracks
+            "listplaylists" => {}
+
+            "listplaylist" | "listplaylistinfo" | "load" => {
+                return Err(MpdError::no_exist("No such playlist"))
+            }
+
+            "list" => self.list(command, &mut response)?,
+
+            "find" | "search" => {
+                let library = self.library()?;
+                let (tracks, _) = self.find(&library, &command.args, name == "search")?;
+
+                for track in tracks {
+                    song_info(&mut response, &library, track, None)
+                }
+            }
+
+            "findadd" | "searchadd" => {
+                let library = self.library()?;
+                let (tracks, options) = self.find(&library, &command.args, name == "searchadd")?;
+
+                let position = options
+                    .position
+                    .map(|position| self.queue_position(&position))
+                    .transpose()?;
+
+                let tracks = tracks.into_iter().cloned().collect::<Vec<_>>();
+
+                if !tracks.is_empty() {
+                    self.prima().add_to_queue(tracks, position)
+                }
+            }
+
+            "count" => {
+                let library = self.library()?;
+                let (filter, rest) = MpdFilter::parse(&command.args, false)?;
+                let options = FilterOptions::parse(rest)?;
+
+                let tracks = library
+                    .get_tracks()
+                    .iter()
+                    .filter(|track| filter.matches(track, &library.track_uri(track)));
+
+                match options.groups.first() {
+                    None => {
+                        let tracks = tracks.collect::<Vec<_>>();
+                        response.pair("songs", tracks.len());
+                        response.pair("playtime", total_seconds(&tracks))
+                    }
+
+                    Some(group) => {
+                        let mut groups = std::collections::BTreeMap::<String, Vec<_>>::new();
+
+                        for track in tracks {
+                            let value = tag_value(track, group, &library.track_uri(track));
+                            groups
+                                .entry(value.unwrap_or_default())
+                                .or_default()
+                                .push(track)
+                        }
+
+                        for (value, tracks) in groups {
+                            response.pair(&tag_display_name(group), value);
+                            response.pair("songs", tracks.len());
+                            response.pair("playtime", total_seconds(&tracks))
+                        }
+                    }
+                }
+            }
+
+            "lsinfo" | "listall" | "listallinfo" => {
+                let library = self.library()?;
+                let uri = command.args.first().map(String::as_str).unwrap_or_default();
+
+                if name == "lsinfo" && !uri.is_empty() && library.tracks_in(uri).is_empty() {
+                    let track = library
+                        .find_track(uri)
+                        .ok_or_else(|| MpdError::no_exist("No such directory"))?;
+
+                    song_info(&mut response, &library, &track, None);
+                    return Ok(response);
+                }
+
+                self.list_dir(&library, uri, name, &mut response)
+            }
+
+            "update" | "rescan" => {
+                self.context.rescan()?;
+                response.pair("updating_db", 1)
+            }
+
+            "albumart" | "readpicture" => {
+                let library = self.library()?;
+                let uri = command.arg(0)?;
+                let offset = command.parse_arg::<usize>(1)?;
+
+                let path = library
+                    .uri_to_path(uri)
+                    .filter(|path| path.is_file())
+                    .ok_or_else(|| MpdError::no_exist("No such file"))?;
+
+                match CoverArt::read(&path) {
+                    None if name == "albumart" => return Err(MpdError::no_exist("No file exists")),
+
+                    None => {}
+                    Some(cover) => self.cover_chunk(&cover, offset, name, &mut response)?,
+                }
+            }
+
+            name if CONNECTION_COMMANDS.contains(&name) => {
+                return Err(MpdError::arg(format!("\"{}\" is not allowed here", name)))
+            }
+
+            name => {
+                return Err(MpdError::new(
+                    ACK_ERROR_UNKNOWN,
+                    format!("unknown command \"{}\"", name),
+                ))
+            }
+        }
+
+        Ok(response)
+    }
+
+    #[inline]
+    fn volume_percent(&self) -> i32 {
+        (self.prima().get_volume() * 100.0).round() as i32
+    }
+
+    #[inline]
+    fn check_position(&self, index: usize) -> Result<(), MpdError> {
+        match index < self.prima().get_cur_playlist().len() {
+            true => Ok(()),
+            false => Err(MpdError::arg("Bad song index")),
+        }
+    }
+
+    /// Absolute position or `+N`/`-N` relative to the current song
+    #[inline]
+    fn queue_position(&self, position: &str) -> Result<usize, MpdError> {
+        let playlist = self.prima().get_cur_playlist();
+        let invalid = || MpdError::arg(format!("Invalid position: {}", position));
+        let cur_ind = playlist.get_cur_ind();
+
+        let position = match position.chars().next() {
+            Some('+') => cur_ind + 1 + position[1..].parse::<usize>().map_err(|_| invalid())?,
+
+            Some('-') => cur_ind
+                .checked_sub(position[1..].parse::<usize>().map_err(|_| invalid())?)
+                .ok_or_else(invalid)?,
+
+            _ => position.parse::<usize>().map_err(|_| invalid())?,
+        };
+
+        match position <= playlist.len() {
+            true => Ok(position),
+            false => Err(invalid()),
+        }
+    }
+
+    #[inline]
+    fn seek(&self, position: Duration) -> Result<(), MpdError> {
+        if self.prima().get_cur_track().is_none() {
+            return Err(MpdError::no_exist("No current song"));
+        }
+
+        Ok(Prima::spawn_action(self.prima().clone(), move |prima| {
+            prima.seek_to(position)
+        })?)
+    }
+
+    #[inline]
+    fn status(&self, response: &mut MpdResponse) {
+        let prima = self.prima();
+        let playlist = prima.get_cur_playlist();
+        let position = prima.get_playback_position();
+        let looping_state = prima.get_looping_state();
+        let is_playing = prima.is_playing();
+
+        response.pair("volume", self.volume_percent());
+        response.pair("repeat", (looping_state != LoopingState::NoLooping) as u8);
+        response.pair("random", prima.is_shuffled() as u8);
+        response.pair("single", (looping_state == LoopingState::Track) as u8);
+        response.pair("consume", 0);
+        response.pair("playlist", queue_version(&playlist));
+        response.pair("playlistlength", playlist.len());
+
+        let state = match (is_playing, playlist.get_cur_track()) {
+            (true, _) => "play",
+            (false, Some(_)) if !position.is_zero() => "pause",
+            _ => "stop",
+        };
+
+        response.pair("state", state);
+
+        if let Some(track) = playlist.get_cur_track() {
+            let index = playlist.get_cur_ind();
+            let duration = track.get_duration().num_milliseconds().max(0) as f64 / 1000.0;
+            let elapsed = position.as_secs_f64();
+
+            response.pair("song", index);
+            response.pair("songid", index);
+
+            if index + 1 < playlist.len() {
+                response.pair("nextsong", index + 1);
+                response.pair("nextsongid", index + 1)
+            }
+
+            response.pair(
+                "time",
+                format!("{}:{}", elapsed as u64, duration.round() as u64),
+            );
+            response.pair("elapsed", format!("{:.3}", elapsed));
+            response.pair("duration", format!("{:.3}", duration))
+        }
+    }
+
+    #[inline]
+    fn stats(&self, response: &mut MpdResponse) -> Result<(), MpdError> {
+        let library = self.library()?;
+        let tracks = library.get_tracks().iter().collect::<Vec<_>>();
+
+        let count = |tag: &str| {
+            tracks
+                .iter()
+                .filter_map(|track| tag_value(track, tag, ""))
+                .collect::<HashSet<_>>()
+                .len()
+        };
+
+        let db_update = library
+            .get_scan_time()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        response.pair("artists", count("artist"));
+        response.pair("albums", count("album"));
+        response.pair("songs", tracks.len());
+        response.pair("uptime", self.context.get_start_time().elapsed().as_secs());
+        response.pair("playtime", 0);
+        response.pair("db_playtime", total_seconds(&tracks));
+        response.pair("db_update", db_update);
+        Ok(())
+    }
+
+    /// Songs of the library that match the filter,
+    /// sorted and windowed with the options
+    #[inline]
+    fn find<'a>(
+        &self,
+        library: &'a MpdLibrary,
+        args: &[String],
+        is_case_insensitive: bool,
+    ) -> Result<(Vec<&'a DefaultTrack>, FilterOptions), MpdError> {
+        let (filter, rest) = MpdFilter::parse(args, is_case_insensitive)?;
+
+        if filter.is_empty() {
+            return Err(MpdError::arg("Filter is missing"));
+        }
+
+        let options = FilterOptions::parse(rest)?;
+
+        let mut tracks = library
+            .get_tracks()
+            .iter()
+            .filter(|track| filter.matches(track, &library.track_uri(track)))
+            .collect::<Vec<_>>();
+
+        if let Some(sort) = &options.sort {
+            let (tag, is_descending) = match sort.strip_prefix('-') {
+                Some(tag) => (tag, true),
+                None => (sort.as_str(), false),
+            };
+
+            tracks.sort_by_cached_key(|track| tag_value(track, tag, &library.track_uri(track)));
+
+            if is_descending {
+                tracks.reverse()
+            }
+        }
+
+        if let Some((start, end)) = options.window {
+            tracks = tracks
+                .into_iter()
+                .skip(start)
+                .take(end.saturating_sub(start))
+                .collect()
+        }
+
+        Ok((tracks, options))
+    }
+
+    /// `list TAG [FILTER] [group TAG]...`.
+    /// Legacy `list album ARTIST` form is supported too
+    #[inline]
+    fn list(&self, command: &MpdCommand, response: &mut MpdResponse) -> Result<(), MpdError> {
+        let library = self.library()?;
+        let tag = command.arg(0)?.to_lowercase();
+        let args = &command.args[1..];
+
+        let (filter, rest) = match args {
+            [artist] if tag == "album" && !artist.starts_with('(') => {
+                (MpdFilter::equals("artist", artist), &args[1..])
+            }
+
+            args => MpdFilter::parse(args, false)?,
+        };
+
+        let options = FilterOptions::parse(rest)?;
+
+        let rows = library
+            .get_tracks()
+            .iter()
+            .filter_map(|track| {
+                let uri = library.track_uri(track);
+
+                if !filter.matches(track, &uri) {
+                    return None;
+                }
+
+                let value = tag_value(track, &tag, &uri).filter(|value| !value.is_empty())?;
+
+                let mut row = options
+                    .groups
+                    .iter()
+                    .map(|group| tag_value(track, group, &uri).unwrap_or_default())
+                    .collect::<Vec<_>>();
+
+                row.push(value);
+                Some(row)
+            })
+            .collect::<BTreeSet<_>>();
+
+        let names = options
+            .groups
+            .iter()
+            .chain(std::iter::once(&tag))
+            .map(|tag| tag_display_name(tag))
+            .collect::<Vec<_>>();
+
+        let mut previous: Option<&Vec<String>> = None;
+
+        // Group's value is written only when it changes, as MPD does
+        for row in &rows {
+            let changed_from = previous
+                .and_then(|previous| (0..row.len()).find(|&i| previous[i] != row[i]))
+                .unwrap_or_default();
+
+            for i in changed_from..row.len() {
+                response.pair(&names[i], &row[i])
+            }
+
+            previous = Some(row)
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn list_dir(&self, library: &MpdLibrary, uri: &str, name: &str, response: &mut MpdResponse) {
+        let (dirs, files) = library.list_dir(uri);
+
+        for track in files {
+            match name {
+                "listall" => response.pair("file", library.track_uri(track)),
+                _ => song_info(response, library, track, None),
+            }
+        }
+
+        for dir in dirs {
+            response.pair("directory", &dir);
+
+            if name != "lsinfo" {
+                self.list_dir(library, &dir, name, response)
+            }
+        }
+    }
+
+    /// Cover is sent in chunks, client asks for the next one with the offset
+    #[inline]
+    fn cover_chunk(
+        &self,
+        cover: &Cover,
+        offset: usize,
+        name: &str,
+        response: &mut MpdResponse,
+    ) -> Result<(), MpdError> {
+        if offset > cover.data.len() {
+            return Err(MpdError::arg("Offset is beyond the end of the image"));
+        }
+
+        let end = (offset + self.binary_limit).min(cover.data.len());
+        response.pair("size", cover.data.len());
+
+        if name == "readpicture" {
+            response.pair("type", &cover.mime_type)
+        }
+
+        response.binary(&cover.data[offset..end]);
+        Ok(())
+    }
+}
+
+/// Seconds with fraction, e.g. `12.5`
+#[inline]
+fn parse_seconds(time: &str) -> Result<Duration, MpdError> {
+    time.parse::<f64>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+        .ok_or_else(|| MpdError::arg(format!("Invalid time: {}", time)))
+}
+
+#[inline]
+fn total_seconds(tracks: &[&DefaultTrack]) -> i64 {
+    tracks
+        .iter()
+        .map(|track| track.get_duration().num_seconds())
+        .sum()
+}
+
+/// Version of the queue for `plchanges`: changes when its tracks change
+#[inline]
+fn queue_version(playlist: &DefaultPlaylist<DefaultTrack>) -> u32 {
+    let mut hasher = DefaultHasher::new();
+
+    playlist
+        .get_tracks()
+        .iter()
+        .for_each(|track| track.get_path().hash(&mut hasher));
+
+    (hasher.finish() as u32).max(1)
+}
+
+/// Song's tags, as `currentsong`, `playlistinfo` and `find` write them.
+/// Queue's songs also have position and id, which are the same
+#[inline]
+fn song_info(
+    response: &mut MpdResponse,
+    library: &MpdLibrary,
+    track: &DefaultTrack,
+    position: Option<usize>,
+) {
+    let duration = track.get_duration().num_milliseconds().max(0) as f64 / 1000.0;
+
+    let modified = SystemTime::from(*track.get_add_date())
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .and_then(|since| chrono::DateTime::<Utc>::from_timestamp(since.as_secs() as i64, 0));
+
+    response.pair("file", library.track_uri(track));
+
+    if let Some(modified) = modified {
+        response.pair("Last-Modified", modified.format("%Y-%m-%dT%H:%M:%SZ"))
+    }
+
+    if let Some(artist) = track.get_artist().filter(|a| !a.is_empty()) {
+        response.pair("Artist", artist)
+    }
+
+    if let Some(album) = track.get_album().filter(|a| !a.is_empty()) {
+        response.pair("Album", album)
+    }
+
+    if let Some(title) = track.get_title().filter(|t| !t.is_empty()) {
+        response.pair("Title", title)
+    }
+
+    if let Some(number) = tag_value(track, "track", "") {
+        response.pair("Track", number)
+    }
+
+    response.pair("Time", duration.round() as u64);
+    response.pair("duration", format!("{:.3}", duration));
+
+    if let Some(position) = position {
+        response.pair("Pos", position);
+        response.pair("Id", position)
+    }
+}
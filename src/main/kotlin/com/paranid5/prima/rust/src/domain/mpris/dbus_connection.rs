@@ -0,0 +1,245 @@
+use crate::{
+    domain::mpris::{
+        dbus_message::{DBusMessage, MessageType},
+        dbus_value::{protocol_error, DBusValue},
+    },
+    error::{Error, Result},
+};
+
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    net::Shutdown,
+    os::{
+        linux::net::SocketAddrExt,
+        unix::{
+            fs::MetadataExt,
+            net::{SocketAddr, UnixStream},
+        },
+    },
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+};
+
+pub const BUS_NAME: &str = "org.freedesktop.DBus";
+pub const BUS_PATH: &str = "/org/freedesktop/DBus";
+
+/// Name is requested without queueing:
+/// if it's taken, request fails instead of waiting
+const NAME_FLAG_DO_NOT_QUEUE: u32 = 0x4;
+const NAME_REPLY_PRIMARY_OWNER: u32 = 1;
+
+/// Address of the user's session bus
+#[inline]
+pub fn session_bus_address() -> Result<String> {
+    if let Some(address) = env::var("DBUS_SESSION_BUS_ADDRESS")
+        .ok()
+        .filter(|a| !a.is_empty())
+    {
+        return Ok(address);
+    }
+
+    env::var("XDG_RUNTIME_DIR")
+        .map(|dir| format!("unix:path={}/bus", dir))
+        .map_err(|_| Error::Config("Session bus address is unknown".to_string()))
+}
+
+/// Connects to the first reachable `unix:` address from the list
+#[inline]
+fn connect_to_address(address: &str) -> Result<UnixStream> {
+    let mut last_error = None;
+
+    for address in address.split(';') {
+        let params = match address.strip_prefix("unix:") {
+            None => continue,
+            Some(params) => params,
+        };
+
+        let param = |key: &str| {
+            params
+                .split(',')
+                .find_map(|kv| kv.strip_prefix(key)?.strip_prefix('='))
+        };
+
+        let stream = match (param("path"), param("abstract")) {
+            (Some(path), _) => UnixStream::connect(path),
+
+            (None, Some(name)) => SocketAddr::from_abstract_name(name)
+                .and_then(|address| UnixStream::connect_addr(&address)),
+
+            _ => continue,
+        };
+
+        match stream {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(match last_error {
+        Some(e) => Error::Io(e),
+        None => Error::Config(format!("Unsupported bus address: {}", address)),
+    })
+}
+
+/// Writes messages to the bus, can be shared between threads
+#[derive(Clone, Debug)]
+pub struct DBusSender {
+    stream: Arc<Mutex<UnixStream>>,
+    last_serial: Arc<AtomicU32>,
+}
+
+impl DBusSender {
+    /// Sends message and returns its serial
+    #[inline]
+    pub fn send(&self, message: &DBusMessage) -> Result<u32> {
+        let serial = self.last_serial.fetch_add(1, Ordering::SeqCst) + 1;
+        let bytes = message.encode(serial);
+        self.stream.lock().unwrap().write_all(&bytes)?;
+        Ok(serial)
+    }
+
+    /// Closes connection, so the thread reading messages stops
+    #[inline]
+    pub fn close(&self) {
+        let _ = self.stream.lock().unwrap().shutdown(Shutdown::Both);
+    }
+}
+
+/// Authenticated connection to the message bus
+#[derive(Debug)]
+pub struct DBusConnection {
+    reader: BufReader<UnixStream>,
+    sender: DBusSender,
+    unique_name: String,
+}
+
+impl DBusConnection {
+    #[inline]
+    pub fn session() -> Result<Self> {
+        Self::open(&session_bus_address()?)
+    }
+
+    /// Connects to the bus, authenticates with the user's id
+    /// and registers on the bus
+    #[inline]
+    pub fn open(address: &str) -> Result<Self> {
+        let stream = connect_to_address(address)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream.try_clone()?;
+
+        let uid = fs::metadata("/proc/self")?.uid().to_string();
+        let uid_hex = uid
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+
+        writer.write_all(format!("\0AUTH EXTERNAL {}\r\n", uid_hex).as_bytes())?;
+
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        if !line.starts_with("OK ") {
+            return Err(protocol_error(format!(
+                "Authentication is rejected: {}",
+                line.trim()
+            )));
+        }
+
+        writer.write_all(b"BEGIN\r\n")?;
+
+        let mut connection = Self {
+            reader,
+            sender: DBusSender {
+                stream: Arc::new(Mutex::new(writer)),
+                last_serial: Arc::new(AtomicU32::new(0)),
+            },
+            unique_name: String::new(),
+        };
+
+        let hello = connection.call(DBusMessage::method_call(
+            BUS_NAME,
+            BUS_PATH,
+            BUS_NAME,
+            "Hello",
+            vec![],
+        ))?;
+
+        connection.unique_name = hello
+            .body
+            .first()
+            .and_then(DBusValue::as_str)
+            .ok_or_else(|| protocol_error("Bus didn't return unique name"))?
+            .to_string();
+
+        Ok(connection)
+    }
+
+    #[inline]
+    pub fn get_sender(&self) -> DBusSender {
+        self.sender.clone()
+    }
+
+    /// Name that bus assigned to the connection, e.g. `:1.42`
+    #[inline]
+    pub fn get_unique_name(&self) -> &str {
+        self.unique_name.as_str()
+    }
+
+    #[inline]
+    pub fn read_message(&mut self) -> Result<DBusMessage> {
+        DBusMessage::read_from(&mut self.reader)
+    }
+
+    /// Calls method and waits for its reply.
+    /// Other messages received meanwhile are dropped,
+    /// so it should be used only before messages are dispatched
+    #[inline]
+    pub fn call(&mut self, message: DBusMessage) -> Result<DBusMessage> {
+        let serial = self.sender.send(&message)?;
+
+        loop {
+            let reply = self.read_message()?;
+
+            if reply.reply_serial != Some(serial) {
+                continue;
+            }
+
+            return match reply.message_type {
+                MessageType::Error => Err(protocol_error(format!(
+                    "{}: {}",
+                    reply.error_name.as_deref().unwrap_or_default(),
+                    reply
+                        .body
+                        .first()
+                        .and_then(DBusValue::as_str)
+                        .unwrap_or_default()
+                ))),
+
+                _ => Ok(reply),
+            };
+        }
+    }
+
+    /// Requests well-known name, fails if it's owned by another connection
+    #[inline]
+    pub fn request_name(&mut self, name: &str) -> Result<()> {
+        let reply = self.call(DBusMessage::method_call(
+            BUS_NAME,
+            BUS_PATH,
+            BUS_NAME,
+            "RequestName",
+            vec![
+                DBusValue::String(name.to_string()),
+                DBusValue::UInt32(NAME_FLAG_DO_NOT_QUEUE),
+            ],
+        ))?;
+
+        match reply.body.first().and_then(DBusValue::as_i64) {
+            Some(code) if code as u32 == NAME_REPLY_PRIMARY_OWNER => Ok(()),
+            _ => Err(Error::Config(format!("Bus name {} is already taken", name))),
+        }
+    }
+}
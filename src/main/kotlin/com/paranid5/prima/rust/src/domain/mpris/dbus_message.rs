@@ -0,0 +1,301 @@
+use crate::{
+    domain::mpris::dbus_value::{protocol_error, DBusReader, DBusValue, DBusWriter},
+    error::Result,
+};
+
+use std::io::Read;
+
+/// Version of D-Bus protocol that is written in every message
+const PROTOCOL_VERSION: u8 = 1;
+
+/// Sender doesn't wait for the reply
+pub const NO_REPLY_EXPECTED: u8 = 0x1;
+
+/// Size of the fixed part of the header,
+/// including the length of header fields array
+const FIXED_HEADER_SIZE: usize = 16;
+
+/// Messages bigger than this are rejected by the specification
+const MAX_MESSAGE_SIZE: usize = 128 * 1024 * 1024;
+
+const FIELD_PATH: u8 = 1;
+const FIELD_INTERFACE: u8 = 2;
+const FIELD_MEMBER: u8 = 3;
+const FIELD_ERROR_NAME: u8 = 4;
+const FIELD_REPLY_SERIAL: u8 = 5;
+const FIELD_DESTINATION: u8 = 6;
+const FIELD_SENDER: u8 = 7;
+const FIELD_SIGNATURE: u8 = 8;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessageType {
+    MethodCall = 1,
+    MethodReturn = 2,
+    Error = 3,
+    Signal = 4,
+}
+
+impl TryFrom<u8> for MessageType {
+    type Error = crate::error::Error;
+
+    #[inline]
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            1 => Ok(MessageType::MethodCall),
+            2 => Ok(MessageType::MethodReturn),
+            3 => Ok(MessageType::Error),
+            4 => Ok(MessageType::Signal),
+            _ => Err(protocol_error(format!("Unknown message type: {}", value))),
+        }
+    }
+}
+
+/// Message of D-Bus wire protocol: method call, reply, error or signal
+#[derive(Clone, Debug, PartialEq)]
+pub struct DBusMessage {
+    pub message_type: MessageType,
+    pub flags: u8,
+    pub serial: u32,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    pub reply_serial: Option<u32>,
+    pub destination: Option<String>,
+    pub sender: Option<String>,
+    pub body: Vec<DBusValue>,
+}
+
+impl DBusMessage {
+    #[inline]
+    fn empty(message_type: MessageType) -> Self {
+        Self {
+            message_type,
+            flags: 0,
+            serial: 0,
+            path: None,
+            interface: None,
+            member: None,
+            error_name: None,
+            reply_serial: None,
+            destination: None,
+            sender: None,
+            body: vec![],
+        }
+    }
+
+    #[inline]
+    pub fn method_call(
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        body: Vec<DBusValue>,
+    ) -> Self {
+        Self {
+            destination: Some(destination.to_string()),
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            body,
+            ..Self::empty(MessageType::MethodCall)
+        }
+    }
+
+    #[inline]
+    pub fn signal(path: &str, interface: &str, member: &str, body: Vec<DBusValue>) -> Self {
+        Self {
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            body,
+            ..Self::empty(MessageType::Signal)
+        }
+    }
+
+    /// Successful reply to the method call
+    #[inline]
+    pub fn method_return(call: &DBusMessage, body: Vec<DBusValue>) -> Self {
+        Self {
+            reply_serial: Some(call.serial),
+            destination: call.sender.clone(),
+            body,
+            ..Self::empty(MessageType::MethodReturn)
+        }
+    }
+
+    /// Error reply to the method call
+    #[inline]
+    pub fn error(call: &DBusMessage, error_name: &str, message: &str) -> Self {
+        Self {
+            error_name: Some(error_name.to_string()),
+            reply_serial: Some(call.serial),
+            destination: call.sender.clone(),
+            body: vec![DBusValue::String(message.to_string())],
+            ..Self::empty(MessageType::Error)
+        }
+    }
+
+    #[inline]
+    pub fn is_reply_expected(&self) -> bool {
+        self.message_type == MessageType::MethodCall && self.flags & NO_REPLY_EXPECTED == 0
+    }
+
+    /// Signature of the body
+    #[inline]
+    pub fn signature(&self) -> String {
+        self.body.iter().map(DBusValue::signature).collect()
+    }
+
+    /// Interface and member of the method call, e.g. `("org.freedesktop.DBus.Peer", "Ping")`
+    #[inline]
+    pub fn get_method(&self) -> (&str, &str) {
+        (
+            self.interface.as_deref().unwrap_or_default(),
+            self.member.as_deref().unwrap_or_default(),
+        )
+    }
+
+    /// Marshals message in little-endian byte order with the given serial
+    #[inline]
+    pub fn encode(&self, serial: u32) -> Vec<u8> {
+        let mut fields = Vec::new();
+        let mut field = |code, value| {
+            fields.push(DBusValue::Struct(vec![
+                DBusValue::Byte(code),
+                DBusValue::variant(value),
+            ]))
+        };
+
+        let strings = [
+            (FIELD_PATH, &self.path),
+            (FIELD_INTERFACE, &self.interface),
+            (FIELD_MEMBER, &self.member),
+            (FIELD_ERROR_NAME, &self.error_name),
+            (FIELD_DESTINATION, &self.destination),
+            (FIELD_SENDER, &self.sender),
+        ];
+
+        for (code, value) in strings {
+            if let Some(value) = value {
+                field(
+                    code,
+                    match code {
+                        FIELD_PATH => DBusValue::ObjectPath(value.clone()),
+                        _ => DBusValue::String(value.clone()),
+                    },
+                )
+            }
+        }
+
+        if let Some(reply_serial) = self.reply_serial {
+            field(FIELD_REPLY_SERIAL, DBusValue::UInt32(reply_serial))
+        }
+
+        if !self.body.is_empty() {
+            field(FIELD_SIGNATURE, DBusValue::Signature(self.signature()))
+        }
+
+        let mut writer = DBusWriter::new();
+        writer.write_u8(b'l');
+        writer.write_u8(self.message_type as u8);
+        writer.write_u8(self.flags);
+        writer.write_u8(PROTOCOL_VERSION);
+        writer.write_u32(0);
+        writer.write_u32(serial);
+        writer.write(&DBusValue::Array("(yv)".to_string(), fields));
+        writer.align(8);
+
+        let body_start = writer.len();
+        self.body.iter().for_each(|value| writer.write(value));
+
+        let body_length = (writer.len() - body_start) as u32;
+        writer.patch_u32(4, body_length);
+        writer.into_bytes()
+    }
+
+    /// Reads the next message from the stream
+    #[inline]
+    pub fn read_from<R: Read>(stream: &mut R) -> Result<Self> {
+        let mut message = vec![0_u8; FIXED_HEADER_SIZE];
+        stream.read_exact(&mut message)?;
+
+        let is_big_endian = match message[0] {
+            b'l' => false,
+            b'B' => true,
+            endianness => {
+                return Err(protocol_error(format!(
+                    "Unknown endianness: {}",
+                    endianness
+                )))
+            }
+        };
+
+        let mut reader = DBusReader::new(&message, is_big_endian);
+        reader.set_position(4);
+        let body_length = reader.read_u32()? as usize;
+        reader.set_position(12);
+        let fields_length = reader.read_u32()? as usize;
+
+        let header_length = (FIXED_HEADER_SIZE + fields_length).next_multiple_of(8);
+        let total_length = header_length + body_length;
+
+        if total_length > MAX_MESSAGE_SIZE {
+            return Err(protocol_error(format!(
+                "Message is too big: {} bytes",
+                total_length
+            )));
+        }
+
+        message.resize(total_length, 0);
+        stream.read_exact(&mut message[FIXED_HEADER_SIZE..])?;
+        Self::decode(&message)
+    }
+
+    /// Unmarshals complete message
+    #[inline]
+    pub fn decode(message: &[u8]) -> Result<Self> {
+        if message.len() < FIXED_HEADER_SIZE {
+            return Err(protocol_error("Message is truncated"));
+        }
+
+        let mut reader = DBusReader::new(message, message[0] == b'B');
+        let mut result = Self::empty(MessageType::try_from(message[1])?);
+        result.flags = message[2];
+
+        reader.set_position(8);
+        result.serial = reader.read_u32()?;
+
+        let fields = reader.read("a(yv)")?;
+        let mut signature = String::new();
+
+        for field in fields.as_array().unwrap_or_default() {
+            let (code, value) = match field {
+                DBusValue::Struct(f) if f.len() == 2 => (&f[0], &f[1]),
+                _ => continue,
+            };
+
+            let text = value.as_str().map(String::from);
+
+            match code {
+                DBusValue::Byte(FIELD_PATH) => result.path = text,
+                DBusValue::Byte(FIELD_INTERFACE) => result.interface = text,
+                DBusValue::Byte(FIELD_MEMBER) => result.member = text,
+                DBusValue::Byte(FIELD_ERROR_NAME) => result.error_name = text,
+                DBusValue::Byte(FIELD_DESTINATION) => result.destination = text,
+                DBusValue::Byte(FIELD_SENDER) => result.sender = text,
+                DBusValue::Byte(FIELD_SIGNATURE) => signature = text.unwrap_or_default(),
+
+                DBusValue::Byte(FIELD_REPLY_SERIAL) => {
+                    result.reply_serial = value.as_i64().map(|s| s as u32)
+                }
+
+                _ => {}
+            }
+        }
+
+        reader.align(8);
+        result.body = reader.read_all(&signature)?;
+        Ok(result)
+    }
+}
@@ -0,0 +1,510 @@
+use crate::error::{Error, Result};
+use std::io::{self, ErrorKind};
+
+/// Value of D-Bus type system.
+/// Arrays keep signature of their elements,
+/// so empty arrays can be marshalled too
+#[derive(Clone, Debug, PartialEq)]
+pub enum DBusValue {
+    Byte(u8),
+    Boolean(bool),
+    Int16(i16),
+    UInt16(u16),
+    Int32(i32),
+    UInt32(u32),
+    Int64(i64),
+    UInt64(u64),
+    Double(f64),
+    String(String),
+    ObjectPath(String),
+    Signature(String),
+    Array(String, Vec<DBusValue>),
+    Struct(Vec<DBusValue>),
+    DictEntry(Box<DBusValue>, Box<DBusValue>),
+    Variant(Box<DBusValue>),
+}
+
+/// Malformed message or unexpected reply of the bus
+#[inline]
+pub fn protocol_error(message: impl Into<String>) -> Error {
+    Error::Io(io::Error::new(ErrorKind::InvalidData, message.into()))
+}
+
+impl DBusValue {
+    /// `a{sv}` dictionary that is used for properties and metadata
+    #[inline]
+    pub fn dict<I: IntoIterator<Item = (&'static str, DBusValue)>>(entries: I) -> Self {
+        DBusValue::Array(
+            "{sv}".to_string(),
+            entries
+                .into_iter()
+                .map(|(key, value)| {
+                    DBusValue::DictEntry(
+                        Box::new(DBusValue::String(key.to_string())),
+                        Box::new(DBusValue::Variant(Box::new(value))),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    #[inline]
+    pub fn string_array<I: IntoIterator<Item = String>>(strings: I) -> Self {
+        DBusValue::Array(
+            "s".to_string(),
+            strings.into_iter().map(DBusValue::String).collect(),
+        )
+    }
+
+    #[inline]
+    pub fn object_path_array<I: IntoIterator<Item = String>>(paths: I) -> Self {
+        DBusValue::Array(
+            "o".to_string(),
+            paths.into_iter().map(DBusValue::ObjectPath).collect(),
+        )
+    }
+
+    #[inline]
+    pub fn variant(value: DBusValue) -> Self {
+        DBusValue::Variant(Box::new(value))
+    }
+
+    /// Type signature of the value
+    #[inline]
+    pub fn signature(&self) -> String {
+        match self {
+            DBusValue::Byte(_) => "y".to_string(),
+            DBusValue::Boolean(_) => "b".to_string(),
+            DBusValue::Int16(_) => "n".to_string(),
+            DBusValue::UInt16(_) => "q".to_string(),
+            DBusValue::Int32(_) => "i".to_string(),
+            DBusValue::UInt32(_) => "u".to_string(),
+            DBusValue::Int64(_) => "x".to_string(),
+            DBusValue::UInt64(_) => "t".to_string(),
+            DBusValue::Double(_) => "d".to_string(),
+            DBusValue::String(_) => "s".to_string(),
+            DBusValue::ObjectPath(_) => "o".to_string(),
+            DBusValue::Signature(_) => "g".to_string(),
+            DBusValue::Array(element, _) => format!("a{}", element),
+            DBusValue::Variant(_) => "v".to_string(),
+
+            DBusValue::Struct(fields) => {
+                format!(
+                    "({})",
+                    fields.iter().map(|f| f.signature()).collect::<String>()
+                )
+            }
+
+            DBusValue::DictEntry(key, value) => {
+                format!("{{{}{}}}", key.signature(), value.signature())
+            }
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            DBusValue::String(s) | DBusValue::ObjectPath(s) | DBusValue::Signature(s) => {
+                Some(s.as_str())
+            }
+
+            DBusValue::Variant(value) => value.as_str(),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            DBusValue::Boolean(b) => Some(*b),
+            DBusValue::Variant(value) => value.as_bool(),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            DBusValue::Byte(x) => Some(*x as i64),
+            DBusValue::Int16(x) => Some(*x as i64),
+            DBusValue::UInt16(x) => Some(*x as i64),
+            DBusValue::Int32(x) => Some(*x as i64),
+            DBusValue::UInt32(x) => Some(*x as i64),
+            DBusValue::Int64(x) => Some(*x),
+            DBusValue::UInt64(x) => i64::try_from(*x).ok(),
+            DBusValue::Variant(value) => value.as_i64(),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            DBusValue::Double(x) => Some(*x),
+            DBusValue::Variant(value) => value.as_f64(),
+            value => value.as_i64().map(|x| x as f64),
+        }
+    }
+
+    #[inline]
+    pub fn as_array(&self) -> Option<&[DBusValue]> {
+        match self {
+            DBusValue::Array(_, items) => Some(items.as_slice()),
+            DBusValue::Variant(value) => value.as_array(),
+            _ => None,
+        }
+    }
+}
+
+/// Alignment of the type by the first character of its signature
+#[inline]
+fn alignment_of(signature: &str) -> usize {
+    match signature.as_bytes().first() {
+        Some(b'n' | b'q') => 2,
+        Some(b'b' | b'i' | b'u' | b's' | b'o' | b'a' | b'h') => 4,
+        Some(b'x' | b't' | b'd' | b'(' | b'{') => 8,
+        _ => 1,
+    }
+}
+
+/// Splits signature into the first complete type and the rest
+#[inline]
+pub fn split_first_type(signature: &str) -> Result<(&str, &str)> {
+    let bytes = signature.as_bytes();
+
+    let end = match bytes.first() {
+        None => return Err(protocol_error("Empty signature")),
+
+        Some(b'a') => {
+            let (element, _) = split_first_type(&signature[1..])?;
+            1 + element.len()
+        }
+
+        Some(&open @ (b'(' | b'{')) => {
+            let close = if open == b'(' { b')' } else { b'}' };
+            let mut depth = 0;
+
+            let position = bytes.iter().position(|&b| {
+                if b == open {
+                    depth += 1
+                } else if b == close {
+                    depth -= 1
+                }
+
+                depth == 0
+            });
+
+            position
+                .ok_or_else(|| protocol_error(format!("Unbalanced signature: {}", signature)))?
+                + 1
+        }
+
+        Some(_) => 1,
+    };
+
+    Ok(signature.split_at(end))
+}
+
+/// Splits signature into the list of complete types
+#[inline]
+pub fn split_signature(mut signature: &str) -> Result<Vec<&str>> {
+    let mut types = Vec::new();
+
+    while !signature.is_empty() {
+        let (first, rest) = split_first_type(signature)?;
+        types.push(first);
+        signature = rest;
+    }
+
+    Ok(types)
+}
+
+/// Marshals values into the message buffer.
+/// Alignment is counted from the start of the message
+#[derive(Debug, Default)]
+pub struct DBusWriter {
+    buffer: Vec<u8>,
+}
+
+impl DBusWriter {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    #[inline]
+    pub fn align(&mut self, alignment: usize) {
+        let padding = (alignment - self.buffer.len() % alignment) % alignment;
+        self.buffer.resize(self.buffer.len() + padding, 0)
+    }
+
+    #[inline]
+    pub fn write_u8(&mut self, value: u8) {
+        self.buffer.push(value)
+    }
+
+    #[inline]
+    pub fn write_u32(&mut self, value: u32) {
+        self.align(4);
+        self.buffer.extend_from_slice(&value.to_le_bytes())
+    }
+
+    /// Overwrites previously written u32, e.g. length of array
+    #[inline]
+    pub fn patch_u32(&mut self, position: usize, value: u32) {
+        self.buffer[position..position + 4].copy_from_slice(&value.to_le_bytes())
+    }
+
+    #[inline]
+    pub fn write(&mut self, value: &DBusValue) {
+        match value {
+            DBusValue::Byte(x) => self.write_u8(*x),
+            DBusValue::Boolean(x) => self.write_u32(*x as u32),
+            DBusValue::UInt32(x) => self.write_u32(*x),
+            DBusValue::Int32(x) => self.write_u32(*x as u32),
+            DBusValue::Int16(x) => self.write_fixed(&x.to_le_bytes()),
+            DBusValue::UInt16(x) => self.write_fixed(&x.to_le_bytes()),
+            DBusValue::Int64(x) => self.write_fixed(&x.to_le_bytes()),
+            DBusValue::UInt64(x) => self.write_fixed(&x.to_le_bytes()),
+            DBusValue::Double(x) => self.write_fixed(&x.to_le_bytes()),
+
+            DBusValue::String(s) | DBusValue::ObjectPath(s) => {
+                self.write_u32(s.len() as u32);
+                self.buffer.extend_from_slice(s.as_bytes());
+                self.buffer.push(0)
+            }
+
+            DBusValue::Signature(s) => {
+                self.buffer.push(s.len() as u8);
+                self.buffer.extend_from_slice(s.as_bytes());
+                self.buffer.push(0)
+            }
+
+            DBusValue::Array(element, items) => {
+                self.write_u32(0);
+                let length_position = self.buffer.len() - 4;
+
+                // Padding before the first element is not counted in the length
+                self.align(alignment_of(element));
+                let start = self.buffer.len();
+                items.iter().for_each(|item| self.write(item));
+
+                let length = (self.buffer.len() - start) as u32;
+                self.patch_u32(length_position, length)
+            }
+
+            DBusValue::Struct(fields) => {
+                self.align(8);
+                fields.iter().for_each(|field| self.write(field))
+            }
+
+            DBusValue::DictEntry(key, value) => {
+                self.align(8);
+                self.write(key);
+                self.write(value)
+            }
+
+            DBusValue::Variant(value) => {
+                self.write(&DBusValue::Signature(value.signature()));
+                self.write(value)
+            }
+        }
+    }
+
+    #[inline]
+    fn write_fixed(&mut self, bytes: &[u8]) {
+        self.align(bytes.len());
+        self.buffer.extend_from_slice(bytes)
+    }
+}
+
+/// Unmarshals values from the message buffer
+#[derive(Debug)]
+pub struct DBusReader<'a> {
+    buffer: &'a [u8],
+    position: usize,
+    is_big_endian: bool,
+}
+
+impl<'a> DBusReader<'a> {
+    #[inline]
+    pub fn new(buffer: &'a [u8], is_big_endian: bool) -> Self {
+        Self {
+            buffer,
+            position: 0,
+            is_big_endian,
+        }
+    }
+
+    #[inline]
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    #[inline]
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position
+    }
+
+    #[inline]
+    pub fn align(&mut self, alignment: usize) {
+        self.position += (alignment - self.position % alignment) % alignment
+    }
+
+    #[inline]
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let bytes = self
+            .buffer
+            .get(self.position..self.position + N)
+            .ok_or_else(|| protocol_error("Message is truncated"))?;
+
+        self.position += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    #[inline]
+    fn take_fixed<const N: usize>(&mut self) -> Result<[u8; N]> {
+        self.align(N);
+        let mut bytes = self.take::<N>()?;
+
+        if self.is_big_endian {
+            bytes.reverse()
+        }
+
+        Ok(bytes)
+    }
+
+    #[inline]
+    pub fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take_fixed()?))
+    }
+
+    #[inline]
+    fn read_bytes(&mut self, length: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .buffer
+            .get(self.position..self.position + length)
+            .ok_or_else(|| protocol_error("Message is truncated"))?;
+
+        // Strings are followed by the zero byte
+        self.position += length + 1;
+        Ok(bytes)
+    }
+
+    #[inline]
+    fn read_string(&mut self, length: usize) -> Result<String> {
+        String::from_utf8(self.read_bytes(length)?.to_vec())
+            .map_err(|_| protocol_error("String is not valid UTF-8"))
+    }
+
+    /// Reads value of a single complete type
+    #[inline]
+    pub fn read(&mut self, signature: &str) -> Result<DBusValue> {
+        let code = *signature
+            .as_bytes()
+            .first()
+            .ok_or_else(|| protocol_error("Empty signature"))?;
+
+        Ok(match code {
+            b'y' => DBusValue::Byte(self.take::<1>()?[0]),
+            b'b' => DBusValue::Boolean(self.read_u32()? != 0),
+            b'n' => DBusValue::Int16(i16::from_le_bytes(self.take_fixed()?)),
+            b'q' => DBusValue::UInt16(u16::from_le_bytes(self.take_fixed()?)),
+            b'i' => DBusValue::Int32(self.read_u32()? as i32),
+            b'u' | b'h' => DBusValue::UInt32(self.read_u32()?),
+            b'x' => DBusValue::Int64(i64::from_le_bytes(self.take_fixed()?)),
+            b't' => DBusValue::UInt64(u64::from_le_bytes(self.take_fixed()?)),
+            b'd' => DBusValue::Double(f64::from_le_bytes(self.take_fixed()?)),
+
+            b's' => {
+                let length = self.read_u32()? as usize;
+                DBusValue::String(self.read_string(length)?)
+            }
+
+            b'o' => {
+                let length = self.read_u32()? as usize;
+                DBusValue::ObjectPath(self.read_string(length)?)
+            }
+
+            b'g' => {
+                let length = self.take::<1>()?[0] as usize;
+                DBusValue::Signature(self.read_string(length)?)
+            }
+
+            b'v' => {
+                let length = self.take::<1>()?[0] as usize;
+                let inner = self.read_string(length)?;
+                DBusValue::Variant(Box::new(self.read(&inner)?))
+            }
+
+            b'a' => {
+                let element = split_first_type(&signature[1..])?.0;
+                let length = self.read_u32()? as usize;
+
+                self.align(alignment_of(element));
+                let end = self.position + length;
+                let mut items = Vec::new();
+
+                while self.position < end {
+                    items.push(self.read(element)?)
+                }
+
+                DBusValue::Array(element.to_string(), items)
+            }
+
+            b'(' => {
+                self.align(8);
+                let fields = split_signature(&signature[1..signature.len() - 1])?
+                    .into_iter()
+                    .map(|field| self.read(field))
+                    .collect::<Result<Vec<_>>>()?;
+
+                DBusValue::Struct(fields)
+            }
+
+            b'{' => {
+                self.align(8);
+                let types = split_signature(&signature[1..signature.len() - 1])?;
+
+                if types.len() != 2 {
+                    return Err(protocol_error(format!("Invalid dict entry: {}", signature)));
+                }
+
+                let key = self.read(types[0])?;
+                let value = self.read(types[1])?;
+                DBusValue::DictEntry(Box::new(key), Box::new(value))
+            }
+
+            _ => {
+                return Err(protocol_error(format!(
+                    "Unsupported signature: {}",
+                    signature
+                )))
+            }
+        })
+    }
+
+    /// Reads all values of the signature one after another
+    #[inline]
+    pub fn read_all(&mut self, signature: &str) -> Result<Vec<DBusValue>> {
+        split_signature(signature)?
+            .into_iter()
+            .map(|tp| self.read(tp))
+            .collect()
+    }
+}
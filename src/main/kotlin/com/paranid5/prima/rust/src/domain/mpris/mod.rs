@@ -0,0 +1,4 @@
+pub mod dbus_connection;
+pub mod dbus_message;
+pub mod dbus_value;
+pub mod mpris_server;
@@ -0,0 +1,819 @@
+extern crate tokio;
+
+use crate::{
+    data::{
+        entities::{
+            playlists::{default_playlist::DefaultPlaylist, playlist_trait::PlaylistTrait},
+            tracks::{default_track::DefaultTrack, track_trait::TrackTrait},
+        },
+        utils::file_url::{file_url_to_path, path_to_file_url},
+    },
+    domain::{
+        audio_player::playback_params::LoopingState,
+        cover_art::CoverArt,
+        events::player_event::PlayerEvent,
+        metadata_reader::MetadataReader,
+        mpris::{
+            dbus_connection::{DBusConnection, DBusSender},
+            dbus_message::{DBusMessage, MessageType},
+            dbus_value::DBusValue,
+        },
+        prima::Prima,
+    },
+    error::{Error, Result},
+};
+
+use std::{sync::Arc, thread, time::Duration};
+use tokio::sync::broadcast::error::RecvError;
+
+/// Well-known name that media widgets look for
+pub const MPRIS_BUS_NAME: &str = "org.mpris.MediaPlayer2.prima";
+pub const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+
+const ROOT_INTERFACE: &str = "org.mpris.MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const TRACK_LIST_INTERFACE: &str = "org.mpris.MediaPlayer2.TrackList";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const INTROSPECTABLE_INTERFACE: &str = "org.freedesktop.DBus.Introspectable";
+const PEER_INTERFACE: &str = "org.freedesktop.DBus.Peer";
+
+/// Tracks are identified by their position in the current playlist
+const TRACK_ID_PREFIX: &str = "/com/paranid5/prima/Track/";
+const NO_TRACK_ID: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+const ERROR_UNKNOWN_METHOD: &str = "org.freedesktop.DBus.Error.UnknownMethod";
+const ERROR_UNKNOWN_PROPERTY: &str = "org.freedesktop.DBus.Error.UnknownProperty";
+const ERROR_INVALID_ARGS: &str = "org.freedesktop.DBus.Error.InvalidArgs";
+const ERROR_READ_ONLY: &str = "org.freedesktop.DBus.Error.PropertyReadOnly";
+const ERROR_FAILED: &str = "org.freedesktop.DBus.Error.Failed";
+
+const READER_THREAD_NAME: &str = "prima-mpris";
+const EVENTS_THREAD_NAME: &str = "prima-mpris-events";
+
+const MIN_RATE: f64 = 0.5;
+const MAX_RATE: f64 = 2.0;
+
+const SUPPORTED_MIME_TYPES: [&str; 4] = ["audio/mpeg", "audio/flac", "audio/ogg", "audio/x-wav"];
+
+const INTROSPECTION_XML: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect"><arg name="xml_data" type="s" direction="out"/></method>
+  </interface>
+  <interface name="org.freedesktop.DBus.Peer">
+    <method name="Ping"/>
+    <method name="GetMachineId"><arg name="machine_uuid" type="s" direction="out"/></method>
+  </interface>
+  <interface name="org.freedesktop.DBus.Properties">
+    <method name="Get">
+      <arg name="interface_name" type="s" direction="in"/>
+      <arg name="property_name" type="s" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <method name="GetAll">
+      <arg name="interface_name" type="s" direction="in"/>
+      <arg name="properties" type="a{sv}" direction="out"/>
+    </method>
+    <method name="Set">
+      <arg name="interface_name" type="s" direction="in"/>
+      <arg name="property_name" type="s" direction="in"/>
+      <arg name="value" type="v" direction="in"/>
+    </method>
+    <signal name="PropertiesChanged">
+      <arg name="interface_name" type="s"/>
+      <arg name="changed_properties" type="a{sv}"/>
+      <arg name="invalidated_properties" type="as"/>
+    </signal>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2">
+    <method name="Raise"/>
+    <method name="Quit"/>
+    <property name="CanQuit" type="b" access="read"/>
+    <property name="CanRaise" type="b" access="read"/>
+    <property name="HasTrackList" type="b" access="read"/>
+    <property name="Identity" type="s" access="read"/>
+    <property name="DesktopEntry" type="s" access="read"/>
+    <property name="SupportedUriSchemes" type="as" access="read"/>
+    <property name="SupportedMimeTypes" type="as" access="read"/>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2.Player">
+    <method name="Next"/>
+    <method name="Previous"/>
+    <method name="Pause"/>
+    <method name="PlayPause"/>
+    <method name="Stop"/>
+    <method name="Play"/>
+    <method name="Seek"><arg name="Offset" type="x" direction="in"/></method>
+    <method name="SetPosition">
+      <arg name="TrackId" type="o" direction="in"/>
+      <arg name="Position" type="x" direction="in"/>
+    </method>
+    <method name="OpenUri"><arg name="Uri" type="s" direction="in"/></method>
+    <signal name="Seeked"><arg name="Position" type="x"/></signal>
+    <property name="PlaybackStatus" type="s" access="read"/>
+    <property name="LoopStatus" type="s" access="readwrite"/>
+    <property name="Rate" type="d" access="readwrite"/>
+    <property name="Shuffle" type="b" access="readwrite"/>
+    <property name="Metadata" type="a{sv}" access="read"/>
+    <property name="Volume" type="d" access="readwrite"/>
+    <property name="Position" type="x" access="read"/>
+    <property name="MinimumRate" type="d" access="read"/>
+    <property name="MaximumRate" type="d" access="read"/>
+    <property name="CanGoNext" type="b" access="read"/>
+    <property name="CanGoPrevious" type="b" access="read"/>
+    <property name="CanPlay" type="b" access="read"/>
+    <property name="CanPause" type="b" access="read"/>
+    <property name="CanSeek" type="b" access="read"/>
+    <property name="CanControl" type="b" access="read"/>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2.TrackList">
+    <method name="GetTracksMetadata">
+      <arg name="TrackIds" type="ao" direction="in"/>
+      <arg name="Metadata" type="aa{sv}" direction="out"/>
+    </method>
+    <method name="AddTrack">
+      <arg name="Uri" type="s" direction="in"/>
+      <arg name="AfterTrack" type="o" direction="in"/>
+      <arg name="SetAsCurrent" type="b" direction="in"/>
+    </method>
+    <method name="RemoveTrack"><arg name="TrackId" type="o" direction="in"/></method>
+    <method name="GoTo"><arg name="TrackId" type="o" direction="in"/></method>
+    <signal name="TrackListReplaced">
+      <arg name="Tracks" type="ao"/>
+      <arg name="CurrentTrack" type="o"/>
+    </signal>
+    <property name="Tracks" type="ao" access="read"/>
+    <property name="CanEditTracks" type="b" access="read"/>
+  </interface>
+</node>"#;
+
+/// D-Bus error that is sent as a reply to the failed call
+#[derive(Debug)]
+struct MethodError {
+    name: &'static str,
+    message: String,
+}
+
+impl MethodError {
+    #[inline]
+    fn new(name: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            message: message.into(),
+        }
+    }
+
+    #[inline]
+    fn invalid_args() -> Self {
+        Self::new(ERROR_INVALID_ARGS, "Invalid arguments")
+    }
+}
+
+impl From<Error> for MethodError {
+    #[inline]
+    fn from(e: Error) -> Self {
+        Self::new(ERROR_FAILED, e.to_string())
+    }
+}
+
+type MethodResult = std::result::Result<Vec<DBusValue>, MethodError>;
+
+#[inline]
+fn track_id(index: usize) -> String {
+    format!("{}{}", TRACK_ID_PREFIX, index)
+}
+
+#[inline]
+fn track_index(track_id: &str) -> Option<usize> {
+    track_id.strip_prefix(TRACK_ID_PREFIX)?.parse().ok()
+}
+
+#[inline]
+fn micros(duration: Duration) -> i64 {
+    duration.as_micros() as i64
+}
+
+#[inline]
+fn looping_status(looping_state: LoopingState) -> &'static str {
+    match looping_state {
+        LoopingState::NoLooping => "None",
+        LoopingState::Track => "Track",
+        LoopingState::Playlist => "Playlist",
+    }
+}
+
+/// `xesam` and `mpris` metadata of the playlist's track
+#[inline]
+fn track_metadata(track: &DefaultTrack, index: usize) -> DBusValue {
+    let text = |s: Option<&String>| s.filter(|s| !s.is_empty()).cloned();
+
+    let title = text(track.get_title()).unwrap_or_else(|| {
+        track
+            .get_path()
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+
+    let mut metadata = vec![
+        ("mpris:trackid", DBusValue::ObjectPath(track_id(index))),
+        (
+            "mpris:length",
+            DBusValue::Int64(track.get_duration().num_microseconds().unwrap_or_default()),
+        ),
+        ("xesam:title", DBusValue::String(title)),
+        (
+            "xesam:url",
+            DBusValue::String(path_to_file_url(track.get_path())),
+        ),
+    ];
+
+    if let Some(artist) = text(track.get_artist()) {
+        metadata.push(("xesam:artist", DBusValue::string_array([artist])))
+    }
+
+    if let Some(album) = text(track.get_album()) {
+        metadata.push(("xesam:album", DBusValue::String(album)))
+    }
+
+    if track.get_number_in_album() > 0 {
+        metadata.push((
+            "xesam:trackNumber",
+            DBusValue::Int32(track.get_number_in_album() as i32),
+        ))
+    }
+
+    if let Some(cover) = CoverArt::find(track.get_path()) {
+        metadata.push(("mpris:artUrl", DBusValue::String(path_to_file_url(&cover))))
+    }
+
+    DBusValue::dict(metadata)
+}
+
+#[inline]
+fn playlist_metadata(playlist: &DefaultPlaylist<DefaultTrack>) -> DBusValue {
+    match playlist.get_cur_track() {
+        None => DBusValue::dict([(
+            "mpris:trackid",
+            DBusValue::ObjectPath(NO_TRACK_ID.to_string()),
+        )]),
+        Some(track) => track_metadata(track, playlist.get_cur_ind()),
+    }
+}
+
+#[inline]
+fn track_ids(playlist: &DefaultPlaylist<DefaultTrack>) -> DBusValue {
+    DBusValue::object_path_array((0..playlist.len()).map(track_id))
+}
+
+#[inline]
+fn properties_changed(interface: &str, changed: Vec<(&'static str, DBusValue)>) -> DBusMessage {
+    DBusMessage::signal(
+        MPRIS_PATH,
+        PROPERTIES_INTERFACE,
+        "PropertiesChanged",
+        vec![
+            DBusValue::String(interface.to_string()),
+            DBusValue::dict(changed),
+            DBusValue::string_array([]),
+        ],
+    )
+}
+
+/// MPRIS2 service: desktop media widgets and media keys
+/// control the session through `org.mpris.MediaPlayer2.prima`.
+/// Implements `MediaPlayer2`, `Player` and `TrackList` interfaces.
+/// Service is stopped when the server is dropped
+pub struct MprisServer {
+    sender: DBusSender,
+    unique_name: String,
+}
+
+impl MprisServer {
+    /// Registers service on the user's session bus
+    #[inline]
+    pub fn start(prima: Arc<Prima>) -> Result<Self> {
+        Self::start_with(prima, DBusConnection::session()?)
+    }
+
+    /// Registers service on the bus with the given address,
+    /// e.g. on a private bus started for tests
+    #[inline]
+    pub fn start_on(prima: Arc<Prima>, address: &str) -> Result<Self> {
+        Self::start_with(prima, DBusConnection::open(address)?)
+    }
+
+    #[inline]
+    fn start_with(prima: Arc<Prima>, mut connection: DBusConnection) -> Result<Self> {
+        connection.request_name(MPRIS_BUS_NAME)?;
+
+        let sender = connection.get_sender();
+        let unique_name = connection.get_unique_name().to_string();
+        let receiver = prima.get_event_bus().subscribe();
+
+        let handler = MprisHandler {
+            prima,
+            sender: sender.clone(),
+        };
+
+        let events_handler = handler.clone();
+
+        thread::Builder::new()
+            .name(EVENTS_THREAD_NAME.to_string())
+            .spawn(move || events_handler.run_event_loop(receiver))?;
+
+        thread::Builder::new()
+            .name(READER_THREAD_NAME.to_string())
+            .spawn(move || handler.run_call_loop(connection))?;
+
+        Ok(Self {
+            sender,
+            unique_name,
+        })
+    }
+
+    /// Name that the bus assigned to the service's connection
+    #[inline]
+    pub fn get_unique_name(&self) -> &str {
+        self.unique_name.as_str()
+    }
+}
+
+impl Drop for MprisServer {
+    #[inline]
+    fn drop(&mut self) {
+        self.sender.close()
+    }
+}
+
+#[derive(Clone)]
+struct MprisHandler {
+    prima: Arc<Prima>,
+    sender: DBusSender,
+}
+
+impl MprisHandler {
+    /// Answers method calls until connection is closed
+    #[inline]
+    fn run_call_loop(&self, mut connection: DBusConnection) {
+        while let Ok(message) = connection.read_message() {
+            if message.message_type != MessageType::MethodCall {
+                continue;
+            }
+
+            let reply = match self.handle_call(&message) {
+                Ok(body) => DBusMessage::method_return(&message, body),
+                Err(e) => DBusMessage::error(&message, e.name, &e.message),
+            };
+
+            if message.is_reply_expected() && self.sender.send(&reply).is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Turns player's events into `PropertiesChanged` and `TrackListReplaced` signals
+    #[inline]
+    fn run_event_loop(&self, mut receiver: tokio::sync::broadcast::Receiver<PlayerEvent>) {
+        loop {
+            let signals = match receiver.blocking_recv() {
+                Ok(event) => self.signals_of(event),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+
+            if signals
+                .iter()
+                .any(|signal| self.sender.send(signal).is_err())
+            {
+                break;
+            }
+        }
+    }
+
+    #[inline]
+    fn signals_of(&self, event: PlayerEvent) -> Vec<DBusMessage> {
+        match event {
+            PlayerEvent::StateChanged { .. } => vec![properties_changed(
+                PLAYER_INTERFACE,
+                vec![("PlaybackStatus", self.playback_status())],
+            )],
+
+            PlayerEvent::TrackChanged { index, track } => {
+                let metadata = match track {
+                    None => playlist_metadata(&DefaultPlaylist::default()),
+                    Some(track) => track_metadata(&track, index),
+                };
+
+                vec![properties_changed(
+                    PLAYER_INTERFACE,
+                    vec![("Metadata", metadata)],
+                )]
+            }
+
+            PlayerEvent::QueueChanged { .. } => {
+                let playlist = self.prima.get_cur_playlist();
+
+                let current = match playlist.get_cur_track() {
+                    None => NO_TRACK_ID.to_string(),
+                    Some(_) => track_id(playlist.get_cur_ind()),
+                };
+
+                vec![
+                    DBusMessage::signal(
+                        MPRIS_PATH,
+                        TRACK_LIST_INTERFACE,
+                        "TrackListReplaced",
+                        vec![track_ids(&playlist), DBusValue::ObjectPath(current)],
+                    ),
+                    properties_changed(
+                        PLAYER_INTERFACE,
+                        vec![
+                            ("Metadata", playlist_metadata(&playlist)),
+                            ("Shuffle", DBusValue::Boolean(self.prima.is_shuffled())),
+                        ],
+                    ),
+                ]
+            }
+
+            _ => vec![],
+        }
+    }
+
+    #[inline]
+    fn handle_call(&self, call: &DBusMessage) -> MethodResult {
+        let (interface, member) = call.get_method();
+
+        match interface {
+            PROPERTIES_INTERFACE => self.handle_properties_call(member, &call.body),
+            ROOT_INTERFACE => Ok(vec![]),
+            PLAYER_INTERFACE => self.handle_player_call(member, &call.body),
+            TRACK_LIST_INTERFACE => self.handle_track_list_call(member, &call.body),
+
+            INTROSPECTABLE_INTERFACE if member == "Introspect" => {
+                Ok(vec![DBusValue::String(INTROSPECTION_XML.to_string())])
+            }
+
+            PEER_INTERFACE if member == "Ping" => Ok(vec![]),
+
+            PEER_INTERFACE if member == "GetMachineId" => Ok(vec![DBusValue::String(
+                std::fs::read_to_string("/etc/machine-id")
+                    .map(|id| id.trim().to_string())
+                    .unwrap_or_default(),
+            )]),
+
+            _ => Err(MethodError::new(
+                ERROR_UNKNOWN_METHOD,
+                format!("Unknown method {}.{}", interface, member),
+            )),
+        }
+    }
+
+    #[inline]
+    fn handle_properties_call(&self, member: &str, args: &[DBusValue]) -> MethodResult {
+        let interface = args
+            .first()
+            .and_then(DBusValue::as_str)
+            .ok_or_else(MethodError::invalid_args)?;
+
+        let properties = self.properties_of(interface).ok_or_else(|| {
+            MethodError::new(
+                ERROR_UNKNOWN_PROPERTY,
+                format!("Unknown interface {}", interface),
+            )
+        })?;
+
+        let property = args.get(1).and_then(DBusValue::as_str);
+
+        match (member, property) {
+            ("GetAll", _) => Ok(vec![DBusValue::dict(properties)]),
+
+            ("Get", Some(property)) => properties
+                .into_iter()
+                .find(|(name, _)| *name == property)
+                .map(|(_, value)| vec![DBusValue::variant(value)])
+                .ok_or_else(|| {
+                    MethodError::new(
+                        ERROR_UNKNOWN_PROPERTY,
+                        format!("Unknown property {}", property),
+                    )
+                }),
+
+            ("Set", Some(property)) => {
+                let value = args.get(2).ok_or_else(MethodError::invalid_args)?;
+                self.set_property(interface, property, value)?;
+                Ok(vec![])
+            }
+
+            _ => Err(MethodError::new(
+                ERROR_UNKNOWN_METHOD,
+                format!("Unknown method {}", member),
+            )),
+        }
+    }
+
+    #[inline]
+    fn playback_status(&self) -> DBusValue {
+        let status = match (self.prima.is_playing(), self.prima.get_cur_track()) {
+            (true, _) => "Playing",
+            (false, Some(_)) => "Paused",
+            (false, None) => "Stopped",
+        };
+
+        DBusValue::String(status.to_string())
+    }
+
+    /// All properties of the interface or None if it has no properties
+    #[inline]
+    fn properties_of(&self, interface: &str) -> Option<Vec<(&'static str, DBusValue)>> {
+        match interface {
+            ROOT_INTERFACE => Some(vec![
+                ("CanQuit", DBusValue::Boolean(false)),
+                ("CanRaise", DBusValue::Boolean(false)),
+                ("HasTrackList", DBusValue::Boolean(true)),
+                ("Identity", DBusValue::String("Prima".to_string())),
+                ("DesktopEntry", DBusValue::String("prima".to_string())),
+                (
+                    "SupportedUriSchemes",
+                    DBusValue::string_array(["file".to_string()]),
+                ),
+                (
+                    "SupportedMimeTypes",
+                    DBusValue::string_array(SUPPORTED_MIME_TYPES.map(String::from)),
+                ),
+            ]),
+
+            PLAYER_INTERFACE => {
+                let playlist = self.prima.get_cur_playlist();
+                let has_track = playlist.get_cur_track().is_some();
+
+                Some(vec![
+                    ("PlaybackStatus", self.playback_status()),
+                    (
+                        "LoopStatus",
+                        DBusValue::String(
+                            looping_status(self.prima.get_looping_state()).to_string(),
+                        ),
+                    ),
+                    ("Rate", DBusValue::Double(self.prima.get_speed() as f64)),
+                    ("Shuffle", DBusValue::Boolean(self.prima.is_shuffled())),
+                    ("Metadata", playlist_metadata(&playlist)),
+                    ("Volume", DBusValue::Double(self.prima.get_volume() as f64)),
+                    (
+                        "Position",
+                        DBusValue::Int64(micros(self.prima.get_playback_position())),
+                    ),
+                    ("MinimumRate", DBusValue::Double(MIN_RATE)),
+                    ("MaximumRate", DBusValue::Double(MAX_RATE)),
+                    ("CanGoNext", DBusValue::Boolean(has_track)),
+                    ("CanGoPrevious", DBusValue::Boolean(has_track)),
+                    ("CanPlay", DBusValue::Boolean(has_track)),
+                    ("CanPause", DBusValue::Boolean(has_track)),
+                    ("CanSeek", DBusValue::Boolean(has_track)),
+                    ("CanControl", DBusValue::Boolean(true)),
+                ])
+            }
+
+            TRACK_LIST_INTERFACE => Some(vec![
+                ("Tracks", track_ids(&self.prima.get_cur_playlist())),
+                ("CanEditTracks", DBusValue::Boolean(true)),
+            ]),
+
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn set_property(
+        &self,
+        interface: &str,
+        property: &str,
+        value: &DBusValue,
+    ) -> std::result::Result<(), MethodError> {
+        if interface != PLAYER_INTERFACE {
+            return Err(MethodError::new(
+                ERROR_READ_ONLY,
+                format!("{} is read-only", property),
+            ));
+        }
+
+        let changed = match property {
+            "LoopStatus" => {
+                let looping_state = match value.as_str() {
+                    Some("None") => LoopingState::NoLooping,
+                    Some("Track") => LoopingState::Track,
+                    Some("Playlist") => LoopingState::Playlist,
+                    _ => return Err(MethodError::invalid_args()),
+                };
+
+                self.prima.set_looping_state(looping_state);
+                (
+                    "LoopStatus",
+                    DBusValue::String(looping_status(looping_state).to_string()),
+                )
+            }
+
+            "Rate" => {
+                let rate = value.as_f64().ok_or_else(MethodError::invalid_args)?;
+
+                // Zero rate means pause by the specification
+                if rate <= 0.0 {
+                    self.prima.pause_playback();
+                    return Ok(());
+                }
+
+                self.prima.set_speed(rate.clamp(MIN_RATE, MAX_RATE) as f32);
+                ("Rate", DBusValue::Double(self.prima.get_speed() as f64))
+            }
+
+            "Shuffle" => {
+                let is_shuffled = value.as_bool().ok_or_else(MethodError::invalid_args)?;
+                self.prima.set_shuffle(is_shuffled);
+                ("Shuffle", DBusValue::Boolean(is_shuffled))
+            }
+
+            "Volume" => {
+                let volume = value.as_f64().ok_or_else(MethodError::invalid_args)?;
+                self.prima.set_volume(volume.max(0.0) as f32);
+                ("Volume", DBusValue::Double(self.prima.get_volume() as f64))
+            }
+
+            _ => {
+                return Err(MethodError::new(
+                    ERROR_READ_ONLY,
+                    format!("{} is read-only", property),
+                ))
+            }
+        };
+
+        self.sender
+            .send(&properties_changed(PLAYER_INTERFACE, vec![changed]))
+            .map(|_| ())
+            .map_err(MethodError::from)
+    }
+
+    #[inline]
+    fn handle_player_call(&self, member: &str, args: &[DBusValue]) -> MethodResult {
+        match member {
+            "Next" => self.spawn_action(|prima| prima.next_track()),
+            "Previous" => self.spawn_action(|prima| prima.previous_track()),
+            "Pause" => self.prima.pause_playback(),
+            "Stop" => self.prima.stop_playback(),
+            "PlayPause" => self.spawn_action(|prima| prima.play_pause()),
+
+            "Play" => {
+                if !self.prima.is_playing() {
+                    self.spawn_action(|prima| prima.play_pause())
+                }
+            }
+
+            "Seek" => {
+                let offset = args
+                    .first()
+                    .and_then(DBusValue::as_i64)
+                    .ok_or_else(MethodError::invalid_args)?;
+
+                let position = micros(self.prima.get_playback_position()) + offset;
+                self.seek(position.max(0))?
+            }
+
+            "SetPosition" => {
+                let (track, position) = match args {
+                    [track, position] => (track.as_str(), position.as_i64()),
+                    _ => return Err(MethodError::invalid_args()),
+                };
+
+                let playlist = self.prima.get_cur_playlist();
+                let position = position.ok_or_else(MethodError::invalid_args)?;
+
+                // Requests for the track that is not current anymore are ignored
+                if track.and_then(track_index) == Some(playlist.get_cur_ind()) && position >= 0 {
+                    self.seek(position)?
+                }
+            }
+
+            "OpenUri" => {
+                let track = args
+                    .first()
+                    .and_then(DBusValue::as_str)
+                    .and_then(file_url_to_path)
+                    .and_then(|path| MetadataReader::read_track(&path))
+                    .ok_or_else(|| MethodError::new(ERROR_INVALID_ARGS, "Unsupported URI"))?;
+
+                self.spawn_action(move |prima| prima.play(DefaultPlaylist::from_iter([track])))
+            }
+
+            _ => {
+                return Err(MethodError::new(
+                    ERROR_UNKNOWN_METHOD,
+                    format!("Unknown method {}", member),
+                ))
+            }
+        }
+
+        Ok(vec![])
+    }
+
+    /// Seeks current track, position past the end skips to the next track
+    #[inline]
+    fn seek(&self, position: i64) -> std::result::Result<(), MethodError> {
+        let track = match self.prima.get_cur_track() {
+            None => return Ok(()),
+            Some(track) => track,
+        };
+
+        if position > track.get_duration().num_microseconds().unwrap_or_default() {
+            self.spawn_action(|prima| prima.next_track());
+            return Ok(());
+        }
+
+        let position = Duration::from_micros(position as u64);
+        self.spawn_action(move |prima| prima.seek_to(position));
+
+        self.sender.send(&DBusMessage::signal(
+            MPRIS_PATH,
+            PLAYER_INTERFACE,
+            "Seeked",
+            vec![DBusValue::Int64(micros(position))],
+        ))?;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn handle_track_list_call(&self, member: &str, args: &[DBusValue]) -> MethodResult {
+        let playlist = self.prima.get_cur_playlist();
+
+        let index_of = |value: Option<&DBusValue>| {
+            value
+                .and_then(DBusValue::as_str)
+                .and_then(track_index)
+                .filter(|index| *index < playlist.len())
+        };
+
+        match member {
+            "GetTracksMetadata" => {
+                let metadata = args
+                    .first()
+                    .and_then(DBusValue::as_array)
+                    .ok_or_else(MethodError::invalid_args)?
+                    .iter()
+                    .filter_map(|id| index_of(Some(id)))
+                    .map(|index| track_metadata(&playlist.get_tracks()[index], index))
+                    .collect();
+
+                return Ok(vec![DBusValue::Array("a{sv}".to_string(), metadata)]);
+            }
+
+            "AddTrack" => {
+                let track = args
+                    .first()
+                    .and_then(DBusValue::as_str)
+                    .and_then(file_url_to_path)
+                    .and_then(|path| MetadataReader::read_track(&path))
+                    .ok_or_else(|| MethodError::new(ERROR_INVALID_ARGS, "Unsupported URI"))?;
+
+                // Track is added to the beginning when it is not after another one
+                let position = index_of(args.get(1)).map(|i| i + 1).unwrap_or_default();
+                let set_as_current = args.get(2).and_then(DBusValue::as_bool).unwrap_or(false);
+
+                self.prima.add_to_queue(vec![track], Some(position));
+
+                if set_as_current {
+                    self.spawn_action(move |prima| prima.play_at(position))
+                }
+            }
+
+            "RemoveTrack" => {
+                let index = index_of(args.first()).ok_or_else(MethodError::invalid_args)?;
+                self.prima.remove_from_queue(index)?
+            }
+
+            "GoTo" => {
+                let index = index_of(args.first()).ok_or_else(MethodError::invalid_args)?;
+                self.spawn_action(move |prima| prima.play_at(index))
+            }
+
+            _ => {
+                return Err(MethodError::new(
+                    ERROR_UNKNOWN_METHOD,
+                    format!("Unknown method {}", member),
+                ))
+            }
+        }
+
+        Ok(vec![])
+    }
+
+    /// Playing blocks until the track ends, but the call must be answered now
+    #[inline]
+    fn spawn_action<F>(&self, action: F)
+    where
+        F: FnOnce(&Prima) -> Result<()> + Send + 'static,
+    {
+        if let Err(e) = Prima::spawn_action(self.prima.clone(), action) {
+            eprintln!("Unable to start MPRIS action: {}", e)
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    data::utils::http_client::HttpClient,
+    error::{Error, Result},
+};
+
+/// Extension of the downloaded file whose URL doesn't tell it
+const DEFAULT_EXTENSION: &str = "mp3";
+
+/// Suffix of the file that is being downloaded
+const PARTIAL_SUFFIX: &str = ".part";
+
+/// Downloads podcast episodes into the managed directory
+#[derive(Debug)]
+pub struct EpisodeDownloader;
+
+impl EpisodeDownloader {
+    /// Downloads audio to the file and returns its size.
+    /// Received bytes are kept in `<file>.part`, so interrupted download
+    /// continues where it has stopped if the server supports ranges.
+    /// File appears only when the download is complete
+    #[inline]
+    pub fn download(url: &str, file: &Path) -> Result<u64> {
+        if let Ok(metadata) = fs::metadata(file) {
+            return Ok(metadata.len());
+        }
+
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let partial = Self::partial_path_of(file);
+        let offset = fs::metadata(&partial).map_or(0, |metadata| metadata.len());
+
+        let size = match Self::download_from(url, &partial, offset)? {
+            Some(size) => size,
+
+            // Server doesn't accept the range: part is stale
+            None => {
+                fs::remove_file(&partial)?;
+                Self::download_from(url, &partial, 0)?.unwrap_or_default()
+            }
+        };
+
+        fs::rename(&partial, file)?;
+        Ok(size)
+    }
+
+    /// File where the downloaded part is kept
+    #[inline]
+    pub fn partial_path_of(file: &Path) -> PathBuf {
+        let mut partial = file.as_os_str().to_owned();
+        partial.push(PARTIAL_SUFFIX);
+        PathBuf::from(partial)
+    }
+
+    /// Name of the episode's file: its id and the extension of its URL
+    #[inline]
+    pub fn file_name_of(episode_id: i32, audio_url: &str) -> String {
+        let path = audio_url.split(['?', '#']).next().unwrap_or_default();
+        let name = path.rsplit('/').next().unwrap_or_default();
+
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_lowercase())
+            .filter(|extension| {
+                (1..=5).contains(&extension.len())
+                    && extension.chars().all(|c| c.is_ascii_alphanumeric())
+            })
+            .unwrap_or_else(|| DEFAULT_EXTENSION.to_string());
+
+        format!("{}.{}", episode_id, extension)
+    }
+
+    /// Appends the rest of the audio to the part from the offset.
+    /// Returns the size of the complete part or None if the range is not satisfiable
+    #[inline]
+    fn download_from(url: &str, partial: &Path, offset: u64) -> Result<Option<u64>> {
+        let range = format!("bytes={}-", offset);
+
+        let headers = match offset {
+            0 => vec![],
+            _ => vec![("Range", range.as_str())],
+        };
+
+        let mut stream = HttpClient::open_stream(url, &headers)?;
+
+        let is_resumed = match stream.status {
+            206 => content_range_start(stream.header("Content-Range")) == Some(offset),
+            416 if offset > 0 => return Ok(None),
+            status if (200..300).contains(&status) => false,
+
+            status => {
+                return Err(Error::Io(io::Error::new(
+                    ErrorKind::ConnectionRefused,
+                    format!("{} has replied with {}", url, status),
+                )))
+            }
+        };
+
+        // Partial reply from another position can't be appended
+        if stream.status == 206 && !is_resumed {
+            return Ok(None);
+        }
+
+        let expected_len = stream
+            .header("Content-Length")
+            .and_then(|len| len.trim().parse::<u64>().ok());
+
+        let file = match is_resumed {
+            true => OpenOptions::new().append(true).open(partial)?,
+            false => File::create(partial)?,
+        };
+
+        let mut writer = BufWriter::new(file);
+        let received = io::copy(&mut stream, &mut writer)?;
+        writer.flush()?;
+
+        if expected_len.is_some_and(|len| received < len) {
+            return Err(Error::Io(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("Download of {} is interrupted", url),
+            )));
+        }
+
+        Ok(Some(match is_resumed {
+            true => offset + received,
+            false => received,
+        }))
+    }
+}
+
+/// First byte of `Content-Range: bytes 100-199/200`
+#[inline]
+fn content_range_start(content_range: Option<&str>) -> Option<u64> {
+    content_range?
+        .trim()
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
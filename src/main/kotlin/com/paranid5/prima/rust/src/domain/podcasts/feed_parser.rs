@@ -0,0 +1,189 @@
+extern crate chrono;
+
+use chrono::DateTime;
+
+use std::{
+    io::{self, ErrorKind},
+    time::Duration,
+};
+
+use crate::{
+    data::utils::{
+        http_client::{resolve_location, HttpClient},
+        xml::XmlElement,
+    },
+    error::{Error, Result},
+};
+
+/// Podcast as it's described by its feed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsedFeed {
+    pub title: String,
+    pub description: Option<String>,
+
+    /// Episodes in the order of the feed, usually the newest first
+    pub episodes: Vec<ParsedEpisode>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsedEpisode {
+    pub guid: String,
+    pub title: String,
+    pub audio_url: String,
+    pub published_at: Option<i64>,
+    pub duration: Option<Duration>,
+}
+
+/// Reads podcasts from RSS 2.0 and Atom feeds
+#[derive(Debug)]
+pub struct FeedParser;
+
+impl FeedParser {
+    /// Downloads and parses the feed
+    #[inline]
+    pub fn fetch(url: &str) -> Result<ParsedFeed> {
+        let reply = HttpClient::get(
+            url,
+            &[("Accept", "application/rss+xml, application/atom+xml, */*")],
+        )?;
+
+        if !reply.is_success() {
+            return Err(Error::Io(io::Error::new(
+                ErrorKind::ConnectionRefused,
+                format!("{} has replied with {}", url, reply.status),
+            )));
+        }
+
+        Self::parse(&reply.text(), url)
+    }
+
+    /// Items without audio are skipped.
+    /// Relative URLs are resolved against the feed's URL
+    #[inline]
+    pub fn parse(xml: &str, url: &str) -> Result<ParsedFeed> {
+        let not_feed = || Error::Decode(format!("{}: not an RSS or Atom feed", url));
+        let root = XmlElement::parse(xml).map_err(|_| not_feed())?;
+
+        let (channel, item_name, episode_of, description_name) = match root.name.as_str() {
+            "rss" => (
+                root.child("channel").ok_or_else(not_feed)?,
+                "item",
+                rss_episode as fn(&XmlElement, &str) -> Option<ParsedEpisode>,
+                "description",
+            ),
+
+            "feed" => (&root, "entry", atom_episode as _, "subtitle"),
+            _ => return Err(not_feed()),
+        };
+
+        let episodes = channel
+            .children_named(item_name)
+            .filter_map(|item| episode_of(item, url))
+            .collect();
+
+        Ok(ParsedFeed {
+            title: channel.child_text("title").unwrap_or(url).to_string(),
+            description: channel
+                .child_text(description_name)
+                .or_else(|| channel.child_text("itunes:summary"))
+                .map(str::to_string),
+            episodes,
+        })
+    }
+}
+
+/// `<item>` with `<enclosure url="...">`
+#[inline]
+fn rss_episode(item: &XmlElement, feed_url: &str) -> Option<ParsedEpisode> {
+    let audio_url = item
+        .child("enclosure")
+        .and_then(|enclosure| enclosure.attribute("url"))
+        .or_else(|| {
+            item.child("media:content")
+                .and_then(|content| content.attribute("url"))
+        })?;
+
+    let published_at = item
+        .child_text("pubDate")
+        .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+        .map(|date| date.timestamp());
+
+    Some(episode(
+        item,
+        feed_url,
+        audio_url,
+        item.child_text("guid"),
+        published_at,
+    ))
+}
+
+/// `<entry>` with `<link rel="enclosure" href="...">`
+#[inline]
+fn atom_episode(entry: &XmlElement, feed_url: &str) -> Option<ParsedEpisode> {
+    let audio_url = entry
+        .children_named("link")
+        .find(|link| link.attribute("rel") == Some("enclosure"))
+        .and_then(|link| link.attribute("href"))?;
+
+    let published_at = entry
+        .child_text("published")
+        .or_else(|| entry.child_text("updated"))
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.timestamp());
+
+    Some(episode(
+        entry,
+        feed_url,
+        audio_url,
+        entry.child_text("id"),
+        published_at,
+    ))
+}
+
+/// Episode without title is named after its file
+#[inline]
+fn episode(
+    item: &XmlElement,
+    feed_url: &str,
+    audio_url: &str,
+    guid: Option<&str>,
+    published_at: Option<i64>,
+) -> ParsedEpisode {
+    let audio_url = resolve_location(feed_url, audio_url.trim());
+
+    let title = item
+        .child_text("title")
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            let path = audio_url.split(['?', '#']).next().unwrap_or_default();
+            path.rsplit('/').next().unwrap_or(path).to_string()
+        });
+
+    ParsedEpisode {
+        guid: guid.map_or_else(|| audio_url.clone(), str::to_string),
+        title,
+        published_at,
+        duration: item.child_text("itunes:duration").and_then(parse_duration),
+        audio_url,
+    }
+}
+
+/// Duration as seconds, `mm:ss` or `hh:mm:ss`
+#[inline]
+pub fn parse_duration(duration: &str) -> Option<Duration> {
+    let parts = duration.trim().split(':').collect::<Vec<_>>();
+
+    if parts.len() > 3 {
+        return None;
+    }
+
+    parts
+        .into_iter()
+        .try_fold(0.0, |secs: f64, part| {
+            part.parse::<f64>()
+                .ok()
+                .filter(|part| part.is_finite() && *part >= 0.0)
+                .map(|part| secs * 60.0 + part)
+        })
+        .map(Duration::from_secs_f64)
+}
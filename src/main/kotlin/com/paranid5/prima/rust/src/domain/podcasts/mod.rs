@@ -0,0 +1,5 @@
+pub mod episode_downloader;
+pub mod feed_parser;
+pub mod podcast_episode;
+pub mod podcast_feed;
+pub mod podcast_refresher;
@@ -0,0 +1,28 @@
+use std::{path::PathBuf, time::Duration};
+
+/// Episode of the subscribed podcast
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PodcastEpisode {
+    pub id: i32,
+    pub feed_id: i32,
+
+    /// Identifies the episode in the feed, audio URL if the feed has no guid
+    pub guid: String,
+    pub title: String,
+    pub audio_url: String,
+
+    /// Unix time of the publication and duration, if the feed tells them
+    pub published_at: Option<i64>,
+    pub duration: Option<Duration>,
+
+    /// Downloaded audio file, None until the download is complete
+    pub file: Option<PathBuf>,
+
+    /// Episode is downloaded by the next refresh
+    pub is_download_queued: bool,
+
+    pub is_played: bool,
+
+    /// Where the episode was left, zero for played episodes
+    pub position: Duration,
+}
@@ -0,0 +1,14 @@
+/// Podcast that the user has subscribed to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PodcastFeed {
+    pub id: i32,
+
+    /// HTTP(S) URL of RSS or Atom feed
+    pub url: String,
+    pub title: String,
+    pub description: Option<String>,
+
+    /// Unix time when the feed was subscribed to and was fetched last
+    pub added_at: i64,
+    pub refreshed_at: i64,
+}
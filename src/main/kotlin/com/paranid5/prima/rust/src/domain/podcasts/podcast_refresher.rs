@@ -0,0 +1,53 @@
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{domain::prima::Prima, error::Result};
+
+const PODCASTS_THREAD_NAME: &str = "prima-podcasts";
+
+/// How often podcasts are refreshed, unless the user chooses another interval
+pub const DEFAULT_PODCAST_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Shorter intervals are raised to this, so servers are not flooded
+const MIN_PODCAST_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Refreshes subscribed podcasts on its own thread, as soon as it's started
+/// and then once in the stored interval. New episodes are downloaded.
+/// Thread stops when the refresher is dropped, running refresh is finished first
+pub struct PodcastRefresher {
+    _stop_sender: Sender<()>,
+}
+
+impl PodcastRefresher {
+    #[inline]
+    pub fn start(prima: Arc<Prima>) -> Result<Self> {
+        let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+
+        thread::Builder::new()
+            .name(PODCASTS_THREAD_NAME.to_string())
+            .spawn(move || loop {
+                if let Err(e) = prima.refresh_podcasts() {
+                    eprintln!("Unable to refresh podcasts: {}", e)
+                }
+
+                let interval = prima
+                    .get_podcast_refresh_interval()
+                    .max(MIN_PODCAST_REFRESH_INTERVAL);
+
+                match stop_receiver.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => return,
+                }
+            })?;
+
+        Ok(Self {
+            _stop_sender: stop_sender,
+        })
+    }
+}
@@ -0,0 +1,2801 @@
+extern crate diesel;
+extern crate rodio;
+extern crate tokio;
+
+use crate::{
+    data::{
+        databases::{
+            analysis::{
+                db::create_tables as create_analysis_tables, track_analysis_dao::TrackAnalysisDao,
+            },
+            bookmarks::{
+                bookmark_dao::BookmarkDao, db::create_tables as create_bookmarks_tables,
+                track_position_dao::TrackPositionDao,
+            },
+            db_entity::DBEntity,
+            entity_dao::EntityDao,
+            favourites::{
+                daos::{
+                    favourite_artist_dao::FavouriteArtistDao,
+                    favourite_playlist_dao::{FavouritePlaylistDBEntity, FavouritePlaylistDao},
+                    favourite_track_dao::FavouriteTrackDao,
+                },
+                db::{create_tables, establish_connection_to, favourite_db_url},
+            },
+            gtm::{db::create_tables as create_gtm_tables, gtm_high_score_dao::GtmHighScoreDao},
+            history::{
+                db::create_tables as create_history_tables, play_history_dao::PlayHistoryDao,
+            },
+            podcasts::{
+                db::create_tables as create_podcasts_tables,
+                podcast_episode_dao::PodcastEpisodeDao, podcast_feed_dao::PodcastFeedDao,
+            },
+            radio::{db::create_tables as create_radio_tables, radio_station_dao::RadioStationDao},
+            scrobbles::{db::create_tables as create_scrobbles_tables, scrobble_dao::ScrobbleDao},
+        },
+        entities::{
+            artists::favourite_artist::FavouriteArtist,
+            favourable::Favourable,
+            playlists::{
+                default_playlist::DefaultPlaylist, playlist_trait::PlaylistTrait,
+                playlist_type::PlaylistType,
+            },
+            tracks::{
+                default_track::DefaultTrack, favourite_track::FavouriteTrack,
+                track_trait::TrackTrait,
+            },
+        },
+        utils::{paths::APP_PATHS, random, track_order::TrackOrder, types::*},
+    },
+    domain::{
+        analysis::{
+            analysis_filter::AnalysisFilter,
+            analysis_worker::AnalysisWorker,
+            library_analyzer::{LibraryAnalysisTask, LibraryAnalyzer, ANALYSIS_DUTY_CYCLE},
+            track_analysis::TrackAnalysis,
+            waveform::Waveform,
+            waveform_cache::WaveformCache,
+        },
+        audio_export::audio_exporter::{AudioExporter, ExportTask},
+        audio_player::{
+            ab_loop::AbLoop,
+            audio_output::{AudioOutput, OutputMode},
+            audio_player::AudioPlayer,
+            decoders::track_decoder::TrackDecoder,
+            meter_feed::{MeterFeed, MeterFrame},
+            playback_params::{LoopingState, PlaybackParams},
+        },
+        audio_scanner::AudioScanner,
+        auto_dj::{
+            auto_dj::{AutoDj, AutoDjContext, AUTO_DJ_LOOKAHEAD, AUTO_DJ_RECENT_PLAYS},
+            auto_dj_settings::{AutoDjSeed, AutoDjSettings},
+        },
+        bookmarks::{
+            bookmark::Bookmark,
+            resume_position::{is_finished, is_long_form, resume_position},
+        },
+        events::{event_bus::EventBus, player_event::PlayerEvent},
+        gtm::{
+            clip_fade::{ClipFade, GTM_CLIP_FADE},
+            clip_finder::{ClipFinder, GTM_WAVEFORM_RESOLUTION},
+            gtm_difficulty::GtmDifficulty,
+            gtm_game::GtmGame,
+            gtm_high_score::GtmHighScore,
+            gtm_round::{GtmRound, GtmRoundResult},
+        },
+        lyrics::{lyrics::Lyrics, lyrics_line::LyricsLine, track_lyrics::TrackLyrics},
+        metadata_reader::MetadataReader,
+        podcasts::{
+            episode_downloader::EpisodeDownloader, feed_parser::FeedParser,
+            podcast_episode::PodcastEpisode, podcast_feed::PodcastFeed,
+        },
+        radio::radio_station::RadioStation,
+        scrobbling::{
+            scrobble_queue::unix_time_now, scrobble_submitter::ScrobbleSubmitter,
+            scrobbling_settings::ScrobblingSettings,
+        },
+        sleep_timer::{SleepTimer, SleepTimerMode},
+        storage_util::StorageUtil,
+    },
+    error::{Error, Result},
+};
+
+use diesel::{RunQueryDsl, SqliteConnection};
+use rodio::Sink;
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet, HashSet},
+    fs,
+    hash::{Hash, Hasher},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use tokio::{runtime::Builder, sync::RwLock};
+
+const ACTION_THREAD_NAME: &str = "prima-action";
+
+/// How long connection waits for another one's write,
+/// e.g. of the podcast refresher, before it fails as locked
+const DB_BUSY_TIMEOUT_MILLIS: u32 = 5000;
+
+/// Length of the remote-control API's token before hex encoding
+const TOKEN_BYTES: usize = 32;
+
+/// Player's session: runtime, settings, player and favourites database.
+/// Every front-end (JNI, CLI, tests) works through it.
+///
+/// Methods are blocking and must not be called from the session's runtime.
+/// Playback methods return when the track ends or is stopped
+pub struct Prima {
+    tokio_runtime: TokioRuntime,
+    storage_util: ARWLStorage,
+    audio_player: ARWLPlayer,
+    event_bus: EventBus,
+    favourite_db_url: String,
+
+    /// Order-independent hash of the last scanned library,
+    /// used to notify only about actual changes
+    library_fingerprint: AtomicU64,
+
+    /// Export that is running now, if any
+    export_task: Mutex<Option<ExportTask>>,
+
+    /// Order of the current playlist before it was shuffled.
+    /// None when shuffle is off
+    unshuffled_tracks: Mutex<Option<Vec<DefaultTrack>>>,
+
+    /// Lyrics of the last requested track, so the active line
+    /// can be polled without reading files every time
+    lyrics_cache: Mutex<Option<(PathBuf, Option<Lyrics>)>>,
+
+    /// Sleep timer that is running or has just finished
+    sleep_timer: Mutex<Option<SleepTimer>>,
+
+    /// Low priority thread where tracks are decoded for analysis
+    analysis_worker: AnalysisWorker,
+
+    /// Library analysis that is running now, if any
+    library_analysis_task: Mutex<Option<LibraryAnalysisTask>>,
+
+    /// Levels and spectrum published by the audio thread,
+    /// kept outside of the player, so reading them never waits for its lock
+    meter_feed: Arc<MeterFeed>,
+
+    /// Picker of the tracks that follow the queue, keeps tags it has read
+    auto_dj: Mutex<AutoDj>,
+
+    /// Guess-the-melody game that is played now, if any
+    gtm_game: Mutex<Option<GtmGame>>,
+
+    /// Sink of the game's clip while it's played
+    gtm_clip_sink: Mutex<Option<Arc<Sink>>>,
+
+    /// Held while an episode is downloaded,
+    /// so the refresher and the user never download the same file together
+    podcast_download_lock: Mutex<()>,
+}
+
+impl Prima {
+    /// Opens session with settings and favourites at their default locations
+    #[inline]
+    pub fn new() -> Result<Self> {
+        Self::open(APP_PATHS.get_state_file(), favourite_db_url())
+    }
+
+    /// Opens session with the given settings file and favourites database.
+    /// Tables of the database are created if they are missing
+    #[inline]
+    pub fn open(state_file: PathBuf, favourite_db_url: String) -> Result<Self> {
+        let tokio_runtime = Arc::new(Builder::new_multi_thread().enable_all().build()?);
+        let event_bus = EventBus::new();
+
+        let storage_util = tokio_runtime
+            .block_on(async { Arc::new(RwLock::new(StorageUtil::from_file(state_file).await)) });
+
+        tokio_runtime.spawn(StorageUtil::run_state_flush_task(storage_util.clone()));
+
+        let audio_player = tokio_runtime.block_on(async {
+            Arc::new(RwLock::new(
+                AudioPlayer::new(
+                    PlaybackParams::default(storage_util.clone()).await,
+                    storage_util.clone(),
+                    event_bus.clone(),
+                )
+                .await,
+            ))
+        });
+
+        let meter_feed =
+            tokio_runtime.block_on(async { audio_player.read().await.get_meter_feed() });
+
+        let prima = Self {
+            tokio_runtime,
+            storage_util,
+            audio_player,
+            event_bus,
+            favourite_db_url,
+            library_fingerprint: AtomicU64::new(0),
+            export_task: Mutex::new(None),
+            unshuffled_tracks: Mutex::new(None),
+            lyrics_cache: Mutex::new(None),
+            sleep_timer: Mutex::new(None),
+            analysis_worker: AnalysisWorker::spawn()?,
+            library_analysis_task: Mutex::new(None),
+            meter_feed,
+            auto_dj: Mutex::new(AutoDj::new()),
+            gtm_game: Mutex::new(None),
+            gtm_clip_sink: Mutex::new(None),
+            podcast_download_lock: Mutex::new(()),
+        };
+
+        let mut conn = prima.connect()?;
+        create_tables(&mut conn)?;
+        create_scrobbles_tables(&mut conn)?;
+        create_bookmarks_tables(&mut conn)?;
+        create_analysis_tables(&mut conn)?;
+        create_history_tables(&mut conn)?;
+        create_gtm_tables(&mut conn)?;
+        create_radio_tables(&mut conn)?;
+        create_podcasts_tables(&mut conn)?;
+        Ok(prima)
+    }
+
+    #[inline]
+    pub fn get_tokio_runtime(&self) -> TokioRuntime {
+        self.tokio_runtime.clone()
+    }
+
+    #[inline]
+    pub fn get_storage_util(&self) -> ARWLStorage {
+        self.storage_util.clone()
+    }
+
+    #[inline]
+    pub fn get_audio_player(&self) -> ARWLPlayer {
+        self.audio_player.clone()
+    }
+
+    #[inline]
+    pub fn get_event_bus(&self) -> &EventBus {
+        &self.event_bus
+    }
+
+    #[inline]
+    pub fn get_favourite_db_url(&self) -> String {
+        self.favourite_db_url.clone()
+    }
+
+    /// Opens new connection to the favourites database
+    #[inline]
+    pub fn connect(&self) -> Result<SqliteConnection> {
+        let mut conn = establish_connection_to(self.favourite_db_url.as_str())?;
+
+        diesel::sql_query(format!("PRAGMA busy_timeout = {}", DB_BUSY_TIMEOUT_MILLIS))
+            .execute(&mut conn)?;
+
+        Ok(conn)
+    }
+
+    /// Writes all unsaved settings to the disk immediately
+    #[inline]
+    pub fn flush(&self) -> Result<()> {
+        self.tokio_runtime
+            .block_on(async { Ok(self.storage_util.write().await.flush().await?) })
+    }
+
+    /// Saves current playback position
+    /// and writes all unsaved state to the disk
+    #[inline]
+    pub fn shutdown(&self) -> Result<()> {
+        self.store_playback_position().unwrap_or_default();
+        self.flush()
+    }
+
+    /// Scans music folder and notifies subscribers
+    /// if the set of found tracks differs from the previous scan.
+    /// Tracks are sorted by the stored analysis if the track order needs it
+    #[inline]
+    pub fn get_all_tracks(&self) -> Result<Vec<DefaultTrack>> {
+        let mut tracks = self
+            .tokio_runtime
+            .block_on(AudioScanner::get_all_tracks(self.storage_util.clone()))?;
+
+        let track_order = self.get_track_order();
+
+        if track_order.comparator.is_analysis() {
+            let analyses = TrackAnalysisDao::get_all(&mut self.connect()?)?;
+            AudioScanner::sort_tracks_by_analysis(&mut tracks, track_order, &analyses);
+        }
+
+        let fingerprint = tracks.iter().fold(tracks.len() as u64, |acc, track| {
+            let mut hasher = DefaultHasher::new();
+            track.get_path().hash(&mut hasher);
+            acc ^ hasher.finish()
+        });
+
+        if self.library_fingerprint.swap(fingerprint, Ordering::SeqCst) != fingerprint {
+            self.event_bus.publish(PlayerEvent::LibraryUpdated {
+                tracks_count: tracks.len(),
+            })
+        }
+
+        Ok(tracks)
+    }
+
+    /// Sorted names of artists in the library.
+    /// Tracks without artist are gathered under the empty name
+    #[inline]
+    pub fn get_all_artists(&self) -> Result<Vec<String>> {
+        Ok(self
+            .get_all_tracks()?
+            .into_iter()
+            .filter_map(|track| track.get_artist().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect())
+    }
+
+    #[inline]
+    pub fn get_artist_tracks(&self, artist: &str) -> Result<Vec<DefaultTrack>> {
+        Ok(self
+            .get_all_tracks()?
+            .into_iter()
+            .filter(|track| track.get_artist().map(String::as_str) == Some(artist))
+            .collect())
+    }
+
+    #[inline]
+    pub fn get_music_search_path(&self) -> Option<PathBuf> {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .read()
+                .await
+                .load_music_search_path()
+                .cloned()
+        })
+    }
+
+    #[inline]
+    pub fn set_music_search_path(&self, path: PathBuf) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .write()
+                .await
+                .store_music_search_path(path)
+        })
+    }
+
+    #[inline]
+    pub fn get_track_order(&self) -> TrackOrder {
+        self.tokio_runtime
+            .block_on(async { self.storage_util.read().await.load_track_order() })
+    }
+
+    #[inline]
+    pub fn set_track_order(&self, track_order: TrackOrder) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .write()
+                .await
+                .store_track_order(track_order)
+        })
+    }
+
+    #[inline]
+    pub fn get_volume(&self) -> f32 {
+        self.tokio_runtime
+            .block_on(async { self.audio_player.read().await.get_volume() })
+    }
+
+    #[inline]
+    pub fn set_volume(&self, volume: f32) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util.write().await.store_volume(volume);
+            AudioPlayer::set_volume(self.audio_player.clone(), volume).await
+        })
+    }
+
+    #[inline]
+    pub fn get_speed(&self) -> f32 {
+        self.tokio_runtime
+            .block_on(async { self.audio_player.read().await.get_speed() })
+    }
+
+    #[inline]
+    pub fn set_speed(&self, speed: f32) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util.write().await.store_speed(speed);
+            AudioPlayer::set_speed(self.audio_player.clone(), speed).await
+        })
+    }
+
+    #[inline]
+    pub fn get_looping_state(&self) -> LoopingState {
+        self.tokio_runtime
+            .block_on(async { self.audio_player.read().await.get_looping_state() })
+    }
+
+    /// Switches to the next looping state and stores it
+    #[inline]
+    pub fn set_next_looping_state(&self) -> LoopingState {
+        self.tokio_runtime.block_on(async {
+            AudioPlayer::set_next_looping_state(self.audio_player.clone()).await;
+            let state = self.audio_player.read().await.get_looping_state();
+            self.storage_util.write().await.store_looping_state(state);
+            state
+        })
+    }
+
+    /// Sets looping state and stores it
+    #[inline]
+    pub fn set_looping_state(&self, looping_state: LoopingState) {
+        self.tokio_runtime.block_on(async {
+            AudioPlayer::set_looping_state(self.audio_player.clone(), looping_state).await;
+            self.storage_util
+                .write()
+                .await
+                .store_looping_state(looping_state)
+        })
+    }
+
+    /// Name of the chosen output device or None if system's default is used
+    #[inline]
+    pub fn get_output_device(&self) -> Option<String> {
+        self.tokio_runtime
+            .block_on(async { self.storage_util.read().await.load_output_device().cloned() })
+    }
+
+    /// Chooses output device and stores the choice.
+    /// None means system's default device
+    #[inline]
+    pub fn set_output_device(&self, device: Option<String>) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .write()
+                .await
+                .store_output_device(device.clone())
+        });
+
+        self.set_output_mode(OutputMode::Device(device))
+    }
+
+    /// Changes output without storing it,
+    /// e.g. to play into [OutputMode::Null] on headless machines
+    #[inline]
+    pub fn set_output_mode(&self, output_mode: OutputMode) {
+        self.tokio_runtime.block_on(AudioPlayer::set_output_mode(
+            self.audio_player.clone(),
+            self.tokio_runtime.clone(),
+            self.storage_util.clone(),
+            output_mode,
+        ))
+    }
+
+    #[inline]
+    pub fn is_http_api_enabled(&self) -> bool {
+        self.tokio_runtime
+            .block_on(async { self.storage_util.read().await.load_http_api_enabled() })
+    }
+
+    /// Stores whether the remote-control API should run.
+    /// Front-end is responsible for starting or stopping the server
+    #[inline]
+    pub fn set_http_api_enabled(&self, is_enabled: bool) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .write()
+                .await
+                .store_http_api_enabled(is_enabled)
+        })
+    }
+
+    #[inline]
+    pub fn is_http_api_lan_enabled(&self) -> bool {
+        self.tokio_runtime
+            .block_on(async { self.storage_util.read().await.load_http_api_lan_enabled() })
+    }
+
+    /// Stores whether the remote-control API accepts connections
+    /// from other devices. Front-end is responsible for restarting the server
+    #[inline]
+    pub fn set_http_api_lan_enabled(&self, is_enabled: bool) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .write()
+                .await
+                .store_http_api_lan_enabled(is_enabled)
+        })
+    }
+
+    #[inline]
+    pub fn get_http_api_port(&self) -> u16 {
+        self.tokio_runtime
+            .block_on(async { self.storage_util.read().await.load_http_api_port() })
+    }
+
+    #[inline]
+    pub fn set_http_api_port(&self, port: u16) {
+        self.tokio_runtime
+            .block_on(async { self.storage_util.write().await.store_http_api_port(port) })
+    }
+
+    /// Token of the remote-control API.
+    /// Random one is generated and stored on the first call
+    #[inline]
+    pub fn get_http_api_token(&self) -> Result<String> {
+        self.tokio_runtime.block_on(async {
+            let mut storage_util = self.storage_util.write().await;
+
+            if let Some(token) = storage_util.load_http_api_token() {
+                return Ok(token.clone());
+            }
+
+            let token = generate_token()?;
+            storage_util.store_http_api_token(token.clone());
+            Ok(token)
+        })
+    }
+
+    #[inline]
+    pub fn is_mpd_enabled(&self) -> bool {
+        self.tokio_runtime
+            .block_on(async { self.storage_util.read().await.load_mpd_enabled() })
+    }
+
+    /// Stores whether the MPD server should run.
+    /// Front-end is responsible for starting or stopping the server
+    #[inline]
+    pub fn set_mpd_enabled(&self, is_enabled: bool) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .write()
+                .await
+                .store_mpd_enabled(is_enabled)
+        })
+    }
+
+    #[inline]
+    pub fn is_mpd_lan_enabled(&self) -> bool {
+        self.tokio_runtime
+            .block_on(async { self.storage_util.read().await.load_mpd_lan_enabled() })
+    }
+
+    /// Stores whether the MPD server accepts connections
+    /// from other devices. Front-end is responsible for restarting the server
+    #[inline]
+    pub fn set_mpd_lan_enabled(&self, is_enabled: bool) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .write()
+                .await
+                .store_mpd_lan_enabled(is_enabled)
+        })
+    }
+
+    #[inline]
+    pub fn get_mpd_port(&self) -> u16 {
+        self.tokio_runtime
+            .block_on(async { self.storage_util.read().await.load_mpd_port() })
+    }
+
+    #[inline]
+    pub fn set_mpd_port(&self, port: u16) {
+        self.tokio_runtime
+            .block_on(async { self.storage_util.write().await.store_mpd_port(port) })
+    }
+
+    /// Password of remote MPD clients, independent of the HTTP API's token.
+    /// Random one is generated and stored on the first call
+    #[inline]
+    pub fn get_mpd_password(&self) -> Result<String> {
+        self.tokio_runtime.block_on(async {
+            let mut storage_util = self.storage_util.write().await;
+
+            if let Some(password) = storage_util.load_mpd_password() {
+                return Ok(password.clone());
+            }
+
+            let password = generate_token()?;
+            storage_util.store_mpd_password(password.clone());
+            Ok(password)
+        })
+    }
+
+    #[inline]
+    pub fn get_scrobbling_settings(&self) -> ScrobblingSettings {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .read()
+                .await
+                .load_scrobbling_settings()
+                .clone()
+        })
+    }
+
+    /// Stores accounts of scrobbling services.
+    /// Front-end is responsible for restarting the scrobbler
+    #[inline]
+    pub fn set_scrobbling_settings(&self, settings: ScrobblingSettings) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .write()
+                .await
+                .store_scrobbling_settings(settings)
+        })
+    }
+
+    /// Submitters of the configured scrobbling services
+    #[inline]
+    pub fn get_scrobble_submitters(&self) -> Vec<Arc<dyn ScrobbleSubmitter>> {
+        self.get_scrobbling_settings().submitters()
+    }
+
+    /// Number of queued scrobbles that are not submitted yet, for all services
+    #[inline]
+    pub fn get_pending_scrobbles_count(&self) -> Result<usize> {
+        ScrobbleDao::count(&mut self.connect()?)
+    }
+
+    #[inline]
+    pub fn get_cur_playlist(&self) -> DefaultPlaylist<DefaultTrack> {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .read()
+                .await
+                .load_current_playlist()
+                .clone()
+        })
+    }
+
+    #[inline]
+    pub fn get_cur_track(&self) -> Option<DefaultTrack> {
+        self.get_cur_playlist().get_cur_track().cloned()
+    }
+
+    #[inline]
+    pub fn get_cur_track_index(&self) -> usize {
+        self.get_cur_playlist().get_cur_ind()
+    }
+
+    /// Replaces tracks of the current playlist,
+    /// current track keeps playing at its new position
+    #[inline]
+    pub fn update_cur_playlist(&self, tracks: Vec<DefaultTrack>) -> Result<()> {
+        let cur_track = self.get_cur_track().ok_or_else(Error::no_current_track)?;
+
+        let new_cur_ind = tracks
+            .iter()
+            .position(|track| *track == cur_track)
+            .ok_or_else(|| {
+                Error::Playback("Current track is missing in the new playlist".to_string())
+            })?;
+
+        let playlist = DefaultPlaylist::new(None, PlaylistType::default(), tracks, new_cur_ind);
+        self.forget_unshuffled_order_if_replaced(&playlist);
+        self.tokio_runtime.block_on(self.set_cur_playlist(playlist));
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_shuffled(&self) -> bool {
+        self.unshuffled_tracks.lock().unwrap().is_some()
+    }
+
+    /// Shuffles tracks of the current playlist, current track becomes the first one.
+    /// When shuffle is turned off, the original order is restored
+    #[inline]
+    pub fn set_shuffle(&self, is_shuffled: bool) {
+        let mut unshuffled_tracks = self.unshuffled_tracks.lock().unwrap();
+
+        if unshuffled_tracks.is_some() == is_shuffled {
+            return;
+        }
+
+        let playlist = self.get_cur_playlist();
+        let cur_track = playlist.get_cur_track().cloned();
+
+        let tracks = match unshuffled_tracks.take() {
+            Some(tracks) => tracks,
+
+            None => {
+                *unshuffled_tracks = Some(playlist.get_tracks().clone());
+
+                let mut tracks = playlist.get_tracks().clone();
+
+                if let Some(cur_track) = &cur_track {
+                    tracks.retain(|track| track != cur_track);
+                    random::shuffle(&mut tracks);
+                    tracks.insert(0, cur_track.clone());
+                } else {
+                    random::shuffle(&mut tracks);
+                }
+
+                tracks
+            }
+        };
+
+        let cur_ind = cur_track
+            .and_then(|cur_track| tracks.iter().position(|track| *track == cur_track))
+            .unwrap_or_default();
+
+        self.tokio_runtime
+            .block_on(self.set_cur_playlist(DefaultPlaylist::new(
+                playlist.get_title().cloned(),
+                playlist.get_type(),
+                tracks,
+                cur_ind,
+            )))
+    }
+
+    /// Inserts tracks into the current playlist before the given position
+    /// or to its end. Current track stays the same
+    #[inline]
+    pub fn add_to_queue(&self, tracks: Vec<DefaultTrack>, position: Option<usize>) {
+        let playlist = self.get_cur_playlist();
+        let mut new_tracks = playlist.get_tracks().clone();
+        let position = position.unwrap_or(new_tracks.len()).min(new_tracks.len());
+
+        let cur_ind = match playlist.get_cur_ind() {
+            ind if !playlist.is_empty() && ind >= position => ind + tracks.len(),
+            ind => ind,
+        };
+
+        if let Some(unshuffled_tracks) = self.unshuffled_tracks.lock().unwrap().as_mut() {
+            unshuffled_tracks.extend(tracks.iter().cloned())
+        }
+
+        new_tracks.splice(position..position, tracks);
+
+        self.tokio_runtime
+            .block_on(self.set_cur_playlist(DefaultPlaylist::new(
+                playlist.get_title().cloned(),
+                playlist.get_type(),
+                new_tracks,
+                cur_ind,
+            )))
+    }
+
+    /// Removes track from the current playlist.
+    /// If it was the current track, playback is stopped
+    /// and the following track becomes current
+    #[inline]
+    pub fn remove_from_queue(&self, index: usize) -> Result<()> {
+        let playlist = self.get_cur_playlist();
+        let mut tracks = playlist.get_tracks().clone();
+
+        if index >= tracks.len() {
+            return Err(Error::Playback(format!("There is no track at {}", index)));
+        }
+
+        let removed = tracks.remove(index);
+        let cur_ind = playlist.get_cur_ind();
+
+        let cur_ind = match index.cmp(&cur_ind) {
+            std::cmp::Ordering::Less => cur_ind - 1,
+            _ if cur_ind >= tracks.len() => 0,
+            _ => cur_ind,
+        };
+
+        if let Some(unshuffled_tracks) = self.unshuffled_tracks.lock().unwrap().as_mut() {
+            if let Some(position) = unshuffled_tracks.iter().position(|t| *t == removed) {
+                unshuffled_tracks.remove(position);
+            }
+        }
+
+        self.tokio_runtime.block_on(async {
+            if index == playlist.get_cur_ind() && self.audio_player.read().await.is_playing() {
+                self.stop().await
+            }
+
+            self.set_cur_playlist(DefaultPlaylist::new(
+                playlist.get_title().cloned(),
+                playlist.get_type(),
+                tracks,
+                cur_ind,
+            ))
+            .await
+        });
+
+        Ok(())
+    }
+
+    /// Moves track of the current playlist to another position.
+    /// Current track stays the same
+    #[inline]
+    pub fn move_in_queue(&self, from: usize, to: usize) -> Result<()> {
+        let playlist = self.get_cur_playlist();
+        let mut tracks = playlist.get_tracks().clone();
+
+        if from >= tracks.len() || to >= tracks.len() {
+            return Err(Error::Playback(format!(
+                "Unable to move track from {} to {}",
+                from, to
+            )));
+        }
+
+        let cur_ind = playlist.get_cur_ind();
+        let track = tracks.remove(from);
+        tracks.insert(to, track);
+
+        let cur_ind = match cur_ind {
+            ind if ind == from => to,
+            ind if from < ind && ind <= to => ind - 1,
+            ind if to <= ind && ind < from => ind + 1,
+            ind => ind,
+        };
+
+        self.tokio_runtime
+            .block_on(self.set_cur_playlist(DefaultPlaylist::new(
+                playlist.get_title().cloned(),
+                playlist.get_type(),
+                tracks,
+                cur_ind,
+            )));
+
+        Ok(())
+    }
+
+    /// Stops playback and removes all tracks from the current playlist
+    #[inline]
+    pub fn clear_queue(&self) {
+        *self.unshuffled_tracks.lock().unwrap() = None;
+
+        self.tokio_runtime.block_on(async {
+            self.stop().await;
+
+            self.set_cur_playlist(DefaultPlaylist::new(
+                None,
+                PlaylistType::default(),
+                vec![],
+                0,
+            ))
+            .await
+        })
+    }
+
+    /// Plays track of the current playlist at the given index
+    #[inline]
+    pub fn play_at(&self, index: usize) -> Result<()> {
+        let playlist = self.get_cur_playlist();
+
+        if index >= playlist.len() {
+            return Err(Error::Playback(format!("There is no track at {}", index)));
+        }
+
+        self.play(DefaultPlaylist::new(
+            playlist.get_title().cloned(),
+            playlist.get_type(),
+            playlist.get_tracks().clone(),
+            index,
+        ))
+    }
+
+    /// Shuffled order is kept only while the playlist has the same tracks
+    #[inline]
+    fn forget_unshuffled_order_if_replaced(&self, playlist: &DefaultPlaylist<DefaultTrack>) {
+        let mut unshuffled_tracks = self.unshuffled_tracks.lock().unwrap();
+
+        let is_replaced = match unshuffled_tracks.as_ref() {
+            None => return,
+
+            Some(unshuffled) => {
+                let paths = unshuffled
+                    .iter()
+                    .map(|t| t.get_path())
+                    .collect::<HashSet<_>>();
+
+                unshuffled.len() != playlist.len()
+                    || playlist
+                        .get_tracks()
+                        .iter()
+                        .any(|track| !paths.contains(track.get_path()))
+            }
+        };
+
+        if is_replaced {
+            *unshuffled_tracks = None
+        }
+    }
+
+    #[inline]
+    async fn set_cur_playlist(&self, playlist: DefaultPlaylist<DefaultTrack>) {
+        let mut storage_util = self.storage_util.write().await;
+        let old_playlist = storage_util.load_current_playlist();
+
+        let is_track_changed = old_playlist.get_cur_ind() != playlist.get_cur_ind()
+            || old_playlist.get_cur_track() != playlist.get_cur_track();
+
+        let is_queue_changed = old_playlist.get_tracks() != playlist.get_tracks();
+
+        let track_event = PlayerEvent::TrackChanged {
+            index: playlist.get_cur_ind(),
+            track: playlist.get_cur_track().cloned(),
+        };
+
+        let queue_event = PlayerEvent::QueueChanged {
+            length: playlist.len(),
+            index: playlist.get_cur_ind(),
+        };
+
+        storage_util.store_current_playlist(playlist);
+
+        if is_queue_changed {
+            self.event_bus.publish(queue_event)
+        }
+
+        if is_track_changed {
+            self.event_bus.publish(track_event)
+        }
+    }
+
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.tokio_runtime
+            .block_on(async { self.audio_player.read().await.is_playing() })
+    }
+
+    /// Position of the current track that was stored last
+    #[inline]
+    pub fn get_playback_position(&self) -> Duration {
+        self.tokio_runtime.block_on(async {
+            Duration::from_millis(
+                self.storage_util
+                    .read()
+                    .await
+                    .load_current_playback_position(),
+            )
+        })
+    }
+
+    /// Stores position of the current track,
+    /// long track also remembers it for the next time it's played
+    #[inline]
+    pub fn store_playback_position(&self) -> Result<()> {
+        self.tokio_runtime.block_on(async {
+            self.remember_cur_track_position().await;
+
+            AudioPlayer::save_cur_playback_pos_async(
+                self.audio_player.clone(),
+                self.tokio_runtime.clone(),
+                self.storage_util.clone(),
+            )
+            .await
+            .await
+            .map_err(|e| Error::Playback(e.to_string()))
+        })
+    }
+
+    /// Runs playback method on its own thread, because it returns only when the track ends.
+    /// Used by front-ends that must answer at once: MPRIS, HTTP API and MPD.
+    /// Failures are published as [PlayerEvent::Error]
+    #[inline]
+    pub fn spawn_action<F>(this: Arc<Self>, action: F) -> Result<()>
+    where
+        F: FnOnce(&Prima) -> Result<()> + Send + 'static,
+    {
+        thread::Builder::new()
+            .name(ACTION_THREAD_NAME.to_string())
+            .spawn(move || {
+                if let Err(e) = action(&this) {
+                    this.event_bus.publish(PlayerEvent::Error {
+                        message: e.to_string(),
+                    })
+                }
+            })?;
+
+        Ok(())
+    }
+
+    /// Makes playlist current and plays its current track from the start
+    #[inline]
+    pub fn play(&self, playlist: DefaultPlaylist<DefaultTrack>) -> Result<()> {
+        self.forget_unshuffled_order_if_replaced(&playlist);
+        self.tokio_runtime
+            .block_on(self.store_and_play_playlist(playlist))
+    }
+
+    /// Reaction on the click on the playlist's track:
+    /// the same track is paused or resumed, another one is played
+    #[inline]
+    pub fn play_pause_playlist(&self, playlist: DefaultPlaylist<DefaultTrack>) -> Result<()> {
+        self.forget_unshuffled_order_if_replaced(&playlist);
+        self.tokio_runtime
+            .block_on(self.play_pause_cur_track(Some(playlist)))
+    }
+
+    /// Pauses or resumes current track or internet radio
+    #[inline]
+    pub fn play_pause(&self) -> Result<()> {
+        self.tokio_runtime.block_on(async {
+            let (is_stream, is_playing) = {
+                let audio_player = self.audio_player.read().await;
+                (
+                    audio_player.get_stream_url().is_some(),
+                    audio_player.is_playing(),
+                )
+            };
+
+            if is_stream {
+                return match is_playing {
+                    true => self.pause().await,
+                    false => self.resume().await,
+                };
+            }
+
+            if self.has_cur_track().await {
+                self.play_pause_cur_track(None).await
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    /// Pauses playback if it's playing
+    #[inline]
+    pub fn pause_playback(&self) {
+        self.tokio_runtime.block_on(async {
+            if self.audio_player.read().await.is_playing() {
+                self.pause().await.unwrap_or_default()
+            }
+        })
+    }
+
+    /// Stops playback, the next play starts the current track from the beginning
+    #[inline]
+    pub fn stop_playback(&self) {
+        self.tokio_runtime.block_on(async {
+            self.stop().await;
+            AudioPlayer::rewind(self.audio_player.clone(), self.storage_util.clone()).await
+        })
+    }
+
+    #[inline]
+    pub fn next_track(&self) -> Result<()> {
+        let mut playlist = self.get_cur_playlist();
+
+        if playlist.get_cur_track().is_none() {
+            return Ok(());
+        }
+
+        playlist.skip_to_next();
+
+        // Sleep timer's track has ended: next track is only prepared for tomorrow
+        if self.take_finished_sleep_timer() {
+            return self
+                .tokio_runtime
+                .block_on(self.store_playlist_without_playing(playlist));
+        }
+
+        self.play_pause_playlist(playlist)
+    }
+
+    #[inline]
+    pub fn previous_track(&self) -> Result<()> {
+        let mut playlist = self.get_cur_playlist();
+
+        if playlist.get_cur_track().is_none() {
+            return Ok(());
+        }
+
+        playlist.skip_to_prev();
+        self.play_pause_playlist(playlist)
+    }
+
+    /// Plays current track from the start
+    #[inline]
+    pub fn replay_cur_track(&self) -> Result<()> {
+        if self.take_finished_sleep_timer() {
+            let playlist = self.get_cur_playlist();
+
+            return self
+                .tokio_runtime
+                .block_on(self.store_playlist_without_playing(playlist));
+        }
+
+        self.tokio_runtime.block_on(async {
+            let (path, duration) = self.get_path_and_duration_of_cur_track().await?;
+
+            AudioPlayer::play(
+                self.audio_player.clone(),
+                self.tokio_runtime.clone(),
+                self.storage_util.clone(),
+                path,
+                duration,
+            )
+            .await
+        })
+    }
+
+    /// Starts sleep timer, replacing the running one
+    #[inline]
+    pub fn start_sleep_timer(&self, mode: SleepTimerMode, is_fading: bool) {
+        self.cancel_sleep_timer();
+
+        *self.sleep_timer.lock().unwrap() = Some(SleepTimer::start(
+            mode,
+            is_fading,
+            self.tokio_runtime.clone(),
+            self.audio_player.clone(),
+            self.storage_util.clone(),
+            self.event_bus.clone(),
+        ))
+    }
+
+    /// Cancels sleep timer and restores volume if it was faded
+    #[inline]
+    pub fn cancel_sleep_timer(&self) {
+        if self.sleep_timer.lock().unwrap().take().is_some() {
+            self.tokio_runtime.block_on(AudioPlayer::set_volume_factor(
+                self.audio_player.clone(),
+                1.0,
+            ))
+        }
+    }
+
+    /// Mode of the running sleep timer, None if there is no such timer
+    #[inline]
+    pub fn get_sleep_timer_mode(&self) -> Option<SleepTimerMode> {
+        self.sleep_timer
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|timer| !timer.is_finished())
+            .map(SleepTimer::get_mode)
+    }
+
+    /// Playback time left until the sleep timer stops it,
+    /// None if there is no running timer
+    #[inline]
+    pub fn get_sleep_timer_time_left(&self) -> Option<Duration> {
+        let sleep_timer = self.sleep_timer.lock().unwrap();
+        let timer = sleep_timer.as_ref().filter(|timer| !timer.is_finished())?;
+
+        Some(
+            self.tokio_runtime
+                .block_on(timer.get_time_left(&self.audio_player, &self.storage_util)),
+        )
+    }
+
+    /// Removes sleep timer whose track or album has just ended.
+    /// Returns whether playback must not continue
+    #[inline]
+    fn take_finished_sleep_timer(&self) -> bool {
+        let mut sleep_timer = self.sleep_timer.lock().unwrap();
+
+        let (should_stop, is_finished) = match sleep_timer.as_ref() {
+            None => (false, false),
+
+            Some(timer) => (
+                self.tokio_runtime.block_on(
+                    timer.should_stop_before_next_track(&self.audio_player, &self.storage_util),
+                ),
+                timer.is_finished(),
+            ),
+        };
+
+        if should_stop {
+            *sleep_timer = None;
+
+            // Timer that has noticed the end itself has already told about it
+            if !is_finished {
+                self.event_bus.publish(PlayerEvent::SleepTimerFinished)
+            }
+        }
+
+        should_stop
+    }
+
+    /// Moves current track to the position.
+    /// Playing track is seeked without stopping its output and the call returns at once,
+    /// otherwise playback starts from the position on the same output
+    /// and blocks until the track ends
+    #[inline]
+    pub fn seek_to(&self, position: Duration) -> Result<()> {
+        self.tokio_runtime.block_on(async {
+            let cur_track = self.get_cur_track_async().await;
+
+            if self.audio_player.read().await.is_playing() {
+                let is_seeked = AudioPlayer::seek_live(
+                    self.audio_player.clone(),
+                    self.tokio_runtime.clone(),
+                    self.storage_util.clone(),
+                    position,
+                )
+                .await?;
+
+                if is_seeked {
+                    return Ok(());
+                }
+            }
+
+            match cur_track {
+                None => Ok(()),
+
+                Some(cur_track) => {
+                    AudioPlayer::seek_to(
+                        self.audio_player.clone(),
+                        self.tokio_runtime.clone(),
+                        self.storage_util.clone(),
+                        position,
+                        cur_track.get_duration().to_std()?,
+                    )
+                    .await
+                }
+            }
+        })
+    }
+
+    /// A-B loop of the current track, None if it's not set
+    #[inline]
+    pub fn get_ab_loop(&self) -> Option<AbLoop> {
+        self.tokio_runtime.block_on(async {
+            let cur_track = self.get_cur_track_async().await?;
+            self.storage_util
+                .read()
+                .await
+                .load_ab_loop(cur_track.get_path())
+        })
+    }
+
+    /// How many times playback has jumped from B back to A
+    #[inline]
+    pub fn get_ab_loop_jumps(&self) -> u32 {
+        self.tokio_runtime
+            .block_on(async { self.audio_player.read().await.get_ab_loop_jumps() })
+    }
+
+    /// Sets or clears A-B loop of the current track, the loop is stored for next time.
+    /// Playing track continues with the new loop at once,
+    /// jumping to A if it's outside of the segment
+    #[inline]
+    pub fn set_ab_loop(this: Arc<Self>, ab_loop: Option<AbLoop>) -> Result<()> {
+        let cur_track = this.get_cur_track().ok_or_else(Error::no_current_track)?;
+        let duration = cur_track.get_duration().to_std()?;
+
+        if ab_loop.is_some_and(|ab_loop| ab_loop.end > duration) {
+            return Err(Error::Config(
+                "A-B loop can't end after the track".to_string(),
+            ));
+        }
+
+        let (is_playing, position) = this.tokio_runtime.block_on(async {
+            this.storage_util
+                .write()
+                .await
+                .store_ab_loop(cur_track.get_path().clone(), ab_loop);
+
+            let audio_player = this.audio_player.read().await;
+            audio_player.reset_ab_loop_jumps();
+
+            // Track that has already ended is not played again
+            (
+                audio_player.is_playing() && !audio_player.is_done(),
+                audio_player.get_cur_playback_pos().await,
+            )
+        });
+
+        if !is_playing {
+            return Ok(());
+        }
+
+        let position = match ab_loop {
+            Some(ab_loop) if !ab_loop.contains(position) => ab_loop.start,
+            _ => position,
+        };
+
+        Self::spawn_action(this, move |prima| prima.seek_to(position))
+    }
+
+    #[inline]
+    async fn get_cur_track_async(&self) -> Option<DefaultTrack> {
+        self.storage_util
+            .read()
+            .await
+            .load_current_playlist()
+            .get_cur_track()
+            .cloned()
+    }
+
+    #[inline]
+    async fn has_cur_track(&self) -> bool {
+        self.get_cur_track_async().await.is_some()
+    }
+
+    #[inline]
+    async fn is_prev_track_equals_cur_track(&self, cur_track: &DefaultTrack) -> bool {
+        self.get_cur_track_async()
+            .await
+            .map(|prev| prev == *cur_track)
+            .unwrap_or(false)
+    }
+
+    #[inline]
+    async fn get_path_and_duration_of_cur_track(&self) -> Result<(PathBuf, Duration)> {
+        let cur_track = self
+            .get_cur_track_async()
+            .await
+            .ok_or_else(Error::no_current_track)?;
+
+        get_path_and_duration_of_track(&cur_track)
+    }
+
+    #[inline]
+    async fn play_pause_cur_track(
+        &self,
+        playlist: Option<DefaultPlaylist<DefaultTrack>>,
+    ) -> Result<()> {
+        let cur_track = playlist.as_ref().and_then(|p| p.get_cur_track()).cloned();
+        let is_playing = self.audio_player.read().await.is_playing();
+
+        if is_playing {
+            self.stop().await;
+
+            let playlist = match playlist {
+                None => return self.pause().await,
+                Some(playlist) => playlist,
+            };
+
+            let cur_track = cur_track.ok_or_else(Error::no_current_track)?;
+
+            return if self.is_prev_track_equals_cur_track(&cur_track).await {
+                self.set_cur_playlist(playlist).await;
+                self.pause().await
+            } else {
+                self.store_and_play_playlist(playlist).await
+            };
+        }
+
+        let playlist = match playlist {
+            None if self.has_cur_track().await => return self.resume().await,
+            None => return Err(Error::no_current_track()),
+            Some(playlist) => playlist,
+        };
+
+        if !self.has_cur_track().await {
+            return self.store_and_play_playlist(playlist).await;
+        }
+
+        let cur_track = cur_track.ok_or_else(Error::no_current_track)?;
+
+        if self.is_prev_track_equals_cur_track(&cur_track).await {
+            self.set_cur_playlist(playlist).await;
+            self.resume().await
+        } else {
+            self.store_and_play_playlist(playlist).await
+        }
+    }
+
+    #[inline]
+    async fn stop(&self) {
+        AudioPlayer::stop(
+            self.audio_player.clone(),
+            self.tokio_runtime.clone(),
+            self.storage_util.clone(),
+        )
+        .await
+    }
+
+    #[inline]
+    async fn pause(&self) -> Result<()> {
+        AudioPlayer::pause(
+            self.audio_player.clone(),
+            self.tokio_runtime.clone(),
+            self.storage_util.clone(),
+        )
+        .await;
+
+        self.remember_cur_track_position().await;
+        Ok(())
+    }
+
+    /// Internet radio that was paused is connected again
+    #[inline]
+    async fn resume(&self) -> Result<()> {
+        let stream_url = self.audio_player.read().await.get_stream_url().cloned();
+
+        if let Some(url) = stream_url {
+            return AudioPlayer::play_stream(
+                self.audio_player.clone(),
+                self.tokio_runtime.clone(),
+                self.storage_util.clone(),
+                url,
+            )
+            .await;
+        }
+
+        let (_, track_duration) = self.get_path_and_duration_of_cur_track().await?;
+
+        let result = AudioPlayer::resume(
+            self.audio_player.clone(),
+            self.tokio_runtime.clone(),
+            self.storage_util.clone(),
+            track_duration,
+        )
+        .await;
+
+        self.remember_cur_episode_position().await;
+        result
+    }
+
+    /// Stops playback and makes playlist current without playing it,
+    /// its current track is resumed from the beginning
+    #[inline]
+    async fn store_playlist_without_playing(
+        &self,
+        playlist: DefaultPlaylist<DefaultTrack>,
+    ) -> Result<()> {
+        let cur_track = playlist
+            .get_cur_track()
+            .ok_or_else(Error::no_current_track)?;
+
+        let (path, track_duration) = get_path_and_duration_of_track(cur_track)?;
+        self.set_cur_playlist(playlist).await;
+
+        AudioPlayer::prepare(
+            self.audio_player.clone(),
+            self.storage_util.clone(),
+            path,
+            track_duration,
+        )
+        .await;
+
+        Ok(self.storage_util.write().await.flush().await?)
+    }
+
+    /// Makes playlist current and plays its current track.
+    /// Long track is resumed where it was left
+    #[inline]
+    async fn store_and_play_playlist(&self, playlist: DefaultPlaylist<DefaultTrack>) -> Result<()> {
+        self.remember_cur_track_position().await;
+
+        let cur_track = playlist
+            .get_cur_track()
+            .ok_or_else(Error::no_current_track)?;
+
+        let position = self.get_start_position(cur_track).await;
+        self.store_and_play_playlist_from(playlist, position).await
+    }
+
+    /// Makes playlist current and plays its current track
+    /// from the position or from the beginning
+    #[inline]
+    async fn store_and_play_playlist_from(
+        &self,
+        playlist: DefaultPlaylist<DefaultTrack>,
+        position: Option<Duration>,
+    ) -> Result<()> {
+        let cur_track = playlist
+            .get_cur_track()
+            .ok_or_else(Error::no_current_track)?;
+
+        let (path, track_duration) = get_path_and_duration_of_track(cur_track)?;
+
+        let result = self
+            .connect()
+            .and_then(|mut conn| PlayHistoryDao::add(&path, unix_time_now(), &mut conn));
+
+        if let Err(e) = result {
+            eprintln!("Unable to remember play of {}: {}", path.display(), e)
+        }
+
+        let playlist = self.top_up_with_auto_dj(playlist).await;
+        self.set_cur_playlist(playlist).await;
+
+        let result = match position {
+            None => {
+                AudioPlayer::play(
+                    self.audio_player.clone(),
+                    self.tokio_runtime.clone(),
+                    self.storage_util.clone(),
+                    path,
+                    track_duration,
+                )
+                .await
+            }
+
+            Some(position) => {
+                AudioPlayer::prepare(
+                    self.audio_player.clone(),
+                    self.storage_util.clone(),
+                    path,
+                    track_duration,
+                )
+                .await;
+
+                AudioPlayer::seek_to(
+                    self.audio_player.clone(),
+                    self.tokio_runtime.clone(),
+                    self.storage_util.clone(),
+                    position,
+                    track_duration,
+                )
+                .await
+            }
+        };
+
+        // Episode that has been played to the end is marked played
+        self.remember_cur_episode_position().await;
+        result
+    }
+
+    /// Remembers where the current long track is left,
+    /// so it's resumed from there next time.
+    /// Failures are only logged, they must not stop playback
+    #[inline]
+    async fn remember_cur_track_position(&self) {
+        let (cur_track, position) = match self.get_cur_track_and_position().await {
+            None => return,
+            Some(track_and_position) => track_and_position,
+        };
+
+        let duration = cur_track.get_duration().to_std().unwrap_or_default();
+
+        match self.remember_episode_position(cur_track.get_path(), position, duration) {
+            Ok(false) => {}
+            Ok(true) => return,
+
+            Err(e) => {
+                eprintln!(
+                    "Unable to remember position of episode {}: {}",
+                    cur_track.get_path().display(),
+                    e
+                );
+                return;
+            }
+        }
+
+        let threshold = self.storage_util.read().await.load_resume_threshold();
+
+        if !is_long_form(duration, threshold) {
+            return;
+        }
+
+        let result =
+            self.connect()
+                .and_then(|mut conn| match resume_position(position, duration) {
+                    None => TrackPositionDao::remove(cur_track.get_path(), &mut conn),
+
+                    Some(position) => TrackPositionDao::store(
+                        cur_track.get_path(),
+                        position,
+                        unix_time_now(),
+                        &mut conn,
+                    ),
+                });
+
+        if let Err(e) = result {
+            eprintln!(
+                "Unable to remember position of {}: {}",
+                cur_track.get_path().display(),
+                e
+            )
+        }
+    }
+
+    /// Remembers where the current podcast episode is left
+    /// or marks it played if it has ended
+    #[inline]
+    async fn remember_cur_episode_position(&self) {
+        let (cur_track, position) = match self.get_cur_track_and_position().await {
+            None => return,
+            Some(track_and_position) => track_and_position,
+        };
+
+        let duration = cur_track.get_duration().to_std().unwrap_or_default();
+
+        if let Err(e) = self.remember_episode_position(cur_track.get_path(), position, duration) {
+            eprintln!(
+                "Unable to remember position of episode {}: {}",
+                cur_track.get_path().display(),
+                e
+            )
+        }
+    }
+
+    /// Current track with the player's position in it.
+    /// None if there is no track or the player has already moved to another one
+    #[inline]
+    async fn get_cur_track_and_position(&self) -> Option<(DefaultTrack, Duration)> {
+        let cur_track = self.get_cur_track_async().await?;
+        let audio_player = self.audio_player.read().await;
+
+        if audio_player
+            .get_cur_path()
+            .is_some_and(|path| path != cur_track.get_path())
+        {
+            return None;
+        }
+
+        let position = audio_player.get_cur_playback_pos().await;
+        Some((cur_track, position))
+    }
+
+    /// Stores position of the downloaded episode or marks it played
+    /// if it's left near the end. Episodes are tracked regardless of their length.
+    /// Returns false if the file is not an episode
+    #[inline]
+    fn remember_episode_position(
+        &self,
+        path: &Path,
+        position: Duration,
+        duration: Duration,
+    ) -> Result<bool> {
+        let mut conn = self.connect()?;
+
+        let episode = match PodcastEpisodeDao::get_by_file(path, &mut conn)? {
+            None => return Ok(false),
+            Some(episode) => episode,
+        };
+
+        if is_finished(position, duration) {
+            PodcastEpisodeDao::set_progress(episode.id, true, Duration::ZERO, &mut conn)?;
+        } else if !position.is_zero() {
+            PodcastEpisodeDao::set_progress(episode.id, false, position, &mut conn)?;
+        }
+
+        Ok(true)
+    }
+
+    /// Position where the long track or the unplayed episode was left,
+    /// None if it starts from the beginning
+    #[inline]
+    async fn get_start_position(&self, track: &DefaultTrack) -> Option<Duration> {
+        let duration = track.get_duration().to_std().unwrap_or_default();
+
+        let episode = self
+            .connect()
+            .and_then(|mut conn| PodcastEpisodeDao::get_by_file(track.get_path(), &mut conn));
+
+        match episode {
+            Ok(None) => {}
+
+            Ok(Some(episode)) => {
+                return (!episode.is_played && !episode.position.is_zero())
+                    .then_some(episode.position)
+                    .filter(|position| *position < duration)
+            }
+
+            Err(e) => eprintln!(
+                "Unable to load episode {}: {}",
+                track.get_path().display(),
+                e
+            ),
+        }
+
+        let threshold = self.storage_util.read().await.load_resume_threshold();
+
+        if !is_long_form(duration, threshold) {
+            return None;
+        }
+
+        self.get_resume_position(track.get_path())
+            .unwrap_or_else(|e| {
+                eprintln!(
+                    "Unable to load position of {}: {}",
+                    track.get_path().display(),
+                    e
+                );
+                None
+            })
+            .filter(|position| *position < duration)
+    }
+
+    /// Position where the track was left when it was played last time
+    #[inline]
+    pub fn get_resume_position(&self, path: &Path) -> Result<Option<Duration>> {
+        TrackPositionDao::get(path, &mut self.connect()?)
+    }
+
+    /// Tracks of at least this length resume where they were left
+    #[inline]
+    pub fn get_resume_threshold(&self) -> Duration {
+        self.tokio_runtime
+            .block_on(async { self.storage_util.read().await.load_resume_threshold() })
+    }
+
+    #[inline]
+    pub fn set_resume_threshold(&self, threshold: Duration) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .write()
+                .await
+                .store_resume_threshold(threshold)
+        })
+    }
+
+    /// Adds bookmark at the current position of the current track.
+    /// Blank name is replaced with the position, e.g. `1:02:03`
+    #[inline]
+    pub fn add_bookmark(&self, name: &str, note: Option<&str>) -> Result<Bookmark> {
+        let cur_track = self.get_cur_track().ok_or_else(Error::no_current_track)?;
+
+        let position = self
+            .tokio_runtime
+            .block_on(async { self.audio_player.read().await.get_cur_playback_pos().await });
+
+        let name = match name.trim() {
+            "" => format_position(position),
+            name => name.to_string(),
+        };
+
+        BookmarkDao::insert(
+            cur_track.get_path(),
+            position,
+            &name,
+            note.filter(|note| !note.trim().is_empty()),
+            unix_time_now(),
+            &mut self.connect()?,
+        )
+    }
+
+    #[inline]
+    pub fn get_bookmark(&self, id: i32) -> Result<Option<Bookmark>> {
+        BookmarkDao::get(id, &mut self.connect()?)
+    }
+
+    /// Bookmarks of the track ordered by position,
+    /// or bookmarks of all tracks if the track is not given
+    #[inline]
+    pub fn get_bookmarks(&self, path: Option<&Path>) -> Result<Vec<Bookmark>> {
+        let mut conn = self.connect()?;
+
+        match path {
+            None => BookmarkDao::get_all(&mut conn),
+            Some(path) => BookmarkDao::get_by_path(path, &mut conn),
+        }
+    }
+
+    /// Renames bookmark and replaces its note.
+    /// Returns false if there is no such bookmark
+    #[inline]
+    pub fn update_bookmark(&self, id: i32, name: &str, note: Option<&str>) -> Result<bool> {
+        BookmarkDao::update(id, name, note, &mut self.connect()?)
+    }
+
+    /// Returns false if there is no such bookmark
+    #[inline]
+    pub fn remove_bookmark(&self, id: i32) -> Result<bool> {
+        BookmarkDao::remove(id, &mut self.connect()?)
+    }
+
+    /// Plays track of the bookmark from its position.
+    /// Track is played in the current playlist if it's there,
+    /// otherwise it's played alone. Returns when the track ends
+    #[inline]
+    pub fn jump_to_bookmark(&self, id: i32) -> Result<()> {
+        let bookmark = self
+            .get_bookmark(id)?
+            .ok_or_else(|| Error::Playback(format!("There is no bookmark {}", id)))?;
+
+        let playlist = self.get_cur_playlist();
+
+        if playlist.get_cur_track().map(|track| track.get_path()) == Some(&bookmark.path) {
+            return self.seek_to(bookmark.position);
+        }
+
+        let playlist = match playlist
+            .get_tracks()
+            .iter()
+            .position(|track| *track.get_path() == bookmark.path)
+        {
+            Some(index) => DefaultPlaylist::new(
+                playlist.get_title().cloned(),
+                playlist.get_type(),
+                playlist.get_tracks().clone(),
+                index,
+            ),
+
+            None => {
+                let track = MetadataReader::read_track(&bookmark.path).ok_or_else(|| {
+                    Error::Decode(format!("Unable to read {}", bookmark.path.display()))
+                })?;
+
+                DefaultPlaylist::new(None, PlaylistType::default(), vec![track], 0)
+            }
+        };
+
+        self.forget_unshuffled_order_if_replaced(&playlist);
+
+        self.tokio_runtime.block_on(async {
+            if self.audio_player.read().await.is_playing() {
+                self.stop().await
+            }
+
+            self.remember_cur_track_position().await;
+            self.store_and_play_playlist_from(playlist, Some(bookmark.position))
+                .await
+        })
+    }
+
+    /// Renders tracks with current speed, reverb and fade-in
+    /// into a single WAV or FLAC file (chosen by output's extension)
+    ///
+    /// # Return
+    /// true if file was written, false if export was cancelled
+    #[inline]
+    pub fn export_tracks(&self, tracks: &[PathBuf], output: &Path) -> Result<bool> {
+        let playback_params = self
+            .tokio_runtime
+            .block_on(async { self.audio_player.read().await.get_playback_params().clone() });
+
+        let task = ExportTask::new();
+        *self.export_task.lock().unwrap() = Some(task.clone());
+
+        let result = AudioExporter::export(tracks, output, &playback_params, &task);
+        self.export_task.lock().unwrap().take();
+        result
+    }
+
+    /// Progress of the running export in range [0, 1]
+    /// or None if there is no export
+    #[inline]
+    pub fn get_export_progress(&self) -> Option<f32> {
+        self.export_task
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|task| task.get_progress())
+    }
+
+    #[inline]
+    pub fn cancel_export(&self) {
+        if let Some(task) = self.export_task.lock().unwrap().as_ref() {
+            task.cancel()
+        }
+    }
+
+    /// Minimal and maximal peaks of the track for the waveform seekbar.
+    /// Track is decoded once on the low priority analysis thread,
+    /// then peaks are taken from the cache, also in next sessions
+    #[inline]
+    pub fn get_waveform(&self, track: &Path, resolution: usize) -> Result<Waveform> {
+        if let Some(waveform) = WaveformCache::load(track, resolution) {
+            return Ok(waveform);
+        }
+
+        let track = track.to_path_buf();
+
+        self.analysis_worker.run(move || {
+            let waveform = Waveform::compute(&track, resolution)?;
+
+            if let Err(e) = WaveformCache::store(&track, &waveform) {
+                eprintln!("Unable to cache waveform of {}: {}", track.display(), e)
+            }
+
+            Ok(waveform)
+        })
+    }
+
+    /// Latest levels and spectrum of the played samples.
+    /// Doesn't block, None if nothing is playing
+    #[inline]
+    pub fn get_meter_frame(&self) -> Option<MeterFrame> {
+        self.meter_feed.latest()
+    }
+
+    #[inline]
+    pub fn get_spectrum_bands(&self) -> usize {
+        self.meter_feed.get_spectrum_bands()
+    }
+
+    /// Number of spectrum bands in the next meter frames
+    #[inline]
+    pub fn set_spectrum_bands(&self, bands: usize) -> Result<()> {
+        self.meter_feed.set_spectrum_bands(bands)
+    }
+
+    /// Stores tempo, key and loudness of the library's tracks.
+    /// Only new and modified tracks are analysed, so the pass can be resumed.
+    /// Tracks are decoded on the low priority analysis thread
+    /// and the pass pauses between tracks to keep the CPU mostly free
+    ///
+    /// # Return
+    /// true if all tracks were analysed, false if the pass was cancelled
+    #[inline]
+    pub fn analyse_library(&self) -> Result<bool> {
+        let task = {
+            let mut running = self.library_analysis_task.lock().unwrap();
+
+            if running.is_some() {
+                return Err(Error::Playback(
+                    "Library analysis is already running".to_string(),
+                ));
+            }
+
+            let task = LibraryAnalysisTask::new();
+            *running = Some(task.clone());
+            task
+        };
+
+        let result = self.get_all_tracks().and_then(|tracks| {
+            let paths = tracks
+                .iter()
+                .map(|track| track.get_path().clone())
+                .collect::<Vec<_>>();
+
+            LibraryAnalyzer::analyse(
+                &paths,
+                &self.analysis_worker,
+                &task,
+                ANALYSIS_DUTY_CYCLE,
+                &mut self.connect()?,
+            )
+        });
+
+        self.library_analysis_task.lock().unwrap().take();
+        result
+    }
+
+    /// Progress of the running library analysis in range [0, 1]
+    /// or None if there is no analysis
+    #[inline]
+    pub fn get_library_analysis_progress(&self) -> Option<f32> {
+        self.library_analysis_task
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|task| task.get_progress())
+    }
+
+    /// Analysis stops after the current track,
+    /// next pass continues from the next one
+    #[inline]
+    pub fn cancel_library_analysis(&self) {
+        if let Some(task) = self.library_analysis_task.lock().unwrap().as_ref() {
+            task.cancel()
+        }
+    }
+
+    /// Stored analysis of the track, None if it was not analysed yet
+    #[inline]
+    pub fn get_track_analysis(&self, track: &Path) -> Result<Option<TrackAnalysis>> {
+        TrackAnalysisDao::get(track, &mut self.connect()?)
+    }
+
+    /// Library tracks which analysis matches the filter in the current track order.
+    /// Tracks that were not analysed yet never match
+    #[inline]
+    pub fn get_tracks_by_analysis(&self, filter: &AnalysisFilter) -> Result<Vec<DefaultTrack>> {
+        let analyses = TrackAnalysisDao::get_all(&mut self.connect()?)?;
+
+        Ok(self
+            .get_all_tracks()?
+            .into_iter()
+            .filter(|track| {
+                analyses
+                    .get(track.get_path())
+                    .is_some_and(|analysis| filter.matches(analysis))
+            })
+            .collect())
+    }
+
+    #[inline]
+    pub fn get_auto_dj_settings(&self) -> AutoDjSettings {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .read()
+                .await
+                .load_auto_dj_settings()
+                .clone()
+        })
+    }
+
+    /// Turns Auto-DJ on or off and stores the choice.
+    /// If the queue is about to end, picks are appended at once
+    #[inline]
+    pub fn set_auto_dj_enabled(&self, is_enabled: bool) {
+        self.update_auto_dj_settings(|settings| settings.is_enabled = is_enabled)
+    }
+
+    /// Chooses what the next picks are similar to.
+    /// None makes them similar to the last track of the queue.
+    /// Tracks that are already queued stay
+    #[inline]
+    pub fn set_auto_dj_seed(&self, seed: Option<AutoDjSeed>) {
+        self.update_auto_dj_settings(|settings| settings.seed = seed)
+    }
+
+    #[inline]
+    fn update_auto_dj_settings(&self, update: impl FnOnce(&mut AutoDjSettings)) {
+        self.tokio_runtime.block_on(async {
+            let playlist = {
+                let mut storage_util = self.storage_util.write().await;
+                let mut settings = storage_util.load_auto_dj_settings().clone();
+                update(&mut settings);
+                storage_util.store_auto_dj_settings(settings);
+                storage_util.load_current_playlist().clone()
+            };
+
+            if playlist.get_cur_track().is_some() {
+                let playlist = self.top_up_with_auto_dj(playlist).await;
+                self.set_cur_playlist(playlist).await
+            }
+        })
+    }
+
+    /// Appends Auto-DJ picks to the playlist if Auto-DJ is enabled, nothing loops
+    /// and less than [AUTO_DJ_LOOKAHEAD] tracks follow the current one.
+    /// Failures are only logged, they must not stop playback
+    #[inline]
+    async fn top_up_with_auto_dj(
+        &self,
+        playlist: DefaultPlaylist<DefaultTrack>,
+    ) -> DefaultPlaylist<DefaultTrack> {
+        let settings = self
+            .storage_util
+            .read()
+            .await
+            .load_auto_dj_settings()
+            .clone();
+
+        let is_looping =
+            self.audio_player.read().await.get_looping_state() != LoopingState::NoLooping;
+
+        let following = playlist.len().saturating_sub(playlist.get_cur_ind() + 1);
+
+        if !settings.is_enabled || is_looping || following >= AUTO_DJ_LOOKAHEAD {
+            return playlist;
+        }
+
+        let picks = match self
+            .pick_auto_dj_tracks(
+                playlist.get_tracks(),
+                settings.seed.as_ref(),
+                AUTO_DJ_LOOKAHEAD - following,
+            )
+            .await
+        {
+            Ok(picks) => picks,
+
+            Err(e) => {
+                eprintln!("Unable to pick tracks for Auto-DJ: {}", e);
+                return playlist;
+            }
+        };
+
+        if let Some(unshuffled_tracks) = self.unshuffled_tracks.lock().unwrap().as_mut() {
+            unshuffled_tracks.extend(picks.iter().cloned())
+        }
+
+        let mut tracks = playlist.get_tracks().clone();
+        tracks.extend(picks);
+
+        DefaultPlaylist::new(
+            playlist.get_title().cloned(),
+            playlist.get_type(),
+            tracks,
+            playlist.get_cur_ind(),
+        )
+    }
+
+    /// Library tracks that fit the queue, weighed with favourites and play history
+    #[inline]
+    async fn pick_auto_dj_tracks(
+        &self,
+        queue: &[DefaultTrack],
+        seed: Option<&AutoDjSeed>,
+        count: usize,
+    ) -> Result<Vec<DefaultTrack>> {
+        let library = AudioScanner::get_all_tracks(self.storage_util.clone()).await?;
+        let mut conn = self.connect()?;
+
+        let context = AutoDjContext {
+            favourite_tracks: self
+                .get_favourite_tracks()?
+                .iter()
+                .map(|track| track.get_path().clone())
+                .collect(),
+
+            favourite_artists: self
+                .get_favourite_artists()?
+                .iter()
+                .map(|artist| artist.get_key().clone())
+                .collect(),
+
+            play_counts: PlayHistoryDao::get_play_counts(&mut conn)?,
+            recent_plays: PlayHistoryDao::get_recent(AUTO_DJ_RECENT_PLAYS, &mut conn)?,
+        };
+
+        Ok(self
+            .auto_dj
+            .lock()
+            .unwrap()
+            .pick(&library, queue, seed, &context, count))
+    }
+
+    /// Starts guess-the-melody game over the library, replacing the running one
+    #[inline]
+    pub fn start_gtm_game(&self, difficulty: GtmDifficulty, rounds_count: usize) -> Result<()> {
+        let game = GtmGame::new(self.get_all_tracks()?, difficulty, rounds_count)?;
+        self.stop_gtm_clip();
+        *self.gtm_game.lock().unwrap() = Some(game);
+        Ok(())
+    }
+
+    /// Picks the track and the answers of the next round.
+    /// Clip starts where the track's waveform is loud enough
+    #[inline]
+    pub fn next_gtm_round(&self) -> Result<GtmRound> {
+        let mut game = self.gtm_game.lock().unwrap();
+        let game = game.as_mut().ok_or_else(Error::no_gtm_game)?;
+        let mut auto_dj = self.auto_dj.lock().unwrap();
+
+        game.next_round(
+            |track, other| auto_dj.get_similarity(track, other),
+            |track, clip_length| {
+                let duration = track.get_duration().to_std()?;
+                let waveform = self.get_waveform(track.get_path(), GTM_WAVEFORM_RESOLUTION)?;
+                Ok(ClipFinder::find_starts(&waveform, duration, clip_length))
+            },
+        )
+    }
+
+    /// Plays clip of the current round, returns when it ends or is stopped.
+    /// Current track is paused, clip is played apart from it,
+    /// so neither the queue nor the events give the answer away
+    #[inline]
+    pub fn play_gtm_clip(&self, is_fading: bool) -> Result<()> {
+        let round = self
+            .gtm_game
+            .lock()
+            .unwrap()
+            .as_ref()
+            .ok_or_else(Error::no_gtm_game)?
+            .get_round()
+            .cloned()
+            .ok_or_else(|| Error::Playback("There is no round to play".to_string()))?;
+
+        self.pause_playback();
+
+        let (output_mode, volume) = self.tokio_runtime.block_on(async {
+            let audio_player = self.audio_player.read().await;
+            (
+                audio_player.get_output_mode().clone(),
+                audio_player.get_volume(),
+            )
+        });
+
+        let fade = match is_fading {
+            true => GTM_CLIP_FADE,
+            false => Duration::ZERO,
+        };
+
+        let source = ClipFade::new(
+            TrackDecoder::open_at(round.track.get_path(), round.clip_start)?,
+            round.clip_length,
+            fade,
+        );
+
+        let output = AudioOutput::open(&output_mode)?;
+        let sink = output.get_sink();
+        sink.set_volume(volume);
+        sink.append(source);
+
+        if let Some(previous) = self.gtm_clip_sink.lock().unwrap().replace(sink.clone()) {
+            previous.stop()
+        }
+
+        sink.sleep_until_end();
+
+        let mut clip_sink = self.gtm_clip_sink.lock().unwrap();
+
+        if clip_sink
+            .as_ref()
+            .is_some_and(|cur| Arc::ptr_eq(cur, &sink))
+        {
+            *clip_sink = None
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn stop_gtm_clip(&self) {
+        if let Some(sink) = self.gtm_clip_sink.lock().unwrap().take() {
+            sink.stop()
+        }
+    }
+
+    /// Checks the answer of the current round and stops its clip.
+    /// Result of the finished game is stored among high scores
+    #[inline]
+    pub fn answer_gtm_round(&self, choice: usize) -> Result<GtmRoundResult> {
+        let mut game = self.gtm_game.lock().unwrap();
+        let game = game.as_mut().ok_or_else(Error::no_gtm_game)?;
+        let result = game.answer(choice)?;
+
+        self.stop_gtm_clip();
+
+        if game.is_over() {
+            let high_score = GtmHighScore {
+                score: game.get_score(),
+                difficulty: game.get_difficulty(),
+                rounds: game.get_rounds_count() as u32,
+                correct_answers: game.get_correct_answers() as u32,
+                played_at: unix_time_now(),
+            };
+
+            let stored = self
+                .connect()
+                .and_then(|mut conn| GtmHighScoreDao::add(&high_score, &mut conn));
+
+            if let Err(e) = stored {
+                eprintln!("Unable to store high score: {}", e)
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Score, number of answered rounds and number of all rounds
+    /// of the running game. Finished game is kept until the next one starts
+    #[inline]
+    pub fn get_gtm_score(&self) -> Option<(u32, usize, usize)> {
+        self.gtm_game.lock().unwrap().as_ref().map(|game| {
+            (
+                game.get_score(),
+                game.get_results().len(),
+                game.get_rounds_count(),
+            )
+        })
+    }
+
+    /// Ends the game without storing its score
+    #[inline]
+    pub fn quit_gtm_game(&self) {
+        self.stop_gtm_clip();
+        *self.gtm_game.lock().unwrap() = None
+    }
+
+    /// Best results of the finished games, None difficulty returns all of them
+    #[inline]
+    pub fn get_gtm_high_scores(
+        &self,
+        difficulty: Option<GtmDifficulty>,
+        limit: usize,
+    ) -> Result<Vec<GtmHighScore>> {
+        GtmHighScoreDao::get_top(difficulty, limit, &mut self.connect()?)
+    }
+
+    /// Plays internet radio from HTTP(S) URL instead of the current track.
+    /// Returns when the radio is paused, stopped or lost
+    #[inline]
+    pub fn play_stream(&self, url: &str) -> Result<()> {
+        let url = validate_http_url(url)?;
+
+        self.tokio_runtime.block_on(AudioPlayer::play_stream(
+            self.audio_player.clone(),
+            self.tokio_runtime.clone(),
+            self.storage_util.clone(),
+            url,
+        ))
+    }
+
+    /// Plays the station of the user's list, see [Prima::play_stream]
+    #[inline]
+    pub fn play_radio_station(&self, id: i32) -> Result<()> {
+        let station = RadioStationDao::get(id, &mut self.connect()?)?
+            .ok_or_else(|| Error::Playback(format!("There is no radio station {}", id)))?;
+
+        self.play_stream(&station.url)
+    }
+
+    /// URL of the internet radio that is played or paused
+    #[inline]
+    pub fn get_stream_url(&self) -> Option<String> {
+        self.tokio_runtime
+            .block_on(async { self.audio_player.read().await.get_stream_url().cloned() })
+    }
+
+    /// Title of the song that the internet radio has announced
+    #[inline]
+    pub fn get_stream_title(&self) -> Option<String> {
+        self.tokio_runtime
+            .block_on(async { self.audio_player.read().await.get_stream_title() })
+    }
+
+    /// Adds station to the user's list. Blank name is replaced with the URL
+    #[inline]
+    pub fn add_radio_station(&self, name: &str, url: &str) -> Result<RadioStation> {
+        let url = validate_http_url(url)?;
+
+        let name = match name.trim() {
+            "" => url.clone(),
+            name => name.to_string(),
+        };
+
+        RadioStationDao::insert(&name, &url, unix_time_now(), &mut self.connect()?)
+    }
+
+    /// Stations in the order they were added
+    #[inline]
+    pub fn get_radio_stations(&self) -> Result<Vec<RadioStation>> {
+        RadioStationDao::get_all(&mut self.connect()?)
+    }
+
+    /// Renames station and replaces its URL.
+    /// Returns false if there is no such station
+    #[inline]
+    pub fn update_radio_station(&self, id: i32, name: &str, url: &str) -> Result<bool> {
+        let url = validate_http_url(url)?;
+        RadioStationDao::update(id, name.trim(), &url, &mut self.connect()?)
+    }
+
+    /// Returns false if there is no such station
+    #[inline]
+    pub fn remove_radio_station(&self, id: i32) -> Result<bool> {
+        RadioStationDao::remove(id, &mut self.connect()?)
+    }
+
+    /// Subscribes to RSS or Atom feed. Episodes that it already has are listed,
+    /// only the ones published later are downloaded automatically
+    #[inline]
+    pub fn subscribe_podcast(&self, url: &str) -> Result<PodcastFeed> {
+        let url = validate_http_url(url)?;
+
+        if PodcastFeedDao::get_by_url(&url, &mut self.connect()?)?.is_some() {
+            return Err(Error::Config(format!("{} is already subscribed", url)));
+        }
+
+        let parsed = FeedParser::fetch(&url)?;
+        let mut conn = self.connect()?;
+
+        let feed = PodcastFeedDao::insert(
+            &url,
+            &parsed.title,
+            parsed.description.as_deref(),
+            unix_time_now(),
+            &mut conn,
+        )?;
+
+        PodcastEpisodeDao::insert_new(feed.id, &parsed.episodes, false, &mut conn)?;
+        Ok(feed)
+    }
+
+    /// Forgets the feed with its episodes and removes their downloads.
+    /// Returns false if there is no such feed
+    #[inline]
+    pub fn unsubscribe_podcast(&self, id: i32) -> Result<bool> {
+        let mut conn = self.connect()?;
+        let episodes = PodcastEpisodeDao::get_of_feed(id, &mut conn)?;
+
+        if !PodcastFeedDao::remove(id, &mut conn)? {
+            return Ok(false);
+        }
+
+        let _download = self.podcast_download_lock.lock().unwrap();
+
+        for file in episodes.iter().filter_map(|episode| episode.file.as_ref()) {
+            remove_episode_files(file)?;
+        }
+
+        match fs::remove_dir_all(self.get_podcasts_dir().join(id.to_string())) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(true),
+        }
+    }
+
+    /// Feeds in the order they were subscribed
+    #[inline]
+    pub fn get_podcast_feeds(&self) -> Result<Vec<PodcastFeed>> {
+        PodcastFeedDao::get_all(&mut self.connect()?)
+    }
+
+    /// Episodes of the feed, the newest first
+    #[inline]
+    pub fn get_podcast_episodes(&self, feed_id: i32) -> Result<Vec<PodcastEpisode>> {
+        PodcastEpisodeDao::get_of_feed(feed_id, &mut self.connect()?)
+    }
+
+    /// Fetches the feed again and downloads its new episodes.
+    /// Returns how many episodes are new
+    #[inline]
+    pub fn refresh_podcast(&self, id: i32) -> Result<usize> {
+        let new_episodes = self.fetch_podcast(id)?;
+        self.download_queued_podcast_episodes(new_episodes);
+        Ok(new_episodes)
+    }
+
+    /// Fetches all feeds and downloads their new episodes.
+    /// Feed that can't be fetched is skipped, so others are still refreshed.
+    /// Returns how many episodes are new
+    #[inline]
+    pub fn refresh_podcasts(&self) -> Result<usize> {
+        let new_episodes = self
+            .get_podcast_feeds()?
+            .into_iter()
+            .map(|feed| {
+                self.fetch_podcast(feed.id).unwrap_or_else(|e| {
+                    eprintln!("Unable to refresh podcast {}: {}", feed.url, e);
+                    0
+                })
+            })
+            .sum();
+
+        self.download_queued_podcast_episodes(new_episodes);
+        Ok(new_episodes)
+    }
+
+    /// Updates the feed's description and stores episodes it didn't have,
+    /// they are queued for the download
+    #[inline]
+    fn fetch_podcast(&self, id: i32) -> Result<usize> {
+        let feed = PodcastFeedDao::get(id, &mut self.connect()?)?
+            .ok_or_else(|| Error::Playback(format!("There is no podcast {}", id)))?;
+
+        let parsed = FeedParser::fetch(&feed.url)?;
+        let mut conn = self.connect()?;
+
+        PodcastFeedDao::update(
+            id,
+            &parsed.title,
+            parsed.description.as_deref(),
+            unix_time_now(),
+            &mut conn,
+        )?;
+
+        PodcastEpisodeDao::insert_new(id, &parsed.episodes, true, &mut conn)
+    }
+
+    /// Downloads episodes that wait for it and publishes
+    /// [PlayerEvent::PodcastsUpdated] if anything has changed.
+    /// Failed downloads stay queued until the next refresh
+    #[inline]
+    fn download_queued_podcast_episodes(&self, new_episodes: usize) {
+        let queued = self
+            .connect()
+            .and_then(|mut conn| PodcastEpisodeDao::get_queued(&mut conn))
+            .unwrap_or_else(|e| {
+                eprintln!("Unable to load queued episodes: {}", e);
+                vec![]
+            });
+
+        let downloaded = queued
+            .iter()
+            .filter(|episode| match self.download_podcast_episode(episode.id) {
+                Ok(_) => true,
+
+                Err(e) => {
+                    eprintln!("Unable to download {}: {}", episode.audio_url, e);
+                    false
+                }
+            })
+            .count();
+
+        if new_episodes > 0 || downloaded > 0 {
+            self.event_bus
+                .publish(PlayerEvent::PodcastsUpdated { new_episodes })
+        }
+    }
+
+    /// Downloads the episode into the podcasts directory and returns its file.
+    /// Interrupted download continues where it has stopped
+    #[inline]
+    pub fn download_podcast_episode(&self, id: i32) -> Result<PathBuf> {
+        let _download = self.podcast_download_lock.lock().unwrap();
+        let mut conn = self.connect()?;
+
+        let episode = PodcastEpisodeDao::get(id, &mut conn)?
+            .ok_or_else(|| Error::Playback(format!("There is no podcast episode {}", id)))?;
+
+        if let Some(file) = episode.file.filter(|file| file.exists()) {
+            return Ok(file);
+        }
+
+        let file = self
+            .get_podcasts_dir()
+            .join(episode.feed_id.to_string())
+            .join(EpisodeDownloader::file_name_of(id, &episode.audio_url));
+
+        EpisodeDownloader::download(&episode.audio_url, &file)?;
+        PodcastEpisodeDao::set_file(id, Some(&file), &mut conn)?;
+        PodcastEpisodeDao::set_download_queued(id, false, &mut conn)?;
+        Ok(file)
+    }
+
+    /// Removes the episode's file, it's not downloaded again automatically.
+    /// Returns false if there is no such episode
+    #[inline]
+    pub fn remove_podcast_download(&self, id: i32) -> Result<bool> {
+        let _download = self.podcast_download_lock.lock().unwrap();
+        let mut conn = self.connect()?;
+
+        let episode = match PodcastEpisodeDao::get(id, &mut conn)? {
+            None => return Ok(false),
+            Some(episode) => episode,
+        };
+
+        if let Some(file) = &episode.file {
+            remove_episode_files(file)?;
+        }
+
+        PodcastEpisodeDao::set_file(id, None, &mut conn)?;
+        PodcastEpisodeDao::set_download_queued(id, false, &mut conn)
+    }
+
+    /// Marks episode played or unplayed, its position is reset.
+    /// Returns false if there is no such episode
+    #[inline]
+    pub fn set_podcast_episode_played(&self, id: i32, is_played: bool) -> Result<bool> {
+        PodcastEpisodeDao::set_progress(id, is_played, Duration::ZERO, &mut self.connect()?)
+    }
+
+    /// Downloaded episodes of the feed or of all feeds, the newest first.
+    /// Episode's podcast is its artist and album
+    #[inline]
+    pub fn get_podcast_playlist(
+        &self,
+        feed_id: Option<i32>,
+    ) -> Result<DefaultPlaylist<DefaultTrack>> {
+        let mut conn = self.connect()?;
+        let feeds = PodcastFeedDao::get_all(&mut conn)?;
+        let episodes = PodcastEpisodeDao::get_downloaded(feed_id, &mut conn)?;
+
+        let feed_title = |feed_id: i32| {
+            feeds
+                .iter()
+                .find(|feed| feed.id == feed_id)
+                .map(|feed| feed.title.clone())
+        };
+
+        let tracks = episodes.into_iter().filter_map(|episode| {
+            let file = episode.file?;
+            let track = MetadataReader::read_track(&file)?;
+            let podcast = feed_title(episode.feed_id);
+
+            Some(DefaultTrack::new(
+                Some(episode.title),
+                podcast.clone(),
+                podcast,
+                file,
+                *track.get_duration(),
+                *track.get_add_date(),
+                0,
+            ))
+        });
+
+        Ok(DefaultPlaylist::new(
+            feed_id.and_then(feed_title),
+            PlaylistType::PODCAST,
+            tracks,
+            0,
+        ))
+    }
+
+    /// Directory where podcast episodes are downloaded
+    #[inline]
+    pub fn get_podcasts_dir(&self) -> PathBuf {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .read()
+                .await
+                .load_podcasts_dir()
+                .to_path_buf()
+        })
+    }
+
+    /// Episodes that are already downloaded stay where they are
+    #[inline]
+    pub fn set_podcasts_dir(&self, podcasts_dir: PathBuf) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .write()
+                .await
+                .store_podcasts_dir(podcasts_dir)
+        })
+    }
+
+    #[inline]
+    pub fn get_podcast_refresh_interval(&self) -> Duration {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .read()
+                .await
+                .load_podcast_refresh_interval()
+        })
+    }
+
+    /// Running refresher waits for the previous interval before it uses the new one
+    #[inline]
+    pub fn set_podcast_refresh_interval(&self, interval: Duration) {
+        self.tokio_runtime.block_on(async {
+            self.storage_util
+                .write()
+                .await
+                .store_podcast_refresh_interval(interval)
+        })
+    }
+
+    /// Lyrics of the track from `.lrc` file or tags
+    #[inline]
+    pub fn get_lyrics(&self, track: &Path) -> Option<Lyrics> {
+        let mut cache = self.lyrics_cache.lock().unwrap();
+
+        match cache.as_ref() {
+            Some((path, lyrics)) if path == track => lyrics.clone(),
+
+            _ => {
+                let lyrics = TrackLyrics::read(track);
+                *cache = Some((track.to_path_buf(), lyrics.clone()));
+                lyrics
+            }
+        }
+    }
+
+    #[inline]
+    pub fn get_cur_lyrics(&self) -> Option<Lyrics> {
+        self.get_lyrics(self.get_cur_track()?.get_path())
+    }
+
+    /// Line of the current track's lyrics that is sung now with its index
+    #[inline]
+    pub fn get_active_lyrics_line(&self) -> Option<(usize, LyricsLine)> {
+        let lyrics = self.get_cur_lyrics()?;
+        let index = lyrics.active_line_index(self.get_playback_position())?;
+        Some((index, lyrics.get_lines()[index].clone()))
+    }
+
+    /// Saves edited lyrics to `.lrc` file next to the track
+    #[inline]
+    pub fn save_lyrics(&self, track: &Path, lyrics: Lyrics) -> Result<PathBuf> {
+        let path = TrackLyrics::save(track, &lyrics)?;
+        *self.lyrics_cache.lock().unwrap() = Some((track.to_path_buf(), Some(lyrics)));
+        Ok(path)
+    }
+
+    /// Likes track if it's not liked yet, otherwise removes it from favourites
+    #[inline]
+    pub fn toggle_track_liked(&self, track: DefaultTrack) -> Result<()> {
+        let is_liked = self.is_track_liked(track.get_path())?;
+        self.set_track_liked(track, !is_liked)
+    }
+
+    #[inline]
+    pub fn set_track_liked(&self, track: DefaultTrack, is_liked: bool) -> Result<()> {
+        let track = track.into_favourable();
+        let mut connection = self.connect()?;
+        let is_stored = FavouriteTrackDao::get_by_key(track.get_key().clone(), &mut connection)?;
+
+        match (is_liked, is_stored.is_some()) {
+            (true, false) => FavouriteTrackDao::insert(vec![track], &mut connection),
+            (false, true) => FavouriteTrackDao::remove(vec![track], &mut connection),
+            _ => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub fn is_track_liked(&self, path: &Path) -> Result<bool> {
+        let stored: Option<FavouriteTrack> =
+            FavouriteTrackDao::get_by_key(path.to_path_buf(), &mut self.connect()?)?;
+
+        Ok(stored.is_some())
+    }
+
+    #[inline]
+    pub fn get_favourite_tracks(&self) -> Result<Vec<FavouriteTrack>> {
+        FavouriteTrackDao::get_all(&mut self.connect()?)
+    }
+
+    /// Likes artist if it's not liked yet, otherwise removes it from favourites
+    #[inline]
+    pub fn toggle_artist_liked(&self, artist: String) -> Result<()> {
+        let artist = FavouriteArtist::new(artist);
+        let mut connection = self.connect()?;
+
+        if FavouriteArtistDao::get_by_key(artist.get_key().clone(), &mut connection)?.is_some() {
+            FavouriteArtistDao::remove(vec![artist], &mut connection)
+        } else {
+            FavouriteArtistDao::insert(vec![artist], &mut connection)
+        }
+    }
+
+    #[inline]
+    pub fn is_artist_liked(&self, artist: String) -> Result<bool> {
+        Ok(FavouriteArtistDao::get_by_key(artist, &mut self.connect()?)?.is_some())
+    }
+
+    #[inline]
+    pub fn get_favourite_artists(&self) -> Result<Vec<FavouriteArtist>> {
+        FavouriteArtistDao::get_all(&mut self.connect()?)
+    }
+
+    /// Likes playlist if it's not liked yet, otherwise removes it from favourites.
+    /// Playlist without id is always added
+    #[inline]
+    pub fn toggle_playlist_liked(
+        &self,
+        id: Option<i32>,
+        title: Option<String>,
+        tp: i32,
+    ) -> Result<()> {
+        let mut connection = self.connect()?;
+
+        let id = match id {
+            Some(id) => id,
+
+            None => {
+                return FavouritePlaylistDao::insert(
+                    vec![FavouritePlaylistDBEntity::new(0, title, tp)],
+                    &mut connection,
+                )
+            }
+        };
+
+        let playlist = FavouritePlaylistDBEntity::new(id, title, tp);
+
+        if FavouritePlaylistDao::get_by_key(id, &mut connection)?.is_some() {
+            FavouritePlaylistDao::remove(vec![playlist], &mut connection)
+        } else {
+            FavouritePlaylistDao::insert(vec![playlist], &mut connection)
+        }
+    }
+
+    #[inline]
+    pub fn is_playlist_liked(&self, id: i32) -> Result<bool> {
+        Ok(FavouritePlaylistDao::get_by_key(id, &mut self.connect()?)?.is_some())
+    }
+
+    #[inline]
+    pub fn get_favourite_playlists(&self) -> Result<Vec<FavouritePlaylistDBEntity>> {
+        FavouritePlaylistDao::get_all(&mut self.connect()?)
+    }
+}
+
+/// 256 bits from the OS's secure random generator as hex.
+/// Used for secrets of the remote-control servers
+#[inline]
+fn generate_token() -> Result<String> {
+    let mut bytes = [0_u8; TOKEN_BYTES];
+    random::fill_random(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[inline]
+fn get_path_and_duration_of_track(track: &DefaultTrack) -> Result<(PathBuf, Duration)> {
+    Ok((track.get_path().clone(), track.get_duration().to_std()?))
+}
+
+/// Position as `m:ss` or `h:mm:ss`
+#[inline]
+fn format_position(position: Duration) -> String {
+    let secs = position.as_secs();
+
+    match secs / 3600 {
+        0 => format!("{}:{:02}", secs / 60, secs % 60),
+        hours => format!("{}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+    }
+}
+
+/// Removes downloaded episode and its unfinished part
+#[inline]
+fn remove_episode_files(file: &Path) -> Result<()> {
+    for path in [file.to_path_buf(), EpisodeDownloader::partial_path_of(file)] {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Internet radio and podcasts are fetched only from HTTP(S) URLs
+#[inline]
+fn validate_http_url(url: &str) -> Result<String> {
+    let url = url.trim();
+    let scheme = url
+        .split_once("://")
+        .map(|(scheme, _)| scheme.to_lowercase());
+
+    match scheme.as_deref() {
+        Some("http") | Some("https") => Ok(url.to_string()),
+        _ => Err(Error::Config(format!("{} is not HTTP(S) URL", url))),
+    }
+}
@@ -0,0 +1,111 @@
+use std::{
+    io::{self, Read},
+    sync::mpsc::Sender,
+};
+
+/// Metadata blocks are measured in these units
+const METADATA_UNIT: usize = 16;
+
+/// Strips ICY metadata from the Shoutcast or Icecast stream.
+/// Server inserts metadata block after every `icy-metaint` bytes of audio;
+/// titles of the blocks are sent to the channel, empty title is sent as None
+pub struct IcyReader<R> {
+    reader: R,
+    metaint: Option<usize>,
+
+    /// Audio bytes left before the next metadata block
+    until_metadata: usize,
+    titles: Sender<Option<String>>,
+}
+
+impl<R: Read> IcyReader<R> {
+    /// Stream without `icy-metaint` has no metadata and is read as it is
+    #[inline]
+    pub fn new(reader: R, metaint: Option<usize>, titles: Sender<Option<String>>) -> Self {
+        let metaint = metaint.filter(|&metaint| metaint > 0);
+
+        Self {
+            reader,
+            until_metadata: metaint.unwrap_or_default(),
+            metaint,
+            titles,
+        }
+    }
+
+    /// Reads the metadata block and sends its title.
+    /// Returns false if the stream has ended
+    #[inline]
+    fn read_metadata(&mut self) -> io::Result<bool> {
+        let mut len = [0_u8];
+
+        if self.reader.read(&mut len)? == 0 {
+            return Ok(false);
+        }
+
+        // Empty block means that the title is the same
+        if len[0] == 0 {
+            return Ok(true);
+        }
+
+        let mut metadata = vec![0_u8; len[0] as usize * METADATA_UNIT];
+        self.reader.read_exact(&mut metadata)?;
+
+        if let Some(title) = parse_stream_title(&metadata) {
+            let title = Some(title).filter(|title| !title.is_empty());
+            let _ = self.titles.send(title);
+        }
+
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for IcyReader<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let metaint = match self.metaint {
+            None => return self.reader.read(buf),
+            Some(metaint) => metaint,
+        };
+
+        if self.until_metadata == 0 {
+            if !self.read_metadata()? {
+                return Ok(0);
+            }
+
+            self.until_metadata = metaint;
+        }
+
+        let len = buf.len().min(self.until_metadata);
+        let read = self.reader.read(&mut buf[..len])?;
+        self.until_metadata -= read;
+        Ok(read)
+    }
+}
+
+/// Title from the metadata block like `StreamTitle='Artist - Song';StreamUrl='';`.
+/// Title may contain quotes, so it ends only at `';`.
+/// Blocks are UTF-8, old servers send Latin-1
+#[inline]
+pub fn parse_stream_title(metadata: &[u8]) -> Option<String> {
+    let metadata = metadata
+        .iter()
+        .position(|&b| b == 0)
+        .map_or(metadata, |end| &metadata[..end]);
+
+    let metadata = match std::str::from_utf8(metadata) {
+        Ok(metadata) => metadata.to_string(),
+        Err(_) => metadata.iter().map(|&b| b as char).collect(),
+    };
+
+    const KEY: &str = "StreamTitle='";
+
+    let start = metadata.find(KEY)? + KEY.len();
+    let rest = &metadata[start..];
+
+    let end = rest
+        .find("';")
+        .or_else(|| rest.rfind('\''))
+        .unwrap_or(rest.len());
+
+    Some(rest[..end].trim().to_string())
+}
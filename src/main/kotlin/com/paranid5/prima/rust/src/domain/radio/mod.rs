@@ -0,0 +1,3 @@
+pub mod icy_reader;
+pub mod radio_source;
+pub mod radio_station;
@@ -0,0 +1,412 @@
+extern crate rodio;
+
+use rodio::Source;
+
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    data::utils::http_client::HttpClient,
+    domain::{
+        audio_player::decoders::stream_decoder::StreamDecoder,
+        events::{event_bus::EventBus, player_event::PlayerEvent},
+        radio::icy_reader::IcyReader,
+    },
+    error::{Error, Result},
+};
+
+const RADIO_THREAD_NAME: &str = "prima-radio";
+
+/// Audio that is received before playback starts and after the buffer runs dry
+const PREBUFFER: Duration = Duration::from_secs(2);
+
+/// Receiving pauses when this much audio is waiting to be played
+const BUFFER_LIMIT: Duration = Duration::from_secs(8);
+
+/// Frames that are moved between the threads at once
+const BATCH_FRAMES: usize = 1024;
+
+/// Connection is given up after this many failures in a row,
+/// every next attempt waits longer
+const MAX_RECONNECTS: u32 = 5;
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// How often the waiting receiver checks that the source is still played
+const WAIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Internet radio stream as a source of the player.
+/// Stream is received and decoded on its own thread into the jitter buffer,
+/// so network hiccups don't stall the output: silence is played
+/// until the buffer is filled again. Dropped connection is restored,
+/// stream ends only when the station can't be reached any more.
+///
+/// Titles of ICY metadata are published as [PlayerEvent::StreamTitleChanged]
+/// when the audio they came with is played
+pub struct RadioSource {
+    buffer: Arc<JitterBuffer>,
+    channels: u16,
+    sample_rate: u32,
+
+    /// Samples taken from the jitter buffer and titles that go with them
+    chunk: VecDeque<i16>,
+    titles: VecDeque<(u64, Option<String>)>,
+
+    /// Samples of the stream that were played, silence is not counted
+    played: u64,
+
+    /// Channel of the next sample
+    channel: u16,
+    is_buffering: bool,
+
+    title: Arc<Mutex<Option<String>>>,
+    event_bus: EventBus,
+}
+
+/// Decoded samples between the receiving thread and the output
+struct JitterBuffer {
+    state: Mutex<BufferState>,
+    space: Condvar,
+    is_stopped: AtomicBool,
+
+    /// Limits in samples
+    prebuffer_len: usize,
+    limit: usize,
+}
+
+#[derive(Default)]
+struct BufferState {
+    samples: VecDeque<i16>,
+
+    /// Titles with the number of the sample they start at
+    titles: VecDeque<(u64, Option<String>)>,
+    pushed: u64,
+
+    /// Stream is lost, nothing will be pushed
+    is_finished: bool,
+}
+
+/// What the output has got from the jitter buffer
+enum Taken {
+    Samples,
+    NotEnough,
+    Finished,
+}
+
+impl RadioSource {
+    /// Connects to the station, so unreachable stations and unsupported codecs
+    /// are reported at once. Samples are received on another thread
+    #[inline]
+    pub fn open(url: &str, event_bus: EventBus) -> Result<Self> {
+        let (decoder, titles) = connect(url)?;
+        let channels = decoder.channels().max(1);
+        let sample_rate = decoder.sample_rate();
+
+        let samples_of =
+            |time: Duration| (time.as_secs_f64() * sample_rate as f64) as usize * channels as usize;
+
+        let buffer = Arc::new(JitterBuffer {
+            state: Mutex::new(BufferState::default()),
+            space: Condvar::new(),
+            is_stopped: AtomicBool::default(),
+            prebuffer_len: samples_of(PREBUFFER),
+            limit: samples_of(BUFFER_LIMIT),
+        });
+
+        let receiver_buffer = buffer.clone();
+        let receiver_event_bus = event_bus.clone();
+        let url = url.to_string();
+
+        thread::Builder::new()
+            .name(RADIO_THREAD_NAME.to_string())
+            .spawn(move || receive(url, decoder, titles, receiver_buffer, receiver_event_bus))?;
+
+        Ok(Self {
+            buffer,
+            channels,
+            sample_rate,
+            chunk: VecDeque::new(),
+            titles: VecDeque::new(),
+            played: 0,
+            channel: 0,
+            is_buffering: true,
+            title: Arc::new(Mutex::new(None)),
+            event_bus,
+        })
+    }
+
+    /// Title of the song that is played now, if the station tells it
+    #[inline]
+    pub fn get_title_ref(&self) -> Arc<Mutex<Option<String>>> {
+        self.title.clone()
+    }
+
+    /// Takes samples from the jitter buffer if it has at least the given amount
+    #[inline]
+    fn take(&mut self, min_len: usize) -> Taken {
+        let mut state = self.buffer.state.lock().unwrap();
+
+        if state.samples.is_empty() && state.is_finished {
+            return Taken::Finished;
+        }
+
+        if state.samples.len() < min_len.max(1) && !state.is_finished {
+            return Taken::NotEnough;
+        }
+
+        let len = state
+            .samples
+            .len()
+            .min(BATCH_FRAMES * self.channels as usize);
+
+        self.chunk.extend(state.samples.drain(..len));
+        self.titles.extend(state.titles.drain(..));
+        self.buffer.space.notify_one();
+        Taken::Samples
+    }
+
+    #[inline]
+    fn publish_titles(&mut self) {
+        while self
+            .titles
+            .front()
+            .is_some_and(|(start, _)| *start < self.played)
+        {
+            let (_, title) = self.titles.pop_front().unwrap();
+            *self.title.lock().unwrap() = title.clone();
+            self.event_bus
+                .publish(PlayerEvent::StreamTitleChanged { title });
+        }
+    }
+}
+
+impl Drop for RadioSource {
+    #[inline]
+    fn drop(&mut self) {
+        self.buffer.stop()
+    }
+}
+
+impl Iterator for RadioSource {
+    type Item = i16;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        // Silence is played by whole frames, so channels are not swapped after it
+        if self.chunk.is_empty() && self.channel == 0 {
+            let min_len = match self.is_buffering {
+                true => self.buffer.prebuffer_len,
+                false => 1,
+            };
+
+            match self.take(min_len) {
+                Taken::Samples => self.is_buffering = false,
+                Taken::NotEnough => self.is_buffering = true,
+                Taken::Finished => return None,
+            }
+        }
+
+        let sample = match self.chunk.pop_front() {
+            None => 0,
+
+            Some(sample) => {
+                self.played += 1;
+                self.publish_titles();
+                sample
+            }
+        };
+
+        self.channel = (self.channel + 1) % self.channels;
+        Some(sample)
+    }
+}
+
+impl Source for RadioSource {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl JitterBuffer {
+    #[inline]
+    fn is_stopped(&self) -> bool {
+        self.is_stopped.load(Ordering::SeqCst)
+    }
+
+    /// Flag is set under the lock, so the waiting receiver can't miss it
+    #[inline]
+    fn stop(&self) {
+        let _state = self.state.lock().unwrap();
+        self.is_stopped.store(true, Ordering::SeqCst);
+        self.space.notify_all();
+    }
+
+    /// Moves samples of the batch and the received titles to the buffer,
+    /// waiting until there is room for them.
+    /// Returns false if the source is not played any more
+    #[inline]
+    fn push(&self, batch: &mut Vec<i16>, titles: &Receiver<Option<String>>) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        while state.samples.len() + batch.len() > self.limit && !self.is_stopped() {
+            state = self.space.wait_timeout(state, WAIT_INTERVAL).unwrap().0;
+        }
+
+        if self.is_stopped() {
+            return false;
+        }
+
+        let start = state.pushed;
+        state
+            .titles
+            .extend(titles.try_iter().map(|title| (start, title)));
+        state.pushed += batch.len() as u64;
+        state.samples.extend(batch.drain(..));
+        true
+    }
+
+    /// Waits before the next connection. Returns true if the source is not played
+    #[inline]
+    fn wait_stopped(&self, timeout: Duration) -> bool {
+        let state = self.state.lock().unwrap();
+
+        let _state = self
+            .space
+            .wait_timeout_while(state, timeout, |_| !self.is_stopped())
+            .unwrap();
+
+        self.is_stopped()
+    }
+
+    #[inline]
+    fn finish(&self) {
+        self.state.lock().unwrap().is_finished = true
+    }
+}
+
+/// Opens the stream with ICY metadata and its decoder
+#[inline]
+fn connect(url: &str) -> Result<(StreamDecoder, Receiver<Option<String>>)> {
+    let stream = HttpClient::open_stream(url, &[("Icy-MetaData", "1")])?;
+
+    if !stream.is_success() {
+        return Err(Error::Io(io::Error::new(
+            ErrorKind::ConnectionRefused,
+            format!("{} has replied with {}", url, stream.status),
+        )));
+    }
+
+    let metaint = stream
+        .header("icy-metaint")
+        .and_then(|metaint| metaint.trim().parse::<usize>().ok());
+
+    let content_type = stream.header("Content-Type").map(str::to_string);
+    let (sender, titles) = mpsc::channel();
+    let reader = IcyReader::new(stream, metaint, sender);
+
+    let decoder = StreamDecoder::open(Box::new(reader), content_type.as_deref(), url)?;
+    Ok((decoder, titles))
+}
+
+/// Decodes the stream into the jitter buffer and reconnects when it's dropped.
+/// Connections that give no audio count as failures
+#[inline]
+fn receive(
+    url: String,
+    mut decoder: StreamDecoder,
+    mut titles: Receiver<Option<String>>,
+    buffer: Arc<JitterBuffer>,
+    event_bus: EventBus,
+) {
+    let format = (decoder.channels().max(1), decoder.sample_rate());
+    let batch_len = BATCH_FRAMES * format.0 as usize;
+    let mut failures = 0;
+
+    loop {
+        let mut batch = Vec::with_capacity(batch_len);
+        let mut is_received = false;
+
+        for sample in decoder.by_ref() {
+            batch.push(sample);
+            is_received = true;
+
+            if batch.len() >= batch_len && !buffer.push(&mut batch, &titles) {
+                return;
+            }
+        }
+
+        if !buffer.push(&mut batch, &titles) {
+            return;
+        }
+
+        failures = match is_received {
+            true => 0,
+            false => failures + 1,
+        };
+
+        let mut last_error = Error::Io(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("{}: stream has ended", url),
+        ));
+
+        loop {
+            if failures >= MAX_RECONNECTS {
+                event_bus.publish(PlayerEvent::Error {
+                    message: format!("Internet radio is lost: {}", last_error),
+                });
+
+                buffer.finish();
+                return;
+            }
+
+            if buffer.wait_stopped(RECONNECT_DELAY * failures) {
+                return;
+            }
+
+            match connect(&url) {
+                Ok((new_decoder, new_titles)) => {
+                    if (new_decoder.channels().max(1), new_decoder.sample_rate()) != format {
+                        last_error =
+                            Error::Decode(format!("{}: format of the stream has changed", url));
+                        failures = MAX_RECONNECTS;
+                        continue;
+                    }
+
+                    decoder = new_decoder;
+                    titles = new_titles;
+                    break;
+                }
+
+                Err(e) => {
+                    eprintln!("Unable to reconnect to {}: {}", url, e);
+                    last_error = e;
+                    failures += 1;
+                }
+            }
+        }
+    }
+}
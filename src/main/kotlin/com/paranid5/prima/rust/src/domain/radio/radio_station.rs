@@ -0,0 +1,12 @@
+/// Internet radio station that is kept in the user's list
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RadioStation {
+    pub id: i32,
+    pub name: String,
+
+    /// HTTP(S) URL of the stream, not of the playlist file
+    pub url: String,
+
+    /// Unix time when the station was added
+    pub added_at: i64,
+}
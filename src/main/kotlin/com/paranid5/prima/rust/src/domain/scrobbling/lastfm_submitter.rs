@@ -0,0 +1,217 @@
+use crate::{
+    data::utils::{
+        http_client::{form_body, HttpClient},
+        json::JsonValue,
+    },
+    domain::scrobbling::{
+        scrobble::Scrobble,
+        scrobble_submitter::{reply_error, ScrobbleSubmitter},
+    },
+    error::Result,
+};
+
+pub const LASTFM_API_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+
+/// Maximal number of scrobbles in one `track.scrobble` request
+const MAX_BATCH_LEN: usize = 50;
+
+/// Errors of the API that mean "try again later":
+/// invalid session key, service is offline, temporary error, rate limit
+const RETRIABLE_ERRORS: [i64; 5] = [9, 11, 16, 26, 29];
+
+/// Submits scrobbles with Last.fm API 2.0,
+/// which is also implemented by Libre.fm and other services.
+/// Session key is obtained by the application's authentication flow
+#[derive(Clone, Debug)]
+pub struct LastFmSubmitter {
+    api_url: String,
+    api_key: String,
+    api_secret: String,
+    session_key: String,
+}
+
+impl LastFmSubmitter {
+    #[inline]
+    pub fn new(
+        api_url: Option<String>,
+        api_key: String,
+        api_secret: String,
+        session_key: String,
+    ) -> Self {
+        Self {
+            api_url: api_url.unwrap_or_else(|| LASTFM_API_URL.to_string()),
+            api_key,
+            api_secret,
+            session_key,
+        }
+    }
+
+    /// Signed call of the method with the given parameters
+    #[inline]
+    fn call(&self, method: &str, mut params: Vec<(String, String)>) -> Result<()> {
+        params.push(("method".to_string(), method.to_string()));
+        params.push(("api_key".to_string(), self.api_key.clone()));
+        params.push(("sk".to_string(), self.session_key.clone()));
+        params.sort();
+
+        let signature = params
+            .iter()
+            .fold(String::new(), |acc, (key, value)| acc + key + value)
+            + &self.api_secret;
+
+        params.push(("api_sig".to_string(), md5_hex(signature.as_bytes())));
+        params.push(("format".to_string(), "json".to_string()));
+
+        let reply = HttpClient::post(
+            &self.api_url,
+            &[("Content-Type", "application/x-www-form-urlencoded")],
+            form_body(&params).as_bytes(),
+        )?;
+
+        let text = reply.text();
+
+        let error = JsonValue::parse(&text).ok().and_then(|json| {
+            let code = json.get("error")?.as_f64()? as i64;
+            let message = json.get("message").and_then(|m| m.as_str());
+            Some((code, message.unwrap_or_default().to_string()))
+        });
+
+        match error {
+            None if reply.is_success() => Ok(()),
+            None => Err(reply_error(self.get_name(), reply.status, &text)),
+
+            Some((code, message)) => {
+                let status = match RETRIABLE_ERRORS.contains(&code) {
+                    true => 503,
+                    false => 400,
+                };
+
+                Err(reply_error(
+                    self.get_name(),
+                    status,
+                    &format!("error {}: {}", code, message),
+                ))
+            }
+        }
+    }
+}
+
+/// Parameters of the scrobble, indexed for batch submissions
+#[inline]
+fn scrobble_params(scrobble: &Scrobble, index: Option<usize>) -> Vec<(String, String)> {
+    let key = |name: &str| match index {
+        None => name.to_string(),
+        Some(index) => format!("{}[{}]", name, index),
+    };
+
+    let mut params = vec![
+        (key("artist"), scrobble.artist.clone()),
+        (key("track"), scrobble.title.clone()),
+        (key("duration"), scrobble.duration.as_secs().to_string()),
+    ];
+
+    if let Some(album) = &scrobble.album {
+        params.push((key("album"), album.clone()))
+    }
+
+    if index.is_some() {
+        params.push((key("timestamp"), scrobble.listened_at.to_string()))
+    }
+
+    params
+}
+
+impl ScrobbleSubmitter for LastFmSubmitter {
+    #[inline]
+    fn get_name(&self) -> &str {
+        "lastfm"
+    }
+
+    #[inline]
+    fn get_max_batch_len(&self) -> usize {
+        MAX_BATCH_LEN
+    }
+
+    #[inline]
+    fn submit_now_playing(&self, scrobble: &Scrobble) -> Result<()> {
+        self.call("track.updateNowPlaying", scrobble_params(scrobble, None))
+    }
+
+    #[inline]
+    fn submit_listens(&self, scrobbles: &[Scrobble]) -> Result<()> {
+        let params = scrobbles
+            .iter()
+            .enumerate()
+            .flat_map(|(index, scrobble)| scrobble_params(scrobble, Some(index)))
+            .collect();
+
+        self.call("track.scrobble", params)
+    }
+}
+
+/// MD5 digest as lowercase hex, as required by API signatures
+#[inline]
+pub fn md5_hex(data: &[u8]) -> String {
+    const SHIFTS: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5,
+        9, 14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10,
+        15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+
+    let constants = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4294967296.0) as u32)
+        .collect::<Vec<_>>();
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+
+    while message.len() % 64 != 56 {
+        message.push(0)
+    }
+
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+    for chunk in message.chunks_exact(64) {
+        let words = chunk
+            .chunks_exact(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect::<Vec<_>>();
+
+        let [mut a, mut b, mut c, mut d] = state;
+
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(constants[i])
+                .wrapping_add(words[g])
+                .rotate_left(SHIFTS[i]);
+
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+
+        state = [
+            state[0].wrapping_add(a),
+            state[1].wrapping_add(b),
+            state[2].wrapping_add(c),
+            state[3].wrapping_add(d),
+        ];
+    }
+
+    state
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,123 @@
+use crate::{
+    data::utils::{http_client::HttpClient, json::JsonValue},
+    domain::scrobbling::{
+        scrobble::Scrobble,
+        scrobble_submitter::{reply_error, ScrobbleSubmitter},
+    },
+    error::Result,
+};
+
+pub const LISTENBRAINZ_API_URL: &str = "https://api.listenbrainz.org";
+
+/// ListenBrainz accepts up to 1000 listens, smaller batches fail less painfully
+const MAX_BATCH_LEN: usize = 100;
+
+const CLIENT_NAME: &str = "Prima";
+
+/// Submits listens to ListenBrainz or a compatible server
+/// with the user's token
+#[derive(Clone, Debug)]
+pub struct ListenBrainzSubmitter {
+    api_url: String,
+    token: String,
+}
+
+impl ListenBrainzSubmitter {
+    #[inline]
+    pub fn new(api_url: Option<String>, token: String) -> Self {
+        Self {
+            api_url: api_url
+                .unwrap_or_else(|| LISTENBRAINZ_API_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            token,
+        }
+    }
+
+    #[inline]
+    fn submit(&self, listen_type: &str, listens: Vec<JsonValue>) -> Result<()> {
+        let body = JsonValue::object([
+            ("listen_type", listen_type.into()),
+            ("payload", listens.into()),
+        ])
+        .to_string();
+
+        let reply = HttpClient::post(
+            &format!("{}/1/submit-listens", self.api_url),
+            &[
+                ("Authorization", &format!("Token {}", self.token)),
+                ("Content-Type", "application/json"),
+            ],
+            body.as_bytes(),
+        )?;
+
+        match reply.is_success() {
+            true => Ok(()),
+            false => Err(reply_error(self.get_name(), reply.status, &reply.text())),
+        }
+    }
+}
+
+/// Listen of the API, playing now listens have no time
+#[inline]
+fn listen_json(scrobble: &Scrobble, is_playing_now: bool) -> JsonValue {
+    let mut track_metadata = vec![
+        ("artist_name", scrobble.artist.as_str().into()),
+        ("track_name", scrobble.title.as_str().into()),
+    ];
+
+    if let Some(album) = &scrobble.album {
+        track_metadata.push(("release_name", album.as_str().into()))
+    }
+
+    track_metadata.push((
+        "additional_info",
+        JsonValue::object([
+            ("duration_ms", (scrobble.duration.as_millis() as i64).into()),
+            ("media_player", CLIENT_NAME.into()),
+            ("submission_client", CLIENT_NAME.into()),
+            (
+                "submission_client_version",
+                env!("CARGO_PKG_VERSION").into(),
+            ),
+        ]),
+    ));
+
+    let mut listen = vec![("track_metadata", JsonValue::object(track_metadata))];
+
+    if !is_playing_now {
+        listen.insert(0, ("listened_at", scrobble.listened_at.into()))
+    }
+
+    JsonValue::object(listen)
+}
+
+impl ScrobbleSubmitter for ListenBrainzSubmitter {
+    #[inline]
+    fn get_name(&self) -> &str {
+        "listenbrainz"
+    }
+
+    #[inline]
+    fn get_max_batch_len(&self) -> usize {
+        MAX_BATCH_LEN
+    }
+
+    #[inline]
+    fn submit_now_playing(&self, scrobble: &Scrobble) -> Result<()> {
+        self.submit("playing_now", vec![listen_json(scrobble, true)])
+    }
+
+    #[inline]
+    fn submit_listens(&self, scrobbles: &[Scrobble]) -> Result<()> {
+        let listen_type = match scrobbles.len() {
+            1 => "single",
+            _ => "import",
+        };
+
+        self.submit(
+            listen_type,
+            scrobbles.iter().map(|s| listen_json(s, false)).collect(),
+        )
+    }
+}
@@ -0,0 +1,7 @@
+pub mod lastfm_submitter;
+pub mod listenbrainz_submitter;
+pub mod scrobble;
+pub mod scrobble_queue;
+pub mod scrobble_submitter;
+pub mod scrobbler;
+pub mod scrobbling_settings;
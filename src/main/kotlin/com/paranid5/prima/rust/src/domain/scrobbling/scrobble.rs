@@ -0,0 +1,58 @@
+use crate::data::entities::tracks::{default_track::DefaultTrack, track_trait::TrackTrait};
+
+use std::time::Duration;
+
+/// Shorter tracks are never scrobbled
+const MIN_TRACK_DURATION: Duration = Duration::from_secs(30);
+
+/// Track is scrobbled after half of it or this time is played
+const MAX_REQUIRED_PLAY_TIME: Duration = Duration::from_secs(4 * 60);
+
+/// Listen of the track as it's submitted to scrobbling services
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scrobble {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub duration: Duration,
+
+    /// Unix time when the listen has started
+    pub listened_at: i64,
+}
+
+impl Scrobble {
+    /// Tracks without artist or title can't be scrobbled
+    #[inline]
+    pub fn from_track(track: &DefaultTrack, listened_at: i64) -> Option<Self> {
+        let tag = |value: Option<&String>| {
+            value
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        Some(Self {
+            artist: tag(track.get_artist())?,
+            title: tag(track.get_title())?,
+            album: tag(track.get_album()),
+            duration: track.get_duration().to_std().unwrap_or_default(),
+            listened_at,
+        })
+    }
+
+    /// Play time after which the listen is scrobbled:
+    /// half of the track, but no more than 4 minutes.
+    /// None for tracks that are too short
+    #[inline]
+    pub fn required_play_time(&self) -> Option<Duration> {
+        match self.duration > MIN_TRACK_DURATION {
+            true => Some((self.duration / 2).min(MAX_REQUIRED_PLAY_TIME)),
+            false => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_played_enough(&self, play_time: Duration) -> bool {
+        self.required_play_time()
+            .is_some_and(|required| play_time >= required)
+    }
+}
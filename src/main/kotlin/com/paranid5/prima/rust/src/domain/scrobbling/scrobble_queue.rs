@@ -0,0 +1,172 @@
+extern crate diesel;
+
+use crate::{
+    data::databases::{
+        favourites::db::establish_connection_to,
+        scrobbles::scrobble_dao::{QueuedScrobble, ScrobbleDao},
+    },
+    domain::scrobbling::{
+        scrobble::Scrobble,
+        scrobble_submitter::{is_rejected, ScrobbleSubmitter},
+    },
+    error::Result,
+};
+
+use diesel::SqliteConnection;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Delay before the first retry, doubled after every failure
+const RETRY_DELAY_SECS: i64 = 30;
+
+/// Failed submissions are retried at least this often
+const MAX_RETRY_DELAY_SECS: i64 = 6 * 60 * 60;
+
+/// Scrobbles that survive restarts and network outages.
+/// Every listen is stored for each backend and removed
+/// when the backend accepts or rejects it for good
+pub struct ScrobbleQueue {
+    db_url: String,
+    submitters: Vec<Arc<dyn ScrobbleSubmitter>>,
+
+    /// Periodic and manual flushes must not submit the same entries twice
+    flush_lock: Mutex<()>,
+}
+
+impl ScrobbleQueue {
+    #[inline]
+    pub fn new(db_url: String, submitters: Vec<Arc<dyn ScrobbleSubmitter>>) -> Self {
+        Self {
+            db_url,
+            submitters,
+            flush_lock: Mutex::new(()),
+        }
+    }
+
+    #[inline]
+    pub fn get_submitters(&self) -> &[Arc<dyn ScrobbleSubmitter>] {
+        &self.submitters
+    }
+
+    #[inline]
+    fn connect(&self) -> Result<SqliteConnection> {
+        Ok(establish_connection_to(&self.db_url)?)
+    }
+
+    /// Stores the listen for every backend
+    #[inline]
+    pub fn enqueue(&self, scrobble: &Scrobble) -> Result<()> {
+        let mut conn = self.connect()?;
+
+        self.submitters.iter().try_for_each(|submitter| {
+            ScrobbleDao::enqueue(submitter.get_name(), scrobble, &mut conn)
+        })
+    }
+
+    /// Submits every scrobble that is due now.
+    /// Returns number of scrobbles that were accepted
+    #[inline]
+    pub fn flush(&self) -> Result<usize> {
+        self.flush_at(unix_time_now())
+    }
+
+    /// Submits scrobbles that are due at the given unix time,
+    /// failed batches are postponed with exponential backoff.
+    /// Returns number of scrobbles that were accepted
+    #[inline]
+    pub fn flush_at(&self, now: i64) -> Result<usize> {
+        let _guard = self.flush_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut conn = self.connect()?;
+        let mut submitted = 0;
+
+        for submitter in &self.submitters {
+            submitted += flush_backend(submitter.as_ref(), now, &mut conn)?;
+        }
+
+        Ok(submitted)
+    }
+
+    /// Number of entries that wait for submission, for all backends
+    #[inline]
+    pub fn len(&self) -> Result<usize> {
+        ScrobbleDao::count(&mut self.connect()?)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+}
+
+#[inline]
+fn flush_backend(
+    submitter: &dyn ScrobbleSubmitter,
+    now: i64,
+    conn: &mut SqliteConnection,
+) -> Result<usize> {
+    let batch_len = submitter.get_max_batch_len().max(1);
+    let mut submitted = 0;
+
+    loop {
+        let batch = ScrobbleDao::get_due(submitter.get_name(), now, batch_len, conn)?;
+
+        if batch.is_empty() {
+            return Ok(submitted);
+        }
+
+        let ids = batch.iter().map(|entry| entry.id).collect::<Vec<_>>();
+
+        let scrobbles = batch
+            .iter()
+            .map(|entry| entry.scrobble.clone())
+            .collect::<Vec<_>>();
+
+        match submitter.submit_listens(&scrobbles) {
+            Ok(()) => {
+                ScrobbleDao::remove(&ids, conn)?;
+                submitted += ids.len();
+            }
+
+            Err(e) if is_rejected(&e) => {
+                eprintln!("Scrobbles are rejected: {}", e);
+                ScrobbleDao::remove(&ids, conn)?;
+            }
+
+            Err(e) => {
+                eprintln!("Scrobbles will be retried later: {}", e);
+                postpone(&batch, now, conn)?;
+                return Ok(submitted);
+            }
+        }
+
+        if batch.len() < batch_len {
+            return Ok(submitted);
+        }
+    }
+}
+
+#[inline]
+fn postpone(batch: &[QueuedScrobble], now: i64, conn: &mut SqliteConnection) -> Result<()> {
+    batch.iter().try_for_each(|entry| {
+        let attempts = entry.attempts + 1;
+        ScrobbleDao::postpone(entry.id, attempts, now + retry_delay_secs(attempts), conn)
+    })
+}
+
+/// Delay before the next attempt after the given number of failures
+#[inline]
+pub fn retry_delay_secs(attempts: u32) -> i64 {
+    let doublings = attempts.saturating_sub(1).min(30);
+    (RETRY_DELAY_SECS << doublings).min(MAX_RETRY_DELAY_SECS)
+}
+
+#[inline]
+pub fn unix_time_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
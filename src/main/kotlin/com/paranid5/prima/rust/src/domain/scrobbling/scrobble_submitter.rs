@@ -0,0 +1,51 @@
+use crate::{
+    domain::scrobbling::scrobble::Scrobble,
+    error::{Error, Result},
+};
+
+use std::io::{self, ErrorKind};
+
+/// Scrobbling service, e.g. ListenBrainz or Last.fm.
+///
+/// Submission that the service has rejected for good, e.g. because of
+/// invalid data, fails with `ErrorKind::InvalidInput` and is dropped.
+/// Other failures (network, server, authentication) are retried later
+pub trait ScrobbleSubmitter: Send + Sync {
+    /// Name of the backend that marks its entries in the queue
+    fn get_name(&self) -> &str;
+
+    /// Maximal number of scrobbles in one submission
+    fn get_max_batch_len(&self) -> usize;
+
+    /// Shows the track as being listened now
+    fn submit_now_playing(&self, scrobble: &Scrobble) -> Result<()>;
+
+    /// Submits finished listens, oldest first
+    fn submit_listens(&self, scrobbles: &[Scrobble]) -> Result<()>;
+}
+
+/// Error of the submission from the service's reply:
+/// malformed requests are rejected for good, everything else is retried
+#[inline]
+pub fn reply_error(backend: &str, status: u16, message: &str) -> Error {
+    let kind = match status {
+        400 | 404 | 413 | 422 => ErrorKind::InvalidInput,
+        401 | 403 => ErrorKind::PermissionDenied,
+        _ => ErrorKind::Other,
+    };
+
+    Error::Io(io::Error::new(
+        kind,
+        format!(
+            "{} has replied with {}: {}",
+            backend,
+            status,
+            message.trim()
+        ),
+    ))
+}
+
+#[inline]
+pub fn is_rejected(error: &Error) -> bool {
+    matches!(error, Error::Io(e) if e.kind() == ErrorKind::InvalidInput)
+}
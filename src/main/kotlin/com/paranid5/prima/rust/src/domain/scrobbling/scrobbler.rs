@@ -0,0 +1,250 @@
+extern crate tokio;
+
+use crate::{
+    data::{entities::tracks::default_track::DefaultTrack, utils::types::TokioRuntime},
+    domain::{
+        events::player_event::PlayerEvent,
+        prima::Prima,
+        scrobbling::{
+            scrobble::Scrobble,
+            scrobble_queue::{unix_time_now, ScrobbleQueue},
+            scrobble_submitter::ScrobbleSubmitter,
+        },
+    },
+};
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    runtime::Handle,
+    sync::broadcast::{error::RecvError, Receiver},
+    task::{self, JoinHandle},
+    time::{self, MissedTickBehavior},
+};
+
+/// Queued scrobbles are retried at least this often
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Position jumping back below this after the scrobble means
+/// that the track is listened again
+const RESTART_POSITION: Duration = Duration::from_secs(2);
+
+/// Listen of the current track
+struct Listen {
+    scrobble: Option<Scrobble>,
+
+    /// Play time before the last resume
+    play_time: Duration,
+
+    /// Start of the current uninterrupted playback, None when paused
+    playing_since: Option<Instant>,
+
+    is_announced: bool,
+    is_scrobbled: bool,
+}
+
+impl Listen {
+    #[inline]
+    fn new(track: Option<&DefaultTrack>, is_playing: bool) -> Self {
+        Self {
+            scrobble: track.and_then(|track| Scrobble::from_track(track, unix_time_now())),
+            play_time: Duration::ZERO,
+            playing_since: is_playing.then(Instant::now),
+            is_announced: false,
+            is_scrobbled: false,
+        }
+    }
+
+    #[inline]
+    fn get_play_time(&self) -> Duration {
+        self.play_time
+            + self
+                .playing_since
+                .map(|since| since.elapsed())
+                .unwrap_or_default()
+    }
+
+    #[inline]
+    fn set_playing(&mut self, is_playing: bool) {
+        match (is_playing, self.playing_since) {
+            (true, None) => self.playing_since = Some(Instant::now()),
+
+            (false, Some(since)) => {
+                self.play_time += since.elapsed();
+                self.playing_since = None;
+            }
+
+            _ => {}
+        }
+    }
+
+    /// Scrobble that should be announced as playing now, only once
+    #[inline]
+    fn take_now_playing(&mut self) -> Option<Scrobble> {
+        if self.is_announced || self.playing_since.is_none() {
+            return None;
+        }
+
+        self.is_announced = true;
+        self.scrobble.clone()
+    }
+
+    /// Scrobble that has just crossed the required play time, only once
+    #[inline]
+    fn take_finished(&mut self) -> Option<Scrobble> {
+        if self.is_scrobbled {
+            return None;
+        }
+
+        let scrobble = self
+            .scrobble
+            .as_ref()
+            .filter(|scrobble| scrobble.is_played_enough(self.get_play_time()))?
+            .clone();
+
+        self.is_scrobbled = true;
+        Some(scrobble)
+    }
+}
+
+/// Submits listens of the session to scrobbling services.
+/// Listens are stored in the durable queue first,
+/// so they survive network outages and restarts.
+///
+/// Scrobbler runs on the session's runtime and stops when it's dropped
+pub struct Scrobbler {
+    runtime: TokioRuntime,
+    queue: Arc<ScrobbleQueue>,
+    events_task: JoinHandle<()>,
+}
+
+impl Scrobbler {
+    /// Follows the player's events, submitting listens to the given services.
+    /// Scrobbles queued by previous sessions are submitted at once
+    #[inline]
+    pub fn start(prima: Arc<Prima>, submitters: Vec<Arc<dyn ScrobbleSubmitter>>) -> Self {
+        let runtime = prima.get_tokio_runtime();
+        let queue = Arc::new(ScrobbleQueue::new(prima.get_favourite_db_url(), submitters));
+
+        let listen = Listen::new(prima.get_cur_track().as_ref(), prima.is_playing());
+        let events = prima.get_event_bus().subscribe();
+
+        let events_task = runtime.spawn(follow_events(queue.clone(), listen, events));
+
+        Self {
+            runtime,
+            queue,
+            events_task,
+        }
+    }
+
+    #[inline]
+    pub fn get_queue(&self) -> Arc<ScrobbleQueue> {
+        self.queue.clone()
+    }
+}
+
+impl Drop for Scrobbler {
+    #[inline]
+    fn drop(&mut self) {
+        self.events_task.abort();
+
+        if Handle::try_current().is_err() {
+            let _ = self.runtime.block_on(&mut self.events_task);
+        }
+    }
+}
+
+#[inline]
+async fn follow_events(
+    queue: Arc<ScrobbleQueue>,
+    mut listen: Listen,
+    mut events: Receiver<PlayerEvent>,
+) {
+    let mut flush_timer = time::interval(FLUSH_INTERVAL);
+    flush_timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+
+            _ = flush_timer.tick() => {
+                flush(queue.clone());
+                continue;
+            }
+        };
+
+        match event {
+            PlayerEvent::TrackChanged { track, .. } => {
+                let is_playing = listen.playing_since.is_some();
+                listen = Listen::new(track.as_ref(), is_playing);
+            }
+
+            PlayerEvent::StateChanged { is_playing } => listen.set_playing(is_playing),
+
+            PlayerEvent::PositionTick { position }
+                if listen.is_scrobbled && position < RESTART_POSITION =>
+            {
+                listen = Listen {
+                    scrobble: listen.scrobble.take().map(|scrobble| Scrobble {
+                        listened_at: unix_time_now(),
+                        ..scrobble
+                    }),
+                    play_time: Duration::ZERO,
+                    playing_since: Some(Instant::now()),
+                    is_announced: false,
+                    is_scrobbled: false,
+                }
+            }
+
+            _ => {}
+        }
+
+        if let Some(scrobble) = listen.take_now_playing() {
+            announce(queue.clone(), scrobble);
+        }
+
+        if let Some(scrobble) = listen.take_finished() {
+            enqueue(queue.clone(), scrobble);
+        }
+    }
+}
+
+/// Now playing status is not queued, failures are only logged
+#[inline]
+fn announce(queue: Arc<ScrobbleQueue>, scrobble: Scrobble) {
+    task::spawn_blocking(move || {
+        for submitter in queue.get_submitters() {
+            if let Err(e) = submitter.submit_now_playing(&scrobble) {
+                eprintln!("Unable to update now playing: {}", e);
+            }
+        }
+    });
+}
+
+#[inline]
+fn enqueue(queue: Arc<ScrobbleQueue>, scrobble: Scrobble) {
+    task::spawn_blocking(move || match queue.enqueue(&scrobble) {
+        Ok(()) => flush_queue(&queue),
+        Err(e) => eprintln!("Unable to store scrobble: {}", e),
+    });
+}
+
+#[inline]
+fn flush(queue: Arc<ScrobbleQueue>) {
+    task::spawn_blocking(move || flush_queue(&queue));
+}
+
+#[inline]
+fn flush_queue(queue: &ScrobbleQueue) {
+    if let Err(e) = queue.flush() {
+        eprintln!("Unable to submit scrobbles: {}", e);
+    }
+}
@@ -0,0 +1,134 @@
+extern crate yaml_rust;
+
+use crate::domain::scrobbling::{
+    lastfm_submitter::LastFmSubmitter, listenbrainz_submitter::ListenBrainzSubmitter,
+    scrobble_submitter::ScrobbleSubmitter,
+};
+
+use std::sync::Arc;
+use yaml_rust::{yaml::Hash, Yaml};
+
+/// Account of ListenBrainz or a compatible server.
+/// Absent URL means the official server
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ListenBrainzAccount {
+    pub api_url: Option<String>,
+    pub token: String,
+}
+
+/// Session of Last.fm or a compatible service (e.g. Libre.fm).
+/// Absent URL means the official API
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LastFmAccount {
+    pub api_url: Option<String>,
+    pub api_key: String,
+    pub api_secret: String,
+    pub session_key: String,
+}
+
+/// Services where listens are submitted, none by default
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScrobblingSettings {
+    pub listenbrainz: Option<ListenBrainzAccount>,
+    pub lastfm: Option<LastFmAccount>,
+}
+
+impl ScrobblingSettings {
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.listenbrainz.is_some() || self.lastfm.is_some()
+    }
+
+    /// Submitters of the configured services
+    #[inline]
+    pub fn submitters(&self) -> Vec<Arc<dyn ScrobbleSubmitter>> {
+        let mut submitters: Vec<Arc<dyn ScrobbleSubmitter>> = Vec::new();
+
+        if let Some(account) = &self.listenbrainz {
+            submitters.push(Arc::new(ListenBrainzSubmitter::new(
+                account.api_url.clone(),
+                account.token.clone(),
+            )))
+        }
+
+        if let Some(account) = &self.lastfm {
+            submitters.push(Arc::new(LastFmSubmitter::new(
+                account.api_url.clone(),
+                account.api_key.clone(),
+                account.api_secret.clone(),
+                account.session_key.clone(),
+            )))
+        }
+
+        submitters
+    }
+}
+
+#[inline]
+fn yaml_key(key: &str) -> Yaml {
+    Yaml::String(key.to_string())
+}
+
+#[inline]
+fn hash_string(hash: &Hash, key: &str) -> Option<String> {
+    hash.get(&yaml_key(key))
+        .and_then(|y| y.as_str())
+        .map(String::from)
+}
+
+#[inline]
+fn insert_string(hash: &mut Hash, key: &str, value: Option<String>) {
+    if let Some(value) = value {
+        hash.insert(yaml_key(key), Yaml::String(value));
+    }
+}
+
+impl From<ScrobblingSettings> for Yaml {
+    #[inline]
+    fn from(settings: ScrobblingSettings) -> Self {
+        let mut hash = Hash::new();
+
+        if let Some(account) = settings.listenbrainz {
+            let mut listenbrainz = Hash::new();
+            insert_string(&mut listenbrainz, "api_url", account.api_url);
+            insert_string(&mut listenbrainz, "token", Some(account.token));
+            hash.insert(yaml_key("listenbrainz"), Self::Hash(listenbrainz));
+        }
+
+        if let Some(account) = settings.lastfm {
+            let mut lastfm = Hash::new();
+            insert_string(&mut lastfm, "api_url", account.api_url);
+            insert_string(&mut lastfm, "api_key", Some(account.api_key));
+            insert_string(&mut lastfm, "api_secret", Some(account.api_secret));
+            insert_string(&mut lastfm, "session_key", Some(account.session_key));
+            hash.insert(yaml_key("lastfm"), Self::Hash(lastfm));
+        }
+
+        Self::Hash(hash)
+    }
+}
+
+impl From<&Hash> for ScrobblingSettings {
+    #[inline]
+    fn from(hash: &Hash) -> Self {
+        let account = |key| hash.get(&yaml_key(key)).and_then(|y| y.as_hash());
+
+        Self {
+            listenbrainz: account("listenbrainz").and_then(|account| {
+                Some(ListenBrainzAccount {
+                    api_url: hash_string(account, "api_url"),
+                    token: hash_string(account, "token")?,
+                })
+            }),
+
+            lastfm: account("lastfm").and_then(|account| {
+                Some(LastFmAccount {
+                    api_url: hash_string(account, "api_url"),
+                    api_key: hash_string(account, "api_key")?,
+                    api_secret: hash_string(account, "api_secret")?,
+                    session_key: hash_string(account, "session_key")?,
+                })
+            }),
+        }
+    }
+}
@@ -0,0 +1,354 @@
+extern crate tokio;
+
+use crate::{
+    data::{
+        entities::{
+            playlists::{default_playlist::DefaultPlaylist, playlist_trait::PlaylistTrait},
+            tracks::{default_track::DefaultTrack, track_trait::TrackTrait},
+        },
+        utils::types::*,
+    },
+    domain::{
+        audio_player::audio_player::AudioPlayer,
+        events::{event_bus::EventBus, player_event::PlayerEvent},
+    },
+};
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    runtime::Handle,
+    task::JoinHandle,
+    time::{self, MissedTickBehavior},
+};
+
+/// Volume is faded out during this time before the timer stops playback
+pub const FADE_DURATION: Duration = Duration::from_secs(60);
+
+/// How often the timer checks playback and updates the fade
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Track is ended when less than this is left to play
+const END_TOLERANCE: Duration = Duration::from_millis(100);
+
+/// Front-end switches to the next track right after the end.
+/// The switch is suppressed only during this time after the timer has finished
+const FINISHED_GRACE: Duration = Duration::from_secs(5);
+
+/// When the sleep timer stops playback
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SleepTimerMode {
+    /// After the given time since the timer was started
+    After(Duration),
+
+    /// When the current track ends
+    EndOfTrack,
+
+    /// When the last of the following tracks of the current album ends
+    EndOfAlbum,
+}
+
+impl SleepTimerMode {
+    /// Mode as it's passed through JNI: 0 - after the given time,
+    /// 1 - end of track, 2 - end of album
+    #[inline]
+    pub fn from_code(code: i32, duration: Duration) -> Option<Self> {
+        match code {
+            0 => Some(Self::After(duration)),
+            1 => Some(Self::EndOfTrack),
+            2 => Some(Self::EndOfAlbum),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn get_code(&self) -> i32 {
+        match self {
+            Self::After(_) => 0,
+            Self::EndOfTrack => 1,
+            Self::EndOfAlbum => 2,
+        }
+    }
+}
+
+/// Stops playback after some time or at the end of the track or album,
+/// optionally fading the volume out during the last minute.
+/// Position is saved when the timer stops playback,
+/// so playback resumes from the same place next time.
+///
+/// Timer runs on the session's runtime and is cancelled when it's dropped
+pub struct SleepTimer {
+    mode: SleepTimerMode,
+    is_fading: bool,
+    started_at: Instant,
+    finished_at: Arc<Mutex<Option<Instant>>>,
+    tokio_runtime: TokioRuntime,
+    task: JoinHandle<()>,
+}
+
+impl SleepTimer {
+    #[inline]
+    pub fn start(
+        mode: SleepTimerMode,
+        is_fading: bool,
+        tokio_runtime: TokioRuntime,
+        audio_player: ARWLPlayer,
+        storage_util: ARWLStorage,
+        event_bus: EventBus,
+    ) -> Self {
+        let started_at = Instant::now();
+        let finished_at = Arc::new(Mutex::new(None));
+
+        let task = tokio_runtime.spawn(run_timer(
+            mode,
+            is_fading,
+            started_at,
+            finished_at.clone(),
+            tokio_runtime.clone(),
+            audio_player,
+            storage_util,
+            event_bus,
+        ));
+
+        Self {
+            mode,
+            is_fading,
+            started_at,
+            finished_at,
+            tokio_runtime,
+            task,
+        }
+    }
+
+    #[inline]
+    pub fn get_mode(&self) -> SleepTimerMode {
+        self.mode
+    }
+
+    #[inline]
+    pub fn is_fading(&self) -> bool {
+        self.is_fading
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.finished_at.lock().unwrap().is_some()
+    }
+
+    /// Playback time left until the timer stops it
+    #[inline]
+    pub async fn get_time_left(
+        &self,
+        audio_player: &ARWLPlayer,
+        storage_util: &ARWLStorage,
+    ) -> Duration {
+        if self.is_finished() {
+            return Duration::ZERO;
+        }
+
+        time_left(self.mode, self.started_at, audio_player, storage_util).await
+    }
+
+    /// Whether the front-end's switch to the next track
+    /// must be suppressed, because the timer's track or album has ended
+    #[inline]
+    pub async fn should_stop_before_next_track(
+        &self,
+        audio_player: &ARWLPlayer,
+        storage_util: &ARWLStorage,
+    ) -> bool {
+        if let SleepTimerMode::After(_) = self.mode {
+            return false;
+        }
+
+        let finished_at = *self.finished_at.lock().unwrap();
+
+        if let Some(finished_at) = finished_at {
+            return finished_at.elapsed() <= FINISHED_GRACE;
+        }
+
+        let (is_playing, is_done, position, speed) = {
+            let audio_player = audio_player.read().await;
+
+            (
+                audio_player.is_playing(),
+                audio_player.is_done(),
+                audio_player.get_cur_playback_pos().await,
+                audio_player.get_speed(),
+            )
+        };
+
+        let playlist = storage_util.read().await.load_current_playlist().clone();
+
+        // Position may lag behind the output for a moment after the track's end
+        let is_track_ended = is_done
+            && (is_playing
+                || playlist_time_left(self.mode, &playlist, position, speed) <= END_TOLERANCE);
+
+        is_track_ended && is_last_track(self.mode, &playlist)
+    }
+}
+
+impl Drop for SleepTimer {
+    #[inline]
+    fn drop(&mut self) {
+        self.task.abort();
+
+        // Task holds the runtime, which must not be dropped on it
+        if Handle::try_current().is_err() {
+            let _ = self.tokio_runtime.block_on(&mut self.task);
+        }
+    }
+}
+
+/// Playback time of the current playlist until the track
+/// or the following tracks of the same album end
+#[inline]
+pub fn playlist_time_left(
+    mode: SleepTimerMode,
+    playlist: &DefaultPlaylist<DefaultTrack>,
+    position: Duration,
+    speed: f32,
+) -> Duration {
+    let duration = |track: &DefaultTrack| track.get_duration().to_std().unwrap_or_default();
+
+    let cur_track = match playlist.get_cur_track() {
+        None => return Duration::ZERO,
+        Some(track) => track,
+    };
+
+    let mut left = duration(cur_track).saturating_sub(position);
+
+    if mode == SleepTimerMode::EndOfAlbum && cur_track.get_album().is_some() {
+        left += playlist
+            .get_tracks()
+            .iter()
+            .skip(playlist.get_cur_ind() + 1)
+            .take_while(|track| track.get_album() == cur_track.get_album())
+            .map(duration)
+            .sum::<Duration>();
+    }
+
+    match speed > 0.0 {
+        true => left.div_f32(speed),
+        false => left,
+    }
+}
+
+/// Whether the timer stops after the current track of the playlist
+#[inline]
+fn is_last_track(mode: SleepTimerMode, playlist: &DefaultPlaylist<DefaultTrack>) -> bool {
+    let cur_duration = playlist
+        .get_cur_track()
+        .and_then(|track| track.get_duration().to_std().ok())
+        .unwrap_or_default();
+
+    playlist_time_left(mode, playlist, cur_duration, 1.0).is_zero()
+}
+
+/// Volume of the track while it's faded out
+#[inline]
+pub fn fade_factor(time_left: Duration) -> f32 {
+    (time_left.as_secs_f32() / FADE_DURATION.as_secs_f32()).min(1.0)
+}
+
+#[inline]
+async fn time_left(
+    mode: SleepTimerMode,
+    started_at: Instant,
+    audio_player: &ARWLPlayer,
+    storage_util: &ARWLStorage,
+) -> Duration {
+    match mode {
+        SleepTimerMode::After(duration) => duration.saturating_sub(started_at.elapsed()),
+
+        _ => {
+            let (position, speed) = {
+                let audio_player = audio_player.read().await;
+                (
+                    audio_player.get_cur_playback_pos().await,
+                    audio_player.get_speed(),
+                )
+            };
+
+            let playlist = storage_util.read().await.load_current_playlist().clone();
+            playlist_time_left(mode, &playlist, position, speed)
+        }
+    }
+}
+
+#[inline]
+#[allow(clippy::too_many_arguments)]
+async fn run_timer(
+    mode: SleepTimerMode,
+    is_fading: bool,
+    started_at: Instant,
+    finished_at: Arc<Mutex<Option<Instant>>>,
+    tokio_runtime: TokioRuntime,
+    audio_player: ARWLPlayer,
+    storage_util: ARWLStorage,
+    event_bus: EventBus,
+) {
+    let mut check_timer = time::interval(CHECK_INTERVAL);
+    check_timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        check_timer.tick().await;
+
+        let is_playing = audio_player.read().await.is_playing();
+        let time_left = time_left(mode, started_at, &audio_player, &storage_util).await;
+
+        // Without a current track there is nothing to wait for yet
+        let has_cur_track = storage_util
+            .read()
+            .await
+            .load_current_playlist()
+            .get_cur_track()
+            .is_some();
+
+        if is_fading && is_playing {
+            AudioPlayer::set_volume_factor(audio_player.clone(), fade_factor(time_left)).await
+        }
+
+        let is_finished = match mode {
+            SleepTimerMode::After(_) => time_left.is_zero(),
+            _ => has_cur_track && !is_playing && time_left <= END_TOLERANCE,
+        };
+
+        if !is_finished {
+            continue;
+        }
+
+        if is_playing {
+            AudioPlayer::pause(
+                audio_player.clone(),
+                tokio_runtime.clone(),
+                storage_util.clone(),
+            )
+            .await;
+
+            AudioPlayer::save_cur_playback_pos_async(
+                audio_player.clone(),
+                tokio_runtime.clone(),
+                storage_util.clone(),
+            )
+            .await
+            .await
+            .unwrap_or_default();
+        }
+
+        AudioPlayer::set_volume_factor(audio_player.clone(), 1.0).await;
+        *finished_at.lock().unwrap() = Some(Instant::now());
+
+        // Position must survive the night even if the application is killed
+        if let Err(e) = storage_util.write().await.flush().await {
+            eprintln!("Unable to save state after sleep timer: {}", e)
+        }
+
+        event_bus.publish(PlayerEvent::SleepTimerFinished);
+        return;
+    }
+}
@@ -0,0 +1,294 @@
+extern crate tokio;
+extern crate yaml_rust;
+
+use std::{
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::AsyncWriteExt,
+    sync::Mutex,
+};
+
+use yaml_rust::{yaml::Hash, Yaml, YamlEmitter, YamlLoader};
+
+/// Time without any changes after which dirty state is flushed
+const FLUSH_DEBOUNCE: Duration = Duration::from_millis(1000);
+
+/// Maximum time dirty state may stay in memory
+/// when it is constantly updated (e.g. playback position)
+const FLUSH_MAX_DELAY: Duration = Duration::from_millis(5000);
+
+/// How often flushing task checks the store
+pub const FLUSH_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// In-memory YAML document backed by a file.
+/// Changes are accumulated in memory and written
+/// atomically (temp file + fsync + rename) by [StateStore::flush].
+/// The previous version of the file is kept as a backup
+/// and used when the main file is corrupted
+#[derive(Debug)]
+pub struct StateStore {
+    path: PathBuf,
+    data: Hash,
+    dirty_since: Option<Instant>,
+    last_change: Option<Instant>,
+
+    /// Incremented on every change, so an older snapshot never replaces a newer one
+    generation: u64,
+
+    /// Generation that is on the disk, locked while the file is written
+    written_generation: Arc<Mutex<u64>>,
+}
+
+/// Serialized state that is written to the disk
+/// without borrowing its store, see [StateStore::take_snapshot]
+#[derive(Debug)]
+pub struct StateSnapshot {
+    path: PathBuf,
+    data: String,
+    generation: u64,
+    dirty_since: Instant,
+    written_generation: Arc<Mutex<u64>>,
+}
+
+impl StateStore {
+    /// Loads state from the file or from its backup
+    /// if the file is missing, empty or corrupted.
+    /// When both are unreadable, empty state is used
+    #[inline]
+    pub async fn load(path: PathBuf) -> Self {
+        let (data, is_recovered) = match Self::read_hash(&path).await {
+            Ok(Some(data)) => (data, false),
+
+            _ => match Self::read_hash(&Self::backup_path_of(&path)).await {
+                Ok(Some(data)) => (data, true),
+                _ => (Hash::new(), false),
+            },
+        };
+
+        let now = Instant::now();
+        let dirty = if is_recovered { Some(now) } else { None };
+
+        Self {
+            path,
+            data,
+            dirty_since: dirty,
+            last_change: dirty,
+            generation: u64::from(is_recovered),
+            written_generation: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    #[inline]
+    pub fn get_path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&Yaml> {
+        self.data.get(&Yaml::String(key.to_string()))
+    }
+
+    #[inline]
+    pub fn set(&mut self, key: &str, value: Yaml) {
+        let key = Yaml::String(key.to_string());
+
+        if self.data.get(&key) == Some(&value) {
+            return;
+        }
+
+        self.data.insert(key, value);
+        self.generation += 1;
+
+        let now = Instant::now();
+        self.last_change = Some(now);
+        self.dirty_since.get_or_insert(now);
+    }
+
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty_since.is_some()
+    }
+
+    /// Checks if state was not changed for [FLUSH_DEBOUNCE]
+    /// or if it stays dirty longer than [FLUSH_MAX_DELAY]
+    #[inline]
+    pub fn should_flush(&self) -> bool {
+        match (self.dirty_since, self.last_change) {
+            (Some(dirty_since), Some(last_change)) => {
+                last_change.elapsed() >= FLUSH_DEBOUNCE || dirty_since.elapsed() >= FLUSH_MAX_DELAY
+            }
+
+            _ => false,
+        }
+    }
+
+    /// Writes state to the file if it has any unsaved changes.
+    /// Current file is kept as a backup before it is replaced
+    #[inline]
+    pub async fn flush(&mut self) -> Result<()> {
+        let snapshot = match self.take_snapshot()? {
+            None => return Ok(()),
+            Some(snapshot) => snapshot,
+        };
+
+        let result = snapshot.write().await;
+
+        if result.is_err() {
+            self.restore_dirty(&snapshot)
+        }
+
+        result
+    }
+
+    /// Flushes state when [StateStore::should_flush] allows it
+    #[inline]
+    pub async fn flush_if_needed(&mut self) -> Result<()> {
+        if self.should_flush() {
+            self.flush().await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Serializes unsaved changes, so they are written to the disk
+    /// while the store may be used again. Store is clean after it,
+    /// [StateStore::restore_dirty] must be called if the snapshot is not written
+    #[inline]
+    pub fn take_snapshot(&mut self) -> Result<Option<StateSnapshot>> {
+        let dirty_since = match self.dirty_since {
+            None => return Ok(None),
+            Some(dirty_since) => dirty_since,
+        };
+
+        let mut data = String::new();
+
+        YamlEmitter::new(&mut data)
+            .dump(&Yaml::Hash(self.data.clone()))
+            .map_err(|_| Error::from(ErrorKind::InvalidData))?;
+
+        self.dirty_since = None;
+        self.last_change = None;
+
+        Ok(Some(StateSnapshot {
+            path: self.path.clone(),
+            data,
+            generation: self.generation,
+            dirty_since,
+            written_generation: self.written_generation.clone(),
+        }))
+    }
+
+    /// Takes snapshot when [StateStore::should_flush] allows it
+    #[inline]
+    pub fn take_snapshot_if_needed(&mut self) -> Result<Option<StateSnapshot>> {
+        match self.should_flush() {
+            true => self.take_snapshot(),
+            false => Ok(None),
+        }
+    }
+
+    /// Marks changes of the snapshot that has failed to be written as unsaved again
+    #[inline]
+    pub fn restore_dirty(&mut self, snapshot: &StateSnapshot) {
+        let dirty_since = self.dirty_since.map_or(snapshot.dirty_since, |since| {
+            since.min(snapshot.dirty_since)
+        });
+
+        self.dirty_since = Some(dirty_since);
+        self.last_change.get_or_insert(dirty_since);
+    }
+
+    /// Reads YAML hash from the file.
+    ///
+    /// # Return
+    /// None if file doesn't exist or it is empty;
+    /// error if file can't be read or it is not a valid YAML hash
+    #[inline]
+    async fn read_hash(path: &Path) -> Result<Option<Hash>> {
+        let data = match fs::read_to_string(path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        if data.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let all_data = YamlLoader::load_from_str(data.as_str())
+            .map_err(|_| Error::from(ErrorKind::InvalidData))?;
+
+        match all_data.first().and_then(|all_data| all_data.as_hash()) {
+            Some(hash) => Ok(Some(hash.clone())),
+            None => Err(Error::from(ErrorKind::InvalidData)),
+        }
+    }
+
+    #[inline]
+    async fn sync_parent_dir(path: &Path) {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            if let Ok(dir) = OpenOptions::new().read(true).open(dir).await {
+                dir.sync_all().await.unwrap_or_default();
+            }
+        }
+    }
+
+    #[inline]
+    fn with_extension_suffix(path: &Path, suffix: &str) -> PathBuf {
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(suffix);
+        path.with_file_name(file_name)
+    }
+
+    #[inline]
+    pub fn backup_path_of(path: &Path) -> PathBuf {
+        Self::with_extension_suffix(path, ".bak")
+    }
+
+    #[inline]
+    fn tmp_path_of(path: &Path) -> PathBuf {
+        Self::with_extension_suffix(path, ".tmp")
+    }
+}
+
+impl StateSnapshot {
+    #[inline]
+    pub fn get_path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Writes the snapshot unless a newer one is already on the disk.
+    /// Concurrent writes of the same store are serialized
+    #[inline]
+    pub async fn write(&self) -> Result<()> {
+        let mut written_generation = self.written_generation.lock().await;
+
+        if *written_generation >= self.generation {
+            return Ok(());
+        }
+
+        let tmp_path = StateStore::tmp_path_of(&self.path);
+
+        {
+            let mut file = File::create(&tmp_path).await?;
+            file.write_all(self.data.as_bytes()).await?;
+            file.sync_all().await?;
+        }
+
+        if let Ok(Some(_)) = StateStore::read_hash(&self.path).await {
+            fs::copy(&self.path, StateStore::backup_path_of(&self.path)).await?;
+        }
+
+        fs::rename(&tmp_path, &self.path).await?;
+        StateStore::sync_parent_dir(&self.path).await;
+
+        *written_generation = self.generation;
+        Ok(())
+    }
+}
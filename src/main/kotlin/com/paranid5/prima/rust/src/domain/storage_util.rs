@@ -1,30 +1,45 @@
 extern crate dirs2;
+extern crate futures_timer;
 extern crate yaml_rust;
 
 use std::{
-    io::{Error, ErrorKind, Result},
-    path::PathBuf,
+    collections::HashMap,
+    io::Result,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
 use crate::{
-    data::utils::extensions::path_buf_ext::PathBufExt,
-    domain::audio_player::playback_params::LoopingState, DefaultPlaylist, DefaultTrack, TrackOrder,
-    AJVM,
+    data::utils::{extensions::path_buf_ext::PathBufExt, paths::APP_PATHS},
+    domain::{
+        audio_player::{ab_loop::AbLoop, playback_params::LoopingState},
+        auto_dj::auto_dj_settings::AutoDjSettings,
+        bookmarks::resume_position::DEFAULT_RESUME_THRESHOLD,
+        podcasts::podcast_refresher::DEFAULT_PODCAST_REFRESH_INTERVAL,
+        scrobbling::scrobbling_settings::ScrobblingSettings,
+        state_store::{StateStore, FLUSH_CHECK_INTERVAL},
+    },
+    ARWLStorage, DefaultPlaylist, DefaultTrack, TrackOrder,
 };
 
 use dirs2::audio_dir;
-
-use tokio::{
-    fs::File,
-    io::{AsyncReadExt, AsyncWriteExt},
-};
-
-use yaml_rust::{yaml::Hash, Yaml, YamlEmitter, YamlLoader};
+use futures_timer::Delay;
+use yaml_rust::{yaml::Hash, Yaml};
 
 const DEFAULT_VOLUME: f32 = 1_f32;
 const DEFAULT_SPEED: f32 = 1_f32;
 
+/// Port of the remote-control API when it's not chosen by the user
+pub const DEFAULT_HTTP_API_PORT: u16 = 8765;
+
+/// Standard port of MPD, clients connect to it without configuration
+pub const DEFAULT_MPD_PORT: u16 = 6600;
+
+/// Directory of the app's data where podcast episodes are downloaded
+const PODCASTS_DIR: &str = "podcasts";
+
 pub struct StorageUtil {
+    state_store: StateStore,
     music_search_path: Option<PathBuf>,
     track_order: TrackOrder,
     current_playlist: DefaultPlaylist<DefaultTrack>,
@@ -32,311 +47,606 @@ pub struct StorageUtil {
     looping_state: LoopingState,
     volume: f32,
     speed: f32,
+    output_device: Option<String>,
+    is_http_api_enabled: bool,
+    is_http_api_lan_enabled: bool,
+    http_api_port: u16,
+    http_api_token: Option<String>,
+    is_mpd_enabled: bool,
+    is_mpd_lan_enabled: bool,
+    mpd_port: u16,
+    mpd_password: Option<String>,
+    scrobbling_settings: ScrobblingSettings,
+    ab_loops: HashMap<PathBuf, AbLoop>,
+    resume_threshold: Duration,
+    auto_dj_settings: AutoDjSettings,
+    podcasts_dir: PathBuf,
+    podcast_refresh_interval: Duration,
 }
 
 impl StorageUtil {
     #[inline]
     pub async fn new() -> Self {
+        Self::from_file(APP_PATHS.get_state_file()).await
+    }
+
+    #[inline]
+    pub async fn from_file(path: PathBuf) -> Self {
+        let state_store = StateStore::load(path).await;
+
         Self {
-            music_search_path: Self::init_music_search_path().await,
-            track_order: Self::init_track_order().await,
-            current_playlist: DefaultPlaylist::default(),
-            current_playback_pos: Self::init_current_playback_position().await,
-            looping_state: Self::init_looping_state().await,
-            volume: Self::init_volume().await,
-            speed: Self::init_speed().await,
+            music_search_path: Self::init_music_search_path(&state_store),
+            track_order: Self::init_track_order(&state_store),
+            current_playlist: Self::init_current_playlist(&state_store),
+            current_playback_pos: Self::init_current_playback_position(&state_store),
+            looping_state: Self::init_looping_state(&state_store),
+            volume: Self::init_volume(&state_store),
+            speed: Self::init_speed(&state_store),
+            output_device: Self::init_output_device(&state_store),
+            is_http_api_enabled: Self::init_http_api_enabled(&state_store),
+            is_http_api_lan_enabled: Self::init_http_api_lan_enabled(&state_store),
+            http_api_port: Self::init_http_api_port(&state_store),
+            http_api_token: Self::init_http_api_token(&state_store),
+            is_mpd_enabled: Self::init_mpd_enabled(&state_store),
+            is_mpd_lan_enabled: Self::init_mpd_lan_enabled(&state_store),
+            mpd_port: Self::init_mpd_port(&state_store),
+            mpd_password: Self::init_mpd_password(&state_store),
+            scrobbling_settings: Self::init_scrobbling_settings(&state_store),
+            ab_loops: Self::init_ab_loops(&state_store),
+            resume_threshold: Self::init_resume_threshold(&state_store),
+            auto_dj_settings: Self::init_auto_dj_settings(&state_store),
+            podcasts_dir: Self::init_podcasts_dir(&state_store),
+            podcast_refresh_interval: Self::init_podcast_refresh_interval(&state_store),
+            state_store,
         }
     }
 
+    /// Writes all unsaved changes to the disk immediately.
+    /// Should be called on application's shutdown
     #[inline]
-    pub async fn initialize_playlist(&mut self, jvm: AJVM) {
-        self.current_playlist = Self::init_current_playlist(jvm).await;
+    pub async fn flush(&mut self) -> Result<()> {
+        self.state_store.flush().await
     }
 
+    /// Flushes unsaved changes to the disk
+    /// when they are not updated for some time.
+    /// Runs until the runtime is shut down
     #[inline]
-    async fn load_or_create_read_only_file() -> Result<File> {
-        Ok(match File::open("data.yaml").await {
-            Ok(file) => file,
-            Err(_) => File::create("data.yaml").await?,
-        })
+    pub async fn run_state_flush_task(this: ARWLStorage) {
+        loop {
+            Delay::new(FLUSH_CHECK_INTERVAL).await;
+
+            // File is written without the lock, so readers never wait for the disk
+            let snapshot = this.write().await.state_store.take_snapshot_if_needed();
+
+            let snapshot = match snapshot {
+                Ok(Some(snapshot)) => snapshot,
+                Ok(None) => continue,
+
+                Err(e) => {
+                    eprintln!("Unable to serialize state: {}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = snapshot.write().await {
+                eprintln!("Unable to save {}: {}", snapshot.get_path().display(), e);
+                this.write().await.state_store.restore_dirty(&snapshot)
+            }
+        }
     }
 
     #[inline]
-    async fn get_write_only_file() -> Result<File> {
-        Ok(File::create("data.yaml").await?)
+    pub fn store_music_search_path(&mut self, music_search_path: PathBuf) {
+        self.state_store.set(
+            "music_search_path",
+            Yaml::String(music_search_path.to_string()),
+        );
+
+        self.music_search_path = Some(music_search_path);
     }
 
     #[inline]
-    async fn read_all_data_from_file() -> Result<Hash> {
-        let mut file = Self::load_or_create_read_only_file().await?;
-        let mut data = String::new();
-        file.read_to_string(&mut data).await?;
+    fn init_music_search_path(state_store: &StateStore) -> Option<PathBuf> {
+        match state_store
+            .get("music_search_path")
+            .and_then(|y| y.as_str())
+        {
+            None => audio_dir(),
+            Some(path) => Some(PathBuf::from(path)),
+        }
+    }
 
-        let all_data = match YamlLoader::load_from_str(data.as_str()) {
-            Ok(x) => x,
-            Err(_) => return Err(Error::from(ErrorKind::InvalidData)),
-        };
+    #[inline]
+    pub fn load_music_search_path(&self) -> Option<&PathBuf> {
+        self.music_search_path.as_ref()
+    }
 
-        Ok(match all_data.first() {
-            None => Hash::new(),
+    #[inline]
+    pub fn store_track_order(&mut self, track_order: TrackOrder) {
+        self.track_order = track_order;
+        self.state_store.set("track_order", track_order.into());
+    }
 
-            Some(all_data) => match all_data.as_hash() {
-                Some(all_data) => all_data,
-                None => return Err(Error::from(ErrorKind::InvalidData)),
-            }
-            .clone(),
-        })
+    #[inline]
+    fn init_track_order(state_store: &StateStore) -> TrackOrder {
+        match state_store.get("track_order").and_then(|y| y.as_hash()) {
+            None => TrackOrder::default(),
+            Some(hash) => hash.into(),
+        }
+    }
+
+    #[inline]
+    pub fn load_track_order(&self) -> TrackOrder {
+        self.track_order
     }
 
     #[inline]
-    async fn write_data_to_file(all_data: Hash) -> Result<()> {
-        let mut data = String::new();
+    pub fn store_current_playlist(&mut self, cur_playlist: DefaultPlaylist<DefaultTrack>) {
+        self.current_playlist = cur_playlist.clone();
+        self.state_store
+            .set("current_playlist", cur_playlist.into());
+    }
 
-        YamlEmitter::new(&mut data)
-            .dump(&Yaml::Hash(all_data))
-            .unwrap();
+    #[inline]
+    fn init_current_playlist(state_store: &StateStore) -> DefaultPlaylist<DefaultTrack> {
+        match state_store
+            .get("current_playlist")
+            .and_then(|y| y.as_hash())
+        {
+            None => DefaultPlaylist::default(),
 
-        let mut file = Self::get_write_only_file().await?;
-        Ok(file.write_all(data.as_bytes()).await?)
+            Some(playlist) => DefaultPlaylist::from_yaml(playlist).unwrap_or_default(),
+        }
     }
 
     #[inline]
-    pub async fn store_music_search_path(&mut self, music_search_path: PathBuf) -> Result<()> {
-        self.music_search_path = Some(music_search_path.clone());
+    pub fn load_current_playlist(&self) -> &DefaultPlaylist<DefaultTrack> {
+        &self.current_playlist
+    }
 
-        let mut all_data = Self::read_all_data_from_file().await?;
+    #[inline]
+    pub fn store_current_playback_position(&mut self, millis: u64) {
+        self.current_playback_pos = millis;
 
-        all_data.insert(
-            Yaml::String("music_search_path".to_string()),
-            Yaml::String(music_search_path.to_string()),
-        );
+        self.state_store
+            .set("current_playback_position", Yaml::Integer(millis as i64));
+    }
 
-        Self::write_data_to_file(all_data).await
+    #[inline]
+    fn init_current_playback_position(state_store: &StateStore) -> u64 {
+        state_store
+            .get("current_playback_position")
+            .and_then(|y| y.as_i64())
+            .unwrap_or_default() as u64
     }
 
     #[inline]
-    async fn init_music_search_path() -> Option<PathBuf> {
-        let all_data = match Self::read_all_data_from_file().await {
-            Ok(x) => x,
-            Err(_) => return audio_dir(),
-        };
+    pub fn load_current_playback_position(&self) -> u64 {
+        self.current_playback_pos
+    }
 
-        match all_data.get(&Yaml::String("music_search_path".to_string())) {
-            None => audio_dir(),
-            Some(y) => Some(PathBuf::from(y.as_str().unwrap().to_string())),
-        }
+    #[inline]
+    pub fn store_looping_state(&mut self, looping_state: LoopingState) {
+        self.looping_state = looping_state;
+
+        self.state_store
+            .set("looping_state", Yaml::Integer(looping_state.into()));
     }
 
     #[inline]
-    pub fn load_music_search_path(&self) -> Option<&PathBuf> {
-        self.music_search_path.as_ref()
+    fn init_looping_state(state_store: &StateStore) -> LoopingState {
+        state_store
+            .get("looping_state")
+            .and_then(|y| y.as_i64())
+            .map(LoopingState::from)
+            .unwrap_or_default()
     }
 
     #[inline]
-    pub async fn store_track_order(&mut self, track_order: TrackOrder) -> Result<()> {
-        self.track_order = track_order;
-        let mut all_data = Self::read_all_data_from_file().await?;
-        all_data.insert(Yaml::String("track_order".to_string()), track_order.into());
-        Self::write_data_to_file(all_data).await
+    pub fn load_looping_state(&self) -> LoopingState {
+        self.looping_state
     }
 
     #[inline]
-    async fn init_track_order() -> TrackOrder {
-        let all_data = match Self::read_all_data_from_file().await {
-            Ok(x) => x,
-            Err(_) => return TrackOrder::default(),
-        };
+    pub fn store_volume(&mut self, volume: f32) {
+        self.volume = volume;
 
-        match all_data.get(&Yaml::String("track_order".to_string())) {
-            None => TrackOrder::default(),
+        self.state_store
+            .set("volume", Yaml::Real(format!("{:.2}", volume)));
+    }
 
-            Some(y) => match y.as_hash() {
-                None => TrackOrder::default(),
-                Some(hash) => hash.into(),
-            },
-        }
+    #[inline]
+    fn init_volume(state_store: &StateStore) -> f32 {
+        state_store
+            .get("volume")
+            .and_then(|y| y.as_f64())
+            .map(|double| double as f32)
+            .unwrap_or(DEFAULT_VOLUME)
     }
 
     #[inline]
-    pub fn load_track_order(&self) -> TrackOrder {
-        self.track_order
+    pub fn load_volume(&self) -> f32 {
+        self.volume
     }
 
     #[inline]
-    pub async fn store_current_playlist(
-        &mut self,
-        cur_playlist: DefaultPlaylist<DefaultTrack>,
-    ) -> Result<()> {
-        self.current_playlist = cur_playlist.clone();
+    pub fn store_speed(&mut self, speed: f32) {
+        self.speed = speed;
+
+        self.state_store
+            .set("speed", Yaml::Real(format!("{:.2}", speed)));
+    }
+
+    #[inline]
+    fn init_speed(state_store: &StateStore) -> f32 {
+        state_store
+            .get("speed")
+            .and_then(|y| y.as_f64())
+            .map(|double| double as f32)
+            .unwrap_or(DEFAULT_SPEED)
+    }
 
-        let mut all_data = Self::read_all_data_from_file().await?;
+    #[inline]
+    pub fn load_speed(&self) -> f32 {
+        self.speed
+    }
 
-        all_data.insert(
-            Yaml::String("current_playlist".to_string()),
-            cur_playlist.into(),
+    /// Stores name of the chosen audio output device.
+    /// None means system's default device
+    #[inline]
+    pub fn store_output_device(&mut self, output_device: Option<String>) {
+        self.state_store.set(
+            "output_device",
+            output_device
+                .clone()
+                .map(Yaml::String)
+                .unwrap_or(Yaml::Null),
         );
 
-        Self::write_data_to_file(all_data).await
+        self.output_device = output_device;
     }
 
     #[inline]
-    async fn init_current_playlist(jvm: AJVM) -> DefaultPlaylist<DefaultTrack> {
-        let all_data = match Self::read_all_data_from_file().await {
-            Ok(x) => x,
-            Err(_) => return DefaultPlaylist::default(),
-        };
+    fn init_output_device(state_store: &StateStore) -> Option<String> {
+        state_store
+            .get("output_device")
+            .and_then(|y| y.as_str())
+            .map(String::from)
+    }
 
-        match all_data.get(&Yaml::String("current_playlist".to_string())) {
-            None => DefaultPlaylist::default(),
+    #[inline]
+    pub fn load_output_device(&self) -> Option<&String> {
+        self.output_device.as_ref()
+    }
 
-            Some(y) => match y.as_hash() {
-                None => DefaultPlaylist::default(),
+    /// Remote-control API is disabled until the user enables it
+    #[inline]
+    pub fn store_http_api_enabled(&mut self, is_enabled: bool) {
+        self.is_http_api_enabled = is_enabled;
 
-                Some(playlist) => DefaultPlaylist::from_yaml(jvm, playlist)
-                    .await
-                    .unwrap_or_default(),
-            },
-        }
+        self.state_store
+            .set("http_api_enabled", Yaml::Boolean(is_enabled));
     }
 
     #[inline]
-    pub fn load_current_playlist(&self) -> &DefaultPlaylist<DefaultTrack> {
-        &self.current_playlist
+    fn init_http_api_enabled(state_store: &StateStore) -> bool {
+        state_store
+            .get("http_api_enabled")
+            .and_then(|y| y.as_bool())
+            .unwrap_or_default()
     }
 
     #[inline]
-    pub async fn store_current_playback_position(&mut self, millis: u64) -> Result<()> {
-        self.current_playback_pos = millis;
+    pub fn load_http_api_enabled(&self) -> bool {
+        self.is_http_api_enabled
+    }
+
+    /// Remote-control API accepts only local connections
+    /// until the user exposes it to the network
+    #[inline]
+    pub fn store_http_api_lan_enabled(&mut self, is_enabled: bool) {
+        self.is_http_api_lan_enabled = is_enabled;
 
-        let mut all_data = Self::read_all_data_from_file().await?;
+        self.state_store
+            .set("http_api_lan_enabled", Yaml::Boolean(is_enabled));
+    }
 
-        all_data.insert(
-            Yaml::String("current_playback_position".to_string()),
-            Yaml::Integer(millis as i64),
-        );
+    #[inline]
+    fn init_http_api_lan_enabled(state_store: &StateStore) -> bool {
+        state_store
+            .get("http_api_lan_enabled")
+            .and_then(|y| y.as_bool())
+            .unwrap_or_default()
+    }
 
-        Self::write_data_to_file(all_data).await
+    #[inline]
+    pub fn load_http_api_lan_enabled(&self) -> bool {
+        self.is_http_api_lan_enabled
     }
 
     #[inline]
-    async fn init_current_playback_position() -> u64 {
-        let all_data = match Self::read_all_data_from_file().await {
-            Ok(x) => x,
-            Err(_) => return 0,
-        };
+    pub fn store_http_api_port(&mut self, port: u16) {
+        self.http_api_port = port;
 
-        match all_data.get(&Yaml::String("current_playback_position".to_string())) {
-            None => 0,
-            Some(y) => y.as_i64().unwrap_or_default() as u64,
-        }
+        self.state_store
+            .set("http_api_port", Yaml::Integer(port as i64));
     }
 
     #[inline]
-    pub fn load_current_playback_position(&self) -> u64 {
-        self.current_playback_pos
+    fn init_http_api_port(state_store: &StateStore) -> u16 {
+        state_store
+            .get("http_api_port")
+            .and_then(|y| y.as_i64())
+            .and_then(|port| u16::try_from(port).ok())
+            .unwrap_or(DEFAULT_HTTP_API_PORT)
     }
 
     #[inline]
-    pub async fn store_looping_state(&mut self, looping_state: LoopingState) -> Result<()> {
-        self.looping_state = looping_state;
+    pub fn load_http_api_port(&self) -> u16 {
+        self.http_api_port
+    }
 
-        let mut all_data = Self::read_all_data_from_file().await?;
+    /// Stores secret that clients of the remote-control API must present
+    #[inline]
+    pub fn store_http_api_token(&mut self, token: String) {
+        self.state_store
+            .set("http_api_token", Yaml::String(token.clone()));
 
-        all_data.insert(
-            Yaml::String("looping_state".to_string()),
-            Yaml::Integer(looping_state.into()),
-        );
+        self.http_api_token = Some(token);
+    }
 
-        Self::write_data_to_file(all_data).await
+    #[inline]
+    fn init_http_api_token(state_store: &StateStore) -> Option<String> {
+        state_store
+            .get("http_api_token")
+            .and_then(|y| y.as_str())
+            .filter(|token| !token.is_empty())
+            .map(String::from)
     }
 
     #[inline]
-    async fn init_looping_state() -> LoopingState {
-        let all_data = match Self::read_all_data_from_file().await {
-            Ok(x) => x,
-            Err(_) => return LoopingState::default(),
-        };
+    pub fn load_http_api_token(&self) -> Option<&String> {
+        self.http_api_token.as_ref()
+    }
 
-        match all_data.get(&Yaml::String("looping_state".to_string())) {
-            None => LoopingState::default(),
-            Some(y) => y.as_i64().map(LoopingState::from).unwrap_or_default(),
-        }
+    /// MPD server is disabled until the user enables it
+    #[inline]
+    pub fn store_mpd_enabled(&mut self, is_enabled: bool) {
+        self.is_mpd_enabled = is_enabled;
+        self.state_store
+            .set("mpd_enabled", Yaml::Boolean(is_enabled));
     }
 
     #[inline]
-    pub fn load_looping_state(&self) -> LoopingState {
-        self.looping_state
+    fn init_mpd_enabled(state_store: &StateStore) -> bool {
+        state_store
+            .get("mpd_enabled")
+            .and_then(|y| y.as_bool())
+            .unwrap_or_default()
     }
 
     #[inline]
-    pub async fn store_volume(&mut self, volume: f32) -> Result<()> {
-        self.volume = volume;
+    pub fn load_mpd_enabled(&self) -> bool {
+        self.is_mpd_enabled
+    }
 
-        let mut all_data = Self::read_all_data_from_file().await?;
+    /// MPD server accepts only local connections
+    /// until the user exposes it to the network
+    #[inline]
+    pub fn store_mpd_lan_enabled(&mut self, is_enabled: bool) {
+        self.is_mpd_lan_enabled = is_enabled;
 
-        all_data.insert(
-            Yaml::String("volume".to_string()),
-            Yaml::Real(format!("{:.2}", volume)),
-        );
+        self.state_store
+            .set("mpd_lan_enabled", Yaml::Boolean(is_enabled));
+    }
 
-        Self::write_data_to_file(all_data).await
+    #[inline]
+    fn init_mpd_lan_enabled(state_store: &StateStore) -> bool {
+        state_store
+            .get("mpd_lan_enabled")
+            .and_then(|y| y.as_bool())
+            .unwrap_or_default()
     }
 
     #[inline]
-    async fn init_volume() -> f32 {
-        let all_data = match Self::read_all_data_from_file().await {
-            Ok(x) => x,
-            Err(_) => return DEFAULT_VOLUME,
-        };
+    pub fn load_mpd_lan_enabled(&self) -> bool {
+        self.is_mpd_lan_enabled
+    }
+
+    #[inline]
+    pub fn store_mpd_port(&mut self, port: u16) {
+        self.mpd_port = port;
+        self.state_store.set("mpd_port", Yaml::Integer(port as i64));
+    }
+
+    #[inline]
+    fn init_mpd_port(state_store: &StateStore) -> u16 {
+        state_store
+            .get("mpd_port")
+            .and_then(|y| y.as_i64())
+            .and_then(|port| u16::try_from(port).ok())
+            .unwrap_or(DEFAULT_MPD_PORT)
+    }
+
+    #[inline]
+    pub fn load_mpd_port(&self) -> u16 {
+        self.mpd_port
+    }
+
+    /// Stores password that remote MPD clients must send
+    #[inline]
+    pub fn store_mpd_password(&mut self, password: String) {
+        self.state_store
+            .set("mpd_password", Yaml::String(password.clone()));
+
+        self.mpd_password = Some(password);
+    }
 
-        match all_data.get(&Yaml::String("volume".to_string())) {
-            None => DEFAULT_VOLUME,
+    #[inline]
+    fn init_mpd_password(state_store: &StateStore) -> Option<String> {
+        state_store
+            .get("mpd_password")
+            .and_then(|y| y.as_str())
+            .filter(|password| !password.is_empty())
+            .map(String::from)
+    }
+
+    #[inline]
+    pub fn load_mpd_password(&self) -> Option<&String> {
+        self.mpd_password.as_ref()
+    }
 
-            Some(y) => y
-                .as_f64()
-                .map(|double| double as f32)
-                .unwrap_or(DEFAULT_VOLUME),
+    #[inline]
+    pub fn store_scrobbling_settings(&mut self, settings: ScrobblingSettings) {
+        self.scrobbling_settings = settings.clone();
+        self.state_store.set("scrobbling", settings.into());
+    }
+
+    #[inline]
+    fn init_scrobbling_settings(state_store: &StateStore) -> ScrobblingSettings {
+        match state_store.get("scrobbling").and_then(|y| y.as_hash()) {
+            None => ScrobblingSettings::default(),
+            Some(hash) => hash.into(),
         }
     }
 
     #[inline]
-    pub fn load_volume(&self) -> f32 {
-        self.volume
+    pub fn load_scrobbling_settings(&self) -> &ScrobblingSettings {
+        &self.scrobbling_settings
     }
 
+    /// Stores A-B loop of the track, so it's restored next time.
+    /// None removes the loop
     #[inline]
-    pub async fn store_speed(&mut self, speed: f32) -> Result<()> {
-        self.speed = speed;
+    pub fn store_ab_loop(&mut self, path: PathBuf, ab_loop: Option<AbLoop>) {
+        match ab_loop {
+            None => self.ab_loops.remove(&path),
+            Some(ab_loop) => self.ab_loops.insert(path, ab_loop),
+        };
+
+        let ab_loops = self
+            .ab_loops
+            .iter()
+            .map(|(path, ab_loop)| (Yaml::String(path.to_string()), (*ab_loop).into()))
+            .collect::<Hash>();
+
+        self.state_store.set("ab_loops", Yaml::Hash(ab_loops));
+    }
+
+    #[inline]
+    fn init_ab_loops(state_store: &StateStore) -> HashMap<PathBuf, AbLoop> {
+        state_store
+            .get("ab_loops")
+            .and_then(|y| y.as_hash())
+            .map(|ab_loops| {
+                ab_loops
+                    .iter()
+                    .filter_map(|(path, ab_loop)| {
+                        Some((
+                            PathBuf::from(path.as_str()?),
+                            AbLoop::from_yaml(ab_loop.as_hash()?)?,
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn load_ab_loop(&self, path: &Path) -> Option<AbLoop> {
+        self.ab_loops.get(path).copied()
+    }
 
-        let mut all_data = Self::read_all_data_from_file().await?;
+    /// Tracks of at least this length remember where they were left
+    #[inline]
+    pub fn store_resume_threshold(&mut self, threshold: Duration) {
+        self.resume_threshold = threshold;
 
-        all_data.insert(
-            Yaml::String("speed".to_string()),
-            Yaml::Real(format!("{:.2}", speed)),
+        self.state_store.set(
+            "resume_threshold",
+            Yaml::Integer(threshold.as_millis() as i64),
         );
+    }
 
-        Self::write_data_to_file(all_data).await
+    #[inline]
+    fn init_resume_threshold(state_store: &StateStore) -> Duration {
+        state_store
+            .get("resume_threshold")
+            .and_then(|y| y.as_i64())
+            .and_then(|millis| u64::try_from(millis).ok())
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_RESUME_THRESHOLD)
     }
 
     #[inline]
-    async fn init_speed() -> f32 {
-        let all_data = match Self::read_all_data_from_file().await {
-            Ok(x) => x,
-            Err(_) => return DEFAULT_SPEED,
-        };
+    pub fn load_resume_threshold(&self) -> Duration {
+        self.resume_threshold
+    }
+
+    #[inline]
+    pub fn store_auto_dj_settings(&mut self, settings: AutoDjSettings) {
+        self.auto_dj_settings = settings.clone();
+        self.state_store.set("auto_dj", settings.into());
+    }
 
-        match all_data.get(&Yaml::String("speed".to_string())) {
-            None => DEFAULT_SPEED,
+    #[inline]
+    fn init_auto_dj_settings(state_store: &StateStore) -> AutoDjSettings {
+        match state_store.get("auto_dj").and_then(|y| y.as_hash()) {
+            None => AutoDjSettings::default(),
+            Some(hash) => hash.into(),
+        }
+    }
+
+    #[inline]
+    pub fn load_auto_dj_settings(&self) -> &AutoDjSettings {
+        &self.auto_dj_settings
+    }
 
-            Some(y) => y
-                .as_f64()
-                .map(|double| double as f32)
-                .unwrap_or(DEFAULT_SPEED),
+    /// Directory where podcast episodes are downloaded
+    #[inline]
+    pub fn store_podcasts_dir(&mut self, podcasts_dir: PathBuf) {
+        self.state_store
+            .set("podcasts_dir", Yaml::String(podcasts_dir.to_string()));
+
+        self.podcasts_dir = podcasts_dir;
+    }
+
+    #[inline]
+    fn init_podcasts_dir(state_store: &StateStore) -> PathBuf {
+        match state_store.get("podcasts_dir").and_then(|y| y.as_str()) {
+            None => APP_PATHS.get_data_dir().join(PODCASTS_DIR),
+            Some(path) => PathBuf::from(path),
         }
     }
 
     #[inline]
-    pub fn load_speed(&self) -> f32 {
-        self.speed
+    pub fn load_podcasts_dir(&self) -> &Path {
+        &self.podcasts_dir
+    }
+
+    #[inline]
+    pub fn store_podcast_refresh_interval(&mut self, interval: Duration) {
+        self.podcast_refresh_interval = interval;
+
+        self.state_store.set(
+            "podcast_refresh_interval",
+            Yaml::Integer(interval.as_millis() as i64),
+        );
+    }
+
+    #[inline]
+    fn init_podcast_refresh_interval(state_store: &StateStore) -> Duration {
+        state_store
+            .get("podcast_refresh_interval")
+            .and_then(|y| y.as_i64())
+            .and_then(|millis| u64::try_from(millis).ok())
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_PODCAST_REFRESH_INTERVAL)
+    }
+
+    #[inline]
+    pub fn load_podcast_refresh_interval(&self) -> Duration {
+        self.podcast_refresh_interval
     }
 }
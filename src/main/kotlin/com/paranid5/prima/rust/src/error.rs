@@ -0,0 +1,181 @@
+extern crate diesel;
+extern crate hound;
+extern crate jni;
+extern crate rodio;
+
+use std::{
+    any::Any,
+    fmt::{Display, Formatter},
+    io,
+};
+
+/// Errors that can happen anywhere in the library.
+/// Every variant is mapped to its own Java exception
+/// when error reaches JNI boundary
+#[derive(Debug)]
+pub enum Error {
+    /// File system errors: opening tracks, scanning folders, saving settings
+    Io(io::Error),
+
+    /// Track can't be decoded or its format is not supported
+    Decode(String),
+
+    /// SQLite connection or query failure
+    Database(String),
+
+    /// Settings are missing or invalid
+    Config(String),
+
+    /// Failure while calling JVM or converting its objects
+    Jni(jni::errors::Error),
+
+    /// Nothing to play, no output device and other player's states
+    Playback(String),
+
+    /// Panic caught on JNI boundary
+    Panic(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub const PRIMA_EXCEPTION_CLASS: &str = "com/paranid5/prima/rust/exceptions/PrimaException";
+
+impl Error {
+    /// Java class of exception which is thrown for this error
+    #[inline]
+    pub fn java_exception_class(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "com/paranid5/prima/rust/exceptions/PrimaIOException",
+            Error::Decode(_) => "com/paranid5/prima/rust/exceptions/PrimaDecodeException",
+            Error::Database(_) => "com/paranid5/prima/rust/exceptions/PrimaDatabaseException",
+            Error::Config(_) => "com/paranid5/prima/rust/exceptions/PrimaConfigException",
+            Error::Jni(_) => "com/paranid5/prima/rust/exceptions/PrimaJniException",
+            Error::Playback(_) => "com/paranid5/prima/rust/exceptions/PrimaPlaybackException",
+            Error::Panic(_) => "com/paranid5/prima/rust/exceptions/PrimaPanicException",
+        }
+    }
+
+    /// Creates error from the payload of caught panic
+    #[inline]
+    pub fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "Unknown panic".to_string(),
+            },
+        };
+
+        Error::Panic(message)
+    }
+
+    #[inline]
+    pub fn no_current_track() -> Self {
+        Error::Playback("There is no current track".to_string())
+    }
+
+    #[inline]
+    pub fn no_gtm_game() -> Self {
+        Error::Playback("There is no game of guess the melody".to_string())
+    }
+}
+
+impl Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Decode(e) => write!(f, "Decoding error: {}", e),
+            Error::Database(e) => write!(f, "Database error: {}", e),
+            Error::Config(e) => write!(f, "Configuration error: {}", e),
+            Error::Jni(e) => write!(f, "JNI error: {}", e),
+            Error::Playback(e) => write!(f, "Playback error: {}", e),
+            Error::Panic(e) => write!(f, "Internal error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Jni(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    #[inline]
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<jni::errors::Error> for Error {
+    #[inline]
+    fn from(e: jni::errors::Error) -> Self {
+        Error::Jni(e)
+    }
+}
+
+impl From<diesel::result::Error> for Error {
+    #[inline]
+    fn from(e: diesel::result::Error) -> Self {
+        Error::Database(e.to_string())
+    }
+}
+
+impl From<diesel::ConnectionError> for Error {
+    #[inline]
+    fn from(e: diesel::ConnectionError) -> Self {
+        Error::Database(e.to_string())
+    }
+}
+
+impl From<rodio::decoder::DecoderError> for Error {
+    #[inline]
+    fn from(e: rodio::decoder::DecoderError) -> Self {
+        Error::Decode(e.to_string())
+    }
+}
+
+impl From<rodio::StreamError> for Error {
+    #[inline]
+    fn from(e: rodio::StreamError) -> Self {
+        Error::Playback(e.to_string())
+    }
+}
+
+impl From<rodio::PlayError> for Error {
+    #[inline]
+    fn from(e: rodio::PlayError) -> Self {
+        Error::Playback(e.to_string())
+    }
+}
+
+impl From<hound::Error> for Error {
+    #[inline]
+    fn from(e: hound::Error) -> Self {
+        match e {
+            hound::Error::IoError(e) => Error::Io(e),
+            e => Error::Io(io::Error::other(e.to_string())),
+        }
+    }
+}
+
+impl From<yaml_rust::ScanError> for Error {
+    #[inline]
+    fn from(e: yaml_rust::ScanError) -> Self {
+        Error::Config(e.to_string())
+    }
+}
+
+impl From<chrono::OutOfRangeError> for Error {
+    #[inline]
+    fn from(e: chrono::OutOfRangeError) -> Self {
+        Error::Decode(format!("Invalid track duration: {}", e))
+    }
+}
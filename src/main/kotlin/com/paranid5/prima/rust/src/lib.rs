@@ -6,26 +6,23 @@ extern crate diesel;
 
 pub mod data;
 pub mod domain;
+pub mod error;
 #[cfg(test)]
 mod tests;
 
-use diesel::prelude::*;
-
-use std::{cell::RefCell, collections::HashSet, path::PathBuf, rc::Rc, sync::Arc, time::Duration};
+use std::{
+    cell::RefCell,
+    path::PathBuf,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use crate::{
     data::{
         databases::{
-            db_entity::DBEntity,
-            entity_dao::EntityDao,
-            favourites::{
-                daos::{
-                    favourite_artist_dao::FavouriteArtistDao,
-                    favourite_playlist_dao::{FavouritePlaylistDBEntity, FavouritePlaylistDao},
-                    favourite_track_dao::FavouriteTrackDao,
-                },
-                db::establish_connection,
-            },
+            db_entity::DBEntity, entity_dao::EntityDao,
+            favourites::daos::favourite_playlist_dao::FavouritePlaylistDBEntity,
         },
         entities::{
             artists::favourite_artist::FavouriteArtist,
@@ -44,110 +41,339 @@ use crate::{
                 jlist_ext::JListExt, playlist_ext::PlaylistExt, string_ext::StringExt,
                 track_ext::TrackExt, vec_ext::ExactSizeIteratorExt,
             },
+            jni_call::catch_jni_call,
+            jvm_event_listener::JvmEventListener,
             track_order::{Comparator, Ord, TrackOrder},
             types::*,
         },
     },
-    domain::{audio_scanner::AudioScanner, storage_util::StorageUtil},
+    domain::{
+        analysis::{analysis_filter::AnalysisFilter, musical_key::MusicalKey},
+        auto_dj::auto_dj_settings::AutoDjSeed,
+        bookmarks::bookmark::Bookmark,
+        gtm::gtm_difficulty::GtmDifficulty,
+        http_api::http_api_server::HttpApiServer,
+        lyrics::lyrics::Lyrics,
+        mpd::mpd_server::MpdServer,
+        podcasts::{
+            podcast_episode::PodcastEpisode, podcast_feed::PodcastFeed,
+            podcast_refresher::PodcastRefresher,
+        },
+        prima::Prima,
+        radio::radio_station::RadioStation,
+        scrobbling::scrobbler::Scrobbler,
+        scrobbling::scrobbling_settings::{LastFmAccount, ListenBrainzAccount},
+        sleep_timer::SleepTimerMode,
+        storage_util::StorageUtil,
+    },
+    error::{Error, Result},
 };
 
 use jni::{
-    objects::{JClass, JList, JObject, JString},
+    objects::{JClass, JList, JObject, JObjectArray, JString, JValue},
     sys::*,
     JNIEnv,
 };
 
-use domain::audio_player::{audio_player::*, playback_params::PlaybackParams};
-use once_cell::sync::Lazy;
-use tokio::sync::RwLock;
-
-static TOKIO_RUNTIME: Lazy<TokioRuntime> = Lazy::new(|| {
-    Arc::new(
-        tokio::runtime::Builder::new_multi_thread()
-            .enable_all()
-            .build()
-            .unwrap(),
-    )
-});
-
-static STORAGE_UTIL: Lazy<ARWLStorage> =
-    Lazy::new(|| TOKIO_RUNTIME.block_on(async { Arc::new(RwLock::new(StorageUtil::new().await)) }));
-
-static AUDIO_PLAYER: Lazy<ARWLPlayer> = Lazy::new(|| {
-    TOKIO_RUNTIME.block_on(async {
-        Arc::new(RwLock::new(
-            AudioPlayer::new(
-                PlaybackParams::default(STORAGE_UTIL.clone()).await,
-                STORAGE_UTIL.clone(),
-            )
-            .await,
-        ))
-    })
-});
+use domain::audio_player::{ab_loop::AbLoop, audio_output::output_device_names, audio_player::*};
+use once_cell::sync::{Lazy, OnceCell};
+
+#[cfg(target_os = "linux")]
+use domain::mpris::mpris_server::MprisServer;
+
+static JVM_EVENT_LISTENER: Lazy<JvmEventListener> = Lazy::new(JvmEventListener::new);
+
+/// Session that is shared by all JNI calls.
+/// Initialized by the first call, see [prima]
+static PRIMA: OnceCell<Arc<Prima>> = OnceCell::new();
+
+/// Media widgets and media keys of the Linux desktop
+#[cfg(target_os = "linux")]
+static MPRIS_SERVER: OnceCell<MprisServer> = OnceCell::new();
+
+/// Remote-control API, runs only when it's enabled in settings
+static HTTP_API_SERVER: Mutex<Option<HttpApiServer>> = Mutex::new(None);
+
+/// MPD protocol server for existing clients, runs only when it's enabled in settings
+static MPD_SERVER: Mutex<Option<MpdServer>> = Mutex::new(None);
+
+/// Submits listens, runs only when some scrobbling service is configured
+static SCROBBLER: Mutex<Option<Scrobbler>> = Mutex::new(None);
+
+/// Refreshes subscribed podcasts and downloads their new episodes
+static PODCAST_REFRESHER: Mutex<Option<PodcastRefresher>> = Mutex::new(None);
+
+/// Starts the session on the first call.
+/// If it fails, error is returned to the caller
+/// and the next call tries to start it again
+#[inline]
+fn prima() -> Result<&'static Arc<Prima>> {
+    PRIMA.get_or_try_init(|| Prima::new().map(Arc::new))
+}
+
+/// Stops running remote-control API and starts it again
+/// with the stored port if it's enabled
+#[inline]
+fn restart_http_api() -> Result<()> {
+    let mut server = HTTP_API_SERVER.lock().unwrap();
+    *server = None;
+
+    if prima()?.is_http_api_enabled() {
+        *server = Some(HttpApiServer::start(
+            prima()?.clone(),
+            prima()?.get_http_api_port(),
+            prima()?.get_http_api_token()?,
+            prima()?.is_http_api_lan_enabled(),
+        )?)
+    }
+
+    Ok(())
+}
+
+/// Stops running MPD server and starts it again
+/// with the stored port and password if it's enabled
+#[inline]
+fn restart_mpd() -> Result<()> {
+    let mut server = MPD_SERVER.lock().unwrap();
+    *server = None;
+
+    if prima()?.is_mpd_enabled() {
+        *server = Some(MpdServer::start(
+            prima()?.clone(),
+            prima()?.get_mpd_port(),
+            Some(prima()?.get_mpd_password()?),
+            prima()?.is_mpd_lan_enabled(),
+        )?)
+    }
+
+    Ok(())
+}
+
+/// Stops running scrobbler and starts it again
+/// with the stored accounts if there are any
+#[inline]
+fn restart_scrobbler() -> Result<()> {
+    let mut scrobbler = SCROBBLER.lock().unwrap();
+    *scrobbler = None;
+
+    let submitters = prima()?.get_scrobble_submitters();
+
+    if !submitters.is_empty() {
+        *scrobbler = Some(Scrobbler::start(prima()?.clone(), submitters))
+    }
+
+    Ok(())
+}
+
+/// String argument that may be null
+#[inline]
+fn get_nullable_string(env: &mut JNIEnv, string: &JString) -> Result<Option<String>> {
+    match string.is_null() {
+        true => Ok(None),
+        false => Ok(Some(String::from(env.get_string(string)?))),
+    }
+}
+
+/// Creates `com.paranid5.prima.rust.Bookmark`
+#[inline]
+fn bookmark_to_java<'a>(env: &mut JNIEnv<'a>, bookmark: &Bookmark) -> Result<JObject<'a>> {
+    let path = env.new_string(bookmark.path.to_string_lossy())?;
+    let name = env.new_string(&bookmark.name)?;
+
+    let note = match &bookmark.note {
+        None => JObject::null(),
+        Some(note) => JObject::from(env.new_string(note)?),
+    };
+
+    Ok(env.new_object(
+        "com/paranid5/prima/rust/Bookmark",
+        "(JLjava/lang/String;JLjava/lang/String;Ljava/lang/String;J)V",
+        &[
+            JValue::Long(bookmark.id as jlong),
+            JValue::Object(&path),
+            JValue::Long(bookmark.position.as_millis() as jlong),
+            JValue::Object(&name),
+            JValue::Object(&note),
+            JValue::Long(bookmark.created_at),
+        ],
+    )?)
+}
+
+#[inline]
+fn get_bookmark_id(id: jlong) -> Result<i32> {
+    i32::try_from(id).map_err(|_| Error::Config(format!("Invalid bookmark id: {}", id)))
+}
+
+/// Creates `com.paranid5.prima.rust.RadioStation`
+#[inline]
+fn radio_station_to_java<'a>(env: &mut JNIEnv<'a>, station: &RadioStation) -> Result<JObject<'a>> {
+    let name = env.new_string(&station.name)?;
+    let url = env.new_string(&station.url)?;
+
+    Ok(env.new_object(
+        "com/paranid5/prima/rust/RadioStation",
+        "(JLjava/lang/String;Ljava/lang/String;J)V",
+        &[
+            JValue::Long(station.id as jlong),
+            JValue::Object(&name),
+            JValue::Object(&url),
+            JValue::Long(station.added_at),
+        ],
+    )?)
+}
+
+#[inline]
+fn get_radio_station_id(id: jlong) -> Result<i32> {
+    i32::try_from(id).map_err(|_| Error::Config(format!("Invalid radio station id: {}", id)))
+}
+
+/// Creates `com.paranid5.prima.rust.PodcastFeed`
+#[inline]
+fn podcast_feed_to_java<'a>(env: &mut JNIEnv<'a>, feed: &PodcastFeed) -> Result<JObject<'a>> {
+    let url = env.new_string(&feed.url)?;
+    let title = env.new_string(&feed.title)?;
+
+    let description = match &feed.description {
+        None => JObject::null(),
+        Some(description) => JObject::from(env.new_string(description)?),
+    };
+
+    Ok(env.new_object(
+        "com/paranid5/prima/rust/PodcastFeed",
+        "(JLjava/lang/String;Ljava/lang/String;Ljava/lang/String;JJ)V",
+        &[
+            JValue::Long(feed.id as jlong),
+            JValue::Object(&url),
+            JValue::Object(&title),
+            JValue::Object(&description),
+            JValue::Long(feed.added_at),
+            JValue::Long(feed.refreshed_at),
+        ],
+    )?)
+}
+
+/// Creates `com.paranid5.prima.rust.PodcastEpisode`
+#[inline]
+fn podcast_episode_to_java<'a>(
+    env: &mut JNIEnv<'a>,
+    episode: &PodcastEpisode,
+) -> Result<JObject<'a>> {
+    let title = env.new_string(&episode.title)?;
+    let audio_url = env.new_string(&episode.audio_url)?;
+
+    let file = match &episode.file {
+        None => JObject::null(),
+        Some(file) => JObject::from(env.new_string(file.to_string_lossy())?),
+    };
+
+    Ok(env.new_object(
+        "com/paranid5/prima/rust/PodcastEpisode",
+        "(JJLjava/lang/String;Ljava/lang/String;JJLjava/lang/String;ZZJ)V",
+        &[
+            JValue::Long(episode.id as jlong),
+            JValue::Long(episode.feed_id as jlong),
+            JValue::Object(&title),
+            JValue::Object(&audio_url),
+            JValue::Long(episode.published_at.unwrap_or(-1)),
+            JValue::Long(
+                episode
+                    .duration
+                    .map_or(-1, |duration| duration.as_millis() as jlong),
+            ),
+            JValue::Object(&file),
+            JValue::Bool(episode.is_download_queued.into()),
+            JValue::Bool(episode.is_played.into()),
+            JValue::Long(episode.position.as_millis() as jlong),
+        ],
+    )?)
+}
+
+#[inline]
+fn get_podcast_id(id: jlong) -> Result<i32> {
+    i32::try_from(id).map_err(|_| Error::Config(format!("Invalid podcast id: {}", id)))
+}
+
+#[inline]
+fn get_podcast_episode_id(id: jlong) -> Result<i32> {
+    i32::try_from(id).map_err(|_| Error::Config(format!("Invalid podcast episode id: {}", id)))
+}
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_initRust(env: JNIEnv, _class: JClass) {
-    let env = Rc::new(RefCell::new(env));
-    let jvm = { ajvm(&*env.borrow()) };
+    catch_jni_call(env, |_| {
+        prima()?;
+
+        // Player works without desktop integration, e.g. when there is no session bus
+        #[cfg(target_os = "linux")]
+        if let Err(e) = MPRIS_SERVER.get_or_try_init(|| MprisServer::start(prima()?.clone())) {
+            eprintln!("Unable to start MPRIS service: {}", e)
+        }
 
-    let _ = STORAGE_UTIL.clone();
+        // Busy port must not break the player itself
+        if let Err(e) = restart_http_api() {
+            eprintln!("Unable to start HTTP API: {}", e)
+        }
 
-    TOKIO_RUNTIME
-        .block_on(async move { STORAGE_UTIL.write().await.initialize_playlist(jvm).await });
+        if let Err(e) = restart_mpd() {
+            eprintln!("Unable to start MPD server: {}", e)
+        }
 
-    let _ = AUDIO_PLAYER.clone();
+        restart_scrobbler()?;
 
-    let mut db_connection = establish_connection().unwrap();
+        let mut podcast_refresher = PODCAST_REFRESHER.lock().unwrap();
 
-    diesel::sql_query(
-        r#"CREATE TABLE IF NOT EXISTS favourite_tracks (
-  title TEXT,
-  artist TEXT,
-  album TEXT,
-  path TEXT PRIMARY KEY NOT NULL,
-  duration BIGINT NOT NULL,
-  add_date BIGINT NOT NULL,
-  number_in_album INTEGER NOT NULL
-);"#,
-    )
-    .execute(&mut db_connection)
-    .unwrap_or_default();
+        if podcast_refresher.is_none() {
+            match PodcastRefresher::start(prima()?.clone()) {
+                Ok(refresher) => *podcast_refresher = Some(refresher),
+                Err(e) => eprintln!("Unable to start podcast refresher: {}", e),
+            }
+        }
 
-    diesel::sql_query(
-        "CREATE TABLE IF NOT EXISTS favourite_artists (name TEXT PRIMARY KEY NOT NULL)",
-    )
-    .execute(&mut db_connection)
-    .unwrap_or_default();
+        Ok(())
+    })
+}
 
-    diesel::sql_query(
-        r#"CREATE TABLE IF NOT EXISTS favourite_playlists (
-  id INTEGER PRIMARY KEY,
-  title TEXT,
-  tp INTEGER NOT NULL
-)"#,
-    )
-    .execute(&mut db_connection)
-    .unwrap_or_default();
+/// Saves current playback position
+/// and writes all unsaved state to the disk.
+/// Should be called before JVM exits
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_shutdownBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) {
+    catch_jni_call(env, |_| match PRIMA.get() {
+        None => Ok(()),
+        Some(prima) => prima.shutdown(),
+    })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_hello(
-    mut env: JNIEnv,
+    env: JNIEnv,
     _class: jclass,
     name: JString,
 ) -> jstring {
-    let name = unsafe { String::from_jstring_unchecked(&mut env, &name) };
-
-    env.new_string(format!("Hello, {}!", name))
-        .unwrap()
-        .into_raw()
+    catch_jni_call(env, |mut env| {
+        let name = unsafe { String::from_jstring_unchecked(&mut env, &name) };
+        Ok(env.new_string(format!("Hello, {}!", name))?.into_raw())
+    })
 }
 
-#[inline]
-fn ajvm(env: &JNIEnv) -> AJVM {
-    Arc::new(env.get_java_vm().unwrap())
+/// Registers listener that receives player's events
+/// on the dedicated thread. Null listener stops delivery
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setPlayerEventListener(
+    env: JNIEnv,
+    _class: JClass,
+    listener: JObject,
+) {
+    catch_jni_call(env, |mut env| {
+        JVM_EVENT_LISTENER.set_listener(&mut env, &listener, prima()?.get_event_bus())
+    })
 }
 
 #[no_mangle]
@@ -156,17 +382,12 @@ pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getAllTracksBlockin
     env: JNIEnv,
     _class: JClass,
 ) -> jobjectArray {
-    let env = Rc::new(RefCell::new(env));
-    let jvm = { ajvm(&*env.borrow()) };
-
-    TOKIO_RUNTIME.block_on(async move {
-        AudioScanner::get_all_tracks(jvm, TOKIO_RUNTIME.clone(), STORAGE_UTIL.clone())
-            .await
-            .lock()
-            .await
+    catch_jni_call(env, |env| {
+        Ok(prima()?
+            .get_all_tracks()?
             .iter()
-            .into_jobject_array(env)
-            .into_raw()
+            .into_jobject_array(Rc::new(RefCell::new(env)))
+            .into_raw())
     })
 }
 
@@ -176,17 +397,11 @@ pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getCurTrackBlocking
     env: JNIEnv,
     _class: JClass,
 ) -> jobject {
-    let env = Rc::new(RefCell::new(env));
-
-    TOKIO_RUNTIME.block_on(async move {
-        let storage_util = STORAGE_UTIL.read().await;
-        let playlist = storage_util.load_current_playlist();
-        let cur_track = playlist.get_cur_track();
-
-        match cur_track {
+    catch_jni_call(env, |env| {
+        Ok(match prima()?.get_cur_track() {
             None => std::ptr::null_mut(),
-            Some(track) => track.to_java_track(env).into_raw(),
-        }
+            Some(track) => track.to_java_track(Rc::new(RefCell::new(env))).into_raw(),
+        })
     })
 }
 
@@ -207,175 +422,26 @@ pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_calcTrackTime(
     _class: JClass,
     mut millis: jint,
 ) -> jintArray {
-    let time = env.new_int_array(3).unwrap();
-
-    let h = millis / 3600000;
-    millis -= h * 3600000;
-
-    let m = millis / 60000;
-    millis -= m * 60000;
-
-    let s = millis / 1000;
-
-    let arr = [h, m, s];
-
-    env.set_int_array_region(&time, 0, arr.as_slice()).unwrap();
-    time.into_raw()
-}
-
-#[inline]
-fn get_path_and_duration_of_playlist_track(
-    playlist: &DefaultPlaylist<DefaultTrack>,
-) -> (PathBuf, Duration) {
-    let cur_track = playlist.get_cur_track().unwrap();
-
-    (
-        cur_track.get_path().clone(),
-        cur_track.get_duration().to_std().unwrap(),
-    )
-}
-
-#[inline]
-async fn get_path_and_duration_of_cur_track() -> (PathBuf, Duration) {
-    let storage_util = STORAGE_UTIL.read().await;
-    let playlist = storage_util.load_current_playlist();
-    get_path_and_duration_of_playlist_track(playlist)
-}
-
-#[inline]
-async fn get_duration_of_cur_track() -> Duration {
-    STORAGE_UTIL
-        .read()
-        .await
-        .load_current_playlist()
-        .get_cur_track()
-        .unwrap()
-        .get_duration()
-        .to_std()
-        .unwrap()
-}
-
-#[inline]
-async fn has_cur_track() -> bool {
-    STORAGE_UTIL
-        .read()
-        .await
-        .load_current_playlist()
-        .get_cur_track()
-        .is_some()
-}
-
-#[inline]
-async fn set_cur_playlist(playlist: DefaultPlaylist<DefaultTrack>) {
-    STORAGE_UTIL
-        .write()
-        .await
-        .store_current_playlist(playlist)
-        .await
-        .unwrap_or_default()
-}
-
-#[inline]
-async fn is_prev_track_equals_cur_track(cur_track: &DefaultTrack) -> bool {
-    let storage_util = STORAGE_UTIL.read().await;
-    let playlist = storage_util.load_current_playlist();
-    let prev_track = playlist.get_cur_track();
-    prev_track.map(|prev| *prev == *cur_track).unwrap_or(false)
-}
-
-#[inline]
-async fn play_pause_cur_track(playlist: Option<DefaultPlaylist<DefaultTrack>>) {
-    let cur_track = playlist.as_ref().map(|p| p.get_cur_track()).flatten();
-    let is_playing = AUDIO_PLAYER.read().await.is_playing();
-
-    if is_playing {
-        println!("Prepare to stop");
-
-        AudioPlayer::stop(
-            AUDIO_PLAYER.clone(),
-            TOKIO_RUNTIME.clone(),
-            STORAGE_UTIL.clone(),
-        )
-        .await;
-
-        if playlist.is_none() {
-            println!("Prepare to pause 1");
-            pause().await;
-            return;
-        }
-
-        if is_prev_track_equals_cur_track(cur_track.unwrap()).await {
-            println!("Prepare to pause 2");
-            set_cur_playlist(playlist.unwrap()).await;
-            pause().await;
-        } else {
-            println!("Prepare to play 1");
-            store_and_play_playlist(playlist.unwrap()).await;
-        }
-
-        return;
-    }
-
-    if !has_cur_track().await {
-        println!("Prepare to play 2");
-        store_and_play_playlist(playlist.unwrap()).await;
-        return;
-    }
+    catch_jni_call(env, |env| {
+        let time = env.new_int_array(3)?;
 
-    if playlist.is_none() {
-        println!("Prepare to resume 1");
-        resume().await;
-        return;
-    }
-
-    if is_prev_track_equals_cur_track(cur_track.unwrap()).await {
-        println!("Prepare to resume 2");
-        set_cur_playlist(playlist.unwrap()).await;
-        resume().await
-    } else {
-        println!("Prepare to play 3");
-        store_and_play_playlist(playlist.unwrap()).await
-    }
-}
+        let h = millis / 3600000;
+        millis -= h * 3600000;
 
-#[inline]
-async fn pause() {
-    AudioPlayer::pause(
-        AUDIO_PLAYER.clone(),
-        TOKIO_RUNTIME.clone(),
-        STORAGE_UTIL.clone(),
-    )
-    .await;
-}
-
-#[inline]
-async fn resume() {
-    let (_, track_duration) = get_path_and_duration_of_cur_track().await;
+        let m = millis / 60000;
+        millis -= m * 60000;
 
-    AudioPlayer::resume(
-        AUDIO_PLAYER.clone(),
-        TOKIO_RUNTIME.clone(),
-        STORAGE_UTIL.clone(),
-        track_duration,
-    )
-    .await
-}
+        let s = millis / 1000;
 
-#[inline]
-async fn store_and_play_playlist(playlist: DefaultPlaylist<DefaultTrack>) {
-    let (path, track_duration) = get_path_and_duration_of_playlist_track(&playlist);
-    set_cur_playlist(playlist).await;
+        let arr = [h, m, s];
 
-    AudioPlayer::play(
-        AUDIO_PLAYER.clone(),
-        TOKIO_RUNTIME.clone(),
-        STORAGE_UTIL.clone(),
-        path,
-        track_duration,
-    )
-    .await
+        env.set_int_array_region(&time, 0, arr.as_slice())?;
+        Ok(time.into_raw())
+    })
 }
 
+/// # Safety
+/// Extern JNI junction
 #[no_mangle]
 #[allow(non_snake_case)]
 pub unsafe extern "system" fn Java_com_paranid5_prima_rust_RustLibs_onTrackClickedBlocking(
@@ -384,244 +450,164 @@ pub unsafe extern "system" fn Java_com_paranid5_prima_rust_RustLibs_onTrackClick
     tracks: JObject,
     track_index: jint,
 ) {
-    let env = Rc::new(RefCell::new(env));
-    let playlist = JList::from_env(&mut *env.borrow_mut(), &tracks).unwrap();
-
-    let playlist = DefaultPlaylist::new(
-        None,
-        PlaylistType::default(),
-        playlist.map(env.clone(), |jtrack, env| {
-            DefaultTrack::from_env(env, jtrack)
-        }),
-        track_index as usize,
-    );
-
-    TOKIO_RUNTIME.block_on(async move { play_pause_cur_track(Some(playlist)).await })
+    catch_jni_call(env, |env| {
+        let env = Rc::new(RefCell::new(env));
+        let playlist = JList::from_env(&mut env.borrow_mut(), &tracks)?;
+
+        let playlist = DefaultPlaylist::new(
+            None,
+            PlaylistType::default(),
+            playlist.map(env.clone(), |jtrack, env| {
+                DefaultTrack::from_env(env, jtrack)
+            }),
+            track_index as usize,
+        );
+
+        prima()?.play_pause_playlist(playlist)
+    })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_onPlayButtonClickedBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) {
-    TOKIO_RUNTIME.block_on(async move {
-        if has_cur_track().await {
-            play_pause_cur_track(None).await
-        }
-    })
+    catch_jni_call(env, |_| prima()?.play_pause())
 }
 
+/// # Safety
+/// Extern JNI junction
 #[no_mangle]
 #[allow(non_snake_case)]
 pub unsafe extern "system" fn Java_com_paranid5_prima_rust_RustLibs_onNextTrackClickedBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) {
-    TOKIO_RUNTIME.block_on(async move {
-        let mut playlist = { STORAGE_UTIL.read().await.load_current_playlist().clone() };
-        let cur_track = playlist.get_cur_track();
-
-        if cur_track.is_some() {
-            playlist.skip_to_next();
-            play_pause_cur_track(Some(playlist)).await
-        }
-    })
+    catch_jni_call(env, |_| prima()?.next_track())
 }
 
+/// # Safety
+/// Extern JNI junction
 #[no_mangle]
 #[allow(non_snake_case)]
 pub unsafe extern "system" fn Java_com_paranid5_prima_rust_RustLibs_onPreviousTrackClickedBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) {
-    TOKIO_RUNTIME.block_on(async move {
-        let mut playlist = STORAGE_UTIL.read().await.load_current_playlist().clone();
-        let cur_track = playlist.get_cur_track();
-
-        if cur_track.is_some() {
-            playlist.skip_to_prev();
-            play_pause_cur_track(Some(playlist)).await
-        }
-    })
+    catch_jni_call(env, |_| prima()?.previous_track())
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getCurTrackIndexBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) -> jsize {
-    TOKIO_RUNTIME.block_on(async move {
-        STORAGE_UTIL
-            .read()
-            .await
-            .load_current_playlist()
-            .get_cur_ind() as jsize
-    })
+    catch_jni_call(env, |_| Ok(prima()?.get_cur_track_index() as jsize))
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getPlaybackPositionBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) -> jlong {
-    TOKIO_RUNTIME
-        .block_on(async { STORAGE_UTIL.read().await.load_current_playback_position() as jlong })
+    catch_jni_call(env, |_| {
+        Ok(prima()?.get_playback_position().as_millis() as jlong)
+    })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_seekToBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     millis: jlong,
 ) {
-    TOKIO_RUNTIME.block_on(async move {
-        let cur_track = {
-            let storage_util = STORAGE_UTIL.read().await;
-            let playlist = storage_util.load_current_playlist();
-            playlist.get_cur_track().map(|track| track.clone())
-        };
-
-        let is_playing = AUDIO_PLAYER.read().await.is_playing();
-
-        if is_playing {
-            AudioPlayer::stop(
-                AUDIO_PLAYER.clone(),
-                TOKIO_RUNTIME.clone(),
-                STORAGE_UTIL.clone(),
-            )
-            .await;
-        }
-
-        if let Some(cur_track) = cur_track {
-            AudioPlayer::seek_to(
-                AUDIO_PLAYER.clone(),
-                TOKIO_RUNTIME.clone(),
-                STORAGE_UTIL.clone(),
-                Duration::from_millis(millis as u64),
-                cur_track.get_duration().to_std().unwrap(),
-            )
-            .await
-        }
+    catch_jni_call(env, |_| {
+        prima()?.seek_to(Duration::from_millis(millis as u64))
     })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_isPlaying(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) -> jboolean {
-    jboolean::from(TOKIO_RUNTIME.block_on(async { AUDIO_PLAYER.read().await.is_playing() }))
+    catch_jni_call(env, |_| Ok(jboolean::from(prima()?.is_playing())))
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_replayCurTrackBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) {
-    TOKIO_RUNTIME.block_on(async move {
-        let (path, duration) = get_path_and_duration_of_cur_track().await;
-
-        AudioPlayer::play(
-            AUDIO_PLAYER.clone(),
-            TOKIO_RUNTIME.clone(),
-            STORAGE_UTIL.clone(),
-            path,
-            duration,
-        )
-        .await
-    })
+    catch_jni_call(env, |_| prima()?.replay_cur_track())
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setNextLoopingStateBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) -> jint {
-    TOKIO_RUNTIME.block_on(async {
-        AudioPlayer::set_next_looping_state(AUDIO_PLAYER.clone()).await;
-        let state = AUDIO_PLAYER.read().await.get_looping_state();
-
-        STORAGE_UTIL
-            .write()
-            .await
-            .store_looping_state(state)
-            .await
-            .unwrap_or_default();
-
-        state.into()
-    })
+    catch_jni_call(env, |_| Ok(prima()?.set_next_looping_state().into()))
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setVolumeBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     volume: jfloat,
 ) {
-    TOKIO_RUNTIME.block_on(async {
-        STORAGE_UTIL
-            .write()
-            .await
-            .store_volume(volume)
-            .await
-            .unwrap_or_default();
-
-        AudioPlayer::set_volume(AUDIO_PLAYER.clone(), volume as f32).await
+    catch_jni_call(env, |_| {
+        prima()?.set_volume(volume);
+        Ok(())
     })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setSpeedBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     speed: jfloat,
 ) {
-    TOKIO_RUNTIME.block_on(async {
-        STORAGE_UTIL
-            .write()
-            .await
-            .store_speed(speed)
-            .await
-            .unwrap_or_default();
-
-        AudioPlayer::set_speed(AUDIO_PLAYER.clone(), speed as f32).await
+    catch_jni_call(env, |_| {
+        prima()?.set_speed(speed);
+        Ok(())
     })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getVolumeBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) -> jfloat {
-    TOKIO_RUNTIME.block_on(async { AUDIO_PLAYER.write().await.get_volume() as jfloat })
+    catch_jni_call(env, |_| Ok(prima()?.get_volume() as jfloat))
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getSpeedBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) -> jfloat {
-    TOKIO_RUNTIME.block_on(async { AUDIO_PLAYER.write().await.get_speed() as jfloat })
+    catch_jni_call(env, |_| Ok(prima()?.get_speed() as jfloat))
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getLoopingStateBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) -> jint {
-    TOKIO_RUNTIME.block_on(async { AUDIO_PLAYER.write().await.get_looping_state().into() })
+    catch_jni_call(env, |_| Ok(prima()?.get_looping_state().into()))
 }
 
 #[no_mangle]
@@ -630,186 +616,1602 @@ pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getTrackOrderBlocki
     env: JNIEnv,
     _class: JClass,
 ) -> jintArray {
-    let ord = TOKIO_RUNTIME.block_on(async {
-        let order = STORAGE_UTIL.read().await.load_track_order();
-        (order.comparator, order.order)
-    });
+    catch_jni_call(env, |env| {
+        let track_order = prima()?.get_track_order();
 
-    let arr = env.new_int_array(2).unwrap();
-    let order: jint = ord.1.into();
+        let arr = env.new_int_array(2)?;
 
-    env.set_int_array_region(&arr, 0, &[ord.0.into(), order + 5])
-        .unwrap();
-
-    arr.into_raw()
+        env.set_int_array_region(
+            &arr,
+            0,
+            &[track_order.comparator.into(), track_order.order.into()],
+        )?;
+        Ok(arr.into_raw())
+    })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setTrackOrderBlocking(
-    _env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     comparator: jint,
     order: jint,
 ) {
-    let order = TrackOrder::new(Comparator::from(comparator), Ord::from(order - 5));
+    catch_jni_call(env, |_| {
+        prima()?.set_track_order(TrackOrder::new(
+            Comparator::try_from(comparator)?,
+            Ord::try_from(order)?,
+        ));
 
-    TOKIO_RUNTIME.block_on(async move {
-        STORAGE_UTIL
-            .write()
-            .await
-            .store_track_order(order)
-            .await
-            .unwrap_or_default()
-    });
+        Ok(())
+    })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setMusicSearchPathBlocking(
-    mut env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     path: JString,
 ) {
-    let path = unsafe { String::from_jstring_unchecked(&mut env, &path) };
+    catch_jni_call(env, |mut env| {
+        let path = unsafe { String::from_jstring_unchecked(&mut env, &path) };
+        prima()?.set_music_search_path(PathBuf::from(path));
+        Ok(())
+    })
+}
+
+/// Renders tracks with current speed, reverb and fade-in
+/// into a single WAV or FLAC file (chosen by output's extension)
+///
+/// # Return
+/// true if file was written, false if export was cancelled
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_exportTracksBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    tracks: JObjectArray,
+    output: JString,
+) -> jboolean {
+    catch_jni_call(env, |mut env| {
+        let output = PathBuf::from(String::from(env.get_string(&output)?));
+        let tracks_len = env.get_array_length(&tracks)?;
+        let mut paths = Vec::with_capacity(tracks_len as usize);
+
+        for ind in 0..tracks_len {
+            let path = JString::from(env.get_object_array_element(&tracks, ind)?);
+            paths.push(PathBuf::from(String::from(env.get_string(&path)?)));
+        }
 
-    TOKIO_RUNTIME.block_on(async {
-        STORAGE_UTIL
-            .write()
-            .await
-            .store_music_search_path(PathBuf::from(path))
-            .await
-            .unwrap_or_default()
+        Ok(jboolean::from(prima()?.export_tracks(&paths, &output)?))
     })
 }
 
+/// Progress of the running export in range [0, 1] or -1 if there is no export
 #[no_mangle]
 #[allow(non_snake_case)]
-pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_storeCurPlaybackPosBlocking(
-    _env: JNIEnv,
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getExportProgress(
+    env: JNIEnv,
+    _class: JClass,
+) -> jfloat {
+    catch_jni_call(env, |_| Ok(prima()?.get_export_progress().unwrap_or(-1.0)))
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_cancelExport(
+    env: JNIEnv,
     _class: JClass,
 ) {
-    TOKIO_RUNTIME.block_on(async {
-        AudioPlayer::save_cur_playback_pos_async(
-            AUDIO_PLAYER.clone(),
-            TOKIO_RUNTIME.clone(),
-            STORAGE_UTIL.clone(),
-        )
-        .await
-        .await
-        .unwrap_or_default()
+    catch_jni_call(env, |_| {
+        prima()?.cancel_export();
+        Ok(())
     })
 }
 
+/// Peaks of the track for the waveform seekbar.
+/// Computed on the first request, then read from the cache
+///
+/// # Return
+/// jbyteArray[min 0, max 0, min 1, max 1, ...] with `resolution` pairs
 #[no_mangle]
 #[allow(non_snake_case)]
-pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_onLikeTrackClicked(
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getWaveformBlocking(
     env: JNIEnv,
     _class: JClass,
-    track: JObject,
-) {
-    let env = Rc::new(RefCell::new(env));
-    let track = DefaultTrack::from_env(env, track).into_favourable();
-    let mut connection = establish_connection().unwrap();
-
-    if FavouriteTrackDao::get_by_key(track.get_key().clone(), &mut connection).is_some() {
-        FavouriteTrackDao::remove(vec![track], &mut connection)
-    } else {
-        FavouriteTrackDao::insert(vec![track], &mut connection)
-    }
+    path: JString,
+    resolution: jint,
+) -> jbyteArray {
+    catch_jni_call(env, |mut env| {
+        let path = PathBuf::from(String::from(env.get_string(&path)?));
+        let resolution = usize::try_from(resolution)
+            .map_err(|_| Error::Config("Waveform resolution must be positive".to_string()))?;
+
+        let waveform = prima()?.get_waveform(&path, resolution)?;
+        let peaks = waveform.peaks.iter().map(|&p| p as u8).collect::<Vec<_>>();
+        Ok(env.byte_array_from_slice(&peaks)?.into_raw())
+    })
 }
 
+/// Analyses tempo, key and loudness of new and modified library tracks.
+/// Cancelled pass continues from the same place next time
+///
+/// # Return
+/// true if all tracks were analysed, false if the pass was cancelled
 #[no_mangle]
 #[allow(non_snake_case)]
-pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_isTrackLiked(
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_analyseLibraryBlocking(
     env: JNIEnv,
     _class: JClass,
-    track: JObject,
 ) -> jboolean {
-    let env = Rc::new(RefCell::new(env));
-    let track = DefaultTrack::from_env(env, track);
-    let mut connection = establish_connection().unwrap();
-
-    jboolean::from(
-        FavouriteTrackDao::get_by_key(track.get_path().clone(), &mut connection).is_some(),
-    )
+    catch_jni_call(env, |_| Ok(jboolean::from(prima()?.analyse_library()?)))
 }
 
+/// Progress of the running library analysis in range [0, 1] or -1 if there is no analysis
 #[no_mangle]
 #[allow(non_snake_case)]
-pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getCurPlaylistBlocking(
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getLibraryAnalysisProgress(
     env: JNIEnv,
     _class: JClass,
-) -> jobjectArray {
-    let env = Rc::new(RefCell::new(env));
-
-    TOKIO_RUNTIME.block_on(async move {
-        STORAGE_UTIL
-            .read()
-            .await
-            .load_current_playlist()
-            .clone()
-            .into_jobject_array(env)
-            .into_raw()
+) -> jfloat {
+    catch_jni_call(env, |_| {
+        Ok(prima()?.get_library_analysis_progress().unwrap_or(-1.0))
     })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
-pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_updateAndStoreCurPlaylistBlocking(
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_cancelLibraryAnalysis(
     env: JNIEnv,
     _class: JClass,
-    cur_playlist: JObject,
 ) {
-    let env = Rc::new(RefCell::new(env));
-    let new_playlist = JList::from_env(&mut *env.borrow_mut(), &cur_playlist).unwrap();
-
-    let new_playlist = new_playlist.map(env.clone(), |jtrack, env| {
-        DefaultTrack::from_env(env, jtrack)
-    });
-
-    TOKIO_RUNTIME.block_on(async move {
-        let cur_track = {
-            let storage_util = STORAGE_UTIL.read().await;
-            let playlist = storage_util.load_current_playlist();
-            playlist.get_cur_track().unwrap().clone()
-        };
+    catch_jni_call(env, |_| {
+        prima()?.cancel_library_analysis();
+        Ok(())
+    })
+}
 
-        let new_cur_ind = new_playlist
-            .iter()
-            .position(|track| *track == cur_track)
-            .unwrap();
+/// Stored analysis of the track
+///
+/// # Return
+/// jfloatArray[bpm, key, energy, loudness range] where unknown bpm and key are -1,
+/// or null if the track was not analysed yet
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getTrackAnalysisBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    path: JString,
+) -> jfloatArray {
+    catch_jni_call(env, |mut env| {
+        let path = PathBuf::from(String::from(env.get_string(&path)?));
+
+        let Some(analysis) = prima()?.get_track_analysis(&path)? else {
+            return Ok(std::ptr::null_mut());
+        };
 
-        STORAGE_UTIL
-            .write()
-            .await
-            .store_current_playlist(DefaultPlaylist::new(
-                None,
-                PlaylistType::default(),
-                new_playlist,
-                new_cur_ind,
-            ))
-            .await
-            .unwrap_or_default();
-    });
+        let values = [
+            analysis.bpm.unwrap_or(-1.0),
+            analysis
+                .key
+                .map(|key| key.get_index() as jfloat)
+                .unwrap_or(-1.0),
+            analysis.energy,
+            analysis.loudness_range,
+        ];
+
+        let array = env.new_float_array(values.len() as jsize)?;
+        env.set_float_array_region(&array, 0, &values)?;
+        Ok(array.into_raw())
+    })
 }
 
+/// Library tracks which analysis matches all given conditions.
+/// Negative bounds and key are not checked
 #[no_mangle]
 #[allow(non_snake_case)]
-pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getFavouriteTracks(
+#[allow(clippy::too_many_arguments)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getTracksByAnalysisBlocking(
     env: JNIEnv,
     _class: JClass,
+    min_bpm: jfloat,
+    max_bpm: jfloat,
+    key: jint,
+    is_harmonic: jboolean,
+    min_energy: jfloat,
+    max_energy: jfloat,
 ) -> jobjectArray {
-    let mut connection = establish_connection().unwrap();
-    let tracks: Vec<FavouriteTrack> = FavouriteTrackDao::get_all(&mut connection);
+    catch_jni_call(env, |env| {
+        let range = |min: jfloat, max: jfloat| {
+            (min >= 0.0 || max >= 0.0).then(|| {
+                let max = if max >= 0.0 { max } else { f32::INFINITY };
+                min.max(0.0)..=max
+            })
+        };
 
-    tracks
-        .into_iter()
-        .into_jobject_array(Rc::new(RefCell::new(env)))
-        .into_raw()
+        let key = match u8::try_from(key) {
+            Err(_) => None,
+            Ok(index) => Some(
+                MusicalKey::from_index(index)
+                    .ok_or_else(|| Error::Config("Key must be in range 0..24".to_string()))?,
+            ),
+        };
+
+        let filter = AnalysisFilter {
+            bpm: range(min_bpm, max_bpm),
+            key,
+            is_harmonic: is_harmonic != JNI_FALSE,
+            energy: range(min_energy, max_energy),
+        };
+
+        Ok(prima()?
+            .get_tracks_by_analysis(&filter)?
+            .iter()
+            .into_jobject_array(Rc::new(RefCell::new(env)))
+            .into_raw())
+    })
 }
 
-/// Converts artist name to the next pattern:
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_isAutoDjEnabledBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jboolean {
+    catch_jni_call(env, |_| {
+        Ok(jboolean::from(prima()?.get_auto_dj_settings().is_enabled))
+    })
+}
+
+/// Turns Auto-DJ on or off. When the playlist is about to end
+/// without looping, similar tracks are appended to it
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setAutoDjEnabledBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    is_enabled: jboolean,
+) {
+    catch_jni_call(env, |_| {
+        prima()?.set_auto_dj_enabled(is_enabled != JNI_FALSE);
+        Ok(())
+    })
+}
+
+/// Path of the track that Auto-DJ picks are similar to,
+/// null if it's seeded with an artist or not seeded
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getAutoDjSeedTrackBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_jni_call(env, |env| {
+        Ok(match prima()?.get_auto_dj_settings().seed {
+            Some(AutoDjSeed::Track(path)) => env
+                .new_string(path.to_string_lossy().to_string())?
+                .into_raw(),
+            _ => std::ptr::null_mut(),
+        })
+    })
+}
+
+/// Seeds Auto-DJ with the track, null removes the seed
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setAutoDjSeedTrackBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    path: JString,
+) {
+    catch_jni_call(env, |mut env| {
+        let seed = match path.is_null() {
+            true => None,
+            false => Some(AutoDjSeed::Track(PathBuf::from(String::from(
+                env.get_string(&path)?,
+            )))),
+        };
+
+        prima()?.set_auto_dj_seed(seed);
+        Ok(())
+    })
+}
+
+/// Artist whose music Auto-DJ picks,
+/// null if it's seeded with a track or not seeded
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getAutoDjSeedArtistBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_jni_call(env, |env| {
+        Ok(match prima()?.get_auto_dj_settings().seed {
+            Some(AutoDjSeed::Artist(artist)) => env.new_string(artist)?.into_raw(),
+            _ => std::ptr::null_mut(),
+        })
+    })
+}
+
+/// Seeds Auto-DJ with the artist, null removes the seed
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setAutoDjSeedArtistBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    artist: JString,
+) {
+    catch_jni_call(env, |mut env| {
+        let seed = match artist.is_null() {
+            true => None,
+            false => Some(AutoDjSeed::Artist(String::from(env.get_string(&artist)?))),
+        };
+
+        prima()?.set_auto_dj_seed(seed);
+        Ok(())
+    })
+}
+
+/// Starts guess-the-melody game over the library
+///
+/// # Arguments
+/// difficulty - 0 easy, 1 normal, 2 hard
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_startGtmGameBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    difficulty: jint,
+    rounds: jint,
+) {
+    catch_jni_call(env, |_| {
+        let difficulty = GtmDifficulty::from_code(difficulty)
+            .ok_or_else(|| Error::Config(format!("Unknown difficulty {}", difficulty)))?;
+
+        let rounds = usize::try_from(rounds)
+            .map_err(|_| Error::Config("Number of rounds can't be negative".to_string()))?;
+
+        prima()?.start_gtm_game(difficulty, rounds)
+    })
+}
+
+/// Picks the next round of the game
+///
+/// # Return
+/// Answers to choose from, one of them is the played track
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_nextGtmRoundBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jobjectArray {
+    catch_jni_call(env, |env| {
+        Ok(prima()?
+            .next_gtm_round()?
+            .answers
+            .iter()
+            .into_jobject_array(Rc::new(RefCell::new(env)))
+            .into_raw())
+    })
+}
+
+/// Plays clip of the current round, returns when it ends or is stopped
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_playGtmClipBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    is_fading: jboolean,
+) {
+    catch_jni_call(env, |_| prima()?.play_gtm_clip(is_fading != JNI_FALSE))
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_stopGtmClip(
+    env: JNIEnv,
+    _class: JClass,
+) {
+    catch_jni_call(env, |_| {
+        prima()?.stop_gtm_clip();
+        Ok(())
+    })
+}
+
+/// Answers the current round
+///
+/// # Return
+/// jintArray[is correct (0 or 1), index of the correct answer, points, answer time in millis]
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_answerGtmRound(
+    env: JNIEnv,
+    _class: JClass,
+    choice: jint,
+) -> jintArray {
+    catch_jni_call(env, |env| {
+        let choice = usize::try_from(choice)
+            .map_err(|_| Error::Config("Answer can't be negative".to_string()))?;
+
+        let result = prima()?.answer_gtm_round(choice)?;
+
+        let arr = env.new_int_array(4)?;
+
+        env.set_int_array_region(
+            &arr,
+            0,
+            &[
+                result.is_correct as jint,
+                result.correct_answer as jint,
+                result.points as jint,
+                result.answer_time.as_millis() as jint,
+            ],
+        )?;
+
+        Ok(arr.into_raw())
+    })
+}
+
+/// # Return
+/// jintArray[score, answered rounds, all rounds] or null if there is no game
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getGtmScore(
+    env: JNIEnv,
+    _class: JClass,
+) -> jintArray {
+    catch_jni_call(env, |env| {
+        let Some((score, answered, rounds)) = prima()?.get_gtm_score() else {
+            return Ok(std::ptr::null_mut());
+        };
+
+        let arr = env.new_int_array(3)?;
+        env.set_int_array_region(&arr, 0, &[score as jint, answered as jint, rounds as jint])?;
+        Ok(arr.into_raw())
+    })
+}
+
+/// Ends the game without storing its score
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_quitGtmGame(
+    env: JNIEnv,
+    _class: JClass,
+) {
+    catch_jni_call(env, |_| {
+        prima()?.quit_gtm_game();
+        Ok(())
+    })
+}
+
+/// Best results of the finished games, the highest first
+///
+/// # Arguments
+/// difficulty - 0 easy, 1 normal, 2 hard, negative for all difficulties
+///
+/// # Return
+/// jlongArray[score, difficulty, rounds, correct answers, unix time in seconds, ...]
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getGtmHighScoresBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    difficulty: jint,
+    limit: jint,
+) -> jlongArray {
+    catch_jni_call(env, |env| {
+        let difficulty = match difficulty < 0 {
+            true => None,
+            false => Some(
+                GtmDifficulty::from_code(difficulty)
+                    .ok_or_else(|| Error::Config(format!("Unknown difficulty {}", difficulty)))?,
+            ),
+        };
+
+        let values = prima()?
+            .get_gtm_high_scores(difficulty, limit.max(0) as usize)?
+            .into_iter()
+            .flat_map(|high_score| {
+                [
+                    high_score.score as jlong,
+                    high_score.difficulty.get_code() as jlong,
+                    high_score.rounds as jlong,
+                    high_score.correct_answers as jlong,
+                    high_score.played_at,
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let array = env.new_long_array(values.len() as jsize)?;
+        env.set_long_array_region(&array, 0, &values)?;
+        Ok(array.into_raw())
+    })
+}
+
+/// Latest levels and spectrum of the played samples for the visualizer.
+/// Never blocks the audio thread
+///
+/// # Return
+/// jfloatArray[channels, rms 0, ..., peak 0, ..., band 0, ...]
+/// or null if nothing is playing
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getMeterFrame(
+    env: JNIEnv,
+    _class: JClass,
+) -> jfloatArray {
+    catch_jni_call(env, |env| {
+        let Some(frame) = prima()?.get_meter_frame() else {
+            return Ok(std::ptr::null_mut());
+        };
+
+        let values = std::iter::once(frame.rms.len() as jfloat)
+            .chain(frame.rms)
+            .chain(frame.peaks)
+            .chain(frame.bands)
+            .collect::<Vec<_>>();
+
+        let array = env.new_float_array(values.len() as jsize)?;
+        env.set_float_array_region(&array, 0, &values)?;
+        Ok(array.into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getSpectrumBands(
+    env: JNIEnv,
+    _class: JClass,
+) -> jint {
+    catch_jni_call(env, |_| Ok(prima()?.get_spectrum_bands() as jint))
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setSpectrumBands(
+    env: JNIEnv,
+    _class: JClass,
+    bands: jint,
+) {
+    catch_jni_call(env, |_| {
+        let bands = usize::try_from(bands)
+            .map_err(|_| Error::Config("Number of spectrum bands must be positive".to_string()))?;
+
+        prima()?.set_spectrum_bands(bands)
+    })
+}
+
+/// Names of all connected audio output devices
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getOutputDevicesBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jobjectArray {
+    catch_jni_call(env, |mut env| {
+        let devices = output_device_names();
+
+        let arr =
+            env.new_object_array(devices.len() as jsize, "java/lang/String", JObject::null())?;
+
+        for (ind, device) in devices.into_iter().enumerate() {
+            let device = env.new_string(device)?;
+            env.set_object_array_element(&arr, ind as jsize, device)?;
+        }
+
+        Ok(arr.into_raw())
+    })
+}
+
+/// Name of the chosen output device or null if system's default is used
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getOutputDeviceBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_jni_call(env, |env| {
+        Ok(match prima()?.get_output_device() {
+            None => std::ptr::null_mut(),
+            Some(device) => env.new_string(device)?.into_raw(),
+        })
+    })
+}
+
+/// Chooses output device and stores the choice.
+/// Null device means system's default one
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setOutputDeviceBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    device: JString,
+) {
+    catch_jni_call(env, |mut env| {
+        let device = match device.is_null() {
+            true => None,
+            false => Some(String::from(env.get_string(&device)?)),
+        };
+
+        prima()?.set_output_device(device);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_isHttpApiEnabledBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jboolean {
+    catch_jni_call(env, |_| Ok(jboolean::from(prima()?.is_http_api_enabled())))
+}
+
+/// Stores the setting and starts or stops remote-control API
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setHttpApiEnabledBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    is_enabled: jboolean,
+) {
+    catch_jni_call(env, |_| {
+        prima()?.set_http_api_enabled(is_enabled != JNI_FALSE);
+        restart_http_api()
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_isHttpApiLanEnabledBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jboolean {
+    catch_jni_call(env, |_| {
+        Ok(jboolean::from(prima()?.is_http_api_lan_enabled()))
+    })
+}
+
+/// Stores whether other devices may connect
+/// and restarts remote-control API if it's running
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setHttpApiLanEnabledBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    is_enabled: jboolean,
+) {
+    catch_jni_call(env, |_| {
+        prima()?.set_http_api_lan_enabled(is_enabled != JNI_FALSE);
+        restart_http_api()
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getHttpApiPortBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jint {
+    catch_jni_call(env, |_| Ok(prima()?.get_http_api_port() as jint))
+}
+
+/// Stores the port and restarts remote-control API if it's running
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setHttpApiPortBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    port: jint,
+) {
+    catch_jni_call(env, |_| {
+        let port = u16::try_from(port)
+            .map_err(|_| Error::Config(format!("Invalid HTTP API port: {}", port)))?;
+
+        prima()?.set_http_api_port(port);
+        restart_http_api()
+    })
+}
+
+/// Token that clients of remote-control API must present
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getHttpApiTokenBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_jni_call(env, |env| {
+        Ok(env.new_string(prima()?.get_http_api_token()?)?.into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_isMpdEnabledBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jboolean {
+    catch_jni_call(env, |_| Ok(jboolean::from(prima()?.is_mpd_enabled())))
+}
+
+/// Stores the setting and starts or stops MPD server
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setMpdEnabledBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    is_enabled: jboolean,
+) {
+    catch_jni_call(env, |_| {
+        prima()?.set_mpd_enabled(is_enabled != JNI_FALSE);
+        restart_mpd()
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_isMpdLanEnabledBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jboolean {
+    catch_jni_call(env, |_| Ok(jboolean::from(prima()?.is_mpd_lan_enabled())))
+}
+
+/// Stores whether other devices may connect
+/// and restarts MPD server if it's running
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setMpdLanEnabledBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    is_enabled: jboolean,
+) {
+    catch_jni_call(env, |_| {
+        prima()?.set_mpd_lan_enabled(is_enabled != JNI_FALSE);
+        restart_mpd()
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getMpdPortBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jint {
+    catch_jni_call(env, |_| Ok(prima()?.get_mpd_port() as jint))
+}
+
+/// Stores the port and restarts MPD server if it's running
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setMpdPortBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    port: jint,
+) {
+    catch_jni_call(env, |_| {
+        let port = u16::try_from(port)
+            .map_err(|_| Error::Config(format!("Invalid MPD port: {}", port)))?;
+
+        prima()?.set_mpd_port(port);
+        restart_mpd()
+    })
+}
+
+/// Password that remote MPD clients must send
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getMpdPasswordBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_jni_call(env, |env| {
+        Ok(env.new_string(prima()?.get_mpd_password()?)?.into_raw())
+    })
+}
+
+/// Lyrics of the track as LRC (plain text if they are not synchronized)
+/// or null if the track has no lyrics
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getLyricsBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    track: JString,
+) -> jstring {
+    catch_jni_call(env, |mut env| {
+        let track = PathBuf::from(String::from(env.get_string(&track)?));
+
+        Ok(match prima()?.get_lyrics(&track) {
+            None => std::ptr::null_mut(),
+            Some(lyrics) => env.new_string(lyrics.to_lrc())?.into_raw(),
+        })
+    })
+}
+
+/// Index of the current track's lyrics line that is sung now,
+/// -1 if there is no such line or lyrics are not synchronized
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getActiveLyricsLineIndexBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jint {
+    catch_jni_call(env, |_| {
+        Ok(prima()?
+            .get_active_lyrics_line()
+            .map(|(index, _)| index as jint)
+            .unwrap_or(-1))
+    })
+}
+
+/// Saves edited LRC lyrics next to the track
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_saveLyricsBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    track: JString,
+    lrc: JString,
+) {
+    catch_jni_call(env, |mut env| {
+        let track = PathBuf::from(String::from(env.get_string(&track)?));
+        let lyrics = Lyrics::parse(&String::from(env.get_string(&lrc)?));
+        prima()?.save_lyrics(&track, lyrics)?;
+        Ok(())
+    })
+}
+
+/// Stores ListenBrainz account and restarts the scrobbler.
+/// Null token disables ListenBrainz, null URL means the official server
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setListenBrainzBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    token: JString,
+    api_url: JString,
+) {
+    catch_jni_call(env, |mut env| {
+        let mut settings = prima()?.get_scrobbling_settings();
+
+        settings.listenbrainz = match get_nullable_string(&mut env, &token)? {
+            None => None,
+
+            Some(token) => Some(ListenBrainzAccount {
+                api_url: get_nullable_string(&mut env, &api_url)?,
+                token,
+            }),
+        };
+
+        prima()?.set_scrobbling_settings(settings);
+        restart_scrobbler()
+    })
+}
+
+/// Stores Last.fm session and restarts the scrobbler.
+/// Null session key disables Last.fm, null URL means the official API
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setLastFmBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    api_key: JString,
+    api_secret: JString,
+    session_key: JString,
+    api_url: JString,
+) {
+    catch_jni_call(env, |mut env| {
+        let mut settings = prima()?.get_scrobbling_settings();
+
+        settings.lastfm = match get_nullable_string(&mut env, &session_key)? {
+            None => None,
+
+            Some(session_key) => Some(LastFmAccount {
+                api_url: get_nullable_string(&mut env, &api_url)?,
+                api_key: String::from(env.get_string(&api_key)?),
+                api_secret: String::from(env.get_string(&api_secret)?),
+                session_key,
+            }),
+        };
+
+        prima()?.set_scrobbling_settings(settings);
+        restart_scrobbler()
+    })
+}
+
+/// Number of listens that wait to be submitted
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getPendingScrobblesCountBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jint {
+    catch_jni_call(env, |_| Ok(prima()?.get_pending_scrobbles_count()? as jint))
+}
+
+/// Starts sleep timer, replacing the running one.
+/// Mode: 0 - after the given time, 1 - end of track, 2 - end of album
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_startSleepTimerBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    mode: jint,
+    millis: jlong,
+    is_fading: jboolean,
+) {
+    catch_jni_call(env, |_| {
+        let mode = SleepTimerMode::from_code(mode, Duration::from_millis(millis.max(0) as u64))
+            .ok_or_else(|| Error::Config(format!("Invalid sleep timer mode: {}", mode)))?;
+
+        prima()?.start_sleep_timer(mode, is_fading != JNI_FALSE);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_cancelSleepTimerBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) {
+    catch_jni_call(env, |_| {
+        prima()?.cancel_sleep_timer();
+        Ok(())
+    })
+}
+
+/// Mode of the running sleep timer or -1 if there is no timer
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getSleepTimerModeBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jint {
+    catch_jni_call(env, |_| {
+        Ok(prima()?
+            .get_sleep_timer_mode()
+            .map(|mode| mode.get_code())
+            .unwrap_or(-1))
+    })
+}
+
+/// Playback time in millis until the sleep timer stops it
+/// or -1 if there is no timer
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getSleepTimerTimeLeftBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jlong {
+    catch_jni_call(env, |_| {
+        Ok(prima()?
+            .get_sleep_timer_time_left()
+            .map(|left| left.as_millis() as jlong)
+            .unwrap_or(-1))
+    })
+}
+
+/// Sets A-B loop of the current track and stores it for next time.
+/// Segment is repeated *iterations* times, until it's cleared if *iterations* <= 0
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setAbLoopBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    start_millis: jlong,
+    end_millis: jlong,
+    iterations: jint,
+) {
+    catch_jni_call(env, |_| {
+        let ab_loop = AbLoop::new(
+            Duration::from_millis(start_millis.max(0) as u64),
+            Duration::from_millis(end_millis.max(0) as u64),
+            u32::try_from(iterations).ok().filter(|n| *n > 0),
+        )
+        .ok_or_else(|| Error::Config("Point A of the loop must be before B".to_string()))?;
+
+        Prima::set_ab_loop(prima()?.clone(), Some(ab_loop))
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_clearAbLoopBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) {
+    catch_jni_call(env, |_| Prima::set_ab_loop(prima()?.clone(), None))
+}
+
+/// # Return
+/// jlongArray[start millis, end millis, iterations, jumps made]
+/// where iterations is 0 for an endless loop, or null if there is no loop
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getAbLoopBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jlongArray {
+    catch_jni_call(env, |env| {
+        let ab_loop = match prima()?.get_ab_loop() {
+            None => return Ok(std::ptr::null_mut()),
+            Some(ab_loop) => ab_loop,
+        };
+
+        let arr = [
+            ab_loop.start.as_millis() as jlong,
+            ab_loop.end.as_millis() as jlong,
+            ab_loop.iterations.unwrap_or_default() as jlong,
+            prima()?.get_ab_loop_jumps() as jlong,
+        ];
+
+        let result = env.new_long_array(arr.len() as jsize)?;
+        env.set_long_array_region(&result, 0, arr.as_slice())?;
+        Ok(result.into_raw())
+    })
+}
+
+/// Position in millis where the track was left last time
+/// or -1 if it starts from the beginning
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getResumePositionBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    path: JString,
+) -> jlong {
+    catch_jni_call(env, |mut env| {
+        let path = PathBuf::from(String::from(env.get_string(&path)?));
+
+        Ok(prima()?
+            .get_resume_position(&path)?
+            .map(|position| position.as_millis() as jlong)
+            .unwrap_or(-1))
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getResumeThresholdBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jlong {
+    catch_jni_call(env, |_| {
+        Ok(prima()?.get_resume_threshold().as_millis() as jlong)
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setResumeThresholdBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    millis: jlong,
+) {
+    catch_jni_call(env, |_| {
+        prima()?.set_resume_threshold(Duration::from_millis(millis.max(0) as u64));
+        Ok(())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_addBookmarkBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    name: JString,
+    note: JString,
+) -> jobject {
+    catch_jni_call(env, |mut env| {
+        let name = String::from(env.get_string(&name)?);
+        let note = get_nullable_string(&mut env, &note)?;
+        let bookmark = prima()?.add_bookmark(&name, note.as_deref())?;
+        Ok(bookmark_to_java(&mut env, &bookmark)?.into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getBookmarksBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    path: JString,
+) -> jobjectArray {
+    catch_jni_call(env, |mut env| {
+        let path = get_nullable_string(&mut env, &path)?.map(PathBuf::from);
+        let bookmarks = prima()?.get_bookmarks(path.as_deref())?;
+
+        let result = env.new_object_array(
+            bookmarks.len() as jsize,
+            "com/paranid5/prima/rust/Bookmark",
+            JObject::null(),
+        )?;
+
+        for (index, bookmark) in bookmarks.iter().enumerate() {
+            let jbookmark = bookmark_to_java(&mut env, bookmark)?;
+            env.set_object_array_element(&result, index as jsize, &jbookmark)?;
+        }
+
+        Ok(result.into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_updateBookmarkBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    id: jlong,
+    name: JString,
+    note: JString,
+) -> jboolean {
+    catch_jni_call(env, |mut env| {
+        let name = String::from(env.get_string(&name)?);
+        let note = get_nullable_string(&mut env, &note)?;
+
+        Ok(jboolean::from(prima()?.update_bookmark(
+            get_bookmark_id(id)?,
+            &name,
+            note.as_deref(),
+        )?))
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_removeBookmarkBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    id: jlong,
+) -> jboolean {
+    catch_jni_call(env, |_| {
+        Ok(jboolean::from(
+            prima()?.remove_bookmark(get_bookmark_id(id)?)?,
+        ))
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_jumpToBookmarkBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    id: jlong,
+) {
+    catch_jni_call(env, |_| prima()?.jump_to_bookmark(get_bookmark_id(id)?))
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_playStreamBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    url: JString,
+) {
+    catch_jni_call(env, |mut env| {
+        let url = String::from(env.get_string(&url)?);
+        prima()?.play_stream(&url)
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_playRadioStationBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    id: jlong,
+) {
+    catch_jni_call(env, |_| {
+        prima()?.play_radio_station(get_radio_station_id(id)?)
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getStreamUrlBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_jni_call(env, |env| {
+        Ok(match prima()?.get_stream_url() {
+            None => std::ptr::null_mut(),
+            Some(url) => env.new_string(url)?.into_raw(),
+        })
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getStreamTitleBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_jni_call(env, |env| {
+        Ok(match prima()?.get_stream_title() {
+            None => std::ptr::null_mut(),
+            Some(title) => env.new_string(title)?.into_raw(),
+        })
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_addRadioStationBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    name: JString,
+    url: JString,
+) -> jobject {
+    catch_jni_call(env, |mut env| {
+        let name = String::from(env.get_string(&name)?);
+        let url = String::from(env.get_string(&url)?);
+        let station = prima()?.add_radio_station(&name, &url)?;
+        Ok(radio_station_to_java(&mut env, &station)?.into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getRadioStationsBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jobjectArray {
+    catch_jni_call(env, |mut env| {
+        let stations = prima()?.get_radio_stations()?;
+
+        let result = env.new_object_array(
+            stations.len() as jsize,
+            "com/paranid5/prima/rust/RadioStation",
+            JObject::null(),
+        )?;
+
+        for (index, station) in stations.iter().enumerate() {
+            let jstation = radio_station_to_java(&mut env, station)?;
+            env.set_object_array_element(&result, index as jsize, &jstation)?;
+        }
+
+        Ok(result.into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_updateRadioStationBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    id: jlong,
+    name: JString,
+    url: JString,
+) -> jboolean {
+    catch_jni_call(env, |mut env| {
+        let name = String::from(env.get_string(&name)?);
+        let url = String::from(env.get_string(&url)?);
+
+        Ok(jboolean::from(prima()?.update_radio_station(
+            get_radio_station_id(id)?,
+            &name,
+            &url,
+        )?))
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_removeRadioStationBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    id: jlong,
+) -> jboolean {
+    catch_jni_call(env, |_| {
+        Ok(jboolean::from(
+            prima()?.remove_radio_station(get_radio_station_id(id)?)?,
+        ))
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_subscribePodcastBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    url: JString,
+) -> jobject {
+    catch_jni_call(env, |mut env| {
+        let url = String::from(env.get_string(&url)?);
+        let feed = prima()?.subscribe_podcast(&url)?;
+        Ok(podcast_feed_to_java(&mut env, &feed)?.into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_unsubscribePodcastBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    id: jlong,
+) -> jboolean {
+    catch_jni_call(env, |_| {
+        Ok(jboolean::from(
+            prima()?.unsubscribe_podcast(get_podcast_id(id)?)?,
+        ))
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getPodcastFeedsBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jobjectArray {
+    catch_jni_call(env, |mut env| {
+        let feeds = prima()?.get_podcast_feeds()?;
+
+        let result = env.new_object_array(
+            feeds.len() as jsize,
+            "com/paranid5/prima/rust/PodcastFeed",
+            JObject::null(),
+        )?;
+
+        for (index, feed) in feeds.iter().enumerate() {
+            let jfeed = podcast_feed_to_java(&mut env, feed)?;
+            env.set_object_array_element(&result, index as jsize, &jfeed)?;
+        }
+
+        Ok(result.into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getPodcastEpisodesBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    feed_id: jlong,
+) -> jobjectArray {
+    catch_jni_call(env, |mut env| {
+        let episodes = prima()?.get_podcast_episodes(get_podcast_id(feed_id)?)?;
+
+        let result = env.new_object_array(
+            episodes.len() as jsize,
+            "com/paranid5/prima/rust/PodcastEpisode",
+            JObject::null(),
+        )?;
+
+        for (index, episode) in episodes.iter().enumerate() {
+            let jepisode = podcast_episode_to_java(&mut env, episode)?;
+            env.set_object_array_element(&result, index as jsize, &jepisode)?;
+        }
+
+        Ok(result.into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_refreshPodcastBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    id: jlong,
+) -> jint {
+    catch_jni_call(env, |_| {
+        Ok(prima()?.refresh_podcast(get_podcast_id(id)?)? as jint)
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_refreshPodcastsBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jint {
+    catch_jni_call(env, |_| Ok(prima()?.refresh_podcasts()? as jint))
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_downloadPodcastEpisodeBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    id: jlong,
+) -> jstring {
+    catch_jni_call(env, |env| {
+        let file = prima()?.download_podcast_episode(get_podcast_episode_id(id)?)?;
+        Ok(env.new_string(file.to_string_lossy())?.into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_removePodcastDownloadBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    id: jlong,
+) -> jboolean {
+    catch_jni_call(env, |_| {
+        Ok(jboolean::from(
+            prima()?.remove_podcast_download(get_podcast_episode_id(id)?)?,
+        ))
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setPodcastEpisodePlayedBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    id: jlong,
+    is_played: jboolean,
+) -> jboolean {
+    catch_jni_call(env, |_| {
+        Ok(jboolean::from(prima()?.set_podcast_episode_played(
+            get_podcast_episode_id(id)?,
+            is_played != JNI_FALSE,
+        )?))
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getPodcastPlaylistBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    feed_id: jlong,
+) -> jobjectArray {
+    catch_jni_call(env, |env| {
+        let feed_id = match feed_id {
+            ..=-1 => None,
+            feed_id => Some(get_podcast_id(feed_id)?),
+        };
+
+        Ok(prima()?
+            .get_podcast_playlist(feed_id)?
+            .get_tracks()
+            .iter()
+            .into_jobject_array(Rc::new(RefCell::new(env)))
+            .into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getPodcastsDirBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    catch_jni_call(env, |env| {
+        Ok(env
+            .new_string(prima()?.get_podcasts_dir().to_string_lossy())?
+            .into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setPodcastsDirBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    dir: JString,
+) {
+    catch_jni_call(env, |mut env| {
+        let dir = String::from(env.get_string(&dir)?);
+        prima()?.set_podcasts_dir(PathBuf::from(dir));
+        Ok(())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getPodcastRefreshIntervalBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jlong {
+    catch_jni_call(env, |_| {
+        Ok(prima()?.get_podcast_refresh_interval().as_millis() as jlong)
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_setPodcastRefreshIntervalBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    millis: jlong,
+) {
+    catch_jni_call(env, |_| {
+        prima()?.set_podcast_refresh_interval(Duration::from_millis(millis.max(0) as u64));
+        Ok(())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_storeCurPlaybackPosBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) {
+    catch_jni_call(env, |_| prima()?.store_playback_position())
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_onLikeTrackClicked(
+    env: JNIEnv,
+    _class: JClass,
+    track: JObject,
+) {
+    catch_jni_call(env, |env| {
+        let env = Rc::new(RefCell::new(env));
+        prima()?.toggle_track_liked(DefaultTrack::from_env(env, track))
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_isTrackLiked(
+    env: JNIEnv,
+    _class: JClass,
+    track: JObject,
+) -> jboolean {
+    catch_jni_call(env, |env| {
+        let env = Rc::new(RefCell::new(env));
+        let track = DefaultTrack::from_env(env, track);
+        Ok(jboolean::from(prima()?.is_track_liked(track.get_path())?))
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getCurPlaylistBlocking(
+    env: JNIEnv,
+    _class: JClass,
+) -> jobjectArray {
+    catch_jni_call(env, |env| {
+        Ok(prima()?
+            .get_cur_playlist()
+            .into_jobject_array(Rc::new(RefCell::new(env)))
+            .into_raw())
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_updateAndStoreCurPlaylistBlocking(
+    env: JNIEnv,
+    _class: JClass,
+    cur_playlist: JObject,
+) {
+    catch_jni_call(env, |env| {
+        let env = Rc::new(RefCell::new(env));
+        let new_playlist = JList::from_env(&mut env.borrow_mut(), &cur_playlist)?;
+
+        let new_playlist = new_playlist.map(env.clone(), |jtrack, env| {
+            DefaultTrack::from_env(env, jtrack)
+        });
+
+        prima()?.update_cur_playlist(new_playlist)
+    })
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getFavouriteTracks(
+    env: JNIEnv,
+    _class: JClass,
+) -> jobjectArray {
+    catch_jni_call(env, |env| {
+        Ok(prima()?
+            .get_favourite_tracks()?
+            .into_iter()
+            .into_jobject_array(Rc::new(RefCell::new(env)))
+            .into_raw())
+    })
+}
+
+/// Converts artist name to the next pattern:
 /// Name Family ... -> NF (upper case)
 /// If artist don't have second word in his name, it will return only first letter
 ///
@@ -821,212 +2223,164 @@ pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getFavouriteTracks(
 ///
 /// # Return
 /// Converted artist's name
-
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_artistImageBind(
-    mut env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     name: JString,
 ) -> jstring {
-    let jstring = unsafe { String::from_jstring_unchecked(&mut env, &name) };
-
-    env.new_string(String::from_iter(
-        jstring
-            .trim()
-            .split_whitespace()
-            .into_iter()
-            .filter(|&x| x != "&" && x != "feat." && x != "/" && x != "ft.")
-            .take(2)
-            .map(|s| s.chars().next().unwrap().to_uppercase().next().unwrap()),
-    ))
-    .unwrap()
-    .into_raw()
+    catch_jni_call(env, |mut env| {
+        let jstring = unsafe { String::from_jstring_unchecked(&mut env, &name) };
+
+        Ok(env
+            .new_string(String::from_iter(
+                jstring
+                    .split_whitespace()
+                    .filter(|&x| x != "&" && x != "feat." && x != "/" && x != "ft.")
+                    .take(2)
+                    .filter_map(|s| s.chars().next())
+                    .filter_map(|c| c.to_uppercase().next()),
+            ))?
+            .into_raw())
+    })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_onLikeArtistClicked(
-    mut env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     artist: JString,
 ) {
-    let artist = FavouriteArtist::new(unsafe { String::from_jstring_unchecked(&mut env, &artist) });
-    let mut connection = establish_connection().unwrap();
-
-    if FavouriteArtistDao::get_by_key(artist.get_key().clone(), &mut connection).is_some() {
-        FavouriteArtistDao::remove(vec![artist], &mut connection)
-    } else {
-        FavouriteArtistDao::insert(vec![artist], &mut connection)
-    }
+    catch_jni_call(env, |mut env| {
+        let artist = unsafe { String::from_jstring_unchecked(&mut env, &artist) };
+        prima()?.toggle_artist_liked(artist)
+    })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_isArtistLiked(
-    mut env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     artist: JString,
 ) -> jboolean {
-    let artist = FavouriteArtist::new(unsafe { String::from_jstring_unchecked(&mut env, &artist) });
-    let mut connection = establish_connection().unwrap();
-    jboolean::from(
-        FavouriteArtistDao::get_by_key(artist.get_key().clone(), &mut connection).is_some(),
-    )
+    catch_jni_call(env, |mut env| {
+        let artist = unsafe { String::from_jstring_unchecked(&mut env, &artist) };
+        Ok(jboolean::from(prima()?.is_artist_liked(artist)?))
+    })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getFavouriteArtists(
-    mut env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
 ) -> jobjectArray {
-    let mut connection = establish_connection().unwrap();
-    let artists = FavouriteArtistDao::get_all(&mut connection);
+    catch_jni_call(env, |mut env| {
+        let artists = prima()?.get_favourite_artists()?;
 
-    let arr = env
-        .new_object_array(artists.len() as jsize, "java/lang/String", &JObject::null())
-        .unwrap();
+        let arr =
+            env.new_object_array(artists.len() as jsize, "java/lang/String", JObject::null())?;
 
-    artists.into_iter().enumerate().for_each(|(ind, artist)| {
-        env.set_object_array_element(
-            &arr,
-            ind as jsize,
-            env.new_string(artist.into_string()).unwrap(),
-        )
-        .unwrap();
-    });
+        for (ind, artist) in artists.into_iter().enumerate() {
+            let artist = env.new_string(artist.into_string())?;
+            env.set_object_array_element(&arr, ind as jsize, artist)?;
+        }
 
-    arr.into_raw()
+        Ok(arr.into_raw())
+    })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getAllArtistsBlocking(
-    mut env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     placeholder: JString,
 ) -> jobjectArray {
-    let jvm = Arc::new(env.get_java_vm().unwrap());
-
-    TOKIO_RUNTIME.block_on(async move {
-        let tracks =
-            AudioScanner::get_all_tracks(jvm, TOKIO_RUNTIME.clone(), STORAGE_UTIL.clone()).await;
-
-        let tracks = tracks.lock().await;
+    catch_jni_call(env, |mut env| {
+        let mut artists = prima()?.get_all_artists()?;
 
-        let mut artists = tracks
-            .iter()
-            .filter_map(|track| track.get_artist())
-            .collect::<HashSet<_>>();
-
-        let is_empty_array_list = artists.remove(&"".to_string());
+        let unknown_artist_position = artists.iter().position(String::is_empty);
 
-        let mut artists = artists.into_iter().collect::<Vec<_>>();
-        artists.sort_unstable();
+        if let Some(position) = unknown_artist_position {
+            artists.remove(position);
+        }
 
         let mut artists = artists
             .into_iter()
-            .map(|string| env.new_string(string.clone()).unwrap())
-            .collect::<Vec<_>>();
+            .map(|string| env.new_string(string))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
 
-        if is_empty_array_list {
+        if unknown_artist_position.is_some() {
             artists.push(placeholder)
         }
 
-        let arr = env
-            .new_object_array(artists.len() as jsize, "java/lang/String", &JObject::null())
-            .unwrap();
+        let arr =
+            env.new_object_array(artists.len() as jsize, "java/lang/String", JObject::null())?;
 
-        artists.into_iter().enumerate().for_each(|(ind, artist)| {
-            env.set_object_array_element(&arr, ind as jsize, artist)
-                .unwrap()
-        });
+        for (ind, artist) in artists.into_iter().enumerate() {
+            env.set_object_array_element(&arr, ind as jsize, artist)?
+        }
 
-        arr.into_raw()
+        Ok(arr.into_raw())
     })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_getArtistTracksBlocking(
-    mut env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     artist: JString,
 ) -> jobjectArray {
-    let jvm = Arc::new(env.get_java_vm().unwrap());
-    let artist = unsafe { String::from_jstring_unchecked(&mut env, &artist) };
-
-    TOKIO_RUNTIME.block_on(async move {
-        AudioScanner::get_all_tracks(jvm, TOKIO_RUNTIME.clone(), STORAGE_UTIL.clone())
-            .await
-            .lock()
-            .await
+    catch_jni_call(env, |mut env| {
+        let artist = unsafe { String::from_jstring_unchecked(&mut env, &artist) };
+
+        Ok(prima()?
+            .get_artist_tracks(&artist)?
             .iter()
-            .filter(|track| track.get_artist().is_some())
-            .filter(|track| artist == *track.get_artist().unwrap())
-            .collect::<Vec<_>>()
-            .into_iter()
             .into_jobject_array(Rc::new(RefCell::new(env)))
-            .into_raw()
+            .into_raw())
     })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_onLikePlaylistClicked(
-    mut env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     id: JObject,
     title: JString,
     tp: jint,
 ) {
-    let title = String::from_jstring(&mut env, &title);
-    let tp = tp as i32;
-    let mut connection = establish_connection().unwrap();
-
-    if id.is_null() {
-        FavouritePlaylistDao::insert(
-            vec![FavouritePlaylistDBEntity::new(0, title, tp)],
-            &mut connection,
-        )
-    } else {
-        let id = env
-            .call_method(id, "intValue", "()I", &[])
-            .unwrap()
-            .i()
-            .unwrap();
-
-        if FavouritePlaylistDao::get_by_key(id, &mut connection).is_some() {
-            FavouritePlaylistDao::remove(
-                vec![FavouritePlaylistDBEntity::new(id, title, tp)],
-                &mut connection,
-            )
-        } else {
-            FavouritePlaylistDao::insert(
-                vec![FavouritePlaylistDBEntity::new(id, title, tp)],
-                &mut connection,
-            )
-        }
-    }
+    catch_jni_call(env, |mut env| {
+        let title = String::from_jstring(&mut env, &title);
+
+        let id = match id.is_null() {
+            true => None,
+            false => Some(env.call_method(id, "intValue", "()I", &[])?.i()?),
+        };
+
+        prima()?.toggle_playlist_liked(id, title, tp)
+    })
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_paranid5_prima_rust_RustLibs_isPlaylistLiked(
-    mut env: JNIEnv,
+    env: JNIEnv,
     _class: JClass,
     id: JObject,
 ) -> jboolean {
-    jboolean::from(if id.is_null() {
-        false
-    } else {
-        let id = env
-            .call_method(id, "intValue", "()I", &[])
-            .unwrap()
-            .i()
-            .unwrap();
-
-        FavouritePlaylistDao::get_by_key(id, &mut establish_connection().unwrap()).is_some()
+    catch_jni_call(env, |mut env| {
+        if id.is_null() {
+            return Ok(jboolean::from(false));
+        }
+
+        let id = env.call_method(id, "intValue", "()I", &[])?.i()?;
+        Ok(jboolean::from(prima()?.is_playlist_liked(id)?))
     })
 }
@@ -1,32 +1,5198 @@
-#[test]
-fn store_music_search_path_test() {
+use std::path::PathBuf;
+
+/// Settings file in the fresh temporary folder of the test
+pub(crate) fn test_data_file(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("prima_test_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("data.yaml")
+}
+
+/// Writes mono 16-bit PCM WAV file filled with silence
+pub(crate) fn write_silent_wav(path: &std::path::Path, millis: u32) {
+    const SAMPLE_RATE: u32 = 8000;
+
+    let data_len = SAMPLE_RATE * millis / 1000 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    wav.extend(b"RIFF");
+    wav.extend((36 + data_len).to_le_bytes());
+    wav.extend(b"WAVEfmt ");
+    wav.extend(16_u32.to_le_bytes());
+    wav.extend(1_u16.to_le_bytes());
+    wav.extend(1_u16.to_le_bytes());
+    wav.extend(SAMPLE_RATE.to_le_bytes());
+    wav.extend((SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend(2_u16.to_le_bytes());
+    wav.extend(16_u16.to_le_bytes());
+    wav.extend(b"data");
+    wav.extend(data_len.to_le_bytes());
+    wav.resize(44 + data_len as usize, 0);
+
+    std::fs::write(path, wav).unwrap()
+}
+
+#[tokio::test]
+async fn store_music_search_path_test() {
     extern crate dirs2;
     use crate::domain::storage_util::StorageUtil;
     use dirs2::audio_dir;
 
-    assert!(StorageUtil::store_music_search_path(audio_dir().unwrap()).is_ok())
+    let path = test_data_file("store_music_search_path");
+    let mut storage_util = StorageUtil::from_file(path.clone()).await;
+
+    storage_util.store_music_search_path(PathBuf::from("/music"));
+    assert!(storage_util.flush().await.is_ok());
+    assert_eq!(
+        StorageUtil::from_file(path).await.load_music_search_path(),
+        Some(&PathBuf::from("/music"))
+    );
+
+    let storage_util = StorageUtil::from_file(test_data_file("load_music_search_path")).await;
+    assert_eq!(storage_util.load_music_search_path(), audio_dir().as_ref())
+}
+
+#[tokio::test]
+async fn store_track_order_test() {
+    use crate::{
+        data::utils::track_order::{Comparator, Ord, TrackOrder},
+        domain::storage_util::StorageUtil,
+    };
+
+    let path = test_data_file("store_track_order");
+    let order = TrackOrder::new(Comparator::Album, Ord::Desc);
+
+    let mut storage_util = StorageUtil::from_file(path.clone()).await;
+    assert_eq!(storage_util.load_track_order(), TrackOrder::default());
+
+    storage_util.store_track_order(order);
+    assert!(storage_util.flush().await.is_ok());
+    assert_eq!(StorageUtil::from_file(path).await.load_track_order(), order)
 }
 
 #[test]
-fn load_music_search_path_test() {
-    extern crate dirs2;
+fn track_order_codes_test() {
+    use crate::data::utils::track_order::{Comparator, Ord};
+    use jni::sys::jint;
+
+    // Codes of comparators and orders don't overlap with each other's meaning
+    assert_eq!(Comparator::try_from(5_i32).unwrap(), Comparator::Bpm);
+    assert_eq!(Ord::try_from(1_i32).unwrap(), Ord::Desc);
+    assert!(Comparator::try_from(8_i32).is_err());
+    assert!(Ord::try_from(5_i64).is_err());
+
+    for comparator in [Comparator::Bpm, Comparator::Key, Comparator::Energy] {
+        assert_eq!(
+            Comparator::try_from(jint::from(comparator)).unwrap(),
+            comparator
+        );
+        assert_eq!(
+            Comparator::try_from(i64::from(comparator)).unwrap(),
+            comparator
+        );
+    }
+}
+
+#[tokio::test]
+async fn state_store_debounce_test() {
+    use crate::domain::state_store::StateStore;
+    use yaml_rust::Yaml;
+
+    let path = test_data_file("state_store_debounce");
+    let mut state_store = StateStore::load(path.clone()).await;
+
+    state_store.set("volume", Yaml::Integer(1));
+    assert!(state_store.is_dirty());
+    assert!(!state_store.should_flush());
+
+    assert!(state_store.flush_if_needed().await.is_ok());
+    assert!(!path.exists());
+
+    assert!(state_store.flush().await.is_ok());
+    assert!(!state_store.is_dirty());
+    assert!(path.exists());
+
+    state_store.set("volume", Yaml::Integer(1));
+    assert!(!state_store.is_dirty())
+}
+
+#[tokio::test]
+async fn state_store_snapshot_test() {
+    use crate::domain::state_store::StateStore;
+    use yaml_rust::Yaml;
+
+    let path = test_data_file("state_store_snapshot");
+    let mut state_store = StateStore::load(path.clone()).await;
+
+    state_store.set("volume", Yaml::Integer(1));
+    let old_snapshot = state_store.take_snapshot().unwrap().unwrap();
+    assert!(!state_store.is_dirty());
+
+    state_store.set("volume", Yaml::Integer(2));
+    let new_snapshot = state_store.take_snapshot().unwrap().unwrap();
+
+    assert!(new_snapshot.write().await.is_ok());
+    assert!(old_snapshot.write().await.is_ok());
+
+    let mut state_store = StateStore::load(path).await;
+    assert_eq!(state_store.get("volume"), Some(&Yaml::Integer(2)));
+
+    state_store.restore_dirty(&old_snapshot);
+    assert!(state_store.is_dirty())
+}
+
+#[tokio::test]
+async fn state_store_backup_test() {
+    use crate::domain::state_store::StateStore;
+    use yaml_rust::Yaml;
+
+    let path = test_data_file("state_store_backup");
+    let mut state_store = StateStore::load(path.clone()).await;
+
+    state_store.set("current_playback_position", Yaml::Integer(1000));
+    assert!(state_store.flush().await.is_ok());
+
+    state_store.set("current_playback_position", Yaml::Integer(2000));
+    assert!(state_store.flush().await.is_ok());
+    assert!(StateStore::backup_path_of(&path).exists());
+
+    std::fs::write(&path, "current_playback_position: [").unwrap();
+
+    let state_store = StateStore::load(path.clone()).await;
+    assert!(state_store.is_dirty());
+    assert_eq!(
+        state_store.get("current_playback_position"),
+        Some(&Yaml::Integer(1000))
+    );
+
+    std::fs::write(&path, "").unwrap();
+
+    let state_store = StateStore::load(path).await;
+    assert_eq!(
+        state_store.get("current_playback_position"),
+        Some(&Yaml::Integer(1000))
+    )
+}
+
+#[test]
+fn xdg_paths_test() {
+    use crate::data::utils::paths::AppPaths;
+    use std::path::Path;
+
+    let cwd = test_data_file("xdg_paths").parent().unwrap().to_path_buf();
+
+    let paths = AppPaths::resolve_with(
+        |key| match key {
+            "XDG_CONFIG_HOME" => Some(PathBuf::from("/xdg/config")),
+            "XDG_DATA_HOME" => Some(PathBuf::from("relative/data")),
+            "HOME" => Some(PathBuf::from("/home/user")),
+            _ => None,
+        },
+        &cwd,
+    );
+
+    assert!(!paths.is_portable());
+    assert_eq!(paths.get_config_dir(), Path::new("/xdg/config/prima"));
+    assert_eq!(
+        paths.get_state_file(),
+        Path::new("/xdg/config/prima/data.yaml")
+    );
+
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        assert_eq!(
+            paths.get_data_dir(),
+            Path::new("/home/user/.local/share/prima")
+        );
+        assert_eq!(paths.get_cache_dir(), Path::new("/home/user/.cache/prima"));
+    }
+
+    let paths = AppPaths::resolve_with(
+        |key| match key {
+            "PRIMA_HOME" => Some(PathBuf::from("portable")),
+            _ => None,
+        },
+        &cwd,
+    );
+
+    assert!(paths.is_portable());
+    assert_eq!(paths.get_favourite_db(), cwd.join("portable/favourite.db"));
+
+    std::fs::write(cwd.join("prima.portable"), "").unwrap();
+
+    let paths = AppPaths::resolve_with(|_| None, &cwd);
+    assert!(paths.is_portable());
+    assert_eq!(paths.get_state_file(), cwd.join("data.yaml"))
+}
+
+#[test]
+fn legacy_files_migration_test() {
+    use crate::data::utils::paths::AppPaths;
+
+    let cwd = test_data_file("legacy_files_migration")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    let xdg = cwd.join("xdg");
+
+    std::fs::write(cwd.join("data.yaml"), "volume: 0.5").unwrap();
+    std::fs::write(cwd.join("favourite.db"), "db").unwrap();
+
+    let paths = AppPaths::resolve_with(
+        |key| match key {
+            "XDG_CONFIG_HOME" => Some(xdg.join("config")),
+            "XDG_DATA_HOME" => Some(xdg.join("data")),
+            "XDG_CACHE_HOME" => Some(xdg.join("cache")),
+            _ => None,
+        },
+        &cwd,
+    );
+
+    assert!(paths.prepare().is_ok());
+    assert!(paths.get_cache_dir().is_dir());
+    assert!(!cwd.join("data.yaml").exists());
+    assert!(!cwd.join("favourite.db").exists());
+
+    assert_eq!(
+        std::fs::read_to_string(paths.get_state_file()).unwrap(),
+        "volume: 0.5"
+    );
+
+    std::fs::write(cwd.join("data.yaml"), "volume: 1.0").unwrap();
+    assert!(paths.prepare().is_ok());
+    assert!(cwd.join("data.yaml").exists());
+
+    assert_eq!(
+        std::fs::read_to_string(paths.get_state_file()).unwrap(),
+        "volume: 0.5"
+    )
+}
+
+#[test]
+fn error_exception_class_test() {
+    use crate::error::{Error, PRIMA_EXCEPTION_CLASS};
+    use std::io::{Error as IOError, ErrorKind};
+
+    let io_error = Error::from(IOError::new(ErrorKind::NotFound, "track.mp3"));
+    assert!(io_error
+        .java_exception_class()
+        .ends_with("PrimaIOException"));
+    assert!(io_error.to_string().contains("track.mp3"));
+
+    let db_error = Error::from(diesel::result::Error::NotFound);
+    assert!(db_error
+        .java_exception_class()
+        .ends_with("PrimaDatabaseException"));
+
+    let panic = std::panic::catch_unwind(|| panic!("player is broken")).unwrap_err();
+    let panic_error = Error::from_panic(panic);
+
+    assert_eq!(panic_error.to_string(), "Internal error: player is broken");
+    assert!(panic_error
+        .java_exception_class()
+        .starts_with(PRIMA_EXCEPTION_CLASS.trim_end_matches("PrimaException")))
+}
+
+#[tokio::test]
+async fn event_bus_test() {
+    use crate::domain::events::{event_bus::EventBus, player_event::PlayerEvent};
+    use std::time::Duration;
+
+    let event_bus = EventBus::new();
+    event_bus.publish(PlayerEvent::StateChanged { is_playing: true });
+
+    let mut scrobbler = event_bus.subscribe();
+    let mut history = event_bus.clone().subscribe();
+    assert_eq!(event_bus.subscribers_count(), 2);
+
+    let tick = PlayerEvent::PositionTick {
+        position: Duration::from_millis(200),
+    };
+
+    event_bus.publish(tick.clone());
+    event_bus.publish(PlayerEvent::LibraryUpdated { tracks_count: 3 });
+
+    assert_eq!(scrobbler.recv().await.unwrap(), tick);
+    assert_eq!(history.recv().await.unwrap(), tick);
+
+    assert_eq!(
+        scrobbler.recv().await.unwrap(),
+        PlayerEvent::LibraryUpdated { tracks_count: 3 }
+    );
+}
+
+#[test]
+fn player_state_events_test() {
+    use crate::domain::{
+        audio_player::{audio_player::AudioPlayer, playback_params::PlaybackParams},
+        events::event_bus::EventBus,
+        storage_util::StorageUtil,
+    };
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+
+    runtime.clone().block_on(async move {
+        let storage_util = Arc::new(RwLock::new(
+            StorageUtil::from_file(test_data_file("player_state_events")).await,
+        ));
+
+        let event_bus = EventBus::new();
+        let mut receiver = event_bus.subscribe();
+
+        let player = Arc::new(RwLock::new(
+            AudioPlayer::new(
+                PlaybackParams::default(storage_util.clone()).await,
+                storage_util.clone(),
+                event_bus,
+            )
+            .await,
+        ));
+
+        AudioPlayer::pause(player, runtime, storage_util).await;
+
+        // Player wasn't playing, so nothing has changed
+        assert!(receiver.try_recv().is_err())
+    })
+}
+
+#[tokio::test]
+async fn store_output_device_test() {
     use crate::domain::storage_util::StorageUtil;
-    use dirs2::audio_dir;
 
+    let path = test_data_file("store_output_device");
+
+    let mut storage_util = StorageUtil::from_file(path.clone()).await;
+    assert_eq!(storage_util.load_output_device(), None);
+
+    storage_util.store_output_device(Some("Headphones".to_string()));
+    assert!(storage_util.flush().await.is_ok());
+
+    let mut storage_util = StorageUtil::from_file(path.clone()).await;
     assert_eq!(
-        StorageUtil::load_music_search_path(),
-        Some(audio_dir().unwrap())
+        storage_util.load_output_device(),
+        Some(&"Headphones".to_string())
+    );
+
+    storage_util.store_output_device(None);
+    assert!(storage_util.flush().await.is_ok());
+    assert_eq!(
+        StorageUtil::from_file(path).await.load_output_device(),
+        None
+    )
+}
+
+#[test]
+fn null_output_playback_test() {
+    use crate::domain::{
+        audio_player::{
+            audio_output::OutputMode, audio_player::AudioPlayer, playback_params::PlaybackParams,
+        },
+        events::{event_bus::EventBus, player_event::PlayerEvent},
+        storage_util::StorageUtil,
+    };
+    use std::{sync::Arc, time::Duration};
+    use tokio::sync::RwLock;
+
+    let data_file = test_data_file("null_output_playback");
+    let track = data_file.with_file_name("silence.wav");
+    write_silent_wav(&track, 300);
+
+    let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+
+    runtime.clone().block_on(async move {
+        let storage_util = Arc::new(RwLock::new(StorageUtil::from_file(data_file).await));
+        let event_bus = EventBus::new();
+        let mut receiver = event_bus.subscribe();
+
+        let player = Arc::new(RwLock::new(
+            AudioPlayer::new(
+                PlaybackParams::default(storage_util.clone()).await,
+                storage_util.clone(),
+                event_bus,
+            )
+            .await,
+        ));
+
+        AudioPlayer::set_output_mode(
+            player.clone(),
+            runtime.clone(),
+            storage_util.clone(),
+            OutputMode::Null,
+        )
+        .await;
+
+        let result = AudioPlayer::play(
+            player.clone(),
+            runtime,
+            storage_util,
+            track,
+            Duration::from_millis(300),
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert!(player.read().await.is_done());
+        assert_eq!(player.read().await.get_output_device_name(), None);
+
+        assert_eq!(
+            receiver.recv().await.unwrap(),
+            PlayerEvent::StateChanged { is_playing: true }
+        )
+    })
+}
+
+#[test]
+fn paused_seek_keeps_output_test() {
+    use crate::domain::{
+        audio_player::{
+            audio_output::OutputMode, audio_player::AudioPlayer, playback_params::PlaybackParams,
+        },
+        events::event_bus::EventBus,
+        storage_util::StorageUtil,
+    };
+    use std::{
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+    use tokio::sync::RwLock;
+
+    let data_file = test_data_file("paused_seek_keeps_output");
+    let track = data_file.with_file_name("silence.wav");
+    write_silent_wav(&track, 3000);
+
+    let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+
+    let (storage_util, player) = runtime.block_on(async {
+        let storage_util = Arc::new(RwLock::new(StorageUtil::from_file(data_file).await));
+
+        let player = Arc::new(RwLock::new(
+            AudioPlayer::new(
+                PlaybackParams::default(storage_util.clone()).await,
+                storage_util.clone(),
+                EventBus::new(),
+            )
+            .await,
+        ));
+
+        AudioPlayer::set_output_mode(
+            player.clone(),
+            runtime.clone(),
+            storage_util.clone(),
+            OutputMode::Null,
+        )
+        .await;
+
+        (storage_util, player)
+    });
+
+    let wait_until_playing = || {
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        while !runtime.block_on(player.read()).is_playing() {
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(10));
+        }
+    };
+
+    let playback = {
+        let (player, runtime, storage_util) =
+            (player.clone(), runtime.clone(), storage_util.clone());
+
+        thread::spawn(move || {
+            runtime.clone().block_on(AudioPlayer::play(
+                player,
+                runtime,
+                storage_util,
+                track,
+                Duration::from_secs(3),
+            ))
+        })
+    };
+
+    wait_until_playing();
+    let sink = runtime.block_on(player.read()).get_sink().unwrap();
+
+    // Pause returns the blocking playback, the output stays open
+    runtime.block_on(AudioPlayer::pause(
+        player.clone(),
+        runtime.clone(),
+        storage_util.clone(),
+    ));
+
+    assert!(playback.join().unwrap().is_ok());
+
+    // Paused track is seeked by its decoder and stays paused
+    let is_seeked = runtime.block_on(AudioPlayer::seek_live(
+        player.clone(),
+        runtime.clone(),
+        storage_util.clone(),
+        Duration::from_secs(1),
+    ));
+
+    assert!(is_seeked.unwrap());
+
+    runtime.block_on(async {
+        let player = player.read().await;
+        assert!(!player.is_playing());
+        assert_eq!(player.get_cur_playback_pos().await, Duration::from_secs(1));
+        assert!(Arc::ptr_eq(&player.get_sink().unwrap(), &sink));
+    });
+
+    // Paused seek continues playback on the same sink
+    let playback = {
+        let (player, runtime, storage_util) =
+            (player.clone(), runtime.clone(), storage_util.clone());
+
+        thread::spawn(move || {
+            runtime.clone().block_on(AudioPlayer::seek_to(
+                player,
+                runtime,
+                storage_util,
+                Duration::from_secs(2),
+                Duration::from_secs(3),
+            ))
+        })
+    };
+
+    wait_until_playing();
+
+    runtime.block_on(async {
+        let player = player.read().await;
+        assert!(player.get_cur_playback_pos().await >= Duration::from_secs(2));
+        assert!(Arc::ptr_eq(&player.get_sink().unwrap(), &sink));
+    });
+
+    runtime.block_on(AudioPlayer::pause(
+        player.clone(),
+        runtime.clone(),
+        storage_util,
+    ));
+
+    assert!(playback.join().unwrap().is_ok());
+}
+
+#[test]
+fn export_tracks_test() {
+    use crate::domain::{
+        audio_export::audio_exporter::{AudioExporter, ExportTask},
+        audio_player::playback_params::{LoopingState, PlaybackParams, ReverbParams},
+    };
+    use rodio::{Decoder, Source};
+    use std::{fs::File, io::BufReader, time::Duration};
+
+    let dir = test_data_file("export_tracks")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let track = dir.join("silence.wav");
+    write_silent_wav(&track, 500);
+
+    let params = PlaybackParams::new(
+        1.0,
+        2.0,
+        ReverbParams::default(),
+        Duration::from_millis(100),
+        LoopingState::NoLooping,
+    );
+
+    let tracks = vec![track.clone(), track];
+
+    for output in [dir.join("export.wav"), dir.join("export.flac")] {
+        let task = ExportTask::new();
+        let result = AudioExporter::export(&tracks, &output, &params, &task);
+
+        assert!(result.unwrap());
+        assert_eq!(task.get_progress(), 1.0);
+
+        // Two tracks of 500 ms played twice faster, sample rate is not changed
+        let decoder = Decoder::new(BufReader::new(File::open(&output).unwrap())).unwrap();
+        assert_eq!(decoder.sample_rate(), 8000);
+
+        let millis = decoder.count() as u64 * 1000 / 8000;
+        assert!((490..=510).contains(&millis), "{} ms", millis);
+    }
+
+    let cancelled = ExportTask::new();
+    cancelled.cancel();
+
+    let output = dir.join("cancelled.flac");
+    let result = AudioExporter::export(&tracks, &output, &params, &cancelled);
+
+    assert!(!result.unwrap());
+    assert!(!output.exists());
+    assert!(!dir.join("cancelled.flac.part").exists());
+    assert!(AudioExporter::export(&tracks, &dir.join("export.mp3"), &params, &cancelled).is_err())
+}
+
+#[test]
+fn flac_writer_compression_test() {
+    use crate::domain::audio_export::flac_writer::FlacWriter;
+    use std::io::Cursor;
+
+    // Stereo tone with noise, silent second channel at the end
+    let samples = (0..10000)
+        .flat_map(|i| {
+            let tone = ((i as f32 * 0.05).sin() * 12000.0) as i16;
+            let noise = ((i * 7919) % 61) as i16 - 30;
+            let right = if i < 9000 { tone / 2 - noise } else { 0 };
+            [tone + noise, right]
+        })
+        .collect::<Vec<_>>();
+
+    let mut flac = FlacWriter::new(Cursor::new(Vec::new()), 2, 44100).unwrap();
+    samples.iter().for_each(|&s| flac.write_sample(s).unwrap());
+    let bytes = flac.finalize().unwrap().into_inner();
+
+    // Less than half of raw 16-bit PCM
+    assert!(bytes.len() < samples.len(), "{} bytes", bytes.len());
+
+    let mut reader = claxon::FlacReader::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(reader.streaminfo().samples, Some(10000));
+
+    let decoded = reader
+        .samples()
+        .map(|s| s.unwrap() as i16)
+        .collect::<Vec<_>>();
+
+    assert_eq!(decoded, samples)
+}
+
+#[test]
+fn native_library_scan_test() {
+    use crate::{
+        data::utils::track_order::{Comparator, Ord, TrackOrder},
+        domain::{
+            audio_export::flac_writer::FlacWriter, audio_scanner::AudioScanner,
+            metadata_reader::MetadataReader,
+        },
+        TrackTrait,
+    };
+    use std::{fs::File, io::BufWriter};
+
+    let dir = test_data_file("native_library_scan")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    std::fs::create_dir_all(dir.join("album")).unwrap();
+    write_silent_wav(&dir.join("b.wav"), 250);
+    write_silent_wav(&dir.join("album").join("a.wav"), 1500);
+    std::fs::write(dir.join("cover.txt"), "not a track").unwrap();
+
+    let mut flac = FlacWriter::new(
+        BufWriter::new(File::create(dir.join("c.flac")).unwrap()),
+        1,
+        8000,
     )
+    .unwrap();
+
+    (0..4000).for_each(|_| flac.write_sample(0).unwrap());
+    flac.finalize().unwrap();
+
+    let metadata = MetadataReader::read_metadata(&dir.join("c.flac")).unwrap();
+    assert_eq!(metadata.duration_millis, 500);
+    assert_eq!(metadata.title, None);
+    assert!(MetadataReader::read_track(&dir.join("cover.txt")).is_none());
+
+    let order = TrackOrder {
+        comparator: Comparator::Title,
+        order: Ord::Asc,
+    };
+
+    let tracks = AudioScanner::get_tracks_in(&dir, order);
+
+    let titles = tracks
+        .iter()
+        .map(|t| t.get_title().unwrap().as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(titles, ["", "a", "b"]);
+    assert_eq!(tracks[1].get_duration().num_milliseconds(), 1500);
+    assert_eq!(tracks[2].get_duration().num_milliseconds(), 250);
+    assert_eq!(tracks[0].get_artist(), Some(&String::new()))
+}
+
+#[test]
+fn prima_session_test() {
+    use crate::{
+        data::entities::{
+            playlists::{
+                default_playlist::DefaultPlaylist, playlist_trait::PlaylistTrait,
+                playlist_type::PlaylistType,
+            },
+            tracks::track_trait::TrackTrait,
+        },
+        domain::{audio_player::audio_output::OutputMode, prima::Prima},
+    };
+
+    let data_file = test_data_file("prima_session");
+    let dir = data_file.parent().unwrap().to_path_buf();
+    let music_dir = dir.join("music");
+    let db_url = dir.join("favourite.db").to_string_lossy().to_string();
+
+    std::fs::create_dir_all(&music_dir).unwrap();
+    write_silent_wav(&music_dir.join("first.wav"), 200);
+    write_silent_wav(&music_dir.join("second.wav"), 200);
+
+    {
+        let prima = Prima::open(data_file.clone(), db_url.clone()).unwrap();
+        prima.set_output_mode(OutputMode::Null);
+        prima.set_music_search_path(music_dir.clone());
+        prima.set_volume(0.5);
+
+        let tracks = prima.get_all_tracks().unwrap();
+        assert_eq!(tracks.len(), 2);
+
+        let playlist = DefaultPlaylist::new(None, PlaylistType::default(), tracks.clone(), 1);
+        prima.play(playlist).unwrap();
+
+        assert_eq!(prima.get_cur_track(), Some(tracks[1].clone()));
+        assert_eq!(prima.get_cur_track_index(), 1);
+
+        prima.set_track_liked(tracks[0].clone(), true).unwrap();
+        prima.toggle_track_liked(tracks[1].clone()).unwrap();
+        prima.toggle_track_liked(tracks[1].clone()).unwrap();
+
+        assert!(prima.is_track_liked(tracks[0].get_path()).unwrap());
+        assert!(!prima.is_track_liked(tracks[1].get_path()).unwrap());
+        assert_eq!(prima.get_favourite_tracks().unwrap().len(), 1);
+
+        prima.shutdown().unwrap();
+    }
+
+    // Settings and current playlist are restored without JVM
+    let prima = Prima::open(data_file, db_url).unwrap();
+
+    assert_eq!(prima.get_volume(), 0.5);
+    assert_eq!(prima.get_music_search_path(), Some(music_dir));
+    assert_eq!(prima.get_cur_playlist().get_tracks().len(), 2);
+    assert_eq!(
+        prima
+            .get_cur_track()
+            .map(|t| t.get_title().cloned().unwrap_or_default()),
+        Some("second".to_string())
+    );
 }
 
+#[cfg(target_os = "linux")]
 #[test]
-fn store_track_order_test() {
-    use crate::{data::utils::track_order::TrackOrder, domain::storage_util::StorageUtil};
-    assert!(StorageUtil::store_track_order(TrackOrder::default()).is_ok())
+fn dbus_marshalling_test() {
+    use crate::{
+        data::utils::file_url::{file_url_to_path, path_to_file_url},
+        domain::mpris::{
+            dbus_message::DBusMessage,
+            dbus_value::{split_signature, DBusValue},
+        },
+    };
+
+    let body = vec![
+        DBusValue::Byte(7),
+        DBusValue::String("org.mpris.MediaPlayer2.Player".to_string()),
+        DBusValue::dict([
+            ("Volume", DBusValue::Double(0.25)),
+            ("Position", DBusValue::Int64(-42)),
+            ("Tracks", DBusValue::object_path_array(["/a/0".to_string()])),
+        ]),
+        DBusValue::string_array([]),
+        DBusValue::Struct(vec![DBusValue::Int16(-3), DBusValue::Boolean(true)]),
+    ];
+
+    let mut message = DBusMessage::signal("/org/mpris/MediaPlayer2", "a.b", "Changed", body);
+    let bytes = message.encode(9);
+    message.serial = 9;
+
+    assert_eq!(DBusMessage::decode(&bytes).unwrap(), message);
+    assert_eq!(message.signature(), "ysa{sv}as(nb)");
+    assert_eq!(
+        split_signature("a{sv}(ii)s").unwrap(),
+        ["a{sv}", "(ii)", "s"]
+    );
+    assert!(DBusMessage::decode(&bytes[..bytes.len() - 1]).is_err());
+
+    let path = std::path::Path::new("/music/Rock & Roll/01 – Intro.flac");
+    let url = path_to_file_url(path);
+
+    assert_eq!(
+        url,
+        "file:///music/Rock%20%26%20Roll/01%20%E2%80%93%20Intro.flac"
+    );
+    assert_eq!(file_url_to_path(&url).unwrap(), path);
+    assert_eq!(
+        file_url_to_path("file://localhost/a%2Fb").unwrap(),
+        PathBuf::from("/a/b")
+    );
+    assert!(file_url_to_path("http://example.com/a.mp3").is_none())
+}
+
+/// MPRIS service on a private bus, controlled as a media widget would.
+/// Session plays the second of two silent tracks and is paused
+#[cfg(target_os = "linux")]
+struct MprisBus {
+    daemon: std::process::Child,
+    prima: std::sync::Arc<crate::domain::prima::Prima>,
+    tracks: Vec<crate::data::entities::tracks::default_track::DefaultTrack>,
+    server: Option<crate::domain::mpris::mpris_server::MprisServer>,
+    client: crate::domain::mpris::dbus_connection::DBusConnection,
+}
+
+#[cfg(target_os = "linux")]
+impl MprisBus {
+    /// Skipped with `None` when `dbus-daemon` is not installed
+    fn start(name: &str) -> Option<Self> {
+        use crate::{
+            data::entities::playlists::{
+                default_playlist::DefaultPlaylist, playlist_type::PlaylistType,
+            },
+            domain::{
+                audio_player::audio_output::OutputMode,
+                mpris::{dbus_connection::DBusConnection, mpris_server::MprisServer},
+                prima::Prima,
+            },
+        };
+        use std::{
+            io::{BufRead, BufReader},
+            process::{Command, Stdio},
+            sync::Arc,
+        };
+
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+
+        let data_file = test_data_file(name);
+        let dir = data_file.parent().unwrap().to_path_buf();
+        let db_url = dir.join("favourite.db").to_string_lossy().to_string();
+
+        write_silent_wav(&dir.join("first.wav"), 100);
+        write_silent_wav(&dir.join("second.wav"), 100);
+
+        let prima = Arc::new(Prima::open(data_file, db_url).unwrap());
+        prima.set_output_mode(OutputMode::Null);
+        prima.set_music_search_path(dir.clone());
+
+        let tracks = prima.get_all_tracks().unwrap();
+        let playlist = DefaultPlaylist::new(None, PlaylistType::default(), tracks.clone(), 1);
+        prima.play(playlist).unwrap();
+        prima.pause_playback();
+
+        let server = MprisServer::start_on(prima.clone(), address.trim()).unwrap();
+        let client = DBusConnection::open(address.trim()).unwrap();
+        assert_ne!(client.get_unique_name(), server.get_unique_name());
+
+        Some(Self {
+            daemon,
+            prima,
+            tracks,
+            server: Some(server),
+            client,
+        })
+    }
+
+    fn call(
+        &mut self,
+        interface: &str,
+        member: &str,
+        body: Vec<crate::domain::mpris::dbus_value::DBusValue>,
+    ) -> crate::error::Result<crate::domain::mpris::dbus_message::DBusMessage> {
+        use crate::domain::mpris::{
+            dbus_message::DBusMessage,
+            mpris_server::{MPRIS_BUS_NAME, MPRIS_PATH},
+        };
+
+        self.client.call(DBusMessage::method_call(
+            MPRIS_BUS_NAME,
+            MPRIS_PATH,
+            interface,
+            member,
+            body,
+        ))
+    }
+
+    /// Property of `Player` interface
+    fn get(&mut self, name: &str) -> crate::domain::mpris::dbus_value::DBusValue {
+        use crate::domain::mpris::dbus_value::DBusValue;
+
+        let body = vec![
+            DBusValue::String("org.mpris.MediaPlayer2.Player".to_string()),
+            DBusValue::String(name.to_string()),
+        ];
+
+        self.call("org.freedesktop.DBus.Properties", "Get", body)
+            .unwrap()
+            .body
+            .remove(0)
+    }
+
+    fn set_body(
+        name: &str,
+        value: crate::domain::mpris::dbus_value::DBusValue,
+    ) -> Vec<crate::domain::mpris::dbus_value::DBusValue> {
+        use crate::domain::mpris::dbus_value::DBusValue;
+
+        vec![
+            DBusValue::String("org.mpris.MediaPlayer2.Player".to_string()),
+            DBusValue::String(name.to_string()),
+            DBusValue::variant(value),
+        ]
+    }
+
+    /// Sets property of `Player` interface
+    fn set(&mut self, name: &str, value: crate::domain::mpris::dbus_value::DBusValue) {
+        use crate::domain::mpris::dbus_message::MessageType;
+
+        let reply = self.call(
+            "org.freedesktop.DBus.Properties",
+            "Set",
+            Self::set_body(name, value),
+        );
+
+        assert_eq!(reply.unwrap().message_type, MessageType::MethodReturn)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for MprisBus {
+    fn drop(&mut self) {
+        drop(self.server.take());
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
 }
 
+#[cfg(target_os = "linux")]
 #[test]
-fn load_track_order_test() {
-    use crate::{data::utils::track_order::TrackOrder, domain::storage_util::StorageUtil};
-    assert_eq!(StorageUtil::load_track_order(), TrackOrder::default())
+fn mpris_get_properties_test() {
+    use crate::domain::mpris::dbus_value::DBusValue;
+
+    let Some(mut bus) = MprisBus::start("mpris_get_properties") else {
+        return;
+    };
+
+    assert_eq!(bus.get("PlaybackStatus").as_str(), Some("Paused"));
+
+    let metadata = bus.get("Metadata");
+
+    let entry = |key: &str| {
+        metadata
+            .as_array()
+            .unwrap()
+            .iter()
+            .find_map(|entry| match entry {
+                DBusValue::DictEntry(k, v) if k.as_str() == Some(key) => Some((**v).clone()),
+                _ => None,
+            })
+    };
+
+    assert_eq!(
+        entry("mpris:trackid").unwrap().as_str(),
+        Some("/com/paranid5/prima/Track/1")
+    );
+    assert_eq!(entry("xesam:title").unwrap().as_str(), Some("second"));
+    assert_eq!(entry("mpris:length").unwrap().as_i64(), Some(100_000));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn mpris_set_properties_test() {
+    use crate::domain::{
+        audio_player::playback_params::LoopingState, mpris::dbus_value::DBusValue,
+    };
+
+    let Some(mut bus) = MprisBus::start("mpris_set_properties") else {
+        return;
+    };
+
+    bus.set("LoopStatus", DBusValue::String("Track".to_string()));
+    assert_eq!(bus.prima.get_looping_state(), LoopingState::Track);
+
+    bus.set("Volume", DBusValue::Double(0.5));
+    assert_eq!(bus.prima.get_volume(), 0.5);
+
+    bus.set("Shuffle", DBusValue::Boolean(true));
+    assert!(bus.prima.is_shuffled());
+    assert_eq!(bus.prima.get_cur_track_index(), 0);
+    assert_eq!(bus.prima.get_cur_track(), Some(bus.tracks[1].clone()));
+
+    bus.set("Shuffle", DBusValue::Boolean(false));
+    assert!(!bus.prima.is_shuffled());
+    assert_eq!(bus.prima.get_cur_track_index(), 1);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn mpris_get_tracks_metadata_test() {
+    use crate::domain::mpris::dbus_value::DBusValue;
+
+    let Some(mut bus) = MprisBus::start("mpris_get_tracks_metadata") else {
+        return;
+    };
+
+    // Unknown ids are skipped
+    let reply = bus
+        .call(
+            "org.mpris.MediaPlayer2.TrackList",
+            "GetTracksMetadata",
+            vec![DBusValue::object_path_array([
+                "/com/paranid5/prima/Track/0".to_string(),
+                "/com/paranid5/prima/Track/5".to_string(),
+            ])],
+        )
+        .unwrap();
+
+    assert_eq!(reply.body[0].as_array().unwrap().len(), 1);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn mpris_remove_track_test() {
+    use crate::{
+        data::entities::{
+            playlists::playlist_trait::PlaylistTrait, tracks::track_trait::TrackTrait,
+        },
+        domain::mpris::dbus_value::DBusValue,
+    };
+
+    let Some(mut bus) = MprisBus::start("mpris_remove_track") else {
+        return;
+    };
+
+    bus.call(
+        "org.mpris.MediaPlayer2.TrackList",
+        "RemoveTrack",
+        vec![DBusValue::ObjectPath(
+            "/com/paranid5/prima/Track/0".to_string(),
+        )],
+    )
+    .unwrap();
+
+    assert_eq!(bus.prima.get_cur_playlist().len(), 1);
+    assert_eq!(bus.prima.get_cur_track_index(), 0);
+    assert_eq!(
+        bus.prima.get_cur_track().unwrap().get_path(),
+        bus.tracks[1].get_path()
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn mpris_unknown_method_test() {
+    let Some(mut bus) = MprisBus::start("mpris_unknown_method") else {
+        return;
+    };
+
+    assert!(bus
+        .call("org.mpris.MediaPlayer2.Player", "Unknown", vec![])
+        .is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn mpris_introspect_test() {
+    let Some(mut bus) = MprisBus::start("mpris_introspect") else {
+        return;
+    };
+
+    let introspection = bus.call("org.freedesktop.DBus.Introspectable", "Introspect", vec![]);
+
+    assert!(introspection.unwrap().body[0]
+        .as_str()
+        .unwrap()
+        .contains("org.mpris.MediaPlayer2.TrackList"));
+}
+
+/// Signals are sent to the client that subscribed to them
+#[cfg(target_os = "linux")]
+#[test]
+fn mpris_signals_test() {
+    use crate::domain::mpris::{
+        dbus_connection::{BUS_NAME, BUS_PATH},
+        dbus_message::{DBusMessage, MessageType},
+        dbus_value::DBusValue,
+        mpris_server::{MPRIS_BUS_NAME, MPRIS_PATH},
+    };
+
+    let Some(mut bus) = MprisBus::start("mpris_signals") else {
+        return;
+    };
+
+    let rule = format!(
+        "type='signal',sender='{}'",
+        bus.server.as_ref().unwrap().get_unique_name()
+    );
+
+    bus.client
+        .call(DBusMessage::method_call(
+            BUS_NAME,
+            BUS_PATH,
+            BUS_NAME,
+            "AddMatch",
+            vec![DBusValue::String(rule)],
+        ))
+        .unwrap();
+
+    // Signals are sent before the reply, so they are read while waiting for it
+    let mut signals_of = |member: &str, body: Vec<DBusValue>| {
+        let interface = match member {
+            "Set" => "org.freedesktop.DBus.Properties",
+            _ => "org.mpris.MediaPlayer2.Player",
+        };
+
+        let serial = bus
+            .client
+            .get_sender()
+            .send(&DBusMessage::method_call(
+                MPRIS_BUS_NAME,
+                MPRIS_PATH,
+                interface,
+                member,
+                body,
+            ))
+            .unwrap();
+
+        let mut signals = vec![];
+
+        loop {
+            let message = bus.client.read_message().unwrap();
+
+            match message.message_type {
+                MessageType::Signal => signals.push(message),
+                _ if message.reply_serial == Some(serial) => return signals,
+                _ => {}
+            }
+        }
+    };
+
+    let signals = signals_of("Set", MprisBus::set_body("Volume", DBusValue::Double(0.5)));
+    assert_eq!(signals.len(), 1);
+    assert_eq!(
+        signals[0].get_method(),
+        ("org.freedesktop.DBus.Properties", "PropertiesChanged")
+    );
+    assert_eq!(
+        signals[0].body[0].as_str(),
+        Some("org.mpris.MediaPlayer2.Player")
+    );
+
+    let signals = signals_of(
+        "SetPosition",
+        vec![
+            DBusValue::ObjectPath("/com/paranid5/prima/Track/1".to_string()),
+            DBusValue::Int64(50_000),
+        ],
+    );
+    assert_eq!(signals.len(), 1);
+    assert_eq!(
+        signals[0].get_method(),
+        ("org.mpris.MediaPlayer2.Player", "Seeked")
+    );
+    assert_eq!(signals[0].body[0].as_i64(), Some(50_000));
+}
+
+#[test]
+fn json_round_trip_test() {
+    use crate::data::utils::json::JsonValue;
+
+    let text = r#"{"name":"Tom \"Jr\"\n","tags":["a",1.5,true,null],"nested":{"x":-2}}"#;
+    let value = JsonValue::parse(text).unwrap();
+
+    assert_eq!(value.get("name").unwrap().as_str(), Some("Tom \"Jr\"\n"));
+    assert_eq!(
+        value.get("nested").unwrap().get("x").unwrap().as_f64(),
+        Some(-2.0)
+    );
+    assert_eq!(value.get("tags").unwrap().as_array().unwrap().len(), 4);
+    assert_eq!(value.to_string(), text);
+
+    let emoji = JsonValue::parse(r#""🎵 é""#).unwrap();
+    assert_eq!(emoji.as_str(), Some("🎵 é"));
+
+    assert!(JsonValue::parse("[1, 2").is_err());
+    assert!(JsonValue::parse("{} {}").is_err());
+    assert!(JsonValue::parse(&"[".repeat(100)).is_err());
+    assert_eq!(JsonValue::from(0.1_f32).to_string(), "0.1");
+}
+
+#[test]
+fn websocket_accept_key_test() {
+    use crate::domain::http_api::websocket::{accept_key, base64_encode};
+
+    // Example of RFC 6455
+    assert_eq!(
+        accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+    assert_eq!(base64_encode(b"ab"), "YWI=");
+    assert_eq!(base64_encode(b""), "");
+}
+
+/// HTTP API of the session paused on the first of two silent tracks
+struct HttpApi {
+    prima: std::sync::Arc<crate::domain::prima::Prima>,
+    dir: PathBuf,
+    token: String,
+    server: crate::domain::http_api::http_api_server::HttpApiServer,
+}
+
+impl HttpApi {
+    fn start(name: &str) -> Self {
+        use crate::{
+            data::entities::playlists::{
+                default_playlist::DefaultPlaylist, playlist_type::PlaylistType,
+            },
+            domain::{
+                audio_player::audio_output::OutputMode, http_api::http_api_server::HttpApiServer,
+                prima::Prima,
+            },
+        };
+        use std::sync::Arc;
+
+        let data_file = test_data_file(name);
+        let dir = data_file.parent().unwrap().to_path_buf();
+        let db_url = dir.join("favourite.db").to_string_lossy().to_string();
+
+        write_silent_wav(&dir.join("first.wav"), 100);
+        write_silent_wav(&dir.join("second.wav"), 100);
+
+        let prima = Arc::new(Prima::open(data_file, db_url).unwrap());
+        prima.set_output_mode(OutputMode::Null);
+        prima.set_music_search_path(dir.clone());
+
+        let tracks = prima.get_all_tracks().unwrap();
+        let playlist = DefaultPlaylist::new(None, PlaylistType::default(), tracks, 0);
+        prima.play(playlist).unwrap();
+        prima.pause_playback();
+
+        let token = prima.get_http_api_token().unwrap();
+        let server = HttpApiServer::start(prima.clone(), 0, token.clone(), false).unwrap();
+
+        Self {
+            prima,
+            dir,
+            token,
+            server,
+        }
+    }
+
+    /// Sends request with the bearer token and returns status and body of the response
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, String) {
+        self.request_as(method, path, Some(&self.token), body)
+    }
+
+    fn request_as(
+        &self,
+        method: &str,
+        path: &str,
+        auth: Option<&str>,
+        body: &str,
+    ) -> (u16, String) {
+        use std::io::{Read, Write};
+
+        let mut stream = self.connect();
+
+        let auth = auth
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{}Content-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            auth,
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head[9..12].parse::<u16>().unwrap();
+        (status, body.to_string())
+    }
+
+    fn connect(&self) -> std::net::TcpStream {
+        std::net::TcpStream::connect(("127.0.0.1", self.server.get_port())).unwrap()
+    }
+}
+
+#[test]
+fn http_api_token_test() {
+    let api = HttpApi::start("http_api_token");
+
+    // Server is started explicitly, token is generated once and kept
+    assert!(!api.prima.is_http_api_enabled());
+    assert!(!api.prima.is_http_api_lan_enabled());
+    assert_eq!(api.token.len(), 64);
+    assert_eq!(api.prima.get_http_api_token().unwrap(), api.token);
+}
+
+#[test]
+fn http_api_auth_test() {
+    let api = HttpApi::start("http_api_auth");
+
+    assert_eq!(api.request_as("GET", "/api/state", None, "").0, 401);
+    assert_eq!(
+        api.request_as("GET", "/api/state", Some("wrong"), "").0,
+        401
+    );
+    assert_eq!(api.request("GET", "/api/state", "").0, 200);
+
+    // Browsers can't set headers of WebSocket, so the token may be passed in the query
+    let path = format!("/api/state?token={}", api.token);
+    assert_eq!(api.request_as("GET", &path, None, "").0, 200);
+}
+
+#[test]
+fn http_api_routing_test() {
+    let api = HttpApi::start("http_api_routing");
+
+    assert_eq!(api.request("GET", "/api/unknown", "").0, 404);
+    assert_eq!(api.request("DELETE", "/api/state", "").0, 405);
+}
+
+#[test]
+fn http_api_state_test() {
+    use crate::data::utils::json::JsonValue;
+
+    let api = HttpApi::start("http_api_state");
+
+    let (status, body) = api.request("GET", "/api/state", "");
+    assert_eq!(status, 200);
+
+    let state = JsonValue::parse(&body).unwrap();
+    assert_eq!(state.get("is_playing").unwrap().as_bool(), Some(false));
+    assert_eq!(state.get("queue_length").unwrap().as_f64(), Some(2.0));
+
+    let title = state.get("track").unwrap().get("title").unwrap();
+    assert_eq!(title.as_str(), Some("first"));
+}
+
+#[test]
+fn http_api_settings_test() {
+    use crate::domain::audio_player::playback_params::LoopingState;
+
+    let api = HttpApi::start("http_api_settings");
+
+    assert_eq!(
+        api.request("PUT", "/api/volume", r#"{"volume": 0.25}"#).0,
+        204
+    );
+    assert_eq!(api.prima.get_volume(), 0.25);
+
+    let looping = api.request("PUT", "/api/looping", r#"{"looping": "track"}"#);
+    assert_eq!(looping.0, 204);
+    assert_eq!(api.prima.get_looping_state(), LoopingState::Track);
+
+    // Malformed JSON and missing fields
+    assert_eq!(api.request("PUT", "/api/speed", "{").0, 400);
+    assert_eq!(api.request("PUT", "/api/speed", "{}").0, 400);
+}
+
+#[test]
+fn http_api_queue_test() {
+    use crate::data::{entities::playlists::playlist_trait::PlaylistTrait, utils::json::JsonValue};
+
+    let api = HttpApi::start("http_api_queue");
+
+    assert_eq!(api.request("DELETE", "/api/queue/7", "").0, 409);
+    assert_eq!(api.request("DELETE", "/api/queue/1", "").0, 204);
+    assert_eq!(api.prima.get_cur_playlist().get_tracks().len(), 1);
+
+    let second = api.dir.join("second.wav").to_string_lossy().to_string();
+    let body = JsonValue::object([("paths", vec![second].into())]).to_string();
+
+    assert_eq!(api.request("POST", "/api/queue", &body).0, 204);
+    assert_eq!(api.prima.get_cur_playlist().get_tracks().len(), 2);
+}
+
+#[test]
+fn http_api_library_test() {
+    use crate::data::utils::json::JsonValue;
+
+    let api = HttpApi::start("http_api_library");
+
+    let (status, body) = api.request("GET", "/api/library/tracks", "");
+    assert_eq!(status, 200);
+    assert_eq!(
+        JsonValue::parse(&body).unwrap().as_array().unwrap().len(),
+        2
+    );
+}
+
+#[test]
+fn http_api_events_test() {
+    use crate::data::utils::json::JsonValue;
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+
+    let api = HttpApi::start("http_api_events");
+
+    // Event stream starts with the state, then pushes changes
+    let mut stream = api.connect();
+
+    write!(
+        stream,
+        "GET /api/events?token={} HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+         Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+         Sec-WebSocket-Version: 13\r\n\r\n",
+        api.token
+    )
+    .unwrap();
+
+    let read_message = |stream: &mut TcpStream| {
+        let mut header = [0_u8; 2];
+        stream.read_exact(&mut header).unwrap();
+
+        let length = match header[1] & 0x7F {
+            126 => {
+                let mut length = [0_u8; 2];
+                stream.read_exact(&mut length).unwrap();
+                u16::from_be_bytes(length) as usize
+            }
+
+            length => length as usize,
+        };
+
+        let mut payload = vec![0_u8; length];
+        stream.read_exact(&mut payload).unwrap();
+        JsonValue::parse(std::str::from_utf8(&payload).unwrap()).unwrap()
+    };
+
+    let mut head = Vec::new();
+
+    while !head.ends_with(b"\r\n\r\n") {
+        let mut byte = [0_u8];
+        stream.read_exact(&mut byte).unwrap();
+        head.push(byte[0])
+    }
+
+    let head = String::from_utf8(head).unwrap();
+    assert!(head.starts_with("HTTP/1.1 101"));
+    assert!(head.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+
+    let state = read_message(&mut stream);
+    assert_eq!(state.get("type").unwrap().as_str(), Some("state"));
+
+    let second = api.dir.join("second.wav").to_string_lossy().to_string();
+    let body = JsonValue::object([("paths", vec![second].into())]).to_string();
+    assert_eq!(api.request("POST", "/api/queue", &body).0, 204);
+
+    let event = loop {
+        let event = read_message(&mut stream);
+
+        if event.get("type").unwrap().as_str() != Some("position") {
+            break event;
+        }
+    };
+
+    assert_eq!(event.get("type").unwrap().as_str(), Some("queue_changed"));
+    assert_eq!(event.get("length").unwrap().as_f64(), Some(3.0));
+}
+
+#[test]
+fn mpd_command_test() {
+    use crate::domain::mpd::{mpd_command::MpdCommand, mpd_filter::MpdFilter};
+
+    let command = MpdCommand::parse(r#"find "(Artist == \"A \\\"B\\\"\")" sort Title"#).unwrap();
+    assert_eq!(command.name, "find");
+    assert_eq!(command.args.len(), 3);
+    assert_eq!(command.args[0], r#"(Artist == "A \"B\"")"#);
+    assert!(command.range_arg(2, 10).is_err());
+    assert!(MpdCommand::parse("add \"unclosed").is_err());
+
+    let command = MpdCommand::parse("playlistinfo 2:").unwrap();
+    assert_eq!(command.range_arg(0, 5).unwrap(), (2, 5));
+    assert_eq!(command.range_arg(1, 5).unwrap(), (0, 5));
+
+    let (filter, rest) = MpdFilter::parse(&command.args[..0], false).unwrap();
+    assert!(filter.is_empty());
+    assert!(rest.is_empty());
+
+    let args = [
+        "((artist == 'A') AND (!(album contains 'x')))",
+        "window",
+        "0:1",
+    ]
+    .map(String::from);
+
+    let (filter, rest) = MpdFilter::parse(&args, false).unwrap();
+    assert!(!filter.is_empty());
+    assert_eq!(rest, &args[1..]);
+
+    let args = ["(artist =~ 'A')".to_string()];
+    assert!(MpdFilter::parse(&args, false).is_err());
+
+    let args = ["artist".to_string()];
+    assert!(MpdFilter::parse(&args, false).is_err());
+}
+
+#[test]
+fn mpd_server_test() {
+    use crate::{
+        data::entities::playlists::{
+            default_playlist::DefaultPlaylist, playlist_trait::PlaylistTrait,
+            playlist_type::PlaylistType,
+        },
+        domain::{
+            audio_player::{audio_output::OutputMode, playback_params::LoopingState},
+            mpd::mpd_server::MpdServer,
+            prima::Prima,
+        },
+    };
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpStream,
+        sync::Arc,
+    };
+
+    let data_file = test_data_file("mpd_server");
+    let dir = data_file.parent().unwrap().to_path_buf();
+    let db_url = dir.join("favourite.db").to_string_lossy().to_string();
+
+    write_silent_wav(&dir.join("first.wav"), 100);
+    write_silent_wav(&dir.join("second.wav"), 100);
+
+    let prima = Arc::new(Prima::open(data_file, db_url).unwrap());
+    prima.set_output_mode(OutputMode::Null);
+    prima.set_music_search_path(dir.clone());
+
+    let tracks = prima.get_all_tracks().unwrap();
+    let playlist = DefaultPlaylist::new(None, PlaylistType::default(), tracks, 0);
+    prima.play(playlist).unwrap();
+    prima.pause_playback();
+
+    assert!(!prima.is_mpd_enabled());
+    assert!(!prima.is_mpd_lan_enabled());
+
+    let password = prima.get_mpd_password().unwrap();
+    assert_eq!(prima.get_mpd_password().unwrap(), password);
+    assert_ne!(password, prima.get_http_api_token().unwrap());
+
+    let server = MpdServer::start(prima.clone(), 0, Some("secret".to_string()), false).unwrap();
+
+    let stream = TcpStream::connect(("127.0.0.1", server.get_port())).unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+
+    let mut greeting = String::new();
+    reader.read_line(&mut greeting).unwrap();
+    assert!(greeting.starts_with("OK MPD "));
+
+    // Reads the reply until its last `OK` or `ACK` line
+    let mut command = |command: &str| {
+        writeln!(writer, "{}", command).unwrap();
+        let mut lines = Vec::new();
+
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end().to_string();
+
+            if line == "OK" || line.starts_with("ACK") {
+                lines.push(line);
+                return lines;
+            }
+
+            lines.push(line)
+        }
+    };
+
+    let status = command("status");
+    assert!(status.contains(&"playlistlength: 2".to_string()));
+    assert!(status.contains(&"song: 0".to_string()));
+    assert_eq!(status.last().unwrap(), "OK");
+
+    assert_eq!(command("setvol 40"), ["OK"]);
+    assert_eq!(prima.get_volume(), 0.4);
+    assert!(command("setvol 400")[0].starts_with("ACK [2@0] {setvol}"));
+
+    assert_eq!(command("single 1"), ["OK"]);
+    assert_eq!(prima.get_looping_state(), LoopingState::Track);
+    assert_eq!(command("repeat 0"), ["OK"]);
+    assert_eq!(prima.get_looping_state(), LoopingState::NoLooping);
+
+    let info = command("playlistinfo");
+    assert!(info.contains(&"file: first.wav".to_string()));
+    assert!(info.contains(&"Pos: 1".to_string()));
+    assert!(info.contains(&"Title: second".to_string()));
+
+    let found = command("find \"(title == 'second')\"");
+    assert_eq!(found.iter().filter(|l| l.starts_with("file:")).count(), 1);
+
+    let list = command("list title");
+    assert_eq!(list, ["Title: first", "Title: second", "OK"]);
+
+    // Files outside of the music folder are not reachable by any URI
+    let outside = test_data_file("mpd_server_outside").with_file_name("outside.wav");
+    write_silent_wav(&outside, 100);
+
+    let relative = outside.strip_prefix(dir.parent().unwrap()).unwrap();
+
+    for uri in [
+        outside.display().to_string(),
+        format!("../{}", relative.display()),
+    ] {
+        assert!(command(&format!("add \"{}\"", uri))[0].starts_with("ACK [50@0] {add}"));
+
+        assert!(command(&format!("albumart \"{}\" 0", uri))[0].starts_with("ACK [50@0] {albumart}"));
+    }
+
+    let reply = command("command_list_ok_begin\nping\nunknown\ncommand_list_end");
+    assert_eq!(reply[0], "list_OK");
+    assert!(reply[1].starts_with("ACK [5@1] {unknown}"));
+
+    // Idle reports changes of the queue made by other clients
+    writeln!(writer, "idle playlist").unwrap();
+    prima.add_to_queue(vec![prima.get_cur_track().unwrap()], None);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(line.trim_end(), "changed: playlist");
+
+    line.clear();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(line.trim_end(), "OK");
+
+    writeln!(writer, "delete 2").unwrap();
+    line.clear();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(line.trim_end(), "OK");
+    assert_eq!(prima.get_cur_playlist().get_tracks().len(), 2);
+
+    drop(server);
+}
+
+#[test]
+fn lrc_parse_test() {
+    use crate::domain::lyrics::lyrics::Lyrics;
+    use std::time::Duration;
+
+    let lrc = "\u{feff}[ar:Artist]\n[offset:+500]\n\n[00:01.00][00:10.50]Chorus\n\
+               [00:05.00]<00:05.00>Word <00:05.50>by <00:06.00>word\n[00:08]\n";
+
+    let lyrics = Lyrics::parse(lrc.trim_start_matches('\u{feff}'));
+    assert!(lyrics.is_synced());
+    assert_eq!(lyrics.get_tag("ar"), Some("Artist"));
+    assert_eq!(lyrics.get_offset_millis(), 500);
+
+    let lines = lyrics.get_lines();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0].text, "Chorus");
+    assert_eq!(lines[1].text, "Word by word");
+    assert_eq!(lines[1].words.len(), 3);
+    assert_eq!(lines[2].text, "");
+    assert_eq!(lines[3].time, Duration::from_millis(10_500));
+
+    // Offset shows lines half a second earlier
+    assert_eq!(lyrics.active_line_index(Duration::from_millis(400)), None);
+    assert_eq!(
+        lyrics.active_line_index(Duration::from_millis(600)),
+        Some(0)
+    );
+    assert_eq!(
+        lyrics.active_line_index(Duration::from_millis(5_200)),
+        Some(1)
+    );
+    assert_eq!(
+        lyrics.active_word_index(Duration::from_millis(5_200)),
+        Some(1)
+    );
+    assert_eq!(lyrics.active_line_index(Duration::from_secs(60)), Some(3));
+
+    assert_eq!(Lyrics::parse(&lyrics.to_lrc()), lyrics);
+
+    let plain = Lyrics::parse("First line\n[not a tag] second\n");
+    assert!(!plain.is_synced());
+    assert_eq!(plain.get_lines()[1].text, "[not a tag] second");
+    assert_eq!(plain.active_line_index(Duration::from_secs(1)), None);
+}
+
+#[test]
+fn track_lyrics_test() {
+    use crate::domain::{
+        lyrics::{lyrics::Lyrics, track_lyrics::TrackLyrics},
+        metadata_reader::MetadataReader,
+    };
+    use std::{fs, time::Duration};
+
+    let data_file = test_data_file("track_lyrics");
+    let dir = data_file.parent().unwrap().to_path_buf();
+    let track = dir.join("song.wav");
+    write_silent_wav(&track, 100);
+
+    assert_eq!(TrackLyrics::read(&track), None);
+
+    fs::write(dir.join("song.LRC"), "[00:00.05]Hello\n").unwrap();
+    let lyrics = TrackLyrics::read(&track).unwrap();
+    assert_eq!(
+        lyrics.active_line(Duration::from_millis(60)).unwrap().text,
+        "Hello"
+    );
+
+    let mut edited = Lyrics::parse("[00:00.02]Edited\n");
+    edited.set_tag("by", "prima");
+    assert_eq!(
+        TrackLyrics::save(&track, &edited).unwrap(),
+        dir.join("song.LRC")
+    );
+    assert_eq!(TrackLyrics::read(&track), Some(edited));
+
+    // ID3v2.3 tag with SYLT frame in UTF-16 and no audio after it
+    let mut sylt = vec![1_u8];
+    sylt.extend_from_slice(b"eng");
+    sylt.extend_from_slice(&[2, 1, 0xFF, 0xFE, 0, 0]);
+
+    for (text, millis) in [("Hi", 1_000_u32), ("there", 2_000)] {
+        sylt.extend_from_slice(&[0xFF, 0xFE]);
+        text.encode_utf16()
+            .for_each(|unit| sylt.extend_from_slice(&unit.to_le_bytes()));
+        sylt.extend_from_slice(&[0, 0]);
+        sylt.extend_from_slice(&millis.to_be_bytes())
+    }
+
+    let mut frame = b"SYLT".to_vec();
+    frame.extend_from_slice(&(sylt.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&[0, 0]);
+    frame.extend(sylt);
+
+    let size = frame.len() as u32;
+    let mut tag = b"ID3\x03\x00\x00".to_vec();
+    tag.extend((0..4).rev().map(|i| ((size >> (i * 7)) & 0x7F) as u8));
+    tag.extend(frame);
+
+    let mp3 = dir.join("synced.mp3");
+    fs::write(&mp3, tag).unwrap();
+
+    let lyrics = MetadataReader::read_lyrics(&mp3).unwrap();
+    assert!(lyrics.is_synced());
+    assert_eq!(lyrics.get_lines().len(), 2);
+    assert_eq!(
+        lyrics
+            .active_line(Duration::from_millis(2_500))
+            .unwrap()
+            .text,
+        "there"
+    );
+}
+
+#[tokio::test]
+async fn scrobble_rules_test() {
+    use crate::{
+        domain::{
+            scrobbling::{
+                lastfm_submitter::md5_hex,
+                scrobble::Scrobble,
+                scrobble_queue::retry_delay_secs,
+                scrobbling_settings::{LastFmAccount, ListenBrainzAccount, ScrobblingSettings},
+            },
+            storage_util::StorageUtil,
+        },
+        DefaultTrack,
+    };
+    use chrono::{Duration as ChronoDuration, Local};
+    use std::time::Duration;
+
+    assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+    assert_eq!(md5_hex(b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+
+    let track = |artist: Option<&str>, seconds| {
+        DefaultTrack::new(
+            Some("Title".to_string()),
+            artist.map(String::from),
+            None,
+            PathBuf::from("/music/track.mp3"),
+            ChronoDuration::seconds(seconds),
+            Local::now(),
+            0,
+        )
+    };
+
+    assert_eq!(Scrobble::from_track(&track(None, 200), 0), None);
+    assert_eq!(Scrobble::from_track(&track(Some(" "), 200), 0), None);
+
+    let short = Scrobble::from_track(&track(Some("Artist"), 30), 0).unwrap();
+    assert_eq!(short.required_play_time(), None);
+    assert!(!short.is_played_enough(Duration::from_secs(30)));
+
+    let regular = Scrobble::from_track(&track(Some("Artist"), 200), 0).unwrap();
+    assert_eq!(regular.required_play_time(), Some(Duration::from_secs(100)));
+    assert!(!regular.is_played_enough(Duration::from_secs(99)));
+    assert!(regular.is_played_enough(Duration::from_secs(100)));
+
+    let long = Scrobble::from_track(&track(Some("Artist"), 3_600), 0).unwrap();
+    assert_eq!(long.required_play_time(), Some(Duration::from_secs(240)));
+
+    assert_eq!(retry_delay_secs(1), 30);
+    assert_eq!(retry_delay_secs(2), 60);
+    assert_eq!(retry_delay_secs(100), 6 * 60 * 60);
+
+    let path = test_data_file("scrobbling_settings");
+    let mut storage_util = StorageUtil::from_file(path.clone()).await;
+    assert!(!storage_util.load_scrobbling_settings().is_enabled());
+
+    let settings = ScrobblingSettings {
+        listenbrainz: Some(ListenBrainzAccount {
+            api_url: None,
+            token: "token".to_string(),
+        }),
+        lastfm: Some(LastFmAccount {
+            api_url: Some("https://libre.fm/2.0/".to_string()),
+            api_key: "key".to_string(),
+            api_secret: "secret".to_string(),
+            session_key: "session".to_string(),
+        }),
+    };
+
+    storage_util.store_scrobbling_settings(settings.clone());
+    assert!(storage_util.flush().await.is_ok());
+
+    let storage_util = StorageUtil::from_file(path).await;
+    assert_eq!(storage_util.load_scrobbling_settings(), &settings);
+    assert_eq!(
+        storage_util.load_scrobbling_settings().submitters().len(),
+        2
+    );
+}
+
+#[test]
+fn curl_config_test() {
+    use crate::data::utils::http_client::curl_config;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        process::{Command, Stdio},
+        thread,
+    };
+
+    let body = b"@not_a_file \"quoted\" back\\slash\nline\ttab";
+    let config = curl_config("http://x", &[("Authorization", "Token secret")], Some(body)).unwrap();
+
+    assert!(!String::from_utf8_lossy(&config).contains('\t'));
+    assert!(curl_config("http://x", &[], Some(b"\0")).is_err());
+
+    // Config is checked with the real curl when it's installed
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let url = format!(
+        "http://127.0.0.1:{}/submit",
+        listener.local_addr().unwrap().port()
+    );
+    let config = curl_config(&url, &[("Authorization", "Token secret")], Some(body)).unwrap();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+
+        while !request.ends_with(body) {
+            let read = stream.read(&mut buf).unwrap();
+            assert_ne!(read, 0);
+            request.extend(&buf[..read]);
+        }
+
+        stream
+            .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        String::from_utf8(request).unwrap()
+    });
+
+    let child = Command::new("curl")
+        .args(["--silent", "--request", "POST", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_) => return,
+    };
+
+    child.stdin.take().unwrap().write_all(&config).unwrap();
+    assert!(child.wait().unwrap().success());
+
+    let request = server.join().unwrap();
+    assert!(request.starts_with("POST /submit HTTP/1.1\r\n"));
+    assert!(request.contains("\r\nAuthorization: Token secret\r\n"));
+    assert!(request.contains(&format!("\r\nContent-Length: {}\r\n", body.len())));
+}
+
+#[test]
+fn http_line_break_test() {
+    use crate::{
+        data::utils::http_client::{curl_config, HttpClient},
+        error::Error,
+    };
+    use std::{io::ErrorKind, net::TcpListener};
+
+    // Requests are rejected before anything is sent
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let url = format!("http://{}/feed", listener.local_addr().unwrap());
+    listener.set_nonblocking(true).unwrap();
+
+    let is_rejected = |result: crate::error::Result<()>| matches!(result, Err(Error::Io(e)) if e.kind() == ErrorKind::InvalidInput);
+
+    let injected = [("Range", "bytes=0-\r\nX-Evil: 1")];
+    assert!(is_rejected(HttpClient::get(&url, &injected).map(|_| ())));
+    assert!(is_rejected(
+        HttpClient::open_stream(&url, &injected).map(|_| ())
+    ));
+    assert!(is_rejected(
+        HttpClient::get(&url, &[("X-Evil\n", "1")]).map(|_| ())
+    ));
+    assert!(is_rejected(
+        HttpClient::post(&format!("{}\r\nHost: evil", url), &[], b"").map(|_| ())
+    ));
+    assert!(listener.accept().is_err());
+
+    assert!(curl_config("https://x", &injected, None).is_err());
+    assert!(curl_config("https://x/\n", &[], None).is_err());
+    assert!(curl_config("https://x", &[], Some(b"multi\nline")).is_ok());
+}
+
+#[test]
+fn scrobble_queue_test() {
+    use crate::{
+        data::{
+            databases::{favourites::db::establish_connection_to, scrobbles::db::create_tables},
+            utils::json::JsonValue,
+        },
+        domain::scrobbling::{
+            listenbrainz_submitter::ListenBrainzSubmitter, scrobble::Scrobble,
+            scrobble_queue::ScrobbleQueue, scrobble_submitter::ScrobbleSubmitter,
+        },
+    };
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::Arc,
+        thread,
+        time::Duration,
+    };
+
+    let data_file = test_data_file("scrobble_queue");
+    let dir = data_file.parent().unwrap().to_path_buf();
+    let db_url = dir.join("favourite.db").to_string_lossy().to_string();
+    create_tables(&mut establish_connection_to(&db_url).unwrap()).unwrap();
+
+    // Mock server replies with the given statuses and records requests
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let server = thread::spawn(move || {
+        [500, 200, 400]
+            .into_iter()
+            .map(|status| {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+
+                    if line == "\r\n" {
+                        break;
+                    }
+
+                    head.push_str(&line)
+                }
+
+                let len = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .unwrap()
+                    .parse::<usize>()
+                    .unwrap();
+
+                let mut body = vec![0; len];
+                reader.read_exact(&mut body).unwrap();
+
+                let reply = format!(
+                    "HTTP/1.1 {} Status\r\nContent-Length: 2\r\n\r\n{{}}",
+                    status
+                );
+
+                reader.get_mut().write_all(reply.as_bytes()).unwrap();
+                (head, String::from_utf8(body).unwrap())
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let submitter: Arc<dyn ScrobbleSubmitter> = Arc::new(ListenBrainzSubmitter::new(
+        Some(format!("http://127.0.0.1:{}/", port)),
+        "secret".to_string(),
+    ));
+
+    let queue = ScrobbleQueue::new(db_url, vec![submitter]);
+
+    let scrobble = |title: &str, listened_at| Scrobble {
+        artist: "Artist".to_string(),
+        title: title.to_string(),
+        album: None,
+        duration: Duration::from_secs(200),
+        listened_at,
+    };
+
+    queue.enqueue(&scrobble("Second", 20)).unwrap();
+    queue.enqueue(&scrobble("First", 10)).unwrap();
+    assert_eq!(queue.len().unwrap(), 2);
+
+    // Server error postpones the batch
+    assert_eq!(queue.flush_at(1_000).unwrap(), 0);
+    assert_eq!(queue.len().unwrap(), 2);
+    assert_eq!(queue.flush_at(1_029).unwrap(), 0);
+
+    assert_eq!(queue.flush_at(1_030).unwrap(), 2);
+    assert!(queue.is_empty().unwrap());
+
+    // Rejected listens are dropped
+    queue.enqueue(&scrobble("Invalid", 30)).unwrap();
+    assert_eq!(queue.flush_at(2_000).unwrap(), 0);
+    assert!(queue.is_empty().unwrap());
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests.len(), 3);
+
+    let (head, body) = &requests[1];
+    assert!(head.starts_with("POST /1/submit-listens HTTP/1.1"));
+    assert!(head.contains("Authorization: Token secret"));
+
+    let body = JsonValue::parse(body).unwrap();
+    assert_eq!(body.get("listen_type").unwrap().as_str(), Some("import"));
+
+    let titles = body
+        .get("payload")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|listen| {
+            let metadata = listen.get("track_metadata").unwrap();
+            metadata.get("track_name").unwrap().as_str().unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(titles, ["First", "Second"]);
+}
+
+#[test]
+fn sleep_timer_test() {
+    use crate::{
+        data::entities::{
+            playlists::{default_playlist::DefaultPlaylist, playlist_type::PlaylistType},
+            tracks::{default_track::DefaultTrack, track_trait::TrackTrait},
+        },
+        domain::{
+            audio_player::audio_output::OutputMode,
+            events::player_event::PlayerEvent,
+            prima::Prima,
+            sleep_timer::{fade_factor, playlist_time_left, SleepTimerMode},
+        },
+    };
+    use chrono::{Duration as ChronoDuration, Local};
+    use std::time::Duration;
+
+    let data_file = test_data_file("sleep_timer");
+    let dir = data_file.parent().unwrap().to_path_buf();
+    let music_dir = dir.join("music");
+    let db_url = dir.join("favourite.db").to_string_lossy().to_string();
+
+    std::fs::create_dir_all(&music_dir).unwrap();
+    write_silent_wav(&music_dir.join("first.wav"), 300);
+    write_silent_wav(&music_dir.join("second.wav"), 2_000);
+
+    let prima = Prima::open(data_file, db_url).unwrap();
+    prima.set_output_mode(OutputMode::Null);
+    prima.set_music_search_path(music_dir);
+
+    let mut tracks = prima.get_all_tracks().unwrap();
+    tracks.sort_by_key(|track| *track.get_duration());
+    assert_eq!(tracks.len(), 2);
+
+    let playlist =
+        |index| DefaultPlaylist::new(None, PlaylistType::default(), tracks.clone(), index);
+
+    let album_track = |album: &str, seconds| {
+        DefaultTrack::new(
+            Some("Title".to_string()),
+            Some("Artist".to_string()),
+            Some(album.to_string()),
+            PathBuf::from("/music/track.mp3"),
+            ChronoDuration::seconds(seconds),
+            Local::now(),
+            0,
+        )
+    };
+
+    let album = DefaultPlaylist::new(
+        None,
+        PlaylistType::default(),
+        vec![
+            album_track("First", 10),
+            album_track("First", 20),
+            album_track("First", 30),
+            album_track("Second", 40),
+        ],
+        1,
+    );
+
+    let position = Duration::from_secs(5);
+
+    assert_eq!(
+        playlist_time_left(SleepTimerMode::EndOfTrack, &album, position, 1.0),
+        Duration::from_secs(15)
+    );
+    assert_eq!(
+        playlist_time_left(SleepTimerMode::EndOfAlbum, &album, position, 2.0),
+        Duration::from_secs(45) / 2
+    );
+    assert_eq!(fade_factor(Duration::from_secs(30)), 0.5);
+    assert_eq!(fade_factor(Duration::from_secs(600)), 1.0);
+
+    let mut events = prima.get_event_bus().subscribe();
+
+    // Front-end's switch after the timer's track is suppressed,
+    // next track waits from its beginning
+    prima.start_sleep_timer(SleepTimerMode::EndOfTrack, false);
+    assert_eq!(
+        prima.get_sleep_timer_mode(),
+        Some(SleepTimerMode::EndOfTrack)
+    );
+
+    prima.play(playlist(0)).unwrap();
+    prima.next_track().unwrap();
+
+    assert!(!prima.is_playing());
+    assert_eq!(prima.get_cur_track_index(), 1);
+    assert_eq!(prima.get_playback_position(), Duration::ZERO);
+    assert_eq!(prima.get_sleep_timer_mode(), None);
+
+    // Timed stop pauses in the middle of the track and keeps the position
+    prima.start_sleep_timer(SleepTimerMode::After(Duration::from_millis(500)), true);
+
+    let time_left = prima.get_sleep_timer_time_left().unwrap();
+    assert!(time_left <= Duration::from_millis(500));
+
+    prima.play(playlist(1)).unwrap();
+
+    assert!(!prima.is_playing());
+    assert_eq!(prima.get_sleep_timer_time_left(), None);
+
+    let position = prima.get_playback_position();
+    assert!(position > Duration::ZERO && position < Duration::from_millis(2_000));
+
+    let finished = std::iter::from_fn(|| events.try_recv().ok())
+        .filter(|event| *event == PlayerEvent::SleepTimerFinished)
+        .count();
+
+    assert_eq!(finished, 2);
+
+    prima.start_sleep_timer(SleepTimerMode::EndOfAlbum, false);
+    prima.cancel_sleep_timer();
+    assert_eq!(prima.get_sleep_timer_mode(), None);
+}
+
+#[test]
+fn ab_loop_test() {
+    use crate::{
+        data::entities::{
+            playlists::{default_playlist::DefaultPlaylist, playlist_type::PlaylistType},
+            tracks::track_trait::TrackTrait,
+        },
+        domain::{
+            audio_player::{
+                ab_loop::{AbLoop, AbLoopSource},
+                audio_output::OutputMode,
+            },
+            prima::Prima,
+            storage_util::StorageUtil,
+        },
+    };
+    use rodio::{buffer::SamplesBuffer, Source};
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    let secs = Duration::from_secs;
+
+    assert_eq!(AbLoop::new(secs(5), secs(2), None), None);
+    assert_eq!(AbLoop::new(secs(2), secs(5), Some(0)), None);
+
+    // Segment 2..5 is played three times, then playback goes on
+    let ab_loop = AbLoop::new(secs(2), secs(5), Some(3)).unwrap();
+    let samples = (0..10).collect::<Vec<i16>>();
+    let source = Source::buffered(SamplesBuffer::new(1, 1, samples));
+
+    assert_eq!(
+        AbLoopSource::new(source.clone(), Duration::ZERO, ab_loop, 0).collect::<Vec<_>>(),
+        vec![0, 1, 2, 3, 4, 2, 3, 4, 2, 3, 4, 5, 6, 7, 8, 9]
+    );
+
+    // Restarted playback keeps the jumps that were made
+    assert_eq!(
+        AbLoopSource::new(source.clone(), secs(3), ab_loop, 1).collect::<Vec<_>>(),
+        vec![3, 4, 2, 3, 4, 5, 6, 7, 8, 9]
+    );
+
+    // Playback after B is not looped
+    assert_eq!(
+        AbLoopSource::new(source, secs(7), ab_loop, 0).collect::<Vec<_>>(),
+        vec![7, 8, 9]
+    );
+
+    assert_eq!(
+        ab_loop.wrap_position(Duration::from_millis(5_500)),
+        Duration::from_millis(2_500)
+    );
+
+    assert!(ab_loop.has_jumps_left(1));
+    assert!(!ab_loop.has_jumps_left(2));
+
+    let data_file = test_data_file("ab_loop");
+    let dir = data_file.parent().unwrap().to_path_buf();
+    let music_dir = dir.join("music");
+    let db_url = dir.join("favourite.db").to_string_lossy().to_string();
+
+    std::fs::create_dir_all(&music_dir).unwrap();
+    write_silent_wav(&music_dir.join("track.wav"), 600);
+
+    let prima = Arc::new(Prima::open(data_file.clone(), db_url).unwrap());
+    prima.set_output_mode(OutputMode::Null);
+    prima.set_music_search_path(music_dir);
+
+    let tracks = prima.get_all_tracks().unwrap();
+    let path = tracks[0].get_path().clone();
+
+    prima
+        .play(DefaultPlaylist::new(
+            None,
+            PlaylistType::default(),
+            tracks,
+            0,
+        ))
+        .unwrap();
+
+    let too_long = AbLoop::new(Duration::from_millis(100), secs(1), None);
+    assert!(Prima::set_ab_loop(prima.clone(), too_long).is_err());
+
+    let ab_loop = AbLoop::new(
+        Duration::from_millis(100),
+        Duration::from_millis(300),
+        Some(3),
+    );
+    Prima::set_ab_loop(prima.clone(), ab_loop).unwrap();
+    assert_eq!(prima.get_ab_loop(), ab_loop);
+
+    let start = Instant::now();
+    prima.replay_cur_track().unwrap();
+
+    assert!(start.elapsed() >= Duration::from_millis(900));
+    assert_eq!(prima.get_ab_loop_jumps(), 2);
+
+    prima.flush().unwrap();
+
+    let storage_util = tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(StorageUtil::from_file(data_file));
+
+    assert_eq!(storage_util.load_ab_loop(&path), ab_loop);
+
+    Prima::set_ab_loop(prima.clone(), None).unwrap();
+    assert_eq!(prima.get_ab_loop(), None);
+}
+
+#[test]
+fn bookmarks_test() {
+    use crate::{
+        data::entities::{
+            playlists::{default_playlist::DefaultPlaylist, playlist_type::PlaylistType},
+            tracks::track_trait::TrackTrait,
+        },
+        domain::{
+            audio_player::audio_output::OutputMode,
+            bookmarks::resume_position::{is_long_form, resume_position},
+            prima::Prima,
+        },
+    };
+    use std::{
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    let secs = Duration::from_secs;
+
+    assert!(is_long_form(secs(60), secs(60)));
+    assert!(!is_long_form(secs(59), secs(60)));
+    assert_eq!(resume_position(secs(5), secs(600)), None);
+    assert_eq!(resume_position(secs(300), secs(600)), Some(secs(300)));
+    assert_eq!(resume_position(secs(595), secs(600)), None);
+
+    let data_file = test_data_file("bookmarks");
+    let dir = data_file.parent().unwrap().to_path_buf();
+    let music_dir = dir.join("music");
+    let db_url = dir.join("favourite.db").to_string_lossy().to_string();
+
+    std::fs::create_dir_all(&music_dir).unwrap();
+    write_silent_wav(&music_dir.join("book.wav"), 30_000);
+    write_silent_wav(&music_dir.join("song.wav"), 300);
+
+    let prima = Arc::new(Prima::open(data_file, db_url).unwrap());
+    prima.set_output_mode(OutputMode::Null);
+    prima.set_music_search_path(music_dir);
+    prima.set_resume_threshold(secs(20));
+
+    assert!(prima.add_bookmark("Chapter", None).is_err());
+
+    let mut tracks = prima.get_all_tracks().unwrap();
+    tracks.sort_by_key(|track| *track.get_duration());
+
+    let song = tracks[0].get_path().clone();
+    let book = tracks[1].get_path().clone();
+
+    let playlist =
+        |index| DefaultPlaylist::new(None, PlaylistType::default(), tracks.clone(), index);
+
+    let wait_until = |condition: &dyn Fn() -> bool| {
+        let start = Instant::now();
+
+        while !condition() {
+            assert!(start.elapsed() < secs(5));
+            thread::sleep(Duration::from_millis(20));
+        }
+    };
+
+    let wait_until_playing =
+        |prima: &Prima, is_playing| wait_until(&|| prima.is_playing() == is_playing);
+
+    // Long track is left in the middle
+    let book_playlist = playlist(1);
+    Prima::spawn_action(prima.clone(), move |prima| prima.play(book_playlist)).unwrap();
+    wait_until_playing(&prima, true);
+
+    Prima::spawn_action(prima.clone(), move |prima| prima.seek_to(secs(15))).unwrap();
+    thread::sleep(Duration::from_millis(300));
+    wait_until_playing(&prima, true);
+
+    let bookmark = prima.add_bookmark(" ", Some("Intro is over")).unwrap();
+    assert_eq!(bookmark.path, book);
+    assert_eq!(bookmark.name, "0:15");
+    assert!(bookmark.position >= secs(15));
+
+    prima.pause_playback();
+
+    let left_at = prima.get_resume_position(&book).unwrap().unwrap();
+    assert!(left_at >= secs(15) && left_at < secs(17));
+
+    // Short track is never remembered and doesn't touch the long one
+    prima.play(playlist(0)).unwrap();
+    wait_until_playing(&prima, false);
+    assert_eq!(prima.get_resume_position(&song).unwrap(), None);
+    assert_eq!(prima.get_resume_position(&book).unwrap(), Some(left_at));
+
+    // Long track resumes where it was left
+    let book_playlist = playlist(1);
+    Prima::spawn_action(prima.clone(), move |prima| prima.play(book_playlist)).unwrap();
+    wait_until(&|| prima.get_playback_position() >= left_at);
+    prima.pause_playback();
+
+    // Jump moves to the bookmark's track
+    prima.play(playlist(0)).unwrap();
+    wait_until_playing(&prima, false);
+
+    let id = bookmark.id;
+    Prima::spawn_action(prima.clone(), move |prima| prima.jump_to_bookmark(id)).unwrap();
+    wait_until(&|| prima.get_playback_position() >= bookmark.position);
+    assert_eq!(prima.get_cur_track().unwrap().get_path(), &book);
+    prima.pause_playback();
+
+    assert!(prima.update_bookmark(id, "Chapter 1", None).unwrap());
+    assert_eq!(prima.get_bookmarks(Some(&song)).unwrap(), vec![]);
+
+    let bookmarks = prima.get_bookmarks(None).unwrap();
+    assert_eq!(bookmarks.len(), 1);
+    assert_eq!(bookmarks[0].name, "Chapter 1");
+    assert_eq!(bookmarks[0].note, None);
+
+    assert!(prima.remove_bookmark(id).unwrap());
+    assert!(!prima.remove_bookmark(id).unwrap());
+    assert!(prima.jump_to_bookmark(id).is_err());
+}
+
+#[test]
+fn live_source_frame_boundary_test() {
+    use crate::domain::audio_player::live_source::LiveSource;
+    use rodio::{buffer::SamplesBuffer, Source};
+
+    let stereo =
+        |left: i16, right: i16| Box::new(SamplesBuffer::new(2, 8000, [left, right].repeat(4)));
+    let (mut source, handle) = LiveSource::new(stereo(1, 2));
+
+    assert_eq!(source.next(), Some(1));
+    assert_eq!(source.current_frame_len(), None);
+
+    // Replacement waits for the right channel of the current frame
+    handle.replace(stereo(3, 4));
+    assert_eq!(source.current_frame_len(), Some(1));
+    assert_eq!(source.next(), Some(2));
+
+    handle.replace(Box::new(SamplesBuffer::new(1, 44100, vec![5_i16; 4])));
+    assert_eq!(source.current_frame_len(), Some(0));
+    assert_eq!(source.channels(), 1);
+    assert_eq!(source.sample_rate(), 44100);
+
+    assert_eq!(source.next(), Some(5));
+    assert_eq!(source.current_frame_len(), None);
+    assert_eq!(source.count(), 3);
+}
+
+#[test]
+fn decoder_seek_test() {
+    use crate::{
+        data::entities::playlists::{
+            default_playlist::DefaultPlaylist, playlist_type::PlaylistType,
+        },
+        domain::{
+            audio_export::flac_writer::FlacWriter,
+            audio_player::{audio_output::OutputMode, decoders::track_decoder::TrackDecoder},
+            events::player_event::PlayerEvent,
+            prima::Prima,
+        },
+    };
+    use hound::{SampleFormat, WavSpec, WavWriter};
+    use std::{
+        fs::File,
+        io::BufWriter,
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    let secs = Duration::from_secs;
+
+    let data_file = test_data_file("decoder_seek");
+    let dir = data_file.parent().unwrap().to_path_buf();
+    let music_dir = dir.join("music");
+    let db_url = dir.join("favourite.db").to_string_lossy().to_string();
+    let path = music_dir.join("lecture.wav");
+
+    std::fs::create_dir_all(&music_dir).unwrap();
+
+    // Every sample is the number of the second it's played at
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: 8000,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+
+    let mut writer = WavWriter::create(&path, spec).unwrap();
+
+    for frame in 0..40 * 8000 {
+        writer.write_sample((frame / 8000) as i16).unwrap();
+    }
+
+    writer.finalize().unwrap();
+
+    let mut decoder = TrackDecoder::open_at(&path, Duration::from_millis(25_500)).unwrap();
+    assert_eq!(decoder.next(), Some(25));
+    assert_eq!(decoder.count(), 14_500 * 8 - 1);
+
+    let decoder = TrackDecoder::open_at(&path, secs(60)).unwrap();
+    assert_eq!(decoder.count(), 0);
+
+    // FLAC is seeked by its demuxer too, stereo frames stay aligned
+    let flac_path = dir.join("lecture.flac");
+    let mut flac =
+        FlacWriter::new(BufWriter::new(File::create(&flac_path).unwrap()), 2, 8000).unwrap();
+
+    for frame in 0..40 * 8000 {
+        let second = (frame / 8000) as i16;
+        flac.write_sample(second).unwrap();
+        flac.write_sample(-second).unwrap();
+    }
+
+    flac.finalize().unwrap();
+
+    let mut decoder = TrackDecoder::open_at(&flac_path, Duration::from_millis(25_500)).unwrap();
+    assert_eq!(decoder.next(), Some(25));
+    assert_eq!(decoder.next(), Some(-25));
+    assert_eq!(decoder.count(), 14_500 * 8 * 2 - 2);
+
+    let prima = Arc::new(Prima::open(data_file, db_url).unwrap());
+    prima.set_output_mode(OutputMode::Null);
+    prima.set_music_search_path(music_dir);
+
+    let tracks = prima.get_all_tracks().unwrap();
+    let playlist = DefaultPlaylist::new(None, PlaylistType::default(), tracks, 0);
+    Prima::spawn_action(prima.clone(), move |prima| prima.play(playlist)).unwrap();
+
+    let start = Instant::now();
+
+    while !prima.is_playing() {
+        assert!(start.elapsed() < secs(5));
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    // Playing track is seeked in place: the call doesn't block
+    // until the track ends and playback is never stopped
+    let mut receiver = prima.get_event_bus().subscribe();
+    let start = Instant::now();
+
+    prima.seek_to(secs(30)).unwrap();
+    assert!(start.elapsed() < secs(5));
+    assert!(prima.is_playing());
+    assert!(prima.get_playback_position() >= secs(30));
+
+    let mut events = vec![];
+
+    while let Ok(event) = receiver.try_recv() {
+        events.push(event);
+    }
+
+    assert!(events.contains(&PlayerEvent::PositionTick { position: secs(30) }));
+
+    assert!(!events.contains(&PlayerEvent::StateChanged { is_playing: false }));
+    prima.pause_playback();
+}
+
+/// Mono 16-bit AIFF of 8000 Hz, every sample is the number of 50 ms part it's played in.
+/// AIFF-C with the compression type is written if it's given
+fn write_aiff_fixture(path: &std::path::Path, compression: Option<&[u8; 4]>) {
+    let frames = 4000_u32;
+    let exponent_shift = 8000_u64.leading_zeros();
+
+    let mut comm = vec![];
+    comm.extend(1_u16.to_be_bytes());
+    comm.extend(frames.to_be_bytes());
+    comm.extend(16_u16.to_be_bytes());
+    comm.extend(((16383 + 63 - exponent_shift) as u16).to_be_bytes());
+    comm.extend((8000_u64 << exponent_shift).to_be_bytes());
+
+    // Compression type is followed by its empty name
+    if let Some(compression) = compression {
+        comm.extend(compression);
+        comm.extend([0, 0]);
+    }
+
+    let mut ssnd = vec![0_u8; 8];
+    (0..frames).for_each(|frame| ssnd.extend(((frame / 400) as i16).to_be_bytes()));
+
+    let chunk = |name: &[u8], data: &[u8]| {
+        let mut chunk = name.to_vec();
+        chunk.extend((data.len() as u32).to_be_bytes());
+        chunk.extend(data);
+        chunk
+    };
+
+    let mut form = match compression {
+        Some(_) => b"AIFC".to_vec(),
+        None => b"AIFF".to_vec(),
+    };
+
+    form.extend(chunk(b"COMM", &comm));
+    form.extend(chunk(b"SSND", &ssnd));
+    std::fs::write(path, chunk(b"FORM", &form)).unwrap();
+}
+
+/// M4A with one second of mono ALAC in two uncompressed frames.
+/// Sample entry is named by the codec, so other codecs are written with the same frames.
+/// Returns the samples of the track
+fn write_mp4_fixture(path: &std::path::Path, entry: &[u8; 4]) -> Vec<i16> {
+    let mp4_box = |name: &[u8], data: &[u8]| {
+        let mut mp4_box = (data.len() as u32 + 8).to_be_bytes().to_vec();
+        mp4_box.extend(name);
+        mp4_box.extend(data);
+        mp4_box
+    };
+
+    let be = |values: &[u32]| {
+        values
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect::<Vec<_>>()
+    };
+    let alac_samples = (0..8000)
+        .map(|i| (i % 100 * 10 - 500) as i16)
+        .collect::<Vec<_>>();
+
+    // Frame header: single channel element with the uncompressed flag, then 16-bit samples
+    let alac_frame = |samples: &[i16]| {
+        let mut bits = vec![false; 22];
+        bits.push(true);
+
+        for sample in samples {
+            bits.extend((0..16).rev().map(|i| (sample >> i) & 1 != 0));
+        }
+
+        bits.chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .enumerate()
+                    .fold(0_u8, |acc, (i, &bit)| acc | ((bit as u8) << (7 - i)))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let frames = alac_samples
+        .chunks(4000)
+        .map(alac_frame)
+        .collect::<Vec<_>>();
+    let frame_len = frames[0].len() as u32;
+    let ftyp = mp4_box(b"ftyp", b"M4A \0\0\0\0");
+    let mdat = mp4_box(b"mdat", &frames.concat());
+    let data_start = ftyp.len() as u32 + 8;
+
+    let mut cookie = be(&[4000]);
+    cookie.extend([0, 16, 40, 10, 14, 1, 0, 255]);
+    cookie.extend(be(&[frame_len, 0, 8000]));
+
+    let mut alac = [0_u8; 6].to_vec();
+    alac.extend([0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 16, 0, 0, 0, 0]);
+    alac.extend(be(&[8000 << 16]));
+    alac.extend(mp4_box(b"alac", &[be(&[0]), cookie].concat()));
+
+    let stbl = [
+        mp4_box(b"stsd", &[be(&[0, 1]), mp4_box(entry, &alac)].concat()),
+        mp4_box(b"stts", &be(&[0, 1, 2, 4000])),
+        mp4_box(b"stsc", &be(&[0, 1, 1, 1, 1])),
+        mp4_box(b"stsz", &be(&[0, frame_len, 2])),
+        mp4_box(b"stco", &be(&[0, 2, data_start, data_start + frame_len])),
+    ];
+
+    let mut hdlr = vec![0_u8; 8];
+    hdlr.extend(b"soun");
+    hdlr.extend([0_u8; 13]);
+
+    let mut mvhd = be(&[0, 0, 0, 1000, 1000, 0x10000]);
+    mvhd.resize(100, 0);
+
+    let mut tkhd = be(&[1, 0, 0, 1, 0, 1000]);
+    tkhd.resize(84, 0);
+
+    let mdia = [
+        mp4_box(b"mdhd", &be(&[0, 0, 0, 8000, 8000, 0])),
+        mp4_box(b"hdlr", &hdlr),
+        mp4_box(b"minf", &mp4_box(b"stbl", &stbl.concat())),
+    ];
+
+    let trak = mp4_box(
+        b"trak",
+        &[mp4_box(b"tkhd", &tkhd), mp4_box(b"mdia", &mdia.concat())].concat(),
+    );
+    let moov = mp4_box(b"moov", &[mp4_box(b"mvhd", &mvhd), trak].concat());
+    std::fs::write(path, [ftyp, mdat, moov].concat()).unwrap();
+
+    alac_samples
+}
+
+/// Ogg page with the identification header of the codec
+fn write_ogg_fixture(path: &std::path::Path, header: &[u8]) {
+    let mut ogg = b"OggS\0\x02".to_vec();
+    ogg.extend([0_u8; 20]);
+    ogg.extend([1, header.len() as u8]);
+    ogg.extend(header);
+    std::fs::write(path, ogg).unwrap();
+}
+
+/// Mono FLAC of 8000 Hz with half a second of silence
+fn write_flac_fixture(path: &std::path::Path) {
+    use crate::domain::audio_export::flac_writer::FlacWriter;
+    use std::{fs::File, io::BufWriter};
+
+    let mut flac = FlacWriter::new(BufWriter::new(File::create(path).unwrap()), 1, 8000).unwrap();
+    (0..4000).for_each(|_| flac.write_sample(0).unwrap());
+    flac.finalize().unwrap();
+}
+
+/// Lossless WavPack encoder of 16-bit samples of 8000 Hz for the decoder's tests.
+/// Every block is a whole frame and stores the same decorrelation terms,
+/// weights, history and medians, so blocks are decoded independently
+struct WavPackFixture {
+    channels: usize,
+    joint_stereo: bool,
+
+    /// Decorrelation terms with their deltas, in the order they are stored
+    terms: Vec<(i32, i32)>,
+
+    block_samples: usize,
+
+    /// Logarithm of the initial medians: zero or a power of two
+    median_log: u16,
+
+    /// Flags that are added to the header of every block, e.g. hybrid mode
+    extra_flags: u32,
+}
+
+impl WavPackFixture {
+    /// Weight byte of every pass and its value that the decoder restores
+    const WEIGHT: (u8, i32) = (64, 516);
+
+    fn encode(&self, samples: &[i16]) -> Vec<u8> {
+        let channels = self.channels;
+        let total_samples = (samples.len() / channels) as u32;
+
+        samples
+            .chunks(self.block_samples * channels)
+            .enumerate()
+            .flat_map(|(index, block)| {
+                self.encode_block(block, (index * self.block_samples) as u32, total_samples)
+            })
+            .collect()
+    }
+
+    /// History sample of the pass is `256 << index`, negative for the second channel.
+    /// Its logarithm has zero fraction, so the decoder restores it exactly
+    fn history(channel: usize, index: usize) -> (i16, i32) {
+        let sign = if channel == 0 { 1 } else { -1 };
+        (
+            sign as i16 * (((9 + index) as i16) << 8),
+            sign * (256 << index),
+        )
+    }
+
+    fn encode_block(&self, block: &[i16], block_index: u32, total_samples: u32) -> Vec<u8> {
+        let channels = self.channels;
+        let mut buffer = block.iter().map(|&s| s as i32).collect::<Vec<_>>();
+
+        let crc = buffer.iter().fold(u32::MAX, |crc, &sample| {
+            crc.wrapping_mul(3).wrapping_add(sample as u32)
+        });
+
+        // Mid is stored instead of the right channel, side instead of the left one
+        if self.joint_stereo {
+            for frame in buffer.chunks_mut(2) {
+                frame[0] -= frame[1];
+                frame[1] += frame[0] >> 1;
+            }
+        }
+
+        let mut terms = vec![];
+        let mut weights = vec![];
+        let mut history = vec![];
+
+        for &(term, delta) in &self.terms {
+            terms.push(((term + 5) as u8 & 0x1F) | (delta as u8) << 5);
+            weights.extend(std::iter::repeat_n(Self::WEIGHT.0, channels));
+
+            let slots = match term {
+                17 | 18 => (0..channels)
+                    .flat_map(|channel| [(channel, 0), (channel, 1)])
+                    .collect::<Vec<_>>(),
+                term if term < 0 => vec![(0, 0), (1, 0)],
+                term => (0..term as usize)
+                    .flat_map(|index| (0..channels).map(move |channel| (channel, index)))
+                    .collect(),
+            };
+
+            for (channel, index) in slots {
+                history.extend(Self::history(channel, index).0.to_le_bytes());
+            }
+
+            Self::decorrelate(&mut buffer, channels, term, delta);
+        }
+
+        let median = match self.median_log {
+            0 => 0,
+            log => 256 << ((log >> 8) - 9),
+        };
+
+        let mut medians = [[median; 3]; 2];
+
+        if channels == 1 {
+            medians[1] = [0; 3];
+        }
+
+        let bitstream = Self::encode_words(&buffer, channels, medians);
+        let entropy = std::iter::repeat_n(self.median_log.to_le_bytes(), channels * 3)
+            .flatten()
+            .collect::<Vec<_>>();
+
+        let sub_block = |id: u8, data: &[u8]| {
+            let words = data.len().div_ceil(2);
+            let id = id | if data.len() % 2 == 1 { 0x40 } else { 0 };
+
+            let mut sub_block = match words > 0xFF {
+                true => [id | 0x80]
+                    .into_iter()
+                    .chain(words.to_le_bytes()[..3].to_vec())
+                    .collect(),
+                false => vec![id, words as u8],
+            };
+
+            sub_block.extend(data);
+            sub_block.resize(sub_block.len() + data.len() % 2, 0);
+            sub_block
+        };
+
+        let data = [
+            sub_block(0x2, &terms),
+            sub_block(0x3, &weights),
+            sub_block(0x4, &history),
+            sub_block(0x5, &entropy),
+            sub_block(0xA, &bitstream),
+        ]
+        .concat();
+
+        // 16-bit samples, initial and final block of the frame, 8000 Hz
+        let flags = 0x1
+            | 0x1800
+            | 1 << 23
+            | if channels == 1 { 0x4 } else { 0 }
+            | if self.joint_stereo { 0x10 } else { 0 }
+            | self.extra_flags;
+
+        let mut header = b"wvpk".to_vec();
+        header.extend((data.len() as u32 + 24).to_le_bytes());
+        header.extend(0x410_u16.to_le_bytes());
+        header.extend([0, 0]);
+        header.extend(total_samples.to_le_bytes());
+        header.extend(block_index.to_le_bytes());
+        header.extend(((block.len() / channels) as u32).to_le_bytes());
+        header.extend(flags.to_le_bytes());
+        header.extend(crc.to_le_bytes());
+        [header, data].concat()
+    }
+
+    /// Replaces samples with the residuals of the pass' prediction
+    fn decorrelate(buffer: &mut [i32], channels: usize, term: i32, delta: i32) {
+        let apply_weight =
+            |weight: i32, sample: i32| ((weight as i64 * sample as i64 + 512) >> 10) as i32;
+
+        let update_weight = |weight: &mut i32, source: i32, result: i32, clip: bool| {
+            if source != 0 && result != 0 {
+                *weight += if (source ^ result) < 0 { -delta } else { delta };
+
+                if clip {
+                    *weight = (*weight).clamp(-1024, 1024);
+                }
+            }
+        };
+
+        let mut weights = [Self::WEIGHT.1; 2];
+
+        let mut samples = [0, 1].map(|channel| {
+            let mut samples = [0; 8];
+            (0..8).for_each(|index| samples[index] = Self::history(channel, index).1);
+            samples
+        });
+
+        for (index, frame) in buffer.chunks_mut(channels).enumerate() {
+            match term {
+                17 | 18 => {
+                    for (channel, sample) in frame.iter_mut().enumerate() {
+                        let history = &mut samples[channel];
+
+                        let predicted = match term {
+                            17 => 2 * history[0] - history[1],
+                            _ => (3 * history[0] - history[1]) >> 1,
+                        };
+
+                        let residual = *sample - apply_weight(weights[channel], predicted);
+                        update_weight(&mut weights[channel], predicted, residual, false);
+                        history[1] = history[0];
+                        history[0] = *sample;
+                        *sample = residual;
+                    }
+                }
+
+                -1 => {
+                    let (left, right) = (frame[0], frame[1]);
+                    frame[0] = left - apply_weight(weights[0], samples[0][0]);
+                    update_weight(&mut weights[0], samples[0][0], frame[0], true);
+                    frame[1] = right - apply_weight(weights[1], left);
+                    update_weight(&mut weights[1], left, frame[1], true);
+                    samples[0][0] = right;
+                }
+
+                -2 => {
+                    let (left, right) = (frame[0], frame[1]);
+                    frame[1] = right - apply_weight(weights[1], samples[1][0]);
+                    update_weight(&mut weights[1], samples[1][0], frame[1], true);
+                    frame[0] = left - apply_weight(weights[0], right);
+                    update_weight(&mut weights[0], right, frame[0], true);
+                    samples[1][0] = left;
+                }
+
+                -3 => {
+                    let (left, right) = (frame[0], frame[1]);
+                    frame[0] = left - apply_weight(weights[0], samples[0][0]);
+                    update_weight(&mut weights[0], samples[0][0], frame[0], true);
+                    frame[1] = right - apply_weight(weights[1], samples[1][0]);
+                    update_weight(&mut weights[1], samples[1][0], frame[1], true);
+                    samples[1][0] = left;
+                    samples[0][0] = right;
+                }
+
+                term => {
+                    let (m, k) = (index % 8, (index + term as usize) % 8);
+
+                    for (channel, sample) in frame.iter_mut().enumerate() {
+                        let predicted = samples[channel][m];
+                        let residual = *sample - apply_weight(weights[channel], predicted);
+                        update_weight(&mut weights[channel], predicted, residual, false);
+                        samples[channel][k] = *sample;
+                        *sample = residual;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Entropy coder that mirrors the decoder's state.
+    /// Parity of the ones count tells the decoder whether the next word has ones,
+    /// so it's found by looking at the next word
+    fn encode_words(words: &[i32], channels: usize, mut medians: [[u32; 3]; 2]) -> Vec<u8> {
+        let mut bits: Vec<bool> = vec![];
+
+        let write_bits = |bits: &mut Vec<bool>, value: u32, count: u32| {
+            (0..count).for_each(|index| bits.push((value >> index) & 1 != 0));
+        };
+
+        let write_count = |bits: &mut Vec<bool>, value: u32| {
+            let bit_count = u32::BITS - value.leading_zeros();
+            bits.extend(std::iter::repeat_n(true, bit_count as usize));
+            bits.push(false);
+
+            if bit_count >= 2 {
+                write_bits(bits, value, bit_count - 1);
+            }
+        };
+
+        let median = |medians: &[u32; 3], index: usize| (medians[index] >> 4) + 1;
+        let magnitude = |word: i32| if word < 0 { !word as u32 } else { word as u32 };
+
+        let ones_count = |medians: &[u32; 3], word: i32| {
+            let magnitude = magnitude(word);
+
+            match magnitude {
+                m if m < median(medians, 0) => 0,
+                m if m - median(medians, 0) < median(medians, 1) => 1,
+                m => 2 + (m - median(medians, 0) - median(medians, 1)) / median(medians, 2),
+            }
+        };
+
+        let increase = |medians: &mut [u32; 3], index: usize| {
+            let div = [128, 64, 32][index];
+            medians[index] += (medians[index] + div) / div * 5;
+        };
+
+        let decrease = |medians: &mut [u32; 3], index: usize| {
+            let div = [128, 64, 32][index];
+            medians[index] -= (medians[index] + div - 2) / div * 2;
+        };
+
+        let (mut holding_one, mut holding_zero, mut zeros_left) = (false, false, 0);
+
+        for (index, &word) in words.iter().enumerate() {
+            let channel = index % channels;
+
+            if medians[0][0] < 2 && medians[1][0] < 2 && !holding_zero && !holding_one {
+                if zeros_left > 0 {
+                    zeros_left -= 1;
+
+                    if zeros_left > 0 {
+                        continue;
+                    }
+                } else {
+                    zeros_left = words[index..].iter().take_while(|&&w| w == 0).count() as u32;
+                    write_count(&mut bits, zeros_left);
+
+                    if zeros_left > 0 {
+                        medians = [[0; 3]; 2];
+                        continue;
+                    }
+                }
+            }
+
+            let ones = ones_count(&medians[channel], word);
+            let medians_before = medians[channel];
+            let channel_medians = &mut medians[channel];
+
+            let (low, high) = match ones {
+                0 => {
+                    decrease(channel_medians, 0);
+                    (0, median(&medians_before, 0) - 1)
+                }
+
+                1 => {
+                    increase(channel_medians, 0);
+                    decrease(channel_medians, 1);
+                    let low = median(&medians_before, 0);
+                    (low, low + median(&medians_before, 1) - 1)
+                }
+
+                ones => {
+                    increase(channel_medians, 0);
+                    increase(channel_medians, 1);
+
+                    match ones {
+                        2 => decrease(channel_medians, 2),
+                        _ => increase(channel_medians, 2),
+                    }
+
+                    let low = median(&medians_before, 0)
+                        + median(&medians_before, 1)
+                        + (ones - 2) * median(&medians_before, 2);
+
+                    (low, low + median(&medians_before, 2) - 1)
+                }
+            };
+
+            match holding_zero {
+                true => holding_zero = false,
+
+                false => {
+                    let has_next_ones = words.get(index + 1).is_some_and(|&next| {
+                        ones_count(&medians[(index + 1) % channels], next) > 0
+                    });
+
+                    let unary = 2 * (ones - holding_one as u32) + has_next_ones as u32;
+
+                    if unary < 16 {
+                        bits.extend(std::iter::repeat_n(true, unary as usize));
+                        bits.push(false);
+                    } else {
+                        bits.extend([true; 16]);
+                        bits.push(false);
+                        write_count(&mut bits, unary - 16);
+                    }
+
+                    holding_one = has_next_ones;
+                    holding_zero = !has_next_ones;
+                }
+            }
+
+            let (code, max_code) = (magnitude(word) - low, high - low);
+
+            match max_code {
+                0 => {}
+                1 => bits.push(code == 1),
+
+                _ => {
+                    let bit_count = u32::BITS - max_code.leading_zeros();
+                    let extras = (1 << bit_count) - max_code - 1;
+
+                    if code < extras {
+                        write_bits(&mut bits, code, bit_count - 1);
+                    } else {
+                        write_bits(&mut bits, (code + extras) >> 1, bit_count - 1);
+                        bits.push((code + extras) & 1 != 0);
+                    }
+                }
+            }
+
+            bits.push(word < 0);
+        }
+
+        bits.chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .enumerate()
+                    .fold(0_u8, |acc, (i, &bit)| acc | ((bit as u8) << i))
+            })
+            .collect()
+    }
+}
+
+/// Half a second of mono: silence, a sawtooth with spikes, then silence again.
+/// Silent parts are coded as runs of zeros
+fn wavpack_mono_samples() -> Vec<i16> {
+    (0..4000)
+        .map(|i: i32| match i {
+            0..=999 | 3500.. => 0,
+            i if i % 97 == 0 => -20000,
+            i => (i * 37 % 2000 - 1000) as i16 as i32,
+        } as i16)
+        .collect()
+}
+
+/// Mono WavPack of half a second in three blocks
+fn write_wavpack_fixture(path: &std::path::Path) -> Vec<i16> {
+    let samples = wavpack_mono_samples();
+
+    let fixture = WavPackFixture {
+        channels: 1,
+        joint_stereo: false,
+        terms: vec![(18, 2), (3, 2), (1, 2)],
+        block_samples: 1500,
+        median_log: 0,
+        extra_flags: 0,
+    };
+
+    std::fs::write(path, fixture.encode(&samples)).unwrap();
+    samples
+}
+
+#[test]
+fn aiff_format_test() {
+    use crate::domain::{
+        audio_player::decoders::{
+            audio_format::{AudioFormat, Codec, Container},
+            track_decoder::TrackDecoder,
+        },
+        metadata_reader::MetadataReader,
+    };
+    use rodio::Source;
+    use std::time::Duration;
+
+    let dir = test_data_file("aiff_format")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    write_aiff_fixture(&dir.join("a.aif"), None);
+    write_aiff_fixture(&dir.join("b.aifc"), Some(b"sowt"));
+    write_aiff_fixture(&dir.join("c.aifc"), Some(b"ulaw"));
+
+    assert_eq!(
+        AudioFormat::probe(&dir.join("a.aif")).unwrap(),
+        AudioFormat::new(Container::Aiff, Codec::Pcm)
+    );
+    assert_eq!(
+        AudioFormat::probe(&dir.join("b.aifc")).unwrap(),
+        AudioFormat::new(Container::Aiff, Codec::Pcm)
+    );
+    assert_eq!(
+        AudioFormat::probe(&dir.join("c.aifc")).unwrap(),
+        AudioFormat::new(Container::Aiff, Codec::Other("ulaw".to_string()))
+    );
+
+    let metadata = MetadataReader::read_metadata(&dir.join("a.aif")).unwrap();
+    assert_eq!(metadata.title.as_deref(), Some("a"));
+    assert_eq!(metadata.duration_millis, 500);
+
+    let mut decoder =
+        TrackDecoder::open_at(&dir.join("a.aif"), Duration::from_millis(250)).unwrap();
+    assert_eq!(decoder.total_duration(), Some(Duration::from_millis(500)));
+    assert_eq!(decoder.next(), Some(5));
+    assert_eq!(decoder.count(), 1999);
+
+    // Compressed AIFF-C names its compression type
+    let error = TrackDecoder::open(&dir.join("c.aifc"))
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(error.ends_with("no decoder for ulaw audio in AIFF container"));
+    assert!(MetadataReader::read_metadata(&dir.join("c.aifc")).is_none());
+}
+
+#[test]
+fn mp4_format_test() {
+    use crate::domain::{
+        audio_player::decoders::{
+            audio_format::{AudioFormat, Codec, Container},
+            track_decoder::TrackDecoder,
+        },
+        metadata_reader::MetadataReader,
+    };
+    use rodio::Source;
+    use std::time::Duration;
+
+    let dir = test_data_file("mp4_format").parent().unwrap().to_path_buf();
+
+    let alac_samples = write_mp4_fixture(&dir.join("a.m4a"), b"alac");
+    write_mp4_fixture(&dir.join("b.m4a"), b"Opus");
+    write_mp4_fixture(&dir.join("c.m4a"), b"fLaC");
+
+    let probe = |name: &str| AudioFormat::probe(&dir.join(name)).unwrap();
+
+    assert_eq!(
+        probe("a.m4a"),
+        AudioFormat::new(Container::Mp4, Codec::Alac)
+    );
+    assert_eq!(
+        probe("b.m4a"),
+        AudioFormat::new(Container::Mp4, Codec::Opus)
+    );
+    assert_eq!(
+        probe("c.m4a"),
+        AudioFormat::new(Container::Mp4, Codec::Flac)
+    );
+
+    let metadata = MetadataReader::read_metadata(&dir.join("a.m4a")).unwrap();
+    assert_eq!(metadata.duration_millis, 1000);
+
+    let mut decoder = TrackDecoder::open(&dir.join("a.m4a")).unwrap();
+    assert_eq!(decoder.total_duration(), Some(Duration::from_secs(1)));
+    assert_eq!(
+        decoder.by_ref().take(100).collect::<Vec<_>>(),
+        alac_samples[..100]
+    );
+
+    decoder.seek(Duration::from_millis(625)).unwrap();
+    assert_eq!(decoder.next(), Some(alac_samples[5000]));
+    assert_eq!(decoder.count(), 2999);
+
+    // Codecs that MP4 may hold, but only AAC and ALAC are decoded from it
+    let open_error = |name: &str| {
+        TrackDecoder::open(&dir.join(name))
+            .err()
+            .unwrap()
+            .to_string()
+    };
+
+    assert!(open_error("b.m4a").ends_with("no decoder for Opus audio in MP4 container"));
+    assert!(open_error("c.m4a").ends_with("no decoder for FLAC audio in MP4 container"));
+}
+
+#[test]
+fn ogg_format_test() {
+    use crate::domain::{
+        audio_player::decoders::{
+            audio_format::{AudioFormat, Codec, Container},
+            track_decoder::TrackDecoder,
+        },
+        metadata_reader::MetadataReader,
+    };
+
+    let dir = test_data_file("ogg_format").parent().unwrap().to_path_buf();
+
+    write_ogg_fixture(
+        &dir.join("a.opus"),
+        b"OpusHead\x01\x01\0\0\x80\xBB\0\0\0\0\0",
+    );
+    write_ogg_fixture(&dir.join("b.spx"), b"Speex   1.2");
+    write_ogg_fixture(&dir.join("c.oga"), b"\x7fFLAC\x01\0");
+
+    let probe = |name: &str| AudioFormat::probe(&dir.join(name)).unwrap();
+
+    assert_eq!(
+        probe("a.opus"),
+        AudioFormat::new(Container::Ogg, Codec::Opus)
+    );
+    assert_eq!(
+        probe("b.spx"),
+        AudioFormat::new(Container::Ogg, Codec::Speex)
+    );
+    assert_eq!(
+        probe("c.oga"),
+        AudioFormat::new(Container::Ogg, Codec::Flac)
+    );
+
+    // Only Vorbis is decoded from Ogg, Opus is recognized to name what is missing
+    let open_error = |name: &str| {
+        TrackDecoder::open(&dir.join(name))
+            .err()
+            .unwrap()
+            .to_string()
+    };
+
+    assert!(open_error("a.opus").ends_with("no decoder for Opus audio in Ogg container"));
+    assert!(open_error("b.spx").ends_with("no decoder for Speex audio in Ogg container"));
+    assert!(open_error("c.oga").ends_with("no decoder for FLAC audio in Ogg container"));
+    assert!(MetadataReader::read_metadata(&dir.join("a.opus")).is_none());
+}
+
+#[test]
+fn flac_format_test() {
+    use crate::domain::{
+        audio_player::decoders::{
+            audio_format::{AudioFormat, Codec, Container},
+            track_decoder::TrackDecoder,
+        },
+        metadata_reader::MetadataReader,
+    };
+    use rodio::Source;
+    use std::time::Duration;
+
+    let dir = test_data_file("flac_format")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    // FLAC is recognized by its content, not by the extension
+    write_flac_fixture(&dir.join("a.bin"));
+
+    assert_eq!(
+        AudioFormat::probe(&dir.join("a.bin")).unwrap(),
+        AudioFormat::new(Container::Flac, Codec::Flac)
+    );
+
+    let metadata = MetadataReader::read_metadata(&dir.join("a.bin")).unwrap();
+    assert_eq!(metadata.duration_millis, 500);
+
+    let decoder = TrackDecoder::open(&dir.join("a.bin")).unwrap();
+    assert_eq!(decoder.total_duration(), Some(Duration::from_millis(500)));
+    assert!(decoder.take(8000).all(|sample| sample == 0));
+}
+
+#[test]
+fn wavpack_mono_format_test() {
+    use crate::domain::{
+        audio_player::decoders::{
+            audio_format::{AudioFormat, Codec, Container},
+            track_decoder::TrackDecoder,
+        },
+        metadata_reader::MetadataReader,
+    };
+    use rodio::Source;
+    use std::time::Duration;
+
+    let dir = test_data_file("wavpack_mono_format")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    let samples = write_wavpack_fixture(&dir.join("a.wv"));
+
+    assert_eq!(
+        AudioFormat::probe(&dir.join("a.wv")).unwrap(),
+        AudioFormat::new(Container::WavPack, Codec::WavPack)
+    );
+
+    let metadata = MetadataReader::read_metadata(&dir.join("a.wv")).unwrap();
+    assert_eq!(metadata.title.as_deref(), Some("a"));
+    assert_eq!(metadata.duration_millis, 500);
+
+    let decoder = TrackDecoder::open(&dir.join("a.wv")).unwrap();
+    assert_eq!(decoder.channels(), 1);
+    assert_eq!(decoder.sample_rate(), 8000);
+    assert_eq!(decoder.total_duration(), Some(Duration::from_millis(500)));
+    assert_eq!(decoder.collect::<Vec<_>>(), samples);
+
+    // Seek finds the block by the headers and skips to the sample inside of it
+    let mut decoder = TrackDecoder::open_at(&dir.join("a.wv"), Duration::from_millis(300)).unwrap();
+    assert_eq!(decoder.next(), Some(samples[2400]));
+    assert_eq!(decoder.count(), 1599);
+
+    let mut decoder = TrackDecoder::open(&dir.join("a.wv")).unwrap();
+    decoder.seek(Duration::from_secs(1)).unwrap();
+    assert_eq!(decoder.next(), None);
+}
+
+#[test]
+fn wavpack_stereo_format_test() {
+    use crate::domain::audio_player::decoders::track_decoder::TrackDecoder;
+    use rodio::Source;
+    use std::time::Duration;
+
+    let dir = test_data_file("wavpack_stereo_format")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    // Right channel follows the left one with its own noise, so every term has something to predict
+    let samples = (0..3000)
+        .flat_map(|i: i32| {
+            let left = (i * 53 % 4000 - 2000) * if i % 1000 < 500 { 1 } else { -3 };
+            let right = left / 2 + (i * i % 301 - 150);
+            [left as i16, right as i16]
+        })
+        .collect::<Vec<_>>();
+
+    let fixture = WavPackFixture {
+        channels: 2,
+        joint_stereo: true,
+        terms: vec![(17, 2), (3, 2), (-1, 3), (-3, 2), (-2, 2), (18, 1)],
+        block_samples: 1000,
+        median_log: 0xB00,
+        extra_flags: 0,
+    };
+
+    std::fs::write(dir.join("a.wv"), fixture.encode(&samples)).unwrap();
+
+    let decoder = TrackDecoder::open(&dir.join("a.wv")).unwrap();
+    assert_eq!(decoder.channels(), 2);
+    assert_eq!(decoder.total_duration(), Some(Duration::from_millis(375)));
+    assert_eq!(decoder.collect::<Vec<_>>(), samples);
+
+    let mut decoder = TrackDecoder::open_at(&dir.join("a.wv"), Duration::from_millis(250)).unwrap();
+    assert_eq!(decoder.next(), Some(samples[4000]));
+    assert_eq!(decoder.next(), Some(samples[4001]));
+    assert_eq!(decoder.count(), 1998);
+}
+
+#[test]
+fn wavpack_broken_format_test() {
+    use crate::domain::audio_player::decoders::track_decoder::TrackDecoder;
+
+    let dir = test_data_file("wavpack_broken_format")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    let samples = write_wavpack_fixture(&dir.join("a.wv"));
+
+    // Block with wrong CRC is skipped, the rest is played
+    let mut wavpack = std::fs::read(dir.join("a.wv")).unwrap();
+    let second_block = u32::from_le_bytes(wavpack[4..8].try_into().unwrap()) as usize + 8;
+    wavpack[second_block + 28] ^= 0xFF;
+    std::fs::write(dir.join("b.wv"), wavpack).unwrap();
+
+    let decoded = TrackDecoder::open(&dir.join("b.wv"))
+        .unwrap()
+        .collect::<Vec<_>>();
+
+    assert_eq!(decoded, [&samples[..1500], &samples[3000..]].concat());
+
+    // Lossy and floating point streams name what is missing
+    let unsupported = |name: &str, extra_flags: u32| {
+        let fixture = WavPackFixture {
+            channels: 1,
+            joint_stereo: false,
+            terms: vec![(1, 2)],
+            block_samples: 1000,
+            median_log: 0,
+            extra_flags,
+        };
+
+        std::fs::write(dir.join(name), fixture.encode(&samples)).unwrap();
+
+        TrackDecoder::open(&dir.join(name))
+            .err()
+            .unwrap()
+            .to_string()
+    };
+
+    assert!(unsupported("c.wv", 0x8).ends_with("lossy (hybrid) WavPack is not supported"));
+    assert!(unsupported("d.wv", 0x80).ends_with("floating point WavPack is not supported"));
+
+    // Header without the rest of the block
+    std::fs::write(dir.join("e.wv"), [b"wvpk".as_slice(), &[0_u8; 28]].concat()).unwrap();
+    assert!(TrackDecoder::open(&dir.join("e.wv")).is_err());
+}
+
+#[test]
+fn unknown_format_test() {
+    use crate::domain::{
+        audio_player::decoders::{audio_format::AudioFormat, track_decoder::TrackDecoder},
+        metadata_reader::MetadataReader,
+    };
+
+    let dir = test_data_file("unknown_format")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    std::fs::write(dir.join("notes.txt"), "not a track").unwrap();
+
+    let error = AudioFormat::probe(&dir.join("notes.txt"))
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(error.ends_with("notes.txt: unknown audio container"));
+
+    let error = TrackDecoder::open(&dir.join("notes.txt"))
+        .err()
+        .unwrap()
+        .to_string();
+
+    assert!(error.ends_with("unknown audio container"));
+    assert!(MetadataReader::read_metadata(&dir.join("notes.txt")).is_none());
+}
+
+#[test]
+fn audio_scanner_formats_test() {
+    use crate::{
+        data::utils::track_order::{Comparator, Ord, TrackOrder},
+        domain::audio_scanner::AudioScanner,
+        TrackTrait,
+    };
+
+    let dir = test_data_file("audio_scanner_formats")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    write_aiff_fixture(&dir.join("a.aif"), None);
+    write_mp4_fixture(&dir.join("b.m4a"), b"alac");
+    write_ogg_fixture(
+        &dir.join("c.opus"),
+        b"OpusHead\x01\x01\0\0\x80\xBB\0\0\0\0\0",
+    );
+    write_wavpack_fixture(&dir.join("d.wv"));
+    write_flac_fixture(&dir.join("e.bin"));
+    std::fs::write(dir.join("notes.txt"), "not a track").unwrap();
+
+    // Scanner keeps only the files that can be played
+    let order = TrackOrder {
+        comparator: Comparator::Title,
+        order: Ord::Asc,
+    };
+
+    let tracks = AudioScanner::get_tracks_in(&dir, order);
+
+    let titles = tracks
+        .iter()
+        .map(|t| t.get_title().unwrap().as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(titles, ["", "", "a", "d"]);
+
+    let mut durations = tracks
+        .iter()
+        .map(|t| t.get_duration().num_milliseconds())
+        .collect::<Vec<_>>();
+
+    durations.sort();
+    assert_eq!(durations, [500, 500, 500, 1000]);
+}
+
+#[test]
+fn waveform_test() {
+    use crate::{
+        domain::analysis::{
+            analysis_worker::AnalysisWorker, waveform::Waveform, waveform_cache::WaveformCache,
+        },
+        error::Error,
+    };
+    use hound::{SampleFormat, WavSpec, WavWriter};
+    use std::thread;
+
+    let dir = test_data_file("waveform").parent().unwrap().to_path_buf();
+    let track = dir.join("track.wav");
+    let cache_dir = dir.join("cache");
+
+    let spec = WavSpec {
+        channels: 2,
+        sample_rate: 8000,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+
+    // Silence, then square wave that is louder in the right channel
+    let write_track = |seconds: u32| {
+        let mut writer = WavWriter::create(&track, spec).unwrap();
+
+        for frame in 0..seconds * 8000 {
+            let (left, right) = match (frame < 4000, frame % 2 == 0) {
+                (true, _) => (0, 0),
+                (false, true) => (8192, 16384),
+                (false, false) => (-8192, -16384),
+            };
+
+            writer.write_sample(left as i16).unwrap();
+            writer.write_sample(right as i16).unwrap();
+        }
+
+        writer.finalize().unwrap();
+    };
+
+    write_track(1);
+
+    assert!(Waveform::compute(&track, 0).is_err());
+
+    let waveform = Waveform::compute(&track, 4).unwrap();
+    assert_eq!(waveform.get_resolution(), 4);
+    assert_eq!(waveform.peaks, vec![0, 0, 0, 0, -64, 64, -64, 64]);
+
+    assert_eq!(WaveformCache::load_in(&track, 4, &cache_dir), None);
+    WaveformCache::store_in(&track, &waveform, &cache_dir).unwrap();
+    assert_eq!(
+        WaveformCache::load_in(&track, 4, &cache_dir),
+        Some(waveform)
+    );
+
+    // Other resolution and changed file are computed again
+    assert_eq!(WaveformCache::load_in(&track, 8, &cache_dir), None);
+    write_track(2);
+    assert_eq!(WaveformCache::load_in(&track, 4, &cache_dir), None);
+
+    let worker = AnalysisWorker::spawn().unwrap();
+
+    assert_eq!(
+        worker
+            .run(|| Ok(thread::current().name().map(String::from)))
+            .unwrap(),
+        Some("prima-analysis".to_string())
+    );
+
+    let panicked = worker.run::<(), _>(|| panic!("Broken track"));
+    assert!(matches!(panicked, Err(Error::Panic(message)) if message == "Broken track"));
+
+    // Worker keeps running jobs after the panic
+    let path = track.clone();
+    let waveform = worker.run(move || Waveform::compute(&path, 2)).unwrap();
+    assert_eq!(waveform.peaks, vec![-64, 64, -64, 64]);
+}
+
+#[test]
+fn meter_tap_test() {
+    use crate::domain::{
+        analysis::{fft::fft, spectrum_analyzer::SpectrumAnalyzer},
+        audio_player::{
+            meter_feed::{MeterFeed, DEFAULT_SPECTRUM_BANDS},
+            meter_tap::MeterTap,
+        },
+    };
+    use rodio::buffer::SamplesBuffer;
+    use std::{f32::consts::PI, sync::Arc, thread};
+
+    // Impulse has flat spectrum
+    let mut re = vec![0.0; 8];
+    let mut im = vec![0.0; 8];
+    re[0] = 1.0;
+    fft(&mut re, &mut im);
+    assert!(re.iter().all(|&x| (x - 1.0).abs() < 1e-6));
+    assert!(im.iter().all(|&x| x.abs() < 1e-6));
+
+    let mut analyzer = SpectrumAnalyzer::new(64);
+    let sine = (0..64).map(|i| (2.0 * PI * 8.0 * i as f32 / 64.0).sin());
+    let magnitudes = analyzer.magnitudes(sine);
+    assert_eq!(magnitudes.len(), 32);
+    assert!((magnitudes[8] - 1.0).abs() < 1e-3);
+    assert!(magnitudes[12] < 1e-3);
+
+    let feed = Arc::new(MeterFeed::default());
+    assert_eq!(feed.latest(), None);
+    assert_eq!(feed.get_spectrum_bands(), DEFAULT_SPECTRUM_BANDS);
+    assert!(feed.set_spectrum_bands(0).is_err());
+    assert!(feed.set_spectrum_bands(1000).is_err());
+    feed.set_spectrum_bands(16).unwrap();
+
+    // Half scale 1 kHz sine in the left channel, silence in the right
+    let samples = (0..8000)
+        .flat_map(|i| {
+            let left = 0.5 * (2.0 * PI * 1000.0 * i as f32 / 8000.0).sin();
+            [(left * i16::MAX as f32) as i16, 0]
+        })
+        .collect::<Vec<_>>();
+
+    let tap = MeterTap::new(SamplesBuffer::new(2, 8000, samples.clone()), feed.clone());
+    assert_eq!(tap.collect::<Vec<_>>(), samples);
+
+    let frame = feed.latest().unwrap();
+    assert_eq!(frame.rms.len(), 2);
+    assert!((frame.rms[0] - 0.5 / 2_f32.sqrt()).abs() < 0.01);
+    assert!((frame.peaks[0] - 0.5).abs() < 0.01);
+    assert_eq!((frame.rms[1], frame.peaks[1]), (0.0, 0.0));
+
+    // Channels are mixed to mono, so the sine is at a quarter of full scale
+    assert_eq!(frame.bands.len(), 16);
+    let loudest = (0..16)
+        .max_by(|&a, &b| frame.bands[a].total_cmp(&frame.bands[b]))
+        .unwrap();
+    assert_eq!(loudest, 11);
+    assert!((frame.bands[loudest] - 0.25).abs() < 0.01);
+
+    // Readers never see frame that is partially overwritten
+    feed.publish(&[0.0; 2], &[0.0; 2], &[0.0; 16]);
+    let writer_feed = feed.clone();
+    let writer = thread::spawn(move || {
+        for i in 0..20_000 {
+            let value = i as f32;
+            writer_feed.publish(&[value; 2], &[value; 2], &[value; 16]);
+        }
+    });
+
+    while !writer.is_finished() {
+        if let Some(frame) = feed.latest() {
+            let value = frame.rms[0];
+            assert!(frame
+                .rms
+                .iter()
+                .chain(&frame.peaks)
+                .chain(&frame.bands)
+                .all(|&x| x == value));
+        }
+    }
+
+    writer.join().unwrap();
+}
+
+#[test]
+fn track_analysis_test() {
+    use crate::{
+        data::{
+            databases::{
+                analysis::{db::create_tables, track_analysis_dao::TrackAnalysisDao},
+                favourites::db::establish_connection_to,
+            },
+            entities::tracks::track_trait::TrackTrait,
+            utils::track_order::{Comparator, Ord, TrackOrder},
+        },
+        domain::{
+            analysis::{
+                analysis_filter::AnalysisFilter,
+                analysis_worker::AnalysisWorker,
+                library_analyzer::{LibraryAnalysisTask, LibraryAnalyzer},
+                musical_key::MusicalKey,
+                track_analysis::TrackAnalysis,
+            },
+            audio_scanner::AudioScanner,
+            metadata_reader::MetadataReader,
+        },
+    };
+    use hound::{SampleFormat, WavSpec, WavWriter};
+    use std::f32::consts::PI;
+
+    const SAMPLE_RATE: u32 = 8000;
+
+    let c_major = MusicalKey::new(0, false);
+    let a_minor = MusicalKey::new(9, true);
+    assert_eq!(c_major.get_camelot(), "8B");
+    assert_eq!(a_minor.get_camelot(), "8A");
+    assert_eq!(MusicalKey::new(7, false).get_camelot(), "9B");
+    assert_eq!(a_minor.to_string(), "A minor");
+    assert_eq!(MusicalKey::from_index(a_minor.get_index()), Some(a_minor));
+    assert_eq!(MusicalKey::from_index(24), None);
+    assert!(c_major.is_compatible_with(&a_minor));
+    assert!(c_major.is_compatible_with(&MusicalKey::new(5, false)));
+    assert!(!c_major.is_compatible_with(&MusicalKey::new(2, false)));
+
+    // 1 kHz clicks every 60 / bpm seconds
+    let clicks = |bpm: f32, seconds: u32| {
+        let period = (SAMPLE_RATE as f32 * 60.0 / bpm) as u32;
+
+        (0..SAMPLE_RATE * seconds).map(move |i| match i % period < SAMPLE_RATE / 50 {
+            true => 0.8 * (2.0 * PI * 1000.0 * i as f32 / SAMPLE_RATE as f32).sin(),
+            false => 0.0,
+        })
+    };
+
+    // A minor triad with a louder tonic
+    let chord = |seconds: u32| {
+        (0..SAMPLE_RATE * seconds).map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+
+            [(220.0, 0.3), (261.63, 0.15), (329.63, 0.15)]
+                .iter()
+                .map(|(frequency, amplitude)| amplitude * (2.0 * PI * frequency * t).sin())
+                .sum::<f32>()
+        })
+    };
+
+    let beat = TrackAnalysis::compute_from(clicks(120.0, 20), SAMPLE_RATE);
+    assert!((beat.bpm.unwrap() - 120.0).abs() < 2.0, "{:?}", beat.bpm);
+
+    let fast_beat = TrackAnalysis::compute_from(clicks(150.0, 20), SAMPLE_RATE);
+    assert!(
+        (fast_beat.bpm.unwrap() - 150.0).abs() < 3.0,
+        "{:?}",
+        fast_beat.bpm
+    );
+
+    let harmony = TrackAnalysis::compute_from(chord(5), SAMPLE_RATE);
+    assert_eq!(harmony.key, Some(a_minor));
+    assert!(harmony.energy > beat.energy);
+    assert!(harmony.loudness_range < 1.0);
+
+    let silence = TrackAnalysis::compute_from(std::iter::repeat_n(0.0, 80_000), SAMPLE_RATE);
+    assert_eq!((silence.bpm, silence.key), (None, None));
+    assert_eq!((silence.energy, silence.loudness_range), (0.0, 0.0));
+
+    let filter = AnalysisFilter {
+        bpm: Some(110.0..=130.0),
+        ..AnalysisFilter::default()
+    };
+
+    assert!(filter.matches(&beat));
+    assert!(!filter.matches(&fast_beat));
+    assert!(!filter.matches(&silence));
+
+    let filter = AnalysisFilter {
+        key: Some(c_major),
+        is_harmonic: true,
+        ..AnalysisFilter::default()
+    };
+
+    assert!(filter.matches(&harmony));
+    assert!(!AnalysisFilter {
+        is_harmonic: false,
+        ..filter
+    }
+    .matches(&harmony));
+
+    // Library pass over tracks in the database
+    let dir = test_data_file("track_analysis")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let mut conn = establish_connection_to(&dir.join("favourite.db").to_string_lossy()).unwrap();
+    create_tables(&mut conn).unwrap();
+
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+
+    let write_track = |name: &str, samples: &mut dyn Iterator<Item = f32>| {
+        let path = dir.join(name);
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+
+        samples.for_each(|sample| {
+            writer
+                .write_sample((sample * i16::MAX as f32) as i16)
+                .unwrap()
+        });
+
+        writer.finalize().unwrap();
+        path
+    };
+
+    let slow = write_track("slow.wav", &mut clicks(100.0, 15));
+    let fast = write_track("fast.wav", &mut clicks(150.0, 15));
+    let broken = dir.join("broken.wav");
+    std::fs::write(&broken, b"not a track").unwrap();
+    let paths = [fast.clone(), broken.clone(), slow.clone()];
+
+    let worker = AnalysisWorker::spawn().unwrap();
+    let task = LibraryAnalysisTask::new();
+    assert!(LibraryAnalyzer::analyse(&paths, &worker, &task, 0.0, &mut conn).is_err());
+
+    // Cancelled pass stores nothing and is resumed by the next one
+    task.cancel();
+    assert!(!LibraryAnalyzer::analyse(&paths, &worker, &task, 1.0, &mut conn).unwrap());
+    assert!(TrackAnalysisDao::get_all(&mut conn).unwrap().is_empty());
+
+    let task = LibraryAnalysisTask::new();
+    assert!(LibraryAnalyzer::analyse(&paths, &worker, &task, 1.0, &mut conn).unwrap());
+    assert_eq!(task.get_progress(), 1.0);
+
+    let analyses = TrackAnalysisDao::get_all(&mut conn).unwrap();
+    assert_eq!(analyses.len(), 2);
+    assert!((analyses[&slow].bpm.unwrap() - 100.0).abs() < 2.0);
+    assert_eq!(TrackAnalysisDao::get(&broken, &mut conn).unwrap(), None);
+
+    // Only modified tracks are analysed again
+    let modified_times = TrackAnalysisDao::get_modified_times(&mut conn).unwrap();
+    let task = LibraryAnalysisTask::new();
+    assert!(LibraryAnalyzer::analyse(&paths, &worker, &task, 1.0, &mut conn).unwrap());
+    assert_eq!(
+        TrackAnalysisDao::get_modified_times(&mut conn).unwrap(),
+        modified_times
+    );
+
+    let mut tracks = vec![
+        MetadataReader::read_track(&slow).unwrap(),
+        MetadataReader::read_track(&fast).unwrap(),
+    ];
+
+    let by_bpm = TrackOrder::new(Comparator::Bpm, Ord::Desc);
+    assert!(by_bpm.comparator.is_analysis());
+    AudioScanner::sort_tracks_by_analysis(&mut tracks, by_bpm, &analyses);
+    assert_eq!(tracks[0].get_path(), &fast);
+}
+
+/// Six tracks of two artists, their titles are `a<n>` and `b<n>`
+fn auto_dj_library(
+    dir: &std::path::Path,
+) -> Vec<crate::data::entities::tracks::default_track::DefaultTrack> {
+    extern crate chrono;
+    use crate::data::entities::tracks::default_track::DefaultTrack;
+    use chrono::{Duration, Local};
+
+    [
+        ("a1", "Alpha"),
+        ("a2", "Alpha"),
+        ("a3", "Alpha"),
+        ("b1", "Beta"),
+        ("b2", "Beta"),
+        ("b3", "Beta"),
+    ]
+    .into_iter()
+    .map(|(name, artist)| {
+        DefaultTrack::new(
+            Some(name.to_string()),
+            Some(artist.to_string()),
+            None,
+            dir.join(format!("{}.mp3", name)),
+            Duration::seconds(180),
+            Local::now(),
+            0,
+        )
+    })
+    .collect()
+}
+
+fn auto_dj_names(
+    tracks: &[crate::data::entities::tracks::default_track::DefaultTrack],
+) -> std::collections::HashSet<String> {
+    use crate::data::entities::tracks::track_trait::TrackTrait;
+
+    tracks
+        .iter()
+        .map(|t| t.get_title().unwrap().clone())
+        .collect()
+}
+
+#[test]
+fn auto_dj_picker_test() {
+    use crate::{
+        data::entities::tracks::track_trait::TrackTrait,
+        domain::auto_dj::{
+            auto_dj::{AutoDj, AutoDjContext},
+            auto_dj_settings::AutoDjSeed,
+        },
+    };
+    use std::collections::HashSet;
+
+    let dir = test_data_file("auto_dj_picker")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let library = auto_dj_library(&dir);
+    let names = auto_dj_names;
+
+    let mut auto_dj = AutoDj::new();
+    let context = AutoDjContext::default();
+
+    // Artist seed is matched case-insensitively, picks don't repeat
+    let seed = AutoDjSeed::Artist("alpha".to_string());
+    let picks = auto_dj.pick(&library, &[], Some(&seed), &context, 3);
+    assert_eq!(
+        names(&picks),
+        HashSet::from(["a1", "a2", "a3"].map(String::from))
+    );
+
+    // Without seed picks follow the queue, queued tracks are skipped
+    let queue = vec![library[0].clone()];
+    let picks = auto_dj.pick(&library, &queue, None, &context, 2);
+    assert_eq!(names(&picks), HashSet::from(["a2", "a3"].map(String::from)));
+
+    // Recently played tracks are skipped as well
+    let context = AutoDjContext {
+        recent_plays: vec![library[1].get_path().clone()],
+        ..AutoDjContext::default()
+    };
+
+    let picks = auto_dj.pick(&library, &queue, None, &context, 1);
+    assert_eq!(names(&picks), HashSet::from(["a3".to_string()]));
+
+    // Exhausted library repeats tracks, but not the last one
+    let picks = auto_dj.pick(&library, &library, None, &AutoDjContext::default(), 2);
+    assert_eq!(picks.len(), 2);
+    assert!(!names(&picks).contains("b3"));
+}
+
+#[test]
+fn auto_dj_weighting_test() {
+    use crate::{
+        data::entities::tracks::track_trait::TrackTrait,
+        domain::auto_dj::auto_dj::{AutoDj, AutoDjContext},
+    };
+    use std::collections::{HashMap, HashSet};
+
+    let dir = test_data_file("auto_dj_weighting")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    let library = auto_dj_library(&dir);
+    let mut auto_dj = AutoDj::new();
+
+    // Favourites win when nothing else is similar
+    let context = AutoDjContext {
+        favourite_tracks: HashSet::from([library[4].get_path().clone()]),
+        ..AutoDjContext::default()
+    };
+
+    let picks = auto_dj.pick(&library, &[], None, &context, 1);
+    assert_eq!(auto_dj_names(&picks), HashSet::from(["b2".to_string()]));
+
+    // Only similar tracks are drawn, the more similar one more often
+    let context = AutoDjContext {
+        favourite_tracks: HashSet::from([
+            library[3].get_path().clone(),
+            library[4].get_path().clone(),
+        ]),
+        play_counts: HashMap::from([(library[3].get_path().clone(), 100)]),
+        ..AutoDjContext::default()
+    };
+
+    let mut counts = HashMap::<String, usize>::new();
+
+    for _ in 0..300 {
+        let picks = auto_dj.pick(&library, &[], None, &context, 1);
+        *counts
+            .entry(picks[0].get_title().unwrap().clone())
+            .or_default() += 1;
+    }
+
+    assert_eq!(counts.len(), 2);
+    assert!(counts["b1"] > counts["b2"]);
+    assert!(counts["b2"] > 0);
+}
+
+#[tokio::test]
+async fn auto_dj_settings_test() {
+    use crate::domain::{
+        auto_dj::auto_dj_settings::{AutoDjSeed, AutoDjSettings},
+        storage_util::StorageUtil,
+    };
+
+    let settings_file = test_data_file("auto_dj_settings");
+
+    let settings = AutoDjSettings {
+        is_enabled: true,
+        seed: Some(AutoDjSeed::Track(settings_file.with_file_name("a1.mp3"))),
+    };
+
+    let mut storage_util = StorageUtil::from_file(settings_file.clone()).await;
+    assert_eq!(
+        storage_util.load_auto_dj_settings(),
+        &AutoDjSettings::default()
+    );
+
+    storage_util.store_auto_dj_settings(settings.clone());
+    storage_util.flush().await.unwrap();
+
+    let storage_util = StorageUtil::from_file(settings_file).await;
+    assert_eq!(storage_util.load_auto_dj_settings(), &settings);
+}
+
+#[test]
+fn auto_dj_session_test() {
+    use crate::{
+        data::{
+            databases::history::play_history_dao::PlayHistoryDao,
+            entities::{
+                playlists::{
+                    default_playlist::DefaultPlaylist, playlist_trait::PlaylistTrait,
+                    playlist_type::PlaylistType,
+                },
+                tracks::track_trait::TrackTrait,
+            },
+        },
+        domain::{
+            audio_player::{audio_output::OutputMode, playback_params::LoopingState},
+            prima::Prima,
+        },
+    };
+
+    // Played playlist is topped up with the rest of the library
+    let dir = test_data_file("auto_dj_session")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    let music_dir = dir.join("music");
+    let db_url = dir.join("favourite.db").to_string_lossy().to_string();
+
+    std::fs::create_dir_all(&music_dir).unwrap();
+
+    for name in ["one", "two", "three"] {
+        write_silent_wav(&music_dir.join(format!("{}.wav", name)), 200);
+    }
+
+    let prima = Prima::open(dir.join("data.yaml"), db_url).unwrap();
+    prima.set_output_mode(OutputMode::Null);
+    prima.set_music_search_path(music_dir);
+    prima.set_looping_state(LoopingState::NoLooping);
+    prima.set_auto_dj_enabled(true);
+
+    let tracks = prima.get_all_tracks().unwrap();
+    let first = tracks[0].clone();
+
+    prima
+        .play(DefaultPlaylist::new(
+            None,
+            PlaylistType::default(),
+            vec![first.clone()],
+            0,
+        ))
+        .unwrap();
+
+    let playlist = prima.get_cur_playlist();
+    assert_eq!(playlist.len(), 3);
+    assert_eq!(playlist.get_cur_track(), Some(&first));
+    assert_eq!(auto_dj_names(playlist.get_tracks()), auto_dj_names(&tracks));
+
+    let plays = PlayHistoryDao::get_recent(10, &mut prima.connect().unwrap()).unwrap();
+    assert_eq!(plays, vec![first.get_path().clone()]);
+
+    // Looping playlist is never topped up
+    prima.set_looping_state(LoopingState::Playlist);
+    prima.play(playlist.clone()).unwrap();
+    assert_eq!(prima.get_cur_playlist().len(), 3);
+}
+
+#[test]
+fn random_test() {
+    use crate::data::utils::random::{random_index, random_unit, shuffle};
+
+    assert_eq!(random_index(0), 0);
+    assert!((0..100).all(|_| random_index(3) < 3));
+    assert!((0..100).all(|_| (0.0..1.0).contains(&random_unit())));
+
+    // Shuffle only reorders the items
+    let mut items = (0..100).collect::<Vec<_>>();
+    shuffle(&mut items);
+    assert_ne!(items, (0..100).collect::<Vec<_>>());
+
+    items.sort();
+    assert_eq!(items, (0..100).collect::<Vec<_>>());
+}
+
+/// Twelve tracks of three minutes, their titles are `t<index>`
+fn gtm_library() -> Vec<crate::data::entities::tracks::default_track::DefaultTrack> {
+    extern crate chrono;
+    use crate::data::entities::tracks::default_track::DefaultTrack;
+    use chrono::Local;
+    use std::path::Path;
+
+    (0..12)
+        .map(|index| {
+            DefaultTrack::new(
+                Some(format!("t{}", index)),
+                Some("Artist".to_string()),
+                None,
+                Path::new("/music").join(format!("t{}.mp3", index)),
+                chrono::Duration::seconds(180),
+                Local::now(),
+                0,
+            )
+        })
+        .collect()
+}
+
+fn gtm_index_of(track: &crate::data::entities::tracks::default_track::DefaultTrack) -> i32 {
+    use crate::data::entities::tracks::track_trait::TrackTrait;
+    track.get_title().unwrap()[1..].parse().unwrap()
+}
+
+/// Tracks with close numbers are similar
+fn gtm_similarity(
+    a: &crate::data::entities::tracks::default_track::DefaultTrack,
+    b: &crate::data::entities::tracks::default_track::DefaultTrack,
+) -> f32 {
+    -(gtm_index_of(a) - gtm_index_of(b)).abs() as f32
+}
+
+#[test]
+fn gtm_difficulty_code_test() {
+    use crate::domain::gtm::gtm_difficulty::GtmDifficulty;
+
+    // Difficulty is passed to Kotlin as a code
+    for difficulty in [
+        GtmDifficulty::Easy,
+        GtmDifficulty::Normal,
+        GtmDifficulty::Hard,
+    ] {
+        assert_eq!(
+            GtmDifficulty::from_code(difficulty.get_code()),
+            Some(difficulty)
+        );
+    }
+
+    assert!(GtmDifficulty::Easy.get_clip_length() > GtmDifficulty::Hard.get_clip_length());
+}
+
+#[test]
+fn gtm_clip_finder_test() {
+    use crate::domain::{analysis::waveform::Waveform, gtm::clip_finder::ClipFinder};
+    use std::time::Duration;
+
+    // Clips skip the silent intro and are as loud as the track
+    let peaks = (0..100)
+        .flat_map(|part| match part {
+            0..=39 => [0, 0],
+            _ => [-100, 100],
+        })
+        .collect();
+
+    let waveform = Waveform { peaks };
+    let starts =
+        ClipFinder::find_starts(&waveform, Duration::from_secs(100), Duration::from_secs(10));
+
+    assert!(!starts.is_empty());
+    assert!(starts
+        .iter()
+        .all(|&start| start >= Duration::from_secs(40) && start <= Duration::from_secs(80)));
+
+    // Track that is not longer than the clip is played from the beginning
+    assert!(
+        ClipFinder::find_starts(&waveform, Duration::from_secs(5), Duration::from_secs(10))
+            .is_empty()
+    );
+}
+
+#[test]
+fn gtm_clip_fade_test() {
+    use crate::domain::gtm::clip_fade::ClipFade;
+    use rodio::{buffer::SamplesBuffer, Source};
+    use std::time::Duration;
+
+    // Clip is cut to its length and fades in and out
+    let source = SamplesBuffer::new(1, 1000, vec![1000_i16; 1000]);
+    let clip = ClipFade::new(
+        source,
+        Duration::from_millis(500),
+        Duration::from_millis(100),
+    );
+    assert_eq!(clip.total_duration(), Some(Duration::from_millis(500)));
+
+    let samples = clip.collect::<Vec<_>>();
+    assert_eq!(samples.len(), 500);
+    assert_eq!(samples[0], 0);
+    assert_eq!(samples[250], 1000);
+    assert!(samples[499] < 100);
+}
+
+#[test]
+fn gtm_game_setup_test() {
+    use crate::{
+        domain::gtm::{gtm_difficulty::GtmDifficulty, gtm_game::GtmGame},
+        error::Error,
+    };
+
+    // Game needs rounds and enough different tracks
+    let library = gtm_library();
+
+    assert!(matches!(
+        GtmGame::new(library.clone(), GtmDifficulty::Hard, 0),
+        Err(Error::Config(_))
+    ));
+
+    assert!(matches!(
+        GtmGame::new(library[..3].to_vec(), GtmDifficulty::Hard, 1),
+        Err(Error::Config(_))
+    ));
+}
+
+#[test]
+fn gtm_game_rounds_test() {
+    use crate::{
+        data::entities::tracks::{default_track::DefaultTrack, track_trait::TrackTrait},
+        domain::gtm::{
+            gtm_difficulty::GtmDifficulty,
+            gtm_game::{GtmGame, GTM_ANSWERS_COUNT},
+        },
+        error::Error,
+    };
+    use std::{collections::HashSet, time::Duration};
+
+    let library = gtm_library();
+
+    // Decoys of the ranked pool: most similar on hard, least similar on easy
+    let pool_of = |track: &DefaultTrack, is_most_similar: bool| {
+        let mut others = library
+            .iter()
+            .filter(|other| other.get_path() != track.get_path())
+            .map(gtm_index_of)
+            .collect::<Vec<_>>();
+
+        others.sort_by_key(|&other| (other - gtm_index_of(track)).abs());
+
+        match is_most_similar {
+            true => others[..6].to_vec(),
+            false => others[others.len() - 6..].to_vec(),
+        }
+    };
+
+    for (difficulty, is_most_similar) in [(GtmDifficulty::Hard, true), (GtmDifficulty::Easy, false)]
+    {
+        let mut game = GtmGame::new(library.clone(), difficulty, 2).unwrap();
+
+        assert!(matches!(game.answer(0), Err(Error::Playback(_))));
+
+        let round = game
+            .next_round(gtm_similarity, |_, _| Ok(vec![Duration::from_secs(5)]))
+            .unwrap();
+
+        assert_eq!(round.answers.len(), GTM_ANSWERS_COUNT);
+        assert_eq!(round.answers[round.correct_answer], round.track);
+        assert_eq!(round.clip_start, Duration::from_secs(5));
+        assert_eq!(round.clip_length, difficulty.get_clip_length());
+
+        let titles = round
+            .answers
+            .iter()
+            .map(|answer| answer.get_title().unwrap().clone())
+            .collect::<HashSet<_>>();
+
+        assert_eq!(titles.len(), GTM_ANSWERS_COUNT);
+
+        let pool = pool_of(&round.track, is_most_similar);
+        assert!(round
+            .answers
+            .iter()
+            .filter(|answer| **answer != round.track)
+            .all(|decoy| pool.contains(&gtm_index_of(decoy))));
+
+        // Round must be answered before the next one
+        assert!(matches!(
+            game.next_round(gtm_similarity, |_, _| Ok(vec![])),
+            Err(Error::Playback(_))
+        ));
+
+        assert!(matches!(
+            game.answer(GTM_ANSWERS_COUNT),
+            Err(Error::Config(_))
+        ));
+
+        let result = game.answer(round.correct_answer).unwrap();
+        let multiplier = difficulty.get_score_multiplier();
+        assert!(result.is_correct);
+        assert!(result.points > 100 * multiplier && result.points <= 200 * multiplier);
+
+        // Wrong answer gives no points, the last round ends the game
+        let round = game.next_round(gtm_similarity, |_, _| Ok(vec![])).unwrap();
+
+        assert_eq!(round.clip_start, Duration::ZERO);
+
+        let result = game
+            .answer((round.correct_answer + 1) % GTM_ANSWERS_COUNT)
+            .unwrap();
+        assert!(!result.is_correct);
+        assert_eq!(result.points, 0);
+
+        assert!(game.is_over());
+        assert_eq!(game.get_correct_answers(), 1);
+        assert_eq!(game.get_score(), game.get_results()[0].points);
+        assert!(matches!(
+            game.next_round(gtm_similarity, |_, _| Ok(vec![])),
+            Err(Error::Playback(_))
+        ));
+    }
+}
+
+#[test]
+fn gtm_session_test() {
+    use crate::{
+        domain::{
+            audio_player::audio_output::OutputMode, gtm::gtm_difficulty::GtmDifficulty,
+            prima::Prima,
+        },
+        error::Error,
+    };
+    use std::time::Duration;
+
+    // Whole game is played with the library's files, the score is stored
+    let data_file = test_data_file("gtm_session");
+    let dir = data_file.parent().unwrap().to_path_buf();
+    let music_dir = dir.join("music");
+    let db_url = dir.join("gtm.db").to_string_lossy().to_string();
+
+    std::fs::create_dir_all(&music_dir).unwrap();
+
+    for name in ["one", "two", "three", "four"] {
+        write_silent_wav(&music_dir.join(format!("{}.wav", name)), 3000);
+    }
+
+    let prima = Prima::open(data_file, db_url).unwrap();
+    prima.set_output_mode(OutputMode::Null);
+    prima.set_music_search_path(music_dir);
+
+    assert!(matches!(prima.next_gtm_round(), Err(Error::Playback(_))));
+
+    prima.start_gtm_game(GtmDifficulty::Hard, 1).unwrap();
+    let round = prima.next_gtm_round().unwrap();
+    assert!(round.clip_start + round.clip_length <= Duration::from_secs(3));
+
+    prima.play_gtm_clip(true).unwrap();
+
+    let result = prima.answer_gtm_round(round.correct_answer).unwrap();
+    assert_eq!(prima.get_gtm_score(), Some((result.points, 1, 1)));
+
+    let high_scores = prima
+        .get_gtm_high_scores(Some(GtmDifficulty::Hard), 10)
+        .unwrap();
+
+    assert_eq!(high_scores.len(), 1);
+    assert_eq!(high_scores[0].score, result.points);
+    assert_eq!(high_scores[0].correct_answers, 1);
+
+    assert!(prima
+        .get_gtm_high_scores(Some(GtmDifficulty::Easy), 10)
+        .unwrap()
+        .is_empty());
+
+    prima.quit_gtm_game();
+    assert_eq!(prima.get_gtm_score(), None);
+}
+
+/// Opens the radio session in the test's own folder
+fn open_radio_prima(name: &str) -> crate::domain::prima::Prima {
+    use crate::domain::{audio_player::audio_output::OutputMode, prima::Prima};
+
+    let data_file = test_data_file(name);
+    let db_url = data_file
+        .with_file_name("radio.db")
+        .to_string_lossy()
+        .to_string();
+
+    let prima = Prima::open(data_file, db_url).unwrap();
+    prima.set_output_mode(OutputMode::Null);
+    prima
+}
+
+/// Sends silent MP3 frames as ICY stream, the title is sent with the first block.
+/// Stream ends after the number of frames or is endless
+fn serve_radio_station(
+    mut client: std::net::TcpStream,
+    title: &str,
+    frames: Option<usize>,
+) -> std::io::Result<()> {
+    use std::io::Write;
+
+    /// Bytes of audio between metadata blocks of the test station
+    const METAINT: usize = 4096;
+
+    // Silent MPEG-1 Layer III frame: 128 kbps, 44.1 kHz, mono, 1152 samples
+    let mut frame = vec![0_u8; 417];
+    frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0xC4]);
+
+    let head = format!(
+        "ICY 200 OK\r\nContent-Type: audio/mpeg\r\nicy-metaint: {}\r\n\r\n",
+        METAINT
+    );
+
+    let mut metadata = format!("StreamTitle='{}';", title).into_bytes();
+    metadata.resize(metadata.len().div_ceil(16) * 16, 0);
+
+    let mut block = vec![(metadata.len() / 16) as u8];
+    block.extend(metadata);
+
+    let mut body = vec![];
+    let mut until_metadata = METAINT;
+    let mut sent_frames = 0;
+
+    client.write_all(head.as_bytes())?;
+
+    while frames.is_none_or(|frames| sent_frames < frames) {
+        for &byte in &frame {
+            body.push(byte);
+            until_metadata -= 1;
+
+            // Later blocks are empty
+            if until_metadata == 0 {
+                match block.is_empty() {
+                    true => body.push(0),
+                    false => body.append(&mut block),
+                }
+
+                until_metadata = METAINT;
+            }
+        }
+
+        sent_frames += 1;
+
+        if sent_frames % 16 == 0 {
+            client.write_all(&body)?;
+            body.clear();
+        }
+    }
+
+    client.write_all(&body)
+}
+
+#[test]
+fn stream_title_test() {
+    use crate::domain::radio::icy_reader::parse_stream_title;
+
+    assert_eq!(
+        parse_stream_title(b"StreamTitle='Artist - It's Song';StreamUrl='';\0\0"),
+        Some("Artist - It's Song".to_string())
+    );
+    assert_eq!(
+        parse_stream_title(b"StreamTitle='Caf\xe9';"),
+        Some("Caf\u{e9}".to_string())
+    );
+    assert_eq!(parse_stream_title(b"StreamUrl='';"), None);
+}
+
+#[test]
+fn icy_reader_test() {
+    use crate::domain::radio::icy_reader::IcyReader;
+    use std::{
+        io::{Cursor, Read},
+        sync::mpsc,
+    };
+
+    // Metadata is stripped from the audio, empty block keeps the title
+    let mut metadata = b"StreamTitle='A - B';".to_vec();
+    metadata.resize(32, 0);
+
+    let mut stream = b"abcd".to_vec();
+    stream.push(2);
+    stream.extend(metadata);
+    stream.extend(b"efgh");
+    stream.push(0);
+    stream.extend(b"ij");
+
+    let (sender, titles) = mpsc::channel();
+    let mut audio = vec![];
+    IcyReader::new(Cursor::new(stream), Some(4), sender)
+        .read_to_end(&mut audio)
+        .unwrap();
+
+    assert_eq!(audio, b"abcdefghij");
+    assert_eq!(
+        titles.try_iter().collect::<Vec<_>>(),
+        [Some("A - B".to_string())]
+    );
+}
+
+#[test]
+fn stream_format_test() {
+    use crate::{
+        domain::audio_player::decoders::{
+            audio_format::{AudioFormat, Codec, Container},
+            stream_decoder::StreamDecoder,
+        },
+        error::Error,
+    };
+    use std::io::Cursor;
+
+    assert_eq!(
+        AudioFormat::from_content_type("audio/mpeg; charset=utf-8"),
+        Some(AudioFormat::new(Container::Mpeg, Codec::Mp3))
+    );
+    assert_eq!(
+        AudioFormat::from_content_type("application/ogg"),
+        Some(AudioFormat::new(Container::Ogg, Codec::Vorbis))
+    );
+    assert_eq!(AudioFormat::from_content_type("text/html"), None);
+
+    match StreamDecoder::open(
+        Box::new(Cursor::new(vec![0; 64])),
+        Some("audio/opus"),
+        "opus",
+    ) {
+        Err(Error::Decode(message)) => assert!(message.contains("Opus")),
+        _ => panic!("Opus stream must not be decoded"),
+    }
+}
+
+#[test]
+fn stream_playback_test() {
+    use crate::{domain::events::player_event::PlayerEvent, error::Error};
+    use std::{
+        net::TcpListener,
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/stream", listener.local_addr().unwrap());
+
+    // The first connection drops, the station is reconnected
+    thread::spawn(move || {
+        for (index, client) in listener.incoming().enumerate() {
+            let client = client.unwrap();
+            let _ = client.set_nodelay(true);
+
+            let _ = match index {
+                0 => serve_radio_station(client, "First", Some(128)),
+                _ => serve_radio_station(client, "Second", None),
+            };
+        }
+    });
+
+    let prima = Arc::new(open_radio_prima("radio_playback"));
+
+    assert!(matches!(
+        prima.play_stream("ftp://example.com/stream"),
+        Err(Error::Config(_))
+    ));
+
+    let mut events = prima.get_event_bus().subscribe();
+
+    let player = prima.clone();
+    let stream_url = url.clone();
+    let playback = thread::spawn(move || player.play_stream(&stream_url));
+
+    // Titles are announced when their audio is played, reconnection continues the stream
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let mut announced = vec![];
+
+    while !announced.contains(&Some("Second".to_string())) {
+        assert!(Instant::now() < deadline, "titles: {:?}", announced);
+
+        match events.try_recv() {
+            Ok(PlayerEvent::StreamTitleChanged { title }) => announced.push(title),
+            Ok(PlayerEvent::Error { message }) => panic!("{}", message),
+            _ => thread::sleep(Duration::from_millis(20)),
+        }
+    }
+
+    assert_eq!(announced[0], Some("First".to_string()));
+    assert!(prima.is_playing());
+    assert_eq!(prima.get_stream_url(), Some(url.clone()));
+    assert_eq!(prima.get_stream_title(), Some("Second".to_string()));
+
+    prima.pause_playback();
+    assert!(playback.join().unwrap().is_ok());
+    assert!(!prima.is_playing());
+    assert_eq!(prima.get_stream_url(), Some(url));
+}
+
+#[test]
+fn radio_stations_test() {
+    use crate::error::Error;
+
+    let prima = open_radio_prima("radio_stations");
+    let url = "http://127.0.0.1:8000/stream";
+
+    // Stations are kept in the database
+    assert!(matches!(
+        prima.add_radio_station("Local", "file:///radio.mp3"),
+        Err(Error::Config(_))
+    ));
+
+    let station = prima.add_radio_station(" ", url).unwrap();
+    assert_eq!(station.name, url);
+
+    let other = prima
+        .add_radio_station("Other", "https://example.com/live")
+        .unwrap();
+
+    assert!(prima
+        .update_radio_station(station.id, "Local", url)
+        .unwrap());
+
+    let stations = prima.get_radio_stations().unwrap();
+    assert_eq!(stations.len(), 2);
+    assert_eq!(stations[0].name, "Local");
+    assert_eq!(stations[1], other);
+
+    assert!(prima.remove_radio_station(other.id).unwrap());
+    assert!(!prima.remove_radio_station(other.id).unwrap());
+    assert!(!prima.update_radio_station(other.id, "Other", url).unwrap());
+
+    assert!(matches!(
+        prima.play_radio_station(other.id),
+        Err(Error::Playback(_))
+    ));
+}
+
+/// Path and Range header of every request to the server
+type PodcastRequests = std::sync::Mutex<Vec<(String, Option<String>)>>;
+
+/// HTTP server of the test feed. Feed is served from memory,
+/// episodes support ranges unless they are under /plain/
+struct PodcastFeedServer {
+    base_url: String,
+    feed_xml: std::sync::Arc<std::sync::Mutex<String>>,
+    requests: std::sync::Arc<PodcastRequests>,
+}
+
+impl PodcastFeedServer {
+    /// Serves files of the folder: `old.wav` of 0.3 s and `new.wav` of 30 s
+    fn start(served_dir: std::path::PathBuf) -> Self {
+        use std::{
+            fs,
+            net::TcpListener,
+            sync::{Arc, Mutex},
+            thread,
+        };
+
+        fs::create_dir_all(&served_dir).unwrap();
+        write_silent_wav(&served_dir.join("old.wav"), 300);
+        write_silent_wav(&served_dir.join("new.wav"), 30_000);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let server = Self {
+            base_url: format!("http://{}", listener.local_addr().unwrap()),
+            feed_xml: Arc::new(Mutex::new(String::new())),
+            requests: Arc::new(Mutex::new(vec![])),
+        };
+
+        let feed_xml = server.feed_xml.clone();
+        let requests = server.requests.clone();
+
+        thread::spawn(move || {
+            for client in listener.incoming() {
+                let _ = serve_podcast_feed(client.unwrap(), &served_dir, &feed_xml, &requests);
+            }
+        });
+
+        server
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn set_feed(&self, items: &[&str]) {
+        *self.feed_xml.lock().unwrap() = format!(
+            "<rss><channel><title>Test Cast</title>{}</channel></rss>",
+            items.concat()
+        );
+    }
+}
+
+fn serve_podcast_feed(
+    mut client: std::net::TcpStream,
+    served_dir: &std::path::Path,
+    feed_xml: &std::sync::Mutex<String>,
+    requests: &PodcastRequests,
+) -> std::io::Result<()> {
+    use std::{
+        fs,
+        io::{BufRead, BufReader, Write},
+    };
+
+    let mut reader = BufReader::new(client.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+
+    let path = line.split_whitespace().nth(1).unwrap_or("/").to_string();
+    let mut range = None;
+
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+
+        match header.trim() {
+            "" => break,
+
+            header => {
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("range") {
+                        range = Some(value.trim().to_string())
+                    }
+                }
+            }
+        }
+    }
+
+    requests.lock().unwrap().push((path.clone(), range.clone()));
+
+    let (status, extra, body) = if path == "/feed.xml" {
+        (
+            "200 OK",
+            String::new(),
+            feed_xml.lock().unwrap().clone().into_bytes(),
+        )
+    } else if let Some(name) = path.strip_prefix("/episodes/") {
+        let audio = fs::read(served_dir.join(name)).unwrap_or_default();
+
+        let offset = range
+            .as_deref()
+            .and_then(|range| range.strip_prefix("bytes="))
+            .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok());
+
+        match offset {
+            None => ("200 OK", String::new(), audio),
+            Some(offset) if offset >= audio.len() => {
+                ("416 Range Not Satisfiable", String::new(), vec![])
+            }
+
+            Some(offset) => (
+                "206 Partial Content",
+                format!(
+                    "Content-Range: bytes {}-{}/{}\r\n",
+                    offset,
+                    audio.len() - 1,
+                    audio.len()
+                ),
+                audio[offset..].to_vec(),
+            ),
+        }
+    } else if let Some(name) = path.strip_prefix("/plain/") {
+        ("200 OK", String::new(), fs::read(served_dir.join(name))?)
+    } else {
+        ("404 Not Found", String::new(), vec![])
+    };
+
+    write!(
+        client,
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+        status,
+        body.len(),
+        extra
+    )?;
+
+    client.write_all(&body)
+}
+
+/// RSS item of the served episode
+fn podcast_item(guid: &str, file: &str, date: &str) -> String {
+    format!(
+        "<item><guid>{}</guid><title>{} episode</title><pubDate>{}</pubDate>\
+         <enclosure url=\"episodes/{}\" type=\"audio/wav\"/></item>",
+        guid, guid, date, file
+    )
+}
+
+fn old_podcast_item() -> String {
+    podcast_item("old", "old.wav", "Mon, 01 Jan 2024 10:00:00 +0000")
+}
+
+fn new_podcast_item() -> String {
+    podcast_item("new", "new.wav", "Tue, 02 Jan 2024 10:00:00 +0000")
+}
+
+/// Session and the server in the test's own folder
+fn open_podcast_prima(
+    name: &str,
+) -> (
+    std::sync::Arc<crate::domain::prima::Prima>,
+    PodcastFeedServer,
+    std::path::PathBuf,
+) {
+    use crate::domain::{audio_player::audio_output::OutputMode, prima::Prima};
+    use std::sync::Arc;
+
+    let data_file = test_data_file(name);
+    let dir = data_file.parent().unwrap().to_path_buf();
+    let db_url = dir.join("podcasts.db").to_string_lossy().to_string();
+
+    let prima = Arc::new(Prima::open(data_file, db_url).unwrap());
+    prima.set_output_mode(OutputMode::Null);
+    prima.set_podcasts_dir(dir.join("podcasts"));
+
+    (prima, PodcastFeedServer::start(dir.join("served")), dir)
+}
+
+fn wait_until_podcast(condition: &dyn Fn() -> bool) {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    let start = Instant::now();
+
+    while !condition() {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+fn get_podcast_episode(
+    prima: &crate::domain::prima::Prima,
+    feed_id: i32,
+    id: i32,
+) -> crate::domain::podcasts::podcast_episode::PodcastEpisode {
+    prima
+        .get_podcast_episodes(feed_id)
+        .unwrap()
+        .into_iter()
+        .find(|episode| episode.id == id)
+        .unwrap()
+}
+
+#[test]
+fn xml_parse_test() {
+    use crate::data::utils::xml::XmlElement;
+
+    let root = XmlElement::parse(
+        "\u{feff}<?xml version=\"1.0\"?>\n<!DOCTYPE a [<!ENTITY x \"y\">]>\n<!-- c -->\
+         <a x='1 &amp; 2'><b>t &lt;&#65;&#x42;&nbsp;</b><![CDATA[<raw>]]><c/></a>",
+    )
+    .unwrap();
+
+    assert_eq!(root.attribute("x"), Some("1 & 2"));
+    assert_eq!(root.child_text("b"), Some("t <AB&nbsp;"));
+    assert_eq!(root.text, "<raw>");
+    assert_eq!(root.child("c").map(|c| c.children.len()), Some(0));
+    assert_eq!(root.child_text("c"), None);
+
+    assert!(XmlElement::parse("<a><b></a>").is_err());
+    assert!(XmlElement::parse("<a>").is_err());
+    assert!(XmlElement::parse("<a/>junk").is_err());
+    assert!(XmlElement::parse(&"<a>".repeat(100)).is_err());
+}
+
+#[test]
+fn parse_duration_test() {
+    use crate::domain::podcasts::feed_parser::parse_duration;
+    use std::time::Duration;
+
+    let secs = Duration::from_secs;
+
+    assert_eq!(parse_duration("90"), Some(secs(90)));
+    assert_eq!(parse_duration("01:30"), Some(secs(90)));
+    assert_eq!(parse_duration("1:00:00"), Some(secs(3600)));
+    assert_eq!(parse_duration("1:2:3:4"), None);
+    assert_eq!(parse_duration("soon"), None);
+}
+
+#[test]
+fn rss_feed_test() {
+    use crate::domain::podcasts::feed_parser::FeedParser;
+    use std::time::Duration;
+
+    let rss = r#"<rss xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"><channel>
+        <title>Cast &amp; Co</title>
+        <itunes:summary>About things</itunes:summary>
+        <item>
+            <title>Second</title>
+            <guid>ep-2</guid>
+            <pubDate>Tue, 02 Jan 2024 10:00:00 +0000</pubDate>
+            <itunes:duration>01:30</itunes:duration>
+            <enclosure url="episodes/2.mp3" type="audio/mpeg"/>
+        </item>
+        <item><title>Text only</title></item>
+        <item><enclosure url="https://cdn.example.com/1.mp3?id=1"/></item>
+    </channel></rss>"#;
+
+    let feed = FeedParser::parse(rss, "http://example.com/cast/feed.xml").unwrap();
+    assert_eq!(feed.title, "Cast & Co");
+    assert_eq!(feed.description.as_deref(), Some("About things"));
+    assert_eq!(feed.episodes.len(), 2);
+    assert_eq!(feed.episodes[0].guid, "ep-2");
+    assert_eq!(
+        feed.episodes[0].audio_url,
+        "http://example.com/cast/episodes/2.mp3"
+    );
+    assert_eq!(feed.episodes[0].published_at, Some(1_704_189_600));
+    assert_eq!(feed.episodes[0].duration, Some(Duration::from_secs(90)));
+    assert_eq!(feed.episodes[1].title, "1.mp3");
+    assert_eq!(feed.episodes[1].guid, "https://cdn.example.com/1.mp3?id=1");
+    assert_eq!(feed.episodes[1].published_at, None);
+}
+
+#[test]
+fn atom_feed_test() {
+    use crate::{domain::podcasts::feed_parser::FeedParser, error::Error};
+
+    let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+        <title>Atom Cast</title>
+        <subtitle>Weekly</subtitle>
+        <entry>
+            <id>urn:1</id>
+            <title>First</title>
+            <updated>2024-01-02T10:00:00Z</updated>
+            <link rel="alternate" href="/page"/>
+            <link rel="enclosure" href="/audio/1.ogg"/>
+        </entry>
+    </feed>"#;
+
+    let feed = FeedParser::parse(atom, "https://example.com/feed").unwrap();
+    assert_eq!(feed.title, "Atom Cast");
+    assert_eq!(feed.description.as_deref(), Some("Weekly"));
+    assert_eq!(feed.episodes[0].guid, "urn:1");
+    assert_eq!(
+        feed.episodes[0].audio_url,
+        "https://example.com/audio/1.ogg"
+    );
+    assert_eq!(feed.episodes[0].published_at, Some(1_704_189_600));
+
+    assert!(matches!(
+        FeedParser::parse("<html><body/></html>", "http://example.com"),
+        Err(Error::Decode(_))
+    ));
+    assert!(matches!(
+        FeedParser::parse("not xml", "http://example.com"),
+        Err(Error::Decode(_))
+    ));
+}
+
+#[test]
+fn episode_file_name_test() {
+    use crate::domain::podcasts::episode_downloader::EpisodeDownloader;
+
+    assert_eq!(
+        EpisodeDownloader::file_name_of(7, "http://x/a/ep.MP3?x=1"),
+        "7.mp3"
+    );
+    assert_eq!(
+        EpisodeDownloader::file_name_of(7, "http://x/ep.ogg"),
+        "7.ogg"
+    );
+    assert_eq!(EpisodeDownloader::file_name_of(7, "http://x/play"), "7.mp3");
+}
+
+#[test]
+fn episode_download_test() {
+    use crate::domain::podcasts::episode_downloader::EpisodeDownloader;
+    use std::fs;
+
+    let dir = test_data_file("episode_download")
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    let server = PodcastFeedServer::start(dir.join("served"));
+    let new_audio = fs::read(dir.join("served").join("new.wav")).unwrap();
+    let downloads = dir.join("downloads");
+    fs::create_dir_all(&downloads).unwrap();
+
+    // Interrupted download continues from its part
+    let file = downloads.join("resumed.wav");
+    fs::write(
+        EpisodeDownloader::partial_path_of(&file),
+        &new_audio[..1000],
+    )
+    .unwrap();
+
+    let url = server.url("/episodes/new.wav");
+    let size = EpisodeDownloader::download(&url, &file).unwrap();
+    assert_eq!(size, new_audio.len() as u64);
+    assert_eq!(fs::read(&file).unwrap(), new_audio);
+    assert!(!EpisodeDownloader::partial_path_of(&file).exists());
+    assert_eq!(
+        server.requests.lock().unwrap().last(),
+        Some(&(
+            "/episodes/new.wav".to_string(),
+            Some("bytes=1000-".to_string())
+        ))
+    );
+
+    // Server that ignores the range and the part that is too long start over
+    let file = downloads.join("restarted.wav");
+    fs::write(EpisodeDownloader::partial_path_of(&file), vec![1_u8; 1000]).unwrap();
+    EpisodeDownloader::download(&server.url("/plain/new.wav"), &file).unwrap();
+    assert_eq!(fs::read(&file).unwrap(), new_audio);
+
+    let file = downloads.join("stale.wav");
+    fs::write(
+        EpisodeDownloader::partial_path_of(&file),
+        vec![1_u8; new_audio.len() + 10],
+    )
+    .unwrap();
+    EpisodeDownloader::download(&url, &file).unwrap();
+    assert_eq!(fs::read(&file).unwrap(), new_audio);
+
+    let file = downloads.join("missing.wav");
+    assert!(EpisodeDownloader::download(&server.url("/missing"), &file).is_err());
+    assert!(!file.exists());
+}
+
+#[test]
+fn podcast_subscription_test() {
+    use crate::{
+        data::entities::{
+            playlists::{playlist_trait::PlaylistTrait, playlist_type::PlaylistType},
+            tracks::track_trait::TrackTrait,
+        },
+        domain::events::player_event::PlayerEvent,
+        error::Error,
+    };
+    use std::{
+        fs, thread,
+        time::{Duration, Instant},
+    };
+
+    let (prima, server, dir) = open_podcast_prima("podcast_subscription");
+    let new_audio = fs::read(dir.join("served").join("new.wav")).unwrap();
+    let feed_url = server.url("/feed.xml");
+
+    server.set_feed(&[&old_podcast_item()]);
+
+    assert!(matches!(
+        prima.subscribe_podcast("ftp://example.com/feed.xml"),
+        Err(Error::Config(_))
+    ));
+    assert!(prima.subscribe_podcast(&server.url("/missing")).is_err());
+    assert_eq!(prima.get_podcast_feeds().unwrap(), vec![]);
+
+    // Subscription lists episodes, refresh downloads the new ones
+    let feed = prima.subscribe_podcast(&feed_url).unwrap();
+    assert_eq!(feed.title, "Test Cast");
+    assert!(matches!(
+        prima.subscribe_podcast(&feed_url),
+        Err(Error::Config(_))
+    ));
+
+    let episodes = prima.get_podcast_episodes(feed.id).unwrap();
+    assert_eq!(episodes.len(), 1);
+    assert_eq!(episodes[0].file, None);
+    assert!(!episodes[0].is_download_queued);
+
+    let mut events = prima.get_event_bus().subscribe();
+    server.set_feed(&[&new_podcast_item(), &old_podcast_item()]);
+    assert_eq!(prima.refresh_podcasts().unwrap(), 1);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    loop {
+        assert!(Instant::now() < deadline);
+
+        match events.try_recv() {
+            Ok(PlayerEvent::PodcastsUpdated { new_episodes }) => {
+                assert_eq!(new_episodes, 1);
+                break;
+            }
+
+            _ => thread::sleep(Duration::from_millis(20)),
+        }
+    }
+
+    let episodes = prima.get_podcast_episodes(feed.id).unwrap();
+    let (new, old) = (episodes[0].clone(), episodes[1].clone());
+    assert_eq!(new.title, "new episode");
+    assert_eq!(fs::read(new.file.as_ref().unwrap()).unwrap(), new_audio);
+    assert!(new.file.as_ref().unwrap().starts_with(dir.join("podcasts")));
+    assert_eq!(old.file, None);
+    assert_eq!(prima.refresh_podcast(feed.id).unwrap(), 0);
+
+    let old_file = prima.download_podcast_episode(old.id).unwrap();
+    assert!(old_file.exists());
+
+    // Downloads are played as a playlist
+    let playlist = prima.get_podcast_playlist(Some(feed.id)).unwrap();
+    assert!(matches!(playlist.get_type(), PlaylistType::PODCAST));
+    assert_eq!(playlist.get_title(), Some(&"Test Cast".to_string()));
+    assert_eq!(playlist.get_tracks().len(), 2);
+
+    let track = &playlist.get_tracks()[0];
+    assert_eq!(track.get_title(), Some(&"new episode".to_string()));
+    assert_eq!(track.get_artist(), Some(&"Test Cast".to_string()));
+    assert_eq!(track.get_path(), new.file.as_ref().unwrap());
+    assert_eq!(track.get_duration().num_seconds(), 30);
+
+    assert!(prima.remove_podcast_download(old.id).unwrap());
+    assert!(!old_file.exists());
+    assert_eq!(get_podcast_episode(&prima, feed.id, old.id).file, None);
+    assert_eq!(
+        prima.get_podcast_playlist(None).unwrap().get_tracks().len(),
+        1
+    );
+
+    assert!(prima.unsubscribe_podcast(feed.id).unwrap());
+    assert!(!prima.unsubscribe_podcast(feed.id).unwrap());
+    assert!(!new.file.as_ref().unwrap().exists());
+    assert_eq!(prima.get_podcast_feeds().unwrap(), vec![]);
+    assert_eq!(prima.get_podcast_episodes(feed.id).unwrap(), vec![]);
+    assert!(prima.refresh_podcast(feed.id).is_err());
+}
+
+#[test]
+fn podcast_position_test() {
+    use crate::domain::prima::Prima;
+    use std::{thread, time::Duration};
+
+    let secs = Duration::from_secs;
+    let (prima, server, _) = open_podcast_prima("podcast_position");
+
+    server.set_feed(&[&new_podcast_item()]);
+
+    let feed = prima.subscribe_podcast(&server.url("/feed.xml")).unwrap();
+    let new = prima.get_podcast_episodes(feed.id).unwrap()[0].clone();
+    prima.download_podcast_episode(new.id).unwrap();
+
+    let playlist = prima.get_podcast_playlist(Some(feed.id)).unwrap();
+    let get_episode = || get_podcast_episode(&prima, feed.id, new.id);
+
+    // Episode is left in the middle and resumed from there
+    let podcasts = playlist.clone();
+    Prima::spawn_action(prima.clone(), move |prima| prima.play(podcasts)).unwrap();
+    wait_until_podcast(&|| prima.is_playing());
+
+    Prima::spawn_action(prima.clone(), move |prima| prima.seek_to(secs(15))).unwrap();
+    thread::sleep(Duration::from_millis(300));
+    wait_until_podcast(&|| prima.is_playing());
+    prima.pause_playback();
+
+    let left = get_episode();
+    assert!(!left.is_played);
+    assert!(left.position >= secs(15) && left.position < secs(17));
+
+    Prima::spawn_action(prima.clone(), move |prima| prima.play(playlist)).unwrap();
+    wait_until_podcast(&|| prima.is_playing());
+    assert!(prima.get_playback_position() >= left.position);
+
+    // Episode that is played to the end is marked played
+    Prima::spawn_action(prima.clone(), move |prima| prima.seek_to(secs(29))).unwrap();
+    wait_until_podcast(&|| get_episode().is_played);
+    assert_eq!(get_episode().position, Duration::ZERO);
+
+    assert!(prima.set_podcast_episode_played(new.id, false).unwrap());
+    assert!(!get_episode().is_played);
 }